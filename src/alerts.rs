@@ -0,0 +1,240 @@
+use bitcoin::hashes::hex::ToHex;
+use lightning::rgb_utils::{get_rgb_channel_info_path, parse_rgb_channel_info};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::error::APIError;
+use crate::routes::{Alert, AlertKind, NodeEvent};
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+/// Thresholds below which the node raises alerts, each one disabled when unset
+#[derive(Clone, Default)]
+pub(crate) struct AlertThresholds {
+    /// Confirmed vanilla sats to keep for each anchor channel, to bump its transactions
+    pub(crate) anchor_reserve_per_channel_sat: Option<u64>,
+    /// Confirmed vanilla UTXOs to keep, each fee bump running at the same time needs its own
+    pub(crate) min_fee_bump_utxos: Option<u64>,
+    /// Asset amount at or below which a side of a channel is considered exhausted
+    pub(crate) min_channel_asset_amount: Option<u64>,
+    /// Seconds after which a wallet transaction still unconfirmed is considered stuck
+    pub(crate) max_unconfirmed_tx_age_secs: Option<u64>,
+}
+
+/// Alerts currently raised, only kept in memory
+#[derive(Default)]
+pub(crate) struct Alerts {
+    active: Mutex<BTreeMap<(AlertKind, Option<String>), Alert>>,
+    changed: Notify,
+}
+
+impl Alerts {
+    /// Ask for the alerts to be evaluated again, after a change of the balances, the channels or
+    /// the thresholds
+    pub(crate) fn trigger(&self) {
+        self.changed.notify_one();
+    }
+
+    pub(crate) fn list(&self) -> Vec<Alert> {
+        self.active.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Replace the active alerts with the given ones, notifying only the ones raised or cleared
+    fn update(&self, static_state: &StaticState, current: Vec<Alert>) {
+        let mut active = self.active.lock().unwrap();
+        let current: BTreeMap<_, _> = current
+            .into_iter()
+            .map(|a| ((a.kind, a.channel_id.clone()), a))
+            .collect();
+        active.retain(|key, alert| {
+            if current.contains_key(key) {
+                return true;
+            }
+            tracing::info!("Cleared alert {:?}: {}", alert.kind, alert.message);
+            static_state.emit_event(NodeEvent::AlertCleared {
+                kind: alert.kind,
+                channel_id: alert.channel_id.clone(),
+            });
+            false
+        });
+        for (key, alert) in current {
+            if active.contains_key(&key) {
+                continue;
+            }
+            tracing::warn!("Raised alert {:?}: {}", alert.kind, alert.message);
+            static_state.emit_event(NodeEvent::AlertRaised {
+                kind: alert.kind,
+                channel_id: alert.channel_id.clone(),
+                asset_id: alert.asset_id.clone(),
+                message: alert.message.clone(),
+            });
+            active.insert(key, alert);
+        }
+    }
+}
+
+/// Conditions currently crossing the configured thresholds
+fn current_alerts(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    thresholds: &AlertThresholds,
+) -> Result<Vec<Alert>, APIError> {
+    let now = get_current_timestamp();
+    let alert = |kind, channel_id, asset_id, message| Alert {
+        kind,
+        channel_id,
+        asset_id,
+        message,
+        raised_at: now,
+    };
+    let mut alerts = vec![];
+    let channels = unlocked_state.channel_manager.list_channels();
+
+    // always raised, the node stays degraded until it restarts
+    if let Some(failure) = static_state.persistence.failure() {
+        alerts.push(alert(AlertKind::PersistenceFailure, None, None, failure));
+    }
+
+    if thresholds.anchor_reserve_per_channel_sat.is_some()
+        || thresholds.min_fee_bump_utxos.is_some()
+    {
+        // fee bumps can only spend confirmed vanilla UTXOs
+        let utxos = unlocked_state.rgb_list_unspents_vanilla(1)?;
+        let confirmed_sat: u64 = utxos.iter().map(|u| u.txout.value).sum();
+        let anchor_channels = channels
+            .iter()
+            .filter(|c| {
+                c.channel_type
+                    .as_ref()
+                    .is_some_and(|t| t.supports_anchors_zero_fee_htlc_tx())
+            })
+            .count() as u64;
+        if let Some(per_channel_sat) = thresholds.anchor_reserve_per_channel_sat {
+            let reserve_sat = per_channel_sat * anchor_channels;
+            if confirmed_sat < reserve_sat {
+                alerts.push(alert(
+                    AlertKind::LowAnchorReserve,
+                    None,
+                    None,
+                    format!(
+                        "confirmed vanilla balance of {confirmed_sat} sat is below the \
+                        {reserve_sat} sat reserve for {anchor_channels} anchor channels"
+                    ),
+                ));
+            }
+        }
+        if let Some(min_utxos) = thresholds.min_fee_bump_utxos {
+            if (utxos.len() as u64) < min_utxos {
+                alerts.push(alert(
+                    AlertKind::LowFeeBumpReserve,
+                    None,
+                    None,
+                    format!(
+                        "only {} confirmed vanilla UTXOs are left to bump fees, {min_utxos} \
+                        expected",
+                        utxos.len()
+                    ),
+                ));
+            }
+        }
+    }
+
+    if let Some(min_amount) = thresholds.min_channel_asset_amount {
+        let ldk_data_dir_path = PathBuf::from(&static_state.ldk_data_dir);
+        for chan_info in channels.iter().filter(|c| c.is_channel_ready) {
+            let channel_id = chan_info.channel_id.to_hex();
+            let info_file_path = get_rgb_channel_info_path(&channel_id, &ldk_data_dir_path, false);
+            if !info_file_path.exists() {
+                continue;
+            }
+            let rgb_info = parse_rgb_channel_info(&info_file_path);
+            for (kind, amount, side) in [
+                (
+                    AlertKind::ChannelAssetOutboundExhausted,
+                    rgb_info.local_rgb_amount,
+                    "local",
+                ),
+                (
+                    AlertKind::ChannelAssetInboundExhausted,
+                    rgb_info.remote_rgb_amount,
+                    "remote",
+                ),
+            ] {
+                if amount <= min_amount {
+                    alerts.push(alert(
+                        kind,
+                        Some(channel_id.clone()),
+                        Some(rgb_info.contract_id.to_string()),
+                        format!("only {amount} of the asset is left on the {side} side"),
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(max_age_secs) = thresholds.max_unconfirmed_tx_age_secs {
+        let stuck: Vec<String> = unlocked_state
+            .database
+            .watched_txs()?
+            .into_iter()
+            .filter(|(_, w)| now.saturating_sub(w.watched_since) >= max_age_secs)
+            .map(|(txid, w)| format!("{txid} ({} rebroadcasts)", w.rebroadcasts))
+            .collect();
+        if !stuck.is_empty() {
+            alerts.push(alert(
+                AlertKind::UnconfirmedTransaction,
+                None,
+                None,
+                format!(
+                    "transactions unconfirmed for more than {max_age_secs} seconds: {}",
+                    stuck.join(", ")
+                ),
+            ));
+        }
+    }
+
+    Ok(alerts)
+}
+
+/// Evaluate the alert thresholds regularly and after every change notified with
+/// [`Alerts::trigger`] until the node gets stopped
+pub(crate) async fn watch_alerts(
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    #[cfg(test)]
+    let interval_secs = 5;
+    #[cfg(not(test))]
+    let interval_secs = 60 * 10;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = unlocked_state.alerts.changed.notified() => {}
+            _ = static_state.persistence.failed.notified() => {}
+        }
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+
+        let unlocked_state = Arc::clone(&unlocked_state);
+        let static_state = Arc::clone(&static_state);
+        tokio::task::spawn_blocking(move || {
+            let current = unlocked_state
+                .database
+                .alert_thresholds()
+                .and_then(|t| current_alerts(&unlocked_state, &static_state, &t));
+            match current {
+                Ok(current) => unlocked_state.alerts.update(&static_state, current),
+                Err(e) => tracing::error!("Failed to evaluate the alerts: {e}"),
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// Bitcoins that will ever exist, in sat
+pub(crate) const MAX_SUPPLY_SAT: u64 = 21_000_000 * 100_000_000;
+
+/// Bitcoins that will ever exist, in msat
+pub(crate) const MAX_SUPPLY_MSAT: u64 = MAX_SUPPLY_SAT * 1000;
+
+/// Bitcoin amount in msat, never above the bitcoin supply
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(try_from = "u64", into = "u64")]
+pub(crate) struct MilliSats(pub(crate) u64);
+
+/// Bitcoin amount in sat, never above the bitcoin supply
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(try_from = "u64", into = "u64")]
+pub(crate) struct Sats(pub(crate) u64);
+
+/// Asset amount in the smallest units of the asset
+///
+/// Any u64 is valid here, amounts get checked against the issued supply of their asset once it
+/// is known.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(transparent)]
+pub(crate) struct AssetUnits(pub(crate) u64);
+
+impl Sats {
+    pub(crate) fn to_msat(self) -> MilliSats {
+        // below the supply in sat, the amount is below the supply in msat too
+        MilliSats(self.0.saturating_mul(1000))
+    }
+}
+
+impl AssetUnits {
+    pub(crate) fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(AssetUnits)
+    }
+}
+
+impl TryFrom<u64> for MilliSats {
+    type Error = String;
+
+    fn try_from(msat: u64) -> Result<Self, Self::Error> {
+        if msat > MAX_SUPPLY_MSAT {
+            return Err(format!(
+                "{msat} msat is more than the bitcoin supply ({MAX_SUPPLY_MSAT} msat)"
+            ));
+        }
+        Ok(MilliSats(msat))
+    }
+}
+
+impl TryFrom<u64> for Sats {
+    type Error = String;
+
+    fn try_from(sat: u64) -> Result<Self, Self::Error> {
+        if sat > MAX_SUPPLY_SAT {
+            return Err(format!(
+                "{sat} sat is more than the bitcoin supply ({MAX_SUPPLY_SAT} sat)"
+            ));
+        }
+        Ok(Sats(sat))
+    }
+}
+
+impl From<MilliSats> for u64 {
+    fn from(msat: MilliSats) -> u64 {
+        msat.0
+    }
+}
+
+impl From<Sats> for u64 {
+    fn from(sat: Sats) -> u64 {
+        sat.0
+    }
+}
@@ -0,0 +1,140 @@
+use bitcoin::secp256k1::PublicKey;
+use lightning::events::MessageSendEvent;
+use lightning::routing::gossip::NodeId;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use crate::ldk::NetworkGraph;
+use crate::utils::get_current_timestamp;
+
+/// Propagation of an announcement of the node, as of its latest broadcast
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) struct AnnouncementStatus {
+    /// Peers connected at the latest broadcast, the announcement being handed to all of them
+    pub(crate) announced_to_peers: usize,
+    /// None if it hasn't been broadcast since the node started
+    pub(crate) last_broadcast_at: Option<u64>,
+}
+
+/// Propagation of the announcement of a public channel and of the node's latest update of it
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) struct ChannelAnnouncementStatus {
+    pub(crate) announcement: AnnouncementStatus,
+    pub(crate) update: AnnouncementStatus,
+}
+
+/// Broadcasts of the node's announcements since it started
+///
+/// The peer manager hands a broadcast to every connected peer at once without reporting back, so
+/// the peers connected at the time are counted. Broadcasts are kept in memory, so they are reset
+/// when the node gets restarted.
+#[derive(Default)]
+pub(crate) struct Announcements {
+    peers: Mutex<HashSet<PublicKey>>,
+    node: Mutex<AnnouncementStatus>,
+    /// By short channel ID
+    channels: Mutex<HashMap<u64, ChannelAnnouncementStatus>>,
+    /// Announcements to broadcast again, handed to the peer manager along with the gossip events
+    rebroadcasts: Mutex<Vec<MessageSendEvent>>,
+}
+
+impl Announcements {
+    pub(crate) fn peer_connected(&self, peer_pubkey: PublicKey) {
+        self.peers.lock().unwrap().insert(peer_pubkey);
+    }
+
+    pub(crate) fn peer_disconnected(&self, peer_pubkey: &PublicKey) {
+        self.peers.lock().unwrap().remove(peer_pubkey);
+    }
+
+    fn broadcast(&self) -> AnnouncementStatus {
+        AnnouncementStatus {
+            announced_to_peers: self.peers.lock().unwrap().len(),
+            last_broadcast_at: Some(get_current_timestamp()),
+        }
+    }
+
+    pub(crate) fn node_broadcast(&self) {
+        *self.node.lock().unwrap() = self.broadcast();
+    }
+
+    pub(crate) fn channel_announcement_broadcast(&self, short_channel_id: u64) {
+        let status = self.broadcast();
+        self.channels
+            .lock()
+            .unwrap()
+            .entry(short_channel_id)
+            .or_default()
+            .announcement = status;
+    }
+
+    pub(crate) fn channel_update_broadcast(&self, short_channel_id: u64) {
+        let status = self.broadcast();
+        self.channels
+            .lock()
+            .unwrap()
+            .entry(short_channel_id)
+            .or_default()
+            .update = status;
+    }
+
+    pub(crate) fn node_status(&self) -> AnnouncementStatus {
+        *self.node.lock().unwrap()
+    }
+
+    pub(crate) fn channel_status(&self, short_channel_id: u64) -> ChannelAnnouncementStatus {
+        self.channels
+            .lock()
+            .unwrap()
+            .get(&short_channel_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Queue the broadcast of the announcement of the given channels and of the node's latest
+    /// update of them, as found in the network graph, returning how many have been queued
+    pub(crate) fn queue_channel_rebroadcasts(
+        &self,
+        network_graph: &NetworkGraph,
+        our_node_id: NodeId,
+        short_channel_ids: impl IntoIterator<Item = u64>,
+    ) -> usize {
+        let graph = network_graph.read_only();
+        let mut rebroadcasts = self.rebroadcasts.lock().unwrap();
+        let mut queued = 0;
+        for short_channel_id in short_channel_ids {
+            let Some(channel) = graph.channel(short_channel_id) else {
+                continue;
+            };
+            let Some(msg) = channel.announcement_message.clone() else {
+                continue;
+            };
+            let our_direction = if channel.node_one == our_node_id {
+                &channel.one_to_two
+            } else {
+                &channel.two_to_one
+            };
+            let update_msg = our_direction
+                .as_ref()
+                .and_then(|u| u.last_update_message.clone());
+            rebroadcasts.push(MessageSendEvent::BroadcastChannelAnnouncement { msg, update_msg });
+            queued += 1;
+        }
+        queued
+    }
+
+    /// Take the queued broadcasts, recording them as the peer manager is about to send them
+    pub(crate) fn take_rebroadcasts(&self) -> Vec<MessageSendEvent> {
+        let rebroadcasts = std::mem::take(&mut *self.rebroadcasts.lock().unwrap());
+        for event in &rebroadcasts {
+            if let MessageSendEvent::BroadcastChannelAnnouncement { msg, update_msg } = event {
+                self.channel_announcement_broadcast(msg.contents.short_channel_id);
+                if update_msg.is_some() {
+                    self.channel_update_broadcast(msg.contents.short_channel_id);
+                }
+            }
+        }
+        rebroadcasts
+    }
+}
@@ -0,0 +1,187 @@
+use amplify::s;
+use axum::{
+    extract::{ConnectInfo, DefaultBodyLimit, State},
+    http::{header, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::auth::token_matches;
+use crate::error::APIError;
+use crate::utils::AppState;
+
+/// Routes sending, receiving or checking payments, whose requests are counted apart
+const PAYMENT_ROUTES: &[&str] = &[
+    "/decodelninvoice",
+    "/invoicestatus",
+    "/keysend",
+    "/lninvoice",
+    "/sendpayment",
+];
+
+/// Routes carrying peer messages or consignments, accepting bodies up to the given size when it's
+/// over the limit
+const LARGE_BODY_ROUTES: &[(&str, usize)] = &[
+    ("/importasset", 16 * 1024 * 1024),
+    ("/sendcustommsg", 256 * 1024),
+    ("/sendonionmessage", 256 * 1024),
+];
+
+/// Seconds of requests at the sustained rate a client can send at once
+const BURST_SECS: f64 = 2.0;
+
+/// Buckets kept before forgetting the ones of idle clients
+const MAX_BUCKETS: usize = 4096;
+
+/// Caps on the API requests, enforced per client: the API token, or the source IP for requests
+/// without a valid one
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ApiLimits {
+    /// Requests per second a client can sustain, 0 for no limit
+    pub(crate) requests_per_sec: u32,
+    /// Requests per second a client can sustain on the payment routes, 0 for no limit
+    pub(crate) payment_requests_per_sec: u32,
+    /// Largest request body accepted, in bytes, 0 for no limit
+    pub(crate) max_body_bytes: usize,
+}
+
+/// Requests a client can still send, refilled at the sustained rate
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token buckets of the API clients, by client and by whether they're for the payment routes
+///
+/// Buckets are kept in memory, so they are reset when the node gets restarted.
+pub(crate) struct ApiLimiter {
+    limits: Option<ApiLimits>,
+    buckets: Mutex<HashMap<(String, bool), Bucket>>,
+}
+
+impl ApiLimiter {
+    pub(crate) fn new(limits: Option<ApiLimits>) -> Self {
+        ApiLimiter {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Largest body accepted on the route, None for no limit
+    fn max_body_bytes(&self, path: &str) -> Option<usize> {
+        let max_body_bytes = self.limits?.max_body_bytes;
+        if max_body_bytes == 0 {
+            return None;
+        }
+        Some(
+            LARGE_BODY_ROUTES
+                .iter()
+                .find(|(route, _)| *route == path)
+                .map_or(max_body_bytes, |(_, max)| (*max).max(max_body_bytes)),
+        )
+    }
+
+    /// Layer capping the bodies of the route while they get read, also when they don't declare
+    /// their size, the default limit for an empty route
+    pub(crate) fn body_limit(&self, path: &str) -> DefaultBodyLimit {
+        match self.max_body_bytes(path) {
+            Some(max_body_bytes) => DefaultBodyLimit::max(max_body_bytes),
+            None => DefaultBodyLimit::disable(),
+        }
+    }
+
+    /// Take a request from the client bucket, false if it's empty
+    fn take(&self, client: String, payment: bool, requests_per_sec: u32) -> bool {
+        if requests_per_sec == 0 {
+            return true;
+        }
+        let rate = requests_per_sec as f64;
+        let capacity = rate * BURST_SECS;
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_BUCKETS {
+            // a bucket idle for the burst duration is full, the same as a new one
+            buckets.retain(|_, b| now.duration_since(b.refilled_at).as_secs_f64() < BURST_SECS);
+        }
+        let bucket = buckets.entry((client, payment)).or_insert(Bucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.refilled_at = now;
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+/// Client a request is counted for: the role of its API token, as all the clients using a token
+/// share it, or else its source IP
+fn request_client<B>(state: &AppState, request: &Request<B>) -> String {
+    let static_state = &state.static_state;
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "));
+    if let Some(provided) = provided {
+        if token_matches(provided, static_state.api_token.as_deref()) {
+            return s!("admin token");
+        }
+        if token_matches(provided, static_state.read_only_api_token.as_deref()) {
+            return s!("read-only token");
+        }
+    }
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|c| c.0.ip().to_string())
+        .unwrap_or_default()
+}
+
+/// Middleware rejecting requests declaring a body over the limit of their route in their
+/// Content-Length, and requests from clients over their request rate
+///
+/// Bodies without a Content-Length or sending more than declared are cut by the body limit layers
+/// of the routes instead, once they get over the limit.
+pub(crate) async fn check_api_limits<B>(
+    State(state): State<Arc<AppState>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, APIError> {
+    let limiter = &state.static_state.api_limiter;
+    let Some(limits) = limiter.limits else {
+        return Ok(next.run(request).await);
+    };
+    let path = request.uri().path();
+
+    if let Some(max_body_bytes) = limiter.max_body_bytes(path) {
+        let body_bytes = request
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.parse::<usize>().ok());
+        if body_bytes.map_or(false, |b| b > max_body_bytes) {
+            return Err(APIError::RequestBodyTooLarge(max_body_bytes));
+        }
+    }
+
+    let payment = PAYMENT_ROUTES.contains(&path);
+    let requests_per_sec = if payment {
+        limits.payment_requests_per_sec
+    } else {
+        limits.requests_per_sec
+    };
+    if !limiter.take(request_client(&state, &request), payment, requests_per_sec) {
+        return Err(APIError::TooManyRequests);
+    }
+
+    Ok(next.run(request).await)
+}
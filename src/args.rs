@@ -6,10 +6,31 @@ use lightning::ln::msgs::SocketAddress;
 use std::collections::HashMap;
 use std::env;
 use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv6Addr};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use crate::api_limits::ApiLimits;
+use crate::channel_handshake::{
+    AnnouncedChannelPreference, ChannelHandshakeDefaults, DEFAULT_DUST_LIMIT_SAT, MIN_TO_SELF_DELAY,
+};
+use crate::channel_opens::{ChannelAcceptancePolicy, MAX_CHANNEL_REQUEST_TIMEOUT_SECS};
+use crate::consolidation::ConsolidationPolicy;
 use crate::error::AppError;
+#[cfg(test)]
+use crate::ldk::PaymentFaults;
+use crate::ldk::{HTLC_EXPIRY_WARNING_BLOCKS, MAX_CHANNEL_MIN_DEPTH, MIN_CHANNEL_CONFIRMATIONS};
+use crate::lsp::LspPolicy;
+use crate::mirror::MirrorTarget;
+use crate::peer_limits::PeerRateLimits;
+#[cfg(test)]
+use crate::persistence::PersistenceHealth;
+use crate::proxy::proxy_url;
+use crate::routes::{OPENCHANNEL_MAX_SAT, OPENCHANNEL_MIN_RGB_AMT, OPENCHANNEL_MIN_SAT};
+use crate::utils::{hex_str_to_color, node_alias_bytes};
+#[cfg(test)]
+use crate::wallet_intents::WalletFaults;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -28,6 +49,14 @@ struct Args {
     #[arg(long, default_value_t = 9735)]
     ldk_peer_listening_port: u16,
 
+    /// Listening interface for LN peers
+    #[arg(long, default_value_t = IpAddr::V6(Ipv6Addr::UNSPECIFIED))]
+    ldk_peer_listening_host: IpAddr,
+
+    /// Don't listen for LN peers, only connecting to them
+    #[arg(long)]
+    disable_ldk_peer_listening: bool,
+
     /// Bitcoin network
     #[arg(long, default_value_t = Network::Testnet, value_parser = value_parser!(Network))]
     network: Network,
@@ -36,9 +65,201 @@ struct Args {
     #[arg(long)]
     announced_node_name: Option<String>,
 
+    /// Announced node color, as an RGB hex code (e.g. 3399ff)
+    #[arg(long)]
+    announced_node_color: Option<String>,
+
     /// Announced listen addresses
     #[arg(long, value_delimiter = ',')]
     announced_listen_addreses: Option<Vec<String>>,
+
+    /// Blocks before the force-close deadline of a stuck outbound HTLC at which the node
+    /// force-closes the channel
+    #[arg(long, default_value_t = 6)]
+    htlc_force_close_buffer: u32,
+
+    /// Confirmations required for incoming asset transfers when an RGB invoice doesn't specify
+    /// them
+    #[arg(long, default_value_t = 1)]
+    default_min_confirmations: u8,
+
+    /// Lowest number of confirmations an RGB invoice can require for incoming asset transfers
+    #[arg(long, default_value_t = 1)]
+    min_allowed_confirmations: u8,
+
+    /// Confirmations the node requires before an inbound channel becomes usable
+    #[arg(long, default_value_t = MIN_CHANNEL_CONFIRMATIONS as u32)]
+    inbound_channel_min_depth: u32,
+
+    /// Directory or HTTP endpoint where channel monitor updates get mirrored
+    #[arg(long)]
+    monitor_mirror: Option<String>,
+
+    /// Bearer token for the HTTP monitor mirror
+    #[arg(long)]
+    monitor_mirror_token: Option<String>,
+
+    /// Pending monitor updates after which the mirror is reported as lagging
+    #[arg(long, default_value_t = 10)]
+    monitor_mirror_max_lag: usize,
+
+    /// Blocks after which the monitor of a fully resolved channel gets archived at unlock
+    #[arg(long, default_value_t = 4032)]
+    monitor_archive_depth: u32,
+
+    /// Size in MB over which the least recently updated channels get pruned from the network graph
+    #[arg(long, default_value_t = 256)]
+    max_graph_size_mb: u64,
+
+    /// Seconds after which a disconnected channel peer gets flagged as offline on its channels
+    #[arg(long, default_value_t = 300)]
+    peer_offline_threshold_secs: u64,
+
+    /// URL of the asset registry checked for ticker collisions
+    #[arg(long)]
+    asset_registry_url: Option<String>,
+
+    /// Unused addresses in a row after which the wallet stops looking for used ones at unlock
+    #[arg(long, default_value_t = 20)]
+    address_gap_limit: u32,
+
+    /// Bearer token required to call the APIs
+    #[arg(long)]
+    api_token: Option<String>,
+
+    /// File holding the bearer token required to call the APIs, instead of --api-token (it can
+    /// also be given with the RLN_API_TOKEN environment variable)
+    #[arg(long)]
+    api_token_file: Option<PathBuf>,
+
+    /// Bearer token only allowed to call the read-only APIs
+    #[arg(long)]
+    read_only_api_token: Option<String>,
+
+    /// File holding the read-only bearer token, instead of --read-only-api-token (it can also be
+    /// given with the RLN_READ_ONLY_API_TOKEN environment variable)
+    #[arg(long)]
+    read_only_api_token_file: Option<PathBuf>,
+
+    /// File holding the password the node gets unlocked with once started, "-" to read it from
+    /// stdin (it can also be given with the RLN_UNLOCK_PASSWORD environment variable)
+    #[arg(long)]
+    unlock_password_file: Option<PathBuf>,
+
+    /// Migrate the node data from the OLD storage directory to the NEW one, which must be the
+    /// storage directory of the node, before starting
+    #[arg(long, num_args = 2, value_names = ["OLD", "NEW"])]
+    migrate_data_dir: Option<Vec<PathBuf>>,
+
+    /// Act as LSP, opening RGB channels to peers that request inbound liquidity
+    #[arg(long)]
+    lsp: bool,
+
+    /// Fee (in sats) charged by the LSP for each requested channel
+    #[arg(long, default_value_t = 0)]
+    lsp_fee_sat: u64,
+
+    /// Largest capacity (in sats) of a channel opened on request by the LSP
+    #[arg(long, default_value_t = OPENCHANNEL_MAX_SAT)]
+    lsp_max_capacity_sat: u64,
+
+    /// Largest asset amount the LSP puts in a requested channel
+    #[arg(long)]
+    lsp_max_asset_amount: Option<u64>,
+
+    /// Relay onion messages for other nodes
+    #[arg(long)]
+    onion_message_forwarding: bool,
+
+    /// Number of allocations of an asset above which the node merges them into one
+    #[arg(long)]
+    consolidation_threshold: Option<usize>,
+
+    /// Highest fee rate (in sat/vB) paid by the transactions consolidating asset allocations
+    #[arg(long, default_value_t = 10.0)]
+    consolidation_max_fee_rate: f32,
+
+    /// Whether to accept or reject the channels opened by peers, or to ask (POST /acceptchannel or
+    /// /rejectchannel)
+    #[arg(long, value_enum, default_value_t = ChannelAcceptancePolicy::Accept)]
+    channel_acceptance_policy: ChannelAcceptancePolicy,
+
+    /// Seconds an inbound channel waits for the user decision when the acceptance policy is ask,
+    /// after which it gets rejected
+    #[arg(long, default_value_t = 45)]
+    channel_request_timeout_secs: u64,
+
+    /// Blocks the peers wait for their funds after force-closing the channels opened from now on
+    /// (144 to 2016)
+    #[arg(long, default_value_t = MIN_TO_SELF_DELAY)]
+    channel_to_self_delay: u16,
+
+    /// Share (in %) of the capacity of the channels opened from now on that the peers can have
+    /// in flight towards the node
+    #[arg(long, default_value_t = 10)]
+    channel_max_htlc_in_flight_percent: u8,
+
+    /// Announcement required of the channels opened by peers (any, public or private)
+    #[arg(long, value_enum, default_value_t = AnnouncedChannelPreference::Any)]
+    announced_channel_preference: AnnouncedChannelPreference,
+
+    /// Dust limit (in sats) of the node, the smallest amount it pushes when opening a channel (at
+    /// least 354)
+    #[arg(long, default_value_t = DEFAULT_DUST_LIMIT_SAT)]
+    dust_limit_sat: u64,
+
+    /// Also accept the channels peers open without anchor outputs (static remote key
+    /// commitments), rejected by default
+    #[arg(long)]
+    accept_non_anchor_channels: bool,
+
+    /// Run the long-running APIs (openchannel, restore, sendasset) as background jobs, answering
+    /// with a job ID instead of their result
+    #[arg(long)]
+    async_jobs: bool,
+
+    /// Unlock in recovery mode, force-closing every channel and sweeping the funds on-chain
+    #[arg(long)]
+    recovery_mode: bool,
+
+    /// Transport endpoints of the RGB proxies to use by default, in order of preference (the
+    /// network default when not given)
+    #[arg(long, value_delimiter = ',')]
+    proxy_endpoints: Option<Vec<String>>,
+
+    /// Electrum server used by the wallets (the network default when not given)
+    #[arg(long)]
+    electrum_url: Option<String>,
+
+    /// Most messages a peer can send per second before getting disconnected and banned
+    #[arg(long, default_value_t = 100)]
+    peer_max_msgs_per_sec: u32,
+
+    /// Most bytes of gossip queries and replies a peer can send per minute before getting
+    /// disconnected and banned
+    #[arg(long, default_value_t = 4_000_000)]
+    peer_max_gossip_bytes_per_min: u64,
+
+    /// Duration (in seconds) of the bans of the peers going over the rate limits
+    #[arg(long, default_value_t = 3600)]
+    peer_ban_duration_secs: u64,
+
+    /// Requests per second an API client can sustain, 0 for no limit (no limits on regtest)
+    #[arg(long, default_value_t = 20)]
+    api_max_requests_per_sec: u32,
+
+    /// Requests per second an API client can sustain on the payment routes, 0 for no limit (no
+    /// limits on regtest)
+    #[arg(long, default_value_t = 100)]
+    api_max_payment_requests_per_sec: u32,
+
+    /// Largest API request body accepted, in bytes, 0 for no limit (no limits on regtest)
+    #[arg(long, default_value_t = 64 * 1024)]
+    api_max_body_bytes: usize,
+
+    /// Track how long the API routes take to answer, reported by GET /latency
+    #[arg(long)]
+    latency_tracking: bool,
 }
 
 pub(crate) struct LdkUserInfo {
@@ -49,9 +270,54 @@ pub(crate) struct LdkUserInfo {
     pub(crate) storage_dir_path: String,
     pub(crate) daemon_listening_port: u16,
     pub(crate) ldk_peer_listening_port: u16,
+    /// Interface peers are listened for on, None if the node doesn't listen
+    pub(crate) ldk_peer_listening_host: Option<IpAddr>,
     pub(crate) ldk_announced_listen_addr: Vec<SocketAddress>,
-    pub(crate) ldk_announced_node_name: [u8; 32],
+    pub(crate) ldk_announced_node_alias: String,
+    pub(crate) ldk_announced_node_color: [u8; 3],
     pub(crate) network: Network,
+    pub(crate) htlc_force_close_buffer: u32,
+    pub(crate) default_min_confirmations: u8,
+    pub(crate) min_allowed_confirmations: u8,
+    pub(crate) inbound_channel_min_depth: u32,
+    pub(crate) monitor_mirror: Option<MirrorTarget>,
+    pub(crate) monitor_mirror_max_lag: usize,
+    pub(crate) monitor_archive_depth: u32,
+    pub(crate) max_graph_size_mb: u64,
+    pub(crate) peer_offline_threshold_secs: u64,
+    pub(crate) asset_registry_url: Option<String>,
+    pub(crate) address_gap_limit: u32,
+    pub(crate) api_token: Option<String>,
+    pub(crate) read_only_api_token: Option<String>,
+    /// Password the node gets unlocked with once started, None to wait for an /unlock call
+    pub(crate) unlock_password: Option<String>,
+    /// Storage directory the node data gets migrated from before starting
+    pub(crate) migrate_data_dir_from: Option<PathBuf>,
+    pub(crate) lsp_policy: Option<LspPolicy>,
+    pub(crate) onion_message_forwarding: bool,
+    pub(crate) consolidation_policy: Option<ConsolidationPolicy>,
+    pub(crate) consolidation_max_fee_rate: f32,
+    pub(crate) channel_acceptance_policy: ChannelAcceptancePolicy,
+    pub(crate) channel_request_timeout_secs: u64,
+    pub(crate) channel_handshake: ChannelHandshakeDefaults,
+    pub(crate) accept_non_anchor_channels: bool,
+    pub(crate) async_jobs: bool,
+    pub(crate) recovery_mode: bool,
+    pub(crate) proxy_endpoints: Vec<String>,
+    pub(crate) electrum_url: Option<String>,
+    pub(crate) peer_rate_limits: PeerRateLimits,
+    /// Caps on the API requests, None to accept them all
+    pub(crate) api_limits: Option<ApiLimits>,
+    pub(crate) latency_tracking: bool,
+    /// Storage health shared with the test, to inject write failures
+    #[cfg(test)]
+    pub(crate) persistence: std::sync::Arc<PersistenceHealth>,
+    /// Interruption points shared with the test, to simulate a crash during wallet operations
+    #[cfg(test)]
+    pub(crate) wallet_faults: std::sync::Arc<WalletFaults>,
+    /// Payment handling faults shared with the test
+    #[cfg(test)]
+    pub(crate) payment_faults: std::sync::Arc<PaymentFaults>,
 }
 
 pub(crate) fn parse_startup_args() -> Result<LdkUserInfo, AppError> {
@@ -90,26 +356,228 @@ pub(crate) fn parse_startup_args() -> Result<LdkUserInfo, AppError> {
         )));
     };
 
+    let migrate_data_dir_from = match args.migrate_data_dir {
+        Some(dirs) => {
+            let [old_dir, new_dir]: [PathBuf; 2] = dirs.try_into().expect("two directories");
+            let storage_dir = &args.storage_directory_path;
+            let same_dir = new_dir == *storage_dir
+                || fs::canonicalize(&new_dir)
+                    .is_ok_and(|d| fs::canonicalize(storage_dir).is_ok_and(|s| s == d));
+            if !same_dir {
+                return Err(AppError::InvalidDataDirMigration(format!(
+                    "the new directory must be the storage directory ({})",
+                    storage_dir.display()
+                )));
+            }
+            Some(old_dir)
+        }
+        None => None,
+    };
     let storage_dir_path = args.storage_directory_path.to_string_lossy().to_string();
 
     let daemon_listening_port = args.daemon_listening_port;
 
     let ldk_peer_listening_port = args.ldk_peer_listening_port;
+    let ldk_peer_listening_host =
+        (!args.disable_ldk_peer_listening).then_some(args.ldk_peer_listening_host);
 
-    let ldk_announced_node_name = match args.announced_node_name {
-        Some(s) => {
-            if s.len() > 32 {
-                return Err(AppError::InvalidNodeAlias(s!(
-                    "cannot be longer than 32 bytes"
-                )));
+    let ldk_announced_node_alias = args.announced_node_name.unwrap_or_default();
+    if node_alias_bytes(&ldk_announced_node_alias).is_none() {
+        return Err(AppError::InvalidNodeAlias(s!(
+            "cannot be longer than 32 bytes when UTF-8 encoded"
+        )));
+    }
+
+    let ldk_announced_node_color = match args.announced_node_color {
+        Some(color) => hex_str_to_color(&color).ok_or_else(|| {
+            AppError::InvalidNodeColor(s!("must be an RGB hex code (e.g. 3399ff)"))
+        })?,
+        None => [0; 3],
+    };
+
+    let htlc_force_close_buffer = args.htlc_force_close_buffer;
+    if htlc_force_close_buffer >= HTLC_EXPIRY_WARNING_BLOCKS {
+        return Err(AppError::InvalidHtlcForceCloseBuffer(format!(
+            "must be lower than {HTLC_EXPIRY_WARNING_BLOCKS}"
+        )));
+    }
+
+    let default_min_confirmations = args.default_min_confirmations;
+    let min_allowed_confirmations = args.min_allowed_confirmations;
+    if default_min_confirmations < min_allowed_confirmations {
+        return Err(AppError::InvalidMinConfirmations(format!(
+            "default ({default_min_confirmations}) cannot be lower than the minimum allowed \
+            ({min_allowed_confirmations})"
+        )));
+    }
+
+    let channel_request_timeout_secs = args.channel_request_timeout_secs;
+    if channel_request_timeout_secs == 0
+        || channel_request_timeout_secs > MAX_CHANNEL_REQUEST_TIMEOUT_SECS
+    {
+        return Err(AppError::InvalidChannelRequestTimeout(format!(
+            "must be between 1 and {MAX_CHANNEL_REQUEST_TIMEOUT_SECS} seconds"
+        )));
+    }
+
+    let channel_handshake = ChannelHandshakeDefaults {
+        to_self_delay: args.channel_to_self_delay,
+        max_htlc_value_in_flight_percent: args.channel_max_htlc_in_flight_percent,
+        announced_channel_preference: args.announced_channel_preference,
+        dust_limit_sat: args.dust_limit_sat,
+    };
+    channel_handshake.validate()?;
+
+    let inbound_channel_min_depth = args.inbound_channel_min_depth;
+    if inbound_channel_min_depth == 0 || inbound_channel_min_depth > MAX_CHANNEL_MIN_DEPTH {
+        return Err(AppError::InvalidMinConfirmations(format!(
+            "inbound channel min depth must be between 1 and {MAX_CHANNEL_MIN_DEPTH}"
+        )));
+    }
+
+    let monitor_mirror = match args.monitor_mirror {
+        Some(m) => match MirrorTarget::from_str(&m).map_err(AppError::InvalidMonitorMirror)? {
+            MirrorTarget::Http { url, .. } => Some(MirrorTarget::Http {
+                url,
+                token: args.monitor_mirror_token,
+            }),
+            MirrorTarget::Directory(_) if args.monitor_mirror_token.is_some() => {
+                return Err(AppError::InvalidMonitorMirror(s!(
+                    "a token can only be used with an HTTP mirror"
+                )))
+            }
+            target => Some(target),
+        },
+        None => None,
+    };
+    let monitor_mirror_max_lag = args.monitor_mirror_max_lag;
+    let monitor_archive_depth = args.monitor_archive_depth;
+    let max_graph_size_mb = args.max_graph_size_mb;
+    let peer_offline_threshold_secs = args.peer_offline_threshold_secs;
+
+    let asset_registry_url = args.asset_registry_url;
+    if asset_registry_url
+        .as_ref()
+        .is_some_and(|url| !url.starts_with("http://") && !url.starts_with("https://"))
+    {
+        return Err(AppError::InvalidAssetRegistry(s!(
+            "the URL must start with http:// or https://"
+        )));
+    }
+
+    let address_gap_limit = args.address_gap_limit;
+    if address_gap_limit == 0 {
+        return Err(AppError::InvalidAddressGapLimit(s!("must be at least 1")));
+    }
+
+    let api_token = secret_arg(
+        args.api_token,
+        args.api_token_file,
+        API_TOKEN_KEY,
+        AppError::InvalidApiTokens,
+    )?;
+    let read_only_api_token = secret_arg(
+        args.read_only_api_token,
+        args.read_only_api_token_file,
+        READ_ONLY_API_TOKEN_KEY,
+        AppError::InvalidApiTokens,
+    )?;
+    if [&api_token, &read_only_api_token]
+        .iter()
+        .any(|t| t.as_ref().is_some_and(|t| t.is_empty()))
+    {
+        return Err(AppError::InvalidApiTokens(s!("tokens cannot be empty")));
+    }
+    if let Some(read_only_api_token) = &read_only_api_token {
+        match &api_token {
+            None => {
+                return Err(AppError::InvalidApiTokens(s!(
+                    "a read-only token requires an API token"
+                )))
+            }
+            Some(api_token) if api_token == read_only_api_token => {
+                return Err(AppError::InvalidApiTokens(s!(
+                    "the read-only token must differ from the API token"
+                )))
             }
-            let mut bytes = [0; 32];
-            bytes[..s.len()].copy_from_slice(s.as_bytes());
-            bytes
+            _ => {}
         }
-        None => [0; 32],
+    }
+
+    let unlock_password = secret_arg(
+        None,
+        args.unlock_password_file,
+        UNLOCK_PASSWORD_KEY,
+        AppError::InvalidUnlockPassword,
+    )?;
+    if unlock_password.as_ref().is_some_and(|p| p.is_empty()) {
+        return Err(AppError::InvalidUnlockPassword(s!("cannot be empty")));
+    }
+
+    let lsp_policy = if args.lsp {
+        if !(OPENCHANNEL_MIN_SAT..=OPENCHANNEL_MAX_SAT).contains(&args.lsp_max_capacity_sat) {
+            return Err(AppError::InvalidLspPolicy(format!(
+                "max capacity must be between {OPENCHANNEL_MIN_SAT} and {OPENCHANNEL_MAX_SAT}"
+            )));
+        }
+        if args
+            .lsp_max_asset_amount
+            .is_some_and(|a| a < OPENCHANNEL_MIN_RGB_AMT)
+        {
+            return Err(AppError::InvalidLspPolicy(format!(
+                "max asset amount must be at least {OPENCHANNEL_MIN_RGB_AMT}"
+            )));
+        }
+        Some(LspPolicy {
+            fee_sat: args.lsp_fee_sat,
+            max_capacity_sat: args.lsp_max_capacity_sat,
+            max_asset_amount: args.lsp_max_asset_amount,
+        })
+    } else {
+        None
+    };
+
+    let consolidation_policy = match args.consolidation_threshold {
+        Some(max_allocations) if max_allocations < 2 => {
+            return Err(AppError::InvalidConsolidationPolicy(s!(
+                "threshold must be at least 2"
+            )))
+        }
+        Some(max_allocations) => Some(ConsolidationPolicy { max_allocations }),
+        None => None,
+    };
+    let consolidation_max_fee_rate = args.consolidation_max_fee_rate;
+    if consolidation_max_fee_rate < 1.0 {
+        return Err(AppError::InvalidConsolidationPolicy(s!(
+            "max fee rate must be at least 1 sat/vB"
+        )));
+    }
+
+    let proxy_endpoints = args.proxy_endpoints.unwrap_or_default();
+    if let Some(endpoint) = proxy_endpoints.iter().find(|e| proxy_url(e).is_none()) {
+        return Err(AppError::InvalidProxyEndpoints(format!(
+            "{endpoint} is not a JSON-RPC transport endpoint"
+        )));
+    }
+
+    if args.peer_max_msgs_per_sec == 0 || args.peer_max_gossip_bytes_per_min == 0 {
+        return Err(AppError::InvalidPeerRateLimits(s!(
+            "message and gossip caps must be greater than 0"
+        )));
+    }
+    let peer_rate_limits = PeerRateLimits {
+        max_msgs_per_sec: args.peer_max_msgs_per_sec,
+        max_gossip_bytes_per_min: args.peer_max_gossip_bytes_per_min,
+        ban_duration_secs: args.peer_ban_duration_secs,
     };
 
+    // regtest nodes are driven by polling-heavy test harnesses
+    let api_limits = (network != Network::Regtest).then_some(ApiLimits {
+        requests_per_sec: args.api_max_requests_per_sec,
+        payment_requests_per_sec: args.api_max_payment_requests_per_sec,
+        max_body_bytes: args.api_max_body_bytes,
+    });
+
     let mut ldk_announced_listen_addr = Vec::new();
     if let Some(addreses) = args.announced_listen_addreses {
         for addr in addreses {
@@ -125,6 +593,11 @@ pub(crate) fn parse_startup_args() -> Result<LdkUserInfo, AppError> {
             }
         }
     }
+    if ldk_peer_listening_host.is_none() && !ldk_announced_listen_addr.is_empty() {
+        return Err(AppError::InvalidAnnouncedListenAddresses(s!(
+            "cannot announce addresses while peer listening is disabled"
+        )));
+    }
 
     Ok(LdkUserInfo {
         bitcoind_rpc_username,
@@ -134,9 +607,47 @@ pub(crate) fn parse_startup_args() -> Result<LdkUserInfo, AppError> {
         storage_dir_path,
         daemon_listening_port,
         ldk_peer_listening_port,
+        ldk_peer_listening_host,
         ldk_announced_listen_addr,
-        ldk_announced_node_name,
+        ldk_announced_node_alias,
+        ldk_announced_node_color,
         network,
+        htlc_force_close_buffer,
+        default_min_confirmations,
+        min_allowed_confirmations,
+        inbound_channel_min_depth,
+        monitor_mirror,
+        monitor_mirror_max_lag,
+        monitor_archive_depth,
+        max_graph_size_mb,
+        peer_offline_threshold_secs,
+        asset_registry_url,
+        address_gap_limit,
+        api_token,
+        read_only_api_token,
+        unlock_password,
+        migrate_data_dir_from,
+        lsp_policy,
+        onion_message_forwarding: args.onion_message_forwarding,
+        consolidation_policy,
+        consolidation_max_fee_rate,
+        channel_acceptance_policy: args.channel_acceptance_policy,
+        channel_request_timeout_secs,
+        channel_handshake,
+        accept_non_anchor_channels: args.accept_non_anchor_channels,
+        async_jobs: args.async_jobs,
+        recovery_mode: args.recovery_mode,
+        proxy_endpoints,
+        electrum_url: args.electrum_url,
+        peer_rate_limits,
+        api_limits,
+        latency_tracking: args.latency_tracking,
+        #[cfg(test)]
+        persistence: Default::default(),
+        #[cfg(test)]
+        wallet_faults: Default::default(),
+        #[cfg(test)]
+        payment_faults: Default::default(),
     })
 }
 
@@ -151,6 +662,65 @@ const DEFAULT_BITCOIN_DATADIR: &str = "Library/Application Support/Bitcoin";
 // Environment variable/.env keys
 const BITCOIND_RPC_USER_KEY: &str = "RPC_USER";
 const BITCOIND_RPC_PASSWORD_KEY: &str = "RPC_PASSWORD";
+const UNLOCK_PASSWORD_KEY: &str = "RLN_UNLOCK_PASSWORD";
+const API_TOKEN_KEY: &str = "RLN_API_TOKEN";
+const READ_ONLY_API_TOKEN_KEY: &str = "RLN_READ_ONLY_API_TOKEN";
+
+/// Get a secret given at most one way among a command line option, a file and an environment
+/// variable
+fn secret_arg(
+    value: Option<String>,
+    file: Option<PathBuf>,
+    env_key: &str,
+    error: fn(String) -> AppError,
+) -> Result<Option<String>, AppError> {
+    let from_env = env::var(env_key).ok();
+    // not to be inherited by the processes spawned by the node
+    env::remove_var(env_key);
+    match (value, file, from_env) {
+        (value, None, None) => Ok(value),
+        (None, Some(path), None) => read_secret_file(&path).map(Some).map_err(error),
+        (None, None, from_env) => Ok(from_env),
+        _ => Err(error(format!(
+            "can be given only one way among the option, a file and {env_key}"
+        ))),
+    }
+}
+
+/// Read the unlock password from a file ("-" for stdin), which must not be readable by other
+/// users, dropping the trailing newline
+pub(crate) fn read_unlock_password_file(path: &Path) -> Result<String, AppError> {
+    read_secret_file(path).map_err(AppError::InvalidUnlockPassword)
+}
+
+/// Read a secret from a file ("-" for stdin), which must not be readable by other users,
+/// dropping the trailing newline
+fn read_secret_file(path: &Path) -> Result<String, String> {
+    let secret = if path == Path::new("-") {
+        let mut line = String::new();
+        io::stdin()
+            .read_line(&mut line)
+            .map_err(|e| format!("cannot read stdin: {e}"))?;
+        line
+    } else {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(path)
+                .map_err(|e| format!("cannot read {}: {e}", path.display()))?
+                .permissions()
+                .mode();
+            if mode & 0o077 != 0 {
+                return Err(format!(
+                    "{} is accessible by other users (hint: chmod 600 it)",
+                    path.display()
+                ));
+            }
+        }
+        fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?
+    };
+    Ok(secret.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
 
 fn print_rpc_auth_help() {
     // Get the default data directory
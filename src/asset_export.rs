@@ -0,0 +1,90 @@
+use lightning::rgb_utils::get_rgb_runtime;
+use rgbstd::containers::{Bindle, Transfer as RgbTransfer};
+use rgbstd::contract::ContractId;
+use rgbstd::persistence::Inventory;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+use crate::error::APIError;
+use crate::utils::{StaticState, UnlockedAppState};
+
+/// Consignment of an asset ready to be handed out
+pub(crate) struct ExportedConsignment {
+    /// Handle on the binary consignment, positioned at its start
+    pub(crate) file: File,
+    pub(crate) file_name: String,
+    pub(crate) armored: String,
+}
+
+impl UnlockedAppState {
+    /// Latest transfer consignment of the wallet for the given asset, which carries the history
+    /// of the asset from its genesis
+    fn latest_transfer_consignment(
+        &self,
+        contract_id: ContractId,
+    ) -> Option<(PathBuf, Bindle<RgbTransfer>)> {
+        let transfers_dir = self.rgb_get_wallet_dir().join("transfers");
+        let mut files: Vec<(SystemTime, PathBuf)> = WalkDir::new(transfers_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                e.file_type().is_file()
+                    && e.file_name().to_string_lossy().starts_with("consignment")
+            })
+            .filter_map(|e| {
+                let modified = e.metadata().ok()?.modified().ok()?;
+                Some((modified, e.into_path()))
+            })
+            .collect();
+        files.sort_by(|a, b| b.0.cmp(&a.0));
+        files.into_iter().find_map(|(_, path)| {
+            let consignment = Bindle::<RgbTransfer>::load(&path).ok()?;
+            (consignment.contract_id() == contract_id).then_some((path, consignment))
+        })
+    }
+
+    /// Export the contract of the given asset or, with its history, the latest transfer
+    /// consignment of the asset known to the wallet
+    ///
+    /// The RGB runtime is only held while exporting the contract, so that transfers in flight
+    /// don't wait on the download.
+    pub(crate) fn export_consignment(
+        &self,
+        static_state: &StaticState,
+        contract_id: ContractId,
+        history: bool,
+    ) -> Result<ExportedConsignment, APIError> {
+        if history {
+            if let Some((path, consignment)) = self.latest_transfer_consignment(contract_id) {
+                return Ok(ExportedConsignment {
+                    file: File::open(path)?,
+                    file_name: format!("{contract_id}_history.rgb"),
+                    armored: consignment.to_string(),
+                });
+            }
+        }
+
+        // an asset that has never been transferred has no history beyond its contract
+        let contract = {
+            let runtime = get_rgb_runtime(Path::new(&static_state.ldk_data_dir));
+            runtime
+                .runtime
+                .export_contract(contract_id)
+                .map_err(|_| APIError::UnknownContractId)?
+        };
+        let tmp_file = tempfile::NamedTempFile::new_in(&static_state.ldk_data_dir)?;
+        contract.save(tmp_file.path()).map_err(|e| {
+            tracing::error!("Cannot save the contract of asset {contract_id}: {e}");
+            APIError::Unexpected
+        })?;
+        // the handle outlives the temporary file, which gets removed right away
+        let file = tmp_file.reopen()?;
+        Ok(ExportedConsignment {
+            file,
+            file_name: format!("{contract_id}.rgb"),
+            armored: contract.to_string(),
+        })
+    }
+}
@@ -0,0 +1,106 @@
+use bitcoin::Network;
+use bp::Chain;
+use lightning::rgb_utils::get_rgb_runtime;
+use rgb_lib::AssetSchema;
+use rgbstd::containers::{Bindle, Contract};
+use rgbstd::validation::Validity;
+use rgbwallet::BlockchainResolver;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::APIError;
+use crate::utils::{StaticState, UnlockedAppState};
+
+/// RGB chain the contracts usable on the given bitcoin network are issued on
+fn rgb_chain(network: Network) -> Chain {
+    match network {
+        Network::Bitcoin => Chain::Bitcoin,
+        Network::Testnet => Chain::Testnet3,
+        Network::Regtest => Chain::Regtest,
+        Network::Signet => Chain::Signet,
+    }
+}
+
+/// Decode a contract consignment, either ASCII-armored or in its binary form
+fn load_contract(consignment: &[u8], ldk_data_dir: &str) -> Result<Bindle<Contract>, APIError> {
+    if let Ok(armored) = std::str::from_utf8(consignment) {
+        if let Ok(contract) = Bindle::<Contract>::from_str(armored.trim()) {
+            return Ok(contract);
+        }
+    }
+    let mut file = tempfile::NamedTempFile::new_in(ldk_data_dir)?;
+    file.write_all(consignment)?;
+    Bindle::<Contract>::load(file.path())
+        .map_err(|e| APIError::InvalidConsignment(format!("cannot decode contract: {e}")))
+}
+
+impl UnlockedAppState {
+    /// Validate the contract of an externally issued asset and make the asset known to the
+    /// wallet, returning its ID
+    ///
+    /// Importing an asset that is already known succeeds without changes.
+    pub(crate) fn import_contract(
+        &self,
+        static_state: &StaticState,
+        consignment: &[u8],
+        expected_asset_id: Option<&str>,
+    ) -> Result<String, APIError> {
+        let contract = load_contract(consignment, &static_state.ldk_data_dir)?.unbindle();
+        let contract_id = contract.contract_id();
+        if let Some(expected_asset_id) = expected_asset_id {
+            if contract_id.to_string() != expected_asset_id {
+                return Err(APIError::InvalidConsignment(format!(
+                    "contract {contract_id} doesn't match the requested asset {expected_asset_id}"
+                )));
+            }
+        }
+
+        let chain = rgb_chain(static_state.network);
+        if contract.genesis.chain != chain {
+            return Err(APIError::InvalidConsignment(format!(
+                "contract is issued on {} while the node runs on {chain}",
+                contract.genesis.chain
+            )));
+        }
+
+        let schema_id = contract.schema_id().to_string();
+        let asset_schema = AssetSchema::from_schema_id(schema_id.clone())
+            .map_err(|_| APIError::InvalidConsignment(format!("unsupported schema {schema_id}")))?;
+
+        let mut resolver = BlockchainResolver::with(&static_state.electrum_url).map_err(|e| {
+            tracing::error!("Cannot connect to the indexer to validate a contract: {e}");
+            APIError::Unexpected
+        })?;
+        let contract = contract.validate(&mut resolver).unwrap_or_else(|c| c);
+        let status = contract
+            .validation_status()
+            .expect("validated contract has a status");
+        if status.validity() != Validity::Valid {
+            let mut details: Vec<String> = status.failures.iter().map(|f| f.to_string()).collect();
+            details.extend(
+                status
+                    .unresolved_txids
+                    .iter()
+                    .map(|txid| format!("unresolved transaction {txid}")),
+            );
+            return Err(APIError::InvalidConsignment(format!(
+                "validation failed: {}",
+                details.join("; ")
+            )));
+        }
+
+        let mut runtime = get_rgb_runtime(Path::new(&static_state.ldk_data_dir));
+        runtime
+            .runtime
+            .import_contract(contract, &mut resolver)
+            .map_err(|e| APIError::InvalidConsignment(format!("cannot import contract: {e}")))?;
+        match self.rgb_save_new_asset(&mut runtime, &asset_schema, contract_id) {
+            Ok(_) => {}
+            Err(e) if e.to_string().contains("UNIQUE constraint failed") => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(contract_id.to_string())
+    }
+}
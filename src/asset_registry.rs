@@ -0,0 +1,120 @@
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::routes::RegistryStatus;
+use crate::utils::get_current_timestamp;
+
+/// Time given to the registry to answer, so that issuance and listing don't wait on it for long
+const REGISTRY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Seconds the assets registered with a ticker are cached for
+#[cfg(not(test))]
+const REGISTRY_CACHE_SECS: u64 = 600;
+#[cfg(test)]
+const REGISTRY_CACHE_SECS: u64 = 2;
+/// Seconds during which the registry isn't queried again after failing to answer
+const REGISTRY_RETRY_SECS: u64 = 60;
+
+#[derive(Deserialize)]
+struct RegisteredAssets {
+    assets: Vec<RegisteredAsset>,
+}
+
+#[derive(Deserialize)]
+struct RegisteredAsset {
+    asset_id: String,
+}
+
+/// Client of the registry telling which assets are known under each ticker
+pub(crate) struct AssetRegistry {
+    url: String,
+    client: Client,
+    /// Asset IDs registered with each ticker, with the time they have been fetched at
+    cache: Mutex<HashMap<String, (u64, Vec<String>)>>,
+    /// Time of the last failed query, to avoid waiting on an unreachable registry for each asset
+    failed_at: Mutex<Option<u64>>,
+}
+
+impl AssetRegistry {
+    pub(crate) fn new(url: String) -> Self {
+        AssetRegistry {
+            url: url.trim_end_matches('/').to_string(),
+            client: Client::builder()
+                .timeout(REGISTRY_TIMEOUT)
+                .build()
+                .expect("valid registry client"),
+            cache: Mutex::new(HashMap::new()),
+            failed_at: Mutex::new(None),
+        }
+    }
+
+    /// IDs of the assets registered with the given ticker, None if the registry can't be reached
+    pub(crate) async fn registered_assets(&self, ticker: &str) -> Option<Vec<String>> {
+        let now = get_current_timestamp();
+        if let Some((fetched_at, asset_ids)) = self.cache.lock().unwrap().get(ticker) {
+            if now.saturating_sub(*fetched_at) < REGISTRY_CACHE_SECS {
+                return Some(asset_ids.clone());
+            }
+        }
+        if self
+            .failed_at
+            .lock()
+            .unwrap()
+            .is_some_and(|failed_at| now.saturating_sub(failed_at) < REGISTRY_RETRY_SECS)
+        {
+            return None;
+        }
+        let res = self
+            .client
+            .get(format!("{}/assets", self.url))
+            .query(&[("ticker", ticker)])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        let registered = match res {
+            Ok(res) => res.json::<RegisteredAssets>().await,
+            Err(e) => Err(e),
+        };
+        match registered {
+            Ok(registered) => {
+                let asset_ids: Vec<String> =
+                    registered.assets.into_iter().map(|a| a.asset_id).collect();
+                self.cache
+                    .lock()
+                    .unwrap()
+                    .insert(ticker.to_string(), (now, asset_ids.clone()));
+                Some(asset_ids)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to look up ticker {ticker} in the asset registry: {e}");
+                *self.failed_at.lock().unwrap() = Some(now);
+                None
+            }
+        }
+    }
+
+    /// Contract consignment of the given asset, as published on the registry
+    pub(crate) async fn contract(&self, asset_id: &str) -> Result<Vec<u8>, reqwest::Error> {
+        let res = self
+            .client
+            .get(format!("{}/contracts/{asset_id}", self.url))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    /// Whether the registry knows the asset under its ticker
+    pub(crate) async fn status(&self, asset_id: &str, ticker: &str) -> RegistryStatus {
+        match self.registered_assets(ticker).await {
+            None => RegistryStatus::Unverified,
+            Some(asset_ids) if asset_ids.iter().any(|id| id == asset_id) => {
+                RegistryStatus::Verified
+            }
+            Some(asset_ids) if asset_ids.is_empty() => RegistryStatus::Unregistered,
+            Some(_) => RegistryStatus::TickerConflict,
+        }
+    }
+}
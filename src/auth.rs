@@ -0,0 +1,88 @@
+use axum::{
+    extract::State,
+    http::{header, Request},
+    middleware::Next,
+    response::Response,
+};
+use bitcoin::hashes::{sha256, Hash};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::error::APIError;
+use crate::utils::AppState;
+use crate::{READ_ONLY_ROUTES, STOPPED_ROUTES};
+
+enum Role {
+    Admin,
+    ReadOnly,
+}
+
+/// Whether the provided token is the expected one, compared in a time that doesn't reveal how
+/// much of them matches
+pub(crate) fn token_matches(provided: &str, expected: Option<&str>) -> bool {
+    let Some(expected) = expected else {
+        return false;
+    };
+    // hashing first makes the comparison independent of the token lengths too
+    let provided = sha256::Hash::hash(provided.as_bytes());
+    let expected = sha256::Hash::hash(expected.as_bytes());
+    provided
+        .as_inner()
+        .iter()
+        .zip(expected.as_inner())
+        .fold(0, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+fn request_role<B>(state: &AppState, request: &Request<B>) -> Result<Role, APIError> {
+    let static_state = &state.static_state;
+    let api_token = match &static_state.api_token {
+        Some(api_token) => api_token,
+        None => return Ok(Role::Admin),
+    };
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or(APIError::Unauthorized)?;
+    if token_matches(provided, Some(api_token)) {
+        Ok(Role::Admin)
+    } else if token_matches(provided, static_state.read_only_api_token.as_deref()) {
+        Ok(Role::ReadOnly)
+    } else {
+        Err(APIError::Unauthorized)
+    }
+}
+
+/// Middleware rejecting requests without a valid API token, when tokens are configured, and
+/// requests to routes not declared as read-only when made with the read-only token
+pub(crate) async fn check_auth<B>(
+    State(state): State<Arc<AppState>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, APIError> {
+    match request_role(&state, &request)? {
+        Role::Admin => {}
+        Role::ReadOnly => {
+            if !READ_ONLY_ROUTES.contains(&request.uri().path()) {
+                return Err(APIError::PermissionDenied);
+            }
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+/// Middleware rejecting requests to the routes not needed to restart the node, while the API is
+/// stopped by a restartable shutdown
+pub(crate) async fn check_stopped<B>(
+    State(state): State<Arc<AppState>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, APIError> {
+    if state.api_stopped.load(Ordering::Acquire) && !STOPPED_ROUTES.contains(&request.uri().path())
+    {
+        return Err(APIError::NodeStopped);
+    }
+    Ok(next.run(request).await)
+}
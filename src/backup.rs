@@ -8,10 +8,12 @@ use typenum::consts::U32;
 use walkdir::WalkDir;
 use zip::write::FileOptions;
 
-use std::fs::{create_dir_all, read_to_string, remove_file, write, File};
+use std::fs::{create_dir_all, read, read_to_string, remove_file, write, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
+use crate::data_dir_lock::LOCK_FNAME;
+use crate::data_version::DataVersion;
 use crate::error::APIError;
 use crate::utils::LOGS_DIR;
 
@@ -19,9 +21,12 @@ const BACKUP_BUFFER_LEN_ENCRYPT: usize = 239; // 255 max, leaving 16 for the che
 const BACKUP_BUFFER_LEN_DECRYPT: usize = BACKUP_BUFFER_LEN_ENCRYPT + 16;
 const BACKUP_KEY_LENGTH: usize = 32;
 const BACKUP_NONCE_LENGTH: usize = 19;
-const BACKUP_VERSION: u8 = 1;
+/// Version of the backup layout, 2 added the data version of the node
+const BACKUP_VERSION: u8 = 2;
+const MIN_BACKUP_VERSION: u8 = 1;
 
 struct BackupPaths {
+    data_version: PathBuf,
     encrypted: PathBuf,
     nonce: PathBuf,
     salt: PathBuf,
@@ -40,7 +45,8 @@ struct CypherSecrets {
 ///
 /// Scrypt is used for hashing and xchacha20poly1305 is used for encryption. A random salt for
 /// hashing and a random nonce for encrypting are randomly generated and included in the final
-/// backup file, along with the backup version
+/// backup file, along with the backup version and, in clear so that it can be checked before
+/// decrypting, the data version of the node
 pub fn do_backup(wallet_dir: PathBuf, backup_path: &str, password: &str) -> Result<(), APIError> {
     // setup
     tracing::info!("starting backup...");
@@ -75,6 +81,10 @@ pub fn do_backup(wallet_dir: PathBuf, backup_path: &str, password: &str) -> Resu
     write(files.nonce, nonce)?;
     write(files.salt, salt)?;
     write(files.version, BACKUP_VERSION.to_string())?;
+    write(
+        files.data_version,
+        serde_json::to_vec(&DataVersion::current()).expect("valid JSON"),
+    )?;
     tracing::debug!("\nzipping {:?} to {:?}", &files.tempdir, &backup_file);
     _zip_dir(&PathBuf::from(files.tempdir.path()), &backup_file)?;
 
@@ -102,11 +112,18 @@ pub fn restore_backup(backup_path: &str, password: &str, target_dir: &str) -> Re
         .parse::<u8>()
         .map_err(|_| APIError::Unexpected)?;
     tracing::debug!("retrieved version: {}", &version);
-    if version != BACKUP_VERSION {
+    if !(MIN_BACKUP_VERSION..=BACKUP_VERSION).contains(&version) {
         return Err(APIError::UnsupportedBackupVersion {
             version: version.to_string(),
         });
     }
+    // older backups hold unversioned data, which gets migrated at unlock
+    if version >= 2 {
+        let data_version: DataVersion =
+            serde_json::from_slice(&read(files.data_version)?).map_err(|_| APIError::Unexpected)?;
+        tracing::debug!("retrieved data version: {:?}", &data_version);
+        data_version.check_supported()?;
+    }
 
     // decrypt backup and restore files
     tracing::info!("decrypting {:?} to {:?}", files.encrypted, files.zip);
@@ -121,12 +138,14 @@ pub fn restore_backup(backup_path: &str, password: &str, target_dir: &str) -> Re
 fn _get_backup_paths(tmp_base_path: &Path) -> Result<BackupPaths, APIError> {
     create_dir_all(tmp_base_path)?;
     let tempdir = tempfile::tempdir_in(tmp_base_path)?;
+    let data_version = tempdir.path().join("backup.data_version");
     let encrypted = tempdir.path().join("backup.enc");
     let nonce = tempdir.path().join("backup.nonce");
     let salt = tempdir.path().join("backup.salt");
     let version = tempdir.path().join("backup.version");
     let zip = tempdir.path().join("backup.zip");
     Ok(BackupPaths {
+        data_version,
         encrypted,
         nonce,
         salt,
@@ -160,7 +179,8 @@ fn _zip_dir(path_in: &PathBuf, path_out: &PathBuf) -> Result<(), APIError> {
             .map_err(|_| APIError::Unexpected)?;
         let name_str = name.to_str().ok_or_else(|| APIError::Unexpected)?;
         if path.is_file() {
-            if path.ends_with("log") {
+            // the lock belongs to the running daemon, not to the node data
+            if path.ends_with("log") || name == Path::new(LOCK_FNAME) {
                 continue;
             }
             tracing::debug!("adding file {path:?} as {name:?}");
@@ -12,8 +12,9 @@ use lightning_block_sync::{AsyncBlockSourceResult, BlockData, BlockHeaderData, B
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
 
 use crate::disk::FilesystemLogger;
 
@@ -22,6 +23,7 @@ pub struct BitcoindClient {
     fees: Arc<HashMap<ConfirmationTarget, AtomicU32>>,
     handle: tokio::runtime::Handle,
     logger: Arc<FilesystemLogger>,
+    broadcast_listener: Mutex<Option<UnboundedSender<Transaction>>>,
 }
 
 impl BlockSource for BitcoindClient {
@@ -161,6 +163,7 @@ impl BitcoindClient {
             fees: Arc::new(fees),
             handle: handle.clone(),
             logger,
+            broadcast_listener: Mutex::new(None),
         };
         BitcoindClient::poll_for_fee_estimates(
             client.fees.clone(),
@@ -170,6 +173,11 @@ impl BitcoindClient {
         Ok(client)
     }
 
+    /// Set where to send a copy of the transactions broadcast by LDK
+    pub(crate) fn set_broadcast_listener(&self, listener: Option<UnboundedSender<Transaction>>) {
+        *self.broadcast_listener.lock().unwrap() = listener;
+    }
+
     fn poll_for_fee_estimates(
         fees: Arc<HashMap<ConfirmationTarget, AtomicU32>>,
         rpc_client: Arc<RpcClient>,
@@ -266,6 +274,34 @@ impl BitcoindClient {
         });
     }
 
+    /// Estimate the fee rate, in sat/kw, getting a transaction confirmed within the given number
+    /// of blocks
+    ///
+    /// Without an estimate from bitcoind for the target, the closest polled estimate is used.
+    pub(crate) async fn estimate_fee_rate(&self, target_conf_blocks: u16) -> u32 {
+        let resp = self
+            .bitcoind_rpc_client
+            .call_method::<FeeResponse>(
+                "estimatesmartfee",
+                &[
+                    serde_json::json!(target_conf_blocks),
+                    serde_json::json!("ECONOMICAL"),
+                ],
+            )
+            .await;
+        match resp.ok().and_then(|r| r.feerate_sat_per_kw) {
+            Some(feerate) => std::cmp::max(feerate, MIN_FEERATE),
+            None => {
+                let confirmation_target = match target_conf_blocks {
+                    0..=6 => ConfirmationTarget::OnChainSweep,
+                    7..=18 => ConfirmationTarget::NonAnchorChannelFee,
+                    _ => ConfirmationTarget::ChannelCloseMinimum,
+                };
+                self.get_est_sat_per_1000_weight(confirmation_target)
+            }
+        }
+    }
+
     pub async fn get_blockchain_info(&self) -> BlockchainInfo {
         self.bitcoind_rpc_client
             .call_method::<BlockchainInfo>("getblockchaininfo", &[])
@@ -288,6 +324,9 @@ impl BroadcasterInterface for BitcoindClient {
         // TODO: Rather than calling `sendrawtransaction` in a a loop, we should probably use
         // `submitpackage` once it becomes available.
         for tx in txs {
+            if let Some(listener) = self.broadcast_listener.lock().unwrap().as_ref() {
+                let _ = listener.send((*tx).clone());
+            }
             let bitcoind_rpc_client = Arc::clone(&self.bitcoind_rpc_client);
             let tx_serialized = encode::serialize_hex(tx);
             let tx_json = serde_json::json!(tx_serialized);
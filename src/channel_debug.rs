@@ -0,0 +1,152 @@
+use bitcoin::hashes::hex::ToHex;
+use lightning::ln::channelmanager::PaymentId;
+use lightning::ln::ChannelId;
+use lightning::rgb_utils::{
+    get_rgb_channel_info_path, get_rgb_payment_info_path, parse_rgb_channel_info,
+    parse_rgb_payment_info,
+};
+use std::path::PathBuf;
+
+use crate::error::APIError;
+use crate::routes::{
+    ChannelDebugHtlc, ChannelDebugPeer, ChannelDebugResponse, ChannelDebugRgb, CommitmentType,
+    ReestablishState,
+};
+use crate::utils::{hex_str, UnlockedAppState};
+
+impl UnlockedAppState {
+    /// Take a snapshot of the state of an open channel as the node understands it, to debug a
+    /// channel stuck or misbehaving
+    ///
+    /// Nothing secret is included: no keys, and no preimages, not even of the HTLCs already
+    /// claimed.
+    pub(crate) fn channel_debug(
+        &self,
+        ldk_data_dir: &str,
+        channel_id: ChannelId,
+    ) -> Result<ChannelDebugResponse, APIError> {
+        let chan_info = self
+            .channel_manager
+            .list_channels()
+            .into_iter()
+            .find(|c| c.channel_id == channel_id)
+            .ok_or(APIError::UnknownChannel)?;
+        let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
+        let cur_height = self.channel_manager.current_best_block().height();
+
+        // LDK keeps the commitment numbers to itself, the monitor update ID moving along with
+        // them as each new commitment gets persisted
+        let (monitor_update_id, pending_monitor_updates) = match chan_info.funding_txo {
+            Some(funding_txo) => (
+                self.chain_monitor
+                    .get_monitor(funding_txo)
+                    .ok()
+                    .map(|m| m.get_latest_update_id()),
+                self.chain_monitor
+                    .list_pending_monitor_updates()
+                    .get(&funding_txo)
+                    .map_or(0, |u| u.len()),
+            ),
+            None => (None, 0),
+        };
+
+        let inbound_payments = self.inbound_payments();
+        let outbound_payments = self.outbound_payments();
+        let htlcs: Vec<ChannelDebugHtlc> = self
+            .pending_htlcs()
+            .into_iter()
+            .filter(|h| h.channel_id == channel_id)
+            .map(|htlc| {
+                let payment = if htlc.inbound {
+                    inbound_payments.get(&htlc.payment_hash)
+                } else {
+                    outbound_payments.get(&PaymentId(htlc.payment_hash.0))
+                };
+                let rgb_payment_info_path =
+                    get_rgb_payment_info_path(&htlc.payment_hash, &ldk_data_dir_path);
+                ChannelDebugHtlc {
+                    inbound: htlc.inbound,
+                    amt_msat: htlc.amt_msat,
+                    asset_amount: rgb_payment_info_path
+                        .exists()
+                        .then(|| parse_rgb_payment_info(&rgb_payment_info_path).amount),
+                    payment_hash: hex_str(&htlc.payment_hash.0),
+                    payment_status: payment.map(|p| p.status),
+                    expiry_height: htlc.expiry_height,
+                    blocks_to_force_close: htlc.force_close_height.saturating_sub(cur_height),
+                }
+            })
+            .collect();
+
+        let info_file_path =
+            get_rgb_channel_info_path(&channel_id.to_hex(), &ldk_data_dir_path, false);
+        let rgb = info_file_path.exists().then(|| {
+            let rgb_info = parse_rgb_channel_info(&info_file_path);
+            let pending_info_file_path =
+                get_rgb_channel_info_path(&channel_id.to_hex(), &ldk_data_dir_path, true);
+            let pending_info = pending_info_file_path
+                .exists()
+                .then(|| parse_rgb_channel_info(&pending_info_file_path));
+            ChannelDebugRgb {
+                asset_id: rgb_info.contract_id.to_string(),
+                local_amount: rgb_info.local_rgb_amount,
+                remote_amount: rgb_info.remote_rgb_amount,
+                pending_local_amount: pending_info.as_ref().map(|i| i.local_rgb_amount),
+                pending_remote_amount: pending_info.as_ref().map(|i| i.remote_rgb_amount),
+                htlc_amount: htlcs.iter().filter_map(|h| h.asset_amount).sum(),
+            }
+        });
+
+        let peer_pubkey = chan_info.counterparty.node_id;
+        let connected = self
+            .peer_manager
+            .get_peer_node_ids()
+            .iter()
+            .any(|(pubkey, _)| *pubkey == peer_pubkey);
+        let connection = self
+            .database
+            .peer_connections()?
+            .remove(&peer_pubkey.to_string());
+        // LDK only considers the channel usable again once the channel_reestablish exchange
+        // following the last connection has succeeded, force-closing it if it has failed
+        let reestablish = if !connected {
+            ReestablishState::PeerDisconnected
+        } else if !chan_info.is_channel_ready {
+            ReestablishState::AwaitingChannelReady
+        } else if chan_info.is_usable {
+            ReestablishState::Completed
+        } else {
+            ReestablishState::Pending
+        };
+
+        Ok(ChannelDebugResponse {
+            channel_id: channel_id.to_hex(),
+            peer_pubkey: hex_str(&peer_pubkey.serialize()),
+            funding_txid: chan_info.funding_txo.map(|o| o.txid.to_string()),
+            short_channel_id: chan_info.short_channel_id,
+            outbound: chan_info.is_outbound,
+            ready: chan_info.is_channel_ready,
+            is_usable: chan_info.is_usable,
+            commitment_type: chan_info.channel_type.as_ref().map(CommitmentType::from),
+            monitor_update_id,
+            pending_monitor_updates,
+            feerate_sat_per_1000_weight: chan_info.feerate_sat_per_1000_weight,
+            capacity_sat: chan_info.channel_value_satoshis,
+            local_balance_msat: chan_info.balance_msat,
+            outbound_capacity_msat: chan_info.outbound_capacity_msat,
+            inbound_capacity_msat: chan_info.inbound_capacity_msat,
+            our_reserve_sat: chan_info.unspendable_punishment_reserve,
+            their_reserve_sat: chan_info.counterparty.unspendable_punishment_reserve,
+            to_self_delay: chan_info.force_close_spend_delay,
+            htlcs,
+            rgb,
+            peer: ChannelDebugPeer {
+                connected,
+                connected_since: connection.as_ref().and_then(|c| c.connected_since),
+                last_seen: connection.as_ref().and_then(|c| c.last_seen),
+                disconnect_count: connection.map_or(0, |c| c.disconnect_count),
+                reestablish,
+            },
+        })
+    }
+}
@@ -0,0 +1,242 @@
+use amplify::s;
+use bitcoin::{OutPoint, Transaction, Txid};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::bdk::get_tx;
+use crate::database::{ChannelFeeKind, ChannelFeeRate};
+use crate::error::APIError;
+use crate::utils::{StaticState, UnlockedAppState};
+
+/// Upper byte of the locktime of commitment transactions (BOLT 3)
+const COMMITMENT_LOCKTIME_TAG: u32 = 0x20;
+
+/// Highest confirmation target bitcoind estimates fee rates for
+const MAX_TARGET_CONF_BLOCKS: u16 = 1008;
+
+/// Transactions broadcast by the node, kept to attribute their fees to channels
+#[derive(Default)]
+pub(crate) struct ChannelFeeTracker {
+    // transactions broadcast since the node has been unlocked, which may not be known to the
+    // indexer yet when their children get broadcast
+    broadcast_txs: Mutex<HashMap<Txid, Transaction>>,
+    // transaction spending each outpoint, to detect fee bumps replacing a recorded transaction
+    spent_outpoints: Mutex<HashMap<OutPoint, Txid>>,
+}
+
+/// Decide the fee rate of a channel transaction, either the requested one or the one estimated
+/// for the requested confirmation target, None if neither has been requested
+pub(crate) async fn choose_fee_rate(
+    static_state: &StaticState,
+    fee_rate_sat_vb: Option<f32>,
+    target_conf_blocks: Option<u16>,
+) -> Result<Option<ChannelFeeRate>, APIError> {
+    match (fee_rate_sat_vb, target_conf_blocks) {
+        (Some(_), Some(_)) => Err(APIError::InvalidFeeRate(s!(
+            "only one of fee_rate_sat_vb and target_conf_blocks can be provided"
+        ))),
+        (Some(fee_rate_sat_vb), None) => {
+            if fee_rate_sat_vb < 1.0 {
+                return Err(APIError::InvalidFeeRate(s!("cannot be less than 1 sat/vB")));
+            }
+            Ok(Some(ChannelFeeRate {
+                target_conf_blocks: None,
+                fee_rate_sat_vb,
+            }))
+        }
+        (None, Some(target_conf_blocks)) => {
+            if target_conf_blocks == 0 || target_conf_blocks > MAX_TARGET_CONF_BLOCKS {
+                return Err(APIError::InvalidFeeRate(format!(
+                    "confirmation target must be between 1 and {MAX_TARGET_CONF_BLOCKS} blocks"
+                )));
+            }
+            let sat_per_kw = static_state
+                .bitcoind_client
+                .estimate_fee_rate(target_conf_blocks)
+                .await;
+            Ok(Some(ChannelFeeRate {
+                target_conf_blocks: Some(target_conf_blocks),
+                fee_rate_sat_vb: sat_per_kw as f32 / 250.0, // 1 sat/vB = 250 sat/kw
+            }))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Record the fees of the channel transactions broadcast by LDK until the node gets stopped
+pub(crate) async fn process_broadcast_txs(
+    mut receiver: UnboundedReceiver<Transaction>,
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    loop {
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        let tx = match tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await {
+            Ok(Some(tx)) => tx,
+            Ok(None) => return,
+            Err(_) => continue,
+        };
+        let unlocked_state = Arc::clone(&unlocked_state);
+        let static_state = Arc::clone(&static_state);
+        tokio::task::spawn_blocking(move || record_channel_tx(&unlocked_state, &static_state, &tx))
+            .await
+            .unwrap();
+    }
+}
+
+/// Record the fee paid by a transaction if it closes one of our channels or claims the outputs of
+/// a force-close
+pub(crate) fn record_channel_tx(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    tx: &Transaction,
+) {
+    let txid = tx.txid();
+    let tracker = &unlocked_state.channel_fee_tracker;
+    tracker
+        .broadcast_txs
+        .lock()
+        .unwrap()
+        .insert(txid, tx.clone());
+
+    let attribution = match attribute_channel_tx(unlocked_state, static_state, tx) {
+        Ok(Some(attribution)) => attribution,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("Failed to attribute transaction {txid} to a channel: {e}");
+            return;
+        }
+    };
+    let (channel_id, kind, fee_sat, tx_fee_sat) = attribution;
+
+    // a transaction spending the same outpoints as a recorded one is a fee bump replacing it
+    let mut spent_outpoints = tracker.spent_outpoints.lock().unwrap();
+    for input in &tx.input {
+        if let Some(replaced_txid) = spent_outpoints.insert(input.previous_output, txid) {
+            if replaced_txid != txid {
+                if let Err(e) = unlocked_state
+                    .database
+                    .remove_channel_fee(&replaced_txid.to_string())
+                {
+                    tracing::error!("Failed to remove fee of replaced transaction: {e}");
+                }
+            }
+        }
+    }
+
+    // commitment and HTLC transactions pay to P2WSH outputs we later claim, while sweeps and
+    // anchor bumps pay to the wallet
+    let claimable = kind == ChannelFeeKind::ForceClose
+        && tx.output.iter().any(|o| o.script_pubkey.is_v0_p2wsh());
+    tracing::info!("Recording {fee_sat} sat {kind:?} fee of {txid} for channel {channel_id}");
+    if let Err(e) = unlocked_state.database.add_channel_fee(
+        &txid.to_string(),
+        &channel_id,
+        kind,
+        fee_sat,
+        claimable,
+        true,
+    ) {
+        tracing::error!("Failed to record channel fee: {e}");
+    }
+    // the fee rate chosen for a cooperative close is only a target of the negotiation
+    if kind == ChannelFeeKind::Close && fee_sat > 0 {
+        let fee_rate_sat_vb = fee_sat as f32 / tx.vsize() as f32;
+        if let Err(e) =
+            unlocked_state
+                .database
+                .update_channel_fee_rate(&channel_id, kind, fee_rate_sat_vb)
+        {
+            tracing::error!("Failed to record channel fee rate: {e}");
+        }
+    }
+    // both sides broadcast the closing transaction they have agreed on, whoever pays for it
+    if kind == ChannelFeeKind::Close {
+        let fee_rate_sat_vb = tx_fee_sat as f32 / tx.vsize() as f32;
+        if let Err(e) = unlocked_state
+            .database
+            .set_negotiated_close_fee_rate(&channel_id, fee_rate_sat_vb)
+        {
+            tracing::error!("Failed to record negotiated closing fee rate: {e}");
+        }
+    }
+}
+
+/// Find the channel a transaction belongs to, with the fee the node pays for it and the whole fee
+/// the transaction pays
+fn attribute_channel_tx(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    tx: &Transaction,
+) -> Result<Option<(String, ChannelFeeKind, u64, u64)>, APIError> {
+    let database = &unlocked_state.database;
+    let output_sat: u64 = tx.output.iter().map(|o| o.value).sum();
+
+    for input in &tx.input {
+        let prevout = input.previous_output;
+        let Some(funding) = database.channel_funding(&prevout.txid.to_string(), prevout.vout)?
+        else {
+            continue;
+        };
+        let kind = if tx.lock_time.0 >> 24 == COMMITMENT_LOCKTIME_TAG {
+            ChannelFeeKind::ForceClose
+        } else {
+            ChannelFeeKind::Close
+        };
+        // the fee of commitment and closing transactions is paid by the channel funder
+        let tx_fee_sat = funding.capacity_sat.saturating_sub(output_sat);
+        let fee_sat = if funding.outbound { tx_fee_sat } else { 0 };
+        return Ok(Some((funding.channel_id, kind, fee_sat, tx_fee_sat)));
+    }
+
+    for input in &tx.input {
+        let Some(channel_id) =
+            database.claimable_tx_channel(&input.previous_output.txid.to_string())?
+        else {
+            continue;
+        };
+        let Some(fee_sat) = get_tx_fee(unlocked_state, static_state, tx) else {
+            tracing::warn!("Cannot compute the fee of {}", tx.txid());
+            return Ok(None);
+        };
+        return Ok(Some((
+            channel_id,
+            ChannelFeeKind::ForceClose,
+            fee_sat,
+            fee_sat,
+        )));
+    }
+
+    Ok(None)
+}
+
+fn get_tx_fee(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    tx: &Transaction,
+) -> Option<u64> {
+    let mut input_sat = 0;
+    for input in &tx.input {
+        let prevout = input.previous_output;
+        let known_tx = unlocked_state
+            .channel_fee_tracker
+            .broadcast_txs
+            .lock()
+            .unwrap()
+            .get(&prevout.txid)
+            .cloned();
+        let prev_tx = match known_tx {
+            Some(prev_tx) => prev_tx,
+            None => get_tx(&prevout.txid, static_state.electrum_url.clone())?,
+        };
+        input_sat += prev_tx.output.get(prevout.vout as usize)?.value;
+    }
+    let output_sat: u64 = tx.output.iter().map(|o| o.value).sum();
+    input_sat.checked_sub(output_sat)
+}
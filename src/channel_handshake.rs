@@ -0,0 +1,104 @@
+use amplify::s;
+use clap::ValueEnum;
+use lightning::util::config::{ChannelHandshakeConfig, UserConfig};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// Lowest to_self_delay LDK accepts to propose, leaving the node a day to punish a revoked
+/// commitment
+pub(crate) const MIN_TO_SELF_DELAY: u16 = 144;
+/// Highest to_self_delay, lnd refusing channels asking for more
+pub(crate) const MAX_TO_SELF_DELAY: u16 = 2016;
+/// Lowest dust limit allowed by the protocol, below which segwit outputs aren't relayed
+pub(crate) const MIN_DUST_LIMIT_SAT: u64 = 354;
+/// Dust limit of P2PKH outputs, the highest among the standard ones
+pub(crate) const DEFAULT_DUST_LIMIT_SAT: u64 = 546;
+
+/// Announcement the node requires of the channels its peers open to it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AnnouncedChannelPreference {
+    /// Accept both public and private channels
+    #[default]
+    Any,
+    /// Only accept channels to be announced
+    Public,
+    /// Only accept unannounced channels
+    Private,
+}
+
+/// Handshake parameters proposed for the channels opened from now on
+///
+/// They only apply to channels negotiated after the node unlocked with them, the existing channels
+/// keep the parameters they were opened with.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) struct ChannelHandshakeDefaults {
+    /// Blocks the peer waits for its funds after force-closing a channel
+    pub(crate) to_self_delay: u16,
+    /// Share (in %) of the channel capacity the peer can have in flight towards the node
+    pub(crate) max_htlc_value_in_flight_percent: u8,
+    pub(crate) announced_channel_preference: AnnouncedChannelPreference,
+    /// Smallest amount (in sats) the node pushes to the peer when opening a channel
+    pub(crate) dust_limit_sat: u64,
+}
+
+impl Default for ChannelHandshakeDefaults {
+    fn default() -> Self {
+        Self {
+            to_self_delay: MIN_TO_SELF_DELAY,
+            max_htlc_value_in_flight_percent: 10,
+            announced_channel_preference: AnnouncedChannelPreference::Any,
+            dust_limit_sat: DEFAULT_DUST_LIMIT_SAT,
+        }
+    }
+}
+
+impl ChannelHandshakeDefaults {
+    pub(crate) fn validate(&self) -> Result<(), AppError> {
+        if !(MIN_TO_SELF_DELAY..=MAX_TO_SELF_DELAY).contains(&self.to_self_delay) {
+            return Err(AppError::InvalidChannelHandshake(format!(
+                "to_self_delay must be between {MIN_TO_SELF_DELAY} and {MAX_TO_SELF_DELAY} blocks"
+            )));
+        }
+        if !(1..=100).contains(&self.max_htlc_value_in_flight_percent) {
+            return Err(AppError::InvalidChannelHandshake(s!(
+                "max HTLC value in flight must be between 1 and 100%"
+            )));
+        }
+        if self.dust_limit_sat < MIN_DUST_LIMIT_SAT {
+            return Err(AppError::InvalidChannelHandshake(format!(
+                "dust limit cannot be lower than the protocol floor ({MIN_DUST_LIMIT_SAT} sat)"
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn dust_limit_msat(&self) -> u64 {
+        self.dust_limit_sat * 1000
+    }
+
+    /// Apply the parameters the node proposes to the handshake of a new channel
+    pub(crate) fn apply(&self, config: &mut ChannelHandshakeConfig) {
+        config.our_to_self_delay = self.to_self_delay;
+        config.max_inbound_htlc_value_in_flight_percent_of_channel =
+            self.max_htlc_value_in_flight_percent;
+    }
+
+    /// Apply the parameters to the config of the channels opened by the peers, also refusing the
+    /// ones not matching the announcement preference
+    pub(crate) fn apply_inbound(&self, config: &mut UserConfig) {
+        self.apply(&mut config.channel_handshake_config);
+        // peers may ask for as long a delay as the node can ask for itself
+        config.channel_handshake_limits.their_to_self_delay = MAX_TO_SELF_DELAY;
+        let announced_channel = match self.announced_channel_preference {
+            AnnouncedChannelPreference::Any => None,
+            AnnouncedChannelPreference::Public => Some(true),
+            AnnouncedChannelPreference::Private => Some(false),
+        };
+        config
+            .channel_handshake_limits
+            .force_announced_channel_preference = announced_channel.is_some();
+        config.channel_handshake_config.announced_channel = announced_channel.unwrap_or_default();
+    }
+}
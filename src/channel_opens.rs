@@ -0,0 +1,455 @@
+use amplify::s;
+use bitcoin::hashes::hex::ToHex;
+use bitcoin::secp256k1::PublicKey;
+use clap::ValueEnum;
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::ChannelId;
+use lightning::rgb_utils::get_rgb_channel_info_path;
+use lightning::sign::EntropySource;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::database::ChannelFeeRate;
+use crate::error::APIError;
+use crate::peer_messages::PeerMessage;
+use crate::routes::{ChannelRequestDecision, NodeEvent};
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+/// Seconds after which the node gives up on an outbound channel whose negotiation the
+/// counterparty hasn't completed
+#[cfg(test)]
+pub(crate) const CHANNEL_NEGOTIATION_TIMEOUT_SECS: u64 = 30;
+#[cfg(not(test))]
+pub(crate) const CHANNEL_NEGOTIATION_TIMEOUT_SECS: u64 = 60 * 5;
+
+/// Time the node waits for the asset details of an inbound channel, sent by the opener right
+/// after the channel itself
+const CHANNEL_PROPOSAL_WAIT: Duration = Duration::from_secs(5);
+
+/// Longest time an inbound channel can wait for the user decision once notified, as the channel
+/// manager drops the requests left unanswered for a minute
+pub(crate) const MAX_CHANNEL_REQUEST_TIMEOUT_SECS: u64 = 50;
+
+/// How the node answers the channels its peers open to it
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub(crate) enum ChannelAcceptancePolicy {
+    #[default]
+    Accept,
+    Reject,
+    /// Hold the channels until the user accepts or rejects them
+    Ask,
+}
+
+/// Asset details of a channel, sent by the opener along with the channel as the counterparty
+/// would otherwise only learn them from the funding consignment
+#[derive(Clone, Debug)]
+pub(crate) struct ChannelProposal {
+    pub(crate) temporary_channel_id: String,
+    pub(crate) asset_id: String,
+    pub(crate) asset_amount: u64,
+}
+
+impl_writeable_tlv_based!(ChannelProposal, {
+    (0, temporary_channel_id, required),
+    (2, asset_id, required),
+    (4, asset_amount, required),
+});
+
+/// Inbound channel held until the user accepts or rejects it
+#[derive(Clone)]
+pub(crate) struct HeldChannelRequest {
+    pub(crate) peer_pubkey: PublicKey,
+    pub(crate) capacity_sat: u64,
+    pub(crate) push_msat: u64,
+    /// Asset details announced by the opener, if they have arrived
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+}
+
+/// Inbound channels waiting for their asset details or for the user decision
+#[derive(Default)]
+pub(crate) struct InboundChannels {
+    /// Asset details sent by the openers and when they arrived, by peer and temporary channel ID
+    proposals: Mutex<HashMap<(PublicKey, String), (ChannelProposal, u64)>>,
+    /// Requests waiting for the user decision, by temporary channel ID
+    held: Mutex<HashMap<String, HeldChannelRequest>>,
+}
+
+impl InboundChannels {
+    fn add_proposal(&self, peer_pubkey: PublicKey, proposal: ChannelProposal) {
+        let now = get_current_timestamp();
+        let mut proposals = self.proposals.lock().unwrap();
+        // drop the proposals of the channels that never came
+        proposals.retain(|_, (_, received_at)| {
+            now.saturating_sub(*received_at) <= CHANNEL_NEGOTIATION_TIMEOUT_SECS
+        });
+        proposals.insert(
+            (peer_pubkey, proposal.temporary_channel_id.clone()),
+            (proposal, now),
+        );
+    }
+
+    fn take_proposal(
+        &self,
+        peer_pubkey: PublicKey,
+        temporary_channel_id: &str,
+    ) -> Option<ChannelProposal> {
+        self.proposals
+            .lock()
+            .unwrap()
+            .remove(&(peer_pubkey, temporary_channel_id.to_string()))
+            .map(|(proposal, _)| proposal)
+    }
+
+    /// Requests waiting for the user decision, by temporary channel ID
+    pub(crate) fn held(&self) -> Vec<(String, HeldChannelRequest)> {
+        self.held
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, request)| (id.clone(), request.clone()))
+            .collect()
+    }
+
+    /// Stop holding a request, returning it if it was waiting for the user decision
+    pub(crate) fn take_held(&self, temporary_channel_id: &str) -> Option<HeldChannelRequest> {
+        self.held.lock().unwrap().remove(temporary_channel_id)
+    }
+}
+
+/// Outbound channel whose negotiation with the counterparty isn't complete yet
+pub(crate) struct PendingChannelOpen {
+    pub(crate) peer_pubkey: PublicKey,
+    pub(crate) asset_id: String,
+    pub(crate) asset_amount: u64,
+    /// Fee rate the funding transaction pays
+    pub(crate) fee_rate: ChannelFeeRate,
+    pub(crate) started_at: u64,
+    /// Transport endpoint announced to the peer for the funding consignment
+    pub(crate) consignment_endpoint: String,
+    /// Confirmations requested before the channel becomes usable
+    pub(crate) min_depth: u32,
+    /// Funding transaction and resulting channel ID, once the funding has been handed to LDK
+    pub(crate) funding_txid: Option<String>,
+    pub(crate) channel_id: Option<String>,
+    /// Why the node itself dropped the channel, if it did
+    pub(crate) failure: Option<String>,
+}
+
+/// Undo the funding preparation of an outbound channel closed before its negotiation completed
+/// and notify the failure, returning false if the channel wasn't being opened
+///
+/// The wallet only records the funding transfer once the counterparty has signed it, so the
+/// asset allocations and BTC UTXOs it selected are already available again.
+pub(crate) fn rollback_channel_open(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    channel_id: &str,
+    reason: String,
+) -> bool {
+    let mut opens = unlocked_state.pending_channel_opens.lock().unwrap();
+    let Some(temporary_channel_id) = opens
+        .iter()
+        .find(|(temp_id, o)| *temp_id == channel_id || o.channel_id.as_deref() == Some(channel_id))
+        .map(|(temp_id, _)| temp_id.clone())
+    else {
+        return false;
+    };
+    let open = opens.remove(&temporary_channel_id).unwrap();
+    drop(opens);
+
+    let ldk_data_dir = PathBuf::from(&static_state.ldk_data_dir);
+    for id in std::iter::once(&temporary_channel_id).chain(open.channel_id.as_ref()) {
+        for pending in [true, false] {
+            let _ = fs::remove_file(get_rgb_channel_info_path(id, &ldk_data_dir, pending));
+        }
+    }
+    if let Some(funding_txid) = &open.funding_txid {
+        let _ = fs::remove_file(ldk_data_dir.join(format!("psbt_{funding_txid}")));
+        unlocked_state.release_utxos(funding_txid, "channel open failed");
+    }
+
+    let reason = open.failure.unwrap_or(reason);
+    tracing::info!("Rolled back the opening of channel {temporary_channel_id}: {reason}");
+    static_state.emit_event(NodeEvent::ChannelOpenFailed {
+        temporary_channel_id,
+        peer_pubkey: open.peer_pubkey.to_string(),
+        asset_id: open.asset_id,
+        asset_amount: open.asset_amount,
+        reason,
+    });
+    true
+}
+
+/// Drop the outbound channels whose negotiation takes too long until the node gets stopped, they
+/// then get rolled back as any other failed channel open
+pub(crate) async fn watch_channel_opens(
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    #[cfg(test)]
+    let interval_secs = 5;
+    #[cfg(not(test))]
+    let interval_secs = 60;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+
+        let now = get_current_timestamp();
+        let expired: Vec<String> = unlocked_state
+            .pending_channel_opens
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter(|(_, o)| {
+                o.failure.is_none()
+                    && now.saturating_sub(o.started_at) > CHANNEL_NEGOTIATION_TIMEOUT_SECS
+            })
+            .map(|(temp_id, o)| {
+                o.failure = Some(format!(
+                    "negotiation timed out after {CHANNEL_NEGOTIATION_TIMEOUT_SECS} seconds"
+                ));
+                o.channel_id.clone().unwrap_or(temp_id.clone())
+            })
+            .collect();
+
+        for channel_id in expired {
+            let chan_info = unlocked_state
+                .channel_manager
+                .list_channels()
+                .into_iter()
+                .find(|c| c.channel_id.to_hex() == channel_id);
+            match chan_info {
+                Some(chan_info) => {
+                    // the closure gets handled by the ChannelClosed event
+                    let _ = unlocked_state
+                        .channel_manager
+                        .force_close_without_broadcasting_txn(
+                            &chan_info.channel_id,
+                            &chan_info.counterparty.node_id,
+                        );
+                }
+                None => {
+                    rollback_channel_open(
+                        &unlocked_state,
+                        &static_state,
+                        &channel_id,
+                        String::new(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Send the asset details of an outbound channel to the counterparty
+pub(crate) fn send_channel_proposal(
+    unlocked_state: &UnlockedAppState,
+    peer_pubkey: PublicKey,
+    proposal: ChannelProposal,
+) {
+    // peers without RGB channels support would ignore it
+    if unlocked_state
+        .peer_message_handler
+        .supports_rgb_channels(&peer_pubkey)
+    {
+        unlocked_state.send_peer_message(peer_pubkey, PeerMessage::ChannelProposal(proposal));
+    }
+}
+
+/// Record the asset details of a channel the peer is opening to the node
+pub(crate) fn handle_channel_proposal(
+    unlocked_state: &UnlockedAppState,
+    peer_pubkey: PublicKey,
+    proposal: ChannelProposal,
+) {
+    unlocked_state
+        .inbound_channels
+        .add_proposal(peer_pubkey, proposal);
+}
+
+/// Accept an inbound channel
+pub(crate) fn accept_inbound_channel(
+    unlocked_state: &UnlockedAppState,
+    temporary_channel_id: &ChannelId,
+    peer_pubkey: &PublicKey,
+) -> Result<(), APIError> {
+    let mut random_bytes = [0u8; 16];
+    random_bytes.copy_from_slice(&unlocked_state.keys_manager.get_secure_random_bytes()[..16]);
+    let user_channel_id = u128::from_be_bytes(random_bytes);
+    unlocked_state
+        .channel_manager
+        .accept_inbound_channel(temporary_channel_id, peer_pubkey, user_channel_id)
+        .map_err(|e| APIError::FailedOpenChannel(format!("{:?}", e)))?;
+    tracing::info!(
+        "EVENT: Accepted inbound channel ({}) from {}",
+        temporary_channel_id,
+        peer_pubkey,
+    );
+    Ok(())
+}
+
+/// Reject an inbound channel, the counterparty gets an error message closing it
+pub(crate) fn reject_inbound_channel(
+    unlocked_state: &UnlockedAppState,
+    temporary_channel_id: &ChannelId,
+    peer_pubkey: &PublicKey,
+    reason: &str,
+) {
+    let _ = unlocked_state
+        .channel_manager
+        .force_close_without_broadcasting_txn(temporary_channel_id, peer_pubkey);
+    tracing::info!(
+        "EVENT: Rejected inbound channel ({}) from {}: {reason}",
+        temporary_channel_id,
+        peer_pubkey,
+    );
+}
+
+/// Answer a channel a peer opens to the node as the acceptance policy says, then notify it once
+/// its asset details have arrived
+pub(crate) fn handle_inbound_channel(
+    unlocked_state: &Arc<UnlockedAppState>,
+    static_state: &Arc<StaticState>,
+    temporary_channel_id: ChannelId,
+    peer_pubkey: PublicKey,
+    capacity_sat: u64,
+    push_msat: u64,
+    anchors: bool,
+) {
+    let policy = static_state.channel_acceptance_policy;
+    let rejection = if let Err(e) = static_state.check_writable() {
+        Some(e.to_string())
+    } else if unlocked_state.recovery_mode {
+        Some(s!("the node is in recovery mode"))
+    } else if policy == ChannelAcceptancePolicy::Reject {
+        Some(s!("refused by the acceptance policy"))
+    } else if !anchors && !static_state.accept_non_anchor_channels {
+        Some(s!("channels without anchor outputs are not accepted"))
+    } else {
+        None
+    };
+
+    let (decision, reason) = match rejection {
+        Some(reason) => {
+            reject_inbound_channel(unlocked_state, &temporary_channel_id, &peer_pubkey, &reason);
+            (ChannelRequestDecision::Rejected, Some(reason))
+        }
+        None if policy == ChannelAcceptancePolicy::Ask => {
+            unlocked_state.inbound_channels.held.lock().unwrap().insert(
+                temporary_channel_id.to_hex(),
+                HeldChannelRequest {
+                    peer_pubkey,
+                    capacity_sat,
+                    push_msat,
+                    asset_id: None,
+                    asset_amount: None,
+                },
+            );
+            (ChannelRequestDecision::AwaitingDecision, None)
+        }
+        None => match accept_inbound_channel(unlocked_state, &temporary_channel_id, &peer_pubkey) {
+            Ok(()) => (ChannelRequestDecision::Accepted, None),
+            Err(e) => {
+                tracing::error!(
+                    "EVENT: Failed to accept inbound channel ({}) from {}: {e}",
+                    temporary_channel_id,
+                    peer_pubkey,
+                );
+                (ChannelRequestDecision::Rejected, Some(e.to_string()))
+            }
+        },
+    };
+
+    tokio::spawn(notify_inbound_channel(
+        Arc::clone(unlocked_state),
+        Arc::clone(static_state),
+        temporary_channel_id,
+        peer_pubkey,
+        capacity_sat,
+        push_msat,
+        decision,
+        reason,
+    ));
+}
+
+/// Emit the event of an inbound channel once its asset details have arrived, or without them if
+/// the peer doesn't send them in time, then give the user the configured time to decide on a
+/// held channel
+#[allow(clippy::too_many_arguments)]
+async fn notify_inbound_channel(
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    channel_id: ChannelId,
+    peer_pubkey: PublicKey,
+    capacity_sat: u64,
+    push_msat: u64,
+    decision: ChannelRequestDecision,
+    reason: Option<String>,
+) {
+    let temporary_channel_id = channel_id.to_hex();
+    let inbound_channels = &unlocked_state.inbound_channels;
+    // only peers supporting RGB channels send the asset details
+    let waiting_proposal = unlocked_state
+        .peer_message_handler
+        .supports_rgb_channels(&peer_pubkey);
+    let t_0 = Instant::now();
+    let proposal = loop {
+        let proposal = inbound_channels.take_proposal(peer_pubkey, &temporary_channel_id);
+        if proposal.is_some() || !waiting_proposal || t_0.elapsed() > CHANNEL_PROPOSAL_WAIT {
+            break proposal;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+    let (asset_id, asset_amount) = proposal.map(|p| (p.asset_id, p.asset_amount)).unzip();
+
+    if let Some(request) = inbound_channels
+        .held
+        .lock()
+        .unwrap()
+        .get_mut(&temporary_channel_id)
+    {
+        request.asset_id = asset_id.clone();
+        request.asset_amount = asset_amount;
+    }
+    let held = decision == ChannelRequestDecision::AwaitingDecision;
+    static_state.emit_event(NodeEvent::InboundChannelRequest {
+        temporary_channel_id: temporary_channel_id.clone(),
+        peer_pubkey: peer_pubkey.to_string(),
+        capacity_sat,
+        push_msat,
+        asset_id: asset_id.clone(),
+        asset_amount,
+        decision,
+        reason,
+    });
+    if !held {
+        return;
+    }
+
+    let timeout_secs = static_state.channel_request_timeout_secs;
+    tokio::time::sleep(Duration::from_secs(timeout_secs)).await;
+    if inbound_channels.take_held(&temporary_channel_id).is_some() {
+        let reason = format!("no decision within {timeout_secs} seconds");
+        reject_inbound_channel(&unlocked_state, &channel_id, &peer_pubkey, &reason);
+        static_state.emit_event(NodeEvent::InboundChannelRequest {
+            temporary_channel_id,
+            peer_pubkey: peer_pubkey.to_string(),
+            capacity_sat,
+            push_msat,
+            asset_id,
+            asset_amount,
+            decision: ChannelRequestDecision::Rejected,
+            reason: Some(reason),
+        });
+    }
+}
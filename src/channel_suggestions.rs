@@ -0,0 +1,158 @@
+use bitcoin::hashes::hex::ToHex;
+use lightning::rgb_utils::{get_rgb_channel_info_path, parse_rgb_channel_info};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use crate::error::APIError;
+use crate::routes::{
+    ChannelSuggestionsResponse, CloseSuggestion, DepletedSide, HTLCStatus, OpenSuggestion,
+    RebalanceSuggestion,
+};
+use crate::utils::{get_current_timestamp, UnlockedAppState};
+
+/// Period analysed when none is requested, in days
+pub(crate) const DEFAULT_SUGGESTIONS_DAYS: u64 = 30;
+
+const BLOCKS_PER_DAY: u64 = 144;
+
+/// A side of a channel holding less than this percentage of the channel is depleted
+const DEPLETED_PERCENT: u64 = 10;
+
+/// Failed payments towards a node needed before suggesting a direct channel to it
+const MIN_FAILED_PAYMENTS: u64 = 3;
+
+/// Outcome of the payments sent to a node over the period
+#[derive(Default)]
+struct PayeeOutcomes {
+    failed: u64,
+    succeeded: u64,
+}
+
+/// Amount to move to bring a depleted side back to half the channel, None if not depleted
+fn rebalance_amount(side: u64, total: u64) -> Option<u64> {
+    (total > 0 && side * 100 < total * DEPLETED_PERCENT).then(|| total / 2 - side)
+}
+
+impl UnlockedAppState {
+    /// Suggest channels to close, to rebalance and to open, from the forwards, the payments and
+    /// the state of the channels over the given number of days
+    ///
+    /// - channels open for the whole period that no payment or forward went through are
+    ///   candidates to close
+    /// - channels with a side holding less than 10% of the channel, while the forwards of the
+    ///   period haven't moved liquidity back to it, are candidates to rebalance towards half the
+    ///   channel, in assets for the colored ones
+    /// - nodes the node has no channel with that most of the payments of the period failed to
+    ///   reach, at least 3 of them, are candidates for a direct channel
+    pub(crate) fn channel_suggestions(
+        &self,
+        ldk_data_dir: &str,
+        days: u64,
+    ) -> Result<ChannelSuggestionsResponse, APIError> {
+        let since = get_current_timestamp().saturating_sub(days * 24 * 60 * 60);
+        let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
+        let channels = self.channel_manager.list_channels();
+
+        // msat forwarded out of each channel minus the msat forwarded into it
+        let mut net_outflows: HashMap<String, i128> = HashMap::new();
+        for forward in self.database.forwards_since(since)? {
+            let amt_msat = forward.amt_msat.unwrap_or(0) as i128;
+            if let Some(channel_id) = forward.next_channel_id {
+                *net_outflows.entry(channel_id).or_default() += amt_msat;
+            }
+            if let Some(channel_id) = forward.prev_channel_id {
+                *net_outflows.entry(channel_id).or_default() -=
+                    amt_msat + forward.fee_msat.unwrap_or(0) as i128;
+            }
+        }
+        let activity = self.database.channel_activity()?;
+
+        let mut close = vec![];
+        let mut rebalance = vec![];
+        for chan_info in &channels {
+            if !chan_info.is_channel_ready {
+                continue;
+            }
+            let channel_id = chan_info.channel_id.to_hex();
+            let peer_pubkey = chan_info.counterparty.node_id.to_string();
+            let info_file_path = get_rgb_channel_info_path(&channel_id, &ldk_data_dir_path, false);
+            let rgb_info = info_file_path
+                .exists()
+                .then(|| parse_rgb_channel_info(&info_file_path));
+            let asset_id = rgb_info.as_ref().map(|i| i.contract_id.to_string());
+
+            let last_activity = activity.get(&channel_id).copied();
+            let open_whole_period =
+                chan_info.confirmations.unwrap_or(0) as u64 >= days * BLOCKS_PER_DAY;
+            if open_whole_period && last_activity.map_or(true, |a| a < since) {
+                close.push(CloseSuggestion {
+                    channel_id: channel_id.clone(),
+                    peer_pubkey: peer_pubkey.clone(),
+                    capacity_sat: chan_info.channel_value_satoshis,
+                    asset_id: asset_id.clone(),
+                    last_activity,
+                });
+            }
+
+            let net_outflow = net_outflows.get(&channel_id).copied().unwrap_or(0);
+            let (outbound, inbound) = match &rgb_info {
+                Some(rgb_info) => (rgb_info.local_rgb_amount, rgb_info.remote_rgb_amount),
+                None => (
+                    chan_info.outbound_capacity_msat,
+                    chan_info.inbound_capacity_msat,
+                ),
+            };
+            let total = outbound + inbound;
+            let depleted = match (
+                rebalance_amount(outbound, total),
+                rebalance_amount(inbound, total),
+            ) {
+                (Some(amount), _) if net_outflow >= 0 => Some((DepletedSide::Outbound, amount)),
+                (_, Some(amount)) if net_outflow <= 0 => Some((DepletedSide::Inbound, amount)),
+                _ => None,
+            };
+            if let Some((depleted_side, amount)) = depleted {
+                rebalance.push(RebalanceSuggestion {
+                    channel_id,
+                    peer_pubkey,
+                    depleted_side,
+                    asset_id,
+                    suggested_amt_msat: rgb_info.is_none().then_some(amount),
+                    suggested_asset_amount: rgb_info.is_some().then_some(amount),
+                });
+            }
+        }
+
+        let mut payees: BTreeMap<String, PayeeOutcomes> = BTreeMap::new();
+        for (_, inbound, info) in self.database.payments_since(since)? {
+            let Some(payee) = info.payee.filter(|_| !inbound && info.created_at >= since) else {
+                continue;
+            };
+            if channels.iter().any(|c| c.counterparty.node_id == payee) {
+                continue;
+            }
+            let outcomes = payees.entry(payee.to_string()).or_default();
+            match info.status {
+                HTLCStatus::Failed => outcomes.failed += 1,
+                HTLCStatus::Succeeded => outcomes.succeeded += 1,
+                HTLCStatus::Pending => {}
+            }
+        }
+        let open = payees
+            .into_iter()
+            .filter(|(_, o)| o.failed >= MIN_FAILED_PAYMENTS && o.failed > o.succeeded)
+            .map(|(peer_pubkey, o)| OpenSuggestion {
+                peer_pubkey,
+                failed_payments: o.failed,
+                succeeded_payments: o.succeeded,
+            })
+            .collect();
+
+        Ok(ChannelSuggestionsResponse {
+            period_start: since,
+            close,
+            rebalance,
+            open,
+        })
+    }
+}
@@ -0,0 +1,155 @@
+use amplify::s;
+use bitcoin::hashes::hex::ToHex;
+use lightning::chain::chaininterface::{ConfirmationTarget, FeeEstimator};
+use lightning::ln::channelmanager::ChannelDetails;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::database::ChannelFeeRate;
+use crate::dry_run::CLOSING_TX_VBYTES;
+use crate::error::APIError;
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+/// Decide the fee rate to target for a cooperative close within the acceptable range, the lower
+/// bound of the range being the target when no fee rate nor confirmation target is requested
+pub(crate) fn bound_close_fee_rate(
+    fee_rate: Option<ChannelFeeRate>,
+    min_fee_rate_sat_vb: Option<f32>,
+    max_fee_rate_sat_vb: Option<f32>,
+) -> Result<Option<ChannelFeeRate>, APIError> {
+    if min_fee_rate_sat_vb.is_some_and(|min| min < 1.0)
+        || max_fee_rate_sat_vb.is_some_and(|max| max < 1.0)
+    {
+        return Err(APIError::InvalidFeeRate(s!(
+            "closing fee rate bounds cannot be less than 1 sat/vB"
+        )));
+    }
+    if let (Some(min), Some(max)) = (min_fee_rate_sat_vb, max_fee_rate_sat_vb) {
+        if min > max {
+            return Err(APIError::InvalidFeeRate(s!(
+                "min_fee_rate_sat_vb cannot exceed max_fee_rate_sat_vb"
+            )));
+        }
+    }
+    let Some(fee_rate) = fee_rate else {
+        return Ok(min_fee_rate_sat_vb.map(|fee_rate_sat_vb| ChannelFeeRate {
+            target_conf_blocks: None,
+            fee_rate_sat_vb,
+        }));
+    };
+    if min_fee_rate_sat_vb.is_some_and(|min| fee_rate.fee_rate_sat_vb < min)
+        || max_fee_rate_sat_vb.is_some_and(|max| fee_rate.fee_rate_sat_vb > max)
+    {
+        return Err(APIError::InvalidFeeRate(format!(
+            "the targeted {} sat/vB is out of the acceptable closing fee rates",
+            fee_rate.fee_rate_sat_vb
+        )));
+    }
+    Ok(Some(fee_rate))
+}
+
+impl UnlockedAppState {
+    /// Cap the fee rate the node accepts to pay for the cooperative close of a channel
+    ///
+    /// Only the channel funder pays the closing fee, so nothing gets capped for the channels
+    /// opened by the peer. LDK accepts up to its own fee estimate plus the force-close avoidance
+    /// allowance of the channel, which gets set to what is left up to the maximum fee rate for a
+    /// closing transaction of the usual size.
+    pub(crate) fn cap_close_fee_rate(
+        &self,
+        static_state: &StaticState,
+        chan_info: &ChannelDetails,
+        max_fee_rate_sat_vb: f32,
+    ) -> Result<(), APIError> {
+        if !chan_info.is_outbound {
+            return Ok(());
+        }
+        let estimate_sat_vb = static_state
+            .bitcoind_client
+            .get_est_sat_per_1000_weight(ConfirmationTarget::NonAnchorChannelFee)
+            as f32
+            / 250.0; // 1 sat/vB = 250 sat/kw
+        if max_fee_rate_sat_vb < estimate_sat_vb {
+            return Err(APIError::InvalidFeeRate(format!(
+                "max_fee_rate_sat_vb cannot be below the {estimate_sat_vb} sat/vB closes are \
+                currently estimated to need"
+            )));
+        }
+        let Some(mut config) = chan_info.config else {
+            return Ok(());
+        };
+        config.force_close_avoidance_max_fee_satoshis =
+            ((max_fee_rate_sat_vb - estimate_sat_vb) * CLOSING_TX_VBYTES as f32) as u64;
+        self.channel_manager
+            .update_channel_config(
+                &chan_info.counterparty.node_id,
+                &[chan_info.channel_id],
+                &config,
+            )
+            .map_err(|e| APIError::FailedClosingChannel(format!("{:?}", e)))
+    }
+}
+
+/// Force-close the channels whose cooperative close hasn't concluded by the requested deadline
+/// until the node gets stopped
+///
+/// A close concludes once the closing transaction is agreed on, LDK then dropping the channel.
+pub(crate) async fn watch_close_negotiations(
+    unlocked_state: Arc<UnlockedAppState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    #[cfg(test)]
+    let interval_secs = 2;
+    #[cfg(not(test))]
+    let interval_secs = 30;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+
+        let deadlines = match unlocked_state.database.close_deadlines() {
+            Ok(deadlines) => deadlines,
+            Err(e) => {
+                tracing::error!("Failed to get the deadlines of cooperative closes: {e}");
+                continue;
+            }
+        };
+        if deadlines.is_empty() {
+            continue;
+        }
+        let now = get_current_timestamp();
+        let channels = unlocked_state.channel_manager.list_channels();
+        for (channel_id, force_at) in deadlines {
+            let chan_info = channels
+                .iter()
+                .find(|c| c.channel_id.to_hex() == channel_id);
+            match chan_info {
+                Some(_) if now < force_at => continue,
+                Some(chan_info) => {
+                    tracing::warn!(
+                        "Cooperative close of channel {channel_id} hasn't concluded in time, \
+                        force-closing it"
+                    );
+                    if let Err(e) = unlocked_state
+                        .channel_manager
+                        .force_close_broadcasting_latest_txn(
+                            &chan_info.channel_id,
+                            &chan_info.counterparty.node_id,
+                        )
+                    {
+                        tracing::error!("Failed to force-close channel {channel_id}: {e:?}");
+                    }
+                }
+                // the channel got closed some other way
+                None => {}
+            }
+            if let Err(e) = unlocked_state.database.clear_close_deadline(&channel_id) {
+                tracing::error!("Failed to clear the close deadline of {channel_id}: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use amplify::map;
+use lightning::chain::chaininterface::{ConfirmationTarget, FeeEstimator};
+use rgb_lib::wallet::{Recipient, RecipientData};
+use rgbstd::contract::SecretSeal;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::APIError;
+use crate::routes::OutpointAllocation;
+use crate::utils::{StaticState, UnlockedAppState};
+
+/// Rule merging the allocations of an asset into one once the wallet holds too many of them
+#[derive(Clone, Debug)]
+pub(crate) struct ConsolidationPolicy {
+    pub(crate) max_allocations: usize,
+}
+
+/// Allocations of an asset that can be moved, leaving out the UTXOs involved in pending
+/// transfers (which include the funding of opening channels)
+pub(crate) fn consolidable_allocations(
+    unlocked_state: &UnlockedAppState,
+    asset_id: &str,
+) -> Result<Vec<OutpointAllocation>, APIError> {
+    Ok(unlocked_state
+        .rgb_list_unspents()?
+        .into_iter()
+        .filter(|u| u.rgb_allocations.iter().all(|a| a.settled))
+        .flat_map(|u| {
+            let outpoint = u.utxo.outpoint.to_string();
+            u.rgb_allocations
+                .into_iter()
+                .filter(|a| a.asset_id.as_deref() == Some(asset_id))
+                .map(move |a| OutpointAllocation {
+                    outpoint: outpoint.clone(),
+                    amount: a.amount,
+                })
+        })
+        .collect())
+}
+
+/// Send all the movable allocations of an asset to a new UTXO of the wallet, returning the txid
+/// and the allocations that have been merged
+///
+/// The transaction pays the background fee rate estimate and is not built if that exceeds the
+/// configured ceiling.
+pub(crate) fn consolidate_asset(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    asset_id: &str,
+) -> Result<(String, Vec<OutpointAllocation>), APIError> {
+    let allocations = consolidable_allocations(unlocked_state, asset_id)?;
+    if allocations.len() < 2 {
+        return Err(APIError::NothingToConsolidate);
+    }
+
+    let fee_rate = static_state
+        .bitcoind_client
+        .get_est_sat_per_1000_weight(ConfirmationTarget::ChannelCloseMinimum)
+        as f32
+        / 250.0; // 1 sat/vB = 250 sat/kw
+    if fee_rate > static_state.consolidation_max_fee_rate {
+        return Err(APIError::FeeRateTooHigh(format!(
+            "estimated {fee_rate:.2} sat/vB, ceiling is {} sat/vB",
+            static_state.consolidation_max_fee_rate
+        )));
+    }
+
+    let amount = allocations.iter().map(|a| a.amount).sum();
+    let transport_endpoints = static_state.proxy_endpoints.clone();
+    let receive_data = unlocked_state.rgb_blind_receive(
+        Some(asset_id.to_string()),
+        None,
+        transport_endpoints.clone(),
+        static_state.default_min_confirmations,
+    )?;
+    let secret_seal = SecretSeal::from_str(&receive_data.recipient_id)
+        .map_err(|e| APIError::InvalidBlindedUTXO(e.to_string()))?;
+    let recipient_map = map! {
+        asset_id.to_string() => vec![Recipient {
+            recipient_data: RecipientData::BlindedUTXO(secret_seal),
+            amount,
+            transport_endpoints,
+        }]
+    };
+    let txid = unlocked_state.rgb_send(
+        recipient_map,
+        true,
+        fee_rate,
+        static_state.default_min_confirmations,
+    )?;
+    tracing::info!(
+        "Consolidating {} allocations of asset {asset_id} in {txid}",
+        allocations.len()
+    );
+
+    Ok((txid, allocations))
+}
+
+/// Consolidate the allocations of the assets exceeding the policy threshold until the node gets
+/// stopped
+pub(crate) async fn watch_asset_allocations(
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    let Some(policy) = static_state.consolidation_policy.clone() else {
+        return;
+    };
+    #[cfg(test)]
+    let interval_secs = 5;
+    #[cfg(not(test))]
+    let interval_secs = 60 * 10;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+
+        let unlocked_state = Arc::clone(&unlocked_state);
+        let static_state = Arc::clone(&static_state);
+        let policy = policy.clone();
+        tokio::task::spawn_blocking(move || {
+            let asset_ids: Vec<String> = match unlocked_state.rgb_list_assets() {
+                Ok(assets) => assets
+                    .nia
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|a| a.asset_id)
+                    .collect(),
+                Err(e) => {
+                    tracing::error!("Failed to list assets for consolidation: {e}");
+                    return;
+                }
+            };
+            for asset_id in asset_ids {
+                match consolidable_allocations(&unlocked_state, &asset_id) {
+                    Ok(allocations) if allocations.len() > policy.max_allocations => {}
+                    Ok(_) => continue,
+                    Err(e) => {
+                        tracing::error!("Failed to list allocations of {asset_id}: {e}");
+                        continue;
+                    }
+                }
+                match consolidate_asset(&unlocked_state, &static_state, &asset_id) {
+                    Ok(_) => {}
+                    Err(APIError::FeeRateTooHigh(details)) => {
+                        tracing::info!("Postponing consolidation of {asset_id}: {details}")
+                    }
+                    Err(e) => tracing::error!("Failed to consolidate {asset_id}: {e}"),
+                }
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
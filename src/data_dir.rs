@@ -0,0 +1,244 @@
+use rusqlite::Connection;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::data_dir_lock::LOCK_FNAME;
+use crate::data_version::check_data_version;
+use crate::error::{APIError, AppError};
+use crate::integrity::check_fund_artifacts;
+use crate::utils::{get_mnemonic_path, LOGS_DIR};
+
+/// File in the storage directory recording its absolute path
+///
+/// This is the only absolute path the node persists, everything else is stored relative to the
+/// storage directory, so that a moved directory can be told apart from the one it was copied from.
+pub(crate) const DATA_DIR_FNAME: &str = "data_dir";
+
+/// Header every SQLite database file starts with
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+/// Absolute path of the storage directory the node runs from
+pub(crate) fn active_data_dir(storage_dir_path: &Path) -> io::Result<PathBuf> {
+    fs::canonicalize(storage_dir_path)
+}
+
+/// Absolute path of the storage directory the last time the node checked it
+fn recorded_data_dir(storage_dir_path: &Path) -> Option<PathBuf> {
+    fs::read_to_string(storage_dir_path.join(DATA_DIR_FNAME))
+        .ok()
+        .map(PathBuf::from)
+}
+
+fn record_data_dir(storage_dir_path: &Path, data_dir: &Path) -> Result<(), APIError> {
+    let path = storage_dir_path.join(DATA_DIR_FNAME);
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, data_dir.to_string_lossy().as_bytes())?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+/// Record the location of the storage directory, warning when it has been moved without
+/// migrating it
+pub(crate) fn check_location(storage_dir_path: &Path) -> Result<(), APIError> {
+    let data_dir = active_data_dir(storage_dir_path)?;
+    match recorded_data_dir(storage_dir_path) {
+        Some(recorded) if recorded == data_dir => Ok(()),
+        Some(recorded) => {
+            tracing::warn!(
+                "The data directory has been moved from {} to {} (hint: restart with \
+                --migrate-data-dir {} {} to update the references to the old location)",
+                recorded.display(),
+                data_dir.display(),
+                recorded.display(),
+                data_dir.display()
+            );
+            Ok(())
+        }
+        None => record_data_dir(storage_dir_path, &data_dir),
+    }
+}
+
+/// Migrate the node data from the old storage directory to the new one, before the node starts
+///
+/// The data gets copied, leaving the old directory untouched, unless the new directory already
+/// holds a copy of it. The references to the old location are then rewritten and the data gets
+/// checked, so that the node doesn't start from a broken copy.
+pub(crate) fn migrate_data_dir(old: &Path, new: &Path) -> Result<(), AppError> {
+    let failed = |e: APIError| AppError::FailedDataDirMigration(e.to_string());
+
+    let new_has_data = Path::new(&get_mnemonic_path(&new.to_string_lossy())).exists();
+    if !new_has_data {
+        if !Path::new(&get_mnemonic_path(&old.to_string_lossy())).exists() {
+            return Err(AppError::FailedDataDirMigration(format!(
+                "no node data found in {} nor in {}",
+                old.display(),
+                new.display()
+            )));
+        }
+        tracing::info!(
+            "Copying the node data from {} to {}",
+            old.display(),
+            new.display()
+        );
+        copy_data(old, new).map_err(failed)?;
+    }
+
+    let data_dir = active_data_dir(new).map_err(|e| failed(e.into()))?;
+    // the old directory may be gone already, having been renamed
+    let mut old_dirs = vec![fs::canonicalize(old)
+        .or_else(|_| std::path::absolute(old))
+        .map_err(|e| failed(e.into()))?];
+    old_dirs.extend(recorded_data_dir(new));
+    old_dirs.sort();
+    old_dirs.dedup();
+    old_dirs.retain(|d| *d != data_dir);
+    let old_dirs: Vec<String> = old_dirs
+        .iter()
+        .map(|d| d.to_string_lossy().to_string())
+        .collect();
+
+    let rewritten = rewrite_references(&data_dir, &old_dirs).map_err(failed)?;
+    check_data_version(&data_dir).map_err(failed)?;
+    check_fund_artifacts(&data_dir).map_err(failed)?;
+    record_data_dir(&data_dir, &data_dir).map_err(failed)?;
+    tracing::info!(
+        "Migrated the data directory to {}, rewriting {rewritten} references to {}",
+        data_dir.display(),
+        old_dirs.join(", ")
+    );
+    Ok(())
+}
+
+/// Copy the node data from the old directory into the new one, leaving out the logs and the lock
+fn copy_data(old: &Path, new: &Path) -> Result<(), APIError> {
+    let entries = WalkDir::new(old)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != LOGS_DIR && e.file_name() != LOCK_FNAME);
+    for entry in entries {
+        let entry = entry.map_err(io::Error::from)?;
+        let relative_path = entry
+            .path()
+            .strip_prefix(old)
+            .expect("walking the old directory");
+        let dest_path = new.join(relative_path);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&dest_path)?;
+        } else {
+            fs::copy(entry.path(), &dest_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite the references to the old directories found in the databases and in the text files
+/// of the node data, checking the databases along the way, returning how many got rewritten
+///
+/// Other files are binary artifacts, which never hold paths.
+fn rewrite_references(data_dir: &Path, old_dirs: &[String]) -> Result<usize, APIError> {
+    let new_dir = data_dir.to_string_lossy();
+    let mut rewritten = 0;
+    let entries = WalkDir::new(data_dir)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != LOGS_DIR);
+    for entry in entries {
+        let entry = entry.map_err(io::Error::from)?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path();
+        let mut header = [0; SQLITE_HEADER.len()];
+        let is_database =
+            File::open(path)?.read_exact(&mut header).is_ok() && header == SQLITE_HEADER;
+        if is_database {
+            rewritten += rewrite_database(path, old_dirs, &new_dir)?;
+            continue;
+        }
+        let Ok(mut text) = String::from_utf8(fs::read(path)?) else {
+            continue;
+        };
+        let mut replaced = 0;
+        for old_dir in old_dirs {
+            let (new_text, count) = replace_dir(&text, old_dir, &new_dir);
+            text = new_text;
+            replaced += count;
+        }
+        if replaced > 0 {
+            let mut tmp_path = path.as_os_str().to_owned();
+            tmp_path.push(".tmp");
+            fs::write(&tmp_path, text)?;
+            fs::rename(tmp_path, path)?;
+            rewritten += replaced;
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Replace the old directory where it starts a path, not where it's the beginning of a longer
+/// name (e.g. /data/node in /data/node2)
+fn replace_dir(text: &str, old_dir: &str, new_dir: &str) -> (String, usize) {
+    let mut replaced = String::with_capacity(text.len());
+    let mut count = 0;
+    let mut rest = text;
+    while let Some(pos) = rest.find(old_dir) {
+        let end = pos + old_dir.len();
+        let whole = rest[end..]
+            .chars()
+            .next()
+            .map_or(true, |c| !c.is_alphanumeric() && !"-_.".contains(c));
+        replaced.push_str(&rest[..pos]);
+        replaced.push_str(if whole { new_dir } else { old_dir });
+        count += whole as usize;
+        rest = &rest[end..];
+    }
+    replaced.push_str(rest);
+    (replaced, count)
+}
+
+/// Rewrite the text values of a database that are paths in an old directory
+fn rewrite_database(path: &Path, old_dirs: &[String], new_dir: &str) -> Result<usize, APIError> {
+    let conn = Connection::open(path)?;
+    let check: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    if check != "ok" {
+        return Err(APIError::CorruptedDataDir(format!(
+            "database {} failed its integrity check ({check})",
+            path.display()
+        )));
+    }
+
+    let tables = conn
+        .prepare(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        )?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut rewritten = 0;
+    for table in tables {
+        let columns = conn
+            .prepare("SELECT name FROM pragma_table_info(?1)")?
+            .query_map([&table], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for column in columns {
+            for old_dir in old_dirs {
+                let old_prefix = format!("{old_dir}/");
+                rewritten += conn.execute(
+                    &format!(
+                        "UPDATE \"{table}\" SET \"{column}\" = ?1 || substr(\"{column}\", ?2)
+                        WHERE typeof(\"{column}\") = 'text'
+                        AND (\"{column}\" = ?3 OR substr(\"{column}\", 1, ?4) = ?5)"
+                    ),
+                    rusqlite::params![
+                        new_dir,
+                        old_dir.chars().count() + 1,
+                        old_dir,
+                        old_prefix.chars().count(),
+                        old_prefix,
+                    ],
+                )?;
+            }
+        }
+    }
+    Ok(rewritten)
+}
@@ -0,0 +1,189 @@
+use amplify::s;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+
+use crate::error::{APIError, AppError};
+use crate::utils::get_current_timestamp;
+
+/// File in the storage directory locked by the daemon running on it
+///
+/// It records the process holding it, so that the error met by a second daemon started on the
+/// same directory tells which one is running. The lock gets released when the daemon exits.
+pub(crate) const LOCK_FNAME: &str = "rln.lock";
+
+/// Daemon holding the lock of a storage directory
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct LockOwner {
+    pid: u32,
+    started_at: u64,
+}
+
+impl std::fmt::Display for LockOwner {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "process {} (started at {})", self.pid, self.started_at)
+    }
+}
+
+#[cfg(unix)]
+fn try_lock(file: &File) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let e = io::Error::last_os_error();
+    match e.kind() {
+        io::ErrorKind::WouldBlock => Ok(false),
+        _ => Err(e),
+    }
+}
+
+/// Without advisory locks only the recorded owner guards the directory
+#[cfg(not(unix))]
+fn try_lock(_file: &File) -> io::Result<bool> {
+    Ok(true)
+}
+
+/// Processes can't be checked, so they are assumed to be running
+#[cfg(not(unix))]
+fn is_alive(_pid: u32) -> bool {
+    true
+}
+
+fn read_owner(file: &mut File) -> io::Result<Option<LockOwner>> {
+    let mut content = vec![];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut content)?;
+    Ok(serde_json::from_slice(&content).ok())
+}
+
+/// Exclusive lock on a storage directory, held for as long as the daemon runs
+pub(crate) struct DataDirLock {
+    path: PathBuf,
+    owner: LockOwner,
+    file: Mutex<Option<File>>,
+}
+
+impl DataDirLock {
+    /// Lock the storage directory, refusing it if another daemon is running on it
+    ///
+    /// A lock left behind by a daemon that stopped abruptly (e.g. after a crash) gets taken over
+    /// with a warning, the advisory lock telling whether its owner is still holding it.
+    pub(crate) fn acquire(storage_dir_path: &Path) -> Result<Self, AppError> {
+        let path = storage_dir_path.join(LOCK_FNAME);
+        let failed = |e: io::Error| {
+            AppError::FailedDataDirLock(format!("cannot lock {}: {e}", path.display()))
+        };
+        fs::create_dir_all(storage_dir_path).map_err(failed)?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(failed)?;
+
+        let locked = try_lock(&file).map_err(failed)?;
+        let previous = read_owner(&mut file).map_err(failed)?;
+        if !locked {
+            return Err(AppError::DataDirInUse(match previous {
+                Some(previous) => format!("locked by {previous}"),
+                None => s!("locked by another process"),
+            }));
+        }
+        if let Some(previous) = previous {
+            // the owner is cleared on exit, so it stopped abruptly. Without advisory locks the
+            // process must be gone, otherwise the lock it dropped is proof enough
+            #[cfg(not(unix))]
+            if previous.pid != process::id() && is_alive(previous.pid) {
+                return Err(AppError::DataDirInUse(format!(
+                    "recorded as used by {previous}, still running (hint: remove {} if it \
+                    isn't a node using the directory)",
+                    path.display()
+                )));
+            }
+            tracing::warn!("Taking over the stale lock of {previous}, which didn't release it");
+        }
+
+        let owner = LockOwner {
+            pid: process::id(),
+            started_at: get_current_timestamp(),
+        };
+        file.set_len(0).map_err(failed)?;
+        file.seek(SeekFrom::Start(0)).map_err(failed)?;
+        file.write_all(&serde_json::to_vec(&owner).expect("valid JSON"))
+            .map_err(failed)?;
+        file.sync_all().map_err(failed)?;
+        tracing::info!("Locked the data directory as {owner}");
+
+        Ok(Self {
+            path,
+            owner,
+            file: Mutex::new(Some(file)),
+        })
+    }
+
+    /// Check the lock is still held by this daemon, i.e. the lock file hasn't been removed and
+    /// taken by another one
+    pub(crate) fn verify(&self) -> Result<(), APIError> {
+        match self.file.lock().unwrap().as_ref() {
+            Some(file) => self.check_held(file),
+            None => Err(APIError::DataDirInUse(s!("the lock has been released"))),
+        }
+    }
+
+    fn check_held(&self, file: &File) -> Result<(), APIError> {
+        let lost = |reason: &str| {
+            APIError::DataDirInUse(format!(
+                "{} {reason} since {} locked it",
+                self.path.display(),
+                self.owner
+            ))
+        };
+        let Ok(mut current) = File::open(&self.path) else {
+            return Err(lost("has been removed"));
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let (held, current) = (file.metadata()?, current.metadata()?);
+            if (held.dev(), held.ino()) != (current.dev(), current.ino()) {
+                return Err(lost("has been replaced"));
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = file;
+        if read_owner(&mut current)?.as_ref() != Some(&self.owner) {
+            return Err(lost("has been overwritten"));
+        }
+        Ok(())
+    }
+
+    /// Release the lock, clearing the owner recorded in the lock file if it's still the one held
+    ///
+    /// The file is kept, as another daemon may be waiting on it: removing it would let a third
+    /// one lock a new file while the second one locks the removed one.
+    pub(crate) fn release(&self) {
+        let Some(file) = self.file.lock().unwrap().take() else {
+            return;
+        };
+        // the file may now belong to another daemon, which took it over
+        if self.check_held(&file).is_ok() {
+            if let Err(e) = file.set_len(0).and_then(|_| file.sync_all()) {
+                tracing::error!("Failed to clear the data directory lock: {e}");
+            }
+        }
+        // closing the file releases the advisory lock
+        drop(file);
+        tracing::info!("Released the data directory lock");
+    }
+}
+
+impl Drop for DataDirLock {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
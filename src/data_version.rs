@@ -0,0 +1,114 @@
+use amplify::s;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::database::{Database, DATABASE_FNAME};
+use crate::error::APIError;
+use crate::utils::LDK_DIR;
+
+/// File in the storage directory recording the format of the node data
+pub(crate) const DATA_VERSION_FNAME: &str = "data_version.json";
+
+/// Format of the node data written by this release
+///
+/// 1: data directories written before the format got recorded, which may still hold the payments
+///    and the RGB invoice labels in legacy files
+/// 2: everything but the LDK and RGB artifacts is in the database
+pub(crate) const DATA_FORMAT_VERSION: u32 = 2;
+
+/// Oldest format this release can migrate from
+pub(crate) const MIN_DATA_FORMAT_VERSION: u32 = 1;
+
+/// Format of the node data and the release that wrote it
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct DataVersion {
+    pub(crate) format_version: u32,
+    pub(crate) crate_version: String,
+}
+
+impl DataVersion {
+    pub(crate) fn current() -> Self {
+        Self {
+            format_version: DATA_FORMAT_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Data written before the format got recorded
+    fn unversioned() -> Self {
+        Self {
+            format_version: 1,
+            crate_version: s!("unknown"),
+        }
+    }
+
+    /// Refuse data this release cannot read or migrate
+    pub(crate) fn check_supported(&self) -> Result<(), APIError> {
+        if (MIN_DATA_FORMAT_VERSION..=DATA_FORMAT_VERSION).contains(&self.format_version) {
+            return Ok(());
+        }
+        Err(APIError::UnsupportedDataVersion {
+            found: format!(
+                "format {} (rgb-lightning-node {})",
+                self.format_version, self.crate_version
+            ),
+            supported: format!("formats {MIN_DATA_FORMAT_VERSION} to {DATA_FORMAT_VERSION}"),
+        })
+    }
+}
+
+/// Record the data of the storage directory as written by this release
+pub(crate) fn write_data_version(storage_dir_path: &Path) -> Result<(), APIError> {
+    let path = storage_dir_path.join(DATA_VERSION_FNAME);
+    let tmp_path = path.with_extension("tmp");
+    fs::write(
+        &tmp_path,
+        serde_json::to_vec(&DataVersion::current()).expect("valid JSON"),
+    )?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+/// Format of the data in the storage directory, unversioned when written by an older release
+fn read_data_version(storage_dir_path: &Path) -> Result<DataVersion, APIError> {
+    match fs::read(storage_dir_path.join(DATA_VERSION_FNAME)) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+            APIError::CorruptedDataDir(format!("{DATA_VERSION_FNAME} cannot be read ({e})"))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DataVersion::unversioned()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Move the payments and the RGB invoice labels from the legacy files to the database
+fn migrate_from_v1(storage_dir_path: &Path) -> Result<(), APIError> {
+    let database = Database::open(&storage_dir_path.join(DATABASE_FNAME))?;
+    database.import_legacy_files(&storage_dir_path.join(LDK_DIR))
+}
+
+/// Check the storage directory holds data this release supports, migrating it forward from an
+/// older format
+///
+/// Each migration is safe to run again, so the new format only gets recorded once all of them
+/// have completed.
+pub(crate) fn check_data_version(storage_dir_path: &Path) -> Result<(), APIError> {
+    let found = read_data_version(storage_dir_path)?;
+    found.check_supported()?;
+    if found.format_version == DATA_FORMAT_VERSION {
+        return Ok(());
+    }
+    tracing::info!(
+        "Migrating the node data from format {} (rgb-lightning-node {}) to {DATA_FORMAT_VERSION}",
+        found.format_version,
+        found.crate_version
+    );
+    for from in found.format_version..DATA_FORMAT_VERSION {
+        match from {
+            1 => migrate_from_v1(storage_dir_path)?,
+            _ => unreachable!("missing migration from data format {from}"),
+        }
+        tracing::info!("Migrated the node data to format {}", from + 1);
+    }
+    write_data_version(storage_dir_path)
+}
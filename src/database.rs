@@ -0,0 +1,2673 @@
+use lightning::ln::channelmanager::PaymentId;
+use lightning::ln::PaymentHash;
+use lightning::util::ser::{Readable, Writeable};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::alerts::AlertThresholds;
+use crate::disk::{INBOUND_PAYMENTS_FNAME, OUTBOUND_PAYMENTS_FNAME, RGB_INVOICE_LABELS_FNAME};
+use crate::error::APIError;
+use crate::graph_pruning::HOT_CHANNEL_AGE_SECS;
+use crate::invoices::InvoiceClosure;
+use crate::ldk::{
+    InboundPaymentInfoStorage, OutboundPaymentInfoStorage, PaymentInfo, RgbInvoiceLabelStorage,
+};
+use crate::pagination::Cursor;
+use crate::routes::{HTLCStatus, InvoiceStatus, Swap, SwapDirection, SwapStatus, UtxoReservation};
+use crate::utils::get_current_timestamp;
+
+pub(crate) const DATABASE_FNAME: &str = "rln.db";
+
+/// Suffix appended to the legacy storage files once their content has been imported
+const MIGRATED_SUFFIX: &str = "migrated";
+
+/// Schema migrations, the database user_version tracks how many have been applied
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE payments (
+        id BLOB NOT NULL,
+        inbound INTEGER NOT NULL,
+        status INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        data BLOB NOT NULL,
+        PRIMARY KEY (id, inbound)
+    );
+    CREATE INDEX payments_created_at ON payments (created_at);
+    CREATE TABLE rgb_invoice_labels (
+        recipient_id TEXT PRIMARY KEY NOT NULL,
+        label TEXT NOT NULL
+    );",
+    "CREATE TABLE payment_policy_destinations (
+        destination TEXT PRIMARY KEY NOT NULL
+    );",
+    "CREATE TABLE spending_limits (
+        asset_id TEXT PRIMARY KEY NOT NULL,
+        max_amount INTEGER NOT NULL
+    );
+    CREATE TABLE spending (
+        id TEXT PRIMARY KEY NOT NULL,
+        asset_id TEXT,
+        amt_msat INTEGER NOT NULL,
+        asset_amount INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX spending_created_at ON spending (created_at);",
+    "CREATE TABLE channel_fundings (
+        channel_id TEXT PRIMARY KEY NOT NULL,
+        funding_txid TEXT NOT NULL,
+        funding_vout INTEGER NOT NULL,
+        capacity_sat INTEGER NOT NULL,
+        outbound INTEGER NOT NULL
+    );
+    CREATE INDEX channel_fundings_outpoint ON channel_fundings (funding_txid, funding_vout);
+    CREATE TABLE channel_fees (
+        txid TEXT PRIMARY KEY NOT NULL,
+        channel_id TEXT NOT NULL,
+        kind INTEGER NOT NULL,
+        fee_sat INTEGER NOT NULL,
+        claimable INTEGER NOT NULL
+    );
+    CREATE INDEX channel_fees_channel_id ON channel_fees (channel_id);
+    CREATE TABLE closed_channels (
+        channel_id TEXT PRIMARY KEY NOT NULL,
+        funding_txid TEXT,
+        peer_pubkey TEXT,
+        capacity_sat INTEGER,
+        asset_id TEXT,
+        asset_local_amount INTEGER,
+        asset_remote_amount INTEGER,
+        reason TEXT NOT NULL,
+        closed_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE channel_notes (
+        channel_id TEXT PRIMARY KEY NOT NULL,
+        note TEXT NOT NULL
+    );",
+    "CREATE TABLE sent_blinded_utxos (
+        blinded_utxo TEXT PRIMARY KEY NOT NULL,
+        asset_id TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE incoming_anchors (
+        txid TEXT PRIMARY KEY NOT NULL,
+        inputs TEXT NOT NULL
+    );
+    CREATE TABLE failed_incoming_transfers (
+        recipient_id TEXT PRIMARY KEY NOT NULL,
+        reason TEXT NOT NULL,
+        failed_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE consignment_endpoints (
+        recipient_id TEXT PRIMARY KEY NOT NULL,
+        endpoint TEXT NOT NULL
+    );",
+    "CREATE TABLE close_asset_destinations (
+        channel_id TEXT PRIMARY KEY NOT NULL,
+        blinded_utxo TEXT NOT NULL,
+        closing_txid TEXT
+    );
+    CREATE INDEX close_asset_destinations_closing_txid
+        ON close_asset_destinations (closing_txid);",
+    "CREATE TABLE alert_thresholds (
+        id INTEGER PRIMARY KEY NOT NULL CHECK (id = 0),
+        anchor_reserve_per_channel_sat INTEGER,
+        min_fee_bump_utxos INTEGER,
+        min_channel_asset_amount INTEGER
+    );",
+    "CREATE TABLE monitor_archive (
+        monitor_key TEXT PRIMARY KEY NOT NULL,
+        resolved_height INTEGER NOT NULL,
+        archived_at INTEGER
+    );",
+    "CREATE TABLE watched_txs (
+        txid TEXT PRIMARY KEY NOT NULL,
+        raw_tx BLOB NOT NULL,
+        watched_since INTEGER NOT NULL,
+        rebroadcasts INTEGER NOT NULL
+    );
+    ALTER TABLE alert_thresholds ADD COLUMN max_unconfirmed_tx_age_secs INTEGER;",
+    "ALTER TABLE payments ADD COLUMN settled_at INTEGER;
+    CREATE INDEX payments_settled_at ON payments (settled_at);",
+    "CREATE TABLE route_hot_channels (
+        short_channel_id INTEGER PRIMARY KEY NOT NULL,
+        last_success INTEGER NOT NULL
+    );",
+    "CREATE TABLE sweeps (
+        txid TEXT PRIMARY KEY NOT NULL,
+        amount_sat INTEGER NOT NULL,
+        asset_id TEXT,
+        asset_amount INTEGER,
+        created_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE forwards (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        prev_channel_id TEXT,
+        next_channel_id TEXT,
+        amt_msat INTEGER,
+        fee_msat INTEGER,
+        forwarded_at INTEGER NOT NULL
+    );
+    CREATE INDEX forwards_forwarded_at ON forwards (forwarded_at);
+    CREATE TABLE channel_activity (
+        channel_id TEXT PRIMARY KEY NOT NULL,
+        last_activity INTEGER NOT NULL
+    );",
+    "CREATE TABLE wallet_intents (
+        txid TEXT PRIMARY KEY NOT NULL,
+        kind INTEGER NOT NULL,
+        signed_psbt TEXT NOT NULL,
+        asset_id TEXT,
+        spending_id TEXT,
+        blinded_utxo TEXT,
+        created_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE peer_connections (
+        peer_pubkey TEXT PRIMARY KEY NOT NULL,
+        connected_since INTEGER,
+        last_seen INTEGER,
+        disconnect_count INTEGER NOT NULL
+    );",
+    "CREATE TABLE channel_fee_rates (
+        channel_id TEXT NOT NULL,
+        kind INTEGER NOT NULL,
+        target_conf_blocks INTEGER,
+        fee_rate_sat_vb REAL NOT NULL,
+        PRIMARY KEY (channel_id, kind)
+    );",
+    "CREATE TABLE wallet_addresses (
+        derivation_index INTEGER PRIMARY KEY NOT NULL,
+        address TEXT NOT NULL UNIQUE,
+        issued_at INTEGER
+    );",
+    "CREATE TABLE close_negotiations (
+        channel_id TEXT PRIMARY KEY NOT NULL,
+        force_at INTEGER,
+        negotiated_fee_rate_sat_vb REAL
+    );",
+    "CREATE TABLE webhooks (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        url TEXT NOT NULL,
+        events TEXT NOT NULL,
+        secret TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        delivered INTEGER NOT NULL DEFAULT 0,
+        failed INTEGER NOT NULL DEFAULT 0,
+        last_attempt_at INTEGER,
+        last_delivered_at INTEGER,
+        last_error TEXT
+    );",
+    "CREATE TABLE reorged_txs (
+        txid TEXT PRIMARY KEY NOT NULL,
+        reorged_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE peer_exposure_limits (
+        peer_pubkey TEXT NOT NULL,
+        asset_id TEXT NOT NULL,
+        max_amount INTEGER NOT NULL,
+        PRIMARY KEY (peer_pubkey, asset_id)
+    );",
+    "CREATE TABLE channel_creations (
+        channel_id TEXT PRIMARY KEY NOT NULL,
+        created_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE utxo_reservations (
+        txid TEXT PRIMARY KEY NOT NULL,
+        kind INTEGER NOT NULL,
+        channel_id TEXT,
+        inputs TEXT NOT NULL,
+        reserved_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE swaps (
+        swap_id TEXT PRIMARY KEY NOT NULL,
+        direction INTEGER NOT NULL,
+        initiator INTEGER NOT NULL,
+        channel_id TEXT NOT NULL,
+        peer_pubkey TEXT NOT NULL,
+        asset_id TEXT NOT NULL,
+        asset_amount INTEGER NOT NULL,
+        status INTEGER NOT NULL,
+        payment_hash TEXT,
+        txid TEXT,
+        reason TEXT,
+        created_at INTEGER NOT NULL,
+        recipient_id TEXT,
+        transport_endpoints TEXT,
+        expires_at INTEGER,
+        preimage TEXT
+    );",
+    "CREATE TABLE channel_htlcs (
+        channel_id TEXT NOT NULL,
+        inbound INTEGER NOT NULL,
+        htlc_id INTEGER NOT NULL,
+        peer_pubkey TEXT NOT NULL,
+        amt_msat INTEGER NOT NULL,
+        payment_hash TEXT NOT NULL,
+        cltv_expiry INTEGER NOT NULL,
+        PRIMARY KEY (channel_id, inbound, htlc_id)
+    );",
+    "ALTER TABLE payments ADD COLUMN label TEXT;
+    ALTER TABLE payments ADD COLUMN expires_at INTEGER;
+    ALTER TABLE payments ADD COLUMN closure INTEGER;
+    CREATE INDEX payments_status ON payments (status);
+    CREATE INDEX payments_label ON payments (label);",
+];
+
+/// Schema version adding the payment columns the payments get filtered on, which the migration
+/// fills from the encoded payments
+const PAYMENT_FILTER_COLUMNS_VERSION: usize = 29;
+
+/// Length of the rolling window spending limits apply to
+pub(crate) const SPENDING_WINDOW_SECS: u64 = 24 * 60 * 60;
+
+/// Key of the msat limit in the spending_limits table, which otherwise holds asset IDs
+const MSAT_LIMIT_KEY: &str = "msat";
+
+#[derive(Clone, Default)]
+pub(crate) struct SpendingLimits {
+    pub(crate) max_msat: Option<u64>,
+    pub(crate) max_asset_amounts: BTreeMap<String, u64>,
+}
+
+/// Caps on the value in flight with a peer, keyed like the spending limits
+#[derive(Clone, Default)]
+pub(crate) struct PeerExposureLimits {
+    pub(crate) max_msat: Option<u64>,
+    pub(crate) max_asset_amounts: BTreeMap<String, u64>,
+}
+
+/// On-chain transaction a channel fee has been paid for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ChannelFeeKind {
+    Funding,
+    Close,
+    ForceClose,
+}
+
+/// On-chain fees paid by the node for a channel, in sats
+#[derive(Clone, Copy, Default)]
+pub(crate) struct ChannelFees {
+    pub(crate) funding_sat: u64,
+    pub(crate) close_sat: u64,
+    pub(crate) force_close_sat: u64,
+}
+
+/// On-chain transaction of a channel broadcast by the node
+pub(crate) struct ChannelTx {
+    pub(crate) channel_id: String,
+    pub(crate) kind: ChannelFeeKind,
+}
+
+/// Fee rate of a channel transaction, with the confirmation target it has been derived from
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ChannelFeeRate {
+    pub(crate) target_conf_blocks: Option<u16>,
+    pub(crate) fee_rate_sat_vb: f32,
+}
+
+pub(crate) struct ChannelFunding {
+    pub(crate) channel_id: String,
+    pub(crate) capacity_sat: u64,
+    pub(crate) outbound: bool,
+}
+
+pub(crate) struct ClosedChannelRecord {
+    pub(crate) channel_id: String,
+    pub(crate) funding_txid: Option<String>,
+    pub(crate) peer_pubkey: Option<String>,
+    pub(crate) capacity_sat: Option<u64>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_local_amount: Option<u64>,
+    pub(crate) asset_remote_amount: Option<u64>,
+    pub(crate) reason: String,
+    pub(crate) closed_at: u64,
+}
+
+/// Transaction sending spendable channel outputs to the wallet
+pub(crate) struct SweepRecord {
+    pub(crate) txid: String,
+    pub(crate) amount_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) created_at: u64,
+}
+
+/// Payment forwarded by the node from a channel to another
+pub(crate) struct ForwardRecord {
+    pub(crate) prev_channel_id: Option<String>,
+    pub(crate) next_channel_id: Option<String>,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) fee_msat: Option<u64>,
+    pub(crate) forwarded_at: u64,
+}
+
+/// Wallet operation recorded before broadcasting its transaction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum WalletIntentKind {
+    CreateUtxos,
+    SendAsset,
+}
+
+/// Signed transaction of a wallet operation, recorded until the wallet has recorded it too
+pub(crate) struct WalletIntent {
+    pub(crate) txid: String,
+    pub(crate) kind: WalletIntentKind,
+    /// Holds the inputs, the planned outputs and their signatures
+    pub(crate) signed_psbt: String,
+    pub(crate) asset_id: Option<String>,
+    /// Spending reservation of the operation, to release if it gets abandoned
+    pub(crate) spending_id: Option<String>,
+    /// Blinded UTXO recorded as sent by the operation, to forget if it gets abandoned
+    pub(crate) blinded_utxo: Option<String>,
+    pub(crate) created_at: u64,
+}
+
+/// UTXOs held by an operation until its transaction confirms
+pub(crate) struct UtxoReservationRecord {
+    pub(crate) txid: String,
+    pub(crate) reservation: UtxoReservation,
+    /// Outpoints spent by the transaction, its outputs being held as well
+    pub(crate) inputs: Vec<String>,
+    pub(crate) reserved_at: u64,
+}
+
+/// Connection history of a peer
+pub(crate) struct PeerConnectionRecord {
+    /// Start of the current connection, None while the peer is disconnected
+    pub(crate) connected_since: Option<u64>,
+    /// End of the last connection, None if the peer hasn't been disconnected yet
+    pub(crate) last_seen: Option<u64>,
+    pub(crate) disconnect_count: u32,
+}
+
+/// HTLC added to a channel, as exchanged with the peer
+pub(crate) struct ChannelHtlcRecord {
+    pub(crate) channel_id: String,
+    pub(crate) inbound: bool,
+    /// ID of the HTLC among the ones added by the same side of the channel
+    pub(crate) htlc_id: u64,
+    pub(crate) peer_pubkey: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) payment_hash: String,
+    pub(crate) cltv_expiry: u32,
+}
+
+/// Address of the vanilla wallet known to the node
+pub(crate) struct WalletAddressRecord {
+    pub(crate) derivation_index: u32,
+    pub(crate) address: String,
+    /// When the address has been handed out, None if it has been found used on-chain instead
+    pub(crate) issued_at: Option<u64>,
+}
+
+/// Callback URL the node posts the selected events to, with the outcome of the deliveries
+pub(crate) struct WebhookRecord {
+    pub(crate) id: u64,
+    pub(crate) url: String,
+    pub(crate) events: Vec<String>,
+    pub(crate) secret: String,
+    pub(crate) created_at: u64,
+    pub(crate) delivered: u64,
+    /// Events given up on after all the delivery attempts have failed
+    pub(crate) failed: u64,
+    pub(crate) last_attempt_at: Option<u64>,
+    pub(crate) last_delivered_at: Option<u64>,
+    /// Error of the last attempt, cleared by a successful one
+    pub(crate) last_error: Option<String>,
+}
+
+/// Swap with a channel peer, with what the node needs to complete it after a restart
+#[derive(Clone)]
+pub(crate) struct SwapRecord {
+    pub(crate) swap: Swap,
+    /// RGB recipient of the on-chain leg and its transport endpoints, known to the node sending
+    /// the assets on-chain once agreed with the peer
+    pub(crate) recipient: Option<(String, Vec<String>)>,
+    /// Time after which a pending swap fails, unset once its outcome depends on a payment
+    pub(crate) expires_at: Option<u64>,
+    /// Preimage of the HTLC paying the node, recorded before sending the assets on-chain
+    pub(crate) preimage: Option<String>,
+}
+
+/// Unconfirmed wallet transaction watched to be broadcast again if it gets dropped
+pub(crate) struct WatchedTx {
+    pub(crate) raw_tx: Vec<u8>,
+    pub(crate) watched_since: u64,
+    pub(crate) rebroadcasts: u32,
+}
+
+#[derive(Default)]
+pub(crate) struct SpendingUsage {
+    pub(crate) msat: u64,
+    pub(crate) asset_amounts: BTreeMap<String, u64>,
+}
+
+/// Embedded SQLite database holding the node runtime data
+pub(crate) struct Database {
+    conn: Mutex<Connection>,
+}
+
+impl Database {
+    /// Open the database at the given path, creating it if needed and applying the pending
+    /// schema migrations
+    pub(crate) fn open(path: &Path) -> Result<Self, APIError> {
+        let mut conn = Connection::open(path)?;
+        let version: usize =
+            conn.pragma_query_value(None, "user_version", |row| row.get::<_, u32>(0))? as usize;
+        // written by a newer release, which may rely on tables this one doesn't keep updated
+        if version > MIGRATIONS.len() {
+            return Err(APIError::UnsupportedDataVersion {
+                found: format!("database schema {version}"),
+                supported: format!("database schemas up to {}", MIGRATIONS.len()),
+            });
+        }
+        for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            if idx + 1 == PAYMENT_FILTER_COLUMNS_VERSION {
+                fill_payment_filter_columns(&tx)?;
+            }
+            tx.pragma_update(None, "user_version", (idx + 1) as u32)?;
+            tx.commit()?;
+            tracing::info!("applied database migration {}", idx + 1);
+        }
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Import the legacy storage files found in the given directory, renaming them once done
+    ///
+    /// Records already in the database are kept, so an interrupted import can be safely retried.
+    pub(crate) fn import_legacy_files(&self, ldk_data_dir: &Path) -> Result<(), APIError> {
+        let inbound_path = ldk_data_dir.join(INBOUND_PAYMENTS_FNAME);
+        if inbound_path.exists() {
+            let inbound: InboundPaymentInfoStorage = read_legacy_file(&inbound_path)?;
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            for (payment_hash, info) in &inbound.payments {
+                insert_payment(&tx, &payment_hash.0, true, info, true)?;
+            }
+            tx.commit()?;
+            mark_migrated(&inbound_path)?;
+        }
+
+        let outbound_path = ldk_data_dir.join(OUTBOUND_PAYMENTS_FNAME);
+        if outbound_path.exists() {
+            let outbound: OutboundPaymentInfoStorage = read_legacy_file(&outbound_path)?;
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            for (payment_id, info) in &outbound.payments {
+                insert_payment(&tx, &payment_id.0, false, info, true)?;
+            }
+            tx.commit()?;
+            mark_migrated(&outbound_path)?;
+        }
+
+        let labels_path = ldk_data_dir.join(RGB_INVOICE_LABELS_FNAME);
+        if labels_path.exists() {
+            let labels: RgbInvoiceLabelStorage = read_legacy_file(&labels_path)?;
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            for (recipient_id, label) in &labels.labels {
+                tx.execute(
+                    "INSERT OR IGNORE INTO rgb_invoice_labels (recipient_id, label)
+                    VALUES (?1, ?2)",
+                    params![recipient_id, label],
+                )?;
+            }
+            tx.commit()?;
+            mark_migrated(&labels_path)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn inbound_payments(&self) -> Result<InboundPaymentInfoStorage, APIError> {
+        let payments = self
+            .payments(true)?
+            .into_iter()
+            .map(|(id, info)| (PaymentHash(id), info))
+            .collect();
+        Ok(InboundPaymentInfoStorage { payments })
+    }
+
+    pub(crate) fn outbound_payments(&self) -> Result<OutboundPaymentInfoStorage, APIError> {
+        let payments = self
+            .payments(false)?
+            .into_iter()
+            .map(|(id, info)| (PaymentId(id), info))
+            .collect();
+        Ok(OutboundPaymentInfoStorage { payments })
+    }
+
+    /// Payments created or settled since the given timestamp, with whether they're inbound
+    ///
+    /// Only the rows of the period are read, through the creation and settlement indexes.
+    pub(crate) fn payments_since(
+        &self,
+        since: u64,
+    ) -> Result<Vec<([u8; 32], bool, PaymentInfo)>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, inbound, data FROM payments WHERE created_at >= ?1 OR settled_at >= ?1",
+        )?;
+        let rows = stmt
+            .query_map(params![since], |row| {
+                Ok((
+                    row.get::<_, Vec<u8>>(0)?,
+                    row.get::<_, bool>(1)?,
+                    row.get::<_, Vec<u8>>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(id, inbound, data)| {
+                let (id, info) = decode_payment(id, data)?;
+                Ok((id, inbound, info))
+            })
+            .collect()
+    }
+
+    /// Payments with the given label and status, with whether they're inbound, sorted by creation
+    /// time then by ID
+    ///
+    /// The filters and the paging are applied on the indexed columns, so only the payments of
+    /// the page get read and decoded.
+    pub(crate) fn payments_page(
+        &self,
+        label: Option<&str>,
+        status: Option<InvoiceStatus>,
+        after: Option<&Cursor>,
+        limit: Option<usize>,
+    ) -> Result<Vec<([u8; 32], bool, PaymentInfo)>, APIError> {
+        let mut conditions = vec![];
+        let mut values: Vec<Value> = vec![];
+        if let Some(label) = label {
+            conditions.push("label = ?");
+            values.push(Value::Text(label.to_string()));
+        }
+        if let Some(status) = status {
+            let now = Value::Integer(get_current_timestamp() as i64);
+            match status {
+                InvoiceStatus::Pending => {
+                    conditions.push(
+                        "status = 0 AND closure IS NULL AND (expires_at IS NULL OR expires_at > ?)",
+                    );
+                    values.push(now);
+                }
+                InvoiceStatus::Expired => {
+                    conditions.push(
+                        "status = 0 AND (closure = 0 OR (closure IS NULL AND expires_at <= ?))",
+                    );
+                    values.push(now);
+                }
+                InvoiceStatus::Cancelled => conditions.push("status = 0 AND closure = 1"),
+                InvoiceStatus::Succeeded => conditions.push("status = 1"),
+                InvoiceStatus::Failed => conditions.push("status = 2"),
+            }
+        }
+        if let Some(after) = after {
+            conditions.push("(created_at > ? OR (created_at = ? AND lower(hex(id)) > ?))");
+            values.push(Value::Integer(after.created_at() as i64));
+            values.push(Value::Integer(after.created_at() as i64));
+            values.push(Value::Text(after.id().to_string()));
+        }
+        let mut query = String::from("SELECT id, inbound, data FROM payments");
+        if !conditions.is_empty() {
+            query.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        query.push_str(" ORDER BY created_at, id");
+        if let Some(limit) = limit {
+            query.push_str(" LIMIT ?");
+            values.push(Value::Integer(i64::try_from(limit).unwrap_or(i64::MAX)));
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&query)?;
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok((
+                    row.get::<_, Vec<u8>>(0)?,
+                    row.get::<_, bool>(1)?,
+                    row.get::<_, Vec<u8>>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(id, inbound, data)| {
+                let (id, info) = decode_payment(id, data)?;
+                Ok((id, inbound, info))
+            })
+            .collect()
+    }
+
+    pub(crate) fn rgb_invoice_labels(&self) -> Result<RgbInvoiceLabelStorage, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT recipient_id, label FROM rgb_invoice_labels")?;
+        let labels = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<String, String>, _>>()?;
+        Ok(RgbInvoiceLabelStorage { labels })
+    }
+
+    /// Payment with the given ID, if any
+    pub(crate) fn payment(
+        &self,
+        id: &[u8; 32],
+        inbound: bool,
+    ) -> Result<Option<PaymentInfo>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let data = conn
+            .query_row(
+                "SELECT data FROM payments WHERE id = ?1 AND inbound = ?2",
+                params![&id[..], inbound],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()?;
+        data.map(|data| decode_payment(id.to_vec(), data).map(|(_, info)| info))
+            .transpose()
+    }
+
+    /// Insert or update a payment
+    pub(crate) fn save_payment(
+        &self,
+        id: &[u8; 32],
+        inbound: bool,
+        info: &PaymentInfo,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        insert_payment(&conn, id, inbound, info, false)
+    }
+
+    pub(crate) fn delete_payment(&self, id: &[u8; 32], inbound: bool) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM payments WHERE id = ?1 AND inbound = ?2",
+            params![&id[..], inbound],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn save_rgb_invoice_label(
+        &self,
+        recipient_id: &str,
+        label: &str,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO rgb_invoice_labels (recipient_id, label) VALUES (?1, ?2)
+            ON CONFLICT (recipient_id) DO UPDATE SET label = excluded.label",
+            params![recipient_id, label],
+        )?;
+        Ok(())
+    }
+
+    /// Get the destinations outbound payments are restricted to, empty if unrestricted
+    pub(crate) fn payment_policy_destinations(&self) -> Result<Vec<String>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT destination FROM payment_policy_destinations")?;
+        let destinations = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(destinations)
+    }
+
+    pub(crate) fn spending_limits(&self) -> Result<SpendingLimits, APIError> {
+        let conn = self.conn.lock().unwrap();
+        spending_limits(&conn)
+    }
+
+    /// Update the payment policy in a single transaction, leaving the parts given as None unchanged
+    ///
+    /// The destinations and the asset limits replace the previous ones, a max_msat of Some(None)
+    /// removes the msat limit.
+    pub(crate) fn update_payment_policy(
+        &self,
+        destinations: Option<&[String]>,
+        max_msat: Option<Option<u64>>,
+        max_asset_amounts: Option<&BTreeMap<String, u64>>,
+    ) -> Result<(), APIError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        if let Some(destinations) = destinations {
+            tx.execute("DELETE FROM payment_policy_destinations", [])?;
+            for destination in destinations {
+                tx.execute(
+                    "INSERT OR IGNORE INTO payment_policy_destinations (destination) VALUES (?1)",
+                    params![destination],
+                )?;
+            }
+        }
+        if let Some(max_msat) = max_msat {
+            tx.execute(
+                "DELETE FROM spending_limits WHERE asset_id = ?1",
+                params![MSAT_LIMIT_KEY],
+            )?;
+            if let Some(max_msat) = max_msat {
+                tx.execute(
+                    "INSERT INTO spending_limits (asset_id, max_amount) VALUES (?1, ?2)",
+                    params![MSAT_LIMIT_KEY, max_msat],
+                )?;
+            }
+        }
+        if let Some(max_asset_amounts) = max_asset_amounts {
+            tx.execute(
+                "DELETE FROM spending_limits WHERE asset_id != ?1",
+                params![MSAT_LIMIT_KEY],
+            )?;
+            for (asset_id, max_amount) in max_asset_amounts {
+                tx.execute(
+                    "INSERT INTO spending_limits (asset_id, max_amount) VALUES (?1, ?2)",
+                    params![asset_id, max_amount],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Exposure limits by peer pubkey
+    pub(crate) fn peer_exposure_limits(
+        &self,
+    ) -> Result<HashMap<String, PeerExposureLimits>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT peer_pubkey, asset_id, max_amount FROM peer_exposure_limits")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, u64>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut limits: HashMap<String, PeerExposureLimits> = HashMap::new();
+        for (peer_pubkey, key, max_amount) in rows {
+            let peer_limits = limits.entry(peer_pubkey).or_default();
+            if key == MSAT_LIMIT_KEY {
+                peer_limits.max_msat = Some(max_amount);
+            } else {
+                peer_limits.max_asset_amounts.insert(key, max_amount);
+            }
+        }
+        Ok(limits)
+    }
+
+    /// Replace the exposure limits of a peer, no limit being left when they're all unset
+    pub(crate) fn set_peer_exposure_limits(
+        &self,
+        peer_pubkey: &str,
+        limits: &PeerExposureLimits,
+    ) -> Result<(), APIError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM peer_exposure_limits WHERE peer_pubkey = ?1",
+            params![peer_pubkey],
+        )?;
+        let max_msat = limits.max_msat.map(|m| (MSAT_LIMIT_KEY, m));
+        for (key, max_amount) in max_msat.into_iter().chain(
+            limits
+                .max_asset_amounts
+                .iter()
+                .map(|(a, m)| (a.as_str(), *m)),
+        ) {
+            tx.execute(
+                "INSERT INTO peer_exposure_limits (peer_pubkey, asset_id, max_amount)
+                VALUES (?1, ?2, ?3)",
+                params![peer_pubkey, key, max_amount],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub(crate) fn alert_thresholds(&self) -> Result<AlertThresholds, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let thresholds = conn
+            .query_row(
+                "SELECT anchor_reserve_per_channel_sat, min_fee_bump_utxos,
+                min_channel_asset_amount, max_unconfirmed_tx_age_secs FROM alert_thresholds
+                WHERE id = 0",
+                [],
+                |row| {
+                    Ok(AlertThresholds {
+                        anchor_reserve_per_channel_sat: row.get(0)?,
+                        min_fee_bump_utxos: row.get(1)?,
+                        min_channel_asset_amount: row.get(2)?,
+                        max_unconfirmed_tx_age_secs: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(thresholds.unwrap_or_default())
+    }
+
+    pub(crate) fn set_alert_thresholds(
+        &self,
+        thresholds: &AlertThresholds,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO alert_thresholds (id, anchor_reserve_per_channel_sat,
+            min_fee_bump_utxos, min_channel_asset_amount, max_unconfirmed_tx_age_secs)
+            VALUES (0, ?1, ?2, ?3, ?4)",
+            params![
+                thresholds.anchor_reserve_per_channel_sat,
+                thresholds.min_fee_bump_utxos,
+                thresholds.min_channel_asset_amount,
+                thresholds.max_unconfirmed_tx_age_secs
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record the height at which the channel of a monitor has been found fully resolved, keeping
+    /// the first one
+    pub(crate) fn set_monitor_resolved(
+        &self,
+        monitor_key: &str,
+        height: u32,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO monitor_archive (monitor_key, resolved_height) VALUES (?1, ?2)",
+            params![monitor_key, height],
+        )?;
+        Ok(())
+    }
+
+    /// Keys of the monitors resolved at or before the given height and not archived yet
+    pub(crate) fn monitors_to_archive(&self, max_height: u32) -> Result<Vec<String>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT monitor_key FROM monitor_archive
+            WHERE archived_at IS NULL AND resolved_height <= ?1",
+        )?;
+        let rows = stmt
+            .query_map(params![max_height], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    pub(crate) fn set_monitor_archived(
+        &self,
+        monitor_key: &str,
+        archived_at: u64,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE monitor_archive SET archived_at = ?2 WHERE monitor_key = ?1",
+            params![monitor_key, archived_at],
+        )?;
+        Ok(())
+    }
+
+    /// Keys of the monitors archived before the given timestamp
+    pub(crate) fn monitors_archived_before(&self, ts: u64) -> Result<Vec<String>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT monitor_key FROM monitor_archive
+            WHERE archived_at IS NOT NULL AND archived_at < ?1",
+        )?;
+        let rows = stmt
+            .query_map(params![ts], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    pub(crate) fn remove_monitor_archive(&self, monitor_key: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM monitor_archive WHERE monitor_key = ?1",
+            params![monitor_key],
+        )?;
+        Ok(())
+    }
+
+    /// Start watching an unconfirmed transaction, keeping the time it was first watched at
+    pub(crate) fn add_watched_tx(
+        &self,
+        txid: &str,
+        raw_tx: &[u8],
+        watched_since: u64,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO watched_txs (txid, raw_tx, watched_since, rebroadcasts)
+            VALUES (?1, ?2, ?3, 0)",
+            params![txid, raw_tx, watched_since],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn watched_txs(&self) -> Result<BTreeMap<String, WatchedTx>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT txid, raw_tx, watched_since, rebroadcasts FROM watched_txs")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    WatchedTx {
+                        raw_tx: row.get(1)?,
+                        watched_since: row.get(2)?,
+                        rebroadcasts: row.get(3)?,
+                    },
+                ))
+            })?
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        Ok(rows)
+    }
+
+    pub(crate) fn add_rebroadcast(&self, txid: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE watched_txs SET rebroadcasts = rebroadcasts + 1 WHERE txid = ?1",
+            params![txid],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn remove_watched_tx(&self, txid: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM watched_txs WHERE txid = ?1", params![txid])?;
+        Ok(())
+    }
+
+    pub(crate) fn add_reorged_tx(&self, txid: &str, reorged_at: u64) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO reorged_txs (txid, reorged_at) VALUES (?1, ?2)",
+            params![txid, reorged_at],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn reorged_txs(&self) -> Result<HashSet<String>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT txid FROM reorged_txs")?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<HashSet<_>, _>>()?;
+        Ok(rows)
+    }
+
+    pub(crate) fn remove_reorged_tx(&self, txid: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM reorged_txs WHERE txid = ?1", params![txid])?;
+        Ok(())
+    }
+
+    pub(crate) fn add_wallet_intent(&self, intent: &WalletIntent) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO wallet_intents (txid, kind, signed_psbt, asset_id,
+            spending_id, blinded_utxo, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                intent.txid,
+                wallet_intent_kind_code(intent.kind),
+                intent.signed_psbt,
+                intent.asset_id,
+                intent.spending_id,
+                intent.blinded_utxo,
+                intent.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn wallet_intents(&self) -> Result<Vec<WalletIntent>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT txid, kind, signed_psbt, asset_id, spending_id, blinded_utxo, created_at
+            FROM wallet_intents ORDER BY created_at",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u8>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, u64>(6)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(
+                |(txid, kind, signed_psbt, asset_id, spending_id, blinded_utxo, created_at)| {
+                    Ok(WalletIntent {
+                        txid,
+                        kind: wallet_intent_kind_from_code(kind)?,
+                        signed_psbt,
+                        asset_id,
+                        spending_id,
+                        blinded_utxo,
+                        created_at,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    pub(crate) fn remove_wallet_intent(&self, txid: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM wallet_intents WHERE txid = ?1", params![txid])?;
+        Ok(())
+    }
+
+    pub(crate) fn save_swap(&self, record: &SwapRecord) -> Result<(), APIError> {
+        let swap = &record.swap;
+        let (recipient_id, transport_endpoints) = match &record.recipient {
+            Some((recipient_id, endpoints)) => (Some(recipient_id), Some(endpoints.join(","))),
+            None => (None, None),
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO swaps (swap_id, direction, initiator, channel_id, peer_pubkey,
+            asset_id, asset_amount, status, payment_hash, txid, reason, created_at, recipient_id,
+            transport_endpoints, expires_at, preimage)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+            params![
+                swap.swap_id,
+                swap_direction_code(swap.direction),
+                swap.initiator,
+                swap.channel_id,
+                swap.peer_pubkey,
+                swap.asset_id,
+                swap.asset_amount,
+                swap_status_code(swap.status),
+                swap.payment_hash,
+                swap.txid,
+                swap.reason,
+                swap.created_at,
+                recipient_id,
+                transport_endpoints,
+                record.expires_at,
+                record.preimage,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn swaps(&self) -> Result<Vec<SwapRecord>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT swap_id, direction, initiator, channel_id, peer_pubkey, asset_id, asset_amount,
+            status, payment_hash, txid, reason, created_at, recipient_id, transport_endpoints,
+            expires_at, preimage FROM swaps",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    (
+                        row.get::<_, String>(0)?,
+                        row.get::<_, u8>(1)?,
+                        row.get::<_, bool>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, String>(4)?,
+                        row.get::<_, String>(5)?,
+                        row.get::<_, u64>(6)?,
+                        row.get::<_, u8>(7)?,
+                    ),
+                    (
+                        row.get::<_, Option<String>>(8)?,
+                        row.get::<_, Option<String>>(9)?,
+                        row.get::<_, Option<String>>(10)?,
+                        row.get::<_, u64>(11)?,
+                        row.get::<_, Option<String>>(12)?,
+                        row.get::<_, Option<String>>(13)?,
+                        row.get::<_, Option<u64>>(14)?,
+                        row.get::<_, Option<String>>(15)?,
+                    ),
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(
+                |(
+                    (
+                        swap_id,
+                        direction,
+                        initiator,
+                        channel_id,
+                        peer_pubkey,
+                        asset_id,
+                        amount,
+                        status,
+                    ),
+                    (
+                        payment_hash,
+                        txid,
+                        reason,
+                        created_at,
+                        recipient_id,
+                        transport_endpoints,
+                        expires_at,
+                        preimage,
+                    ),
+                )| {
+                    let endpoints = transport_endpoints
+                        .unwrap_or_default()
+                        .split(',')
+                        .filter(|e| !e.is_empty())
+                        .map(|e| e.to_string())
+                        .collect();
+                    Ok(SwapRecord {
+                        swap: Swap {
+                            swap_id,
+                            direction: swap_direction_from_code(direction)?,
+                            initiator,
+                            channel_id,
+                            peer_pubkey,
+                            asset_id,
+                            asset_amount: amount,
+                            status: swap_status_from_code(status)?,
+                            payment_hash,
+                            txid,
+                            reason,
+                            created_at,
+                        },
+                        recipient: recipient_id.map(|r| (r, endpoints)),
+                        expires_at,
+                        preimage,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    pub(crate) fn add_utxo_reservation(
+        &self,
+        record: &UtxoReservationRecord,
+    ) -> Result<(), APIError> {
+        let channel_id = match &record.reservation {
+            UtxoReservation::ChannelOpen { channel_id } => Some(channel_id),
+            _ => None,
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO utxo_reservations (txid, kind, channel_id, inputs, reserved_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                record.txid,
+                utxo_reservation_kind_code(&record.reservation),
+                channel_id,
+                record.inputs.join(","),
+                record.reserved_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn utxo_reservations(&self) -> Result<Vec<UtxoReservationRecord>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT txid, kind, channel_id, inputs, reserved_at FROM utxo_reservations
+            ORDER BY reserved_at",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u8>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, u64>(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(txid, kind, channel_id, inputs, reserved_at)| {
+                Ok(UtxoReservationRecord {
+                    reservation: utxo_reservation_from_code(kind, &txid, channel_id)?,
+                    txid,
+                    inputs: inputs
+                        .split(',')
+                        .filter(|i| !i.is_empty())
+                        .map(|i| i.to_string())
+                        .collect(),
+                    reserved_at,
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn remove_utxo_reservation(&self, txid: &str) -> Result<bool, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let removed = conn.execute(
+            "DELETE FROM utxo_reservations WHERE txid = ?1",
+            params![txid],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Get how much has been spent since the given timestamp
+    pub(crate) fn spending_usage(&self, since: u64) -> Result<SpendingUsage, APIError> {
+        let conn = self.conn.lock().unwrap();
+        spending_usage(&conn, since)
+    }
+
+    /// Record an outbound payment, refusing it if it would exceed the spending limits, returning
+    /// whether it has been recorded by this call
+    ///
+    /// Payments already recorded with the same ID are not counted again.
+    pub(crate) fn reserve_spending(
+        &self,
+        id: &str,
+        amt_msat: u64,
+        asset: Option<(&str, u64)>,
+        now: u64,
+    ) -> Result<bool, APIError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let already_recorded = tx
+            .query_row("SELECT 1 FROM spending WHERE id = ?1", params![id], |_| {
+                Ok(())
+            })
+            .optional()?
+            .is_some();
+        if already_recorded {
+            return Ok(false);
+        }
+
+        check_spending_limits(&tx, amt_msat, asset, now)?;
+
+        // SQLite integers are signed, a bigger asset amount would exhaust any limit all the same
+        let asset_amount = asset.map_or(0, |(_, a)| a.min(i64::MAX as u64));
+        tx.execute(
+            "INSERT INTO spending (id, asset_id, amt_msat, asset_amount, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, asset.map(|(a, _)| a), amt_msat, asset_amount, now],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Check an outbound payment is within the spending limits, without recording it
+    pub(crate) fn check_spending(
+        &self,
+        amt_msat: u64,
+        asset: Option<(&str, u64)>,
+        now: u64,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        check_spending_limits(&conn, amt_msat, asset, now)
+    }
+
+    /// Remove a payment from the spending count, as it has failed
+    pub(crate) fn release_spending(&self, id: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM spending WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub(crate) fn add_channel_funding(
+        &self,
+        channel_id: &str,
+        funding_txid: &str,
+        funding_vout: u32,
+        capacity_sat: u64,
+        outbound: bool,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO channel_fundings
+            (channel_id, funding_txid, funding_vout, capacity_sat, outbound)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                channel_id,
+                funding_txid,
+                funding_vout,
+                capacity_sat,
+                outbound
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get the channel funded by the given outpoint
+    pub(crate) fn channel_funding(
+        &self,
+        funding_txid: &str,
+        funding_vout: u32,
+    ) -> Result<Option<ChannelFunding>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let funding = conn
+            .query_row(
+                "SELECT channel_id, capacity_sat, outbound FROM channel_fundings
+                WHERE funding_txid = ?1 AND funding_vout = ?2",
+                params![funding_txid, funding_vout],
+                |row| {
+                    Ok(ChannelFunding {
+                        channel_id: row.get(0)?,
+                        capacity_sat: row.get(1)?,
+                        outbound: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(funding)
+    }
+
+    pub(crate) fn channel_funding_txid(
+        &self,
+        channel_id: &str,
+    ) -> Result<Option<String>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let funding_txid = conn
+            .query_row(
+                "SELECT funding_txid FROM channel_fundings WHERE channel_id = ?1",
+                params![channel_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(funding_txid)
+    }
+
+    /// Record the fee paid by a channel transaction
+    ///
+    /// Claimable transactions have outputs the node claims with further transactions, whose fees
+    /// get attributed to the same channel. A transaction already recorded is only updated if
+    /// `replace` is set.
+    pub(crate) fn add_channel_fee(
+        &self,
+        txid: &str,
+        channel_id: &str,
+        kind: ChannelFeeKind,
+        fee_sat: u64,
+        claimable: bool,
+        replace: bool,
+    ) -> Result<(), APIError> {
+        let verb = if replace {
+            "INSERT OR REPLACE"
+        } else {
+            "INSERT OR IGNORE"
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            &format!(
+                "{verb} INTO channel_fees (txid, channel_id, kind, fee_sat, claimable)
+                VALUES (?1, ?2, ?3, ?4, ?5)"
+            ),
+            params![
+                txid,
+                channel_id,
+                channel_fee_kind_code(kind),
+                fee_sat,
+                claimable
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get the channel a claimable transaction belongs to
+    pub(crate) fn claimable_tx_channel(&self, txid: &str) -> Result<Option<String>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let channel_id = conn
+            .query_row(
+                "SELECT channel_id FROM channel_fees WHERE txid = ?1 AND claimable = 1",
+                params![txid],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(channel_id)
+    }
+
+    /// Forget the fee of a transaction, as it has been replaced
+    pub(crate) fn remove_channel_fee(&self, txid: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM channel_fees WHERE txid = ?1", params![txid])?;
+        Ok(())
+    }
+
+    /// Record the fee rate chosen for a channel transaction
+    pub(crate) fn set_channel_fee_rate(
+        &self,
+        channel_id: &str,
+        kind: ChannelFeeKind,
+        fee_rate: ChannelFeeRate,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO channel_fee_rates
+            (channel_id, kind, target_conf_blocks, fee_rate_sat_vb) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                channel_id,
+                channel_fee_kind_code(kind),
+                fee_rate.target_conf_blocks,
+                fee_rate.fee_rate_sat_vb
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Replace the chosen fee rate of a channel transaction with the one it actually pays,
+    /// keeping its confirmation target
+    pub(crate) fn update_channel_fee_rate(
+        &self,
+        channel_id: &str,
+        kind: ChannelFeeKind,
+        fee_rate_sat_vb: f32,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE channel_fee_rates SET fee_rate_sat_vb = ?3 WHERE channel_id = ?1 AND kind = ?2",
+            params![channel_id, channel_fee_kind_code(kind), fee_rate_sat_vb],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn channel_fee_rate(
+        &self,
+        channel_id: &str,
+        kind: ChannelFeeKind,
+    ) -> Result<Option<ChannelFeeRate>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let fee_rate = conn
+            .query_row(
+                "SELECT target_conf_blocks, fee_rate_sat_vb FROM channel_fee_rates
+                WHERE channel_id = ?1 AND kind = ?2",
+                params![channel_id, channel_fee_kind_code(kind)],
+                |row| {
+                    Ok(ChannelFeeRate {
+                        target_conf_blocks: row.get(0)?,
+                        fee_rate_sat_vb: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(fee_rate)
+    }
+
+    /// Record when to force-close a channel whose cooperative close hasn't concluded yet
+    pub(crate) fn set_close_deadline(
+        &self,
+        channel_id: &str,
+        force_at: u64,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO close_negotiations (channel_id, force_at) VALUES (?1, ?2)
+            ON CONFLICT (channel_id) DO UPDATE SET force_at = ?2",
+            params![channel_id, force_at],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn clear_close_deadline(&self, channel_id: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE close_negotiations SET force_at = NULL WHERE channel_id = ?1",
+            params![channel_id],
+        )?;
+        Ok(())
+    }
+
+    /// Get the deadlines of the pending cooperative closes, by channel ID
+    pub(crate) fn close_deadlines(&self) -> Result<HashMap<String, u64>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT channel_id, force_at FROM close_negotiations WHERE force_at IS NOT NULL",
+        )?;
+        let deadlines = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<String, u64>, _>>()?;
+        Ok(deadlines)
+    }
+
+    /// Record the fee rate a cooperative close has been agreed on, which concludes it
+    pub(crate) fn set_negotiated_close_fee_rate(
+        &self,
+        channel_id: &str,
+        fee_rate_sat_vb: f32,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO close_negotiations (channel_id, negotiated_fee_rate_sat_vb)
+            VALUES (?1, ?2)
+            ON CONFLICT (channel_id)
+            DO UPDATE SET force_at = NULL, negotiated_fee_rate_sat_vb = ?2",
+            params![channel_id, fee_rate_sat_vb],
+        )?;
+        Ok(())
+    }
+
+    /// Get the fee rates cooperative closes have been agreed on, by channel ID
+    pub(crate) fn negotiated_close_fee_rates(&self) -> Result<HashMap<String, f32>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT channel_id, negotiated_fee_rate_sat_vb FROM close_negotiations
+            WHERE negotiated_fee_rate_sat_vb IS NOT NULL",
+        )?;
+        let fee_rates = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<String, f32>, _>>()?;
+        Ok(fee_rates)
+    }
+
+    /// Get the on-chain fees paid so far, by channel ID
+    /// Get the recorded channel transactions, by txid
+    pub(crate) fn channel_txs(&self) -> Result<HashMap<String, ChannelTx>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut channel_txs = HashMap::new();
+        let mut stmt = conn.prepare("SELECT funding_txid, channel_id FROM channel_fundings")?;
+        for row in stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })? {
+            let (txid, channel_id) = row?;
+            channel_txs.insert(
+                txid,
+                ChannelTx {
+                    channel_id,
+                    kind: ChannelFeeKind::Funding,
+                },
+            );
+        }
+        let mut stmt = conn.prepare("SELECT txid, channel_id, kind FROM channel_fees")?;
+        for row in stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, u8>(2)?,
+            ))
+        })? {
+            let (txid, channel_id, kind) = row?;
+            channel_txs.insert(
+                txid,
+                ChannelTx {
+                    channel_id,
+                    kind: channel_fee_kind_from_code(kind)?,
+                },
+            );
+        }
+        Ok(channel_txs)
+    }
+
+    pub(crate) fn channel_fees(&self) -> Result<HashMap<String, ChannelFees>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT channel_id, kind, SUM(fee_sat) FROM channel_fees GROUP BY channel_id, kind",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u8>(1)?,
+                    row.get::<_, u64>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut fees: HashMap<String, ChannelFees> = HashMap::new();
+        for (channel_id, kind, fee_sat) in rows {
+            let channel_fees = fees.entry(channel_id).or_default();
+            match channel_fee_kind_from_code(kind)? {
+                ChannelFeeKind::Funding => channel_fees.funding_sat += fee_sat,
+                ChannelFeeKind::Close => channel_fees.close_sat += fee_sat,
+                ChannelFeeKind::ForceClose => channel_fees.force_close_sat += fee_sat,
+            }
+        }
+        Ok(fees)
+    }
+
+    pub(crate) fn add_closed_channel(&self, channel: &ClosedChannelRecord) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO closed_channels (channel_id, funding_txid, peer_pubkey,
+            capacity_sat, asset_id, asset_local_amount, asset_remote_amount, reason, closed_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                channel.channel_id,
+                channel.funding_txid,
+                channel.peer_pubkey,
+                channel.capacity_sat,
+                channel.asset_id,
+                channel.asset_local_amount,
+                channel.asset_remote_amount,
+                channel.reason,
+                channel.closed_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn is_closed_channel(&self, channel_id: &str) -> Result<bool, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let closed = conn
+            .query_row(
+                "SELECT 1 FROM closed_channels WHERE channel_id = ?1",
+                params![channel_id],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        Ok(closed)
+    }
+
+    /// Get the closed channels, most recently closed first
+    pub(crate) fn closed_channels(&self) -> Result<Vec<ClosedChannelRecord>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT channel_id, funding_txid, peer_pubkey, capacity_sat, asset_id,
+            asset_local_amount, asset_remote_amount, reason, closed_at
+            FROM closed_channels ORDER BY closed_at DESC",
+        )?;
+        let channels = stmt
+            .query_map([], |row| {
+                Ok(ClosedChannelRecord {
+                    channel_id: row.get(0)?,
+                    funding_txid: row.get(1)?,
+                    peer_pubkey: row.get(2)?,
+                    capacity_sat: row.get(3)?,
+                    asset_id: row.get(4)?,
+                    asset_local_amount: row.get(5)?,
+                    asset_remote_amount: row.get(6)?,
+                    reason: row.get(7)?,
+                    closed_at: row.get(8)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(channels)
+    }
+
+    pub(crate) fn add_sweep(&self, sweep: &SweepRecord) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO sweeps (txid, amount_sat, asset_id, asset_amount, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                sweep.txid,
+                sweep.amount_sat,
+                sweep.asset_id,
+                sweep.asset_amount,
+                sweep.created_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn sweeps(&self) -> Result<Vec<SweepRecord>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT txid, amount_sat, asset_id, asset_amount, created_at
+            FROM sweeps ORDER BY created_at DESC",
+        )?;
+        let sweeps = stmt
+            .query_map([], |row| {
+                Ok(SweepRecord {
+                    txid: row.get(0)?,
+                    amount_sat: row.get(1)?,
+                    asset_id: row.get(2)?,
+                    asset_amount: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sweeps)
+    }
+
+    /// Get the channel notes, by channel ID
+    pub(crate) fn channel_notes(&self) -> Result<HashMap<String, String>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT channel_id, note FROM channel_notes")?;
+        let notes = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<String, String>, _>>()?;
+        Ok(notes)
+    }
+
+    /// Set the note of a channel, removing it if None
+    pub(crate) fn set_channel_note(
+        &self,
+        channel_id: &str,
+        note: Option<&str>,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        match note {
+            Some(note) => conn.execute(
+                "INSERT OR REPLACE INTO channel_notes (channel_id, note) VALUES (?1, ?2)",
+                params![channel_id, note],
+            )?,
+            None => conn.execute(
+                "DELETE FROM channel_notes WHERE channel_id = ?1",
+                params![channel_id],
+            )?,
+        };
+        Ok(())
+    }
+
+    /// Move the note of a channel to its new ID, as a funded channel replaces its temporary ID
+    pub(crate) fn rename_channel_note(&self, old_id: &str, new_id: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE channel_notes SET channel_id = ?2 WHERE channel_id = ?1",
+            params![old_id, new_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record the channels of a successful payment path, forgetting the ones unused for too long
+    pub(crate) fn set_hot_channels(
+        &self,
+        short_channel_ids: &[u64],
+        timestamp: u64,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        for short_channel_id in short_channel_ids {
+            conn.execute(
+                "INSERT OR REPLACE INTO route_hot_channels (short_channel_id, last_success)
+                VALUES (?1, ?2)",
+                params![short_channel_id, timestamp],
+            )?;
+        }
+        conn.execute(
+            "DELETE FROM route_hot_channels WHERE last_success < ?1",
+            params![timestamp.saturating_sub(HOT_CHANNEL_AGE_SECS)],
+        )?;
+        Ok(())
+    }
+
+    /// Channels of the payment paths that succeeded since the given timestamp
+    pub(crate) fn hot_channels(&self, since: u64) -> Result<HashSet<u64>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT short_channel_id FROM route_hot_channels WHERE last_success >= ?1")?;
+        let short_channel_ids = stmt
+            .query_map(params![since], |row| row.get(0))?
+            .collect::<Result<HashSet<u64>, _>>()?;
+        Ok(short_channel_ids)
+    }
+
+    /// Record a forward, which counts as activity of both its channels
+    pub(crate) fn add_forward(&self, forward: &ForwardRecord) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO forwards (prev_channel_id, next_channel_id, amt_msat, fee_msat, forwarded_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                forward.prev_channel_id,
+                forward.next_channel_id,
+                forward.amt_msat,
+                forward.fee_msat,
+                forward.forwarded_at
+            ],
+        )?;
+        drop(conn);
+        let channel_ids: Vec<&str> = [&forward.prev_channel_id, &forward.next_channel_id]
+            .into_iter()
+            .flatten()
+            .map(|c| c.as_str())
+            .collect();
+        self.set_channel_activity(&channel_ids, forward.forwarded_at)
+    }
+
+    /// Forwards since the given timestamp
+    pub(crate) fn forwards_since(&self, since: u64) -> Result<Vec<ForwardRecord>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT prev_channel_id, next_channel_id, amt_msat, fee_msat, forwarded_at
+            FROM forwards WHERE forwarded_at >= ?1",
+        )?;
+        let forwards = stmt
+            .query_map(params![since], |row| {
+                Ok(ForwardRecord {
+                    prev_channel_id: row.get(0)?,
+                    next_channel_id: row.get(1)?,
+                    amt_msat: row.get(2)?,
+                    fee_msat: row.get(3)?,
+                    forwarded_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(forwards)
+    }
+
+    /// Record that payments went through the given channels
+    pub(crate) fn set_channel_activity(
+        &self,
+        channel_ids: &[&str],
+        timestamp: u64,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        for channel_id in channel_ids {
+            conn.execute(
+                "INSERT OR REPLACE INTO channel_activity (channel_id, last_activity)
+                VALUES (?1, ?2)",
+                params![channel_id, timestamp],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Last time payments went through each channel, by channel ID
+    pub(crate) fn channel_activity(&self) -> Result<HashMap<String, u64>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT channel_id, last_activity FROM channel_activity")?;
+        let activity = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<String, u64>, _>>()?;
+        Ok(activity)
+    }
+
+    /// Record the creation time of the given channels, unless they already have one
+    pub(crate) fn add_channel_creations(
+        &self,
+        channel_ids: &[&str],
+        timestamp: u64,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        for channel_id in channel_ids {
+            conn.execute(
+                "INSERT OR IGNORE INTO channel_creations (channel_id, created_at) VALUES (?1, ?2)",
+                params![channel_id, timestamp],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Creation time of each channel, by channel ID
+    pub(crate) fn channel_creations(&self) -> Result<HashMap<String, u64>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT channel_id, created_at FROM channel_creations")?;
+        let creations = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<String, u64>, _>>()?;
+        Ok(creations)
+    }
+
+    /// Keep the creation time of a channel once its temporary ID gets replaced
+    pub(crate) fn rename_channel_creation(
+        &self,
+        old_id: &str,
+        new_id: &str,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE OR IGNORE channel_creations SET channel_id = ?2 WHERE channel_id = ?1",
+            params![old_id, new_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record an HTLC added to a channel, replacing it if it's sent again
+    pub(crate) fn add_channel_htlc(&self, record: &ChannelHtlcRecord) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO channel_htlcs
+            (channel_id, inbound, htlc_id, peer_pubkey, amt_msat, payment_hash, cltv_expiry)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                record.channel_id,
+                record.inbound,
+                record.htlc_id,
+                record.peer_pubkey,
+                record.amt_msat,
+                record.payment_hash,
+                record.cltv_expiry
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Forget an HTLC once it has been fulfilled or failed
+    pub(crate) fn remove_channel_htlc(
+        &self,
+        channel_id: &str,
+        inbound: bool,
+        htlc_id: u64,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM channel_htlcs WHERE channel_id = ?1 AND inbound = ?2 AND htlc_id = ?3",
+            params![channel_id, inbound, htlc_id],
+        )?;
+        Ok(())
+    }
+
+    /// Forget the HTLCs of a channel that is no longer open
+    pub(crate) fn remove_channel_htlcs(&self, channel_id: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM channel_htlcs WHERE channel_id = ?1",
+            params![channel_id],
+        )?;
+        Ok(())
+    }
+
+    /// Get the HTLCs pending on the channels, ordered by channel and HTLC ID
+    pub(crate) fn channel_htlcs(&self) -> Result<Vec<ChannelHtlcRecord>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT channel_id, inbound, htlc_id, peer_pubkey, amt_msat, payment_hash, cltv_expiry
+            FROM channel_htlcs ORDER BY channel_id, inbound, htlc_id",
+        )?;
+        let htlcs = stmt
+            .query_map([], |row| {
+                Ok(ChannelHtlcRecord {
+                    channel_id: row.get(0)?,
+                    inbound: row.get(1)?,
+                    htlc_id: row.get(2)?,
+                    peer_pubkey: row.get(3)?,
+                    amt_msat: row.get(4)?,
+                    payment_hash: row.get(5)?,
+                    cltv_expiry: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(htlcs)
+    }
+
+    /// Record that a peer has connected
+    pub(crate) fn set_peer_connected(
+        &self,
+        peer_pubkey: &str,
+        timestamp: u64,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO peer_connections (peer_pubkey, connected_since, disconnect_count)
+            VALUES (?1, ?2, 0)
+            ON CONFLICT (peer_pubkey) DO UPDATE SET connected_since = ?2",
+            params![peer_pubkey, timestamp],
+        )?;
+        Ok(())
+    }
+
+    /// Record that a peer has disconnected, counting the disconnection unless the node is the one
+    /// going offline
+    pub(crate) fn set_peer_disconnected(
+        &self,
+        peer_pubkey: &str,
+        timestamp: u64,
+        counted: bool,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE peer_connections
+            SET connected_since = NULL, last_seen = ?2, disconnect_count = disconnect_count + ?3
+            WHERE peer_pubkey = ?1",
+            params![peer_pubkey, timestamp, counted as u32],
+        )?;
+        Ok(())
+    }
+
+    /// Close the connections left open when the node has been stopped and start tracking the
+    /// given channel peers, both as last seen at the given timestamp
+    pub(crate) fn reset_peer_connections(
+        &self,
+        channel_peers: &[String],
+        timestamp: u64,
+    ) -> Result<(), APIError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE peer_connections SET connected_since = NULL, last_seen = ?1
+            WHERE connected_since IS NOT NULL",
+            params![timestamp],
+        )?;
+        for peer_pubkey in channel_peers {
+            tx.execute(
+                "INSERT OR IGNORE INTO peer_connections (peer_pubkey, last_seen, disconnect_count)
+                VALUES (?1, ?2, 0)",
+                params![peer_pubkey, timestamp],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get the connection history of the peers, by peer pubkey
+    pub(crate) fn peer_connections(
+        &self,
+    ) -> Result<HashMap<String, PeerConnectionRecord>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT peer_pubkey, connected_since, last_seen, disconnect_count
+            FROM peer_connections",
+        )?;
+        let connections = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    PeerConnectionRecord {
+                        connected_since: row.get(1)?,
+                        last_seen: row.get(2)?,
+                        disconnect_count: row.get(3)?,
+                    },
+                ))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(connections)
+    }
+
+    /// Record an address of the vanilla wallet, unless it's already known
+    pub(crate) fn add_wallet_address(&self, record: &WalletAddressRecord) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO wallet_addresses (derivation_index, address, issued_at)
+            VALUES (?1, ?2, ?3)",
+            params![record.derivation_index, record.address, record.issued_at],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn is_known_wallet_address(&self, address: &str) -> Result<bool, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let known = conn
+            .query_row(
+                "SELECT 1 FROM wallet_addresses WHERE address = ?1",
+                params![address],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        Ok(known)
+    }
+
+    /// Get the address handed out last, if any
+    pub(crate) fn last_issued_wallet_address(
+        &self,
+    ) -> Result<Option<WalletAddressRecord>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let record = conn
+            .query_row(
+                "SELECT derivation_index, address, issued_at FROM wallet_addresses
+                WHERE issued_at IS NOT NULL ORDER BY issued_at DESC, derivation_index DESC LIMIT 1",
+                [],
+                |row| {
+                    Ok(WalletAddressRecord {
+                        derivation_index: row.get(0)?,
+                        address: row.get(1)?,
+                        issued_at: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(record)
+    }
+
+    /// Get the known addresses of the vanilla wallet, by derivation index
+    pub(crate) fn wallet_addresses(&self) -> Result<Vec<WalletAddressRecord>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT derivation_index, address, issued_at FROM wallet_addresses
+            ORDER BY derivation_index",
+        )?;
+        let records = stmt
+            .query_map([], |row| {
+                Ok(WalletAddressRecord {
+                    derivation_index: row.get(0)?,
+                    address: row.get(1)?,
+                    issued_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(records)
+    }
+
+    /// Register a webhook, returning its ID
+    pub(crate) fn add_webhook(
+        &self,
+        url: &str,
+        events: &[String],
+        secret: &str,
+        created_at: u64,
+    ) -> Result<u64, APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO webhooks (url, events, secret, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![url, events.join(","), secret, created_at],
+        )?;
+        Ok(conn.last_insert_rowid() as u64)
+    }
+
+    /// Remove a webhook, returning whether it existed
+    pub(crate) fn delete_webhook(&self, id: u64) -> Result<bool, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let removed = conn.execute("DELETE FROM webhooks WHERE id = ?1", params![id])?;
+        Ok(removed > 0)
+    }
+
+    pub(crate) fn webhooks(&self) -> Result<Vec<WebhookRecord>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, url, events, secret, created_at, delivered, failed, last_attempt_at,
+            last_delivered_at, last_error FROM webhooks ORDER BY id",
+        )?;
+        let records = stmt
+            .query_map([], |row| {
+                Ok(WebhookRecord {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    events: row
+                        .get::<_, String>(2)?
+                        .split(',')
+                        .map(|e| e.to_string())
+                        .collect(),
+                    secret: row.get(3)?,
+                    created_at: row.get(4)?,
+                    delivered: row.get(5)?,
+                    failed: row.get(6)?,
+                    last_attempt_at: row.get(7)?,
+                    last_delivered_at: row.get(8)?,
+                    last_error: row.get(9)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(records)
+    }
+
+    /// Record the outcome of an attempt to deliver an event to a webhook, a failed attempt
+    /// counting as a failed delivery once no attempt is left
+    pub(crate) fn record_webhook_attempt(
+        &self,
+        id: u64,
+        timestamp: u64,
+        error: Option<&str>,
+        gave_up: bool,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        match error {
+            None => conn.execute(
+                "UPDATE webhooks SET delivered = delivered + 1, last_attempt_at = ?2,
+                last_delivered_at = ?2, last_error = NULL WHERE id = ?1",
+                params![id, timestamp],
+            )?,
+            Some(error) => conn.execute(
+                "UPDATE webhooks SET failed = failed + ?4, last_attempt_at = ?2, last_error = ?3
+                WHERE id = ?1",
+                params![id, timestamp, error, gave_up as u32],
+            )?,
+        };
+        Ok(())
+    }
+
+    /// Record the blinded UTXO the assets of a channel being cooperatively closed go to
+    pub(crate) fn set_close_asset_destination(
+        &self,
+        channel_id: &str,
+        blinded_utxo: &str,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO close_asset_destinations (channel_id, blinded_utxo)
+            VALUES (?1, ?2)",
+            params![channel_id, blinded_utxo],
+        )?;
+        Ok(())
+    }
+
+    /// Link the asset destination of a channel, if any, to the transaction closing it
+    pub(crate) fn link_close_asset_destination(
+        &self,
+        channel_id: &str,
+        closing_txid: &str,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE close_asset_destinations SET closing_txid = ?2 WHERE channel_id = ?1",
+            params![channel_id, closing_txid],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn close_asset_destination(
+        &self,
+        closing_txid: &str,
+    ) -> Result<Option<String>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let blinded_utxo = conn
+            .query_row(
+                "SELECT blinded_utxo FROM close_asset_destinations WHERE closing_txid = ?1",
+                params![closing_txid],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(blinded_utxo)
+    }
+
+    /// Record a blinded UTXO the node is sending assets to, returning false if it already was
+    pub(crate) fn add_sent_blinded_utxo(
+        &self,
+        blinded_utxo: &str,
+        asset_id: &str,
+        created_at: u64,
+    ) -> Result<bool, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO sent_blinded_utxos (blinded_utxo, asset_id, created_at)
+            VALUES (?1, ?2, ?3)",
+            params![blinded_utxo, asset_id, created_at],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Whether the node has already sent assets to a blinded UTXO
+    pub(crate) fn is_sent_blinded_utxo(&self, blinded_utxo: &str) -> Result<bool, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let sent = conn
+            .query_row(
+                "SELECT 1 FROM sent_blinded_utxos WHERE blinded_utxo = ?1",
+                params![blinded_utxo],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        Ok(sent)
+    }
+
+    /// Get the blinded UTXOs the node has sent assets to, with the asset sent
+    pub(crate) fn sent_blinded_utxos(&self) -> Result<Vec<(String, String)>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT blinded_utxo, asset_id FROM sent_blinded_utxos")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    pub(crate) fn remove_sent_blinded_utxo(&self, blinded_utxo: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM sent_blinded_utxos WHERE blinded_utxo = ?1",
+            params![blinded_utxo],
+        )?;
+        Ok(())
+    }
+
+    /// Record the inputs of the transaction anchoring an incoming transfer, keeping the ones
+    /// already recorded
+    pub(crate) fn add_incoming_anchor(
+        &self,
+        txid: &str,
+        inputs: &[String],
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO incoming_anchors (txid, inputs) VALUES (?1, ?2)",
+            params![txid, inputs.join(",")],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn incoming_anchor_inputs(
+        &self,
+        txid: &str,
+    ) -> Result<Option<Vec<String>>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let inputs: Option<String> = conn
+            .query_row(
+                "SELECT inputs FROM incoming_anchors WHERE txid = ?1",
+                params![txid],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(inputs.map(|i| i.split(',').map(|s| s.to_string()).collect()))
+    }
+
+    pub(crate) fn remove_incoming_anchor(&self, txid: &str) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM incoming_anchors WHERE txid = ?1",
+            params![txid],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn add_failed_incoming_transfer(
+        &self,
+        recipient_id: &str,
+        reason: &str,
+        failed_at: u64,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO failed_incoming_transfers (recipient_id, reason, failed_at)
+            VALUES (?1, ?2, ?3)",
+            params![recipient_id, reason, failed_at],
+        )?;
+        Ok(())
+    }
+
+    /// Record the transport endpoint a consignment posted by the node has been uploaded to
+    pub(crate) fn set_consignment_endpoint(
+        &self,
+        recipient_id: &str,
+        endpoint: &str,
+    ) -> Result<(), APIError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO consignment_endpoints (recipient_id, endpoint) VALUES (?1, ?2)",
+            params![recipient_id, endpoint],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn consignment_endpoint(
+        &self,
+        recipient_id: &str,
+    ) -> Result<Option<String>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let endpoint = conn
+            .query_row(
+                "SELECT endpoint FROM consignment_endpoints WHERE recipient_id = ?1",
+                params![recipient_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(endpoint)
+    }
+
+    /// Get the incoming transfers the node has failed, by recipient ID, with the failure reason
+    pub(crate) fn failed_incoming_transfers(&self) -> Result<HashMap<String, String>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT recipient_id, reason FROM failed_incoming_transfers")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(rows)
+    }
+
+    fn payments(&self, inbound: bool) -> Result<Vec<([u8; 32], PaymentInfo)>, APIError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, data FROM payments WHERE inbound = ?1")?;
+        let rows = stmt
+            .query_map(params![inbound], |row| {
+                Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(id, data)| decode_payment(id, data))
+            .collect()
+    }
+}
+
+fn spending_limits(conn: &Connection) -> Result<SpendingLimits, APIError> {
+    let mut stmt = conn.prepare("SELECT asset_id, max_amount FROM spending_limits")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut limits = SpendingLimits::default();
+    for (key, max_amount) in rows {
+        if key == MSAT_LIMIT_KEY {
+            limits.max_msat = Some(max_amount);
+        } else {
+            limits.max_asset_amounts.insert(key, max_amount);
+        }
+    }
+    Ok(limits)
+}
+
+fn spending_usage(conn: &Connection, since: u64) -> Result<SpendingUsage, APIError> {
+    let mut stmt = conn
+        .prepare("SELECT asset_id, amt_msat, asset_amount FROM spending WHERE created_at > ?1")?;
+    let rows = stmt
+        .query_map(params![since], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, u64>(1)?,
+                row.get::<_, u64>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut usage = SpendingUsage::default();
+    for (asset_id, amt_msat, asset_amount) in rows {
+        usage.msat = usage.msat.saturating_add(amt_msat);
+        if let Some(asset_id) = asset_id {
+            let used = usage.asset_amounts.entry(asset_id).or_insert(0);
+            *used = used.saturating_add(asset_amount);
+        }
+    }
+    Ok(usage)
+}
+
+fn check_spending_limits(
+    conn: &Connection,
+    amt_msat: u64,
+    asset: Option<(&str, u64)>,
+    now: u64,
+) -> Result<(), APIError> {
+    let limits = spending_limits(conn)?;
+    let usage = spending_usage(conn, now.saturating_sub(SPENDING_WINDOW_SECS))?;
+    if let Some(max_msat) = limits.max_msat {
+        let remaining = max_msat.saturating_sub(usage.msat);
+        if amt_msat > remaining {
+            return Err(APIError::SpendingLimitExceeded(format!(
+                "remaining allowance is {remaining} msat"
+            )));
+        }
+    }
+    if let Some((asset_id, amount)) = asset {
+        if let Some(max_amount) = limits.max_asset_amounts.get(asset_id) {
+            let used = usage.asset_amounts.get(asset_id).copied().unwrap_or(0);
+            let remaining = max_amount.saturating_sub(used);
+            if amount > remaining {
+                return Err(APIError::SpendingLimitExceeded(format!(
+                    "remaining allowance for asset {asset_id} is {remaining}"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn htlc_status_code(status: HTLCStatus) -> u8 {
+    match status {
+        HTLCStatus::Pending => 0,
+        HTLCStatus::Succeeded => 1,
+        HTLCStatus::Failed => 2,
+    }
+}
+
+fn invoice_closure_code(closure: InvoiceClosure) -> u8 {
+    match closure {
+        InvoiceClosure::Expired => 0,
+        InvoiceClosure::Cancelled => 1,
+    }
+}
+
+fn channel_fee_kind_code(kind: ChannelFeeKind) -> u8 {
+    match kind {
+        ChannelFeeKind::Funding => 0,
+        ChannelFeeKind::Close => 1,
+        ChannelFeeKind::ForceClose => 2,
+    }
+}
+
+fn channel_fee_kind_from_code(code: u8) -> Result<ChannelFeeKind, APIError> {
+    match code {
+        0 => Ok(ChannelFeeKind::Funding),
+        1 => Ok(ChannelFeeKind::Close),
+        2 => Ok(ChannelFeeKind::ForceClose),
+        _ => {
+            tracing::error!("unknown channel fee kind {code}");
+            Err(APIError::Unexpected)
+        }
+    }
+}
+
+fn wallet_intent_kind_code(kind: WalletIntentKind) -> u8 {
+    match kind {
+        WalletIntentKind::CreateUtxos => 0,
+        WalletIntentKind::SendAsset => 1,
+    }
+}
+
+fn wallet_intent_kind_from_code(code: u8) -> Result<WalletIntentKind, APIError> {
+    match code {
+        0 => Ok(WalletIntentKind::CreateUtxos),
+        1 => Ok(WalletIntentKind::SendAsset),
+        _ => {
+            tracing::error!("unknown wallet intent kind {code}");
+            Err(APIError::Unexpected)
+        }
+    }
+}
+
+fn swap_direction_code(direction: SwapDirection) -> u8 {
+    match direction {
+        SwapDirection::IntoChannel => 0,
+        SwapDirection::OutOfChannel => 1,
+    }
+}
+
+fn swap_direction_from_code(code: u8) -> Result<SwapDirection, APIError> {
+    match code {
+        0 => Ok(SwapDirection::IntoChannel),
+        1 => Ok(SwapDirection::OutOfChannel),
+        _ => {
+            tracing::error!("unknown swap direction {code}");
+            Err(APIError::Unexpected)
+        }
+    }
+}
+
+fn swap_status_code(status: SwapStatus) -> u8 {
+    match status {
+        SwapStatus::Pending => 0,
+        SwapStatus::Succeeded => 1,
+        SwapStatus::Failed => 2,
+    }
+}
+
+fn swap_status_from_code(code: u8) -> Result<SwapStatus, APIError> {
+    match code {
+        0 => Ok(SwapStatus::Pending),
+        1 => Ok(SwapStatus::Succeeded),
+        2 => Ok(SwapStatus::Failed),
+        _ => {
+            tracing::error!("unknown swap status {code}");
+            Err(APIError::Unexpected)
+        }
+    }
+}
+
+fn utxo_reservation_kind_code(reservation: &UtxoReservation) -> u8 {
+    match reservation {
+        UtxoReservation::ChannelOpen { .. } => 0,
+        UtxoReservation::AssetTransfer { .. } => 1,
+        UtxoReservation::UtxoCreation { .. } => 2,
+    }
+}
+
+fn utxo_reservation_from_code(
+    code: u8,
+    txid: &str,
+    channel_id: Option<String>,
+) -> Result<UtxoReservation, APIError> {
+    match (code, channel_id) {
+        (0, Some(channel_id)) => Ok(UtxoReservation::ChannelOpen { channel_id }),
+        (1, _) => Ok(UtxoReservation::AssetTransfer {
+            txid: txid.to_string(),
+        }),
+        (2, _) => Ok(UtxoReservation::UtxoCreation {
+            txid: txid.to_string(),
+        }),
+        _ => {
+            tracing::error!("invalid UTXO reservation kind {code}");
+            Err(APIError::Unexpected)
+        }
+    }
+}
+
+/// Insert a payment, replacing the existing record unless asked to keep it
+fn insert_payment(
+    conn: &Connection,
+    id: &[u8; 32],
+    inbound: bool,
+    info: &PaymentInfo,
+    keep_existing: bool,
+) -> Result<(), APIError> {
+    let on_conflict = if keep_existing {
+        "DO NOTHING"
+    } else {
+        "DO UPDATE SET status = excluded.status, created_at = excluded.created_at,
+            settled_at = excluded.settled_at, label = excluded.label,
+            expires_at = excluded.expires_at, closure = excluded.closure, data = excluded.data"
+    };
+    conn.execute(
+        &format!(
+            "INSERT INTO payments (id, inbound, status, created_at, settled_at, label, expires_at,
+                closure, data)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT (id, inbound) {on_conflict}"
+        ),
+        params![
+            &id[..],
+            inbound,
+            htlc_status_code(info.status),
+            info.created_at,
+            info.settled_at,
+            info.label,
+            info.expires_at,
+            info.closure.map(invoice_closure_code),
+            info.encode()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Fill the columns the payments get filtered on from the payments encoded before they existed
+fn fill_payment_filter_columns(conn: &Connection) -> Result<(), APIError> {
+    let mut stmt = conn.prepare("SELECT id, inbound, data FROM payments")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, Vec<u8>>(0)?,
+                row.get::<_, bool>(1)?,
+                row.get::<_, Vec<u8>>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (id, inbound, data) in rows {
+        let (id, info) = decode_payment(id, data)?;
+        conn.execute(
+            "UPDATE payments SET label = ?1, expires_at = ?2, closure = ?3
+            WHERE id = ?4 AND inbound = ?5",
+            params![
+                info.label,
+                info.expires_at,
+                info.closure.map(invoice_closure_code),
+                &id[..],
+                inbound
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn decode_payment(id: Vec<u8>, data: Vec<u8>) -> Result<([u8; 32], PaymentInfo), APIError> {
+    let id: [u8; 32] = id.try_into().map_err(|_| APIError::Unexpected)?;
+    let info = PaymentInfo::read(&mut &data[..]).map_err(|e| {
+        tracing::error!("cannot decode payment: {e:?}");
+        APIError::Unexpected
+    })?;
+    Ok((id, info))
+}
+
+fn read_legacy_file<T: Readable>(path: &Path) -> Result<T, APIError> {
+    let file = File::open(path)?;
+    T::read(&mut BufReader::new(file)).map_err(|e| {
+        tracing::error!("cannot decode {path:?}: {e:?}");
+        APIError::Unexpected
+    })
+}
+
+fn mark_migrated(path: &Path) -> Result<(), APIError> {
+    let mut migrated = path.as_os_str().to_owned();
+    migrated.push(format!(".{MIGRATED_SUFFIX}"));
+    fs::rename(path, migrated)?;
+    tracing::info!("imported {path:?} into the database");
+    Ok(())
+}
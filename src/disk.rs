@@ -3,7 +3,7 @@ use bitcoin::Network;
 use chrono::Utc;
 use lightning::routing::scoring::{ProbabilisticScorer, ProbabilisticScoringDecayParameters};
 use lightning::util::logger::{Logger, Record};
-use lightning::util::ser::{Readable, ReadableArgs, Writer};
+use lightning::util::ser::{ReadableArgs, Writer};
 use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
@@ -13,13 +13,19 @@ use std::path::Path;
 use std::sync::Arc;
 
 use crate::error::APIError;
-use crate::ldk::{InboundPaymentInfoStorage, NetworkGraph, OutboundPaymentInfoStorage};
+use crate::ldk::NetworkGraph;
 use crate::utils::{parse_peer_info, LOGS_DIR};
 
 pub(crate) const LDK_LOGS_FILE: &str = "logs.txt";
 
+// legacy storage files, imported into the database at unlock
 pub(crate) const INBOUND_PAYMENTS_FNAME: &str = "inbound_payments";
 pub(crate) const OUTBOUND_PAYMENTS_FNAME: &str = "outbound_payments";
+pub(crate) const RGB_INVOICE_LABELS_FNAME: &str = "rgb_invoice_labels";
+
+pub(crate) const CHANNEL_MANAGER_FNAME: &str = "manager";
+pub(crate) const NETWORK_GRAPH_FNAME: &str = "network_graph";
+pub(crate) const SCORER_FNAME: &str = "scorer";
 
 pub(crate) const PENDING_SPENDABLE_OUTPUT_DIR: &str = "pending_spendable_outputs";
 
@@ -50,13 +56,14 @@ impl Logger for FilesystemLogger {
             raw_log
         );
         let logs_file_path = format!("{}/{LDK_LOGS_FILE}", self.data_dir.clone());
-        fs::OpenOptions::new()
+        // losing log lines, e.g. when the disk is full, must not stop the node
+        if let Ok(mut file) = fs::OpenOptions::new()
             .create(true)
             .append(true)
             .open(logs_file_path)
-            .unwrap()
-            .write_all(log.as_bytes())
-            .unwrap();
+        {
+            let _ = file.write_all(log.as_bytes());
+        }
     }
 }
 pub(crate) fn persist_channel_peer(path: &Path, peer_info: &str) -> std::io::Result<()> {
@@ -100,28 +107,6 @@ pub(crate) fn read_network(
     NetworkGraph::new(network, logger)
 }
 
-pub(crate) fn read_inbound_payment_info(path: &Path) -> InboundPaymentInfoStorage {
-    if let Ok(file) = File::open(path) {
-        if let Ok(info) = InboundPaymentInfoStorage::read(&mut BufReader::new(file)) {
-            return info;
-        }
-    }
-    InboundPaymentInfoStorage {
-        payments: HashMap::new(),
-    }
-}
-
-pub(crate) fn read_outbound_payment_info(path: &Path) -> OutboundPaymentInfoStorage {
-    if let Ok(file) = File::open(path) {
-        if let Ok(info) = OutboundPaymentInfoStorage::read(&mut BufReader::new(file)) {
-            return info;
-        }
-    }
-    OutboundPaymentInfoStorage {
-        payments: HashMap::new(),
-    }
-}
-
 pub(crate) fn read_scorer(
     path: &Path,
     graph: Arc<NetworkGraph>,
@@ -0,0 +1,218 @@
+use bitcoin::hashes::hex::ToHex;
+use lightning::chain::chaininterface::{ConfirmationTarget, FeeEstimator};
+use lightning::ln::channelmanager::ChannelDetails;
+use lightning::rgb_utils::{get_rgb_channel_info_path, parse_rgb_channel_info};
+use std::path::PathBuf;
+
+use crate::database::ChannelFeeRate;
+use crate::error::APIError;
+use crate::routes::{DryRunResult, OutpointAllocation};
+use crate::utils::{StaticState, UnlockedAppState};
+
+// estimated sizes of the transactions built by the wallet, counting P2WPKH inputs and outputs no
+// smaller than a P2WSH or an OP_RETURN commitment
+const TX_BASE_VBYTES: u64 = 11;
+const TX_INPUT_VBYTES: u64 = 68;
+const TX_OUTPUT_VBYTES: u64 = 43;
+/// Estimated size of a cooperative closing transaction (2-of-2 funding input, two outputs)
+pub(crate) const CLOSING_TX_VBYTES: u64 = TX_BASE_VBYTES + 105 + 2 * TX_OUTPUT_VBYTES;
+// weight of an anchor commitment transaction without HTLCs, of a legacy one and of each of their
+// HTLC outputs, and value of the two anchor outputs (BOLT 3)
+const COMMITMENT_TX_BASE_WEIGHT: u64 = 1124;
+const LEGACY_COMMITMENT_TX_BASE_WEIGHT: u64 = 724;
+const COMMITMENT_TX_HTLC_WEIGHT: u64 = 172;
+const ANCHOR_OUTPUT_SAT: u64 = 330;
+/// HTLCs the initial commitment transaction of a new channel must leave room for (LDK's
+/// MIN_AFFORDABLE_HTLC_COUNT)
+const COMMITMENT_TX_MIN_AFFORDABLE_HTLCS: u64 = 4;
+/// Lowest channel reserve a peer following the LDK defaults asks for
+const MIN_CHANNEL_RESERVE_SAT: u64 = 1000;
+/// Share of the capacity a peer following the LDK defaults asks to keep as channel reserve
+const CHANNEL_RESERVE_PROPORTIONAL_MILLIONTHS: u64 = 10_000;
+
+/// Largest push_msat a channel of the given capacity can currently be opened with
+///
+/// After the push the opener still has to pay the initial commitment transaction, with its anchor
+/// outputs if any, and keep the reserve the peer asks for, otherwise the peer rejects the channel.
+pub(crate) fn max_push_msat(
+    static_state: &StaticState,
+    capacity_sat: u64,
+    with_anchors: bool,
+) -> u64 {
+    // legacy commitment transactions can't be bumped, so they pay a higher fee rate upfront
+    let (confirmation_target, base_weight, anchors_sat) = if with_anchors {
+        (
+            ConfirmationTarget::AnchorChannelFee,
+            COMMITMENT_TX_BASE_WEIGHT,
+            2 * ANCHOR_OUTPUT_SAT,
+        )
+    } else {
+        (
+            ConfirmationTarget::NonAnchorChannelFee,
+            LEGACY_COMMITMENT_TX_BASE_WEIGHT,
+            0,
+        )
+    };
+    let feerate = static_state
+        .bitcoind_client
+        .get_est_sat_per_1000_weight(confirmation_target) as u64;
+    let commitment_weight =
+        base_weight + COMMITMENT_TX_MIN_AFFORDABLE_HTLCS * COMMITMENT_TX_HTLC_WEIGHT;
+    let commitment_fee_sat = feerate * commitment_weight / 1000 + anchors_sat;
+    let reserve_sat = (capacity_sat * CHANNEL_RESERVE_PROPORTIONAL_MILLIONTHS / 1_000_000)
+        .max(MIN_CHANNEL_RESERVE_SAT);
+    capacity_sat.saturating_sub(commitment_fee_sat + reserve_sat) * 1000
+}
+
+fn estimate_wallet_tx_fee(inputs: usize, outputs: u64, fee_rate: f32) -> u64 {
+    let vbytes = TX_BASE_VBYTES + inputs as u64 * TX_INPUT_VBYTES + outputs * TX_OUTPUT_VBYTES;
+    (vbytes as f32 * fee_rate).ceil() as u64
+}
+
+/// Estimate the transaction sending an asset amount and the given BTC outputs, selecting the
+/// allocations to spend without touching the wallet
+///
+/// The UTXOs holding the asset are spent in the wallet order, BTC-only UTXOs are then added
+/// (uncolorable ones and bigger ones first) until the outputs and the fee are covered. This only
+/// approximates the rgb-lib selection, which can't be steered.
+pub(crate) fn simulate_rgb_send(
+    unlocked_state: &UnlockedAppState,
+    asset_id: &str,
+    amount: u64,
+    outputs: &[u64],
+    fee_rate: f32,
+) -> Result<DryRunResult, APIError> {
+    // UTXOs with pending allocations can't be spent yet
+    let unspents: Vec<_> = unlocked_state
+        .rgb_list_unspents()?
+        .into_iter()
+        .filter(|u| u.rgb_allocations.iter().all(|a| a.settled))
+        .collect();
+
+    let mut inputs = vec![];
+    let mut consumed_allocations = vec![];
+    let mut input_sat = 0;
+    let mut asset_input = 0;
+    for unspent in &unspents {
+        if asset_input >= amount {
+            break;
+        }
+        let asset_amount: u64 = unspent
+            .rgb_allocations
+            .iter()
+            .filter(|a| a.asset_id.as_deref() == Some(asset_id))
+            .map(|a| a.amount)
+            .sum();
+        if asset_amount == 0 {
+            continue;
+        }
+        let outpoint = unspent.utxo.outpoint.to_string();
+        consumed_allocations.push(OutpointAllocation {
+            outpoint: outpoint.clone(),
+            amount: asset_amount,
+        });
+        inputs.push(outpoint);
+        input_sat += unspent.utxo.btc_amount;
+        asset_input += asset_amount;
+    }
+    if asset_input < amount {
+        return Err(APIError::InsufficientAssets(asset_input));
+    }
+
+    let mut btc_unspents: Vec<_> = unspents
+        .iter()
+        .filter(|u| u.rgb_allocations.is_empty())
+        .collect();
+    btc_unspents.sort_by_key(|u| (u.utxo.colorable, std::cmp::Reverse(u.utxo.btc_amount)));
+    let mut btc_unspents = btc_unspents.into_iter();
+
+    let output_sat: u64 = outputs.iter().sum();
+    // the requested outputs, the RGB commitment and the change
+    let output_count = outputs.len() as u64 + 2;
+    loop {
+        let fee_sat = estimate_wallet_tx_fee(inputs.len(), output_count, fee_rate);
+        if input_sat >= output_sat + fee_sat {
+            return Ok(DryRunResult {
+                inputs,
+                consumed_allocations,
+                fee_sat,
+                btc_change_sat: input_sat - output_sat - fee_sat,
+                asset_change: Some(asset_input - amount),
+            });
+        }
+        match btc_unspents.next() {
+            Some(unspent) => {
+                inputs.push(unspent.utxo.outpoint.to_string());
+                input_sat += unspent.utxo.btc_amount;
+            }
+            None => {
+                return Err(APIError::InsufficientFunds(
+                    output_sat + fee_sat - input_sat,
+                ))
+            }
+        }
+    }
+}
+
+/// Estimate the closing of a channel: its funding outpoint, the fee paid by the node (only the
+/// funder pays it) and the BTC and asset amounts the node gets back
+///
+/// For force-closes only the commitment transaction is accounted for, not the transactions
+/// later claiming its outputs.
+pub(crate) fn simulate_channel_close(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    chan_info: &ChannelDetails,
+    force: bool,
+    fee_rate: Option<&ChannelFeeRate>,
+) -> DryRunResult {
+    let fee_sat = if !chan_info.is_outbound || chan_info.funding_txo.is_none() {
+        0
+    } else if force {
+        let htlcs = unlocked_state
+            .pending_htlcs()
+            .iter()
+            .filter(|h| h.channel_id == chan_info.channel_id && !h.dust)
+            .count() as u64;
+        let weight = COMMITMENT_TX_BASE_WEIGHT + htlcs * COMMITMENT_TX_HTLC_WEIGHT;
+        let feerate = chan_info.feerate_sat_per_1000_weight.unwrap_or(0) as u64;
+        feerate * weight / 1000 + 2 * ANCHOR_OUTPUT_SAT
+    } else if let Some(fee_rate) = fee_rate {
+        (fee_rate.fee_rate_sat_vb * CLOSING_TX_VBYTES as f32).ceil() as u64
+    } else {
+        let feerate = static_state
+            .bitcoind_client
+            .get_est_sat_per_1000_weight(ConfirmationTarget::NonAnchorChannelFee)
+            as u64;
+        feerate * CLOSING_TX_VBYTES * 4 / 1000
+    };
+
+    let mut inputs = vec![];
+    let mut consumed_allocations = vec![];
+    let mut asset_change = None;
+    if let Some(funding_txo) = chan_info.funding_txo {
+        let outpoint = format!("{}:{}", funding_txo.txid, funding_txo.index);
+        let info_file_path = get_rgb_channel_info_path(
+            &chan_info.channel_id.to_hex(),
+            &PathBuf::from(&static_state.ldk_data_dir),
+            false,
+        );
+        if info_file_path.exists() {
+            let rgb_info = parse_rgb_channel_info(&info_file_path);
+            consumed_allocations.push(OutpointAllocation {
+                outpoint: outpoint.clone(),
+                amount: rgb_info.local_rgb_amount + rgb_info.remote_rgb_amount,
+            });
+            asset_change = Some(rgb_info.local_rgb_amount);
+        }
+        inputs.push(outpoint);
+    }
+
+    DryRunResult {
+        inputs,
+        consumed_allocations,
+        fee_sat,
+        btc_change_sat: (chan_info.balance_msat / 1000).saturating_sub(fee_sat),
+        asset_change,
+    }
+}
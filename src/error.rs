@@ -1,5 +1,5 @@
 use axum::{
-    extract::rejection::JsonRejection,
+    extract::rejection::{JsonRejection, QueryRejection},
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
@@ -16,15 +16,42 @@ pub enum APIError {
     #[error("Node has already been initialized")]
     AlreadyInitialized,
 
-    #[error("Anchor outputs are required for RGB channels")]
-    AnchorsRequired,
+    #[error("Amount below the dust limit of colored HTLCs: {0}")]
+    AmountBelowDustLimit(String),
+
+    #[error("Amount exceeds the issued supply of the asset ({0})")]
+    AmountExceedsSupply(u64),
+
+    #[error("Automatic unlock failed: {0}")]
+    AutoUnlockFailed(String),
+
+    #[error("Blinded UTXO already used by a previous send (hint: set force to send anyway)")]
+    BlindedUtxoAlreadyUsed,
 
     #[error("Cannot call other APIs while node is changing state")]
     ChangingState,
 
+    #[error("Corrupted data directory: {0}")]
+    CorruptedDataDir(String),
+
+    #[error("Data directory in use by another node: {0}")]
+    DataDirInUse(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("Payment already pending or succeeded")]
+    DuplicatePayment,
+
+    #[error("Ticker already registered to other assets: {0}")]
+    DuplicateTicker(String),
+
     #[error("Failed closing channel: {0}")]
     FailedClosingChannel(String),
 
+    #[error("Failed to fetch contract: {0}")]
+    FailedFetchingContract(String),
+
     #[error("Failed to create invoice: {0}")]
     FailedInvoiceCreation(String),
 
@@ -46,24 +73,57 @@ pub enum APIError {
     #[error("Failed to disconnect to peer: {0}")]
     FailedPeerDisconnection(String),
 
+    #[error("Failed to query the indexer: {0}")]
+    FailedQueryingIndexer(String),
+
+    #[error("Regtest bitcoind call failed: {0}")]
+    FailedRegtestCall(String),
+
     #[error("Failed to send onion message: {0}")]
     FailedSendingOnionMessage(String),
 
     #[error("Failed to start LDK: {0}")]
     FailedStartingLDK(String),
 
+    #[error("Fee rate too high: {0}")]
+    FeeRateTooHigh(String),
+
+    #[error("Not enough asset balance, available: {0}, reserved by pending payments: {1}")]
+    InsufficientAssetBalance(u64, u64),
+
     #[error("Not enough assets, available: {0}")]
     InsufficientAssets(u64),
 
     #[error("Not enough funds, call getaddress and send {0} satoshis")]
     InsufficientFunds(u64),
 
+    #[error("Not enough funds to create {requested} UTXOs, can create at most {possible}")]
+    InsufficientFundsForUtxos { requested: u8, possible: u8 },
+
+    #[error("Not enough inbound liquidity, can receive at most {0}")]
+    InsufficientInboundLiquidity(String),
+
+    #[error("Not enough outbound asset liquidity, can send at most {0}")]
+    InsufficientOutboundAssetLiquidity(u64),
+
+    #[error("Not enough outbound liquidity, can send at most {0}")]
+    InsufficientOutboundLiquidity(String),
+
+    #[error("Invalid address request: {0}")]
+    InvalidAddressRequest(String),
+
     #[error("Invalid amount: {0}")]
     InvalidAmount(String),
 
+    #[error("Invalid asset destination: {0}")]
+    InvalidAssetDestination(String),
+
     #[error("Invalid asset ID: {0}")]
     InvalidAssetID(String),
 
+    #[error("Invalid asset import: {0}")]
+    InvalidAssetImport(String),
+
     #[error("Invalid backup path")]
     InvalidBackupPath,
 
@@ -73,24 +133,60 @@ pub enum APIError {
     #[error("Invalid channel ID")]
     InvalidChannelID,
 
+    #[error("Invalid close timeout: {0}")]
+    InvalidCloseTimeout(String),
+
+    #[error("Invalid CLTV expiry delta: {0}")]
+    InvalidCltvExpiryDelta(String),
+
+    #[error("Invalid consignment: {0}")]
+    InvalidConsignment(String),
+
+    #[error("Invalid custom message: {0}")]
+    InvalidCustomMessage(String),
+
     #[error("Invalid fee rate: {0}")]
     InvalidFeeRate(String),
 
     #[error("Invalid invoice: {0}")]
     InvalidInvoice(String),
 
+    #[error("Invalid label: {0}")]
+    InvalidLabel(String),
+
+    #[error("Invalid min confirmations: {0}")]
+    InvalidMinConfirmations(String),
+
     #[error("Invalid name: {0}")]
     InvalidName(String),
 
+    #[error("Invalid note: {0}")]
+    InvalidNote(String),
+
+    #[error("Invalid node alias: {0}")]
+    InvalidNodeAlias(String),
+
     #[error("Invalid node IDs: {0}")]
     InvalidNodeIds(String),
 
     #[error("Invalid onion data: {0}")]
     InvalidOnionData(String),
 
+    #[error("Invalid pagination: {0}")]
+    InvalidPagination(String),
+
     #[error("Invalid password: {0}")]
     InvalidPassword(String),
 
+    #[error("Invalid payment hash: {0}")]
+    InvalidPaymentHash(String),
+
+    #[error("Invalid payment policy: {0}")]
+    InvalidPaymentPolicy(String),
+
+    #[error("Invalid payment request: {0}")]
+    InvalidPaymentRequest(String),
+
     #[error("Invalid peer info: {0}")]
     InvalidPeerInfo(String),
 
@@ -100,6 +196,12 @@ pub enum APIError {
     #[error("Invalid pubkey")]
     InvalidPubkey,
 
+    #[error("Invalid shutdown request: {0}")]
+    InvalidShutdownRequest(String),
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
     #[error("Invalid ticker: {0}")]
     InvalidTicker(String),
 
@@ -109,9 +211,21 @@ pub enum APIError {
     #[error("Invalid transport endpoints: {0}")]
     InvalidTransportEndpoints(String),
 
+    #[error("Invalid webhook: {0}")]
+    InvalidWebhook(String),
+
+    #[error("Invoice cannot be cancelled: {0}")]
+    InvoiceNotCancellable(String),
+
     #[error("IO error: {0}")]
     IO(#[from] std::io::Error),
 
+    #[error("Job has been cancelled")]
+    JobCancelled,
+
+    #[error("Job cannot be cancelled: {0}")]
+    JobNotCancellable(String),
+
     #[error(transparent)]
     JsonExtractorRejection(#[from] JsonRejection),
 
@@ -121,33 +235,132 @@ pub enum APIError {
     #[error("No uncolored UTXOs are available (hint: call createutxos)")]
     NoAvailableUtxos,
 
+    #[error("The node has no public channels to announce")]
+    NoPublicChannels,
+
+    #[error("The node has been stopped (hint: call unlock)")]
+    NodeStopped,
+
     #[error("Wallet has not been initialized (hint: call init)")]
     NotInitialized,
 
+    #[error("Fewer than two allocations of the asset can be consolidated")]
+    NothingToConsolidate,
+
+    #[error("On-chain fallback unavailable: {0}")]
+    OnchainFallbackUnavailable(String),
+
     #[error("Output below the dust limit")]
     OutputBelowDustLimit,
 
+    #[error("Payment cannot be abandoned: {0}")]
+    PaymentNotAbandonable(String),
+
+    #[error("Cannot delete a pending payment")]
+    PaymentPending,
+
+    #[error("Peer {0} is banned (hint: call unbanpeer)")]
+    PeerBanned(String),
+
+    #[error("Peer {0} does not support RGB channels")]
+    PeerDoesNotSupportRgbChannels(String),
+
+    #[error("HTLC exposure limit reached: {0}")]
+    PeerExposureLimit(String),
+
+    #[error("Node has open channels with peer {0} (hint: set force to ban it anyway)")]
+    PeerHasChannels(String),
+
+    #[error("Peer {0} is not connected")]
+    PeerNotConnected(String),
+
+    #[error("The API token doesn't allow this operation")]
+    PermissionDenied,
+
+    #[error("Node degraded, new payments and channel updates are refused: {0}")]
+    PersistenceFailed(String),
+
+    #[error("Payment policy violation: destination {0} is not allowed")]
+    PolicyViolation(String),
+
     #[error("Proxy error: {0}")]
     Proxy(#[from] reqwest::Error),
 
+    #[error("Push amount leaves no room for the reserve and the commitment fee, max: {0} msat")]
+    PushAmountTooHigh(u64),
+
+    #[error(transparent)]
+    QueryExtractorRejection(#[from] QueryRejection),
+
     #[error("Recipient ID already used")]
     RecipientIDAlreadyUsed,
 
+    #[error("Not allowed while the node is in recovery mode")]
+    RecoveryMode,
+
+    #[error("Only available on regtest")]
+    RegtestOnly,
+
+    #[error("Request body too large, the limit is {0} bytes")]
+    RequestBodyTooLarge(usize),
+
+    #[error("Spending limit exceeded: {0}")]
+    SpendingLimitExceeded(String),
+
+    #[error("Swap not possible: {0}")]
+    SwapNotPossible(String),
+
+    #[error("Too many requests, retry later")]
+    TooManyRequests,
+
+    #[error("Missing or invalid API token")]
+    Unauthorized,
+
     #[error("Unexpected error")]
     Unexpected,
 
+    #[error("Peer is not banned")]
+    UnknownBan,
+
+    #[error("Unknown channel")]
+    UnknownChannel,
+
+    #[error("Unknown channel request")]
+    UnknownChannelRequest,
+
     #[error("Unknown RGB contract ID")]
     UnknownContractId,
 
+    #[error("Unknown job")]
+    UnknownJob,
+
     #[error("Unknown LN invoice")]
     UnknownLNInvoice,
 
+    #[error("Unknown node")]
+    UnknownNode,
+
+    #[error("Unknown payment")]
+    UnknownPayment,
+
+    #[error("Unknown recipient ID")]
+    UnknownRecipientID,
+
+    #[error("Unknown webhook")]
+    UnknownWebhook,
+
     #[error("Node is unlocked (hint: call lock)")]
     UnlockedNode,
 
     #[error("The provided backup has an unsupported version: {version}")]
     UnsupportedBackupVersion { version: String },
 
+    #[error("Unsupported data version: found {found}, supported {supported}")]
+    UnsupportedDataVersion { found: String, supported: String },
+
+    #[error("Cannot prune monitors while {0} spendable outputs are waiting to be swept")]
+    UnsweptOutputs(usize),
+
     #[error("The provided password is incorrect")]
     WrongPassword,
 }
@@ -158,7 +371,13 @@ impl IntoResponse for APIError {
             APIError::JsonExtractorRejection(json_rejection) => {
                 (json_rejection.status(), json_rejection.body_text())
             }
-            APIError::FailedClosingChannel(_)
+            APIError::QueryExtractorRejection(query_rejection) => {
+                (query_rejection.status(), query_rejection.body_text())
+            }
+            APIError::CorruptedDataDir(_)
+            | APIError::Database(_)
+            | APIError::FailedClosingChannel(_)
+            | APIError::FailedFetchingContract(_)
             | APIError::FailedInvoiceCreation(_)
             | APIError::FailedIssuingAsset(_)
             | APIError::FailedKeysCreation(_, _)
@@ -166,46 +385,114 @@ impl IntoResponse for APIError {
             | APIError::FailedOpenChannel(_)
             | APIError::FailedPeerConnection
             | APIError::FailedPeerDisconnection(_)
+            | APIError::FailedQueryingIndexer(_)
+            | APIError::FailedRegtestCall(_)
             | APIError::FailedSendingOnionMessage(_)
             | APIError::FailedStartingLDK(_)
             | APIError::IO(_)
             | APIError::Proxy(_)
             | APIError::Unexpected => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-            APIError::AnchorsRequired
+            APIError::AmountBelowDustLimit(_)
+            | APIError::AmountExceedsSupply(_)
+            | APIError::InvalidAddressRequest(_)
             | APIError::InvalidAmount(_)
+            | APIError::InvalidAssetDestination(_)
             | APIError::InvalidAssetID(_)
+            | APIError::InvalidAssetImport(_)
             | APIError::InvalidBackupPath
             | APIError::InvalidBlindedUTXO(_)
             | APIError::InvalidChannelID
+            | APIError::InvalidCloseTimeout(_)
+            | APIError::InvalidCltvExpiryDelta(_)
+            | APIError::InvalidConsignment(_)
+            | APIError::InvalidCustomMessage(_)
             | APIError::InvalidFeeRate(_)
             | APIError::InvalidInvoice(_)
+            | APIError::InvalidLabel(_)
+            | APIError::InvalidMinConfirmations(_)
             | APIError::InvalidName(_)
+            | APIError::InvalidNote(_)
+            | APIError::InvalidNodeAlias(_)
             | APIError::InvalidNodeIds(_)
             | APIError::InvalidOnionData(_)
+            | APIError::InvalidPagination(_)
             | APIError::InvalidPassword(_)
+            | APIError::InvalidPaymentHash(_)
+            | APIError::InvalidPaymentPolicy(_)
+            | APIError::InvalidPaymentRequest(_)
             | APIError::InvalidPeerInfo(_)
             | APIError::InvalidPrecision(_)
             | APIError::InvalidPubkey
+            | APIError::InvalidShutdownRequest(_)
+            | APIError::InvalidSignature(_)
             | APIError::InvalidTicker(_)
             | APIError::InvalidTlvType(_)
             | APIError::InvalidTransportEndpoints(_)
+            | APIError::InvalidWebhook(_)
+            | APIError::OnchainFallbackUnavailable(_)
             | APIError::OutputBelowDustLimit
-            | APIError::UnsupportedBackupVersion { .. } => {
+            | APIError::PushAmountTooHigh(_)
+            | APIError::UnsupportedBackupVersion { .. }
+            | APIError::UnsupportedDataVersion { .. } => {
                 (StatusCode::BAD_REQUEST, self.to_string())
             }
-            APIError::WrongPassword => (StatusCode::UNAUTHORIZED, self.to_string()),
+            APIError::Unauthorized | APIError::WrongPassword => {
+                (StatusCode::UNAUTHORIZED, self.to_string())
+            }
+            APIError::RequestBodyTooLarge(_) => (StatusCode::PAYLOAD_TOO_LARGE, self.to_string()),
+            APIError::TooManyRequests => (StatusCode::TOO_MANY_REQUESTS, self.to_string()),
+            APIError::NodeStopped => (StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
             APIError::AllocationsAlreadyAvailable
             | APIError::AlreadyInitialized
+            | APIError::AutoUnlockFailed(_)
+            | APIError::BlindedUtxoAlreadyUsed
             | APIError::ChangingState
+            | APIError::DataDirInUse(_)
+            | APIError::DuplicatePayment
+            | APIError::DuplicateTicker(_)
+            | APIError::FeeRateTooHigh(_)
+            | APIError::InsufficientAssetBalance(_, _)
             | APIError::InsufficientAssets(_)
             | APIError::InsufficientFunds(_)
+            | APIError::InsufficientFundsForUtxos { .. }
+            | APIError::InsufficientInboundLiquidity(_)
+            | APIError::InsufficientOutboundAssetLiquidity(_)
+            | APIError::InsufficientOutboundLiquidity(_)
+            | APIError::InvoiceNotCancellable(_)
+            | APIError::JobCancelled
+            | APIError::JobNotCancellable(_)
             | APIError::LockedNode
             | APIError::NoAvailableUtxos
+            | APIError::NoPublicChannels
             | APIError::NotInitialized
+            | APIError::NothingToConsolidate
+            | APIError::PaymentNotAbandonable(_)
+            | APIError::PaymentPending
+            | APIError::PeerBanned(_)
+            | APIError::PeerDoesNotSupportRgbChannels(_)
+            | APIError::PeerExposureLimit(_)
+            | APIError::PeerHasChannels(_)
+            | APIError::PeerNotConnected(_)
+            | APIError::PermissionDenied
+            | APIError::PersistenceFailed(_)
+            | APIError::PolicyViolation(_)
             | APIError::RecipientIDAlreadyUsed
+            | APIError::RecoveryMode
+            | APIError::RegtestOnly
+            | APIError::SpendingLimitExceeded(_)
+            | APIError::SwapNotPossible(_)
+            | APIError::UnknownBan
+            | APIError::UnknownChannel
+            | APIError::UnknownChannelRequest
             | APIError::UnknownContractId
+            | APIError::UnknownJob
             | APIError::UnknownLNInvoice
-            | APIError::UnlockedNode => (StatusCode::FORBIDDEN, self.to_string()),
+            | APIError::UnknownNode
+            | APIError::UnknownPayment
+            | APIError::UnknownRecipientID
+            | APIError::UnknownWebhook
+            | APIError::UnlockedNode
+            | APIError::UnsweptOutputs(_) => (StatusCode::FORBIDDEN, self.to_string()),
         };
 
         let body = Json(json!({
@@ -220,21 +507,75 @@ impl IntoResponse for APIError {
 /// The error variants returned by the app
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
+    #[error("Data directory in use by another node: {0}")]
+    DataDirInUse(String),
+
     #[error("Failed to connect to bitcoind client: {0}")]
     FailedBitcoindConnection(String),
 
+    #[error("Failed to lock the data directory: {0}")]
+    FailedDataDirLock(String),
+
+    #[error("Failed to migrate the data directory: {0}")]
+    FailedDataDirMigration(String),
+
+    #[error("Invalid address gap limit: {0}")]
+    InvalidAddressGapLimit(String),
+
+    #[error("Invalid API tokens: {0}")]
+    InvalidApiTokens(String),
+
     #[error("Invalid announced listen addresses: {0}")]
     InvalidAnnouncedListenAddresses(String),
 
+    #[error("Invalid asset registry: {0}")]
+    InvalidAssetRegistry(String),
+
     #[error("Chain argument ({0}) didn't match bitcoind chain ({1})")]
     InvalidBitcoinNetwork(Network, String),
 
     #[error("Invalid bitcoind RPC info: {0}")]
     InvalidBitcoinRPCInfo(String),
 
+    #[error("Invalid channel handshake defaults: {0}")]
+    InvalidChannelHandshake(String),
+
+    #[error("Invalid channel request timeout: {0}")]
+    InvalidChannelRequestTimeout(String),
+
+    #[error("Invalid consolidation policy: {0}")]
+    InvalidConsolidationPolicy(String),
+
+    #[error("Invalid data directory migration: {0}")]
+    InvalidDataDirMigration(String),
+
+    #[error("Invalid HTLC force-close buffer: {0}")]
+    InvalidHtlcForceCloseBuffer(String),
+
+    #[error("Invalid min confirmations: {0}")]
+    InvalidMinConfirmations(String),
+
+    #[error("Invalid LSP policy: {0}")]
+    InvalidLspPolicy(String),
+
+    #[error("Invalid monitor mirror: {0}")]
+    InvalidMonitorMirror(String),
+
     #[error("Invalid node alias: {0}")]
     InvalidNodeAlias(String),
 
+    #[error("Invalid node color: {0}")]
+    InvalidNodeColor(String),
+
+    #[error("Invalid peer rate limits: {0}")]
+    InvalidPeerRateLimits(String),
+
+    #[error("Invalid proxy endpoints: {0}")]
+    InvalidProxyEndpoints(String),
+
+    #[error("Invalid unlock password: {0}")]
+    InvalidUnlockPassword(String),
+
     #[error("PoC does not support selected network")]
     UnsupportedBitcoinNetwork,
 }
@@ -0,0 +1,212 @@
+use bitcoin::OutPoint;
+use lightning::routing::gossip::{ChannelInfo, NodeId};
+use lightning::util::persist::{
+    KVStore, NETWORK_GRAPH_PERSISTENCE_KEY, NETWORK_GRAPH_PERSISTENCE_PRIMARY_NAMESPACE,
+    NETWORK_GRAPH_PERSISTENCE_SECONDARY_NAMESPACE,
+};
+use lightning::util::ser::Writeable;
+use lightning_block_sync::gossip::UtxoSource;
+use lightning_block_sync::{BlockData, BlockSource};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::error::APIError;
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+/// Channels not updated for this long are considered zombies and pruned
+const STALE_CHANNEL_AGE_SECS: u64 = 14 * 24 * 60 * 60;
+
+/// Channels of a successful payment path are kept for this long after the payment
+pub(crate) const HOT_CHANNEL_AGE_SECS: u64 = 14 * 24 * 60 * 60;
+
+/// Channels removed by a pruning of the network graph and the nodes left without channels
+#[derive(Debug, Default)]
+pub(crate) struct GraphPruneResult {
+    pub(crate) nodes_removed: usize,
+    pub(crate) channels_removed: usize,
+}
+
+/// Funding outpoints of the graph channels, looked up once as it takes fetching their block
+#[derive(Default)]
+pub(crate) struct GraphPruner {
+    funding_outpoints: Mutex<HashMap<u64, OutPoint>>,
+    /// Serializes the prunings, which look the funding outputs up without holding the graph
+    running: tokio::sync::Mutex<()>,
+}
+
+/// Last time a channel has been updated, or announced when never updated
+fn last_update(channel: &ChannelInfo) -> u64 {
+    [&channel.one_to_two, &channel.two_to_one]
+        .into_iter()
+        .flatten()
+        .map(|d| d.last_update as u64)
+        .max()
+        .unwrap_or(channel.announcement_received_time)
+}
+
+impl UnlockedAppState {
+    /// Write the network graph to the store, which the background processor doesn't do as it
+    /// doesn't prune the graph
+    pub(crate) fn persist_network_graph(&self) -> Result<(), APIError> {
+        self.persister.write(
+            NETWORK_GRAPH_PERSISTENCE_PRIMARY_NAMESPACE,
+            NETWORK_GRAPH_PERSISTENCE_SECONDARY_NAMESPACE,
+            NETWORK_GRAPH_PERSISTENCE_KEY,
+            &self.network_graph.encode(),
+        )?;
+        Ok(())
+    }
+
+    /// Channels that must never be pruned: the ones of the node and the ones it recently paid
+    /// through
+    fn protected_channels(&self) -> Result<HashSet<u64>, APIError> {
+        let mut protected = self
+            .database
+            .hot_channels(get_current_timestamp().saturating_sub(HOT_CHANNEL_AGE_SECS))?;
+        protected.extend(
+            self.channel_manager
+                .list_channels()
+                .iter()
+                .filter_map(|c| c.short_channel_id),
+        );
+        Ok(protected)
+    }
+
+    /// Whether the funding output of a graph channel is still unspent, None if it can't be told
+    async fn funding_unspent(&self, static_state: &StaticState, scid: u64) -> Option<bool> {
+        let rpc_client = &static_state.bitcoind_client.bitcoind_rpc_client;
+        let cached = self
+            .graph_pruner
+            .funding_outpoints
+            .lock()
+            .unwrap()
+            .get(&scid)
+            .copied();
+        let outpoint = match cached {
+            Some(outpoint) => outpoint,
+            None => {
+                let block_height = (scid >> 40) as u32;
+                let tx_index = ((scid >> 16) & 0xff_ffff) as usize;
+                let vout = (scid & 0xffff) as u32;
+                let block_hash = rpc_client
+                    .get_block_hash_by_height(block_height)
+                    .await
+                    .ok()?;
+                let BlockData::FullBlock(block) = rpc_client.get_block(&block_hash).await.ok()?
+                else {
+                    return None;
+                };
+                let outpoint = OutPoint {
+                    txid: block.txdata.get(tx_index)?.txid(),
+                    vout,
+                };
+                self.graph_pruner
+                    .funding_outpoints
+                    .lock()
+                    .unwrap()
+                    .insert(scid, outpoint);
+                outpoint
+            }
+        };
+        rpc_client.is_output_unspent(outpoint).await.ok()
+    }
+
+    /// Remove from the network graph the channels not updated for two weeks or whose funding
+    /// output has been spent, then the least recently updated ones until the graph fits in the
+    /// configured size
+    ///
+    /// The channels of the node and the ones of its recent successful payment paths are never
+    /// removed. Removed channels are not accepted again from gossip until the node restarts.
+    pub(crate) async fn prune_network_graph(
+        &self,
+        static_state: &StaticState,
+    ) -> Result<GraphPruneResult, APIError> {
+        let _running = self.graph_pruner.running.lock().await;
+        let protected = self.protected_channels()?;
+        let now = get_current_timestamp();
+
+        // channels by last update, oldest first
+        let mut candidates: Vec<(u64, u64)> = self
+            .network_graph
+            .read_only()
+            .channels()
+            .unordered_iter()
+            .filter(|(scid, _)| !protected.contains(scid))
+            .map(|(scid, channel)| (last_update(channel), *scid))
+            .collect();
+        candidates.sort_unstable();
+
+        let mut to_remove = vec![];
+        let mut kept = vec![];
+        for (last_update, scid) in candidates {
+            if last_update + STALE_CHANNEL_AGE_SECS < now
+                || self.funding_unspent(static_state, scid).await == Some(false)
+            {
+                to_remove.push(scid);
+            } else {
+                kept.push(scid);
+            }
+        }
+
+        let max_graph_bytes = static_state.max_graph_size_mb * 1024 * 1024;
+        let graph_bytes = self.network_graph.serialized_length() as u64;
+        let channel_count = self.network_graph.read_only().channels().len() as u64;
+        if graph_bytes > max_graph_bytes && channel_count > 0 {
+            let bytes_per_channel = (graph_bytes / channel_count).max(1);
+            let excess = ((graph_bytes - max_graph_bytes) / bytes_per_channel) as usize + 1;
+            let evicted = excess.saturating_sub(to_remove.len()).min(kept.len());
+            to_remove.extend_from_slice(&kept[..evicted]);
+        }
+
+        let mut nodes: HashSet<NodeId> = HashSet::new();
+        let mut result = GraphPruneResult::default();
+        for scid in to_remove {
+            let Some(channel) = self.network_graph.read_only().channel(scid).cloned() else {
+                continue;
+            };
+            self.network_graph.channel_failed_permanent(scid);
+            nodes.insert(channel.node_one);
+            nodes.insert(channel.node_two);
+            result.channels_removed += 1;
+        }
+        let graph = self.network_graph.read_only();
+        result.nodes_removed = nodes.iter().filter(|n| graph.node(n).is_none()).count();
+        drop(graph);
+
+        if result.channels_removed > 0 {
+            self.persist_network_graph()?;
+        }
+        Ok(result)
+    }
+}
+
+/// Prune and persist the network graph every hour until the node gets stopped
+pub(crate) async fn watch_network_graph(
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        match unlocked_state.prune_network_graph(&static_state).await {
+            Ok(result) if result.channels_removed > 0 => tracing::info!(
+                "Pruned {} channels and {} nodes from the network graph",
+                result.channels_removed,
+                result.nodes_removed
+            ),
+            Ok(_) => {
+                if let Err(e) = unlocked_state.persist_network_graph() {
+                    tracing::error!("Failed persisting the network graph: {e}");
+                }
+            }
+            Err(e) => tracing::error!("Failed pruning the network graph: {e}"),
+        }
+    }
+}
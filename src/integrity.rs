@@ -0,0 +1,124 @@
+use lightning::rgb_utils::WALLET_FINGERPRINT_FNAME;
+use lightning::routing::scoring::{ProbabilisticScorer, ProbabilisticScoringDecayParameters};
+use lightning::util::persist::CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE;
+use lightning::util::ser::ReadableArgs;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::data_dir::check_location;
+use crate::disk::{CHANNEL_MANAGER_FNAME, NETWORK_GRAPH_FNAME, SCORER_FNAME};
+use crate::error::APIError;
+use crate::ldk::NetworkGraph;
+use crate::utils::{StaticState, LDK_DIR};
+
+/// Error for an artifact the node cannot rebuild, so that only restoring a backup can help
+pub(crate) fn fatal_artifact_error(artifact: &str, problem: &str) -> APIError {
+    APIError::CorruptedDataDir(format!(
+        "{artifact} {problem}, it cannot be regenerated (hint: restore it from a backup)"
+    ))
+}
+
+/// Read an artifact the node can rebuild from scratch, removing it when it cannot be read and
+/// regenerating is allowed
+fn check_regenerable<T, E: Display>(
+    path: &Path,
+    artifact: &str,
+    regenerate: bool,
+    read: impl FnOnce(&mut BufReader<File>) -> Result<T, E>,
+) -> Result<Option<T>, APIError> {
+    let Ok(file) = File::open(path) else {
+        // a missing artifact simply gets created from scratch
+        return Ok(None);
+    };
+    let e = match read(&mut BufReader::new(file)) {
+        Ok(artifact) => return Ok(Some(artifact)),
+        Err(e) => e,
+    };
+    if !regenerate {
+        return Err(APIError::CorruptedDataDir(format!(
+            "{artifact} cannot be read ({e}), it can be regenerated (hint: set regenerate to \
+            rebuild it)"
+        )));
+    }
+    tracing::warn!("Regenerating {artifact}, which cannot be read ({e})");
+    fs::remove_file(path)?;
+    Ok(None)
+}
+
+/// Check the data directory holds everything the node needs to unlock, before starting anything
+///
+/// The network graph and the scorer only speed up routing, so they can be rebuilt when corrupted.
+/// The channel manager, the channel monitors and the RGB wallet data instead hold funds and
+/// cannot be recovered from anything else. Channel monitors get parsed while starting LDK, as
+/// reading them requires the node keys. Once checked, the location of the data directory gets
+/// recorded.
+pub(crate) fn check_data_dir(static_state: &StaticState, regenerate: bool) -> Result<(), APIError> {
+    let ldk_data_dir = Path::new(&static_state.ldk_data_dir);
+    let logger = Arc::clone(&static_state.logger);
+
+    let graph = check_regenerable(
+        &ldk_data_dir.join(NETWORK_GRAPH_FNAME),
+        "network graph",
+        regenerate,
+        |reader| NetworkGraph::read(reader, Arc::clone(&logger)),
+    )?
+    .unwrap_or_else(|| NetworkGraph::new(static_state.network, Arc::clone(&logger)));
+    let graph = Arc::new(graph);
+    check_regenerable(
+        &ldk_data_dir.join(SCORER_FNAME),
+        "scorer",
+        regenerate,
+        |reader| {
+            let args = (
+                ProbabilisticScoringDecayParameters::default(),
+                Arc::clone(&graph),
+                Arc::clone(&logger),
+            );
+            ProbabilisticScorer::read(reader, args)
+        },
+    )?;
+
+    check_fund_artifacts(Path::new(&static_state.storage_dir_path))?;
+    check_location(Path::new(&static_state.storage_dir_path))
+}
+
+/// Check the artifacts holding funds are all there, the ones that can be checked without the
+/// node keys
+pub(crate) fn check_fund_artifacts(storage_dir_path: &Path) -> Result<(), APIError> {
+    let ldk_data_dir = storage_dir_path.join(LDK_DIR);
+    let monitors_dir = ldk_data_dir.join(CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE);
+    let monitors = match fs::read_dir(&monitors_dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().map_or(true, |ext| ext != "tmp"))
+            .count(),
+        Err(_) => 0,
+    };
+    let manager_path = ldk_data_dir.join(CHANNEL_MANAGER_FNAME);
+    if monitors > 0 && !manager_path.exists() {
+        return Err(fatal_artifact_error(
+            "channel manager",
+            &format!("is missing while {monitors} channel monitors exist"),
+        ));
+    }
+    if manager_path.exists() && fs::metadata(&manager_path)?.len() == 0 {
+        return Err(fatal_artifact_error("channel manager", "is empty"));
+    }
+
+    // the fingerprint file gets written once the RGB wallet has been created
+    let fingerprint_path = storage_dir_path.join(WALLET_FINGERPRINT_FNAME);
+    if let Ok(fingerprint) = fs::read_to_string(fingerprint_path) {
+        let wallet_dir = storage_dir_path.join(fingerprint.trim());
+        if !wallet_dir.is_dir() {
+            return Err(fatal_artifact_error(
+                "RGB wallet data",
+                &format!("is missing from {}", wallet_dir.display()),
+            ));
+        }
+    }
+
+    Ok(())
+}
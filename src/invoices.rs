@@ -0,0 +1,104 @@
+use lightning::impl_writeable_tlv_based_enum;
+use lightning::ln::PaymentHash;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::APIError;
+use crate::routes::{HTLCStatus, InvoiceStatus};
+use crate::utils::{get_current_timestamp, UnlockedAppState};
+
+/// Why an invoice stopped accepting payments before being paid
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum InvoiceClosure {
+    Expired,
+    Cancelled,
+}
+
+impl_writeable_tlv_based_enum!(InvoiceClosure,
+    (0, Expired) => {},
+    (1, Cancelled) => {};
+);
+
+impl UnlockedAppState {
+    /// Close the pending invoices past their expiry, returning how many have been closed
+    pub(crate) fn expire_invoices(&self) -> usize {
+        let now = get_current_timestamp();
+        let mut inbound = self.get_inbound_payments();
+        let mut expired = 0;
+        for (payment_hash, payment) in inbound.payments.iter_mut().filter(|(_, p)| {
+            p.status == HTLCStatus::Pending
+                && p.closure.is_none()
+                && p.expires_at.is_some_and(|exp| exp <= now)
+        }) {
+            payment.closure = Some(InvoiceClosure::Expired);
+            self.save_inbound_payment(payment_hash, payment);
+            expired += 1;
+        }
+        expired
+    }
+
+    /// Stop accepting payments for a pending invoice
+    pub(crate) fn cancel_invoice(&self, payment_hash: &PaymentHash) -> Result<(), APIError> {
+        let mut inbound = self.get_inbound_payments();
+        let payment = inbound
+            .payments
+            .get_mut(payment_hash)
+            .ok_or(APIError::UnknownLNInvoice)?;
+        let reason = match payment.invoice_status() {
+            InvoiceStatus::Pending => None,
+            InvoiceStatus::Succeeded => Some("invoice has already been paid"),
+            InvoiceStatus::Failed => Some("invoice is not pending"),
+            InvoiceStatus::Expired => Some("invoice has expired"),
+            InvoiceStatus::Cancelled => Some("invoice has already been cancelled"),
+        };
+        if let Some(reason) = reason {
+            return Err(APIError::InvoiceNotCancellable(reason.to_string()));
+        }
+        payment.closure = Some(InvoiceClosure::Cancelled);
+        self.save_inbound_payment(payment_hash, payment);
+        Ok(())
+    }
+
+    /// Check an incoming payment is for an invoice still accepting payments, returning the status
+    /// of the invoice otherwise
+    ///
+    /// Spontaneous payments have no invoice and are always accepted.
+    pub(crate) fn check_invoice_open(
+        &self,
+        payment_hash: &PaymentHash,
+    ) -> Result<(), InvoiceStatus> {
+        let inbound = self.get_inbound_payments();
+        match inbound
+            .payments
+            .get(payment_hash)
+            .map(|p| p.invoice_status())
+        {
+            Some(status @ (InvoiceStatus::Expired | InvoiceStatus::Cancelled)) => Err(status),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Mark the invoices past their expiry as expired until the node gets stopped
+pub(crate) async fn watch_invoice_expiry(
+    unlocked_state: Arc<UnlockedAppState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    #[cfg(test)]
+    let interval_secs = 5;
+    #[cfg(not(test))]
+    let interval_secs = 60;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        let expired = unlocked_state.expire_invoices();
+        if expired > 0 {
+            tracing::info!("Marked {expired} invoices as expired");
+        }
+    }
+}
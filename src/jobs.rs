@@ -0,0 +1,197 @@
+use amplify::s;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use rand::RngCore;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::task::AbortHandle;
+
+use crate::error::APIError;
+use crate::routes::{JobKind, JobResponse, JobStatus, JobStatusResponse};
+use crate::utils::{get_current_timestamp, hex_str, no_cancel, AppState};
+
+/// Finished jobs kept in memory, the ones that finished first get forgotten first
+const MAX_FINISHED_JOBS: usize = 1000;
+
+struct Job {
+    kind: JobKind,
+    status: JobStatus,
+    created_at: u64,
+    finished_at: Option<u64>,
+    result: Option<serde_json::Value>,
+    error: Option<String>,
+    /// False once the job went past the point where stopping it would leave side effects
+    cancellable: bool,
+    abort_handle: Option<AbortHandle>,
+}
+
+/// Long-running API calls running in background, only kept in memory
+#[derive(Clone, Default)]
+pub(crate) struct Jobs(Arc<Mutex<HashMap<String, Job>>>);
+
+impl Jobs {
+    fn create(&self, kind: JobKind) -> String {
+        let mut id_bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut id_bytes);
+        let job_id = hex_str(&id_bytes);
+
+        let mut jobs = self.0.lock().unwrap();
+        let mut finished: Vec<(u64, String)> = jobs
+            .iter()
+            .filter_map(|(id, j)| j.finished_at.map(|t| (t, id.clone())))
+            .collect();
+        if finished.len() >= MAX_FINISHED_JOBS {
+            finished.sort();
+            for (_, id) in &finished[..=finished.len() - MAX_FINISHED_JOBS] {
+                jobs.remove(id);
+            }
+        }
+        jobs.insert(
+            job_id.clone(),
+            Job {
+                kind,
+                status: JobStatus::Running,
+                created_at: get_current_timestamp(),
+                finished_at: None,
+                result: None,
+                error: None,
+                cancellable: true,
+                abort_handle: None,
+            },
+        );
+        job_id
+    }
+
+    fn finish(&self, job_id: &str, result: Result<serde_json::Value, APIError>) {
+        let mut jobs = self.0.lock().unwrap();
+        let Some(job) = jobs.get_mut(job_id) else {
+            return;
+        };
+        if job.status != JobStatus::Running {
+            return;
+        }
+        match result {
+            Ok(result) => {
+                job.status = JobStatus::Succeeded;
+                job.result = Some(result);
+            }
+            Err(e) => {
+                job.status = JobStatus::Failed;
+                job.error = Some(e.to_string());
+            }
+        }
+        job.cancellable = false;
+        job.finished_at = Some(get_current_timestamp());
+    }
+
+    /// Stop a job that hasn't gone past its point of no return yet
+    pub(crate) fn cancel(&self, job_id: &str) -> Result<(), APIError> {
+        let mut jobs = self.0.lock().unwrap();
+        let job = jobs.get_mut(job_id).ok_or(APIError::UnknownJob)?;
+        if job.status != JobStatus::Running {
+            return Err(APIError::JobNotCancellable(s!(
+                "the job has already finished"
+            )));
+        }
+        if !job.cancellable {
+            return Err(APIError::JobNotCancellable(s!(
+                "the job has already started changing the node state"
+            )));
+        }
+        if let Some(abort_handle) = job.abort_handle.take() {
+            abort_handle.abort();
+        }
+        job.status = JobStatus::Cancelled;
+        job.cancellable = false;
+        job.finished_at = Some(get_current_timestamp());
+        tracing::info!("Cancelled job {job_id}");
+        Ok(())
+    }
+
+    pub(crate) fn status(&self, job_id: &str) -> Result<JobStatusResponse, APIError> {
+        let jobs = self.0.lock().unwrap();
+        let job = jobs.get(job_id).ok_or(APIError::UnknownJob)?;
+        Ok(JobStatusResponse {
+            job_id: job_id.to_string(),
+            kind: job.kind,
+            status: job.status,
+            cancellable: job.cancellable,
+            created_at: job.created_at,
+            finished_at: job.finished_at,
+            result: job.result.clone(),
+            error: job.error.clone(),
+        })
+    }
+}
+
+/// Handle through which a job declares when it stops being cancellable, without a job when the
+/// API call runs synchronously
+#[derive(Clone)]
+pub(crate) struct JobHandle {
+    job: Option<(String, Jobs)>,
+}
+
+impl JobHandle {
+    /// Mark the point after which the job has effects that can't be undone (e.g. broadcasting a
+    /// transaction or proposing a channel to a peer), failing if the job has been cancelled
+    /// before it
+    pub(crate) fn commit(&self) -> Result<(), APIError> {
+        let Some((job_id, jobs)) = &self.job else {
+            return Ok(());
+        };
+        let mut jobs = jobs.0.lock().unwrap();
+        match jobs.get_mut(job_id) {
+            Some(job) if job.status == JobStatus::Running => {
+                job.cancellable = false;
+                Ok(())
+            }
+            _ => Err(APIError::JobCancelled),
+        }
+    }
+}
+
+/// Register a job for a long-running API call, if the node has been started with jobs enabled and
+/// the call isn't synchronous anyway
+pub(crate) fn new_job(state: &AppState, kind: JobKind, synchronous: bool) -> JobHandle {
+    if synchronous || !state.static_state.async_jobs {
+        return JobHandle { job: None };
+    }
+    let job_id = state.jobs.create(kind);
+    tracing::info!("Started {kind:?} job {job_id}");
+    JobHandle {
+        job: Some((job_id, state.jobs.clone())),
+    }
+}
+
+/// Run a long-running API call in background, answering with its job ID, or wait for its
+/// result when it has no job
+pub(crate) async fn run_job<T, Fut>(job: JobHandle, fut: Fut) -> Result<Response, APIError>
+where
+    Fut: Future<Output = Result<T, APIError>> + Send + 'static,
+    T: Serialize + Send + 'static,
+{
+    let Some((job_id, jobs)) = job.job else {
+        let result = no_cancel(fut).await?;
+        return Ok(Json(result).into_response());
+    };
+
+    let task = {
+        let jobs = jobs.clone();
+        let job_id = job_id.clone();
+        tokio::spawn(async move {
+            let result = fut
+                .await
+                .and_then(|r| serde_json::to_value(r).map_err(|_| APIError::Unexpected));
+            jobs.finish(&job_id, result);
+        })
+    };
+    if let Some(job) = jobs.0.lock().unwrap().get_mut(&job_id) {
+        if job.status == JobStatus::Running {
+            job.abort_handle = Some(task.abort_handle());
+        }
+    }
+
+    Ok(Json(JobResponse { job_id }).into_response())
+}
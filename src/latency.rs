@@ -0,0 +1,129 @@
+use axum::{
+    extract::{MatchedPath, State},
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+
+use crate::utils::AppState;
+
+/// Latest requests of each route the percentiles are computed over
+pub(crate) const LATENCY_WINDOW: usize = 1024;
+
+/// Durations (in µs) of the latest requests of a route, in a ring buffer
+struct Window {
+    samples: [u32; LATENCY_WINDOW],
+    next: usize,
+    len: usize,
+    count: u64,
+}
+
+impl Window {
+    fn new() -> Self {
+        Window {
+            samples: [0; LATENCY_WINDOW],
+            next: 0,
+            len: 0,
+            count: 0,
+        }
+    }
+
+    fn record(&mut self, micros: u32) {
+        self.samples[self.next] = micros;
+        self.next = (self.next + 1) % LATENCY_WINDOW;
+        self.len = (self.len + 1).min(LATENCY_WINDOW);
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct RouteLatency {
+    pub(crate) route: String,
+    /// Requests served since the node started
+    pub(crate) count: u64,
+    /// Latest requests the percentiles are computed over
+    pub(crate) samples: usize,
+    pub(crate) p50_ms: f64,
+    pub(crate) p95_ms: f64,
+    pub(crate) p99_ms: f64,
+    pub(crate) max_ms: f64,
+}
+
+/// Durations of the requests served by each API route
+///
+/// The window of a route is allocated at its first request, recording the following ones only
+/// takes locks. Durations are kept in memory, so they are reset when the node gets restarted.
+#[derive(Default)]
+pub(crate) struct LatencyTracker {
+    routes: RwLock<HashMap<String, Mutex<Window>>>,
+}
+
+impl LatencyTracker {
+    fn record(&self, route: &str, micros: u32) {
+        if let Some(window) = self.routes.read().unwrap().get(route) {
+            window.lock().unwrap().record(micros);
+            return;
+        }
+        self.routes
+            .write()
+            .unwrap()
+            .entry(route.to_string())
+            .or_insert_with(|| Mutex::new(Window::new()))
+            .lock()
+            .unwrap()
+            .record(micros);
+    }
+
+    /// Latency percentiles of the routes requested since the node started, sorted by route
+    pub(crate) fn latencies(&self) -> Vec<RouteLatency> {
+        let routes = self.routes.read().unwrap();
+        let mut latencies: Vec<RouteLatency> = routes
+            .iter()
+            .map(|(route, window)| {
+                let window = window.lock().unwrap();
+                let mut samples = window.samples[..window.len].to_vec();
+                samples.sort_unstable();
+                let percentile = |percent: usize| {
+                    let index = (samples.len() * percent).div_ceil(100).saturating_sub(1);
+                    samples[index] as f64 / 1000.0
+                };
+                RouteLatency {
+                    route: route.clone(),
+                    count: window.count,
+                    samples: samples.len(),
+                    p50_ms: percentile(50),
+                    p95_ms: percentile(95),
+                    p99_ms: percentile(99),
+                    max_ms: percentile(100),
+                }
+            })
+            .collect();
+        latencies.sort_by(|a, b| a.route.cmp(&b.route));
+        latencies
+    }
+}
+
+/// Middleware recording how long the requests reaching a route take to be served, doing nothing
+/// when latency tracking is disabled
+pub(crate) async fn track_latency<B>(
+    State(state): State<Arc<AppState>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let Some(tracker) = &state.static_state.latency_tracker else {
+        return next.run(request).await;
+    };
+    // the matched path is shared, cloning it doesn't allocate
+    let Some(route) = request.extensions().get::<MatchedPath>().cloned() else {
+        return next.run(request).await;
+    };
+    let started_at = Instant::now();
+    let response = next.run(request).await;
+    let micros = u32::try_from(started_at.elapsed().as_micros()).unwrap_or(u32::MAX);
+    tracker.record(route.as_str(), micros);
+    response
+}
@@ -3,39 +3,63 @@ use bdk::bitcoin::psbt::PartiallySignedTransaction;
 use bdk::bitcoin::{psbt::Psbt as BdkPsbt, OutPoint, Script as BdkScript};
 use bdk::keys::bip39::Mnemonic;
 use bdk::keys::{DerivableKey, ExtendedKey};
+use bdk::psbt::PsbtUtils;
 use bdk::{FeeRate, SignOptions};
+use bitcoin::blockdata::constants::ChainHash;
 use bitcoin::blockdata::transaction::Transaction;
+use bitcoin::hashes::hex::ToHex;
 use bitcoin::network::constants::Network;
-use bitcoin::secp256k1::Secp256k1;
+use bitcoin::secp256k1::{All, PublicKey, Secp256k1};
 use bitcoin::util::bip32::{ChildNumber, ExtendedPrivKey};
 use bitcoin::{BlockHash, LockTime, PackedLockTime, Script, Sequence, TxIn, TxOut, Witness};
 use bitcoin_30::{Address, ScriptBuf};
 use bitcoin_bech32::WitnessProgram;
+use lightning::chain::channelmonitor::Balance;
 use lightning::chain::{chainmonitor, ChannelMonitorUpdateStatus};
 use lightning::chain::{Filter, Watch};
 use lightning::events::bump_transaction::{BumpTransactionEventHandler, Wallet};
-use lightning::events::{Event, PaymentFailureReason, PaymentPurpose};
-use lightning::ln::channelmanager::{self, PaymentId, RecentPaymentDetails};
+use lightning::events::{
+    Event, MessageSendEvent, MessageSendEventsProvider, PathFailure, PaymentFailureReason,
+    PaymentPurpose,
+};
+use lightning::ln::channelmanager::{
+    self, PaymentId, RecentPaymentDetails,
+    MIN_FINAL_CLTV_EXPIRY_DELTA as LDK_MIN_FINAL_CLTV_EXPIRY_DELTA,
+};
 use lightning::ln::channelmanager::{
-    ChainParameters, ChannelManagerReadArgs, SimpleArcChannelManager,
+    ChainParameters, ChannelDetails, ChannelManagerReadArgs, FailureCode, SimpleArcChannelManager,
+};
+use lightning::ln::features::{InitFeatures, NodeFeatures};
+use lightning::ln::msgs::{
+    self, ChannelAnnouncement, ChannelMessageHandler, ChannelUpdate, Init, LightningError,
+    NodeAnnouncement, OnionMessage, OnionMessageHandler, OnionMessageProvider, QueryChannelRange,
+    QueryShortChannelIds, ReplyChannelRange, ReplyShortChannelIdsEnd, RoutingMessageHandler,
 };
-use lightning::ln::peer_handler::{IgnoringMessageHandler, MessageHandler, SimpleArcPeerManager};
+use lightning::ln::peer_handler::{MessageHandler, PeerManager as LdkPeerManager};
 use lightning::ln::{ChannelId, PaymentHash, PaymentPreimage, PaymentSecret};
-use lightning::onion_message::{DefaultMessageRouter, SimpleArcOnionMessenger};
+use lightning::onion_message::{peel_onion_message, DefaultMessageRouter, PeeledOnion};
 use lightning::rgb_utils::{
-    get_rgb_channel_info_pending, get_rgb_runtime, parse_rgb_payment_info, read_rgb_transfer_info,
-    update_rgb_channel_amount, STATIC_BLINDING, WALLET_FINGERPRINT_FNAME,
+    get_rgb_channel_info_path, get_rgb_channel_info_pending, get_rgb_payment_info_path,
+    get_rgb_runtime, parse_rgb_channel_info, parse_rgb_payment_info, read_rgb_transfer_info,
+    update_rgb_channel_amount, write_rgb_channel_info, RgbInfo, STATIC_BLINDING,
+    WALLET_FINGERPRINT_FNAME,
 };
 use lightning::routing::gossip;
-use lightning::routing::gossip::{NodeId, P2PGossipSync};
-use lightning::routing::router::DefaultRouter;
-use lightning::routing::scoring::ProbabilisticScoringFeeParameters;
+use lightning::routing::gossip::{NetworkUpdate, NodeId, P2PGossipSync};
+use lightning::routing::router::{DefaultRouter, Path as RoutePath};
+use lightning::routing::scoring::{ProbabilisticScorer, ProbabilisticScoringFeeParameters};
 use lightning::sign::{
     DelayedPaymentOutputDescriptor, EntropySource, InMemorySigner, KeysManager,
     SpendableOutputDescriptor,
 };
-use lightning::util::config::UserConfig;
-use lightning::util::persist::{KVStore, MonitorUpdatingPersister};
+use lightning::util::config::{
+    ChannelConfig, ChannelHandshakeConfig, ChannelHandshakeLimits, UserConfig,
+};
+use lightning::util::errors::APIError as LdkAPIError;
+use lightning::util::persist::{
+    KVStore, MonitorUpdatingPersister, CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+};
 use lightning::util::ser::{Readable, ReadableArgs, WithoutLength, Writeable};
 use lightning::{chain, impl_writeable_tlv_based};
 use lightning_background_processor::{process_events_async, GossipSync};
@@ -45,41 +69,122 @@ use lightning_block_sync::SpvClient;
 use lightning_block_sync::UnboundedCache;
 use lightning_net_tokio::SocketDescriptor;
 use lightning_persister::fs_store::FilesystemStore;
+use lightning_rapid_gossip_sync::RapidGossipSync;
 use rand::{thread_rng, Rng, RngCore};
 use rgb_lib::wallet::{DatabaseType, Recipient, RecipientData, Wallet as RgbLibWallet, WalletData};
 use rgb_lib::AssetSchema;
 use rgbstd::containers::{Bindle, Transfer as RgbTransfer};
+use rgbstd::contract::{ContractId, SecretSeal};
 use rgbstd::persistence::Inventory;
 use rgbstd::Txid as RgbTxid;
+use rgbwallet::RgbTransport;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::fs;
 use std::io::{Read, Seek, SeekFrom};
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, SystemTime};
 use strict_encoding::{FieldName, TypeName};
 use tokio::sync::watch::Sender;
 use tokio::task::JoinHandle;
 
+use crate::alerts::{watch_alerts, Alerts};
+use crate::announcements::Announcements;
 use crate::bdk::{broadcast_tx, get_bdk_wallet_seckey, sync_wallet};
 use crate::bitcoind::BitcoindClient;
-use crate::disk::{self, INBOUND_PAYMENTS_FNAME, OUTBOUND_PAYMENTS_FNAME};
-use crate::disk::{FilesystemLogger, PENDING_SPENDABLE_OUTPUT_DIR};
+use crate::channel_fees::{process_broadcast_txs, record_channel_tx, ChannelFeeTracker};
+use crate::channel_handshake::ChannelHandshakeDefaults;
+use crate::channel_opens::{
+    handle_inbound_channel, rollback_channel_open, send_channel_proposal, watch_channel_opens,
+    ChannelProposal, InboundChannels, PendingChannelOpen,
+};
+use crate::close_negotiation::watch_close_negotiations;
+use crate::consolidation::watch_asset_allocations;
+use crate::database::{
+    ChannelFeeKind, ChannelFeeRate, ChannelHtlcRecord, ClosedChannelRecord, Database,
+    ForwardRecord, SweepRecord, DATABASE_FNAME,
+};
+use crate::disk::{
+    self, FilesystemLogger, CHANNEL_MANAGER_FNAME, NETWORK_GRAPH_FNAME,
+    PENDING_SPENDABLE_OUTPUT_DIR, SCORER_FNAME,
+};
 use crate::error::APIError;
-use crate::proxy::post_consignment;
+use crate::graph_pruning::{watch_network_graph, GraphPruner};
+use crate::integrity::fatal_artifact_error;
+use crate::invoices::{watch_invoice_expiry, InvoiceClosure};
+use crate::lsp::{self, LspState};
+use crate::mirror::{restore_from_mirror, MirroredStore, MonitorMirror};
+use crate::monitor_archive::{archive_resolved_monitors, watch_resolved_monitors};
+use crate::outbound_reservations::OutboundReservations;
+use crate::payer::{read_payer_tlvs, PayerInfo, PayerRequirements};
+use crate::payment_quotes::QuoteCache;
+use crate::peer_limits::{enforce_peer_limits, PeerLimiter};
+use crate::peer_messages::{process_peer_messages, PeerMessageHandler};
+use crate::pending_transfers::resume_pending_transfers;
+use crate::persistence::UnpersistedOutputs;
+use crate::proxy::{
+    check_default_proxies, post_consignment_with_failover, preferred_proxy_endpoint, watch_proxy,
+};
+use crate::rebroadcast::watch_unconfirmed_txs;
+use crate::reorgs::{watch_chain_reorgs, ChainReorgs};
 use crate::rgb::{
     get_bitcoin_network, update_transition_beneficiary, RgbLibWalletWrapper, RgbUtilities,
 };
-use crate::routes::HTLCStatus;
-use crate::utils::{do_connect_peer, hex_str, AppState, StaticState, UnlockedAppState};
+use crate::routes::{
+    FailingHop, FailureReason, HTLCStatus, HtlcExpiryAction, InvoiceStatus, NodeEvent, Payment,
+    PaymentHop, SettlementMethod, UtxoReservation, HTLC_MIN_MSAT,
+};
+use crate::swaps::{self, Swaps};
+use crate::utils::{
+    do_connect_peer, get_current_timestamp, hex_str, hex_str_to_payment_hash, node_alias_bytes,
+    AppState, StaticState, UnlockedAppState, UserOnionMessageHandler,
+};
+use crate::wallet_addresses::VanillaAddresses;
+use crate::webhooks::{deliver_webhooks, Webhooks};
 
 pub(crate) const FEE_RATE: f32 = 7.0;
 pub(crate) const UTXO_SIZE_SAT: u32 = 32000;
 pub(crate) const MIN_CHANNEL_CONFIRMATIONS: u8 = 6;
+/// Highest number of confirmations a channel can require before becoming usable (mirrors LDK's
+/// default max_minimum_depth)
+pub(crate) const MAX_CHANNEL_MIN_DEPTH: u32 = 144;
+
+/// Blocks after the expiry of an outbound HTLC at which LDK force-closes the channel (mirrors
+/// LDK's LATENCY_GRACE_PERIOD_BLOCKS)
+const HTLC_OUTBOUND_GRACE_BLOCKS: u32 = 3;
+/// Blocks before the expiry of an inbound HTLC at which LDK force-closes the channel to claim it
+/// on-chain (mirrors LDK's CLTV_CLAIM_BUFFER)
+const HTLC_INBOUND_CLAIM_BUFFER: u32 = 18;
+/// HTLCs that would cause a force-close in less than this number of blocks are flagged
+pub(crate) const HTLC_EXPIRY_WARNING_BLOCKS: u32 = 24;
+/// Lowest final CLTV expiry delta the invoices of the node can advertise, so that an HTLC paying
+/// one with the least expiry allowed doesn't get failed back by the HTLC expiry watch as soon as
+/// it arrives (and never lower than what LDK requires)
+pub(crate) const MIN_FINAL_CLTV_EXPIRY_DELTA: u16 = {
+    let delta = (HTLC_INBOUND_CLAIM_BUFFER + HTLC_EXPIRY_WARNING_BLOCKS) as u16;
+    if delta > LDK_MIN_FINAL_CLTV_EXPIRY_DELTA {
+        delta
+    } else {
+        LDK_MIN_FINAL_CLTV_EXPIRY_DELTA
+    }
+};
+
+/// Points where the handling of payments can be held, shared with the test to simulate
+/// misbehaving nodes
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct PaymentFaults {
+    /// Leave the claimable payments unclaimed, as a receiver not settling them
+    pub(crate) hold_claimable_payments: AtomicBool,
+    /// Leave the HTLCs of the swaps unclaimed once their assets are sent on-chain, as a node
+    /// stopping in between
+    pub(crate) stop_swap_claims: AtomicBool,
+}
 
 pub(crate) struct LdkBackgroundServices {
     stop_processing: Arc<AtomicBool>,
@@ -94,6 +199,24 @@ pub(crate) struct PaymentInfo {
     pub(crate) secret: Option<PaymentSecret>,
     pub(crate) status: HTLCStatus,
     pub(crate) amt_msat: Option<u64>,
+    pub(crate) label: Option<String>,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: Option<u64>,
+    pub(crate) failure_reason: Option<FailureReason>,
+    pub(crate) failing_hop: Option<FailingHop>,
+    pub(crate) settled_at: Option<u64>,
+    /// Identification required from the payer of an invoice
+    pub(crate) payer_requirements: Option<PayerRequirements>,
+    /// Identification attached by the payer of an incoming payment
+    pub(crate) payer: Option<PayerInfo>,
+    /// Recipient of an outgoing payment
+    pub(crate) payee: Option<PublicKey>,
+    /// Transaction paying the fallback address of the invoice of an outgoing payment that failed
+    pub(crate) onchain_txid: Option<String>,
+    /// Why the invoice of an inbound payment stopped accepting payments before being paid
+    pub(crate) closure: Option<InvoiceClosure>,
+    /// Hops of the paths taken by a successful outbound payment
+    pub(crate) hops: Vec<PaymentHop>,
 }
 
 impl_writeable_tlv_based!(PaymentInfo, {
@@ -101,164 +224,1471 @@ impl_writeable_tlv_based!(PaymentInfo, {
     (2, secret, required),
     (4, status, required),
     (6, amt_msat, required),
+    (7, label, option),
+    (9, created_at, (default_value, 0)),
+    (11, expires_at, option),
+    (15, failing_hop, option),
+    (17, settled_at, option),
+    (19, payer_requirements, option),
+    (21, payer, option),
+    (23, payee, option),
+    (25, onchain_txid, option),
+    (27, closure, option),
+    (29, hops, optional_vec),
+    // 13 held the failure reason as a string before it got decoded, it's skipped when found
+    (31, failure_reason, option),
 });
 
+impl PaymentInfo {
+    /// The status of the payment, taking into account the expiration and cancellation of the
+    /// invoice
+    pub(crate) fn invoice_status(&self) -> InvoiceStatus {
+        match self.status {
+            HTLCStatus::Pending if self.closure == Some(InvoiceClosure::Cancelled) => {
+                InvoiceStatus::Cancelled
+            }
+            HTLCStatus::Pending
+                if self.closure == Some(InvoiceClosure::Expired)
+                    || self
+                        .expires_at
+                        .is_some_and(|exp| exp <= get_current_timestamp()) =>
+            {
+                InvoiceStatus::Expired
+            }
+            HTLCStatus::Pending => InvoiceStatus::Pending,
+            HTLCStatus::Succeeded => InvoiceStatus::Succeeded,
+            HTLCStatus::Failed => InvoiceStatus::Failed,
+        }
+    }
+
+    pub(crate) fn settlement_method(&self) -> SettlementMethod {
+        match self.onchain_txid {
+            Some(_) => SettlementMethod::Onchain,
+            None => SettlementMethod::Lightning,
+        }
+    }
+
+    /// The payment as reported by the API, without its hops
+    pub(crate) fn to_payment(
+        &self,
+        payment_hash: &PaymentHash,
+        inbound: bool,
+        ldk_data_dir_path: &Path,
+        asset_precisions: &HashMap<String, u8>,
+    ) -> Payment {
+        let rgb_payment_info_path = get_rgb_payment_info_path(payment_hash, ldk_data_dir_path);
+        let (asset_amount, asset_id) = if rgb_payment_info_path.exists() {
+            let rgb_payment_info = parse_rgb_payment_info(&rgb_payment_info_path);
+            (
+                Some(rgb_payment_info.amount),
+                Some(rgb_payment_info.contract_id.to_string()),
+            )
+        } else {
+            (None, None)
+        };
+        let asset_precision = asset_id
+            .as_ref()
+            .and_then(|id| asset_precisions.get(id).copied());
+        Payment {
+            amt_msat: self.amt_msat,
+            asset_amount,
+            asset_id,
+            asset_precision,
+            payment_hash: hex_str(&payment_hash.0),
+            inbound,
+            status: self.status,
+            invoice_status: self.invoice_status(),
+            label: self.label.clone(),
+            created_at: self.created_at,
+            failure_reason: self.failure_reason,
+            failing_hop: self.failing_hop.clone(),
+            payer_pubkey: self
+                .payer
+                .as_ref()
+                .and_then(|p| p.pubkey)
+                .map(|pk| pk.to_string()),
+            payer_note: self.payer.as_ref().and_then(|p| p.note.clone()),
+            settlement_method: self.settlement_method(),
+            onchain_txid: self.onchain_txid.clone(),
+            hops: None,
+        }
+    }
+}
+
 pub(crate) struct InboundPaymentInfoStorage {
     pub(crate) payments: HashMap<PaymentHash, PaymentInfo>,
 }
 
-impl_writeable_tlv_based!(InboundPaymentInfoStorage, {
-    (0, payments, required),
-});
+impl_writeable_tlv_based!(InboundPaymentInfoStorage, {
+    (0, payments, required),
+});
+
+pub(crate) struct OutboundPaymentInfoStorage {
+    pub(crate) payments: HashMap<PaymentId, PaymentInfo>,
+}
+
+impl_writeable_tlv_based!(OutboundPaymentInfoStorage, {
+    (0, payments, required),
+});
+
+pub(crate) struct RgbInvoiceLabelStorage {
+    pub(crate) labels: HashMap<String, String>,
+}
+
+impl_writeable_tlv_based!(RgbInvoiceLabelStorage, {
+    (0, labels, required),
+});
+
+pub(crate) struct PendingHtlc {
+    pub(crate) channel_id: ChannelId,
+    pub(crate) counterparty_node_id: PublicKey,
+    pub(crate) inbound: bool,
+    pub(crate) amt_msat: u64,
+    pub(crate) payment_hash: PaymentHash,
+    pub(crate) expiry_height: u32,
+    pub(crate) force_close_height: u32,
+    /// Whether the HTLC has no output on the commitment transaction, as the ones below the dust
+    /// limit
+    pub(crate) dust: bool,
+}
+
+/// Height at which the channel of an HTLC gets force-closed if it's still pending
+fn htlc_force_close_height(inbound: bool, expiry_height: u32) -> u32 {
+    if inbound {
+        expiry_height.saturating_sub(HTLC_INBOUND_CLAIM_BUFFER)
+    } else {
+        expiry_height + HTLC_OUTBOUND_GRACE_BLOCKS
+    }
+}
+
+/// Complete the HTLCs a channel monitor reports with the ones recorded for the channel
+///
+/// The monitor reports the amounts in sats, rounded down, so each of its HTLCs takes the msat
+/// amount of the matching recorded one. The recorded HTLCs left have no output on the commitment
+/// transaction and are added as dust.
+pub(crate) fn merge_recorded_htlcs(
+    channel_id: ChannelId,
+    counterparty_node_id: PublicKey,
+    monitor_htlcs: Vec<PendingHtlc>,
+    mut recorded: Vec<ChannelHtlcRecord>,
+) -> Vec<PendingHtlc> {
+    let mut htlcs = Vec::with_capacity(monitor_htlcs.len() + recorded.len());
+    for mut htlc in monitor_htlcs {
+        let payment_hash = hex_str(&htlc.payment_hash.0);
+        if let Some(pos) = recorded.iter().position(|r| {
+            r.inbound == htlc.inbound
+                && r.payment_hash == payment_hash
+                && r.amt_msat / 1000 == htlc.amt_msat / 1000
+        }) {
+            htlc.amt_msat = recorded.remove(pos).amt_msat;
+        }
+        htlcs.push(htlc);
+    }
+    for record in recorded {
+        let Some(payment_hash) = hex_str_to_payment_hash(&record.payment_hash) else {
+            continue;
+        };
+        htlcs.push(PendingHtlc {
+            channel_id,
+            counterparty_node_id,
+            inbound: record.inbound,
+            amt_msat: record.amt_msat,
+            payment_hash,
+            expiry_height: record.cltv_expiry,
+            force_close_height: htlc_force_close_height(record.inbound, record.cltv_expiry),
+            dust: true,
+        });
+    }
+    htlcs
+}
+
+impl UnlockedAppState {
+    pub(crate) fn add_inbound_payment(&self, payment_hash: PaymentHash, payment_info: PaymentInfo) {
+        let mut inbound = self.get_inbound_payments();
+        self.save_inbound_payment(&payment_hash, &payment_info);
+        inbound.payments.insert(payment_hash, payment_info);
+    }
+
+    /// Record an attempt of an outbound payment, a retry replacing the record of the previous
+    /// attempt but keeping its creation time
+    pub(crate) fn add_outbound_payment(
+        &self,
+        payment_id: PaymentId,
+        mut payment_info: PaymentInfo,
+    ) {
+        let mut outbound = self.get_outbound_payments();
+        if let Some(previous) = outbound.payments.get(&payment_id) {
+            payment_info.created_at = previous.created_at;
+        }
+        self.save_outbound_payment(&payment_id, &payment_info);
+        outbound.payments.insert(payment_id, payment_info);
+    }
+
+    /// Record a new attempt of an outbound payment, refusing it while a previous attempt is
+    /// pending or has succeeded, and returning the record of the failed attempt it replaces
+    pub(crate) fn start_outbound_payment(
+        &self,
+        payment_id: PaymentId,
+        mut payment_info: PaymentInfo,
+    ) -> Result<Option<PaymentInfo>, APIError> {
+        let mut outbound = self.get_outbound_payments();
+        let previous = outbound.payments.get(&payment_id).cloned();
+        if let Some(previous) = &previous {
+            if previous.status != HTLCStatus::Failed {
+                return Err(APIError::DuplicatePayment);
+            }
+            payment_info.created_at = previous.created_at;
+        }
+        self.save_outbound_payment(&payment_id, &payment_info);
+        outbound.payments.insert(payment_id, payment_info);
+        Ok(previous)
+    }
+
+    /// Put back the record replaced by an attempt of an outbound payment that hasn't been sent
+    pub(crate) fn restore_outbound_payment(
+        &self,
+        payment_id: PaymentId,
+        previous: Option<PaymentInfo>,
+    ) {
+        let mut outbound = self.get_outbound_payments();
+        match previous {
+            Some(previous) => {
+                self.save_outbound_payment(&payment_id, &previous);
+                outbound.payments.insert(payment_id, previous);
+            }
+            None => {
+                outbound.payments.remove(&payment_id);
+                self.delete_payment_record(&payment_id.0, false);
+            }
+        }
+    }
+
+    fn fail_outbound_pending_payments(&self, recent_payments_payment_ids: Vec<PaymentId>) {
+        let mut outbound = self.get_outbound_payments();
+        for (payment_id, payment_info) in outbound
+            .payments
+            .iter_mut()
+            .filter(|(_, i)| matches!(i.status, HTLCStatus::Pending))
+        {
+            if !recent_payments_payment_ids.contains(payment_id) {
+                payment_info.status = HTLCStatus::Failed;
+                self.save_outbound_payment(payment_id, payment_info);
+                self.release_spending(payment_id);
+                self.outbound_reservations.release(payment_id);
+            }
+        }
+    }
+
+    /// Stop counting a failed outbound payment towards the spending limits
+    fn release_spending(&self, payment_id: &PaymentId) {
+        if let Err(e) = self.database.release_spending(&hex_str(&payment_id.0)) {
+            tracing::error!("failed to release spending of payment: {e}");
+        }
+    }
+
+    pub(crate) fn inbound_payments(&self) -> HashMap<PaymentHash, PaymentInfo> {
+        self.get_inbound_payments().payments.clone()
+    }
+
+    pub(crate) fn outbound_payments(&self) -> HashMap<PaymentId, PaymentInfo> {
+        self.get_outbound_payments().payments.clone()
+    }
+
+    /// Delete the records of the terminated payments with the given hash, returning how many
+    /// records have been removed
+    pub(crate) fn delete_payment(&self, payment_hash: PaymentHash) -> Result<u64, APIError> {
+        let mut inbound = self.get_inbound_payments();
+        let mut outbound = self.get_outbound_payments();
+        let payment_id = PaymentId(payment_hash.0);
+        let records = [
+            inbound.payments.get(&payment_hash),
+            outbound.payments.get(&payment_id),
+        ];
+        if records.iter().all(|r| r.is_none()) {
+            return Err(APIError::UnknownPayment);
+        }
+        if records
+            .iter()
+            .flatten()
+            .any(|p| p.invoice_status() == InvoiceStatus::Pending)
+        {
+            return Err(APIError::PaymentPending);
+        }
+        let mut removed = 0;
+        if inbound.payments.remove(&payment_hash).is_some() {
+            self.delete_payment_record(&payment_hash.0, true);
+            removed += 1;
+        }
+        if outbound.payments.remove(&payment_id).is_some() {
+            self.delete_payment_record(&payment_id.0, false);
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Delete the records of the terminated payments created before the given timestamp and
+    /// having one of the given statuses, returning how many records have been removed
+    pub(crate) fn prune_payments(&self, older_than_ts: u64, statuses: &[InvoiceStatus]) -> u64 {
+        let to_prune = |p: &PaymentInfo| {
+            let status = p.invoice_status();
+            status != InvoiceStatus::Pending
+                && statuses.contains(&status)
+                && p.created_at < older_than_ts
+        };
+        let mut removed = 0;
+        self.get_inbound_payments()
+            .payments
+            .retain(|payment_hash, p| {
+                let prune = to_prune(p);
+                if prune {
+                    self.delete_payment_record(&payment_hash.0, true);
+                    removed += 1;
+                }
+                !prune
+            });
+        self.get_outbound_payments()
+            .payments
+            .retain(|payment_id, p| {
+                let prune = to_prune(p);
+                if prune {
+                    self.delete_payment_record(&payment_id.0, false);
+                    removed += 1;
+                }
+                !prune
+            });
+        removed
+    }
+
+    /// List the HTLCs pending on each channel, including the dust ones
+    ///
+    /// The monitors only report the HTLCs with an output on the current commitment transaction,
+    /// so they are completed with the HTLCs recorded as they get exchanged with the peers.
+    pub(crate) fn pending_htlcs(&self) -> Vec<PendingHtlc> {
+        let mut recorded = self.database.channel_htlcs().unwrap_or_else(|e| {
+            tracing::error!("Failed to get the recorded HTLCs: {e}");
+            vec![]
+        });
+        let mut htlcs = vec![];
+        for chan_info in self.channel_manager.list_channels() {
+            let channel_id = chan_info.channel_id.to_hex();
+            let (channel_recorded, others): (Vec<_>, Vec<_>) = recorded
+                .into_iter()
+                .partition(|r| r.channel_id == channel_id);
+            recorded = others;
+            let monitor = chan_info
+                .funding_txo
+                .and_then(|funding_txo| self.chain_monitor.get_monitor(funding_txo).ok());
+            let monitor_htlcs = monitor
+                .map(|monitor| monitor.get_claimable_balances())
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|balance| {
+                    let (inbound, amt_sat, payment_hash, expiry_height) = match balance {
+                        Balance::MaybeTimeoutClaimableHTLC {
+                            claimable_amount_satoshis,
+                            claimable_height,
+                            payment_hash,
+                            ..
+                        } => (
+                            false,
+                            claimable_amount_satoshis,
+                            payment_hash,
+                            claimable_height,
+                        ),
+                        Balance::MaybePreimageClaimableHTLC {
+                            claimable_amount_satoshis,
+                            expiry_height,
+                            payment_hash,
+                            ..
+                        } => (true, claimable_amount_satoshis, payment_hash, expiry_height),
+                        _ => return None,
+                    };
+                    Some(PendingHtlc {
+                        channel_id: chan_info.channel_id,
+                        counterparty_node_id: chan_info.counterparty.node_id,
+                        inbound,
+                        amt_msat: amt_sat * 1000,
+                        payment_hash,
+                        expiry_height,
+                        force_close_height: htlc_force_close_height(inbound, expiry_height),
+                        dust: false,
+                    })
+                })
+                .collect();
+            htlcs.extend(merge_recorded_htlcs(
+                chan_info.channel_id,
+                chan_info.counterparty.node_id,
+                monitor_htlcs,
+                channel_recorded,
+            ));
+        }
+        // the HTLCs left belong to channels that are no longer open
+        let closed_channel_ids: HashSet<String> =
+            recorded.into_iter().map(|r| r.channel_id).collect();
+        for channel_id in closed_channel_ids {
+            if let Err(e) = self.database.remove_channel_htlcs(&channel_id) {
+                tracing::error!("Failed to forget the HTLCs of channel {channel_id}: {e}");
+            }
+        }
+        htlcs
+    }
+
+    /// Write a changed inbound payment to the database, to be called while holding the lock of
+    /// the in-memory records so that writes of the same payment don't get reordered
+    pub(crate) fn save_inbound_payment(&self, payment_hash: &PaymentHash, info: &PaymentInfo) {
+        if let Err(e) = self.database.save_payment(&payment_hash.0, true, info) {
+            self.persistence.record_failure("inbound payments", e);
+        }
+    }
+
+    /// Write a changed outbound payment to the database, as for inbound ones
+    fn save_outbound_payment(&self, payment_id: &PaymentId, info: &PaymentInfo) {
+        if let Err(e) = self.database.save_payment(&payment_id.0, false, info) {
+            self.persistence.record_failure("outbound payments", e);
+        }
+    }
+
+    fn delete_payment_record(&self, id: &[u8; 32], inbound: bool) {
+        if let Err(e) = self.database.delete_payment(id, inbound) {
+            self.persistence.record_failure("payments", e);
+        }
+    }
+
+    pub(crate) fn set_rgb_invoice_label(&self, recipient_id: &str, label: &str) {
+        if let Err(e) = self.database.save_rgb_invoice_label(recipient_id, label) {
+            self.persistence.record_failure("RGB invoice labels", e);
+        }
+    }
+
+    /// Set the label of the payment with the given hash, returning false if no inbound or
+    /// outbound payment with such hash is known
+    pub(crate) fn update_payment_label(&self, payment_hash: PaymentHash, label: String) -> bool {
+        let mut found = false;
+        let mut inbound = self.get_inbound_payments();
+        if let Some(payment) = inbound.payments.get_mut(&payment_hash) {
+            payment.label = Some(label.clone());
+            self.save_inbound_payment(&payment_hash, payment);
+            found = true;
+        }
+        let mut outbound = self.get_outbound_payments();
+        let payment_id = PaymentId(payment_hash.0);
+        if let Some(payment) = outbound.payments.get_mut(&payment_id) {
+            payment.label = Some(label);
+            self.save_outbound_payment(&payment_id, payment);
+            found = true;
+        }
+        found
+    }
+
+    fn upsert_inbound_payment(
+        &self,
+        payment_hash: PaymentHash,
+        status: HTLCStatus,
+        preimage: Option<PaymentPreimage>,
+        secret: Option<PaymentSecret>,
+        amt_msat: Option<u64>,
+    ) {
+        let mut inbound = self.get_inbound_payments();
+        let payment = match inbound.payments.entry(payment_hash) {
+            Entry::Occupied(e) => {
+                let payment = e.into_mut();
+                if status == HTLCStatus::Succeeded && payment.status != HTLCStatus::Succeeded {
+                    payment.settled_at = Some(get_current_timestamp());
+                }
+                payment.status = status;
+                payment.preimage = preimage;
+                payment.secret = secret;
+                payment
+            }
+            Entry::Vacant(e) => e.insert(PaymentInfo {
+                preimage,
+                secret,
+                status,
+                amt_msat,
+                label: None,
+                created_at: get_current_timestamp(),
+                expires_at: None,
+                failure_reason: None,
+                failing_hop: None,
+                settled_at: (status == HTLCStatus::Succeeded).then(get_current_timestamp),
+                payer_requirements: None,
+                payer: None,
+                payee: None,
+                onchain_txid: None,
+                closure: None,
+                hops: vec![],
+            }),
+        };
+        self.save_inbound_payment(&payment_hash, payment);
+    }
+
+    /// Record the identification the payer attached to an incoming invoice payment
+    fn set_inbound_payer(&self, payment_hash: &PaymentHash, payer: PayerInfo) {
+        let mut inbound = self.get_inbound_payments();
+        if let Some(payment) = inbound.payments.get_mut(payment_hash) {
+            payment.payer = Some(payer);
+            self.save_inbound_payment(payment_hash, payment);
+        }
+    }
+
+    pub(crate) fn update_outbound_payment(
+        &self,
+        payment_id: PaymentId,
+        status: HTLCStatus,
+        preimage: Option<PaymentPreimage>,
+    ) -> PaymentInfo {
+        let mut outbound = self.get_outbound_payments();
+        let outbound_payment = outbound.payments.get_mut(&payment_id).unwrap();
+        if status == HTLCStatus::Succeeded && outbound_payment.status != HTLCStatus::Succeeded {
+            outbound_payment.settled_at = Some(get_current_timestamp());
+        }
+        outbound_payment.status = status;
+        outbound_payment.preimage = preimage;
+        self.save_outbound_payment(&payment_id, outbound_payment);
+        let payment = outbound_payment.clone();
+        drop(outbound);
+        if status != HTLCStatus::Pending {
+            self.outbound_reservations.release(&payment_id);
+        }
+        payment
+    }
+
+    pub(crate) fn update_outbound_payment_failure(
+        &self,
+        payment_id: PaymentId,
+        failure_reason: FailureReason,
+        failing_hop: Option<FailingHop>,
+    ) {
+        let mut outbound = self.get_outbound_payments();
+        if let Some(payment) = outbound.payments.get_mut(&payment_id) {
+            payment.failure_reason = Some(failure_reason);
+            payment.failing_hop = failing_hop;
+            self.save_outbound_payment(&payment_id, payment);
+        }
+    }
+
+    /// Record a path taken by a successful outbound payment, as a new part of it
+    fn add_outbound_payment_path(&self, payment_id: PaymentId, path: &RoutePath) {
+        let mut outbound = self.get_outbound_payments();
+        let Some(payment) = outbound.payments.get_mut(&payment_id) else {
+            return;
+        };
+        let part = payment.hops.iter().map(|h| h.part + 1).max().unwrap_or(0);
+        let last = path.hops.len().saturating_sub(1);
+        payment
+            .hops
+            .extend(path.hops.iter().enumerate().map(|(i, hop)| PaymentHop {
+                part,
+                node_pubkey: hop.pubkey.to_string(),
+                short_channel_id: hop.short_channel_id,
+                // the last hop carries the amount received by the recipient
+                fee_msat: if i == last { 0 } else { hop.fee_msat },
+            }));
+        self.save_outbound_payment(&payment_id, payment);
+    }
+
+    /// Mark a failed outbound payment as paid on-chain, to the fallback address of its invoice
+    pub(crate) fn settle_outbound_payment_onchain(&self, payment_id: PaymentId, txid: String) {
+        let mut outbound = self.get_outbound_payments();
+        if let Some(payment) = outbound.payments.get_mut(&payment_id) {
+            payment.status = HTLCStatus::Succeeded;
+            payment.settled_at = Some(get_current_timestamp());
+            payment.failure_reason = None;
+            payment.failing_hop = None;
+            payment.onchain_txid = Some(txid);
+            self.save_outbound_payment(&payment_id, payment);
+        }
+        drop(outbound);
+        self.outbound_reservations.release(&payment_id);
+    }
+
+    /// Mark an outbound payment as failed, keeping the reason of the last path failure if any
+    pub(crate) fn fail_outbound_payment(
+        &self,
+        static_state: &StaticState,
+        payment_id: PaymentId,
+        failure_reason: FailureReason,
+        failing_hop: Option<FailingHop>,
+    ) {
+        let mut outbound = self.get_outbound_payments();
+        let Some(payment) = outbound.payments.get_mut(&payment_id) else {
+            return;
+        };
+        if payment.failure_reason.is_none() {
+            payment.failure_reason = Some(self.rgb_failure_reason(
+                &static_state.ldk_data_dir,
+                &PaymentHash(payment_id.0),
+                failure_reason,
+            ));
+            payment.failing_hop = failing_hop;
+        }
+        payment.status = HTLCStatus::Failed;
+        self.save_outbound_payment(&payment_id, payment);
+        let (failure_reason, failing_hop) = (payment.failure_reason, payment.failing_hop.clone());
+        drop(outbound);
+        self.release_spending(&payment_id);
+        self.outbound_reservations.release(&payment_id);
+
+        static_state.emit_event(NodeEvent::PaymentFailed {
+            payment_hash: hex_str(&payment_id.0),
+            failure_reason: failure_reason.unwrap(),
+            failing_hop,
+        });
+    }
+
+    /// Open a channel funded with an asset, returning the temporary channel ID
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn open_colored_channel(
+        &self,
+        static_state: &StaticState,
+        peer_pubkey: PublicKey,
+        capacity_sat: u64,
+        push_msat: u64,
+        rgb_info: RgbInfo,
+        fee_rate: ChannelFeeRate,
+        config: UserConfig,
+    ) -> Result<String, APIError> {
+        let spendable_rgb_amount = self.rgb_get_asset_balance(rgb_info.contract_id)?.spendable;
+        if rgb_info.local_rgb_amount > spendable_rgb_amount {
+            return Err(APIError::InsufficientAssets(spendable_rgb_amount));
+        }
+
+        // the peer fetches the funding consignment from this endpoint only
+        let consignment_endpoint = preferred_proxy_endpoint(static_state);
+        let temporary_channel_id = self
+            .channel_manager
+            .create_channel(
+                peer_pubkey,
+                capacity_sat,
+                push_msat,
+                0,
+                Some(config),
+                Some(RgbTransport::from_str(&consignment_endpoint).unwrap()),
+            )
+            .map_err(|e| APIError::FailedOpenChannel(format!("{:?}", e)))?;
+        tracing::info!("EVENT: initiated channel with peer {}", peer_pubkey);
+
+        let temporary_channel_id = temporary_channel_id.to_hex();
+        let ldk_data_dir_path = PathBuf::from(&static_state.ldk_data_dir);
+        write_rgb_channel_info(
+            &get_rgb_channel_info_path(&temporary_channel_id, &ldk_data_dir_path, true),
+            &rgb_info,
+        );
+        write_rgb_channel_info(
+            &get_rgb_channel_info_path(&temporary_channel_id, &ldk_data_dir_path, false),
+            &rgb_info,
+        );
+        self.pending_channel_opens.lock().unwrap().insert(
+            temporary_channel_id.clone(),
+            PendingChannelOpen {
+                peer_pubkey,
+                asset_id: rgb_info.contract_id.to_string(),
+                asset_amount: rgb_info.local_rgb_amount,
+                fee_rate,
+                started_at: get_current_timestamp(),
+                consignment_endpoint,
+                min_depth: config.channel_handshake_config.minimum_depth,
+                funding_txid: None,
+                channel_id: None,
+                failure: None,
+            },
+        );
+        send_channel_proposal(
+            self,
+            peer_pubkey,
+            ChannelProposal {
+                temporary_channel_id: temporary_channel_id.clone(),
+                asset_id: rgb_info.contract_id.to_string(),
+                asset_amount: rgb_info.local_rgb_amount,
+            },
+        );
+
+        Ok(temporary_channel_id)
+    }
+
+    /// Get how many msat and, for the given asset, how many asset units can be received over the
+    /// usable channels (only the ones carrying the asset when one is given)
+    pub(crate) fn inbound_liquidity(
+        &self,
+        ldk_data_dir: &str,
+        contract_id: Option<ContractId>,
+    ) -> (u64, u64) {
+        let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
+        let mut inbound_msat = 0;
+        let mut inbound_asset = 0;
+        for chan_info in self.channel_manager.list_usable_channels() {
+            if let Some(contract_id) = contract_id {
+                let info_file_path = get_rgb_channel_info_path(
+                    &chan_info.channel_id.to_hex(),
+                    &ldk_data_dir_path,
+                    false,
+                );
+                if !info_file_path.exists() {
+                    continue;
+                }
+                let rgb_info = parse_rgb_channel_info(&info_file_path);
+                if rgb_info.contract_id != contract_id {
+                    continue;
+                }
+                inbound_asset += rgb_info.remote_rgb_amount;
+            }
+            inbound_msat += chan_info.inbound_capacity_msat;
+        }
+        (inbound_msat, inbound_asset)
+    }
+
+    /// The usable channels carrying an asset
+    pub(crate) fn colored_channels(
+        &self,
+        ldk_data_dir: &str,
+        contract_id: ContractId,
+    ) -> Vec<ChannelDetails> {
+        let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
+        self.channel_manager
+            .list_usable_channels()
+            .into_iter()
+            .filter(|chan_info| {
+                let info_file_path = get_rgb_channel_info_path(
+                    &chan_info.channel_id.to_hex(),
+                    &ldk_data_dir_path,
+                    false,
+                );
+                info_file_path.exists()
+                    && parse_rgb_channel_info(&info_file_path).contract_id == contract_id
+            })
+            .collect()
+    }
+
+    /// Lowest msat amount of a colored HTLC through the usable channels of an asset, in the given
+    /// direction, None if no usable channel carries the asset
+    pub(crate) fn colored_htlc_min_msat(
+        &self,
+        ldk_data_dir: &str,
+        contract_id: ContractId,
+        outbound: bool,
+    ) -> Option<u64> {
+        self.colored_channels(ldk_data_dir, contract_id)
+            .iter()
+            .map(|chan_info| {
+                if outbound {
+                    htlc_min_sendable_msat(chan_info)
+                } else {
+                    htlc_min_receivable_msat(chan_info)
+                }
+            })
+            .min()
+    }
+
+    /// Highest msat amount of a single colored HTLC the node can send through the usable channels
+    /// of an asset, None if no usable channel carries the asset
+    pub(crate) fn colored_htlc_max_sendable_msat(
+        &self,
+        ldk_data_dir: &str,
+        contract_id: ContractId,
+    ) -> Option<u64> {
+        self.colored_channels(ldk_data_dir, contract_id)
+            .iter()
+            .map(|chan_info| chan_info.next_outbound_htlc_limit_msat)
+            .max()
+    }
+
+    /// Check a colored payment can leave through a usable channel of its asset, as a single HTLC
+    /// carrying the whole asset amount, before sending it
+    ///
+    /// The asset must be known to the wallet, the payment would otherwise only fail once no
+    /// route is found.
+    pub(crate) fn check_colored_payable(
+        &self,
+        ldk_data_dir: &str,
+        contract_id: ContractId,
+        amt_msat: u64,
+        asset_amount: u64,
+    ) -> Result<(), APIError> {
+        self.rgb_get_asset_balance(contract_id)?;
+
+        let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
+        let channels: Vec<(u64, u64)> = self
+            .colored_channels(ldk_data_dir, contract_id)
+            .iter()
+            .map(|chan_info| {
+                let info_file_path = get_rgb_channel_info_path(
+                    &chan_info.channel_id.to_hex(),
+                    &ldk_data_dir_path,
+                    false,
+                );
+                (
+                    parse_rgb_channel_info(&info_file_path).local_rgb_amount,
+                    chan_info.next_outbound_htlc_limit_msat,
+                )
+            })
+            .collect();
+        let max_asset_amount = channels
+            .iter()
+            .map(|(amount, _)| *amount)
+            .max()
+            .unwrap_or(0);
+        if asset_amount > max_asset_amount {
+            return Err(APIError::InsufficientOutboundAssetLiquidity(
+                max_asset_amount,
+            ));
+        }
+        // the msat must go through one of the channels carrying enough of the asset
+        let max_msat = channels
+            .iter()
+            .filter(|(amount, _)| *amount >= asset_amount)
+            .map(|(_, msat)| *msat)
+            .max()
+            .unwrap_or(0);
+        if amt_msat > max_msat {
+            return Err(APIError::InsufficientOutboundLiquidity(format!(
+                "{max_msat} msat"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Smallest msat amount a colored keysend of an asset to a node can carry
+    ///
+    /// When the node is a channel peer only the channels with it are considered, otherwise the
+    /// minimum of the first hop is used and the payment fails if a later hop requires more.
+    pub(crate) fn colored_keysend_min_msat(
+        &self,
+        ldk_data_dir: &str,
+        contract_id: ContractId,
+        dest_pubkey: &PublicKey,
+    ) -> Option<u64> {
+        let channels = self.colored_channels(ldk_data_dir, contract_id);
+        let direct_min = channels
+            .iter()
+            .filter(|c| c.counterparty.node_id == *dest_pubkey)
+            .map(htlc_min_sendable_msat)
+            .min();
+        direct_min.or_else(|| channels.iter().map(htlc_min_sendable_msat).min())
+    }
+
+    /// Tell apart failures of the RGB layer for colored payments
+    pub(crate) fn rgb_failure_reason(
+        &self,
+        ldk_data_dir: &str,
+        payment_hash: &PaymentHash,
+        failure_reason: FailureReason,
+    ) -> FailureReason {
+        let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
+        let rgb_payment_info_path = get_rgb_payment_info_path(payment_hash, &ldk_data_dir_path);
+        if !rgb_payment_info_path.exists() {
+            return failure_reason;
+        }
+        match failure_reason {
+            FailureReason::IncorrectPaymentDetails => FailureReason::RgbIncorrectPaymentDetails,
+            FailureReason::RouteNotFound => {
+                let rgb_payment_info = parse_rgb_payment_info(&rgb_payment_info_path);
+                let has_asset_liquidity =
+                    self.channel_manager
+                        .list_usable_channels()
+                        .iter()
+                        .any(|chan_info| {
+                            let info_file_path = get_rgb_channel_info_path(
+                                &chan_info.channel_id.to_hex(),
+                                &ldk_data_dir_path,
+                                false,
+                            );
+                            if !info_file_path.exists() {
+                                return false;
+                            }
+                            let rgb_info = parse_rgb_channel_info(&info_file_path);
+                            rgb_info.contract_id == rgb_payment_info.contract_id
+                                && rgb_info.local_rgb_amount >= rgb_payment_info.amount
+                        });
+                if has_asset_liquidity {
+                    failure_reason
+                } else {
+                    FailureReason::RgbInsufficientAssetLiquidity
+                }
+            }
+            _ => failure_reason,
+        }
+    }
+
+    pub(crate) fn update_outbound_payment_status(&self, payment_id: PaymentId, status: HTLCStatus) {
+        let mut outbound = self.get_outbound_payments();
+        let payment = outbound.payments.get_mut(&payment_id).unwrap();
+        payment.status = status;
+        self.save_outbound_payment(&payment_id, payment);
+        drop(outbound);
+        if status == HTLCStatus::Failed {
+            self.release_spending(&payment_id);
+        }
+        if status != HTLCStatus::Pending {
+            self.outbound_reservations.release(&payment_id);
+        }
+    }
+}
+
+pub(crate) type ChainMonitor = chainmonitor::ChainMonitor<
+    InMemorySigner,
+    Arc<dyn Filter + Send + Sync>,
+    Arc<BitcoindClient>,
+    Arc<BitcoindClient>,
+    Arc<FilesystemLogger>,
+    Arc<
+        MonitorUpdatingPersister<
+            Arc<MirroredStore>,
+            Arc<FilesystemLogger>,
+            Arc<KeysManager>,
+            Arc<KeysManager>,
+        >,
+    >,
+>;
+
+pub(crate) type GossipVerifier = lightning_block_sync::gossip::GossipVerifier<
+    lightning_block_sync::gossip::TokioSpawner,
+    Arc<lightning_block_sync::rpc::RpcClient>,
+    Arc<FilesystemLogger>,
+    SocketDescriptor,
+    Arc<ChannelMessageGate>,
+    Arc<OnionMessageGate>,
+    Arc<PeerMessageHandler>,
+    Arc<KeysManager>,
+>;
+
+pub(crate) type PeerManager = LdkPeerManager<
+    SocketDescriptor,
+    Arc<ChannelMessageGate>,
+    Arc<GossipGate>,
+    Arc<OnionMessageGate>,
+    Arc<FilesystemLogger>,
+    Arc<PeerMessageHandler>,
+    Arc<KeysManager>,
+>;
+
+pub(crate) type GossipSyncHandler =
+    P2PGossipSync<Arc<NetworkGraph>, Arc<GossipVerifier>, Arc<FilesystemLogger>>;
+
+pub(crate) type ChannelManager =
+    SimpleArcChannelManager<ChainMonitor, BitcoindClient, BitcoindClient, FilesystemLogger>;
+
+pub(crate) type NetworkGraph = gossip::NetworkGraph<Arc<FilesystemLogger>>;
+
+pub(crate) type Scorer = ProbabilisticScorer<Arc<NetworkGraph>, Arc<FilesystemLogger>>;
+
+pub(crate) type Router = DefaultRouter<
+    Arc<NetworkGraph>,
+    Arc<FilesystemLogger>,
+    Arc<RwLock<Scorer>>,
+    ProbabilisticScoringFeeParameters,
+    Scorer,
+>;
+
+pub(crate) type OnionMessenger = lightning::onion_message::OnionMessenger<
+    Arc<KeysManager>,
+    Arc<KeysManager>,
+    Arc<FilesystemLogger>,
+    Arc<DefaultMessageRouter>,
+    Arc<ChannelManager>,
+    Arc<UserOnionMessageHandler>,
+>;
+
+/// Onion message handler dropping the messages to relay when forwarding is disabled
+pub(crate) struct OnionMessageGate {
+    onion_messenger: Arc<OnionMessenger>,
+    keys_manager: Arc<KeysManager>,
+    logger: Arc<FilesystemLogger>,
+    custom_handler: Arc<UserOnionMessageHandler>,
+    forwarding: bool,
+    secp_ctx: Secp256k1<All>,
+    /// Only handler given the peers' init messages, forwarded to record their features
+    peer_message_handler: Arc<PeerMessageHandler>,
+    peer_limiter: Arc<PeerLimiter>,
+    /// Records the connections of the peers and reports them as events
+    database: Arc<Database>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+    /// Counts the peers the announcements of the node get broadcast to
+    announcements: Arc<Announcements>,
+}
+
+impl OnionMessageProvider for OnionMessageGate {
+    fn next_onion_message_for_peer(&self, peer_node_id: PublicKey) -> Option<OnionMessage> {
+        self.onion_messenger
+            .next_onion_message_for_peer(peer_node_id)
+    }
+}
+
+impl OnionMessageHandler for OnionMessageGate {
+    fn handle_onion_message(&self, peer_node_id: &PublicKey, msg: &OnionMessage) {
+        if !self.peer_limiter.record_message(peer_node_id, 0) {
+            return;
+        }
+        if !self.forwarding {
+            if let Ok(PeeledOnion::Forward(next_node_id, _)) = peel_onion_message(
+                msg,
+                &self.secp_ctx,
+                &*self.keys_manager,
+                &*self.logger,
+                &*self.custom_handler,
+            ) {
+                tracing::debug!(
+                    "Dropping onion message from {peer_node_id} to {next_node_id}, forwarding is disabled"
+                );
+                return;
+            }
+        }
+        self.onion_messenger.handle_onion_message(peer_node_id, msg)
+    }
+
+    fn peer_connected(
+        &self,
+        their_node_id: &PublicKey,
+        init: &Init,
+        inbound: bool,
+    ) -> Result<(), ()> {
+        if self.peer_limiter.is_banned(their_node_id) {
+            tracing::debug!("Rejecting banned peer {their_node_id}");
+            return Err(());
+        }
+        self.peer_message_handler
+            .peer_connected(*their_node_id, &init.features);
+        self.onion_messenger
+            .peer_connected(their_node_id, init, inbound)?;
+        let peer_pubkey = their_node_id.to_string();
+        if let Err(e) = self
+            .database
+            .set_peer_connected(&peer_pubkey, get_current_timestamp())
+        {
+            tracing::error!("Failed to record the connection of peer {peer_pubkey}: {e}");
+        }
+        self.announcements.peer_connected(*their_node_id);
+        self.static_state
+            .emit_event(NodeEvent::PeerConnected { peer_pubkey });
+        Ok(())
+    }
+
+    fn peer_disconnected(&self, their_node_id: &PublicKey) {
+        self.peer_message_handler.peer_disconnected(their_node_id);
+        self.peer_limiter.peer_disconnected(their_node_id);
+        self.onion_messenger.peer_disconnected(their_node_id);
+        self.announcements.peer_disconnected(their_node_id);
+        let peer_pubkey = their_node_id.to_string();
+        // peers get disconnected when the node stops, which isn't on them
+        let counted = !self.stop_processing.load(Ordering::Acquire);
+        if let Err(e) =
+            self.database
+                .set_peer_disconnected(&peer_pubkey, get_current_timestamp(), counted)
+        {
+            tracing::error!("Failed to record the disconnection of peer {peer_pubkey}: {e}");
+        }
+        self.static_state
+            .emit_event(NodeEvent::PeerDisconnected { peer_pubkey });
+    }
+
+    fn provided_node_features(&self) -> NodeFeatures {
+        self.onion_messenger.provided_node_features()
+    }
+
+    fn provided_init_features(&self, their_node_id: &PublicKey) -> InitFeatures {
+        self.onion_messenger.provided_init_features(their_node_id)
+    }
+}
+
+/// Gossip handler accounting the gossip queries and replies of each peer against its rate limits
+///
+/// Announcements and updates reach the handler without the peer they come from, so they can only
+/// be passed through. The new ones get relayed to all the peers, so the ones about the node's
+/// channels are recorded as broadcast, whether the channel manager or the counterparty made them.
+pub(crate) struct GossipGate {
+    gossip_sync: Arc<GossipSyncHandler>,
+    peer_limiter: Arc<PeerLimiter>,
+    network_graph: Arc<NetworkGraph>,
+    announcements: Arc<Announcements>,
+    our_node_id: NodeId,
+}
+
+impl GossipGate {
+    fn allow<M: Writeable>(&self, their_node_id: &PublicKey, msg: &M) -> bool {
+        self.peer_limiter
+            .record_message(their_node_id, msg.serialized_length() as u64)
+    }
+
+    /// Whether the update comes from the node, its direction telling which end of the channel
+    /// made it
+    fn is_our_update(&self, msg: &ChannelUpdate) -> bool {
+        let graph = self.network_graph.read_only();
+        let Some(channel) = graph.channel(msg.contents.short_channel_id) else {
+            return false;
+        };
+        let origin = if msg.contents.flags & 1 == 0 {
+            channel.node_one
+        } else {
+            channel.node_two
+        };
+        origin == self.our_node_id
+    }
+}
+
+impl MessageSendEventsProvider for GossipGate {
+    fn get_and_clear_pending_msg_events(&self) -> Vec<MessageSendEvent> {
+        let mut events = self.gossip_sync.get_and_clear_pending_msg_events();
+        events.extend(self.announcements.take_rebroadcasts());
+        events
+    }
+}
+
+impl RoutingMessageHandler for GossipGate {
+    fn handle_node_announcement(&self, msg: &NodeAnnouncement) -> Result<bool, LightningError> {
+        self.gossip_sync.handle_node_announcement(msg)
+    }
+
+    fn handle_channel_announcement(
+        &self,
+        msg: &ChannelAnnouncement,
+    ) -> Result<bool, LightningError> {
+        let result = self.gossip_sync.handle_channel_announcement(msg);
+        if matches!(result, Ok(true))
+            && (msg.contents.node_id_1 == self.our_node_id
+                || msg.contents.node_id_2 == self.our_node_id)
+        {
+            self.announcements
+                .channel_announcement_broadcast(msg.contents.short_channel_id);
+        }
+        result
+    }
+
+    fn handle_channel_update(&self, msg: &ChannelUpdate) -> Result<bool, LightningError> {
+        let result = self.gossip_sync.handle_channel_update(msg);
+        if matches!(result, Ok(true)) && self.is_our_update(msg) {
+            self.announcements
+                .channel_update_broadcast(msg.contents.short_channel_id);
+        }
+        result
+    }
+
+    fn get_next_channel_announcement(
+        &self,
+        starting_point: u64,
+    ) -> Option<(
+        ChannelAnnouncement,
+        Option<ChannelUpdate>,
+        Option<ChannelUpdate>,
+    )> {
+        self.gossip_sync
+            .get_next_channel_announcement(starting_point)
+    }
+
+    fn get_next_node_announcement(
+        &self,
+        starting_point: Option<&NodeId>,
+    ) -> Option<NodeAnnouncement> {
+        self.gossip_sync.get_next_node_announcement(starting_point)
+    }
+
+    fn peer_connected(
+        &self,
+        their_node_id: &PublicKey,
+        init: &Init,
+        inbound: bool,
+    ) -> Result<(), ()> {
+        self.gossip_sync
+            .peer_connected(their_node_id, init, inbound)
+    }
+
+    fn handle_reply_channel_range(
+        &self,
+        their_node_id: &PublicKey,
+        msg: ReplyChannelRange,
+    ) -> Result<(), LightningError> {
+        if !self.allow(their_node_id, &msg) {
+            return Ok(());
+        }
+        self.gossip_sync
+            .handle_reply_channel_range(their_node_id, msg)
+    }
+
+    fn handle_reply_short_channel_ids_end(
+        &self,
+        their_node_id: &PublicKey,
+        msg: ReplyShortChannelIdsEnd,
+    ) -> Result<(), LightningError> {
+        if !self.allow(their_node_id, &msg) {
+            return Ok(());
+        }
+        self.gossip_sync
+            .handle_reply_short_channel_ids_end(their_node_id, msg)
+    }
+
+    fn handle_query_channel_range(
+        &self,
+        their_node_id: &PublicKey,
+        msg: QueryChannelRange,
+    ) -> Result<(), LightningError> {
+        if !self.allow(their_node_id, &msg) {
+            return Ok(());
+        }
+        self.gossip_sync
+            .handle_query_channel_range(their_node_id, msg)
+    }
+
+    fn handle_query_short_channel_ids(
+        &self,
+        their_node_id: &PublicKey,
+        msg: QueryShortChannelIds,
+    ) -> Result<(), LightningError> {
+        if !self.allow(their_node_id, &msg) {
+            return Ok(());
+        }
+        self.gossip_sync
+            .handle_query_short_channel_ids(their_node_id, msg)
+    }
+
+    fn processing_queue_high(&self) -> bool {
+        self.gossip_sync.processing_queue_high()
+    }
+
+    fn provided_node_features(&self) -> NodeFeatures {
+        self.gossip_sync.provided_node_features()
+    }
+
+    fn provided_init_features(&self, their_node_id: &PublicKey) -> InitFeatures {
+        self.gossip_sync.provided_init_features(their_node_id)
+    }
+}
+
+/// Channel message handler recording the HTLCs added to the channels until they get removed
+///
+/// The channel monitors only report the HTLCs with an output on the commitment transactions, so
+/// the ones exchanged with the peers are recorded to also list the dust ones, with their msat
+/// amount. HTLCs the peer adds are removed once the node fulfills or fails them and the other way
+/// around.
+pub(crate) struct ChannelMessageGate {
+    channel_manager: Arc<ChannelManager>,
+    database: Arc<Database>,
+}
+
+impl ChannelMessageGate {
+    fn htlc_added(&self, peer_pubkey: &PublicKey, inbound: bool, msg: &msgs::UpdateAddHTLC) {
+        let record = ChannelHtlcRecord {
+            channel_id: msg.channel_id.to_hex(),
+            inbound,
+            htlc_id: msg.htlc_id,
+            peer_pubkey: peer_pubkey.to_string(),
+            amt_msat: msg.amount_msat,
+            payment_hash: hex_str(&msg.payment_hash.0),
+            cltv_expiry: msg.cltv_expiry,
+        };
+        if let Err(e) = self.database.add_channel_htlc(&record) {
+            tracing::error!("Failed to record an HTLC: {e}");
+        }
+    }
+
+    fn htlc_removed(&self, channel_id: &ChannelId, inbound: bool, htlc_id: u64) {
+        if let Err(e) = self
+            .database
+            .remove_channel_htlc(&channel_id.to_hex(), inbound, htlc_id)
+        {
+            tracing::error!("Failed to forget an HTLC: {e}");
+        }
+    }
+}
+
+impl MessageSendEventsProvider for ChannelMessageGate {
+    fn get_and_clear_pending_msg_events(&self) -> Vec<MessageSendEvent> {
+        let events = self.channel_manager.get_and_clear_pending_msg_events();
+        for event in &events {
+            if let MessageSendEvent::UpdateHTLCs { node_id, updates } = event {
+                for msg in &updates.update_add_htlcs {
+                    self.htlc_added(node_id, false, msg);
+                }
+                for msg in &updates.update_fulfill_htlcs {
+                    self.htlc_removed(&msg.channel_id, true, msg.htlc_id);
+                }
+                for msg in &updates.update_fail_htlcs {
+                    self.htlc_removed(&msg.channel_id, true, msg.htlc_id);
+                }
+                for msg in &updates.update_fail_malformed_htlcs {
+                    self.htlc_removed(&msg.channel_id, true, msg.htlc_id);
+                }
+            }
+        }
+        events
+    }
+}
+
+impl ChannelMessageHandler for ChannelMessageGate {
+    fn handle_open_channel(&self, their_node_id: &PublicKey, msg: &msgs::OpenChannel) {
+        self.channel_manager.handle_open_channel(their_node_id, msg)
+    }
+
+    fn handle_open_channel_v2(&self, their_node_id: &PublicKey, msg: &msgs::OpenChannelV2) {
+        self.channel_manager
+            .handle_open_channel_v2(their_node_id, msg)
+    }
+
+    fn handle_accept_channel(&self, their_node_id: &PublicKey, msg: &msgs::AcceptChannel) {
+        self.channel_manager
+            .handle_accept_channel(their_node_id, msg)
+    }
+
+    fn handle_accept_channel_v2(&self, their_node_id: &PublicKey, msg: &msgs::AcceptChannelV2) {
+        self.channel_manager
+            .handle_accept_channel_v2(their_node_id, msg)
+    }
+
+    fn handle_funding_created(&self, their_node_id: &PublicKey, msg: &msgs::FundingCreated) {
+        self.channel_manager
+            .handle_funding_created(their_node_id, msg)
+    }
+
+    fn handle_funding_signed(&self, their_node_id: &PublicKey, msg: &msgs::FundingSigned) {
+        self.channel_manager
+            .handle_funding_signed(their_node_id, msg)
+    }
+
+    fn handle_channel_ready(&self, their_node_id: &PublicKey, msg: &msgs::ChannelReady) {
+        self.channel_manager
+            .handle_channel_ready(their_node_id, msg)
+    }
+
+    fn handle_shutdown(&self, their_node_id: &PublicKey, msg: &msgs::Shutdown) {
+        self.channel_manager.handle_shutdown(their_node_id, msg)
+    }
+
+    fn handle_closing_signed(&self, their_node_id: &PublicKey, msg: &msgs::ClosingSigned) {
+        self.channel_manager
+            .handle_closing_signed(their_node_id, msg)
+    }
+
+    fn handle_tx_add_input(&self, their_node_id: &PublicKey, msg: &msgs::TxAddInput) {
+        self.channel_manager.handle_tx_add_input(their_node_id, msg)
+    }
+
+    fn handle_tx_add_output(&self, their_node_id: &PublicKey, msg: &msgs::TxAddOutput) {
+        self.channel_manager
+            .handle_tx_add_output(their_node_id, msg)
+    }
+
+    fn handle_tx_remove_input(&self, their_node_id: &PublicKey, msg: &msgs::TxRemoveInput) {
+        self.channel_manager
+            .handle_tx_remove_input(their_node_id, msg)
+    }
+
+    fn handle_tx_remove_output(&self, their_node_id: &PublicKey, msg: &msgs::TxRemoveOutput) {
+        self.channel_manager
+            .handle_tx_remove_output(their_node_id, msg)
+    }
+
+    fn handle_tx_complete(&self, their_node_id: &PublicKey, msg: &msgs::TxComplete) {
+        self.channel_manager.handle_tx_complete(their_node_id, msg)
+    }
+
+    fn handle_tx_signatures(&self, their_node_id: &PublicKey, msg: &msgs::TxSignatures) {
+        self.channel_manager
+            .handle_tx_signatures(their_node_id, msg)
+    }
+
+    fn handle_tx_init_rbf(&self, their_node_id: &PublicKey, msg: &msgs::TxInitRbf) {
+        self.channel_manager.handle_tx_init_rbf(their_node_id, msg)
+    }
 
-pub(crate) struct OutboundPaymentInfoStorage {
-    pub(crate) payments: HashMap<PaymentId, PaymentInfo>,
-}
+    fn handle_tx_ack_rbf(&self, their_node_id: &PublicKey, msg: &msgs::TxAckRbf) {
+        self.channel_manager.handle_tx_ack_rbf(their_node_id, msg)
+    }
 
-impl_writeable_tlv_based!(OutboundPaymentInfoStorage, {
-    (0, payments, required),
-});
+    fn handle_tx_abort(&self, their_node_id: &PublicKey, msg: &msgs::TxAbort) {
+        self.channel_manager.handle_tx_abort(their_node_id, msg)
+    }
 
-impl UnlockedAppState {
-    pub(crate) fn add_inbound_payment(&self, payment_hash: PaymentHash, payment_info: PaymentInfo) {
-        let mut inbound = self.get_inbound_payments();
-        inbound.payments.insert(payment_hash, payment_info);
-        self.save_inbound_payments(inbound);
+    fn handle_update_add_htlc(&self, their_node_id: &PublicKey, msg: &msgs::UpdateAddHTLC) {
+        self.htlc_added(their_node_id, true, msg);
+        self.channel_manager
+            .handle_update_add_htlc(their_node_id, msg)
     }
 
-    pub(crate) fn add_outbound_payment(&self, payment_id: PaymentId, payment_info: PaymentInfo) {
-        let mut outbound = self.get_outbound_payments();
-        outbound.payments.insert(payment_id, payment_info);
-        self.save_outbound_payments(outbound);
+    fn handle_update_fulfill_htlc(&self, their_node_id: &PublicKey, msg: &msgs::UpdateFulfillHTLC) {
+        self.htlc_removed(&msg.channel_id, false, msg.htlc_id);
+        self.channel_manager
+            .handle_update_fulfill_htlc(their_node_id, msg)
     }
 
-    fn fail_outbound_pending_payments(&self, recent_payments_payment_ids: Vec<PaymentId>) {
-        let mut outbound = self.get_outbound_payments();
-        for (payment_id, payment_info) in outbound
-            .payments
-            .iter_mut()
-            .filter(|(_, i)| matches!(i.status, HTLCStatus::Pending))
-        {
-            if !recent_payments_payment_ids.contains(payment_id) {
-                payment_info.status = HTLCStatus::Failed;
-            }
-        }
-        self.save_outbound_payments(outbound);
+    fn handle_update_fail_htlc(&self, their_node_id: &PublicKey, msg: &msgs::UpdateFailHTLC) {
+        self.htlc_removed(&msg.channel_id, false, msg.htlc_id);
+        self.channel_manager
+            .handle_update_fail_htlc(their_node_id, msg)
     }
 
-    pub(crate) fn inbound_payments(&self) -> HashMap<PaymentHash, PaymentInfo> {
-        self.get_inbound_payments().payments.clone()
+    fn handle_update_fail_malformed_htlc(
+        &self,
+        their_node_id: &PublicKey,
+        msg: &msgs::UpdateFailMalformedHTLC,
+    ) {
+        self.htlc_removed(&msg.channel_id, false, msg.htlc_id);
+        self.channel_manager
+            .handle_update_fail_malformed_htlc(their_node_id, msg)
     }
 
-    pub(crate) fn outbound_payments(&self) -> HashMap<PaymentId, PaymentInfo> {
-        self.get_outbound_payments().payments.clone()
+    fn handle_commitment_signed(&self, their_node_id: &PublicKey, msg: &msgs::CommitmentSigned) {
+        self.channel_manager
+            .handle_commitment_signed(their_node_id, msg)
     }
 
-    fn save_inbound_payments(&self, inbound: MutexGuard<InboundPaymentInfoStorage>) {
-        self.fs_store
-            .write("", "", INBOUND_PAYMENTS_FNAME, &inbound.encode())
-            .unwrap();
+    fn handle_revoke_and_ack(&self, their_node_id: &PublicKey, msg: &msgs::RevokeAndACK) {
+        self.channel_manager
+            .handle_revoke_and_ack(their_node_id, msg)
     }
 
-    fn save_outbound_payments(&self, outbound: MutexGuard<OutboundPaymentInfoStorage>) {
-        self.fs_store
-            .write("", "", OUTBOUND_PAYMENTS_FNAME, &outbound.encode())
-            .unwrap();
+    fn handle_update_fee(&self, their_node_id: &PublicKey, msg: &msgs::UpdateFee) {
+        self.channel_manager.handle_update_fee(their_node_id, msg)
     }
 
-    fn upsert_inbound_payment(
+    fn handle_announcement_signatures(
         &self,
-        payment_hash: PaymentHash,
-        status: HTLCStatus,
-        preimage: Option<PaymentPreimage>,
-        secret: Option<PaymentSecret>,
-        amt_msat: Option<u64>,
+        their_node_id: &PublicKey,
+        msg: &msgs::AnnouncementSignatures,
     ) {
-        let mut inbound = self.get_inbound_payments();
-        match inbound.payments.entry(payment_hash) {
-            Entry::Occupied(mut e) => {
-                let payment = e.get_mut();
-                payment.status = status;
-                payment.preimage = preimage;
-                payment.secret = secret;
-            }
-            Entry::Vacant(e) => {
-                e.insert(PaymentInfo {
-                    preimage,
-                    secret,
-                    status,
-                    amt_msat,
-                });
-            }
-        }
-        self.save_inbound_payments(inbound);
+        self.channel_manager
+            .handle_announcement_signatures(their_node_id, msg)
     }
 
-    pub(crate) fn update_outbound_payment(
-        &self,
-        payment_id: PaymentId,
-        status: HTLCStatus,
-        preimage: Option<PaymentPreimage>,
-    ) -> PaymentInfo {
-        let mut outbound = self.get_outbound_payments();
-        let outbound_payment = outbound.payments.get_mut(&payment_id).unwrap();
-        outbound_payment.status = status;
-        outbound_payment.preimage = preimage;
-        let payment = (*outbound_payment).clone();
-        self.save_outbound_payments(outbound);
-        payment
+    fn peer_disconnected(&self, their_node_id: &PublicKey) {
+        ChannelMessageHandler::peer_disconnected(&*self.channel_manager, their_node_id)
     }
 
-    pub(crate) fn update_outbound_payment_status(&self, payment_id: PaymentId, status: HTLCStatus) {
-        let mut outbound = self.get_outbound_payments();
-        outbound.payments.get_mut(&payment_id).unwrap().status = status;
-        self.save_outbound_payments(outbound);
+    fn peer_connected(
+        &self,
+        their_node_id: &PublicKey,
+        init: &Init,
+        inbound: bool,
+    ) -> Result<(), ()> {
+        ChannelMessageHandler::peer_connected(&*self.channel_manager, their_node_id, init, inbound)
     }
-}
 
-type ChainMonitor = chainmonitor::ChainMonitor<
-    InMemorySigner,
-    Arc<dyn Filter + Send + Sync>,
-    Arc<BitcoindClient>,
-    Arc<BitcoindClient>,
-    Arc<FilesystemLogger>,
-    Arc<
-        MonitorUpdatingPersister<
-            Arc<FilesystemStore>,
-            Arc<FilesystemLogger>,
-            Arc<KeysManager>,
-            Arc<KeysManager>,
-        >,
-    >,
->;
+    fn handle_channel_reestablish(
+        &self,
+        their_node_id: &PublicKey,
+        msg: &msgs::ChannelReestablish,
+    ) {
+        self.channel_manager
+            .handle_channel_reestablish(their_node_id, msg)
+    }
 
-pub(crate) type GossipVerifier = lightning_block_sync::gossip::GossipVerifier<
-    lightning_block_sync::gossip::TokioSpawner,
-    Arc<lightning_block_sync::rpc::RpcClient>,
-    Arc<FilesystemLogger>,
-    SocketDescriptor,
-    Arc<ChannelManager>,
-    Arc<OnionMessenger>,
-    IgnoringMessageHandler,
-    Arc<KeysManager>,
->;
+    fn handle_channel_update(&self, their_node_id: &PublicKey, msg: &ChannelUpdate) {
+        ChannelMessageHandler::handle_channel_update(&*self.channel_manager, their_node_id, msg)
+    }
 
-pub(crate) type PeerManager = SimpleArcPeerManager<
-    SocketDescriptor,
-    ChainMonitor,
-    BitcoindClient,
-    BitcoindClient,
-    GossipVerifier,
-    FilesystemLogger,
->;
+    fn handle_error(&self, their_node_id: &PublicKey, msg: &msgs::ErrorMessage) {
+        self.channel_manager.handle_error(their_node_id, msg)
+    }
 
-pub(crate) type ChannelManager =
-    SimpleArcChannelManager<ChainMonitor, BitcoindClient, BitcoindClient, FilesystemLogger>;
+    fn provided_node_features(&self) -> NodeFeatures {
+        ChannelMessageHandler::provided_node_features(&*self.channel_manager)
+    }
 
-pub(crate) type NetworkGraph = gossip::NetworkGraph<Arc<FilesystemLogger>>;
+    fn provided_init_features(&self, their_node_id: &PublicKey) -> InitFeatures {
+        ChannelMessageHandler::provided_init_features(&*self.channel_manager, their_node_id)
+    }
 
-pub(crate) type OnionMessenger =
-    SimpleArcOnionMessenger<ChainMonitor, BitcoindClient, BitcoindClient, FilesystemLogger>;
+    fn get_genesis_hashes(&self) -> Option<Vec<ChainHash>> {
+        self.channel_manager.get_genesis_hashes()
+    }
+}
 
 pub(crate) type BumpTxEventHandler = BumpTransactionEventHandler<
     Arc<BitcoindClient>,
@@ -267,6 +1697,241 @@ pub(crate) type BumpTxEventHandler = BumpTransactionEventHandler<
     Arc<FilesystemLogger>,
 >;
 
+/// Broadcast our node_announcement, returning whether it has been sent
+pub(crate) fn announce_node(
+    peer_manager: &PeerManager,
+    channel_manager: &ChannelManager,
+    announcements: &Announcements,
+    static_state: &StaticState,
+) -> bool {
+    // Don't bother trying to announce if we don't have any public channls, though our
+    // peers should drop such an announcement anyway. Note that announcement may not
+    // propagate until we have a channel with 6+ confirmations.
+    if !channel_manager
+        .list_channels()
+        .iter()
+        .any(|chan| chan.is_public)
+    {
+        return false;
+    }
+    let alias = static_state
+        .ldk_announced_node_alias
+        .lock()
+        .unwrap()
+        .clone();
+    peer_manager.broadcast_node_announcement(
+        static_state.ldk_announced_node_color,
+        node_alias_bytes(&alias).expect("validated alias"),
+        static_state.ldk_announced_listen_addr.clone(),
+    );
+    announcements.node_broadcast();
+    true
+}
+
+/// Lowest msat amount of an HTLC the node can send through a channel
+///
+/// HTLCs below the dust limit are trimmed from the commitment transactions, leaving no output to
+/// carry their assets, so colored HTLCs never go below HTLC_MIN_MSAT.
+pub(crate) fn htlc_min_sendable_msat(chan_info: &ChannelDetails) -> u64 {
+    chan_info.next_outbound_htlc_minimum_msat.max(HTLC_MIN_MSAT)
+}
+
+/// Lowest msat amount of an HTLC the node accepts through a channel
+pub(crate) fn htlc_min_receivable_msat(chan_info: &ChannelDetails) -> u64 {
+    chan_info
+        .inbound_htlc_minimum_msat
+        .unwrap_or(HTLC_MIN_MSAT)
+        .max(HTLC_MIN_MSAT)
+}
+
+/// Close an outbound channel before funding it, the open then gets rolled back with the failure
+fn drop_channel_open(
+    unlocked_state: &UnlockedAppState,
+    temporary_channel_id: &ChannelId,
+    counterparty_node_id: &PublicKey,
+    failure: String,
+) {
+    tracing::error!(
+        "ERROR: dropping channel {}: {failure}",
+        temporary_channel_id.to_hex()
+    );
+    if let Some(open) = unlocked_state
+        .pending_channel_opens
+        .lock()
+        .unwrap()
+        .get_mut(&temporary_channel_id.to_hex())
+    {
+        open.failure = Some(failure);
+    }
+    let _ = unlocked_state
+        .channel_manager
+        .force_close_without_broadcasting_txn(temporary_channel_id, counterparty_node_id);
+}
+
+/// Config of the channels opened by the node
+pub(crate) fn open_channel_config(
+    public: bool,
+    min_depth: u32,
+    htlc_minimum_msat: u64,
+    with_anchors: bool,
+    channel_config: ChannelConfig,
+    handshake: &ChannelHandshakeDefaults,
+) -> UserConfig {
+    let mut config = UserConfig {
+        channel_handshake_limits: ChannelHandshakeLimits {
+            // lnd's max to_self_delay is 2016, so we want to be compatible.
+            their_to_self_delay: 2016,
+            ..Default::default()
+        },
+        channel_handshake_config: ChannelHandshakeConfig {
+            announced_channel: public,
+            our_htlc_minimum_msat: htlc_minimum_msat,
+            minimum_depth: min_depth,
+            negotiate_anchors_zero_fee_htlc_tx: with_anchors,
+            ..Default::default()
+        },
+        channel_config,
+        ..Default::default()
+    };
+    handshake.apply(&mut config.channel_handshake_config);
+    config
+}
+
+impl From<PaymentFailureReason> for FailureReason {
+    fn from(reason: PaymentFailureReason) -> Self {
+        match reason {
+            PaymentFailureReason::RecipientRejected => FailureReason::IncorrectPaymentDetails,
+            PaymentFailureReason::UserAbandoned => FailureReason::UserAbandoned,
+            PaymentFailureReason::RetriesExhausted => FailureReason::RetriesExhausted,
+            PaymentFailureReason::PaymentExpired => FailureReason::PaymentExpired,
+            PaymentFailureReason::RouteNotFound => FailureReason::RouteNotFound,
+            PaymentFailureReason::UnexpectedError => FailureReason::UnexpectedError,
+        }
+    }
+}
+
+/// The hop at the given position along the path, 0 being this node and the path length being the
+/// recipient, with the channel it failed to forward to
+fn failing_hop(path: &RoutePath, position: usize) -> FailingHop {
+    FailingHop {
+        position: position as u32,
+        node_pubkey: position
+            .checked_sub(1)
+            .and_then(|i| path.hops.get(i))
+            .map(|hop| hop.pubkey.to_string()),
+        short_channel_id: path.hops.get(position).map(|hop| hop.short_channel_id),
+    }
+}
+
+/// Infer which BOLT4 update error was returned by comparing the channel update received in the
+/// onion error with the parameters used to build the path
+fn channel_update_failure_reason(
+    path: &RoutePath,
+    idx: usize,
+    update: &ChannelUpdate,
+) -> FailureReason {
+    let update = &update.contents;
+    let forwarded_msat: u64 = path.hops[idx..].iter().map(|hop| hop.fee_msat).sum();
+    let prev_hop = idx.checked_sub(1).map(|i| &path.hops[i]);
+    let required_fee_msat = update.fee_base_msat as u64
+        + forwarded_msat * update.fee_proportional_millionths as u64 / 1_000_000;
+    if update.flags & 0b10 != 0 {
+        FailureReason::ChannelDisabled
+    } else if forwarded_msat < update.htlc_minimum_msat {
+        FailureReason::AmountBelowMinimum
+    } else if prev_hop.is_some_and(|hop| hop.fee_msat < required_fee_msat) {
+        FailureReason::FeeInsufficient
+    } else if prev_hop.is_some_and(|hop| hop.cltv_expiry_delta < update.cltv_expiry_delta as u32) {
+        FailureReason::IncorrectCltvExpiry
+    } else {
+        FailureReason::TemporaryChannelFailure
+    }
+}
+
+/// Decode the failure of a payment path into a failure reason and the hop that reported it
+fn decode_path_failure(
+    failure: &PathFailure,
+    path: &RoutePath,
+    short_channel_id: Option<u64>,
+    payment_failed_permanently: bool,
+) -> (FailureReason, Option<FailingHop>) {
+    let scid_position = |scid: u64| {
+        path.hops
+            .iter()
+            .position(|hop| hop.short_channel_id == scid)
+    };
+    if payment_failed_permanently {
+        return (
+            FailureReason::IncorrectPaymentDetails,
+            Some(failing_hop(path, path.hops.len())),
+        );
+    }
+    match failure {
+        PathFailure::InitialSend { err } => {
+            let failure_reason = match err {
+                LdkAPIError::ChannelUnavailable { .. } | LdkAPIError::MonitorUpdateInProgress => {
+                    FailureReason::TemporaryChannelFailure
+                }
+                _ => FailureReason::UnexpectedError,
+            };
+            (failure_reason, Some(failing_hop(path, 0)))
+        }
+        PathFailure::OnPath {
+            network_update: Some(NetworkUpdate::ChannelUpdateMessage { msg }),
+        } => match scid_position(msg.contents.short_channel_id) {
+            Some(idx) => (
+                channel_update_failure_reason(path, idx, msg),
+                Some(failing_hop(path, idx)),
+            ),
+            None => (FailureReason::TemporaryChannelFailure, None),
+        },
+        PathFailure::OnPath {
+            network_update:
+                Some(NetworkUpdate::ChannelFailure {
+                    short_channel_id,
+                    is_permanent,
+                }),
+        } => {
+            let failure_reason = if *is_permanent {
+                FailureReason::PermanentChannelFailure
+            } else {
+                FailureReason::TemporaryChannelFailure
+            };
+            (
+                failure_reason,
+                scid_position(*short_channel_id).map(|idx| failing_hop(path, idx)),
+            )
+        }
+        PathFailure::OnPath {
+            network_update:
+                Some(NetworkUpdate::NodeFailure {
+                    node_id,
+                    is_permanent,
+                }),
+        } => {
+            let failure_reason = if *is_permanent {
+                FailureReason::PermanentNodeFailure
+            } else {
+                FailureReason::TemporaryNodeFailure
+            };
+            let position = path
+                .hops
+                .iter()
+                .position(|hop| hop.pubkey == *node_id)
+                .map(|i| i + 1);
+            (failure_reason, position.map(|pos| failing_hop(path, pos)))
+        }
+        PathFailure::OnPath {
+            network_update: None,
+        } => (
+            FailureReason::UnknownPathFailure,
+            short_channel_id
+                .and_then(scid_position)
+                .map(|idx| failing_hop(path, idx)),
+        ),
+    }
+}
+
 fn _update_rgb_channel_amount(ldk_data_dir: &str, payment_hash: &PaymentHash, receiver: bool) {
     let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
     let payment_hash_str = hex_str(&payment_hash.0);
@@ -299,11 +1964,116 @@ fn _update_rgb_channel_amount(ldk_data_dir: &str, payment_hash: &PaymentHash, re
     }
 }
 
+/// Get the RGB payment info files written for each HTLC of an inbound payment, along with the
+/// ID of the channel the HTLC was received on
+fn _rgb_htlc_payment_info_paths(
+    ldk_data_dir_path: &Path,
+    payment_hash: &PaymentHash,
+) -> Vec<(String, PathBuf)> {
+    let payment_hash_str = hex_str(&payment_hash.0);
+    fs::read_dir(ldk_data_dir_path)
+        .unwrap()
+        .filter_map(|entry| {
+            let file = entry.unwrap();
+            let file_name = file.file_name().to_string_lossy().to_string();
+            let channel_id_str = file_name.strip_suffix(&payment_hash_str)?;
+            if channel_id_str.is_empty() {
+                return None;
+            }
+            Some((channel_id_str.to_string(), file.path()))
+        })
+        .collect()
+}
+
+/// Check the RGB data received with the HTLCs of an inbound payment before claiming it
+///
+/// Each HTLC must carry the asset of the channel it was received on and, for invoice payments,
+/// the HTLCs must carry at least the asset amount requested by the invoice.
+fn _validate_rgb_htlcs(
+    ldk_data_dir: &str,
+    payment_hash: &PaymentHash,
+    invoice_payment: bool,
+) -> Result<(), String> {
+    let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
+    let mut received = HashMap::new();
+    for (channel_id_str, path) in _rgb_htlc_payment_info_paths(&ldk_data_dir_path, payment_hash) {
+        let htlc_info = parse_rgb_payment_info(&path);
+        let channel_info_path =
+            get_rgb_channel_info_path(&channel_id_str, &ldk_data_dir_path, false);
+        if !channel_info_path.exists() {
+            return Err(format!(
+                "HTLC carries asset {} on channel {channel_id_str} that has no asset",
+                htlc_info.contract_id
+            ));
+        }
+        let channel_info = parse_rgb_channel_info(&channel_info_path);
+        if channel_info.contract_id != htlc_info.contract_id {
+            return Err(format!(
+                "HTLC carries asset {} on channel {channel_id_str} that has asset {}",
+                htlc_info.contract_id, channel_info.contract_id
+            ));
+        }
+        *received.entry(htlc_info.contract_id).or_insert(0) += htlc_info.amount;
+    }
+
+    if !invoice_payment {
+        return Ok(());
+    }
+    let invoice_info_path = get_rgb_payment_info_path(payment_hash, &ldk_data_dir_path);
+    if !invoice_info_path.exists() {
+        return Ok(());
+    }
+    let invoice_info = parse_rgb_payment_info(&invoice_info_path);
+    let received_amount = received
+        .get(&invoice_info.contract_id)
+        .copied()
+        .unwrap_or(0);
+    if received_amount < invoice_info.amount {
+        return Err(format!(
+            "HTLCs carry {received_amount} of asset {} but the invoice requires {}",
+            invoice_info.contract_id, invoice_info.amount
+        ));
+    }
+    Ok(())
+}
+
+/// Fail back the HTLCs of an inbound payment the node refuses to claim
+fn _fail_claimable_payment(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    payment_hash: &PaymentHash,
+) {
+    // drop the HTLC info so a later valid payment isn't accounted with it
+    for (_, path) in
+        _rgb_htlc_payment_info_paths(&PathBuf::from(&static_state.ldk_data_dir), payment_hash)
+    {
+        fs::remove_file(path).expect("able to remove file");
+    }
+    unlocked_state
+        .channel_manager
+        .fail_htlc_backwards_with_reason(
+            payment_hash,
+            FailureCode::IncorrectOrUnknownPaymentDetails,
+        );
+}
+
 async fn handle_ldk_events(
     event: Event,
     unlocked_state: Arc<UnlockedAppState>,
     static_state: Arc<StaticState>,
 ) {
+    // events changing the balances or the channels the alerts depend on
+    let alerts_changed = matches!(
+        event,
+        Event::PaymentClaimed { .. }
+            | Event::PaymentSent { .. }
+            | Event::PaymentForwarded { .. }
+            | Event::ChannelReady { .. }
+            | Event::ChannelClosed { .. }
+            | Event::BumpTransaction(_)
+    );
+    let alerts = Arc::clone(&unlocked_state.alerts);
+
     match event {
         Event::FundingGenerationReady {
             temporary_channel_id,
@@ -325,6 +2095,32 @@ async fn handle_ldk_events(
             .to_scriptpubkey();
             let script_buf = ScriptBuf::from_bytes(addr);
 
+            // LDK waits for the confirmations the counterparty asks for, drop the channel instead
+            // of letting it become usable with fewer than requested
+            let requested_min_depth = unlocked_state
+                .pending_channel_opens
+                .lock()
+                .unwrap()
+                .get(&temporary_channel_id.to_hex())
+                .map(|o| o.min_depth);
+            let accepted_min_depth = unlocked_state
+                .channel_manager
+                .list_channels()
+                .into_iter()
+                .find(|c| c.channel_id == temporary_channel_id)
+                .and_then(|c| c.confirmations_required);
+            if let (Some(requested), Some(accepted)) = (requested_min_depth, accepted_min_depth) {
+                if accepted < requested {
+                    drop_channel_open(
+                        &unlocked_state,
+                        &temporary_channel_id,
+                        &counterparty_node_id,
+                        format!("peer requires {accepted} confirmations instead of {requested}"),
+                    );
+                    return;
+                }
+            }
+
             let (rgb_info, _) = get_rgb_channel_info_pending(
                 &temporary_channel_id,
                 &PathBuf::from(&static_state.ldk_data_dir),
@@ -332,6 +2128,13 @@ async fn handle_ldk_events(
 
             let channel_rgb_amount: u64 = rgb_info.local_rgb_amount;
             let asset_id = rgb_info.contract_id.to_string();
+            let (consignment_endpoint, fee_rate) = unlocked_state
+                .pending_channel_opens
+                .lock()
+                .unwrap()
+                .get(&temporary_channel_id.to_hex())
+                .map(|o| (o.consignment_endpoint.clone(), o.fee_rate.fee_rate_sat_vb))
+                .unwrap_or_else(|| (preferred_proxy_endpoint(&static_state), FEE_RATE));
 
             let recipient_map = map! {
                 asset_id.clone() => vec![Recipient {
@@ -341,14 +2144,14 @@ async fn handle_ldk_events(
                         blinding: Some(STATIC_BLINDING),
                     },
                     amount: channel_rgb_amount,
-                    transport_endpoints: vec![static_state.proxy_endpoint.clone()]
+                    transport_endpoints: vec![consignment_endpoint.clone()]
                 }]
             };
 
             let unlocked_state_copy = unlocked_state.clone();
             let unsigned_psbt = tokio::task::spawn_blocking(move || {
                 unlocked_state_copy
-                    .rgb_send_begin(recipient_map, true, FEE_RATE, MIN_CHANNEL_CONFIRMATIONS)
+                    .rgb_send_begin(recipient_map, true, fee_rate, MIN_CHANNEL_CONFIRMATIONS)
                     .unwrap()
             })
             .await
@@ -370,18 +2173,18 @@ async fn handle_ldk_events(
                 .join(funding_txid.clone())
                 .join(asset_id)
                 .join("consignment_out");
-            let proxy_ref = (*static_state.proxy_client).clone();
-            let proxy_url_copy = static_state.proxy_url.clone();
-            let res = post_consignment(
-                proxy_ref,
-                &proxy_url_copy,
+            if post_consignment_with_failover(
+                &unlocked_state,
+                &static_state,
+                &[consignment_endpoint],
                 funding_txid.clone(),
                 consignment_path,
-                funding_txid,
+                funding_txid.clone(),
                 Some(0),
             )
-            .await;
-            if res.is_err() || res.unwrap().result.is_none() {
+            .await
+            .is_none()
+            {
                 tracing::error!("Cannot post consignment");
                 return;
             }
@@ -399,6 +2202,33 @@ async fn handle_ldk_events(
             {
                 tracing::error!(
                         "ERROR: Channel went away before we could fund it. The peer disconnected or refused the channel.");
+                return;
+            }
+            let channel_id = unlocked_state
+                .channel_manager
+                .list_channels()
+                .into_iter()
+                .find(|c| {
+                    c.funding_txo
+                        .is_some_and(|txo| txo.txid.to_string() == funding_txid)
+                })
+                .map(|c| c.channel_id.to_hex());
+            unlocked_state.reserve_utxos(
+                &signed_psbt,
+                UtxoReservation::ChannelOpen {
+                    channel_id: channel_id
+                        .clone()
+                        .unwrap_or_else(|| temporary_channel_id.to_hex()),
+                },
+            );
+            if let Some(open) = unlocked_state
+                .pending_channel_opens
+                .lock()
+                .unwrap()
+                .get_mut(&temporary_channel_id.to_hex())
+            {
+                open.funding_txid = Some(funding_txid);
+                open.channel_id = channel_id;
             }
         }
         Event::PaymentClaimable {
@@ -406,10 +2236,10 @@ async fn handle_ldk_events(
             purpose,
             amount_msat,
             receiver_node_id: _,
-            via_channel_id: _,
+            via_channel_id,
             via_user_channel_id: _,
             claim_deadline: _,
-            onion_fields: _,
+            onion_fields,
             counterparty_skimmed_fee_msat: _,
         } => {
             tracing::info!(
@@ -417,12 +2247,83 @@ async fn handle_ldk_events(
                 payment_hash,
                 amount_msat,
             );
-            let payment_preimage = match purpose {
+            let (payment_preimage, invoice_payment) = match purpose {
                 PaymentPurpose::InvoicePayment {
                     payment_preimage, ..
-                } => payment_preimage,
-                PaymentPurpose::SpontaneousPayment(preimage) => Some(preimage),
+                } => (payment_preimage, true),
+                PaymentPurpose::SpontaneousPayment(preimage) => (Some(preimage), false),
             };
+            // LDK checks the expiry against the block times, which lag behind
+            if let Err(status) = unlocked_state.check_invoice_open(&payment_hash) {
+                tracing::error!(
+                    "ERROR: refusing to claim payment with hash {}: invoice is {status:?}",
+                    payment_hash
+                );
+                _fail_claimable_payment(&unlocked_state, &static_state, &payment_hash);
+                return;
+            }
+            let payer = read_payer_tlvs(
+                &payment_hash,
+                onion_fields
+                    .as_ref()
+                    .map(|f| f.custom_tlvs().as_slice())
+                    .unwrap_or_default(),
+            );
+            if let Err(reason) = unlocked_state.check_payer(&payment_hash, &payer) {
+                tracing::error!(
+                    "ERROR: refusing to claim payment with hash {}: {reason}",
+                    payment_hash
+                );
+                _fail_claimable_payment(&unlocked_state, &static_state, &payment_hash);
+                static_state.emit_event(NodeEvent::PayerIdentificationRejected {
+                    payment_hash: hex_str(&payment_hash.0),
+                    reason,
+                });
+                return;
+            }
+            if let Err(reason) =
+                unlocked_state.check_claimable_exposure(&static_state, via_channel_id)
+            {
+                tracing::error!(
+                    "ERROR: refusing to claim payment with hash {}: {reason}",
+                    payment_hash
+                );
+                _fail_claimable_payment(&unlocked_state, &static_state, &payment_hash);
+                return;
+            }
+            if let Err(reason) =
+                _validate_rgb_htlcs(&static_state.ldk_data_dir, &payment_hash, invoice_payment)
+            {
+                tracing::error!(
+                    "ERROR: refusing to claim payment with hash {}: {reason}",
+                    payment_hash
+                );
+                _fail_claimable_payment(&unlocked_state, &static_state, &payment_hash);
+                static_state.emit_event(NodeEvent::RgbPaymentRejected {
+                    payment_hash: hex_str(&payment_hash.0),
+                    reason,
+                });
+                return;
+            }
+            if !payer.is_empty() {
+                unlocked_state.set_inbound_payer(&payment_hash, payer);
+            }
+            if swaps::handle_claimable_payment(
+                unlocked_state.clone(),
+                static_state.clone(),
+                &payment_hash,
+                payment_preimage.unwrap(),
+            ) {
+                return;
+            }
+            #[cfg(test)]
+            if static_state
+                .payment_faults
+                .hold_claimable_payments
+                .load(Ordering::Acquire)
+            {
+                return;
+            }
             unlocked_state
                 .channel_manager
                 .claim_funds(payment_preimage.unwrap());
@@ -432,7 +2333,7 @@ async fn handle_ldk_events(
             purpose,
             amount_msat,
             receiver_node_id: _,
-            htlcs: _,
+            htlcs,
             sender_intended_total_msat: _,
         } => {
             tracing::info!(
@@ -440,6 +2341,14 @@ async fn handle_ldk_events(
                 payment_hash,
                 amount_msat,
             );
+            let channel_ids: Vec<String> = htlcs.iter().map(|h| h.channel_id.to_hex()).collect();
+            let channel_ids: Vec<&str> = channel_ids.iter().map(|c| c.as_str()).collect();
+            if let Err(e) = unlocked_state
+                .database
+                .set_channel_activity(&channel_ids, get_current_timestamp())
+            {
+                tracing::error!("Failed to record the activity of the receiving channels: {e}");
+            }
             let (payment_preimage, payment_secret) = match purpose {
                 PaymentPurpose::InvoicePayment {
                     payment_preimage,
@@ -449,6 +2358,22 @@ async fn handle_ldk_events(
                 PaymentPurpose::SpontaneousPayment(preimage) => (Some(preimage), None),
             };
 
+            if payment_secret.is_some() {
+                let rgb_payment_info_path = get_rgb_payment_info_path(
+                    &payment_hash,
+                    &PathBuf::from(&static_state.ldk_data_dir),
+                );
+                let rgb_payment_info = rgb_payment_info_path
+                    .exists()
+                    .then(|| parse_rgb_payment_info(&rgb_payment_info_path));
+                static_state.emit_event(NodeEvent::InvoicePaid {
+                    payment_hash: hex_str(&payment_hash.0),
+                    amt_msat: amount_msat,
+                    asset_id: rgb_payment_info.as_ref().map(|i| i.contract_id.to_string()),
+                    asset_amount: rgb_payment_info.map(|i| i.amount),
+                });
+            }
+
             _update_rgb_channel_amount(&static_state.ldk_data_dir, &payment_hash, true);
 
             unlocked_state.upsert_inbound_payment(
@@ -458,6 +2383,8 @@ async fn handle_ldk_events(
                 payment_secret,
                 Some(amount_msat),
             );
+
+            lsp::handle_fee_payment(&unlocked_state, &static_state, &payment_hash);
         }
         Event::PaymentSent {
             payment_preimage,
@@ -467,6 +2394,7 @@ async fn handle_ldk_events(
             ..
         } => {
             _update_rgb_channel_amount(&static_state.ldk_data_dir, &payment_hash, false);
+            swaps::handle_payment_sent(&unlocked_state, &payment_hash);
 
             let payment = unlocked_state.update_outbound_payment(
                 payment_id.unwrap(),
@@ -487,37 +2415,84 @@ async fn handle_ldk_events(
             );
         }
         Event::OpenChannelRequest {
-            ref temporary_channel_id,
-            ref counterparty_node_id,
+            temporary_channel_id,
+            counterparty_node_id,
+            funding_satoshis,
+            push_msat,
+            channel_type,
             ..
         } => {
-            let mut random_bytes = [0u8; 16];
-            random_bytes
-                .copy_from_slice(&unlocked_state.keys_manager.get_secure_random_bytes()[..16]);
-            let user_channel_id = u128::from_be_bytes(random_bytes);
-            let res = unlocked_state.channel_manager.accept_inbound_channel(
+            handle_inbound_channel(
+                &unlocked_state,
+                &static_state,
                 temporary_channel_id,
                 counterparty_node_id,
-                user_channel_id,
+                funding_satoshis,
+                push_msat,
+                channel_type.supports_anchors_zero_fee_htlc_tx(),
             );
-
-            if let Err(e) = res {
-                tracing::error!(
-                    "EVENT: Failed to accept inbound channel ({}) from {}: {:?}",
-                    temporary_channel_id,
-                    hex_str(&counterparty_node_id.serialize()),
-                    e,
+        }
+        Event::PaymentPathSuccessful {
+            payment_id, path, ..
+        } => {
+            unlocked_state.add_outbound_payment_path(payment_id, &path);
+            // keep the channels that routed the payment out of the graph pruning
+            let short_channel_ids: Vec<u64> =
+                path.hops.iter().map(|h| h.short_channel_id).collect();
+            if let Err(e) = unlocked_state
+                .database
+                .set_hot_channels(&short_channel_ids, get_current_timestamp())
+            {
+                tracing::error!("Failed to record the channels of a payment path: {e}");
+            }
+            let first_hop = unlocked_state
+                .channel_manager
+                .list_channels()
+                .into_iter()
+                .find(|c| c.get_outbound_payment_scid() == short_channel_ids.first().copied());
+            if let Some(channel) = first_hop {
+                if let Err(e) = unlocked_state
+                    .database
+                    .set_channel_activity(&[&channel.channel_id.to_hex()], get_current_timestamp())
+                {
+                    tracing::error!("Failed to record the activity of the sending channel: {e}");
+                }
+            }
+        }
+        Event::PaymentPathFailed {
+            payment_id,
+            payment_hash,
+            payment_failed_permanently,
+            failure,
+            path,
+            short_channel_id,
+            ..
+        } => {
+            if let Some(payment_id) = payment_id {
+                let (failure_reason, failing_hop) = decode_path_failure(
+                    &failure,
+                    &path,
+                    short_channel_id,
+                    payment_failed_permanently,
+                );
+                let failure_reason = unlocked_state.rgb_failure_reason(
+                    &static_state.ldk_data_dir,
+                    &payment_hash,
+                    failure_reason,
                 );
-            } else {
                 tracing::info!(
-                    "EVENT: Accepted inbound channel ({}) from {}",
-                    temporary_channel_id,
-                    hex_str(&counterparty_node_id.serialize()),
+                    "EVENT: path failed for payment with id {}: {:?} (failing hop: {:?})",
+                    payment_id,
+                    failure_reason,
+                    failing_hop
+                );
+                unlocked_state.update_outbound_payment_failure(
+                    payment_id,
+                    failure_reason,
+                    failing_hop,
                 );
             }
         }
-        Event::PaymentPathSuccessful { .. } => {}
-        Event::PaymentPathFailed { .. } => {}
         Event::ProbeSuccessful { .. } => {}
         Event::ProbeFailed { .. } => {}
         Event::PaymentFailed {
@@ -536,7 +2511,15 @@ async fn handle_ldk_events(
                 }
             );
 
-            unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
+            unlocked_state.fail_outbound_payment(
+                &static_state,
+                payment_id,
+                reason
+                    .unwrap_or(PaymentFailureReason::RetriesExhausted)
+                    .into(),
+                None,
+            );
+            swaps::handle_payment_failed(&unlocked_state, &payment_hash);
         }
         Event::InvoiceRequestFailed { payment_id } => {
             tracing::error!(
@@ -618,6 +2601,16 @@ async fn handle_ldk_events(
                     from_onchain_str
                 );
             }
+            let forward = ForwardRecord {
+                prev_channel_id: prev_channel_id.map(|c| c.to_hex()),
+                next_channel_id: next_channel_id.map(|c| c.to_hex()),
+                amt_msat: outbound_amount_forwarded_msat,
+                fee_msat: fee_earned_msat,
+                forwarded_at: get_current_timestamp(),
+            };
+            if let Err(e) = unlocked_state.database.add_forward(&forward) {
+                tracing::error!("Failed to record a forward: {e}");
+            }
         }
         Event::HTLCHandlingFailed { .. } => {}
         Event::PendingHTLCsForwardable { time_forwardable } => {
@@ -631,7 +2624,7 @@ async fn handle_ldk_events(
         }
         Event::SpendableOutputs {
             outputs,
-            channel_id: _,
+            channel_id,
         } => {
             // SpendableOutputDescriptors, of which outputs is a vec of, are critical to keep track
             // of! While a `StaticOutput` descriptor is just an output to a static, well-known key,
@@ -646,14 +2639,54 @@ async fn handle_ldk_events(
                 let key = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
                 // Note that if the type here changes our read code needs to change as well.
                 let output: SpendableOutputDescriptor = output;
-                unlocked_state
-                    .fs_store
-                    .write(PENDING_SPENDABLE_OUTPUT_DIR, "", &key, &output.encode())
-                    .unwrap();
+                let encoded = output.encode();
+                if unlocked_state
+                    .persister
+                    .write(PENDING_SPENDABLE_OUTPUT_DIR, "", &key, &encoded)
+                    .is_err()
+                {
+                    unlocked_state.unpersisted_outputs.push(key, encoded);
+                }
+                // the output of a cooperative close, send its assets where the close asked to
+                if let (
+                    SpendableOutputDescriptor::StaticOutput { outpoint, .. },
+                    Some(channel_id),
+                ) = (&output, channel_id)
+                {
+                    if let Err(e) = unlocked_state.database.link_close_asset_destination(
+                        &channel_id.to_hex(),
+                        &outpoint.txid.to_string(),
+                    ) {
+                        tracing::error!("Failed to link the close asset destination: {e}");
+                    }
+                }
+                // these outputs only come from force-closes, link the transactions holding them
+                // to the channel (possibly with no fee, e.g. the counterparty's commitment) so the
+                // sweep fee gets attributed to it
+                let outpoint = match (&output, channel_id) {
+                    (SpendableOutputDescriptor::StaticPaymentOutput(descriptor), Some(_)) => {
+                        descriptor.outpoint
+                    }
+                    (SpendableOutputDescriptor::DelayedPaymentOutput(descriptor), Some(_)) => {
+                        descriptor.outpoint
+                    }
+                    _ => continue,
+                };
+                if let Err(e) = unlocked_state.database.add_channel_fee(
+                    &outpoint.txid.to_string(),
+                    &channel_id.unwrap().to_hex(),
+                    ChannelFeeKind::ForceClose,
+                    0,
+                    true,
+                    false,
+                ) {
+                    tracing::error!("Failed to link spendable output to its channel: {e}");
+                }
             }
         }
         Event::ChannelPending {
             channel_id,
+            former_temporary_channel_id,
             counterparty_node_id,
             funding_txo,
             ..
@@ -667,9 +2700,84 @@ async fn handle_ldk_events(
             let funding_txid = funding_txo.txid.to_string();
             let psbt_path = format!("{}/psbt_{funding_txid}", static_state.ldk_data_dir);
 
+            let mut funding_target_conf_blocks = None;
+            if let Some(temporary_channel_id) = former_temporary_channel_id {
+                if let Err(e) = unlocked_state
+                    .database
+                    .rename_channel_note(&temporary_channel_id.to_hex(), &channel_id.to_hex())
+                {
+                    tracing::error!("Failed to move channel note: {e}");
+                }
+                if let Err(e) = unlocked_state
+                    .database
+                    .rename_channel_creation(&temporary_channel_id.to_hex(), &channel_id.to_hex())
+                {
+                    tracing::error!("Failed to move channel creation time: {e}");
+                }
+                // the counterparty signed the funding, the negotiation is complete
+                funding_target_conf_blocks = unlocked_state
+                    .pending_channel_opens
+                    .lock()
+                    .unwrap()
+                    .remove(&temporary_channel_id.to_hex())
+                    .and_then(|o| o.fee_rate.target_conf_blocks);
+            }
+            if let Err(e) = unlocked_state
+                .database
+                .add_channel_creations(&[&channel_id.to_hex()], get_current_timestamp())
+            {
+                tracing::error!("Failed to record channel creation time: {e}");
+            }
+
+            if let Some(chan_info) = unlocked_state
+                .channel_manager
+                .list_channels()
+                .into_iter()
+                .find(|c| c.channel_id == channel_id)
+            {
+                if let Err(e) = unlocked_state.database.add_channel_funding(
+                    &channel_id.to_hex(),
+                    &funding_txid,
+                    funding_txo.index as u32,
+                    chan_info.channel_value_satoshis,
+                    chan_info.is_outbound,
+                ) {
+                    tracing::error!("Failed to record channel funding: {e}");
+                }
+            }
+
             if Path::new(&psbt_path).exists() {
                 let psbt_str = fs::read_to_string(psbt_path).unwrap();
 
+                // the node alone funds the channels it opens
+                let psbt = BdkPsbt::from_str(&psbt_str).unwrap();
+                match psbt.fee_amount() {
+                    Some(fee_sat) => {
+                        let fee_rate = ChannelFeeRate {
+                            target_conf_blocks: funding_target_conf_blocks,
+                            fee_rate_sat_vb: fee_sat as f32 / psbt.extract_tx().vsize() as f32,
+                        };
+                        if let Err(e) = unlocked_state.database.set_channel_fee_rate(
+                            &channel_id.to_hex(),
+                            ChannelFeeKind::Funding,
+                            fee_rate,
+                        ) {
+                            tracing::error!("Failed to record funding fee rate: {e}");
+                        }
+                        if let Err(e) = unlocked_state.database.add_channel_fee(
+                            &funding_txid,
+                            &channel_id.to_hex(),
+                            ChannelFeeKind::Funding,
+                            fee_sat,
+                            false,
+                            true,
+                        ) {
+                            tracing::error!("Failed to record funding fee: {e}");
+                        }
+                    }
+                    None => tracing::warn!("Cannot compute the fee of funding tx {funding_txid}"),
+                }
+
                 let state_copy = unlocked_state.clone();
                 let psbt_str_copy = psbt_str.clone();
                 let _txid = tokio::task::spawn_blocking(move || {
@@ -677,6 +2785,14 @@ async fn handle_ldk_events(
                 })
                 .await
                 .unwrap();
+
+                // the funding may have been reserved under the temporary channel ID
+                unlocked_state.reserve_utxos(
+                    &psbt_str,
+                    UtxoReservation::ChannelOpen {
+                        channel_id: channel_id.to_hex(),
+                    },
+                );
             } else {
                 // acceptor
                 let consignment_path =
@@ -719,7 +2835,7 @@ async fn handle_ldk_events(
             reason,
             user_channel_id: _,
             counterparty_node_id,
-            channel_capacity_sats: _,
+            channel_capacity_sats,
         } => {
             tracing::info!(
                 "EVENT: Channel {} with counterparty {} closed due to: {:?}",
@@ -729,14 +2845,81 @@ async fn handle_ldk_events(
                     .unwrap_or("".to_owned()),
                 reason
             );
+
+            let channel_id = channel_id.to_hex();
+            static_state.emit_event(NodeEvent::ChannelClosed {
+                channel_id: channel_id.clone(),
+                peer_pubkey: counterparty_node_id.map(|id| id.to_string()),
+                reason: reason.to_string(),
+            });
+            let info_file_path = get_rgb_channel_info_path(
+                &channel_id,
+                &PathBuf::from(&static_state.ldk_data_dir),
+                false,
+            );
+            let failed_open_id = channel_id.clone();
+            let rgb_info = if info_file_path.exists() {
+                Some(parse_rgb_channel_info(&info_file_path))
+            } else {
+                None
+            };
+            let funding_txid = match unlocked_state.database.channel_funding_txid(&channel_id) {
+                Ok(funding_txid) => funding_txid,
+                Err(e) => {
+                    tracing::error!("Failed to get channel funding: {e}");
+                    None
+                }
+            };
+            if let Err(e) = unlocked_state
+                .database
+                .add_closed_channel(&ClosedChannelRecord {
+                    channel_id,
+                    funding_txid,
+                    peer_pubkey: counterparty_node_id.map(|id| id.to_string()),
+                    capacity_sat: channel_capacity_sats,
+                    asset_id: rgb_info.as_ref().map(|i| i.contract_id.to_string()),
+                    asset_local_amount: rgb_info.as_ref().map(|i| i.local_rgb_amount),
+                    asset_remote_amount: rgb_info.as_ref().map(|i| i.remote_rgb_amount),
+                    reason: reason.to_string(),
+                    closed_at: get_current_timestamp(),
+                })
+            {
+                tracing::error!("Failed to record closed channel: {e}");
+            }
+
+            rollback_channel_open(
+                &unlocked_state,
+                &static_state,
+                &failed_open_id,
+                reason.to_string(),
+            );
         }
-        Event::DiscardFunding { .. } => {
-            // A "real" node should probably "lock" the UTXOs spent in funding transactions until
-            // the funding transaction either confirms, or this event is generated.
+        Event::DiscardFunding { transaction, .. } => {
+            // the funding transaction won't be broadcast, its UTXOs can be spent again
+            unlocked_state.release_utxos(&transaction.txid().to_string(), "funding discarded");
         }
         Event::HTLCIntercepted { .. } => {}
         Event::BumpTransaction(event) => unlocked_state.bump_tx_event_handler.handle_event(&event),
     }
+
+    if alerts_changed {
+        alerts.trigger();
+    }
+}
+
+/// Record a transaction sweeping channel outputs to the wallet, along with the assets it moves
+fn record_sweep(unlocked_state: &UnlockedAppState, tx: &Transaction, asset: Option<(String, u64)>) {
+    let (asset_id, asset_amount) = asset.unzip();
+    let sweep = SweepRecord {
+        txid: tx.txid().to_string(),
+        amount_sat: tx.output.iter().map(|o| o.value).sum(),
+        asset_id,
+        asset_amount,
+        created_at: get_current_timestamp(),
+    };
+    if let Err(e) = unlocked_state.database.add_sweep(&sweep) {
+        tracing::error!("Failed to record sweep {}: {e}", sweep.txid);
+    }
 }
 
 async fn _spend_outputs(
@@ -795,12 +2978,31 @@ async fn _spend_outputs(
 
         let contract_id = transfer_info.contract_id;
 
-        let receive_data = unlocked_state
-            .rgb_witness_receive(vec![static_state.proxy_endpoint.clone()])
-            .unwrap();
-        let script_buf_str = receive_data.recipient_id;
-        let script_buf = ScriptBuf::from_hex(&script_buf_str).unwrap();
-        let bdk_script = BdkScript::from(script_buf.clone().into_bytes());
+        let destination = unlocked_state
+            .database
+            .close_asset_destination(&txid.to_string())
+            .unwrap_or_else(|e| {
+                tracing::error!("Cannot get the close asset destination: {e}");
+                None
+            });
+        let (recipient_id, bdk_script, secret_seal) = match destination {
+            Some(blinded_utxo) => {
+                // the assets go to the blinded UTXO, the bitcoins to a vanilla address
+                let secret_seal = SecretSeal::from_str(&blinded_utxo).unwrap();
+                let address_str = unlocked_state.rgb_get_address().unwrap();
+                let address = Address::from_str(&address_str).unwrap().assume_checked();
+                let bdk_script = BdkScript::from(address.script_pubkey().into_bytes());
+                (blinded_utxo, bdk_script, Some(secret_seal))
+            }
+            None => {
+                let receive_data = unlocked_state
+                    .rgb_witness_receive(static_state.proxy_endpoints.clone())
+                    .unwrap();
+                let script_buf = ScriptBuf::from_hex(&receive_data.recipient_id).unwrap();
+                let bdk_script = BdkScript::from(script_buf.into_bytes());
+                (receive_data.recipient_id, bdk_script, None)
+            }
+        };
 
         let mut runtime = get_rgb_runtime(Path::new(&static_state.ldk_data_dir));
 
@@ -833,10 +3035,21 @@ async fn _spend_outputs(
 
         let (tx, vout, consignment) = match outp {
             SpendableOutputDescriptor::StaticPaymentOutput(descriptor) => {
+                // only anchor channels lock the output paying us on the counterparty's commitment
+                // for a block, on legacy ones it's a plain P2WPKH output
+                let sequence = if descriptor
+                    .channel_transaction_parameters
+                    .as_ref()
+                    .is_some_and(|p| p.channel_type_features.supports_anchors_zero_fee_htlc_tx())
+                {
+                    Sequence::from_consensus(1)
+                } else {
+                    Sequence::ZERO
+                };
                 let input = vec![TxIn {
                     previous_output: descriptor.outpoint.into_bitcoin_outpoint(),
                     script_sig: Script::new(),
-                    sequence: Sequence::from_consensus(1),
+                    sequence,
                     witness: Witness::new(),
                 }];
                 let witness_weight = descriptor.max_witness_length();
@@ -870,6 +3083,7 @@ async fn _spend_outputs(
                     asset_transition_builder,
                     assignment_id,
                     amt_rgb,
+                    secret_seal,
                 );
                 let (psbt, consignment) =
                     runtime.send_rgb(contract_id, psbt, asset_transition_builder, beneficiaries);
@@ -928,6 +3142,7 @@ async fn _spend_outputs(
                     asset_transition_builder,
                     assignment_id,
                     amt_rgb,
+                    secret_seal,
                 );
                 let (psbt, consignment) =
                     runtime.send_rgb(contract_id, psbt, asset_transition_builder, beneficiaries);
@@ -983,6 +3198,7 @@ async fn _spend_outputs(
                     asset_transition_builder,
                     assignment_id,
                     amt_rgb,
+                    secret_seal,
                 );
                 let (mut psbt, consignment) =
                     runtime.send_rgb(contract_id, psbt, asset_transition_builder, beneficiaries);
@@ -996,24 +3212,31 @@ async fn _spend_outputs(
         };
 
         broadcast_tx(&tx, static_state.electrum_url.clone());
+        record_channel_tx(&unlocked_state, &static_state, &tx);
+        record_sweep(
+            &unlocked_state,
+            &tx,
+            Some((contract_id.to_string(), amt_rgb)),
+        );
 
         let closing_txid = tx.txid().to_string();
         let consignment_path = format!("{}/consignment_{closing_txid}", static_state.ldk_data_dir);
         consignment
             .save(&consignment_path)
             .expect("successful save");
-        let proxy_ref = (*static_state.proxy_client).clone();
-        let proxy_url_copy = static_state.proxy_url.clone();
-        let res = post_consignment(
-            proxy_ref,
-            &proxy_url_copy,
-            script_buf_str,
+        // the wallet fetches the consignment trying all the default endpoints
+        if post_consignment_with_failover(
+            &unlocked_state,
+            &static_state,
+            &static_state.proxy_endpoints,
+            recipient_id,
             consignment_path.into(),
             closing_txid,
-            Some(vout),
+            vout,
         )
-        .await;
-        if res.is_err() || res.unwrap().result.is_none() {
+        .await
+        .is_none()
+        {
             tracing::error!("Cannot post consignment");
             return;
         }
@@ -1036,6 +3259,8 @@ async fn _spend_outputs(
             // Note that, most likely, we've already sweeped this set of outputs
             // and they're already confirmed on-chain, so this broadcast will fail.
             broadcast_tx(&spending_tx, static_state.electrum_url.clone());
+            record_channel_tx(&unlocked_state, &static_state, &spending_tx);
+            record_sweep(&unlocked_state, &spending_tx, None);
         } else {
             tracing::error!("Failed to sweep spendable outputs! This may indicate the outputs are dust. Will try again in a day.");
         }
@@ -1051,6 +3276,142 @@ async fn _spend_outputs(
     }
 }
 
+/// Act on the pending HTLCs getting close to a force-close of their channel, reporting each action
+/// as a node event and in the counters
+async fn watch_htlc_expiry(
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    // Regularly check the HTLCs pending in our channels and act on the ones approaching the height
+    // at which LDK would force-close the channel, so that a single stuck HTLC doesn't cost a
+    // channel without the node having tried to resolve it first.
+    #[cfg(test)]
+    let interval_secs = 5;
+    #[cfg(not(test))]
+    let interval_secs = 60;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    let peer_data_path = format!("{}/channel_peer_data", static_state.ldk_data_dir);
+    let mut done_actions: HashSet<(PaymentHash, HtlcExpiryAction)> = HashSet::new();
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+
+        let cur_height = unlocked_state.channel_manager.current_best_block().height();
+        let pending_htlcs = unlocked_state.pending_htlcs();
+        done_actions.retain(|(payment_hash, _)| {
+            pending_htlcs
+                .iter()
+                .any(|htlc| htlc.payment_hash == *payment_hash)
+        });
+
+        for htlc in pending_htlcs {
+            let blocks_to_force_close = htlc.force_close_height.saturating_sub(cur_height);
+            if blocks_to_force_close >= HTLC_EXPIRY_WARNING_BLOCKS {
+                continue;
+            }
+
+            let actions = if htlc.inbound {
+                vec![HtlcExpiryAction::HtlcFailBack]
+            } else if blocks_to_force_close <= static_state.htlc_force_close_buffer {
+                vec![HtlcExpiryAction::ChannelForceClose]
+            } else {
+                vec![
+                    HtlcExpiryAction::PeerReconnect,
+                    HtlcExpiryAction::PaymentAbandon,
+                ]
+            };
+
+            for action in actions {
+                if !done_actions.insert((htlc.payment_hash, action)) {
+                    continue;
+                }
+                let success = match action {
+                    HtlcExpiryAction::PeerReconnect => {
+                        let peer_pubkey = htlc.counterparty_node_id;
+                        if unlocked_state
+                            .peer_manager
+                            .get_peer_node_ids()
+                            .iter()
+                            .any(|(pk, _)| *pk == peer_pubkey)
+                        {
+                            continue;
+                        }
+                        match disk::read_channel_peer_data(Path::new(&peer_data_path)) {
+                            Ok(info) => match info.get(&peer_pubkey) {
+                                Some(peer_addr) => do_connect_peer(
+                                    peer_pubkey,
+                                    *peer_addr,
+                                    Arc::clone(&unlocked_state.peer_manager),
+                                )
+                                .await
+                                .is_ok(),
+                                None => false,
+                            },
+                            Err(_) => false,
+                        }
+                    }
+                    HtlcExpiryAction::PaymentAbandon => {
+                        let payment_id = PaymentId(htlc.payment_hash.0);
+                        match unlocked_state.outbound_payments().get(&payment_id) {
+                            Some(payment) if payment.status == HTLCStatus::Pending => {
+                                unlocked_state.channel_manager.abandon_payment(payment_id);
+                                true
+                            }
+                            // the HTLC is being forwarded, not ours to abandon
+                            _ => continue,
+                        }
+                    }
+                    HtlcExpiryAction::HtlcFailBack => {
+                        if !unlocked_state
+                            .inbound_payments()
+                            .contains_key(&htlc.payment_hash)
+                        {
+                            continue;
+                        }
+                        unlocked_state
+                            .channel_manager
+                            .fail_htlc_backwards(&htlc.payment_hash);
+                        true
+                    }
+                    HtlcExpiryAction::ChannelForceClose => unlocked_state
+                        .channel_manager
+                        .force_close_broadcasting_latest_txn(
+                            &htlc.channel_id,
+                            &htlc.counterparty_node_id,
+                        )
+                        .is_ok(),
+                };
+                tracing::warn!(
+                    "HTLC {} in channel {} is {} blocks from force-close: {:?} {}",
+                    hex_str(&htlc.payment_hash.0),
+                    htlc.channel_id,
+                    blocks_to_force_close,
+                    action,
+                    if success { "succeeded" } else { "failed" },
+                );
+                static_state.increment_counter(match action {
+                    HtlcExpiryAction::PeerReconnect => "htlc_expiry_peer_reconnect",
+                    HtlcExpiryAction::PaymentAbandon => "htlc_expiry_payment_abandon",
+                    HtlcExpiryAction::HtlcFailBack => "htlc_expiry_htlc_fail_back",
+                    HtlcExpiryAction::ChannelForceClose => "htlc_expiry_channel_force_close",
+                });
+                static_state.emit_event(NodeEvent::HtlcExpiryAction {
+                    action,
+                    channel_id: htlc.channel_id.to_hex(),
+                    peer_pubkey: htlc.counterparty_node_id.to_string(),
+                    payment_hash: hex_str(&htlc.payment_hash.0),
+                    blocks_to_force_close,
+                    success,
+                });
+            }
+        }
+    }
+}
+
 /// If we have any pending claimable outputs, we should slowly sweep them to our BDK
 /// wallet. We technically don't need to do this - they're ours to spend when we want and can just
 /// use them to build new transactions instead, but we cannot feed them direclty into BDK's
@@ -1082,7 +3443,12 @@ async fn periodic_sweep(
     #[cfg(test)]
     let interval_secs = 5;
     #[cfg(not(test))]
-    let interval_secs = 60 * 60 * 24;
+    let interval_secs = if unlocked_state.recovery_mode {
+        // funds become claimable as the force-close timelocks expire, get them back quickly
+        60 * 10
+    } else {
+        60 * 60 * 24
+    };
     let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
 
     loop {
@@ -1119,7 +3485,7 @@ async fn periodic_sweep(
                         .keys_manager
                         .get_secure_random_bytes(),
                 );
-                unlocked_state
+                if unlocked_state
                     .persister
                     .write(
                         "spendable_outputs",
@@ -1127,7 +3493,11 @@ async fn periodic_sweep(
                         &key,
                         &WithoutLength(&outputs).encode(),
                     )
-                    .unwrap();
+                    .is_err()
+                {
+                    // the outputs stay in the processing directory for the next attempt
+                    continue;
+                }
                 fs::remove_dir_all(&processing_spendables_dir).unwrap();
             }
         }
@@ -1168,9 +3538,32 @@ async fn periodic_sweep(
     }
 }
 
+/// Retry writing the spendable outputs that failed to be persisted, so that the sweep finds them
+async fn retry_unpersisted_outputs(
+    unlocked_state: Arc<UnlockedAppState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    #[cfg(test)]
+    let interval_secs = 1;
+    #[cfg(not(test))]
+    let interval_secs = 60;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        unlocked_state
+            .unpersisted_outputs
+            .retry(&*unlocked_state.persister, PENDING_SPENDABLE_OUTPUT_DIR);
+    }
+}
+
 pub(crate) async fn start_ldk(
     app_state: Arc<AppState>,
     mnemonic: Mnemonic,
+    recovery_mode: bool,
 ) -> Result<(LdkBackgroundServices, Arc<UnlockedAppState>), APIError> {
     let static_state = &app_state.static_state;
 
@@ -1180,11 +3573,12 @@ pub(crate) async fn start_ldk(
     let logger = static_state.logger.clone();
     let network = static_state.network;
     let ldk_peer_listening_port = static_state.ldk_peer_listening_port;
-    let ldk_announced_listen_addr = static_state.ldk_announced_listen_addr.clone();
-    let ldk_announced_node_name = static_state.ldk_announced_node_name;
     let electrum_url = static_state.electrum_url.clone();
     let bitcoin_network = get_bitcoin_network(&network);
 
+    // problems with the proxy would otherwise only show up when sending assets or opening channels
+    check_default_proxies(static_state).await;
+
     // Initialize the FeeEstimator
     // BitcoindClient implements the FeeEstimator trait, so it'll act as our fee estimator.
     let fee_estimator = bitcoind_client.clone();
@@ -1221,8 +3615,35 @@ pub(crate) async fn start_ldk(
 
     // Initialize Persistence
     let fs_store = Arc::new(FilesystemStore::new(ldk_data_dir.clone().into()));
-    let persister = Arc::new(MonitorUpdatingPersister::new(
+    let monitor_mirror = if let Some(target) = &static_state.monitor_mirror {
+        restore_from_mirror(target, &ldk_data_dir_path)
+            .await
+            .map_err(|e| APIError::FailedStartingLDK(format!("monitor restore failed: {e}")))?;
+        Some(MonitorMirror::start(target.clone()))
+    } else {
+        None
+    };
+    let monitor_store = Arc::new(MirroredStore::new(
         Arc::clone(&fs_store),
+        monitor_mirror.clone(),
+        Arc::clone(&static_state.persistence),
+    ));
+
+    let database = Arc::new(Database::open(
+        &Path::new(&static_state.storage_dir_path).join(DATABASE_FNAME),
+    )?);
+
+    // monitors of long resolved channels are not needed anymore
+    let tip_height = bitcoind_client.get_blockchain_info().await.latest_height as u32;
+    archive_resolved_monitors(
+        &database,
+        &monitor_store,
+        &ldk_data_dir_path,
+        tip_height,
+        static_state.monitor_archive_depth,
+    )?;
+    let persister = Arc::new(MonitorUpdatingPersister::new(
+        monitor_store,
         Arc::clone(&logger),
         1000,
         Arc::clone(&keys_manager),
@@ -1239,10 +3660,26 @@ pub(crate) async fn start_ldk(
         Arc::clone(&persister),
     ));
 
-    // Read ChannelMonitor state from disk
-    let mut channelmonitors = persister
-        .read_all_channel_monitors_with_updates(&bitcoind_client, &bitcoind_client)
-        .unwrap();
+    // Read ChannelMonitor state from disk, naming the monitor that cannot be read
+    let mut channelmonitors = vec![];
+    for monitor_key in fs_store.list(
+        CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+        CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+    )? {
+        let monitor = persister
+            .read_channel_monitor_with_updates(
+                &bitcoind_client,
+                &bitcoind_client,
+                monitor_key.clone(),
+            )
+            .map_err(|e| {
+                fatal_artifact_error(
+                    &format!("channel monitor {monitor_key}"),
+                    &format!("cannot be read ({e})"),
+                )
+            })?;
+        channelmonitors.push(monitor);
+    }
 
     // Poll for the best chain tip, which may be used by the channel manager & spv client
     let polled_chain_tip = init::validate_best_block_header(bitcoind_client.as_ref())
@@ -1250,14 +3687,14 @@ pub(crate) async fn start_ldk(
         .expect("Failed to fetch best block header and best block");
 
     // Initialize routing ProbabilisticScorer
-    let network_graph_path = format!("{}/network_graph", ldk_data_dir.clone());
+    let network_graph_path = format!("{}/{NETWORK_GRAPH_FNAME}", ldk_data_dir.clone());
     let network_graph = Arc::new(disk::read_network(
         Path::new(&network_graph_path),
         network,
         logger.clone(),
     ));
 
-    let scorer_path = format!("{}/scorer", ldk_data_dir.clone());
+    let scorer_path = format!("{}/{SCORER_FNAME}", ldk_data_dir.clone());
     let scorer = Arc::new(RwLock::new(disk::read_scorer(
         Path::new(&scorer_path),
         Arc::clone(&network_graph),
@@ -1282,10 +3719,15 @@ pub(crate) async fn start_ldk(
     user_config
         .channel_handshake_config
         .negotiate_anchors_zero_fee_htlc_tx = true;
+    user_config.channel_handshake_config.minimum_depth = static_state.inbound_channel_min_depth;
     user_config.manually_accept_inbound_channels = true;
+    // channels already open keep the parameters they were negotiated with
+    static_state
+        .channel_handshake
+        .apply_inbound(&mut user_config);
     let mut restarting_node = true;
     let (channel_manager_blockhash, channel_manager) = {
-        if let Ok(mut f) = fs::File::open(format!("{}/manager", ldk_data_dir.clone())) {
+        if let Ok(mut f) = fs::File::open(format!("{}/{CHANNEL_MANAGER_FNAME}", ldk_data_dir)) {
             let mut channel_monitor_mut_references = Vec::new();
             for (_, channel_monitor) in channelmonitors.iter_mut() {
                 channel_monitor_mut_references.push(channel_monitor);
@@ -1297,13 +3739,15 @@ pub(crate) async fn start_ldk(
                 fee_estimator.clone(),
                 chain_monitor.clone(),
                 broadcaster.clone(),
-                router,
+                router.clone(),
                 logger.clone(),
                 user_config,
                 channel_monitor_mut_references,
                 ldk_data_dir_path.clone(),
             );
-            <(BlockHash, ChannelManager)>::read(&mut f, read_args).unwrap()
+            <(BlockHash, ChannelManager)>::read(&mut f, read_args).map_err(|e| {
+                fatal_artifact_error("channel manager", &format!("cannot be read ({e})"))
+            })?
         } else {
             // We're starting a fresh node.
             restarting_node = false;
@@ -1318,7 +3762,7 @@ pub(crate) async fn start_ldk(
                 fee_estimator.clone(),
                 chain_monitor.clone(),
                 broadcaster.clone(),
-                router,
+                router.clone(),
                 logger.clone(),
                 keys_manager.clone(),
                 keys_manager.clone(),
@@ -1393,25 +3837,69 @@ pub(crate) async fn start_ldk(
 
     // Initialize the PeerManager
     let channel_manager: Arc<ChannelManager> = Arc::new(channel_manager);
+
+    // connections left open were closed when the node got stopped, while channel peers never
+    // connected are tracked from now on
+    let channel_peers: Vec<String> = channel_manager
+        .list_channels()
+        .iter()
+        .map(|chan| chan.counterparty.node_id.to_string())
+        .collect();
+    database.reset_peer_connections(&channel_peers, get_current_timestamp())?;
+
+    let user_onion_message_handler = Arc::new(UserOnionMessageHandler {
+        static_state: Arc::clone(static_state),
+    });
     let onion_messenger: Arc<OnionMessenger> = Arc::new(OnionMessenger::new(
         Arc::clone(&keys_manager),
         Arc::clone(&keys_manager),
         Arc::clone(&logger),
         Arc::new(DefaultMessageRouter {}),
         Arc::clone(&channel_manager),
-        IgnoringMessageHandler {},
+        Arc::clone(&user_onion_message_handler),
     ));
+    let peer_limiter = Arc::new(PeerLimiter::new(static_state.peer_rate_limits));
+    let (peer_message_handler, peer_message_receiver) =
+        PeerMessageHandler::new(Arc::clone(&peer_limiter));
+    let peer_message_handler = Arc::new(peer_message_handler);
+    let stop_processing = Arc::new(AtomicBool::new(false));
+    let announcements = Arc::new(Announcements::default());
+    let onion_message_gate = Arc::new(OnionMessageGate {
+        onion_messenger: Arc::clone(&onion_messenger),
+        keys_manager: Arc::clone(&keys_manager),
+        logger: Arc::clone(&logger),
+        custom_handler: user_onion_message_handler,
+        forwarding: static_state.onion_message_forwarding,
+        secp_ctx: Secp256k1::new(),
+        peer_message_handler: Arc::clone(&peer_message_handler),
+        peer_limiter: Arc::clone(&peer_limiter),
+        database: Arc::clone(&database),
+        static_state: Arc::clone(static_state),
+        stop_processing: Arc::clone(&stop_processing),
+        announcements: Arc::clone(&announcements),
+    });
+    let gossip_gate = Arc::new(GossipGate {
+        gossip_sync: Arc::clone(&gossip_sync),
+        peer_limiter: Arc::clone(&peer_limiter),
+        network_graph: Arc::clone(&network_graph),
+        announcements: Arc::clone(&announcements),
+        our_node_id: NodeId::from_pubkey(&channel_manager.get_our_node_id()),
+    });
     let mut ephemeral_bytes = [0; 32];
     let current_time = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
         .as_secs();
     rand::thread_rng().fill_bytes(&mut ephemeral_bytes);
+    let channel_message_gate = Arc::new(ChannelMessageGate {
+        channel_manager: Arc::clone(&channel_manager),
+        database: Arc::clone(&database),
+    });
     let lightning_msg_handler = MessageHandler {
-        chan_handler: channel_manager.clone(),
-        route_handler: gossip_sync.clone(),
-        onion_message_handler: onion_messenger.clone(),
-        custom_message_handler: IgnoringMessageHandler {},
+        chan_handler: channel_message_gate,
+        route_handler: gossip_gate,
+        onion_message_handler: onion_message_gate,
+        custom_message_handler: Arc::clone(&peer_message_handler),
     };
     let peer_manager: Arc<PeerManager> = Arc::new(PeerManager::new(
         lightning_msg_handler,
@@ -1433,50 +3921,70 @@ pub(crate) async fn start_ldk(
     // ## Running LDK
     // Initialize networking
 
-    let peer_manager_connection_handler = peer_manager.clone();
-    let listening_port = ldk_peer_listening_port;
-    let stop_processing = Arc::new(AtomicBool::new(false));
-    let stop_listen = Arc::clone(&stop_processing);
-    tokio::spawn(async move {
-        let listener = tokio::net::TcpListener::bind(format!("[::]:{}", listening_port))
-            .await
-            .expect("Failed to bind to listen port - is something else already listening on it?");
-        loop {
-            let peer_mgr = peer_manager_connection_handler.clone();
-            let tcp_stream = listener.accept().await.unwrap().0;
-            if stop_listen.load(Ordering::Acquire) {
-                return;
+    // signals the node is being stopped to the background processor and the peer listener
+    let (bp_exit, bp_exit_check) = tokio::sync::watch::channel(());
+
+    if let Some(listening_host) = static_state.ldk_peer_listening_host {
+        let peer_manager_connection_handler = peer_manager.clone();
+        let listening_addr = SocketAddr::new(listening_host, ldk_peer_listening_port);
+        let stop_listen = Arc::clone(&stop_processing);
+        let mut listen_exit_check = bp_exit_check.clone();
+        let listen_limiter = Arc::clone(&peer_limiter);
+        tokio::spawn(async move {
+            let listener = tokio::net::TcpListener::bind(listening_addr).await.expect(
+                "Failed to bind to listen port - is something else already listening on it?",
+            );
+            loop {
+                let peer_mgr = peer_manager_connection_handler.clone();
+                // the listener gets dropped as soon as the node is stopped, freeing the port
+                let tcp_stream = tokio::select! {
+                    accepted = listener.accept() => accepted.unwrap().0,
+                    _ = listen_exit_check.changed() => return,
+                };
+                if stop_listen.load(Ordering::Acquire) {
+                    return;
+                }
+                // the peer can only be identified once the handshake completes, so connections
+                // from the address of a banned peer are dropped before doing any work for them
+                if tcp_stream
+                    .peer_addr()
+                    .is_ok_and(|addr| listen_limiter.is_banned_address(addr.ip()))
+                {
+                    continue;
+                }
+                tokio::spawn(async move {
+                    lightning_net_tokio::setup_inbound(
+                        peer_mgr.clone(),
+                        tcp_stream.into_std().unwrap(),
+                    )
+                    .await;
+                });
             }
-            tokio::spawn(async move {
-                lightning_net_tokio::setup_inbound(
-                    peer_mgr.clone(),
-                    tcp_stream.into_std().unwrap(),
-                )
-                .await;
-            });
-        }
-    });
+        });
+    } else {
+        tracing::info!("Peer listening disabled, only outbound connections will be made");
+    }
 
     // Connect and Disconnect Blocks
+    let chain_reorgs = Arc::new(ChainReorgs::new(Arc::clone(&database))?);
     let channel_manager_listener = channel_manager.clone();
     let chain_monitor_listener = chain_monitor.clone();
+    let chain_reorgs_listener = chain_reorgs.clone();
     let bitcoind_block_source = bitcoind_client.clone();
     tokio::spawn(async move {
         let chain_poller = poll::ChainPoller::new(bitcoind_block_source.as_ref(), network);
-        let chain_listener = (chain_monitor_listener, channel_manager_listener);
+        let ldk_listener = (chain_monitor_listener, channel_manager_listener);
+        let chain_listener = (&ldk_listener, Arc::clone(&chain_reorgs_listener));
         let mut spv_client = SpvClient::new(chain_tip, chain_poller, &mut cache, &chain_listener);
         loop {
             spv_client.poll_best_tip().await.unwrap();
+            chain_reorgs_listener.end_poll();
             tokio::time::sleep(Duration::from_secs(1)).await;
         }
     });
 
-    let inbound_payments = Arc::new(Mutex::new(disk::read_inbound_payment_info(Path::new(
-        &format!("{}/{}", ldk_data_dir, INBOUND_PAYMENTS_FNAME),
-    ))));
-    let outbound_payments = Arc::new(Mutex::new(disk::read_outbound_payment_info(Path::new(
-        &format!("{}/{}", ldk_data_dir, OUTBOUND_PAYMENTS_FNAME),
-    ))));
+    let inbound_payments = Arc::new(Mutex::new(database.inbound_payments()?));
+    let outbound_payments = Arc::new(Mutex::new(database.outbound_payments()?));
 
     let xkey: ExtendedKey = mnemonic
         .clone()
@@ -1484,6 +3992,7 @@ pub(crate) async fn start_ldk(
         .expect("a valid key should have been provided");
     let xpub = xkey.into_xpub(network, &secp);
     let pubkey = xpub.to_string();
+    let vanilla_addresses = Arc::new(VanillaAddresses::new(&pubkey, network));
     let data_dir = static_state.storage_dir_path.clone();
     let mut rgb_wallet = tokio::task::spawn_blocking(move || {
         RgbLibWallet::new(WalletData {
@@ -1495,10 +4004,10 @@ pub(crate) async fn start_ldk(
             mnemonic: Some(mnemonic.to_string()),
             vanilla_keychain: None,
         })
-        .expect("valid rgb-lib wallet")
     })
     .await
-    .unwrap();
+    .unwrap()
+    .map_err(|e| fatal_artifact_error("RGB wallet data", &format!("cannot be opened ({e})")))?;
     let rgb_online = rgb_wallet
         .go_online(false, electrum_url.clone())
         .map_err(|e| APIError::FailedStartingLDK(e.to_string()))?;
@@ -1525,21 +4034,48 @@ pub(crate) async fn start_ldk(
     ));
 
     // Persist ChannelManager and NetworkGraph
-    let persister = Arc::new(FilesystemStore::new(ldk_data_dir_path.clone()));
+    let persister = Arc::new(MirroredStore::new(
+        Arc::clone(&fs_store),
+        None,
+        Arc::clone(&static_state.persistence),
+    ));
+
+    let swaps = Arc::new(Swaps::load(Arc::clone(&database))?);
 
     let unlocked_state = Arc::new(UnlockedAppState {
+        chain_monitor: Arc::clone(&chain_monitor),
         channel_manager: Arc::clone(&channel_manager),
+        database,
         inbound_payments,
         keys_manager,
         network_graph,
         onion_messenger,
         outbound_payments,
+        outbound_reservations: Arc::new(OutboundReservations::default()),
+        quote_cache: Arc::new(QuoteCache::default()),
+        router,
         peer_manager: Arc::clone(&peer_manager),
-        fs_store: Arc::clone(&fs_store),
+        peer_message_handler,
+        peer_limiter,
+        lsp: Arc::new(LspState::default()),
+        swaps,
+        alerts: Arc::new(Alerts::default()),
+        announcements,
+        channel_fee_tracker: Arc::new(ChannelFeeTracker::default()),
+        graph_pruner: Arc::new(GraphPruner::default()),
+        chain_reorgs,
+        recovery_mode,
+        pending_channel_opens: Arc::new(Mutex::new(HashMap::new())),
+        inbound_channels: Arc::new(InboundChannels::default()),
         persister: Arc::clone(&persister),
+        persistence: Arc::clone(&static_state.persistence),
+        unpersisted_outputs: Arc::new(UnpersistedOutputs::default()),
+        monitor_mirror,
         bump_tx_event_handler,
         rgb_wallet,
         rgb_online,
+        vanilla_addresses,
+        webhooks: Arc::new(Webhooks::default()),
     });
 
     let recent_payments_payment_ids = channel_manager
@@ -1553,6 +4089,7 @@ pub(crate) async fn start_ldk(
         })
         .collect::<Vec<PaymentId>>();
     unlocked_state.fail_outbound_pending_payments(recent_payments_payment_ids);
+    unlocked_state.restore_outbound_reservations(&static_state.ldk_data_dir);
 
     // Handle LDK Events
     let unlocked_state_copy = Arc::clone(&unlocked_state);
@@ -1566,13 +4103,20 @@ pub(crate) async fn start_ldk(
     };
 
     // Background Processing
-    let (bp_exit, bp_exit_check) = tokio::sync::watch::channel(());
+    // the network graph is pruned and persisted by watch_network_graph instead, as the background
+    // processor would also prune the channels we want to keep
     let background_processor = tokio::spawn(process_events_async(
         persister,
         event_handler,
         chain_monitor.clone(),
         channel_manager.clone(),
-        GossipSync::p2p(gossip_sync),
+        GossipSync::<
+            Arc<GossipSyncHandler>,
+            Arc<RapidGossipSync<Arc<NetworkGraph>, Arc<FilesystemLogger>>>,
+            Arc<NetworkGraph>,
+            Arc<GossipVerifier>,
+            Arc<FilesystemLogger>,
+        >::none(),
         peer_manager.clone(),
         logger.clone(),
         Some(scorer.clone()),
@@ -1591,6 +4135,7 @@ pub(crate) async fn start_ldk(
     // Regularly reconnect to channel peers.
     let connect_cm = Arc::clone(&channel_manager);
     let connect_pm = Arc::clone(&peer_manager);
+    let connect_limiter = Arc::clone(&unlocked_state.peer_limiter);
     let peer_data_path = format!("{}/channel_peer_data", ldk_data_dir.clone());
     let stop_connect = Arc::clone(&stop_processing);
     tokio::spawn(async move {
@@ -1606,6 +4151,7 @@ pub(crate) async fn start_ldk(
                         .iter()
                         .map(|chan| chan.counterparty.node_id)
                         .filter(|id| !peers.iter().any(|(pk, _)| id == pk))
+                        .filter(|id| !connect_limiter.is_banned(id))
                     {
                         if stop_connect.load(Ordering::Acquire) {
                             return;
@@ -1631,6 +4177,8 @@ pub(crate) async fn start_ldk(
     // some public channels.
     let peer_man = Arc::clone(&peer_manager);
     let chan_man = Arc::clone(&channel_manager);
+    let node_announcements = Arc::clone(&unlocked_state.announcements);
+    let announcement_state = Arc::clone(static_state);
     tokio::spawn(async move {
         // First wait a minute until we have some peers and maybe have opened a channel.
         tokio::time::sleep(Duration::from_secs(60)).await;
@@ -1639,25 +4187,143 @@ pub(crate) async fn start_ldk(
         let mut interval = tokio::time::interval(Duration::from_secs(3600));
         loop {
             interval.tick().await;
-            // Don't bother trying to announce if we don't have any public channls, though our
-            // peers should drop such an announcement anyway. Note that announcement may not
-            // propagate until we have a channel with 6+ confirmations.
-            if chan_man.list_channels().iter().any(|chan| chan.is_public) {
-                peer_man.broadcast_node_announcement(
-                    [0; 3],
-                    ldk_announced_node_name,
-                    ldk_announced_listen_addr.clone(),
-                );
-            }
+            announce_node(
+                &peer_man,
+                &chan_man,
+                &node_announcements,
+                &announcement_state,
+            );
         }
     });
 
+    // wallet operations interrupted before the wallet recorded their transaction, reconciled
+    // before the wallet gets used again
+    let reconcile_state = Arc::clone(&unlocked_state);
+    let reconcile_static_state = Arc::clone(static_state);
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = reconcile_state.reconcile_wallet_intents(&reconcile_static_state) {
+            tracing::error!("Failed to reconcile the interrupted wallet operations: {e}");
+        }
+        // a restored wallet may have been used past the addresses it watches
+        if let Err(e) = reconcile_state.discover_wallet_addresses(&reconcile_static_state) {
+            tracing::error!("Failed to discover the used wallet addresses: {e}");
+        }
+    })
+    .await
+    .unwrap();
+
+    swaps::resume_swaps(&unlocked_state);
+
+    if unlocked_state.recovery_mode {
+        unlocked_state.force_close_all_channels();
+    }
+
     tokio::spawn(periodic_sweep(
         Arc::clone(&unlocked_state),
         Arc::clone(static_state),
         Arc::clone(&stop_processing),
     ));
 
+    tokio::spawn(retry_unpersisted_outputs(
+        Arc::clone(&unlocked_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(watch_htlc_expiry(
+        Arc::clone(&unlocked_state),
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(watch_asset_allocations(
+        Arc::clone(&unlocked_state),
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(watch_resolved_monitors(
+        Arc::clone(&unlocked_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(watch_invoice_expiry(
+        Arc::clone(&unlocked_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(watch_alerts(
+        Arc::clone(&unlocked_state),
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(watch_channel_opens(
+        Arc::clone(&unlocked_state),
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(deliver_webhooks(
+        Arc::clone(&unlocked_state),
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(watch_close_negotiations(
+        Arc::clone(&unlocked_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(watch_chain_reorgs(
+        Arc::clone(&unlocked_state),
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(watch_unconfirmed_txs(
+        Arc::clone(&unlocked_state),
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(resume_pending_transfers(
+        Arc::clone(&unlocked_state),
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(watch_network_graph(
+        Arc::clone(&unlocked_state),
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(enforce_peer_limits(
+        Arc::clone(&unlocked_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(watch_proxy(
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    tokio::spawn(process_peer_messages(
+        peer_message_receiver,
+        Arc::clone(&unlocked_state),
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    let (broadcast_sender, broadcast_receiver) = tokio::sync::mpsc::unbounded_channel();
+    bitcoind_client.set_broadcast_listener(Some(broadcast_sender));
+    tokio::spawn(process_broadcast_txs(
+        broadcast_receiver,
+        Arc::clone(&unlocked_state),
+        Arc::clone(static_state),
+        Arc::clone(&stop_processing),
+    ));
+
     tracing::info!("LDK logs are available at <your-supplied-ldk-data-dir-path>/.ldk/logs");
     tracing::info!("Local Node ID is {}", channel_manager.get_our_node_id());
 
@@ -1705,7 +4371,21 @@ pub(crate) async fn stop_ldk(app_state: Arc<AppState>) {
     tracing::info!("Stopping LDK");
 
     if let Some(join_handle) = app_state.stop_ldk() {
-        join_handle.await.unwrap().unwrap();
+        // the error has already degraded the node when it comes from a failed write
+        if let Err(e) = join_handle.await.unwrap() {
+            tracing::error!("Background processor stopped with an error: {e}");
+        }
+    }
+
+    if let Some(unlocked_state) = app_state.get_unlocked_app_state().await.as_ref() {
+        if let Err(e) = unlocked_state.persist_network_graph() {
+            tracing::error!("Failed to persist the network graph: {e}");
+        }
+        // so that a backup taken while the node is stopped lets the restored node resume them
+        let storage_dir_path = Path::new(&app_state.static_state.storage_dir_path);
+        if let Err(e) = unlocked_state.record_pending_transfers(storage_dir_path) {
+            tracing::error!("Failed to record the pending RGB transfers: {e}");
+        }
     }
 
     tracing::info!("Stopped LDK");
@@ -0,0 +1,345 @@
+use amplify::s;
+use bitcoin::hashes::Hash;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Network;
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::PaymentHash;
+use lightning::rgb_utils::RgbInfo;
+use lightning::util::config::ChannelConfig;
+use lightning_invoice::{utils::create_invoice_from_channelmanager, Currency};
+use rgbstd::contract::ContractId;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::database::ChannelFeeRate;
+use crate::ldk::{
+    open_channel_config, PaymentInfo, FEE_RATE, MIN_CHANNEL_CONFIRMATIONS,
+    MIN_FINAL_CLTV_EXPIRY_DELTA,
+};
+use crate::peer_messages::PeerMessage;
+use crate::routes::{
+    HTLCStatus, InboundChannelRequest, InboundChannelRequestStatus, HTLC_MIN_MSAT,
+    OPENCHANNEL_MAX_SAT, OPENCHANNEL_MIN_RGB_AMT, OPENCHANNEL_MIN_SAT,
+};
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+const FEE_INVOICE_EXPIRY_SEC: u32 = 3600;
+
+/// Terms under which the node opens RGB channels requested by its peers
+#[derive(Clone, Debug)]
+pub(crate) struct LspPolicy {
+    pub(crate) fee_sat: u64,
+    pub(crate) max_capacity_sat: u64,
+    pub(crate) max_asset_amount: Option<u64>,
+}
+
+/// Request for a channel carrying the given asset amount on the LSP side
+#[derive(Clone, Debug)]
+pub(crate) struct LspChannelRequest {
+    pub(crate) request_id: String,
+    pub(crate) asset_id: String,
+    pub(crate) asset_amount: u64,
+    pub(crate) capacity_sat: u64,
+}
+
+impl_writeable_tlv_based!(LspChannelRequest, {
+    (0, request_id, required),
+    (2, asset_id, required),
+    (4, asset_amount, required),
+    (6, capacity_sat, required),
+});
+
+/// Answer of the LSP to a channel request, sent again once a paid channel starts opening
+#[derive(Clone, Debug)]
+pub(crate) struct LspChannelResponse {
+    pub(crate) request_id: String,
+    pub(crate) accepted: bool,
+    pub(crate) fee_sat: u64,
+    pub(crate) fee_invoice: Option<String>,
+    pub(crate) temporary_channel_id: Option<String>,
+    pub(crate) reason: Option<String>,
+}
+
+impl_writeable_tlv_based!(LspChannelResponse, {
+    (0, request_id, required),
+    (2, accepted, required),
+    (4, fee_sat, required),
+    (6, fee_invoice, option),
+    (8, temporary_channel_id, option),
+    (10, reason, option),
+});
+
+impl LspChannelResponse {
+    fn rejected(request_id: String, reason: String) -> Self {
+        Self {
+            request_id,
+            accepted: false,
+            fee_sat: 0,
+            fee_invoice: None,
+            temporary_channel_id: None,
+            reason: Some(reason),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct LspState {
+    // channels requested by this node, by request ID
+    requests: Mutex<BTreeMap<String, InboundChannelRequest>>,
+    // channels granted by this node that are waiting for the fee payment, with the fee asked
+    awaiting_fee: Mutex<HashMap<PaymentHash, (PublicKey, LspChannelRequest, u64)>>,
+}
+
+impl LspState {
+    pub(crate) fn add_request(&self, request: InboundChannelRequest) {
+        self.requests
+            .lock()
+            .unwrap()
+            .insert(request.request_id.clone(), request);
+    }
+
+    pub(crate) fn requests(&self) -> Vec<InboundChannelRequest> {
+        self.requests.lock().unwrap().values().cloned().collect()
+    }
+}
+
+pub(crate) fn handle_channel_request(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    peer_pubkey: PublicKey,
+    request: LspChannelRequest,
+) {
+    tracing::info!(
+        "Received channel request {} from {}",
+        request.request_id,
+        peer_pubkey
+    );
+    let request_id = request.request_id.clone();
+    let response = grant_channel_request(unlocked_state, static_state, peer_pubkey, request)
+        .unwrap_or_else(|reason| {
+            tracing::info!("Rejected channel request {request_id}: {reason}");
+            LspChannelResponse::rejected(request_id, reason)
+        });
+    unlocked_state.send_peer_message(peer_pubkey, PeerMessage::LspChannelResponse(response));
+}
+
+fn grant_channel_request(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    peer_pubkey: PublicKey,
+    request: LspChannelRequest,
+) -> Result<LspChannelResponse, String> {
+    let policy = static_state
+        .lsp_policy
+        .as_ref()
+        .ok_or(s!("not accepting channel requests"))?;
+
+    let max_capacity_sat = policy.max_capacity_sat.min(OPENCHANNEL_MAX_SAT);
+    if !(OPENCHANNEL_MIN_SAT..=max_capacity_sat).contains(&request.capacity_sat) {
+        return Err(format!(
+            "capacity must be between {OPENCHANNEL_MIN_SAT} and {max_capacity_sat} sats"
+        ));
+    }
+    if request.asset_amount < OPENCHANNEL_MIN_RGB_AMT {
+        return Err(format!(
+            "asset amount must be at least {OPENCHANNEL_MIN_RGB_AMT}"
+        ));
+    }
+    if let Some(max_asset_amount) = policy.max_asset_amount {
+        if request.asset_amount > max_asset_amount {
+            return Err(format!("asset amount must be at most {max_asset_amount}"));
+        }
+    }
+    let contract_id =
+        ContractId::from_str(&request.asset_id).map_err(|_| s!("invalid asset ID"))?;
+    let spendable = unlocked_state
+        .rgb_get_asset_balance(contract_id)
+        .map_err(|_| s!("unknown asset"))?
+        .spendable;
+    if request.asset_amount > spendable {
+        return Err(s!("not enough assets available"));
+    }
+
+    if policy.fee_sat == 0 {
+        let temporary_channel_id =
+            open_requested_channel(unlocked_state, static_state, peer_pubkey, &request)?;
+        return Ok(LspChannelResponse {
+            request_id: request.request_id,
+            accepted: true,
+            fee_sat: 0,
+            fee_invoice: None,
+            temporary_channel_id: Some(temporary_channel_id),
+            reason: None,
+        });
+    }
+
+    let currency = match static_state.network {
+        Network::Bitcoin => Currency::Bitcoin,
+        Network::Testnet => Currency::BitcoinTestnet,
+        Network::Regtest => Currency::Regtest,
+        Network::Signet => Currency::Signet,
+    };
+    let amt_msat = policy.fee_sat * 1000;
+    let invoice = create_invoice_from_channelmanager(
+        &unlocked_state.channel_manager,
+        unlocked_state.keys_manager.clone(),
+        static_state.logger.clone(),
+        currency,
+        Some(amt_msat),
+        s!("LSP channel fee"),
+        FEE_INVOICE_EXPIRY_SEC,
+        Some(MIN_FINAL_CLTV_EXPIRY_DELTA),
+        None,
+        None,
+    )
+    .map_err(|e| format!("failed to create the fee invoice: {e}"))?;
+    let payment_hash = PaymentHash((*invoice.payment_hash()).into_inner());
+    unlocked_state.add_inbound_payment(
+        payment_hash,
+        PaymentInfo {
+            preimage: None,
+            secret: Some(*invoice.payment_secret()),
+            status: HTLCStatus::Pending,
+            amt_msat: Some(amt_msat),
+            label: Some(format!("LSP channel request {}", request.request_id)),
+            created_at: get_current_timestamp(),
+            expires_at: Some(
+                invoice.duration_since_epoch().as_secs() + invoice.expiry_time().as_secs(),
+            ),
+            failure_reason: None,
+            failing_hop: None,
+            settled_at: None,
+            payer_requirements: None,
+            payer: None,
+            payee: None,
+            onchain_txid: None,
+            closure: None,
+            hops: vec![],
+        },
+    );
+    let request_id = request.request_id.clone();
+    unlocked_state
+        .lsp
+        .awaiting_fee
+        .lock()
+        .unwrap()
+        .insert(payment_hash, (peer_pubkey, request, policy.fee_sat));
+
+    Ok(LspChannelResponse {
+        request_id,
+        accepted: true,
+        fee_sat: policy.fee_sat,
+        fee_invoice: Some(invoice.to_string()),
+        temporary_channel_id: None,
+        reason: None,
+    })
+}
+
+/// Open the channel of a granted request whose fee has just been paid
+pub(crate) fn handle_fee_payment(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    payment_hash: &PaymentHash,
+) {
+    let Some((peer_pubkey, request, fee_sat)) = unlocked_state
+        .lsp
+        .awaiting_fee
+        .lock()
+        .unwrap()
+        .remove(payment_hash)
+    else {
+        return;
+    };
+    let response = match open_requested_channel(unlocked_state, static_state, peer_pubkey, &request)
+    {
+        Ok(temporary_channel_id) => LspChannelResponse {
+            request_id: request.request_id,
+            accepted: true,
+            fee_sat,
+            fee_invoice: None,
+            temporary_channel_id: Some(temporary_channel_id),
+            reason: None,
+        },
+        Err(reason) => {
+            tracing::error!(
+                "Failed to open channel for paid request {}: {reason}",
+                request.request_id
+            );
+            LspChannelResponse::rejected(request.request_id, reason)
+        }
+    };
+    unlocked_state.send_peer_message(peer_pubkey, PeerMessage::LspChannelResponse(response));
+}
+
+fn open_requested_channel(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    peer_pubkey: PublicKey,
+    request: &LspChannelRequest,
+) -> Result<String, String> {
+    let contract_id =
+        ContractId::from_str(&request.asset_id).map_err(|_| s!("invalid asset ID"))?;
+    let temporary_channel_id = unlocked_state
+        .open_colored_channel(
+            static_state,
+            peer_pubkey,
+            request.capacity_sat,
+            static_state.channel_handshake.dust_limit_msat(),
+            RgbInfo {
+                contract_id,
+                local_rgb_amount: request.asset_amount,
+                remote_rgb_amount: 0,
+            },
+            None,
+            ChannelFeeRate {
+                target_conf_blocks: None,
+                fee_rate_sat_vb: FEE_RATE,
+            },
+            open_channel_config(
+                false,
+                MIN_CHANNEL_CONFIRMATIONS as u32,
+                HTLC_MIN_MSAT,
+                true,
+                ChannelConfig::default(),
+                &static_state.channel_handshake,
+            ),
+        )
+        .map_err(|e| e.to_string())?;
+    tracing::info!(
+        "Opening channel {temporary_channel_id} requested by {peer_pubkey} ({})",
+        request.request_id
+    );
+    Ok(temporary_channel_id)
+}
+
+pub(crate) fn handle_channel_response(
+    unlocked_state: &UnlockedAppState,
+    peer_pubkey: PublicKey,
+    response: LspChannelResponse,
+) {
+    let mut requests = unlocked_state.lsp.requests.lock().unwrap();
+    let Some(request) = requests
+        .get_mut(&response.request_id)
+        .filter(|r| r.lsp_pubkey == peer_pubkey.to_string())
+    else {
+        tracing::warn!(
+            "Ignoring response from {peer_pubkey} to unknown channel request {}",
+            response.request_id
+        );
+        return;
+    };
+    request.status = if !response.accepted {
+        InboundChannelRequestStatus::Rejected
+    } else if response.temporary_channel_id.is_some() {
+        InboundChannelRequestStatus::Opening
+    } else {
+        InboundChannelRequestStatus::AwaitingFee
+    };
+    request.fee_sat = Some(response.fee_sat);
+    if response.fee_invoice.is_some() {
+        request.fee_invoice = response.fee_invoice;
+    }
+    request.temporary_channel_id = response.temporary_channel_id;
+    request.reason = response.reason;
+}
@@ -1,20 +1,74 @@
+mod alerts;
+mod amounts;
+mod announcements;
+mod api_limits;
 mod args;
+mod asset_export;
+mod asset_import;
+mod asset_registry;
+mod auth;
 mod backup;
 mod bdk;
 mod bitcoind;
+mod channel_debug;
+mod channel_fees;
+mod channel_handshake;
+mod channel_opens;
+mod channel_suggestions;
+mod close_negotiation;
+mod consolidation;
+mod data_dir;
+mod data_dir_lock;
+mod data_version;
+mod database;
 mod disk;
+mod dry_run;
 mod error;
+mod graph_pruning;
+mod integrity;
+mod invoices;
+mod jobs;
+mod latency;
 mod ldk;
+mod lsp;
+mod mirror;
+mod monitor_archive;
+mod onchain_fallback;
+mod outbound_reservations;
+mod pagination;
+mod payer;
+mod payment_quotes;
+mod payment_request;
+mod peer_exposure;
+mod peer_limits;
+mod peer_messages;
+mod pending_transfers;
+mod persistence;
 mod proxy;
+mod rebroadcast;
+mod received_transfers;
+mod recovery;
+#[cfg(any(test, feature = "regtest-endpoints"))]
+mod regtest;
+mod reorgs;
+mod replaced_transfers;
 mod rgb;
 mod routes;
+mod stats;
+mod swaps;
 mod utils;
+mod utxo_creation;
+mod utxo_reservations;
+mod wallet_addresses;
+mod wallet_intents;
+mod webhooks;
 
 #[cfg(test)]
 mod test;
 
 use anyhow::Result;
 use axum::{
+    middleware,
     routing::{get, post},
     Router,
 };
@@ -24,19 +78,35 @@ use tower_http::cors::CorsLayer;
 use tower_http::trace::{self, TraceLayer};
 use tracing_subscriber::{filter, prelude::*};
 
+use crate::api_limits::check_api_limits;
 use crate::args::LdkUserInfo;
+use crate::auth::{check_auth, check_stopped};
 use crate::error::AppError;
+use crate::latency::track_latency;
 use crate::ldk::stop_ldk;
 use crate::routes::{
-    address, asset_balance, backup, btc_balance, change_password, close_channel, connect_peer,
-    create_utxos, decode_ln_invoice, decode_rgb_invoice, disconnect_peer, init, invoice_status,
-    issue_asset, keysend, list_assets, list_channels, list_payments, list_peers, list_transactions,
-    list_transfers, list_unspents, ln_invoice, lock, network_info, node_info, open_channel,
-    refresh_transfers, restore, rgb_invoice, send_asset, send_btc, send_onion_message,
-    send_payment, shutdown, sign_message, unlock,
+    abandon_payment, accept_channel, add_webhook, address, alerts, asset_balance, auto_unlock,
+    backup, balances, ban_peer, btc_balance, cancel_invoice, cancel_job, change_password,
+    channel_debug, channel_suggestions, close_channel, connect_peer, consolidate_assets, counters,
+    create_utxos, decode_ln_invoice, decode_payment_request, decode_rgb_invoice, delete_payment,
+    delete_webhook, disconnect_peer, events, export_asset, fail_transfers, get_payment, health,
+    import_asset, init, invoice_status, issue_asset, job_status, keysend, latency, list_addresses,
+    list_assets, list_bans, list_channels, list_closed_channels, list_htlcs,
+    list_inbound_channel_requests, list_payments, list_peers, list_swaps, list_sweeps,
+    list_transactions, list_transfers, list_unspents, list_webhooks, ln_invoice, lock,
+    network_info, node_info, open_channel, pending_channels, prune_graph, prune_monitors,
+    prune_payments, query_node_info, quote_payment, ready, rebroadcast_announcements,
+    refresh_transfers, reject_channel, request_inbound_channel, restore, rgb_invoice, send_asset,
+    send_btc, send_custom_message, send_onion_message, send_payment, set_alerts, set_alias,
+    set_channel_note, set_label, set_payment_policy, set_peer_exposure_limits, shutdown,
+    sign_message, spending_limits, stats, swap_into_channel, swap_out_of_channel, unban_peer,
+    unlock, verify_message,
 };
 use crate::utils::{start_daemon, AppState, LOGS_DIR};
 
+#[cfg(feature = "regtest-endpoints")]
+use crate::routes::{fund_address, mine_blocks};
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = args::parse_startup_args()?;
@@ -67,7 +137,7 @@ async fn main() -> Result<()> {
 
     tracing::info!("Listening on {}", addr);
     axum::Server::bind(&addr)
-        .serve(router.into_make_service())
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(shutdown_signal(app_state))
         .await
         .unwrap();
@@ -75,47 +145,187 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-pub(crate) async fn app(args: LdkUserInfo) -> Result<(Router, Arc<AppState>), AppError> {
+/// Routes that can be called with the read-only API token, all others require the admin one
+pub(crate) const READ_ONLY_ROUTES: &[&str] = &[
+    "/alerts",
+    "/assetbalance",
+    "/balances",
+    "/btcbalance",
+    "/channelsuggestions",
+    "/counters",
+    "/decodelninvoice",
+    "/decodepaymentrequest",
+    "/decodergbinvoice",
+    "/events",
+    "/exportasset",
+    "/getpayment",
+    "/health",
+    "/invoicestatus",
+    "/jobstatus",
+    "/latency",
+    "/listaddresses",
+    "/listassets",
+    "/listbans",
+    "/listchannels",
+    "/listclosedchannels",
+    "/listhtlcs",
+    "/listinboundchannelrequests",
+    "/listpayments",
+    "/listpeers",
+    "/listswaps",
+    "/listsweeps",
+    "/listtransactions",
+    "/listtransfers",
+    "/listunspents",
+    "/networkinfo",
+    "/nodeinfo",
+    "/pendingchannels",
+    "/querynodeinfo",
+    "/quotepayment",
+    "/ready",
+    "/spendinglimits",
+    "/stats",
+    "/verifymessage",
+];
+
+/// Routes still served after a restartable shutdown, all others are refused until an unlock
+pub(crate) const STOPPED_ROUTES: &[&str] = &["/health", "/nodeinfo", "/unlock"];
+
+pub(crate) async fn app(mut args: LdkUserInfo) -> Result<(Router, Arc<AppState>), AppError> {
+    let unlock_password = args.unlock_password.take();
     let app_state = start_daemon(args).await?;
+    let api_limiter = &app_state.static_state.api_limiter;
 
     let router = Router::new()
+        .route("/abandonpayment", post(abandon_payment))
+        .route("/acceptchannel", post(accept_channel))
         .route("/address", post(address))
+        .route("/addwebhook", post(add_webhook))
+        .route("/alerts", get(alerts))
         .route("/assetbalance", post(asset_balance))
         .route("/backup", post(backup))
+        .route("/balances", get(balances))
+        .route("/banpeer", post(ban_peer))
         .route("/btcbalance", get(btc_balance))
+        .route("/cancelinvoice", post(cancel_invoice))
+        .route("/canceljob", post(cancel_job))
         .route("/changepassword", post(change_password))
+        .route("/channeldebug", get(channel_debug))
+        .route("/channelsuggestions", get(channel_suggestions))
         .route("/closechannel", post(close_channel))
         .route("/connectpeer", post(connect_peer))
+        .route("/consolidateassets", post(consolidate_assets))
+        .route("/counters", get(counters))
         .route("/createutxos", post(create_utxos))
         .route("/decodelninvoice", post(decode_ln_invoice))
+        .route("/decodepaymentrequest", post(decode_payment_request))
         .route("/decodergbinvoice", post(decode_rgb_invoice))
+        .route("/deletepayment", post(delete_payment))
+        .route("/deletewebhook", post(delete_webhook))
         .route("/disconnectpeer", post(disconnect_peer))
+        .route("/events", get(events))
+        .route("/exportasset", get(export_asset))
+        .route("/failtransfers", post(fail_transfers))
+        .route("/getpayment", get(get_payment))
+        .route("/health", get(health))
+        .route(
+            "/importasset",
+            post(import_asset).layer(api_limiter.body_limit("/importasset")),
+        )
         .route("/init", post(init))
         .route("/invoicestatus", post(invoice_status))
         .route("/issueasset", post(issue_asset))
+        .route("/jobstatus", get(job_status))
         .route("/keysend", post(keysend))
+        .route("/latency", get(latency))
+        .route("/listaddresses", get(list_addresses))
         .route("/listassets", get(list_assets))
+        .route("/listbans", get(list_bans))
         .route("/listchannels", get(list_channels))
+        .route("/listclosedchannels", get(list_closed_channels))
+        .route("/listhtlcs", get(list_htlcs))
+        .route(
+            "/listinboundchannelrequests",
+            get(list_inbound_channel_requests),
+        )
         .route("/listpayments", get(list_payments))
         .route("/listpeers", get(list_peers))
+        .route("/listswaps", get(list_swaps))
+        .route("/listsweeps", get(list_sweeps))
         .route("/listtransactions", get(list_transactions))
         .route("/listtransfers", post(list_transfers))
         .route("/listunspents", get(list_unspents))
+        .route("/listwebhooks", get(list_webhooks))
         .route("/lninvoice", post(ln_invoice))
         .route("/lock", post(lock))
         .route("/networkinfo", get(network_info))
         .route("/nodeinfo", get(node_info))
         .route("/openchannel", post(open_channel))
+        .route("/pendingchannels", get(pending_channels))
+        .route("/prunegraph", post(prune_graph))
+        .route("/prunemonitors", post(prune_monitors))
+        .route("/prunepayments", post(prune_payments))
+        .route("/querynodeinfo", get(query_node_info))
+        .route("/quotepayment", post(quote_payment))
+        .route("/ready", get(ready))
+        .route("/rebroadcastannouncements", post(rebroadcast_announcements))
         .route("/refreshtransfers", post(refresh_transfers))
+        .route("/rejectchannel", post(reject_channel))
+        .route("/requestinboundchannel", post(request_inbound_channel))
         .route("/restore", post(restore))
         .route("/rgbinvoice", post(rgb_invoice))
         .route("/sendasset", post(send_asset))
         .route("/sendbtc", post(send_btc))
-        .route("/sendonionmessage", post(send_onion_message))
+        .route(
+            "/sendcustommsg",
+            post(send_custom_message).layer(api_limiter.body_limit("/sendcustommsg")),
+        )
+        .route(
+            "/sendonionmessage",
+            post(send_onion_message).layer(api_limiter.body_limit("/sendonionmessage")),
+        )
         .route("/sendpayment", post(send_payment))
+        .route("/setalerts", post(set_alerts))
+        .route("/setalias", post(set_alias))
+        .route("/setchannelnote", post(set_channel_note))
+        .route("/setlabel", post(set_label))
+        .route("/setpaymentpolicy", post(set_payment_policy))
+        .route("/setpeerexposurelimits", post(set_peer_exposure_limits))
         .route("/shutdown", post(shutdown))
         .route("/signmessage", post(sign_message))
+        .route("/spendinglimits", get(spending_limits))
+        .route("/stats", get(stats))
+        .route("/swapintochannel", post(swap_into_channel))
+        .route("/swapoutofchannel", post(swap_out_of_channel))
+        .route("/unbanpeer", post(unban_peer))
         .route("/unlock", post(unlock))
+        .route("/verifymessage", post(verify_message));
+
+    // test-only endpoints, to drive a regtest node from external test harnesses
+    #[cfg(feature = "regtest-endpoints")]
+    let router = router
+        .route("/fundaddress", post(fund_address))
+        .route("/mineblocks", post(mine_blocks));
+
+    // the latency is measured innermost, only for the requests reaching the handlers
+    let router = router
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            track_latency,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            check_stopped,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            check_auth,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            check_api_limits,
+        ))
+        .layer(api_limiter.body_limit(""))
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(trace::DefaultMakeSpan::new().level(tracing::Level::INFO))
@@ -124,6 +334,11 @@ pub(crate) async fn app(args: LdkUserInfo) -> Result<(Router, Arc<AppState>), Ap
         .layer(CorsLayer::permissive())
         .with_state(app_state.clone());
 
+    // the unlock runs while the API starts serving, so that /ready can report how it went
+    if let Some(password) = unlock_password {
+        tokio::spawn(auto_unlock(app_state.clone(), password));
+    }
+
     Ok((router, app_state))
 }
 
@@ -179,5 +394,6 @@ async fn shutdown_signal(app_state: Arc<AppState>) {
         tracing::info!("Will shutdown after change state is complete");
         tokio::time::sleep(Duration::from_millis(300)).await;
     }
-    stop_ldk(app_state).await;
+    stop_ldk(app_state.clone()).await;
+    app_state.static_state.data_dir_lock.release();
 }
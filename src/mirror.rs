@@ -0,0 +1,405 @@
+use amplify::s;
+use lightning::util::persist::{
+    KVStore, CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+    MONITOR_UPDATING_PERSISTER_PREPEND_SENTINEL,
+};
+use lightning_persister::fs_store::FilesystemStore;
+use reqwest::Client;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::persistence::PersistenceHealth;
+
+const MIRROR_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Location channel monitors get mirrored to
+#[derive(Clone, Debug)]
+pub(crate) enum MirrorTarget {
+    Directory(PathBuf),
+    Http { url: String, token: Option<String> },
+}
+
+impl FromStr for MirrorTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("http://") || s.starts_with("https://") {
+            return Ok(MirrorTarget::Http {
+                url: s.trim_end_matches('/').to_string(),
+                token: None,
+            });
+        }
+        if s.contains("://") && !s.starts_with("file://") {
+            return Err(format!("unsupported target {s}"));
+        }
+        let path = s.trim_start_matches("file://");
+        if path.is_empty() {
+            return Err(s!("empty path"));
+        }
+        Ok(MirrorTarget::Directory(PathBuf::from(path)))
+    }
+}
+
+enum MirrorOp {
+    Write { key_path: String, buf: Vec<u8> },
+    Remove { key_path: String },
+}
+
+/// Queue of channel monitor changes waiting to be mirrored
+pub(crate) struct MonitorMirror {
+    sender: UnboundedSender<MirrorOp>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl MonitorMirror {
+    /// Start the task that applies queued changes to the target, retrying until they succeed
+    pub(crate) fn start(target: MirrorTarget) -> Arc<Self> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let pending = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(run_mirror(target, receiver, Arc::clone(&pending)));
+        Arc::new(MonitorMirror { sender, pending })
+    }
+
+    /// Number of changes not yet applied to the target
+    pub(crate) fn pending_updates(&self) -> usize {
+        self.pending.load(Ordering::Acquire)
+    }
+
+    fn enqueue(&self, op: MirrorOp) {
+        self.pending.fetch_add(1, Ordering::AcqRel);
+        if self.sender.send(op).is_err() {
+            self.pending.fetch_sub(1, Ordering::AcqRel);
+            tracing::error!("monitor mirror task is not running");
+        }
+    }
+}
+
+/// KVStore persisting to the local filesystem and mirroring channel monitor changes, a failed
+/// write degrades the node
+pub(crate) struct MirroredStore {
+    inner: Arc<FilesystemStore>,
+    mirror: Option<Arc<MonitorMirror>>,
+    health: Arc<PersistenceHealth>,
+}
+
+impl MirroredStore {
+    pub(crate) fn new(
+        inner: Arc<FilesystemStore>,
+        mirror: Option<Arc<MonitorMirror>>,
+        health: Arc<PersistenceHealth>,
+    ) -> Self {
+        Self {
+            inner,
+            mirror,
+            health,
+        }
+    }
+
+    fn mirror_for(&self, primary_namespace: &str) -> Option<&Arc<MonitorMirror>> {
+        if primary_namespace == CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE
+            || primary_namespace == CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE
+        {
+            self.mirror.as_ref()
+        } else {
+            None
+        }
+    }
+}
+
+impl KVStore for MirroredStore {
+    fn read(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+    ) -> Result<Vec<u8>, io::Error> {
+        self.inner.read(primary_namespace, secondary_namespace, key)
+    }
+
+    fn write(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        buf: &[u8],
+    ) -> Result<(), io::Error> {
+        let path = key_path(primary_namespace, secondary_namespace, key);
+        self.health.write(&path, || {
+            self.inner
+                .write(primary_namespace, secondary_namespace, key, buf)
+        })?;
+        if let Some(mirror) = self.mirror_for(primary_namespace) {
+            mirror.enqueue(MirrorOp::Write {
+                key_path: path,
+                buf: buf.to_vec(),
+            });
+        }
+        Ok(())
+    }
+
+    fn remove(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        lazy: bool,
+    ) -> Result<(), io::Error> {
+        let path = key_path(primary_namespace, secondary_namespace, key);
+        self.health.write(&path, || {
+            self.inner
+                .remove(primary_namespace, secondary_namespace, key, lazy)
+        })?;
+        if let Some(mirror) = self.mirror_for(primary_namespace) {
+            mirror.enqueue(MirrorOp::Remove { key_path: path });
+        }
+        Ok(())
+    }
+
+    fn list(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+    ) -> Result<Vec<String>, io::Error> {
+        self.inner.list(primary_namespace, secondary_namespace)
+    }
+}
+
+fn key_path(primary_namespace: &str, secondary_namespace: &str, key: &str) -> String {
+    [primary_namespace, secondary_namespace, key]
+        .iter()
+        .filter(|p| !p.is_empty())
+        .cloned()
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+async fn run_mirror(
+    target: MirrorTarget,
+    mut receiver: UnboundedReceiver<MirrorOp>,
+    pending: Arc<AtomicUsize>,
+) {
+    let client = Client::new();
+    while let Some(op) = receiver.recv().await {
+        // changes are applied in order, so a failing one holds back the following ones
+        loop {
+            match apply_op(&target, &client, &op).await {
+                Ok(()) => break,
+                Err(e) => {
+                    tracing::error!(
+                        "failed to mirror channel monitor change (pending: {}): {e}",
+                        pending.load(Ordering::Acquire)
+                    );
+                    tokio::time::sleep(MIRROR_RETRY_INTERVAL).await;
+                }
+            }
+        }
+        pending.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+async fn apply_op(target: &MirrorTarget, client: &Client, op: &MirrorOp) -> Result<(), String> {
+    match target {
+        MirrorTarget::Directory(dir) => {
+            let dir = dir.clone();
+            let (key_path, buf) = match op {
+                MirrorOp::Write { key_path, buf } => (key_path.clone(), Some(buf.clone())),
+                MirrorOp::Remove { key_path } => (key_path.clone(), None),
+            };
+            tokio::task::spawn_blocking(move || apply_dir_op(&dir, &key_path, buf))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| e.to_string())
+        }
+        MirrorTarget::Http { url, token } => {
+            let request = match op {
+                MirrorOp::Write { key_path, buf } => {
+                    client.put(format!("{url}/{key_path}")).body(buf.clone())
+                }
+                MirrorOp::Remove { key_path } => client.delete(format!("{url}/{key_path}")),
+            };
+            let request = match token {
+                Some(token) => request.bearer_auth(token),
+                None => request,
+            };
+            let res = request.send().await.map_err(|e| e.to_string())?;
+            let status = res.status();
+            if status.is_success() || (matches!(op, MirrorOp::Remove { .. }) && status == 404) {
+                Ok(())
+            } else {
+                Err(format!("HTTP status {status}"))
+            }
+        }
+    }
+}
+
+fn apply_dir_op(dir: &Path, key_path: &str, buf: Option<Vec<u8>>) -> Result<(), io::Error> {
+    let dest = dir.join(key_path);
+    match buf {
+        Some(buf) => {
+            fs::create_dir_all(dest.parent().expect("key path has a parent"))?;
+            let tmp = dest.with_extension("tmp");
+            fs::write(&tmp, buf)?;
+            fs::rename(tmp, dest)
+        }
+        None => match fs::remove_file(dest) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Bring the local store up to date with the channel monitors and monitor updates on the mirror
+///
+/// Monitors missing locally are restored together with their updates, monitors the mirror has a
+/// newer copy of, by their latest update ID, get replaced, and the updates missing locally are
+/// added, which LDK applies on top of the monitors when loading. HTTP targets list the key paths
+/// under a namespace as a JSON array when it's fetched with a GET, e.g. `GET <url>/monitors`, and
+/// return the file when a key path is.
+pub(crate) async fn restore_from_mirror(
+    target: &MirrorTarget,
+    ldk_data_dir: &Path,
+) -> Result<usize, io::Error> {
+    let client = Client::new();
+    let mut restored = 0;
+    for namespace in [
+        CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+        CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+    ] {
+        for key in list_mirror(target, &client, namespace).await? {
+            let key_path = format!("{namespace}/{key}");
+            let dest = ldk_data_dir.join(&key_path);
+            let local = match fs::read(&dest) {
+                Ok(local) => Some(local),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            };
+            // monitor updates never change once written
+            if local.is_some() && namespace == CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE
+            {
+                continue;
+            }
+            let buf = read_mirror(target, &client, &key_path).await?;
+            if let Some(local) = local {
+                match (monitor_update_id(&local), monitor_update_id(&buf)) {
+                    (Some(local_id), Some(mirror_id)) if mirror_id > local_id => {
+                        tracing::info!(
+                            "replacing monitor {key} (update {local_id}) with the mirror copy \
+                            (update {mirror_id})"
+                        );
+                    }
+                    _ => continue,
+                }
+            }
+            apply_dir_op(ldk_data_dir, &key_path, Some(buf))?;
+            restored += 1;
+        }
+    }
+    if restored > 0 {
+        tracing::info!("restored {restored} channel monitor files from mirror");
+    }
+    Ok(restored)
+}
+
+/// Latest update ID of a serialized channel monitor, which follows its version prefix
+fn monitor_update_id(buf: &[u8]) -> Option<u64> {
+    let buf = buf
+        .strip_prefix(MONITOR_UPDATING_PERSISTER_PREPEND_SENTINEL)
+        .unwrap_or(buf);
+    Some(u64::from_be_bytes(buf.get(2..10)?.try_into().ok()?))
+}
+
+/// Key paths of the mirror files under the namespace, relative to it
+async fn list_mirror(
+    target: &MirrorTarget,
+    client: &Client,
+    namespace: &str,
+) -> Result<Vec<String>, io::Error> {
+    match target {
+        MirrorTarget::Directory(dir) => {
+            let mut keys = vec![];
+            list_dir(&dir.join(namespace), "", &mut keys)?;
+            Ok(keys)
+        }
+        MirrorTarget::Http { url, token } => {
+            let url = format!("{url}/{namespace}");
+            let Some(res) = http_get(client, &url, token.as_deref()).await? else {
+                return Ok(vec![]);
+            };
+            res.json::<Vec<String>>()
+                .await
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+        }
+    }
+}
+
+fn list_dir(dir: &Path, prefix: &str, keys: &mut Vec<String>) -> Result<(), io::Error> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap().to_string_lossy();
+        let key = format!("{prefix}{name}");
+        if path.is_dir() {
+            list_dir(&path, &format!("{key}/"), keys)?;
+        } else if path.extension().map_or(true, |e| e != "tmp") {
+            keys.push(key);
+        }
+    }
+    Ok(())
+}
+
+async fn read_mirror(
+    target: &MirrorTarget,
+    client: &Client,
+    key_path: &str,
+) -> Result<Vec<u8>, io::Error> {
+    match target {
+        MirrorTarget::Directory(dir) => fs::read(dir.join(key_path)),
+        MirrorTarget::Http { url, token } => {
+            let url = format!("{url}/{key_path}");
+            let res = http_get(client, &url, token.as_deref())
+                .await?
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, url))?;
+            let bytes = res
+                .bytes()
+                .await
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            Ok(bytes.to_vec())
+        }
+    }
+}
+
+/// Response to a GET of the URL, None if it's not found
+async fn http_get(
+    client: &Client,
+    url: &str,
+    token: Option<&str>,
+) -> Result<Option<reqwest::Response>, io::Error> {
+    let request = match token {
+        Some(token) => client.get(url).bearer_auth(token),
+        None => client.get(url),
+    };
+    let res = request
+        .send()
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    let status = res.status();
+    if status == 404 {
+        Ok(None)
+    } else if status.is_success() {
+        Ok(Some(res))
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("HTTP status {status}"),
+        ))
+    }
+}
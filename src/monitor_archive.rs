@@ -0,0 +1,185 @@
+use bitcoin::hashes::hex::ToHex;
+use lightning::chain::transaction::OutPoint;
+use lightning::util::persist::{
+    KVStore, CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+    CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::database::Database;
+use crate::disk::PENDING_SPENDABLE_OUTPUT_DIR;
+use crate::error::APIError;
+use crate::mirror::MirroredStore;
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+pub(crate) const ARCHIVED_MONITORS_DIR: &str = "archived_monitors";
+
+/// Directories holding spendable outputs until they get swept
+const SPENDABLE_OUTPUT_DIRS: [&str; 3] = [
+    PENDING_SPENDABLE_OUTPUT_DIR,
+    "processing_spendable_outputs",
+    "spendable_outputs",
+];
+
+/// Name the monitor of the channel with the given funding outpoint is persisted with
+fn monitor_key(funding_txo: &OutPoint) -> String {
+    format!("{}_{}", funding_txo.txid.to_hex(), funding_txo.index)
+}
+
+/// Number of spendable outputs not swept yet
+pub(crate) fn unswept_outputs(ldk_data_dir: &Path) -> usize {
+    SPENDABLE_OUTPUT_DIRS
+        .iter()
+        .filter_map(|dir| fs::read_dir(ldk_data_dir.join(dir)).ok())
+        .map(|entries| entries.count())
+        .sum()
+}
+
+/// Number of monitors moved to the archive
+pub(crate) fn archived_monitors(ldk_data_dir: &Path) -> usize {
+    fs::read_dir(ldk_data_dir.join(ARCHIVED_MONITORS_DIR)).map_or(0, |entries| entries.count())
+}
+
+/// Record the monitors of closed channels with nothing left to claim on-chain, along with the
+/// height at which they have first been found in that state
+fn record_resolved_monitors(unlocked_state: &UnlockedAppState) -> Result<(), APIError> {
+    let open_funding_txos: HashSet<OutPoint> = unlocked_state
+        .channel_manager
+        .list_channels()
+        .iter()
+        .filter_map(|c| c.funding_txo)
+        .collect();
+    let height = unlocked_state.channel_manager.current_best_block().height();
+    for funding_txo in unlocked_state.chain_monitor.list_monitors() {
+        if open_funding_txos.contains(&funding_txo) {
+            continue;
+        }
+        let Ok(monitor) = unlocked_state.chain_monitor.get_monitor(funding_txo) else {
+            continue;
+        };
+        if !monitor.get_claimable_balances().is_empty() {
+            continue;
+        }
+        unlocked_state
+            .database
+            .set_monitor_resolved(&monitor_key(&funding_txo), height)?;
+    }
+    Ok(())
+}
+
+/// Move a monitor and its pending updates to the archive, removing them from the store
+fn archive_monitor(store: &MirroredStore, archive_dir: &Path, key: &str) -> Result<(), APIError> {
+    fs::create_dir_all(archive_dir)?;
+    for update_key in store.list(CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE, key)? {
+        let update = store.read(
+            CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+            key,
+            &update_key,
+        )?;
+        fs::write(archive_dir.join(&update_key), update)?;
+        store.remove(
+            CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+            key,
+            &update_key,
+            false,
+        )?;
+    }
+    if let Ok(monitor) = store.read(
+        CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+        CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+        key,
+    ) {
+        fs::write(archive_dir.join("monitor"), monitor)?;
+        store.remove(
+            CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+            CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+            key,
+            false,
+        )?;
+    }
+    Ok(())
+}
+
+/// Move the monitors of the channels resolved at least the given number of blocks ago to the
+/// archive, so that they don't get read at unlock anymore
+///
+/// Monitors are loaded by the chain monitor for the whole time the node is unlocked, so they can
+/// only be archived before reading them. A monitor that cannot be archived stays where it is.
+pub(crate) fn archive_resolved_monitors(
+    database: &Database,
+    store: &MirroredStore,
+    ldk_data_dir: &Path,
+    tip_height: u32,
+    archive_depth: u32,
+) -> Result<(), APIError> {
+    let Some(max_height) = tip_height.checked_sub(archive_depth) else {
+        return Ok(());
+    };
+    for key in database.monitors_to_archive(max_height)? {
+        let archive_dir = ldk_data_dir.join(ARCHIVED_MONITORS_DIR).join(&key);
+        if let Err(e) = archive_monitor(store, &archive_dir, &key) {
+            tracing::error!("Failed archiving monitor {key}: {e}");
+            continue;
+        }
+        database.set_monitor_archived(&key, get_current_timestamp())?;
+        tracing::info!("Archived monitor {key}");
+    }
+    Ok(())
+}
+
+/// Delete the monitors archived before the given timestamp, returning how many have been deleted
+///
+/// Nothing is deleted while spendable outputs wait to be swept, as the monitors may still be
+/// needed to investigate a failed sweep.
+pub(crate) fn prune_archived_monitors(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    older_than_ts: u64,
+) -> Result<u64, APIError> {
+    let ldk_data_dir = Path::new(&static_state.ldk_data_dir);
+    let unswept = unswept_outputs(ldk_data_dir);
+    if unswept > 0 {
+        return Err(APIError::UnsweptOutputs(unswept));
+    }
+    let mut removed = 0;
+    for key in unlocked_state
+        .database
+        .monitors_archived_before(older_than_ts)?
+    {
+        let archive_dir = ldk_data_dir.join(ARCHIVED_MONITORS_DIR).join(&key);
+        if archive_dir.exists() {
+            fs::remove_dir_all(archive_dir)?;
+        }
+        unlocked_state.database.remove_monitor_archive(&key)?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Record the monitors getting fully resolved until the node gets stopped
+pub(crate) async fn watch_resolved_monitors(
+    unlocked_state: Arc<UnlockedAppState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    #[cfg(test)]
+    let interval_secs = 5;
+    #[cfg(not(test))]
+    let interval_secs = 60 * 60;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        if let Err(e) = record_resolved_monitors(&unlocked_state) {
+            tracing::error!("Failed recording resolved monitors: {e}");
+        }
+    }
+}
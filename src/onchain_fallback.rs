@@ -0,0 +1,112 @@
+use amplify::s;
+use lightning::ln::channelmanager::PaymentId;
+use lightning::sign::EntropySource;
+use lightning_invoice::Bolt11Invoice;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::APIError;
+use crate::ldk::FEE_RATE;
+use crate::routes::HTLCStatus;
+use crate::utils::{get_current_timestamp, hex_str, UnlockedAppState};
+
+/// Seconds to wait, past the time budget of the payment, for LDK to report its outcome
+const OUTCOME_GRACE_SECS: u64 = 60;
+
+/// On-chain payment of an invoice to do if paying it over Lightning fails
+#[derive(Clone, Debug)]
+pub(crate) struct OnchainFallback {
+    pub(crate) address: String,
+    pub(crate) amount_sat: u64,
+    pub(crate) fee_rate: f32,
+}
+
+/// The on-chain payment of the invoice, which needs a fallback address and no RGB amount
+pub(crate) fn onchain_fallback(
+    invoice: &Bolt11Invoice,
+    fee_rate: Option<f32>,
+) -> Result<OnchainFallback, APIError> {
+    if invoice.rgb_contract_id().is_some() || invoice.rgb_amount().is_some() {
+        return Err(APIError::OnchainFallbackUnavailable(s!(
+            "the assets of an RGB invoice cannot be paid on-chain"
+        )));
+    }
+    let Some(address) = invoice.fallback_addresses().first().map(|a| a.to_string()) else {
+        return Err(APIError::OnchainFallbackUnavailable(s!(
+            "the invoice has no fallback address"
+        )));
+    };
+    let fee_rate = fee_rate.unwrap_or(FEE_RATE);
+    if fee_rate < 1.0 {
+        return Err(APIError::InvalidFeeRate(s!("cannot be less than 1 sat/vB")));
+    }
+    let amt_msat = invoice.amount_milli_satoshis().unwrap_or_default();
+    Ok(OnchainFallback {
+        address,
+        // round up so that the payee doesn't receive less than requested
+        amount_sat: (amt_msat + 999) / 1000,
+        fee_rate,
+    })
+}
+
+/// Pay the invoice on-chain once LDK reports the Lightning payment as failed, waiting for it up
+/// to the payment time budget
+///
+/// Failures are only reported once no HTLC of the payment can succeed anymore, so the invoice
+/// doesn't get paid twice. The fallback is forgotten if the node gets stopped in the meantime.
+pub(crate) async fn pay_onchain_on_failure(
+    unlocked_state: Arc<UnlockedAppState>,
+    payment_id: PaymentId,
+    fallback: OnchainFallback,
+    timeout_sec: u64,
+) {
+    let payment_hash = hex_str(&payment_id.0);
+    let mut waited_secs = 0;
+    loop {
+        let status = unlocked_state
+            .outbound_payments()
+            .get(&payment_id)
+            .map(|p| p.status);
+        match status {
+            Some(HTLCStatus::Failed) => break,
+            Some(HTLCStatus::Pending) if waited_secs < timeout_sec + OUTCOME_GRACE_SECS => {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                waited_secs += 1;
+            }
+            Some(HTLCStatus::Pending) => {
+                tracing::warn!("Payment {payment_hash} still pending, not paying it on-chain");
+                return;
+            }
+            Some(HTLCStatus::Succeeded) | None => return,
+        }
+    }
+
+    tracing::info!(
+        "Paying {} sat to fallback address {} for failed payment {payment_hash}",
+        fallback.amount_sat,
+        fallback.address
+    );
+    let res = tokio::task::spawn_blocking(move || {
+        let spending_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        unlocked_state.database.reserve_spending(
+            &spending_id,
+            fallback.amount_sat * 1000,
+            None,
+            get_current_timestamp(),
+        )?;
+        let txid = unlocked_state
+            .rgb_send_btc(fallback.address, fallback.amount_sat, fallback.fee_rate)
+            .map_err(|e| {
+                let _ = unlocked_state.database.release_spending(&spending_id);
+                APIError::from(e)
+            })?;
+        unlocked_state.settle_outbound_payment_onchain(payment_id, txid.clone());
+        Ok::<_, APIError>(txid)
+    })
+    .await
+    .unwrap();
+    match res {
+        Ok(txid) => tracing::info!("Paid failed payment {payment_hash} on-chain in {txid}"),
+        Err(e) => tracing::error!("Failed to pay failed payment {payment_hash} on-chain: {e}"),
+    }
+}
@@ -0,0 +1,176 @@
+use bitcoin::hashes::hex::ToHex;
+use lightning::ln::channelmanager::PaymentId;
+use lightning::ln::PaymentHash;
+use lightning::rgb_utils::{
+    get_rgb_channel_info_path, get_rgb_payment_info_path, parse_rgb_channel_info,
+    parse_rgb_payment_info,
+};
+use rgbstd::contract::ContractId;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::APIError;
+use crate::routes::HTLCStatus;
+use crate::utils::UnlockedAppState;
+
+/// Amounts of a colored outbound payment in flight
+struct Reservation {
+    amt_msat: u64,
+    contract_id: ContractId,
+    asset_amount: u64,
+    /// Handed to LDK, which already takes its HTLCs off the msat channel balances
+    submitted: bool,
+}
+
+/// Amounts of the colored outbound payments in flight, not taken off the channel balances yet
+///
+/// The RGB amounts of the channels only change once a payment succeeds, so payments sent in
+/// quick succession would otherwise all be checked against the same balance.
+#[derive(Default)]
+pub(crate) struct OutboundReservations(Mutex<HashMap<PaymentId, Reservation>>);
+
+impl OutboundReservations {
+    /// Mark a payment as handed to LDK
+    pub(crate) fn submitted(&self, payment_id: &PaymentId) {
+        if let Some(reservation) = self.0.lock().unwrap().get_mut(payment_id) {
+            reservation.submitted = true;
+        }
+    }
+
+    /// Stop reserving the amounts of a payment, as it has failed or succeeded
+    pub(crate) fn release(&self, payment_id: &PaymentId) {
+        self.0.lock().unwrap().remove(payment_id);
+    }
+
+    fn restore(
+        &self,
+        payment_id: PaymentId,
+        amt_msat: u64,
+        contract_id: ContractId,
+        asset_amount: u64,
+    ) {
+        self.0.lock().unwrap().insert(
+            payment_id,
+            Reservation {
+                amt_msat,
+                contract_id,
+                asset_amount,
+                submitted: true,
+            },
+        );
+    }
+}
+
+impl UnlockedAppState {
+    /// Reserve the amounts of the colored payments still pending from before the unlock
+    pub(crate) fn restore_outbound_reservations(&self, ldk_data_dir: &str) {
+        let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
+        for (payment_id, payment) in self.outbound_payments() {
+            if payment.status != HTLCStatus::Pending {
+                continue;
+            }
+            let info_path =
+                get_rgb_payment_info_path(&PaymentHash(payment_id.0), &ldk_data_dir_path);
+            if !info_path.exists() {
+                continue;
+            }
+            let info = parse_rgb_payment_info(&info_path);
+            self.outbound_reservations.restore(
+                payment_id,
+                payment.amt_msat.unwrap_or(0),
+                info.contract_id,
+                info.amount,
+            );
+        }
+    }
+
+    /// Reserve the amounts of a colored outbound payment against what the usable channels of the
+    /// asset can send, refusing the payment if they exceed it once the payments in flight are
+    /// accounted for, unless the balances are not to be checked, returning whether the payment
+    /// has been reserved by this call
+    ///
+    /// Payments already reserved with the same ID are not reserved again.
+    pub(crate) fn reserve_outbound(
+        &self,
+        ldk_data_dir: &str,
+        payment_id: PaymentId,
+        amt_msat: u64,
+        contract_id: ContractId,
+        asset_amount: u64,
+        check_balances: bool,
+    ) -> Result<bool, APIError> {
+        // the balances are read with the reservations locked, so that a payment released in the
+        // meantime can't be accounted twice
+        let mut reservations = self.outbound_reservations.0.lock().unwrap();
+        if reservations.contains_key(&payment_id) {
+            return Ok(false);
+        }
+        if check_balances {
+            self.check_reservation(
+                ldk_data_dir,
+                &reservations,
+                amt_msat,
+                contract_id,
+                asset_amount,
+            )?;
+        }
+
+        reservations.insert(
+            payment_id,
+            Reservation {
+                amt_msat,
+                contract_id,
+                asset_amount,
+                submitted: false,
+            },
+        );
+        Ok(true)
+    }
+
+    /// Check the usable channels of the asset can send the amounts once the payments in flight
+    /// are accounted for
+    fn check_reservation(
+        &self,
+        ldk_data_dir: &str,
+        reservations: &HashMap<PaymentId, Reservation>,
+        amt_msat: u64,
+        contract_id: ContractId,
+        asset_amount: u64,
+    ) -> Result<(), APIError> {
+        let channels = self.colored_channels(ldk_data_dir, contract_id);
+        let in_flight: Vec<&Reservation> = reservations
+            .values()
+            .filter(|r| r.contract_id == contract_id)
+            .collect();
+
+        let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
+        let spendable: u64 = channels
+            .iter()
+            .map(|c| {
+                let info_path =
+                    get_rgb_channel_info_path(&c.channel_id.to_hex(), &ldk_data_dir_path, false);
+                parse_rgb_channel_info(&info_path).local_rgb_amount
+            })
+            .sum();
+        let reserved: u64 = in_flight.iter().map(|r| r.asset_amount).sum();
+        let available = spendable.saturating_sub(reserved);
+        if asset_amount > available {
+            return Err(APIError::InsufficientAssetBalance(available, reserved));
+        }
+
+        let spendable_msat: u64 = channels.iter().map(|c| c.outbound_capacity_msat).sum();
+        let reserved_msat: u64 = in_flight
+            .iter()
+            .filter(|r| !r.submitted)
+            .map(|r| r.amt_msat)
+            .sum();
+        let available_msat = spendable_msat.saturating_sub(reserved_msat);
+        if amt_msat > available_msat {
+            return Err(APIError::InsufficientOutboundLiquidity(format!(
+                "{available_msat} msat ({reserved_msat} msat reserved by pending payments)"
+            )));
+        }
+        Ok(())
+    }
+}
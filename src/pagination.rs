@@ -0,0 +1,110 @@
+use amplify::s;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::APIError;
+use crate::utils::get_current_timestamp;
+
+/// Position of an item in a list sorted by creation time, then by ID
+///
+/// Items created while paging through a list sort after the ones already returned, so they
+/// show up in the following pages without shifting the others.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Cursor {
+    created_at: u64,
+    id: String,
+}
+
+impl Cursor {
+    pub(crate) fn new(created_at: u64, id: &str) -> Self {
+        Cursor {
+            created_at,
+            id: id.to_string(),
+        }
+    }
+
+    pub(crate) fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.created_at, self.id)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = APIError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || APIError::InvalidPagination(format!("unknown cursor {s}"));
+        let (created_at, id) = s.split_once(':').ok_or_else(invalid)?;
+        if id.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Cursor {
+            created_at: created_at.parse().map_err(|_| invalid())?,
+            id: id.to_string(),
+        })
+    }
+}
+
+/// Check the paging parameters of a list, returning the cursor the page follows
+pub(crate) fn page_start(
+    after: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Option<Cursor>, APIError> {
+    if limit == Some(0) {
+        return Err(APIError::InvalidPagination(s!("limit must be positive")));
+    }
+    after.map(Cursor::from_str).transpose()
+}
+
+/// Truncate the items, sorted by their cursor and following the start of the page, to the page
+/// of at most limit items, returning the cursor of the next page if there are more items
+pub(crate) fn page_end<T>(
+    items: &mut Vec<T>,
+    cursor: impl Fn(&T) -> Cursor,
+    limit: Option<usize>,
+) -> Option<String> {
+    match limit {
+        Some(limit) if items.len() > limit => {
+            items.truncate(limit);
+            // an item created later in the same second as the last one of the page could sort
+            // before it, so the page ends before the items created this second when possible
+            let now = get_current_timestamp();
+            if let Some(end) = items
+                .iter()
+                .position(|i| cursor(i).created_at >= now)
+                .filter(|end| *end > 0)
+            {
+                items.truncate(end);
+            }
+            items.last().map(|i| cursor(i).to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Sort the items by their cursor and return the page of at most limit items following the
+/// after cursor, along with the cursor of the next page if there are more items
+pub(crate) fn paginate<T>(
+    mut items: Vec<T>,
+    cursor: impl Fn(&T) -> Cursor,
+    after: Option<&str>,
+    limit: Option<usize>,
+) -> Result<(Vec<T>, Option<String>), APIError> {
+    let after = page_start(after, limit)?;
+
+    items.sort_by_cached_key(&cursor);
+    if let Some(after) = after {
+        items.retain(|i| cursor(i) > after);
+    }
+    let next_cursor = page_end(&mut items, cursor, limit);
+    Ok((items, next_cursor))
+}
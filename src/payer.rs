@@ -0,0 +1,127 @@
+use amplify::s;
+use bitcoin::secp256k1::{PublicKey, SecretKey};
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::PaymentHash;
+use lightning::util::message_signing;
+
+use crate::error::APIError;
+use crate::utils::{hex_str, UnlockedAppState};
+
+// custom TLV types are odd so recipients that don't know them can still claim the payment
+/// Signature of the payment hash by the payer node, from which its pubkey gets recovered
+pub(crate) const PAYER_SIGNATURE_TLV_TYPE: u64 = 530_001;
+/// Free-text note of the payer
+pub(crate) const PAYER_NOTE_TLV_TYPE: u64 = 530_003;
+
+/// Largest payer note, to leave room in the onion for the rest of the payload
+pub(crate) const PAYER_NOTE_MAX_LEN: usize = 256;
+
+/// Identification an invoice requires from the node paying it
+#[derive(Clone, Debug)]
+pub(crate) struct PayerRequirements {
+    /// Only this node can pay the invoice
+    pub(crate) pubkey: Option<PublicKey>,
+    /// The payer has to attach a note
+    pub(crate) note: bool,
+}
+
+impl_writeable_tlv_based!(PayerRequirements, {
+    (0, pubkey, option),
+    (2, note, required),
+});
+
+/// Identification attached by the payer to an incoming payment
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PayerInfo {
+    pub(crate) pubkey: Option<PublicKey>,
+    pub(crate) note: Option<String>,
+}
+
+impl_writeable_tlv_based!(PayerInfo, {
+    (1, pubkey, option),
+    (3, note, option),
+});
+
+impl PayerInfo {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pubkey.is_none() && self.note.is_none()
+    }
+}
+
+impl PayerRequirements {
+    pub(crate) fn check(&self, payer: &PayerInfo) -> Result<(), String> {
+        if let Some(pubkey) = self.pubkey {
+            match payer.pubkey {
+                None => return Err(s!("payer did not identify itself")),
+                Some(payer_pubkey) if payer_pubkey != pubkey => {
+                    return Err(format!("payer {payer_pubkey} is not the expected one"))
+                }
+                Some(_) => {}
+            }
+        }
+        if self.note && payer.note.as_ref().map_or(true, |n| n.is_empty()) {
+            return Err(s!("payer did not attach a note"));
+        }
+        Ok(())
+    }
+}
+
+/// The custom TLVs identifying the payer of a payment, in the order LDK expects them
+pub(crate) fn payer_tlvs(
+    payment_hash: &PaymentHash,
+    node_secret_key: Option<&SecretKey>,
+    note: Option<&str>,
+) -> Result<Vec<(u64, Vec<u8>)>, APIError> {
+    let mut tlvs = vec![];
+    if let Some(secret_key) = node_secret_key {
+        let signature = message_signing::sign(hex_str(&payment_hash.0).as_bytes(), secret_key)
+            .map_err(|e| APIError::FailedMessageSigning(e.to_string()))?;
+        tlvs.push((PAYER_SIGNATURE_TLV_TYPE, signature.into_bytes()));
+    }
+    if let Some(note) = note {
+        tlvs.push((PAYER_NOTE_TLV_TYPE, note.as_bytes().to_vec()));
+    }
+    Ok(tlvs)
+}
+
+/// Read the payer identification from the custom TLVs of an incoming payment
+///
+/// A signature of something else than the payment hash recovers to an unrelated pubkey, so a
+/// payer can only claim to be a node it has the key of.
+pub(crate) fn read_payer_tlvs(
+    payment_hash: &PaymentHash,
+    custom_tlvs: &[(u64, Vec<u8>)],
+) -> PayerInfo {
+    let mut payer = PayerInfo::default();
+    for (tlv_type, value) in custom_tlvs {
+        match *tlv_type {
+            PAYER_SIGNATURE_TLV_TYPE => {
+                payer.pubkey = std::str::from_utf8(value).ok().and_then(|signature| {
+                    message_signing::recover_pk(hex_str(&payment_hash.0).as_bytes(), signature).ok()
+                })
+            }
+            PAYER_NOTE_TLV_TYPE => payer.note = String::from_utf8(value.clone()).ok(),
+            _ => {}
+        }
+    }
+    payer
+}
+
+impl UnlockedAppState {
+    /// Check the payer of an incoming payment against the requirements of its invoice, if any
+    pub(crate) fn check_payer(
+        &self,
+        payment_hash: &PaymentHash,
+        payer: &PayerInfo,
+    ) -> Result<(), String> {
+        let requirements = self
+            .get_inbound_payments()
+            .payments
+            .get(payment_hash)
+            .and_then(|p| p.payer_requirements.clone());
+        match requirements {
+            Some(requirements) => requirements.check(payer),
+            None => Ok(()),
+        }
+    }
+}
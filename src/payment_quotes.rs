@@ -0,0 +1,211 @@
+use bitcoin::hashes::hex::ToHex;
+use lightning::ln::channelmanager::ChannelDetails;
+use lightning::ln::ChannelId;
+use lightning::rgb_utils::{get_rgb_channel_info_path, parse_rgb_channel_info};
+use lightning::routing::router::{Route, RouteParameters, Router as _};
+use rgbstd::contract::ContractId;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::APIError;
+use crate::routes::{PaymentFeasibility, QuotePaymentResponse, HTLC_MIN_MSAT};
+use crate::utils::{check_htlc_amounts, UnlockedAppState};
+
+/// Routes looked for when quoting a payment, each one avoiding the channels of the previous ones
+const QUOTE_CANDIDATE_ROUTES: usize = 5;
+/// How long the routes found for a quote are reused for the same payment
+const QUOTE_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// What the candidate routes of a payment are quoted by
+#[derive(Clone, Copy)]
+struct RouteQuote {
+    fee_msat: u64,
+    cltv_expiry_delta: u32,
+}
+
+impl From<&Route> for RouteQuote {
+    fn from(route: &Route) -> Self {
+        RouteQuote {
+            fee_msat: route.get_total_fees(),
+            // the payment is stuck for as long as its slowest path may take
+            cltv_expiry_delta: route
+                .paths
+                .iter()
+                .map(|p| p.hops.iter().map(|h| h.cltv_expiry_delta).sum())
+                .max()
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Payment to quote with the first hops it can take, the routes depending on nothing else
+#[derive(PartialEq, Eq, Hash)]
+struct QuoteKey {
+    route_params: RouteParameters,
+    first_hops: Vec<ChannelId>,
+}
+
+/// Candidate routes recently found for quoted payments, so that quoting the same payment again
+/// doesn't go through pathfinding again
+#[derive(Default)]
+pub(crate) struct QuoteCache(Mutex<HashMap<QuoteKey, (Instant, Vec<RouteQuote>)>>);
+
+impl QuoteCache {
+    fn get(&self, key: &QuoteKey) -> Option<Vec<RouteQuote>> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|(found_at, _)| found_at.elapsed() < QUOTE_CACHE_TTL)
+            .map(|(_, quotes)| quotes.clone())
+    }
+
+    fn insert(&self, key: QuoteKey, quotes: Vec<RouteQuote>) {
+        let mut cache = self.0.lock().unwrap();
+        cache.retain(|_, (found_at, _)| found_at.elapsed() < QUOTE_CACHE_TTL);
+        cache.insert(key, (Instant::now(), quotes));
+    }
+}
+
+fn infeasible(feasibility: PaymentFeasibility) -> QuotePaymentResponse {
+    QuotePaymentResponse {
+        feasibility,
+        best_fee_msat: None,
+        p90_fee_msat: None,
+        cltv_expiry_delta: None,
+        candidate_routes: 0,
+    }
+}
+
+/// Fee the given share of the quotes cost at most, the quotes being sorted by fee
+fn fee_percentile(quotes: &[RouteQuote], percent: usize) -> u64 {
+    let index = (quotes.len() * percent).div_ceil(100).saturating_sub(1);
+    quotes[index].fee_msat
+}
+
+impl UnlockedAppState {
+    /// Quote a payment, with the colored ones carrying the given asset amount, without sending
+    /// it nor recording anything about it
+    ///
+    /// The fees and the CLTV delay come from the cheapest of up to 5 candidate routes, the
+    /// fees of the others giving a sense of how much retries could cost.
+    pub(crate) fn quote_payment(
+        &self,
+        ldk_data_dir: &str,
+        route_params: RouteParameters,
+        asset: Option<(ContractId, u64)>,
+    ) -> QuotePaymentResponse {
+        let amt_msat = route_params.final_value_msat;
+        let below_minimum = match asset {
+            Some((contract_id, asset_amount)) => matches!(
+                check_htlc_amounts(
+                    self,
+                    ldk_data_dir,
+                    contract_id,
+                    amt_msat,
+                    Some(asset_amount),
+                    true
+                ),
+                Err(APIError::AmountBelowDustLimit(_))
+            ),
+            None => amt_msat < HTLC_MIN_MSAT,
+        };
+        if below_minimum {
+            return infeasible(PaymentFeasibility::AmountBelowMinimum);
+        }
+
+        let first_hops = self.quote_first_hops(ldk_data_dir, asset);
+        let outbound_msat: u64 = first_hops.iter().map(|c| c.outbound_capacity_msat).sum();
+        if outbound_msat < amt_msat {
+            return infeasible(PaymentFeasibility::InsufficientLiquidity);
+        }
+
+        let key = QuoteKey {
+            route_params,
+            first_hops: first_hops.iter().map(|c| c.channel_id).collect(),
+        };
+        let mut quotes = match self.quote_cache.get(&key) {
+            Some(quotes) => quotes,
+            None => {
+                let quotes = self.candidate_routes(&key.route_params, &first_hops);
+                self.quote_cache.insert(key, quotes.clone());
+                quotes
+            }
+        };
+        if quotes.is_empty() {
+            return infeasible(PaymentFeasibility::NoRoute);
+        }
+        quotes.sort_by_key(|q| q.fee_msat);
+        QuotePaymentResponse {
+            feasibility: PaymentFeasibility::Ok,
+            best_fee_msat: Some(quotes[0].fee_msat),
+            p90_fee_msat: Some(fee_percentile(&quotes, 90)),
+            cltv_expiry_delta: Some(quotes[0].cltv_expiry_delta),
+            candidate_routes: quotes.len(),
+        }
+    }
+
+    /// The usable channels a payment can leave through, those carrying enough of the asset for
+    /// colored payments
+    fn quote_first_hops(
+        &self,
+        ldk_data_dir: &str,
+        asset: Option<(ContractId, u64)>,
+    ) -> Vec<ChannelDetails> {
+        let Some((contract_id, asset_amount)) = asset else {
+            return self.channel_manager.list_usable_channels();
+        };
+        let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
+        self.colored_channels(ldk_data_dir, contract_id)
+            .into_iter()
+            .filter(|chan_info| {
+                let info_file_path = get_rgb_channel_info_path(
+                    &chan_info.channel_id.to_hex(),
+                    &ldk_data_dir_path,
+                    false,
+                );
+                parse_rgb_channel_info(&info_file_path).local_rgb_amount >= asset_amount
+            })
+            .collect()
+    }
+
+    /// Look for routes of a payment, each one avoiding the channels past the first hop of the
+    /// previous ones
+    fn candidate_routes(
+        &self,
+        route_params: &RouteParameters,
+        first_hops: &[ChannelDetails],
+    ) -> Vec<RouteQuote> {
+        let payer = self.channel_manager.get_our_node_id();
+        let first_hops: Vec<&ChannelDetails> = first_hops.iter().collect();
+        let mut route_params = route_params.clone();
+        let mut quotes = vec![];
+        while quotes.len() < QUOTE_CANDIDATE_ROUTES {
+            let Ok(route) = self.router.find_route(
+                &payer,
+                &route_params,
+                Some(&first_hops),
+                self.channel_manager.compute_inflight_htlcs(),
+            ) else {
+                break;
+            };
+            quotes.push(RouteQuote::from(&route));
+            let avoided: Vec<u64> = route
+                .paths
+                .iter()
+                .flat_map(|p| p.hops.iter().skip(1).map(|h| h.short_channel_id))
+                .collect();
+            // a direct payment has no other route through the same first hops
+            if avoided.is_empty() {
+                break;
+            }
+            route_params
+                .payment_params
+                .previously_failed_channels
+                .extend(avoided);
+        }
+        quotes
+    }
+}
@@ -0,0 +1,176 @@
+use amplify::s;
+use lightning_invoice::Bolt11Invoice;
+use rgb_lib::wallet::Invoice as RgbLibInvoice;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use crate::error::APIError;
+use crate::routes::{
+    AssetIface, DecodeLNInvoiceResponse, DecodePaymentRequestResponse, DecodeRGBInvoiceResponse,
+};
+use crate::utils::hex_str;
+
+/// Scheme of the BIP21-style URIs carrying a BOLT11 invoice and an RGB invoice together
+const UNIFIED_URI_SCHEME: &str = "bitcoin:";
+const LIGHTNING_PARAM: &str = "lightning";
+const RGB_PARAM: &str = "rgb";
+
+/// Build a BIP21-style URI carrying the given invoices, as `bitcoin:?lightning=...&rgb=...`
+pub(crate) fn unified_uri(lightning: Option<&str>, rgb: Option<&str>) -> String {
+    let params: Vec<String> = [(LIGHTNING_PARAM, lightning), (RGB_PARAM, rgb)]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|v| format!("{name}={}", percent_encode(v))))
+        .collect();
+    format!("{UNIFIED_URI_SCHEME}?{}", params.join("&"))
+}
+
+/// Find out whether the payment request is a BOLT11 invoice, an RGB invoice or a unified URI,
+/// and decode it
+pub(crate) fn decode_payment_request(
+    payment_request: &str,
+) -> Result<DecodePaymentRequestResponse, APIError> {
+    let payment_request = payment_request.trim();
+    // the scheme is case-insensitive, as in BIP21
+    if let Some(scheme) = payment_request.get(..UNIFIED_URI_SCHEME.len()) {
+        if scheme.eq_ignore_ascii_case(UNIFIED_URI_SCHEME) {
+            let (lightning_invoice, rgb_invoice) =
+                parse_unified_uri(&payment_request[UNIFIED_URI_SCHEME.len()..])?;
+            return Ok(DecodePaymentRequestResponse::Unified {
+                lightning: lightning_invoice
+                    .as_deref()
+                    .map(decode_ln_invoice)
+                    .transpose()?,
+                rgb: rgb_invoice.clone().map(decode_rgb_invoice).transpose()?,
+                lightning_invoice,
+                rgb_invoice,
+            });
+        }
+    }
+    if let Ok(decoded) = decode_ln_invoice(payment_request) {
+        return Ok(DecodePaymentRequestResponse::Bolt11(decoded));
+    }
+    if let Ok(decoded) = decode_rgb_invoice(payment_request.to_string()) {
+        return Ok(DecodePaymentRequestResponse::RgbInvoice(decoded));
+    }
+    Err(APIError::InvalidPaymentRequest(s!(
+        "not a BOLT11 invoice, an RGB invoice nor a unified URI"
+    )))
+}
+
+pub(crate) fn decode_ln_invoice(invoice: &str) -> Result<DecodeLNInvoiceResponse, APIError> {
+    let invoice =
+        Bolt11Invoice::from_str(invoice).map_err(|e| APIError::InvalidInvoice(e.to_string()))?;
+
+    Ok(DecodeLNInvoiceResponse {
+        amt_msat: invoice.amount_milli_satoshis(),
+        expiry_sec: invoice.expiry_time().as_secs(),
+        timestamp: invoice
+            .timestamp()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        asset_id: invoice.rgb_contract_id().map(|c| c.to_string()),
+        asset_amount: invoice.rgb_amount(),
+        payment_hash: hex_str(&invoice.payment_hash().into_inner()),
+        payment_secret: hex_str(&invoice.payment_secret().0),
+        payee_pubkey: invoice.payee_pub_key().map(|p| p.to_string()),
+        network: invoice.network().into(),
+        min_final_cltv_expiry_delta: invoice.min_final_cltv_expiry_delta(),
+    })
+}
+
+pub(crate) fn decode_rgb_invoice(invoice: String) -> Result<DecodeRGBInvoiceResponse, APIError> {
+    let invoice_data = RgbLibInvoice::new(invoice)
+        .map_err(|e| APIError::InvalidInvoice(e.to_string()))?
+        .invoice_data();
+
+    Ok(DecodeRGBInvoiceResponse {
+        recipient_id: invoice_data.recipient_id,
+        asset_iface: invoice_data.asset_iface.map(|i| match i {
+            rgb_lib::wallet::AssetIface::RGB20 => AssetIface::RGB20,
+            rgb_lib::wallet::AssetIface::RGB21 => AssetIface::RGB21,
+            rgb_lib::wallet::AssetIface::RGB25 => AssetIface::RGB25,
+        }),
+        asset_id: invoice_data.asset_id,
+        amount: invoice_data.amount,
+        network: invoice_data.network.map(|n| n.into()),
+        expiration_timestamp: invoice_data.expiration_timestamp,
+        transport_endpoints: invoice_data.transport_endpoints,
+    })
+}
+
+/// Get the BOLT11 invoice and the RGB invoice carried by a unified URI, given without its scheme
+fn parse_unified_uri(uri: &str) -> Result<(Option<String>, Option<String>), APIError> {
+    let (address, query) = uri.split_once('?').unwrap_or((uri, ""));
+    if !address.is_empty() {
+        return Err(APIError::InvalidPaymentRequest(s!(
+            "on-chain addresses are not supported"
+        )));
+    }
+
+    let (mut lightning, mut rgb) = (None, None);
+    for param in query.split('&').filter(|p| !p.is_empty()) {
+        let (name, value) = param.split_once('=').unwrap_or((param, ""));
+        let name = name.to_ascii_lowercase();
+        let slot = match name.as_str() {
+            LIGHTNING_PARAM => &mut lightning,
+            RGB_PARAM => &mut rgb,
+            // BIP21 has the URIs with required parameters not understood refused
+            n if n.starts_with("req-") => {
+                return Err(APIError::InvalidPaymentRequest(format!(
+                    "unsupported required parameter {name}"
+                )))
+            }
+            _ => continue,
+        };
+        if slot.is_some() {
+            return Err(APIError::InvalidPaymentRequest(format!(
+                "duplicate {name} parameter"
+            )));
+        }
+        let value = percent_decode(value).ok_or_else(|| {
+            APIError::InvalidPaymentRequest(format!("badly encoded {name} parameter"))
+        })?;
+        *slot = Some(value);
+    }
+
+    if lightning.is_none() && rgb.is_none() {
+        return Err(APIError::InvalidPaymentRequest(s!(
+            "the URI carries no invoice"
+        )));
+    }
+    Ok((lightning, rgb))
+}
+
+/// Escape what would end a URI query parameter, RGB invoices carrying a query of their own
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b':' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3)?;
+            if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
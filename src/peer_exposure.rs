@@ -0,0 +1,188 @@
+use bitcoin::secp256k1::PublicKey;
+use lightning::ln::channelmanager::ChannelDetails;
+use lightning::ln::ChannelId;
+use lightning::rgb_utils::{get_rgb_payment_info_path, RgbPaymentInfo};
+use rgbstd::contract::ContractId;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::database::PeerExposureLimits;
+use crate::error::APIError;
+use crate::utils::{StaticState, UnlockedAppState};
+
+/// Prefix of the counters of the HTLCs refused because of the exposure limit of a peer, followed
+/// by its pubkey
+pub(crate) const EXPOSURE_VIOLATION_COUNTER: &str = "htlc_exposure_violation";
+
+/// Value in flight with a peer across its channels, in both directions
+#[derive(Default)]
+pub(crate) struct Exposure {
+    pub(crate) msat: u64,
+    pub(crate) asset_amounts: BTreeMap<String, u64>,
+}
+
+impl Exposure {
+    fn asset_amount(&self, asset_id: &str) -> u64 {
+        self.asset_amounts.get(asset_id).copied().unwrap_or(0)
+    }
+}
+
+impl PeerExposureLimits {
+    /// Whether an HTLC of the given amounts can be added to the exposure
+    fn allows(&self, exposure: &Exposure, amt_msat: u64, asset: Option<(&str, u64)>) -> bool {
+        let msat_allowed = self
+            .max_msat
+            .map_or(true, |max| exposure.msat.saturating_add(amt_msat) <= max);
+        let asset_allowed = asset.map_or(true, |(asset_id, amount)| {
+            self.max_asset_amounts.get(asset_id).map_or(true, |max| {
+                exposure.asset_amount(asset_id).saturating_add(amount) <= *max
+            })
+        });
+        msat_allowed && asset_allowed
+    }
+
+    /// Whether the exposure, including HTLCs already accepted, has gone over a limit
+    fn exceeded(&self, exposure: &Exposure) -> bool {
+        self.max_msat.is_some_and(|max| exposure.msat > max)
+            || self
+                .max_asset_amounts
+                .iter()
+                .any(|(asset_id, max)| exposure.asset_amount(asset_id) > *max)
+    }
+}
+
+impl StaticState {
+    fn count_exposure_violation(&self, peer_pubkey: &PublicKey) {
+        self.increment_counter(&format!("{EXPOSURE_VIOLATION_COUNTER}:{peer_pubkey}"));
+    }
+}
+
+/// Read the RGB info of a payment, None if it's not colored or its info can't be read
+fn read_rgb_payment_info(path: &Path) -> Option<RgbPaymentInfo> {
+    let serialized_info = match fs::read_to_string(path) {
+        Ok(serialized_info) => serialized_info,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            tracing::error!("Failed to read RGB payment info {}: {e}", path.display());
+            return None;
+        }
+    };
+    serde_json::from_str(&serialized_info)
+        .map_err(|e| tracing::error!("Invalid RGB payment info {}: {e}", path.display()))
+        .ok()
+}
+
+impl UnlockedAppState {
+    /// Value in flight with each peer, from the HTLCs pending on the channels
+    pub(crate) fn peer_exposures(&self, ldk_data_dir: &str) -> HashMap<PublicKey, Exposure> {
+        let ldk_data_dir_path = Path::new(ldk_data_dir);
+        let mut exposures: HashMap<PublicKey, Exposure> = HashMap::new();
+        for htlc in self.pending_htlcs() {
+            let exposure = exposures.entry(htlc.counterparty_node_id).or_default();
+            exposure.msat = exposure.msat.saturating_add(htlc.amt_msat);
+            let rgb_payment_info_path =
+                get_rgb_payment_info_path(&htlc.payment_hash, ldk_data_dir_path);
+            if let Some(rgb_payment_info) = read_rgb_payment_info(&rgb_payment_info_path) {
+                let asset_amount = exposure
+                    .asset_amounts
+                    .entry(rgb_payment_info.contract_id.to_string())
+                    .or_insert(0);
+                *asset_amount = asset_amount.saturating_add(rgb_payment_info.amount);
+            }
+        }
+        exposures
+    }
+
+    /// Short channel IDs of the channels an outbound payment has to avoid, as their peer can't
+    /// take an HTLC of its amounts without going over its exposure limits
+    ///
+    /// The payment is refused, counting a violation for each of the peers, when no usable
+    /// channel is left to send it through.
+    pub(crate) fn exposure_avoided_channels(
+        &self,
+        static_state: &StaticState,
+        amt_msat: u64,
+        asset: Option<(ContractId, u64)>,
+    ) -> Result<Vec<u64>, APIError> {
+        let limits = self.database.peer_exposure_limits()?;
+        if limits.is_empty() {
+            return Ok(vec![]);
+        }
+        let channels = match asset {
+            Some((contract_id, _)) => {
+                self.colored_channels(&static_state.ldk_data_dir, contract_id)
+            }
+            None => self.channel_manager.list_usable_channels(),
+        };
+        let exposures = self.peer_exposures(&static_state.ldk_data_dir);
+        let asset = asset.map(|(contract_id, amount)| (contract_id.to_string(), amount));
+        let no_exposure = Exposure::default();
+        let (allowed, avoided): (Vec<&ChannelDetails>, Vec<&ChannelDetails>) =
+            channels.iter().partition(|c| {
+                let Some(peer_limits) = limits.get(&c.counterparty.node_id.to_string()) else {
+                    return true;
+                };
+                let exposure = exposures
+                    .get(&c.counterparty.node_id)
+                    .unwrap_or(&no_exposure);
+                peer_limits.allows(
+                    exposure,
+                    amt_msat,
+                    asset.as_ref().map(|(a, n)| (a.as_str(), *n)),
+                )
+            });
+        if allowed.is_empty() && !avoided.is_empty() {
+            let peers: BTreeSet<PublicKey> =
+                avoided.iter().map(|c| c.counterparty.node_id).collect();
+            for peer_pubkey in &peers {
+                static_state.count_exposure_violation(peer_pubkey);
+            }
+            let peers: Vec<String> = peers.iter().map(|p| p.to_string()).collect();
+            return Err(APIError::PeerExposureLimit(format!(
+                "the payment would exceed the limits of {}",
+                peers.join(", ")
+            )));
+        }
+        Ok(avoided
+            .iter()
+            .filter_map(|c| c.get_outbound_payment_scid())
+            .collect())
+    }
+
+    /// Check a payment received through a channel doesn't put its peer over its exposure limits,
+    /// returning the reason to refuse it otherwise
+    pub(crate) fn check_claimable_exposure(
+        &self,
+        static_state: &StaticState,
+        via_channel_id: Option<ChannelId>,
+    ) -> Result<(), String> {
+        let Some(channel) = via_channel_id.and_then(|channel_id| {
+            self.channel_manager
+                .list_channels()
+                .into_iter()
+                .find(|c| c.channel_id == channel_id)
+        }) else {
+            return Ok(());
+        };
+        let peer_pubkey = channel.counterparty.node_id;
+        let limits = match self.database.peer_exposure_limits() {
+            Ok(limits) => limits,
+            Err(e) => {
+                tracing::error!("Failed to get the peer exposure limits: {e}");
+                return Ok(());
+            }
+        };
+        let Some(peer_limits) = limits.get(&peer_pubkey.to_string()) else {
+            return Ok(());
+        };
+        let exposures = self.peer_exposures(&static_state.ldk_data_dir);
+        let no_exposure = Exposure::default();
+        if !peer_limits.exceeded(exposures.get(&peer_pubkey).unwrap_or(&no_exposure)) {
+            return Ok(());
+        }
+        static_state.count_exposure_violation(&peer_pubkey);
+        Err(format!("exposure limit of peer {peer_pubkey} exceeded"))
+    }
+}
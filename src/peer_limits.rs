@@ -0,0 +1,230 @@
+use bitcoin::secp256k1::PublicKey;
+use lightning::ln::msgs::SocketAddress;
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::utils::{get_current_timestamp, UnlockedAppState};
+
+/// Caps on what a single peer can send, over which it gets disconnected and banned
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PeerRateLimits {
+    pub(crate) max_msgs_per_sec: u32,
+    pub(crate) max_gossip_bytes_per_min: u64,
+    pub(crate) ban_duration_secs: u64,
+}
+
+/// Messages received from a peer in the current second and gossip bytes in the current minute
+#[derive(Default)]
+struct PeerUsage {
+    second: u64,
+    msgs: u32,
+    minute: u64,
+    gossip_bytes: u64,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct PeerBan {
+    /// Address the peer was connected from, inbound connections from it are dropped too
+    pub(crate) address: Option<IpAddr>,
+    pub(crate) reason: String,
+    pub(crate) banned_at: u64,
+    pub(crate) expires_at: u64,
+}
+
+/// Accounting of the messages received from each peer and the bans of the misbehaving ones
+///
+/// Bans are kept in memory, so they are lifted when the node gets locked or restarted.
+pub(crate) struct PeerLimiter {
+    limits: PeerRateLimits,
+    usage: Mutex<HashMap<PublicKey, PeerUsage>>,
+    bans: Mutex<BTreeMap<PublicKey, PeerBan>>,
+    /// Peers over a cap, with the reason, waiting to be handled by enforce_peer_limits
+    offenders: Mutex<HashMap<PublicKey, String>>,
+    offender_notify: Notify,
+}
+
+/// Compare IPv4 peers the same way whether they connected over IPv4 or an IPv6 socket
+fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+pub(crate) fn socket_address_ip(address: &SocketAddress) -> Option<IpAddr> {
+    match address {
+        SocketAddress::TcpIpV4 { addr, .. } => Some(canonical_ip(IpAddr::from(*addr))),
+        SocketAddress::TcpIpV6 { addr, .. } => Some(canonical_ip(IpAddr::from(*addr))),
+        _ => None,
+    }
+}
+
+impl PeerLimiter {
+    pub(crate) fn new(limits: PeerRateLimits) -> Self {
+        PeerLimiter {
+            limits,
+            usage: Mutex::new(HashMap::new()),
+            bans: Mutex::new(BTreeMap::new()),
+            offenders: Mutex::new(HashMap::new()),
+            offender_notify: Notify::new(),
+        }
+    }
+
+    pub(crate) fn ban_duration_secs(&self) -> u64 {
+        self.limits.ban_duration_secs
+    }
+
+    /// Account a message received from a peer, returning false when the peer is over a cap and
+    /// the message should be dropped
+    pub(crate) fn record_message(&self, peer_pubkey: &PublicKey, gossip_bytes: u64) -> bool {
+        let now = get_current_timestamp();
+        let reason = {
+            let mut usage = self.usage.lock().unwrap();
+            let usage = usage.entry(*peer_pubkey).or_default();
+            if usage.second != now {
+                usage.second = now;
+                usage.msgs = 0;
+            }
+            if usage.minute != now / 60 {
+                usage.minute = now / 60;
+                usage.gossip_bytes = 0;
+            }
+            usage.msgs += 1;
+            usage.gossip_bytes += gossip_bytes;
+            if usage.msgs > self.limits.max_msgs_per_sec {
+                format!(
+                    "sent more than {} messages per second",
+                    self.limits.max_msgs_per_sec
+                )
+            } else if usage.gossip_bytes > self.limits.max_gossip_bytes_per_min {
+                format!(
+                    "sent more than {} gossip bytes per minute",
+                    self.limits.max_gossip_bytes_per_min
+                )
+            } else {
+                return true;
+            }
+        };
+        let mut offenders = self.offenders.lock().unwrap();
+        if !offenders.contains_key(peer_pubkey) {
+            offenders.insert(*peer_pubkey, reason);
+            self.offender_notify.notify_one();
+        }
+        false
+    }
+
+    pub(crate) fn peer_disconnected(&self, peer_pubkey: &PublicKey) {
+        self.usage.lock().unwrap().remove(peer_pubkey);
+    }
+
+    pub(crate) fn ban(
+        &self,
+        peer_pubkey: PublicKey,
+        address: Option<IpAddr>,
+        reason: String,
+        duration_secs: u64,
+    ) {
+        let banned_at = get_current_timestamp();
+        self.bans.lock().unwrap().insert(
+            peer_pubkey,
+            PeerBan {
+                address,
+                reason,
+                banned_at,
+                expires_at: banned_at.saturating_add(duration_secs),
+            },
+        );
+    }
+
+    /// Lift the ban of a peer, returning false if it wasn't banned
+    pub(crate) fn unban(&self, peer_pubkey: &PublicKey) -> bool {
+        self.bans.lock().unwrap().remove(peer_pubkey).is_some()
+    }
+
+    /// The active bans, forgetting the expired ones
+    pub(crate) fn bans(&self) -> BTreeMap<PublicKey, PeerBan> {
+        let now = get_current_timestamp();
+        let mut bans = self.bans.lock().unwrap();
+        bans.retain(|_, b| b.expires_at > now);
+        bans.clone()
+    }
+
+    pub(crate) fn is_banned(&self, peer_pubkey: &PublicKey) -> bool {
+        let now = get_current_timestamp();
+        self.bans
+            .lock()
+            .unwrap()
+            .get(peer_pubkey)
+            .is_some_and(|b| b.expires_at > now)
+    }
+
+    /// Whether inbound connections from an address should be dropped before the handshake
+    pub(crate) fn is_banned_address(&self, ip: IpAddr) -> bool {
+        let ip = canonical_ip(ip);
+        let now = get_current_timestamp();
+        self.bans
+            .lock()
+            .unwrap()
+            .values()
+            .any(|b| b.address == Some(ip) && b.expires_at > now)
+    }
+}
+
+impl UnlockedAppState {
+    pub(crate) fn has_channels_with(&self, peer_pubkey: &PublicKey) -> bool {
+        self.channel_manager
+            .list_channels()
+            .iter()
+            .any(|c| c.counterparty.node_id == *peer_pubkey)
+    }
+
+    /// Ban a peer, along with the address it's connected from, and disconnect it
+    pub(crate) fn ban_peer(&self, peer_pubkey: PublicKey, reason: String, duration_secs: u64) {
+        let address = self
+            .peer_manager
+            .get_peer_node_ids()
+            .into_iter()
+            .find(|(pk, _)| *pk == peer_pubkey)
+            .and_then(|(_, addr)| addr)
+            .and_then(|addr| socket_address_ip(&addr));
+        tracing::warn!("Banning peer {peer_pubkey} for {duration_secs}s: {reason}");
+        self.peer_limiter
+            .ban(peer_pubkey, address, reason, duration_secs);
+        self.peer_manager.disconnect_by_node_id(peer_pubkey);
+    }
+}
+
+/// Disconnect and ban the peers going over the rate limits until the node gets stopped
+///
+/// Peers we have channels with are never banned automatically, their messages over the caps are
+/// only dropped.
+pub(crate) async fn enforce_peer_limits(
+    unlocked_state: Arc<UnlockedAppState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    let peer_limiter = Arc::clone(&unlocked_state.peer_limiter);
+    loop {
+        // wake up regularly anyway to notice the node getting stopped
+        let _ = tokio::time::timeout(
+            Duration::from_secs(1),
+            peer_limiter.offender_notify.notified(),
+        )
+        .await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+
+        let offenders = std::mem::take(&mut *peer_limiter.offenders.lock().unwrap());
+        for (peer_pubkey, reason) in offenders {
+            if unlocked_state.has_channels_with(&peer_pubkey) {
+                tracing::warn!("Channel peer {peer_pubkey} {reason}, dropping its messages");
+                continue;
+            }
+            unlocked_state.ban_peer(peer_pubkey, reason, peer_limiter.ban_duration_secs());
+        }
+    }
+}
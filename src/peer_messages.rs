@@ -0,0 +1,254 @@
+use bitcoin::secp256k1::PublicKey;
+use lightning::ln::features::{InitFeatures, NodeFeatures};
+use lightning::ln::msgs::{DecodeError, LightningError};
+use lightning::ln::peer_handler::CustomMessageHandler;
+use lightning::ln::wire::{CustomMessageReader, Type};
+use lightning::util::ser::{Readable, Writeable, Writer};
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::channel_opens::{self, ChannelProposal};
+use crate::lsp::{self, LspChannelRequest, LspChannelResponse};
+use crate::peer_limits::PeerLimiter;
+use crate::routes::NodeEvent;
+use crate::swaps::{self, SwapRequest, SwapResponse};
+use crate::utils::{hex_str, StaticState, UnlockedAppState};
+
+// custom message types are odd so peers that don't know them ignore them
+pub(crate) const LSP_CHANNEL_REQUEST_TYPE: u16 = 53001;
+pub(crate) const LSP_CHANNEL_RESPONSE_TYPE: u16 = 53003;
+pub(crate) const SWAP_REQUEST_TYPE: u16 = 53005;
+pub(crate) const SWAP_RESPONSE_TYPE: u16 = 53007;
+pub(crate) const CHANNEL_PROPOSAL_TYPE: u16 = 53009;
+
+/// Lowest message type available to custom messages (BOLT 1)
+pub(crate) const CUSTOM_MSG_MIN_TYPE: u16 = 32768;
+/// Largest payload of a custom message (a Lightning message is at most 65535 bytes, type included)
+pub(crate) const CUSTOM_MSG_MAX_DATA_LEN: usize = 65533;
+
+/// Custom feature bit (bLIP 2 range) advertising support for RGB channels, set as optional so
+/// peers that don't know it can still connect
+pub(crate) const RGB_CHANNELS_FEATURE_BIT: usize = 257;
+
+/// Custom messages exchanged with peers over the Lightning transport
+#[derive(Debug)]
+pub(crate) enum PeerMessage {
+    ChannelProposal(ChannelProposal),
+    LspChannelRequest(LspChannelRequest),
+    LspChannelResponse(LspChannelResponse),
+    SwapRequest(SwapRequest),
+    SwapResponse(SwapResponse),
+    Custom { msg_type: u16, data: Vec<u8> },
+}
+
+/// Whether a custom message type can be used by applications
+pub(crate) fn is_app_msg_type(msg_type: u16) -> bool {
+    msg_type >= CUSTOM_MSG_MIN_TYPE
+        && ![
+            LSP_CHANNEL_REQUEST_TYPE,
+            LSP_CHANNEL_RESPONSE_TYPE,
+            SWAP_REQUEST_TYPE,
+            SWAP_RESPONSE_TYPE,
+            CHANNEL_PROPOSAL_TYPE,
+        ]
+        .contains(&msg_type)
+}
+
+impl Type for PeerMessage {
+    fn type_id(&self) -> u16 {
+        match self {
+            PeerMessage::ChannelProposal(_) => CHANNEL_PROPOSAL_TYPE,
+            PeerMessage::LspChannelRequest(_) => LSP_CHANNEL_REQUEST_TYPE,
+            PeerMessage::LspChannelResponse(_) => LSP_CHANNEL_RESPONSE_TYPE,
+            PeerMessage::SwapRequest(_) => SWAP_REQUEST_TYPE,
+            PeerMessage::SwapResponse(_) => SWAP_RESPONSE_TYPE,
+            PeerMessage::Custom { msg_type, .. } => *msg_type,
+        }
+    }
+}
+
+impl Writeable for PeerMessage {
+    fn write<W: Writer>(&self, w: &mut W) -> Result<(), io::Error> {
+        match self {
+            PeerMessage::ChannelProposal(msg) => msg.write(w),
+            PeerMessage::LspChannelRequest(msg) => msg.write(w),
+            PeerMessage::LspChannelResponse(msg) => msg.write(w),
+            PeerMessage::SwapRequest(msg) => msg.write(w),
+            PeerMessage::SwapResponse(msg) => msg.write(w),
+            PeerMessage::Custom { data, .. } => w.write_all(data),
+        }
+    }
+}
+
+/// Handler queueing outgoing custom messages and forwarding the received ones to the node
+pub(crate) struct PeerMessageHandler {
+    pending_msgs: Mutex<Vec<(PublicKey, PeerMessage)>>,
+    received_sender: UnboundedSender<(PublicKey, PeerMessage)>,
+    /// Whether each connected peer advertised RGB channels support in its init message
+    rgb_support: Mutex<HashMap<PublicKey, bool>>,
+    peer_limiter: Arc<PeerLimiter>,
+}
+
+impl PeerMessageHandler {
+    pub(crate) fn new(
+        peer_limiter: Arc<PeerLimiter>,
+    ) -> (Self, UnboundedReceiver<(PublicKey, PeerMessage)>) {
+        let (received_sender, received_receiver) = mpsc::unbounded_channel();
+        let handler = PeerMessageHandler {
+            pending_msgs: Mutex::new(vec![]),
+            received_sender,
+            rgb_support: Mutex::new(HashMap::new()),
+            peer_limiter,
+        };
+        (handler, received_receiver)
+    }
+
+    /// Queue a message for a peer, it gets sent on the next PeerManager::process_events
+    pub(crate) fn enqueue(&self, peer_pubkey: PublicKey, msg: PeerMessage) {
+        self.pending_msgs.lock().unwrap().push((peer_pubkey, msg));
+    }
+
+    /// Record the features a peer sent in its init message
+    pub(crate) fn peer_connected(&self, peer_pubkey: PublicKey, features: &InitFeatures) {
+        let flags = features.le_flags();
+        // a peer requiring the feature sets the even bit instead of the odd one
+        let supported = [RGB_CHANNELS_FEATURE_BIT - 1, RGB_CHANNELS_FEATURE_BIT]
+            .iter()
+            .any(|bit| {
+                flags
+                    .get(bit / 8)
+                    .is_some_and(|b| b & (1 << (bit % 8)) != 0)
+            });
+        self.rgb_support
+            .lock()
+            .unwrap()
+            .insert(peer_pubkey, supported);
+    }
+
+    pub(crate) fn peer_disconnected(&self, peer_pubkey: &PublicKey) {
+        self.rgb_support.lock().unwrap().remove(peer_pubkey);
+    }
+
+    /// Whether a connected peer supports RGB channels, false if the peer is not connected
+    pub(crate) fn supports_rgb_channels(&self, peer_pubkey: &PublicKey) -> bool {
+        self.rgb_support
+            .lock()
+            .unwrap()
+            .get(peer_pubkey)
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+impl CustomMessageReader for PeerMessageHandler {
+    type CustomMessage = PeerMessage;
+
+    fn read<R: io::Read>(
+        &self,
+        message_type: u16,
+        buffer: &mut R,
+    ) -> Result<Option<PeerMessage>, DecodeError> {
+        match message_type {
+            LSP_CHANNEL_REQUEST_TYPE => Ok(Some(PeerMessage::LspChannelRequest(Readable::read(
+                buffer,
+            )?))),
+            LSP_CHANNEL_RESPONSE_TYPE => Ok(Some(PeerMessage::LspChannelResponse(Readable::read(
+                buffer,
+            )?))),
+            SWAP_REQUEST_TYPE => Ok(Some(PeerMessage::SwapRequest(Readable::read(buffer)?))),
+            SWAP_RESPONSE_TYPE => Ok(Some(PeerMessage::SwapResponse(Readable::read(buffer)?))),
+            CHANNEL_PROPOSAL_TYPE => {
+                Ok(Some(PeerMessage::ChannelProposal(Readable::read(buffer)?)))
+            }
+            // even types we don't know are left to LDK, which disconnects as BOLT 1 requires
+            msg_type if msg_type % 2 == 1 && is_app_msg_type(msg_type) => {
+                let mut data = vec![];
+                buffer
+                    .read_to_end(&mut data)
+                    .map_err(|e| DecodeError::Io(e.kind()))?;
+                Ok(Some(PeerMessage::Custom { msg_type, data }))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl CustomMessageHandler for PeerMessageHandler {
+    fn handle_custom_message(
+        &self,
+        msg: PeerMessage,
+        sender_node_id: &PublicKey,
+    ) -> Result<(), LightningError> {
+        if !self.peer_limiter.record_message(sender_node_id, 0) {
+            return Ok(());
+        }
+        if self.received_sender.send((*sender_node_id, msg)).is_err() {
+            tracing::error!("peer message processing task is not running");
+        }
+        Ok(())
+    }
+
+    fn get_and_clear_pending_msg(&self) -> Vec<(PublicKey, PeerMessage)> {
+        std::mem::take(&mut *self.pending_msgs.lock().unwrap())
+    }
+
+    fn provided_node_features(&self) -> NodeFeatures {
+        NodeFeatures::empty()
+    }
+
+    fn provided_init_features(&self, _their_node_id: &PublicKey) -> InitFeatures {
+        let mut features = InitFeatures::empty();
+        features
+            .set_optional_custom_bit(RGB_CHANNELS_FEATURE_BIT)
+            .expect("custom feature bit");
+        features
+    }
+}
+
+/// Handle the custom messages received from peers until the node gets stopped
+pub(crate) async fn process_peer_messages(
+    mut receiver: UnboundedReceiver<(PublicKey, PeerMessage)>,
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    loop {
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        let (peer_pubkey, msg) =
+            match tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await {
+                Ok(Some(received)) => received,
+                Ok(None) => return,
+                Err(_) => continue,
+            };
+        match msg {
+            PeerMessage::ChannelProposal(proposal) => {
+                channel_opens::handle_channel_proposal(&unlocked_state, peer_pubkey, proposal)
+            }
+            PeerMessage::LspChannelRequest(request) => {
+                lsp::handle_channel_request(&unlocked_state, &static_state, peer_pubkey, request)
+            }
+            PeerMessage::LspChannelResponse(response) => {
+                lsp::handle_channel_response(&unlocked_state, peer_pubkey, response)
+            }
+            PeerMessage::SwapRequest(request) => {
+                swaps::handle_swap_request(&unlocked_state, &static_state, peer_pubkey, request)
+            }
+            PeerMessage::SwapResponse(response) => {
+                swaps::handle_swap_response(&unlocked_state, &static_state, peer_pubkey, response)
+            }
+            PeerMessage::Custom { msg_type, data } => {
+                static_state.emit_event(NodeEvent::CustomMessageReceived {
+                    peer_pubkey: peer_pubkey.to_string(),
+                    msg_type,
+                    data: hex_str(&data),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::APIError;
+use crate::received_transfers::{
+    is_incoming, notify_received_transfers, unsettled_incoming_transfers,
+};
+use crate::replaced_transfers::{fail_expired_receives, fail_replaced_transfers};
+use crate::utils::{StaticState, UnlockedAppState};
+
+/// File in the storage directory listing the RGB transfers still pending when the node stopped
+///
+/// The transfers and their consignments are kept in the RGB wallet data, which backups include,
+/// but nothing moves them forward until they get refreshed, so a restored node would otherwise
+/// never complete them on its own.
+pub(crate) const PENDING_TRANSFERS_FNAME: &str = "pending_transfers.json";
+
+/// RGB transfer waiting for the counterparty or for its anchoring transaction to confirm
+#[derive(Debug, Deserialize, Serialize)]
+struct PendingTransfer {
+    asset_id: String,
+    idx: i32,
+    incoming: bool,
+    recipient_id: Option<String>,
+    txid: Option<String>,
+}
+
+fn pending_transfers(unlocked_state: &UnlockedAppState) -> Result<Vec<PendingTransfer>, APIError> {
+    let mut pending = vec![];
+    let assets = unlocked_state.rgb_list_assets()?.nia.unwrap_or_default();
+    for asset in assets {
+        for transfer in unlocked_state.rgb_list_transfers(asset.asset_id.clone())? {
+            if !matches!(
+                transfer.status,
+                rgb_lib::TransferStatus::WaitingCounterparty
+                    | rgb_lib::TransferStatus::WaitingConfirmations
+            ) {
+                continue;
+            }
+            pending.push(PendingTransfer {
+                asset_id: asset.asset_id.clone(),
+                idx: transfer.idx,
+                incoming: is_incoming(&transfer),
+                recipient_id: transfer.recipient_id,
+                txid: transfer.txid,
+            });
+        }
+    }
+    Ok(pending)
+}
+
+fn recorded_pending_transfers(storage_dir_path: &Path) -> Vec<PendingTransfer> {
+    let Ok(content) = fs::read(storage_dir_path.join(PENDING_TRANSFERS_FNAME)) else {
+        return vec![];
+    };
+    serde_json::from_slice(&content).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring the unreadable list of pending RGB transfers: {e}");
+        vec![]
+    })
+}
+
+/// Refresh the RGB transfers, notifying the incoming ones that settled or failed
+pub(crate) fn refresh_pending_transfers(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+) -> Result<(), APIError> {
+    let unsettled = unsettled_incoming_transfers(unlocked_state)?;
+    fail_expired_receives(unlocked_state, static_state)?;
+    let refreshed = unlocked_state.rgb_refresh();
+    fail_replaced_transfers(unlocked_state, static_state)?;
+    notify_received_transfers(unlocked_state, static_state, &unsettled)?;
+    refreshed.map(|_| ()).map_err(APIError::from)
+}
+
+impl UnlockedAppState {
+    /// Record the RGB transfers still pending, removing the record when there are none
+    pub(crate) fn record_pending_transfers(&self, storage_dir_path: &Path) -> Result<(), APIError> {
+        let path = storage_dir_path.join(PENDING_TRANSFERS_FNAME);
+        let pending = pending_transfers(self)?;
+        if pending.is_empty() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec(&pending).expect("valid JSON"))?;
+        fs::rename(tmp_path, path)?;
+        tracing::info!("Recorded {} pending RGB transfers", pending.len());
+        Ok(())
+    }
+}
+
+/// Refresh the RGB transfers pending when the node stopped, regularly until they all complete or
+/// the node gets stopped
+///
+/// This resumes the transfers of a node restored from a backup taken while they were pending:
+/// refreshing fetches again from the proxy the consignments not received yet and accepts the
+/// received ones once their anchoring transaction confirms.
+pub(crate) async fn resume_pending_transfers(
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    let recorded = recorded_pending_transfers(Path::new(&static_state.storage_dir_path));
+    if recorded.is_empty() {
+        return;
+    }
+    tracing::info!(
+        "Resuming {} RGB transfers pending when the node stopped",
+        recorded.len()
+    );
+
+    #[cfg(test)]
+    let interval_secs = 5;
+    #[cfg(not(test))]
+    let interval_secs = 60;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+
+        let unlocked_state = Arc::clone(&unlocked_state);
+        let static_state = Arc::clone(&static_state);
+        let pending = tokio::task::spawn_blocking(move || {
+            if let Err(e) = refresh_pending_transfers(&unlocked_state, &static_state) {
+                tracing::error!("Failed to refresh the pending RGB transfers: {e}");
+            }
+            pending_transfers(&unlocked_state)
+        })
+        .await
+        .unwrap();
+        let pending: HashSet<(String, i32)> = match pending {
+            Ok(pending) => pending.into_iter().map(|t| (t.asset_id, t.idx)).collect(),
+            Err(e) => {
+                tracing::error!("Failed to list the pending RGB transfers: {e}");
+                continue;
+            }
+        };
+        let remaining = recorded
+            .iter()
+            .filter(|t| pending.contains(&(t.asset_id.clone(), t.idx)))
+            .count();
+        if remaining == 0 {
+            tracing::info!("The RGB transfers pending when the node stopped have completed");
+            return;
+        }
+        tracing::debug!(
+            "{remaining} of the RGB transfers pending when the node stopped have yet to complete"
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use lightning::util::persist::KVStore;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::Path;
+#[cfg(test)]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::error::APIError;
+use crate::utils::hex_str;
+
+const WRITE_PROBE_FNAME: &str = "write_probe";
+/// How long a successful write probe of the data directory is trusted for
+const WRITE_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Health of the node storage, degraded by the first write that fails until the node restarts
+///
+/// A failed write, typically because the disk is full, leaves the persisted state behind the
+/// in-memory one, so the node stops starting new payments and channel updates instead of risking
+/// to apply them only partially.
+#[derive(Default)]
+pub(crate) struct PersistenceHealth {
+    failure: Mutex<Option<String>>,
+    pub(crate) failed: Notify,
+    /// When the data directory last accepted the write probe
+    last_probe: Mutex<Option<Instant>>,
+    /// Make every checked write fail, to simulate a full disk
+    #[cfg(test)]
+    pub(crate) fail_writes: AtomicBool,
+    /// Make the checked writes of the artifacts under the given path fail
+    #[cfg(test)]
+    pub(crate) fail_writes_under: Mutex<Option<String>>,
+}
+
+impl PersistenceHealth {
+    /// Reason of the first write failure, if any
+    pub(crate) fn failure(&self) -> Option<String> {
+        self.failure.lock().unwrap().clone()
+    }
+
+    /// Degrade the node after a failed write, keeping the first failure as the reason
+    pub(crate) fn record_failure(&self, artifact: &str, error: impl Display) {
+        let mut failure = self.failure.lock().unwrap();
+        tracing::error!("Failed persisting {artifact}: {error}");
+        if failure.is_none() {
+            *failure = Some(format!("failed persisting {artifact}: {error}"));
+            self.failed.notify_one();
+        }
+    }
+
+    /// Run a write, degrading the node if it fails
+    pub(crate) fn write<T>(
+        &self,
+        artifact: &str,
+        write: impl FnOnce() -> io::Result<T>,
+    ) -> io::Result<T> {
+        #[cfg(test)]
+        if self.injected_failure(artifact) {
+            let error = io::Error::new(io::ErrorKind::Other, "injected write failure");
+            self.record_failure(artifact, &error);
+            return Err(error);
+        }
+        write().map_err(|e| {
+            self.record_failure(artifact, &e);
+            e
+        })
+    }
+
+    #[cfg(test)]
+    fn injected_failure(&self, artifact: &str) -> bool {
+        self.fail_writes.load(Ordering::Acquire)
+            || self
+                .fail_writes_under
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|path| artifact.starts_with(path.as_str()))
+    }
+
+    /// Refuse an operation that would change the persisted state if the node is degraded or the
+    /// data directory doesn't accept writes anymore
+    ///
+    /// The data directory is probed again only once the last successful probe is older than
+    /// [`WRITE_PROBE_INTERVAL`], a failed write degrading the node in the meantime.
+    pub(crate) fn check_writable(&self, data_dir: &Path) -> Result<(), APIError> {
+        if let Some(failure) = self.failure() {
+            return Err(APIError::PersistenceFailed(failure));
+        }
+        let mut last_probe = self.last_probe.lock().unwrap();
+        // an injected failure stands for the disk filling up since the last probe
+        #[cfg(test)]
+        let probe_due = self.injected_failure("data directory");
+        #[cfg(not(test))]
+        let probe_due = false;
+        if !probe_due && last_probe.is_some_and(|t| t.elapsed() < WRITE_PROBE_INTERVAL) {
+            return Ok(());
+        }
+        self.write("data directory", || {
+            fs::write(data_dir.join(WRITE_PROBE_FNAME), b"probe")
+        })
+        .map_err(|_| APIError::PersistenceFailed(self.failure().unwrap_or_default()))?;
+        *last_probe = Some(Instant::now());
+        Ok(())
+    }
+}
+
+/// Spendable output descriptors that failed to be persisted, kept until a retry succeeds
+///
+/// LDK hands out the descriptor of a spendable output only once and doesn't replay the event, so
+/// losing it would lose track of the funds it holds.
+#[derive(Default)]
+pub(crate) struct UnpersistedOutputs {
+    /// Encoded descriptors by key
+    outputs: Mutex<Vec<(String, Vec<u8>)>>,
+}
+
+impl UnpersistedOutputs {
+    pub(crate) fn push(&self, key: String, output: Vec<u8>) {
+        // also logged so that the output can be recovered by hand if the node stops before a
+        // retry succeeds
+        tracing::error!(
+            "Spendable output {key} not persisted, retrying: {}",
+            hex_str(&output)
+        );
+        self.outputs.lock().unwrap().push((key, output));
+    }
+
+    /// Try writing the queued descriptors again, keeping the ones that still fail
+    pub(crate) fn retry(&self, store: &impl KVStore, primary_namespace: &str) {
+        self.outputs.lock().unwrap().retain(|(key, output)| {
+            match store.write(primary_namespace, "", key, output) {
+                Ok(()) => {
+                    tracing::info!("Spendable output {key} persisted");
+                    false
+                }
+                Err(_) => true,
+            }
+        });
+    }
+}
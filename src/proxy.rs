@@ -1,10 +1,24 @@
 use reqwest::{multipart, Body, Client};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::fs::File;
 use tokio_util::codec::{BytesCodec, FramedRead};
 
 use crate::error::APIError;
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+/// Version of the proxy protocol the node speaks
+pub(crate) const PROXY_PROTOCOL_VERSION: &str = "0.2";
+
+/// Seconds after which the result of a proxy check gets refreshed
+const PROXY_CHECK_TTL_SECS: u64 = 60 * 10;
+
+/// Seconds a proxy has to answer a check
+const PROXY_CHECK_TIMEOUT_SECS: u64 = 10;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct JsonRpcError {
@@ -27,6 +41,13 @@ pub struct JsonRpcResponse<R> {
     pub(crate) error: Option<JsonRpcError>,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ServerInfo {
+    pub(crate) protocol_version: String,
+    pub(crate) version: String,
+    uptime: u64,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PostConsignmentParams {
     recipient_id: String,
@@ -81,3 +102,218 @@ pub async fn post_consignment(
         .json::<JsonRpcResponse<bool>>()
         .await?)
 }
+
+pub async fn get_info(
+    proxy_client: Client,
+    url: &str,
+) -> Result<JsonRpcResponse<ServerInfo>, APIError> {
+    let body = JsonRpcRequest::<()> {
+        method: "server.info".to_string(),
+        jsonrpc: "2.0".to_string(),
+        id: Some("1".to_string()),
+        params: None,
+    };
+    Ok(proxy_client
+        .post(url)
+        .timeout(Duration::from_secs(PROXY_CHECK_TIMEOUT_SECS))
+        .json(&body)
+        .send()
+        .await?
+        .json::<JsonRpcResponse<ServerInfo>>()
+        .await?)
+}
+
+/// Outcome of the last check of a proxy
+#[derive(Clone, Debug)]
+pub(crate) struct ProxyStatus {
+    pub(crate) reachable: bool,
+    pub(crate) version: Option<String>,
+    pub(crate) protocol_version: Option<String>,
+    checked_at: u64,
+}
+
+impl ProxyStatus {
+    pub(crate) fn compatible(&self) -> bool {
+        self.protocol_version.as_deref() == Some(PROXY_PROTOCOL_VERSION)
+    }
+
+    /// Error to give when the proxy can't be used, if it can't
+    fn check(&self, url: &str) -> Result<(), APIError> {
+        if !self.reachable {
+            return Err(APIError::InvalidTransportEndpoints(format!(
+                "proxy {url} is unreachable"
+            )));
+        }
+        if !self.compatible() {
+            return Err(APIError::InvalidTransportEndpoints(format!(
+                "proxy {url} speaks protocol version {}, {PROXY_PROTOCOL_VERSION} is required",
+                self.protocol_version.as_deref().unwrap_or("unknown")
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Results of the proxy checks, by proxy URL
+#[derive(Default)]
+pub(crate) struct ProxyChecks(Mutex<HashMap<String, ProxyStatus>>);
+
+impl ProxyChecks {
+    pub(crate) fn get(&self, url: &str) -> Option<ProxyStatus> {
+        self.0.lock().unwrap().get(url).cloned()
+    }
+}
+
+/// Count the outcome of an operation with a proxy, available from /counters
+fn count_proxy_operation(static_state: &StaticState, operation: &str, url: &str, success: bool) {
+    let outcome = if success { "succeeded" } else { "failed" };
+    static_state.increment_counter(&format!("proxy_{operation}_{outcome}:{url}"));
+}
+
+/// Ask a proxy for its version, unless it has been checked recently and refresh is false
+pub(crate) async fn check_proxy(
+    static_state: &StaticState,
+    url: &str,
+    refresh: bool,
+) -> ProxyStatus {
+    if !refresh {
+        if let Some(status) = static_state.proxy_checks.get(url) {
+            if get_current_timestamp().saturating_sub(status.checked_at) < PROXY_CHECK_TTL_SECS {
+                return status;
+            }
+        }
+    }
+
+    let info = get_info((*static_state.proxy_client).clone(), url)
+        .await
+        .ok()
+        .and_then(|r| r.result);
+    let status = ProxyStatus {
+        reachable: info.is_some(),
+        version: info.as_ref().map(|i| i.version.clone()),
+        protocol_version: info.map(|i| i.protocol_version),
+        checked_at: get_current_timestamp(),
+    };
+    count_proxy_operation(static_state, "check", url, status.reachable);
+    if !status.reachable {
+        tracing::warn!("Proxy {url} is unreachable");
+    } else if !status.compatible() {
+        tracing::warn!(
+            "Proxy {url} speaks protocol version {:?}, {PROXY_PROTOCOL_VERSION} is required",
+            status.protocol_version
+        );
+    }
+    static_state
+        .proxy_checks
+        .0
+        .lock()
+        .unwrap()
+        .insert(url.to_string(), status.clone());
+    status
+}
+
+/// URL to reach the proxy of a JSON-RPC transport endpoint
+pub(crate) fn proxy_url(transport_endpoint: &str) -> Option<String> {
+    if let Some(rest) = transport_endpoint.strip_prefix("rpcs://") {
+        Some(format!("https://{rest}"))
+    } else {
+        transport_endpoint
+            .strip_prefix("rpc://")
+            .map(|rest| format!("http://{rest}"))
+    }
+}
+
+/// First default transport endpoint whose proxy passed its last check, or the first one when
+/// none did
+pub(crate) fn preferred_proxy_endpoint(static_state: &StaticState) -> String {
+    static_state
+        .proxy_endpoints
+        .iter()
+        .find(|e| {
+            proxy_url(e)
+                .and_then(|url| static_state.proxy_checks.get(&url))
+                .is_some_and(|s| s.reachable && s.compatible())
+        })
+        .unwrap_or(&static_state.proxy_endpoints[0])
+        .clone()
+}
+
+/// Make sure the proxies of the given transport endpoints can be reached and speak a compatible
+/// protocol, relying on recent checks when available
+pub(crate) async fn check_transport_endpoints(
+    static_state: &StaticState,
+    transport_endpoints: &[String],
+) -> Result<(), APIError> {
+    for endpoint in transport_endpoints {
+        let Some(url) = proxy_url(endpoint) else {
+            // let the wallet reject unsupported endpoints
+            continue;
+        };
+        check_proxy(static_state, &url, false).await.check(&url)?;
+    }
+    Ok(())
+}
+
+/// Check the proxies of the default transport endpoints
+pub(crate) async fn check_default_proxies(static_state: &StaticState) {
+    for endpoint in &static_state.proxy_endpoints {
+        if let Some(url) = proxy_url(endpoint) {
+            check_proxy(static_state, &url, true).await;
+        }
+    }
+}
+
+/// Post a consignment to the first of the given transport endpoints whose proxy accepts it,
+/// recording and returning the endpoint that got it
+pub(crate) async fn post_consignment_with_failover(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    transport_endpoints: &[String],
+    recipient_id: String,
+    consignment_path: PathBuf,
+    txid: String,
+    vout: Option<u32>,
+) -> Option<String> {
+    for endpoint in transport_endpoints {
+        let Some(url) = proxy_url(endpoint) else {
+            continue;
+        };
+        let res = post_consignment(
+            (*static_state.proxy_client).clone(),
+            &url,
+            recipient_id.clone(),
+            consignment_path.clone(),
+            txid.clone(),
+            vout,
+        )
+        .await;
+        let success = res.is_ok_and(|r| r.result.is_some());
+        count_proxy_operation(static_state, "upload", &url, success);
+        if success {
+            if let Err(e) = unlocked_state
+                .database
+                .set_consignment_endpoint(&recipient_id, endpoint)
+            {
+                tracing::error!("Cannot record the endpoint of consignment {recipient_id}: {e}");
+            }
+            return Some(endpoint.clone());
+        }
+        tracing::warn!("Cannot post consignment for {recipient_id} to proxy {url}");
+    }
+    None
+}
+
+/// Check the default proxies periodically until the node gets stopped
+pub(crate) async fn watch_proxy(static_state: Arc<StaticState>, stop_processing: Arc<AtomicBool>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(PROXY_CHECK_TTL_SECS));
+    // the first check happens at unlock
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        check_default_proxies(&static_state).await;
+    }
+}
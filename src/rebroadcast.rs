@@ -0,0 +1,116 @@
+use bdk::electrum_client::{Client, ElectrumApi};
+use bitcoin::consensus::encode::{deserialize, serialize};
+use bitcoin::{Transaction, Txid};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::APIError;
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+/// Watch the unconfirmed transactions spending wallet funds until they confirm, broadcasting again
+/// the ones the indexer doesn't know anymore
+///
+/// A transaction gets watched once the indexer returns it, as its raw form is needed to broadcast
+/// it again, and stops being watched when the wallet sees it confirmed or forgets it after a
+/// replacement.
+fn rebroadcast_unconfirmed_txs(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+) -> Result<(), APIError> {
+    let client = match Client::new(&static_state.electrum_url) {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Cannot check for dropped transactions: {e}");
+            return Ok(());
+        }
+    };
+    let unconfirmed: HashSet<String> = unlocked_state
+        .rgb_list_transactions()?
+        .into_iter()
+        .filter(|t| t.confirmation_time.is_none() && t.sent > 0)
+        .map(|t| t.txid)
+        .collect();
+
+    let watched = unlocked_state.database.watched_txs()?;
+    let resolved: Vec<&String> = watched
+        .keys()
+        .filter(|t| !unconfirmed.contains(*t))
+        .collect();
+    for txid in &resolved {
+        unlocked_state.database.remove_watched_tx(txid)?;
+    }
+    if !resolved.is_empty() {
+        unlocked_state.alerts.trigger();
+    }
+
+    for txid_str in &unconfirmed {
+        let Ok(txid) = Txid::from_str(txid_str) else {
+            continue;
+        };
+        let watched_tx = watched.get(txid_str);
+        match (client.transaction_get(&txid), watched_tx) {
+            (Ok(tx), None) => {
+                unlocked_state.database.add_watched_tx(
+                    txid_str,
+                    &serialize(&tx),
+                    get_current_timestamp(),
+                )?;
+            }
+            (Ok(_), Some(_)) => {}
+            (Err(_), Some(watched_tx)) => {
+                let tx: Transaction = match deserialize(&watched_tx.raw_tx) {
+                    Ok(tx) => tx,
+                    Err(e) => {
+                        tracing::error!("Cannot decode watched transaction {txid}: {e}");
+                        continue;
+                    }
+                };
+                match client.transaction_broadcast(&tx) {
+                    Ok(_) => {
+                        tracing::warn!("Rebroadcast {txid}, dropped from the mempool");
+                        unlocked_state.database.add_rebroadcast(txid_str)?;
+                    }
+                    Err(e) => tracing::error!("Failed to rebroadcast {txid}: {e}"),
+                }
+            }
+            (Err(_), None) => tracing::debug!("Cannot watch {txid}, unknown to the indexer"),
+        }
+    }
+    Ok(())
+}
+
+/// Check the unconfirmed wallet transactions regularly until the node gets stopped
+pub(crate) async fn watch_unconfirmed_txs(
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    #[cfg(test)]
+    let interval_secs = 5;
+    #[cfg(not(test))]
+    let interval_secs = 60 * 5;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+
+        let unlocked_state = Arc::clone(&unlocked_state);
+        let static_state = Arc::clone(&static_state);
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = rebroadcast_unconfirmed_txs(&unlocked_state, &static_state) {
+                tracing::error!("Failed to check the unconfirmed transactions: {e}");
+            }
+            if let Err(e) = unlocked_state.release_utxo_reservations() {
+                tracing::error!("Failed to release the UTXO reservations: {e}");
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+use rgb_lib::wallet::Transfer;
+use std::collections::HashSet;
+
+use crate::error::APIError;
+use crate::routes::NodeEvent;
+use crate::utils::{StaticState, UnlockedAppState};
+
+pub(crate) fn is_incoming(transfer: &Transfer) -> bool {
+    matches!(
+        transfer.kind,
+        rgb_lib::TransferKind::ReceiveBlind | rgb_lib::TransferKind::ReceiveWitness
+    )
+}
+
+/// Whether the given recipient ID comes from an RGB invoice of the node
+pub(crate) fn is_own_recipient_id(
+    unlocked_state: &UnlockedAppState,
+    recipient_id: &str,
+) -> Result<bool, APIError> {
+    let is_invoice =
+        |t: &Transfer| is_incoming(t) && t.recipient_id.as_deref() == Some(recipient_id);
+    if unlocked_state
+        .rgb_list_transfers_without_asset()?
+        .iter()
+        .any(is_invoice)
+    {
+        return Ok(true);
+    }
+    for asset in unlocked_state.rgb_list_assets()?.nia.unwrap_or_default() {
+        if unlocked_state
+            .rgb_list_transfers(asset.asset_id)?
+            .iter()
+            .any(is_invoice)
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Incoming transfers not settled yet, by asset ID and transfer index
+pub(crate) fn unsettled_incoming_transfers(
+    unlocked_state: &UnlockedAppState,
+) -> Result<HashSet<(String, i32)>, APIError> {
+    let mut unsettled = HashSet::new();
+    let assets = unlocked_state.rgb_list_assets()?.nia.unwrap_or_default();
+    for asset in assets {
+        for transfer in unlocked_state.rgb_list_transfers(asset.asset_id.clone())? {
+            if is_incoming(&transfer)
+                && !matches!(transfer.status, rgb_lib::TransferStatus::Settled)
+            {
+                unsettled.insert((asset.asset_id.clone(), transfer.idx));
+            }
+        }
+    }
+    Ok(unsettled)
+}
+
+/// Notify the incoming transfers that have settled among the given unsettled ones
+pub(crate) fn notify_received_transfers(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    unsettled: &HashSet<(String, i32)>,
+) -> Result<(), APIError> {
+    if unsettled.is_empty() {
+        return Ok(());
+    }
+    let assets = unlocked_state.rgb_list_assets()?.nia.unwrap_or_default();
+    for asset in assets {
+        for transfer in unlocked_state.rgb_list_transfers(asset.asset_id.clone())? {
+            if !is_incoming(&transfer)
+                || !matches!(transfer.status, rgb_lib::TransferStatus::Settled)
+                || !unsettled.contains(&(asset.asset_id.clone(), transfer.idx))
+            {
+                continue;
+            }
+            tracing::info!(
+                "Received {} of asset {} with transfer {}",
+                transfer.amount,
+                asset.asset_id,
+                transfer.idx
+            );
+            static_state.emit_event(NodeEvent::AssetReceived {
+                asset_id: asset.asset_id.clone(),
+                amount: transfer.amount,
+                recipient_id: transfer.recipient_id,
+                txid: transfer.txid,
+            });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use bitcoin::hashes::hex::ToHex;
+use lightning::ln::ChannelId;
+use std::collections::HashMap;
+
+use crate::error::APIError;
+use crate::utils::UnlockedAppState;
+
+impl UnlockedAppState {
+    /// Refuse the operations creating channels or moving funds through them in recovery mode
+    pub(crate) fn check_not_recovering(&self) -> Result<(), APIError> {
+        if self.recovery_mode {
+            return Err(APIError::RecoveryMode);
+        }
+        Ok(())
+    }
+
+    /// Force-close every channel, broadcasting the latest commitment transaction held by its
+    /// monitor, so that all the funds end up swept to the on-chain wallet
+    pub(crate) fn force_close_all_channels(&self) {
+        let channels = self.channel_manager.list_channels();
+        tracing::warn!("Recovery mode: force-closing {} channels", channels.len());
+        self.channel_manager
+            .force_close_all_channels_broadcasting_latest_txn();
+    }
+
+    /// Amount still to be claimed on-chain for each closed channel, by channel ID
+    pub(crate) fn unclaimed_balances(&self) -> HashMap<String, u64> {
+        let open_channel_ids: Vec<ChannelId> = self
+            .channel_manager
+            .list_channels()
+            .iter()
+            .map(|c| c.channel_id)
+            .collect();
+        let mut balances = HashMap::new();
+        for funding_txo in self.chain_monitor.list_monitors() {
+            let channel_id = ChannelId::v1_from_funding_outpoint(funding_txo);
+            if open_channel_ids.contains(&channel_id) {
+                continue;
+            }
+            let Ok(monitor) = self.chain_monitor.get_monitor(funding_txo) else {
+                continue;
+            };
+            let unclaimed_sat = monitor
+                .get_claimable_balances()
+                .iter()
+                .map(|b| b.claimable_amount_satoshis())
+                .sum();
+            balances.insert(channel_id.to_hex(), unclaimed_sat);
+        }
+        balances
+    }
+}
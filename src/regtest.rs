@@ -0,0 +1,165 @@
+use bitcoin::hash_types::{BlockHash, Txid};
+use bitcoin::hashes::hex::FromHex;
+use lightning_block_sync::http::{HttpEndpoint, JsonResponse};
+use lightning_block_sync::rpc::RpcClient;
+use serde_json::{json, Value};
+use std::convert::TryInto;
+use std::io;
+
+/// Name of the bitcoind wallet mining blocks and funding addresses
+const MINER_WALLET: &str = "miner";
+
+/// Raw result of an RPC call, for the calls not having a dedicated response type
+struct RpcValue(Value);
+
+impl TryInto<RpcValue> for JsonResponse {
+    type Error = io::Error;
+    fn try_into(self) -> io::Result<RpcValue> {
+        Ok(RpcValue(self.0))
+    }
+}
+
+fn invalid_response(method: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("unexpected {method} response"),
+    )
+}
+
+/// Regtest bitcoind driven through its RPC interface, to mine blocks and fund addresses with the
+/// coins of its miner wallet
+///
+/// Only the RPC credentials are needed, so any reachable regtest bitcoind can back the tests and
+/// the test-only endpoints.
+pub(crate) struct RegtestBackend {
+    node_client: RpcClient,
+    wallet_client: RpcClient,
+}
+
+impl RegtestBackend {
+    pub(crate) fn new(
+        host: &str,
+        port: u16,
+        rpc_user: &str,
+        rpc_password: &str,
+    ) -> io::Result<Self> {
+        let rpc_credentials = base64::encode(format!("{rpc_user}:{rpc_password}"));
+        let endpoint = HttpEndpoint::for_host(host.to_string()).with_port(port);
+        let node_client = RpcClient::new(&rpc_credentials, endpoint.clone())?;
+        let wallet_client = RpcClient::new(
+            &rpc_credentials,
+            endpoint.with_path(format!("/wallet/{MINER_WALLET}")),
+        )?;
+        Ok(Self {
+            node_client,
+            wallet_client,
+        })
+    }
+
+    /// Load the miner wallet, creating it if bitcoind doesn't have it yet
+    pub(crate) async fn ensure_wallet(&self) -> io::Result<()> {
+        let RpcValue(loaded) = self.node_client.call_method("listwallets", &[]).await?;
+        let loaded = loaded
+            .as_array()
+            .ok_or_else(|| invalid_response("listwallets"))?;
+        if loaded.iter().any(|w| w.as_str() == Some(MINER_WALLET)) {
+            return Ok(());
+        }
+        let load: io::Result<RpcValue> = self
+            .node_client
+            .call_method("loadwallet", &[json!(MINER_WALLET)])
+            .await;
+        if load.is_err() {
+            let _: RpcValue = self
+                .node_client
+                .call_method("createwallet", &[json!(MINER_WALLET)])
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Mine the given number of blocks, sending the rewards to the miner wallet
+    pub(crate) async fn mine(&self, num_blocks: u16) -> io::Result<Vec<BlockHash>> {
+        let RpcValue(address) = self.wallet_client.call_method("getnewaddress", &[]).await?;
+        let RpcValue(hashes) = self
+            .wallet_client
+            .call_method("generatetoaddress", &[json!(num_blocks), address])
+            .await?;
+        hashes
+            .as_array()
+            .ok_or_else(|| invalid_response("generatetoaddress"))?
+            .iter()
+            .map(|h| {
+                h.as_str()
+                    .and_then(|h| BlockHash::from_hex(h).ok())
+                    .ok_or_else(|| invalid_response("generatetoaddress"))
+            })
+            .collect()
+    }
+
+    /// Send the given amount from the miner wallet to an address
+    pub(crate) async fn fund_address(&self, address: &str, amount_sat: u64) -> io::Result<Txid> {
+        let amount_btc = amount_sat as f64 / 100_000_000.0;
+        let RpcValue(txid) = self
+            .wallet_client
+            .call_method("sendtoaddress", &[json!(address), json!(amount_btc)])
+            .await?;
+        txid.as_str()
+            .and_then(|t| Txid::from_hex(t).ok())
+            .ok_or_else(|| invalid_response("sendtoaddress"))
+    }
+
+    /// Height of the chain tip
+    #[cfg(test)]
+    pub(crate) async fn block_count(&self) -> io::Result<u32> {
+        let RpcValue(count) = self.node_client.call_method("getblockcount", &[]).await?;
+        count
+            .as_u64()
+            .map(|c| c as u32)
+            .ok_or_else(|| invalid_response("getblockcount"))
+    }
+
+    /// Spendable balance of the miner wallet
+    #[cfg(test)]
+    pub(crate) async fn wallet_balance_sat(&self) -> io::Result<u64> {
+        let RpcValue(balance) = self.wallet_client.call_method("getbalance", &[]).await?;
+        balance
+            .as_f64()
+            .map(|b| (b * 100_000_000.0).round() as u64)
+            .ok_or_else(|| invalid_response("getbalance"))
+    }
+
+    /// Whether an output is unspent, including the outputs of transactions still in the mempool
+    #[cfg(test)]
+    pub(crate) async fn txout_exists(&self, txid: &str, vout: u32) -> io::Result<bool> {
+        let RpcValue(txout) = self
+            .node_client
+            .call_method("gettxout", &[json!(txid), json!(vout)])
+            .await?;
+        Ok(!txout.is_null())
+    }
+
+    /// Mine a block confirming only the given mempool transactions
+    #[cfg(test)]
+    pub(crate) async fn generate_block(&self, txids: &[String]) -> io::Result<BlockHash> {
+        let RpcValue(address) = self.wallet_client.call_method("getnewaddress", &[]).await?;
+        let RpcValue(block) = self
+            .wallet_client
+            .call_method("generateblock", &[address, json!(txids)])
+            .await?;
+        block["hash"]
+            .as_str()
+            .and_then(|h| BlockHash::from_hex(h).ok())
+            .ok_or_else(|| invalid_response("generateblock"))
+    }
+
+    /// Mark a block as invalid, disconnecting it and its descendants from the chain
+    #[cfg(test)]
+    pub(crate) async fn invalidate_block(&self, block_hash: &BlockHash) -> io::Result<()> {
+        let _: RpcValue = self
+            .node_client
+            .call_method("invalidateblock", &[json!(block_hash.to_string())])
+            .await?;
+        Ok(())
+    }
+}
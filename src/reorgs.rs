@@ -0,0 +1,200 @@
+use bitcoin::hashes::hex::ToHex;
+use bitcoin::{BlockHash, BlockHeader, Txid};
+use lightning::chain::transaction::TransactionData;
+use lightning::chain::Listen;
+use lightning_block_sync::{BlockData, BlockSource};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::database::Database;
+use crate::error::APIError;
+use crate::routes::{NodeEvent, ReorgedTransfer};
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+/// Blocks disconnected by a reorg, with the transactions confirmed again since then
+#[derive(Default)]
+pub(crate) struct Reorg {
+    disconnected: Vec<(BlockHash, u32)>,
+    reconnected_txids: HashSet<Txid>,
+}
+
+/// Chain listener keeping track of the reorgs and of the anchoring transactions they
+/// unconfirmed
+pub(crate) struct ChainReorgs {
+    database: Arc<Database>,
+    /// Reorg being applied by the ongoing poll of the chain tip
+    ongoing: Mutex<Option<Reorg>>,
+    /// Reorgs applied and waiting to be looked at
+    completed: Mutex<Vec<Reorg>>,
+    /// Anchoring transactions of settled transfers disconnected by a reorg and not confirmed
+    /// again yet
+    unconfirmed: Mutex<HashSet<String>>,
+}
+
+impl ChainReorgs {
+    pub(crate) fn new(database: Arc<Database>) -> Result<Self, APIError> {
+        let unconfirmed = database.reorged_txs()?;
+        Ok(ChainReorgs {
+            database,
+            ongoing: Mutex::new(None),
+            completed: Mutex::new(vec![]),
+            unconfirmed: Mutex::new(unconfirmed),
+        })
+    }
+
+    /// Mark the end of a poll of the chain tip, completing the reorg it applied if any
+    pub(crate) fn end_poll(&self) {
+        if let Some(reorg) = self.ongoing.lock().unwrap().take() {
+            self.completed.lock().unwrap().push(reorg);
+        }
+    }
+
+    fn take_completed(&self) -> Vec<Reorg> {
+        std::mem::take(&mut *self.completed.lock().unwrap())
+    }
+
+    /// Anchoring transactions disconnected by a reorg that aren't confirmed anymore
+    pub(crate) fn unconfirmed_txids(&self) -> HashSet<String> {
+        self.unconfirmed.lock().unwrap().clone()
+    }
+
+    fn mark_unconfirmed(&self, txids: &HashSet<String>) -> Result<(), APIError> {
+        let mut unconfirmed = self.unconfirmed.lock().unwrap();
+        for txid in txids {
+            self.database
+                .add_reorged_tx(txid, get_current_timestamp())?;
+            unconfirmed.insert(txid.clone());
+        }
+        Ok(())
+    }
+}
+
+impl Listen for ChainReorgs {
+    fn filtered_block_connected(&self, _header: &BlockHeader, txdata: &TransactionData, _: u32) {
+        let txids: Vec<Txid> = txdata.iter().map(|(_, tx)| tx.txid()).collect();
+        if let Some(reorg) = self.ongoing.lock().unwrap().as_mut() {
+            reorg.reconnected_txids.extend(&txids);
+        }
+        for reorg in self.completed.lock().unwrap().iter_mut() {
+            reorg.reconnected_txids.extend(&txids);
+        }
+
+        let mut unconfirmed = self.unconfirmed.lock().unwrap();
+        for txid in txids.iter().map(|t| t.to_string()) {
+            if !unconfirmed.remove(&txid) {
+                continue;
+            }
+            tracing::info!("Transaction {txid} disconnected by a reorg got confirmed again");
+            if let Err(e) = self.database.remove_reorged_tx(&txid) {
+                tracing::error!("Failed to forget reorged transaction {txid}: {e}");
+            }
+        }
+    }
+
+    fn block_disconnected(&self, header: &BlockHeader, height: u32) {
+        self.ongoing
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Reorg::default)
+            .disconnected
+            .push((header.block_hash(), height));
+    }
+}
+
+impl UnlockedAppState {
+    /// Look at the transactions a reorg disconnected without confirming them again, reporting
+    /// the channels and the settled transfers they affect
+    ///
+    /// Channels follow the confirmations of their funding transaction on their own, transfers
+    /// are reported as waiting for confirmations until their anchoring transaction confirms
+    /// again.
+    async fn handle_reorg(&self, static_state: &StaticState, reorg: Reorg) -> Result<(), APIError> {
+        let depth = reorg.disconnected.len() as u32;
+        let fork_height = reorg
+            .disconnected
+            .iter()
+            .map(|(_, h)| *h)
+            .min()
+            .unwrap_or(1)
+            - 1;
+        tracing::warn!("Chain reorg of {depth} blocks after height {fork_height}");
+
+        let mut reorged = HashSet::new();
+        for (block_hash, height) in &reorg.disconnected {
+            match static_state.bitcoind_client.get_block(block_hash).await {
+                // the coinbase can't be confirmed again
+                Ok(BlockData::FullBlock(block)) => reorged.extend(
+                    block
+                        .txdata
+                        .iter()
+                        .skip(1)
+                        .map(|tx| tx.txid())
+                        .filter(|t| !reorg.reconnected_txids.contains(t)),
+                ),
+                _ => tracing::error!("Cannot get block {block_hash} disconnected at {height}"),
+            }
+        }
+
+        let channel_ids = self
+            .channel_manager
+            .list_channels()
+            .into_iter()
+            .filter(|c| c.funding_txo.is_some_and(|o| reorged.contains(&o.txid)))
+            .map(|c| c.channel_id.to_hex())
+            .collect();
+
+        let reorged: HashSet<String> = reorged.iter().map(|t| t.to_string()).collect();
+        let mut transfers = vec![];
+        let assets = self.rgb_list_assets()?.nia.unwrap_or_default();
+        for asset in assets {
+            for transfer in self.rgb_list_transfers(asset.asset_id.clone())? {
+                let Some(txid) = transfer.txid else {
+                    continue;
+                };
+                if reorged.contains(&txid)
+                    && matches!(transfer.status, rgb_lib::TransferStatus::Settled)
+                {
+                    transfers.push(ReorgedTransfer {
+                        asset_id: asset.asset_id.clone(),
+                        idx: transfer.idx,
+                        txid,
+                    });
+                }
+            }
+        }
+        let unconfirmed = transfers.iter().map(|t| t.txid.clone()).collect();
+        self.chain_reorgs.mark_unconfirmed(&unconfirmed)?;
+
+        static_state.emit_event(NodeEvent::ChainReorg {
+            depth,
+            fork_height,
+            channel_ids,
+            transfers,
+        });
+        Ok(())
+    }
+}
+
+/// Report the reorgs seen by the chain listener until the node gets stopped
+pub(crate) async fn watch_chain_reorgs(
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+
+        for reorg in unlocked_state.chain_reorgs.take_completed() {
+            if let Err(e) = unlocked_state.handle_reorg(&static_state, reorg).await {
+                tracing::error!("Failed to handle chain reorg: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use amplify::s;
+use bdk::electrum_client::{Client, ElectrumApi};
+use bitcoin::{OutPoint, Txid};
+use std::str::FromStr;
+
+use crate::error::APIError;
+use crate::received_transfers::is_incoming;
+use crate::routes::NodeEvent;
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+/// Reason recorded for the incoming transfers failed because their invoice expired
+const EXPIRED_INVOICE_REASON: &str = "invoice expired before the transfer completed";
+
+/// Find the transaction, other than the given one, spending one of the given outpoints
+fn find_spender(client: &Client, txid: &Txid, inputs: &[String]) -> Option<Txid> {
+    for input in inputs {
+        let Ok(outpoint) = OutPoint::from_str(input) else {
+            continue;
+        };
+        let Ok(prev_tx) = client.transaction_get(&outpoint.txid) else {
+            continue;
+        };
+        let Some(prev_output) = prev_tx.output.get(outpoint.vout as usize) else {
+            continue;
+        };
+        let Ok(history) = client.script_get_history(&prev_output.script_pubkey) else {
+            continue;
+        };
+        for entry in history.iter().filter(|h| h.tx_hash != *txid) {
+            let Ok(tx) = client.transaction_get(&entry.tx_hash) else {
+                continue;
+            };
+            if tx.input.iter().any(|i| i.previous_output == outpoint) {
+                return Some(entry.tx_hash);
+            }
+        }
+    }
+    None
+}
+
+/// Fail the incoming transfers whose anchoring transaction has been replaced by another one
+/// spending the same inputs, notifying each failure
+///
+/// The inputs of the anchoring transactions get recorded while the transactions can still be
+/// found, so that a conflicting transaction can be looked for once they disappear. The wallet
+/// only allows failing transfers still waiting for the counterparty, so the failures are kept by
+/// the node, which reports the transfers as failed and leaves them out of the future balance.
+pub(crate) fn fail_replaced_transfers(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+) -> Result<(), APIError> {
+    let client = match Client::new(&static_state.electrum_url) {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Cannot check for replaced anchoring transactions: {e}");
+            return Ok(());
+        }
+    };
+    let failed = unlocked_state.database.failed_incoming_transfers()?;
+
+    let assets = unlocked_state.rgb_list_assets()?.nia.unwrap_or_default();
+    for asset in assets {
+        for transfer in unlocked_state.rgb_list_transfers(asset.asset_id.clone())? {
+            if !is_incoming(&transfer) {
+                continue;
+            }
+            let (Some(txid_str), Some(recipient_id)) = (&transfer.txid, &transfer.recipient_id)
+            else {
+                continue;
+            };
+            if !matches!(
+                transfer.status,
+                rgb_lib::TransferStatus::WaitingConfirmations
+            ) {
+                unlocked_state.database.remove_incoming_anchor(txid_str)?;
+                continue;
+            }
+            if failed.contains_key(recipient_id) {
+                continue;
+            }
+            let Ok(txid) = Txid::from_str(txid_str) else {
+                continue;
+            };
+
+            if let Ok(tx) = client.transaction_get(&txid) {
+                let inputs: Vec<String> = tx
+                    .input
+                    .iter()
+                    .map(|i| i.previous_output.to_string())
+                    .collect();
+                unlocked_state
+                    .database
+                    .add_incoming_anchor(txid_str, &inputs)?;
+                continue;
+            }
+            let Some(inputs) = unlocked_state.database.incoming_anchor_inputs(txid_str)? else {
+                continue;
+            };
+            let Some(replaced_by) = find_spender(&client, &txid, &inputs) else {
+                continue;
+            };
+
+            let reason = format!("anchoring transaction {txid} replaced by {replaced_by}");
+            unlocked_state.database.add_failed_incoming_transfer(
+                recipient_id,
+                &reason,
+                get_current_timestamp(),
+            )?;
+            unlocked_state.database.remove_incoming_anchor(txid_str)?;
+            tracing::warn!("Failed incoming transfer to {recipient_id}: {reason}");
+            static_state.emit_event(NodeEvent::IncomingTransferFailed {
+                asset_id: Some(asset.asset_id.clone()),
+                recipient_id: recipient_id.clone(),
+                txid: Some(txid_str.clone()),
+                reason,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Recipient IDs of the incoming transfers still waiting for the counterparty after their invoice
+/// expired, with their asset ID if the invoice has one
+fn expired_receives(
+    unlocked_state: &UnlockedAppState,
+    now: i64,
+) -> Result<Vec<(Option<String>, String)>, APIError> {
+    let mut transfers: Vec<(Option<String>, rgb_lib::wallet::Transfer)> = unlocked_state
+        .rgb_list_transfers_without_asset()?
+        .into_iter()
+        .map(|t| (None, t))
+        .collect();
+    for asset in unlocked_state.rgb_list_assets()?.nia.unwrap_or_default() {
+        for transfer in unlocked_state.rgb_list_transfers(asset.asset_id.clone())? {
+            transfers.push((Some(asset.asset_id.clone()), transfer));
+        }
+    }
+    Ok(transfers
+        .into_iter()
+        .filter(|(_, t)| {
+            is_incoming(t)
+                && matches!(t.status, rgb_lib::TransferStatus::WaitingCounterparty)
+                && t.expiration.is_some_and(|e| e < now)
+        })
+        .filter_map(|(asset_id, t)| t.recipient_id.map(|r| (asset_id, r)))
+        .collect())
+}
+
+/// Fail the incoming transfers whose invoice expired before they completed, notifying each
+/// failure
+///
+/// Failing them in the wallet releases their receiving UTXO for reuse. This also covers the
+/// transfers whose anchoring transaction got replaced before the node could accept them, which
+/// would otherwise keep waiting for the counterparty.
+pub(crate) fn fail_expired_receives(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+) -> Result<(), APIError> {
+    let now = get_current_timestamp() as i64;
+    let expired = expired_receives(unlocked_state, now)?;
+    if expired.is_empty() {
+        return Ok(());
+    }
+    // without a batch transfer the wallet fails all the expired transfers waiting for the
+    // counterparty
+    unlocked_state.rgb_fail_transfers(None, false)?;
+    unlocked_state.release_utxo_reservations()?;
+
+    let still_waiting = expired_receives(unlocked_state, now)?;
+    for (asset_id, recipient_id) in expired {
+        if still_waiting.iter().any(|(_, r)| *r == recipient_id) {
+            continue;
+        }
+        unlocked_state.database.add_failed_incoming_transfer(
+            &recipient_id,
+            EXPIRED_INVOICE_REASON,
+            get_current_timestamp(),
+        )?;
+        tracing::warn!("Failed incoming transfer to {recipient_id}: {EXPIRED_INVOICE_REASON}");
+        static_state.emit_event(NodeEvent::IncomingTransferFailed {
+            asset_id,
+            recipient_id,
+            txid: None,
+            reason: s!(EXPIRED_INVOICE_REASON),
+        });
+    }
+    Ok(())
+}
+
+/// Amount of the incoming transfers of an asset the node has failed but the wallet still counts
+/// in the future balance
+pub(crate) fn failed_incoming_amount(
+    unlocked_state: &UnlockedAppState,
+    asset_id: &str,
+) -> Result<u64, APIError> {
+    let failed = unlocked_state.database.failed_incoming_transfers()?;
+    if failed.is_empty() {
+        return Ok(0);
+    }
+    Ok(unlocked_state
+        .rgb_list_transfers(asset_id.to_string())?
+        .into_iter()
+        .filter(|t| {
+            matches!(t.status, rgb_lib::TransferStatus::WaitingConfirmations)
+                && t.recipient_id
+                    .as_ref()
+                    .is_some_and(|r| failed.contains_key(r))
+        })
+        .map(|t| t.amount)
+        .sum())
+}
@@ -1,5 +1,6 @@
 use amplify::ByteArray;
 use bdk::bitcoin::psbt::PartiallySignedTransaction;
+use bdk::LocalUtxo;
 use bitcoin::blockdata::constants::WITNESS_SCALE_FACTOR;
 use bitcoin::hashes::hex::{FromHex, ToHex};
 use bitcoin::hashes::Hash;
@@ -25,7 +26,7 @@ use rgb_lib::{
     AssetSchema, BitcoinNetwork, Error as RgbLibError, SignOptions, Wallet as RgbLibWallet,
 };
 use rgbstd::containers::{Bindle, BuilderSeal, Transfer as RgbTransfer};
-use rgbstd::contract::{ContractId, GraphSeal};
+use rgbstd::contract::{ContractId, GraphSeal, SecretSeal};
 use rgbstd::interface::{TransitionBuilder, TypedState};
 use rgbstd::persistence::Inventory;
 use rgbstd::Txid as RgbTxid;
@@ -38,13 +39,24 @@ use std::sync::{Arc, Mutex};
 
 use crate::utils::UnlockedAppState;
 
+/// Assign the amount to the given blinded UTXO, or to the output next to the OP_RETURN one when
+/// there's none, returning the vout of the latter
 pub(crate) fn update_transition_beneficiary(
     psbt: &PartiallySignedTransaction,
     beneficiaries: &mut Vec<BuilderSeal<BlindSeal<TxPtr>>>,
     mut asset_transition_builder: TransitionBuilder,
     assignment_id: u16,
     amt_rgb: u64,
-) -> (u32, TransitionBuilder) {
+    secret_seal: Option<SecretSeal>,
+) -> (Option<u32>, TransitionBuilder) {
+    if let Some(secret_seal) = secret_seal {
+        let seal = BuilderSeal::Concealed(secret_seal);
+        beneficiaries.push(seal);
+        asset_transition_builder = asset_transition_builder
+            .add_raw_state(assignment_id, seal, TypedState::Amount(amt_rgb))
+            .expect("ok");
+        return (None, asset_transition_builder);
+    }
     let mut seal_vout = 0;
     if let Some((index, _)) = psbt
         .clone()
@@ -65,7 +77,7 @@ pub(crate) fn update_transition_beneficiary(
     asset_transition_builder = asset_transition_builder
         .add_raw_state(assignment_id, seal, TypedState::Amount(amt_rgb))
         .expect("ok");
-    (seal_vout, asset_transition_builder)
+    (Some(seal_vout), asset_transition_builder)
 }
 
 // TODO: remove after updating to bitcoin 0.30
@@ -77,26 +89,27 @@ impl UnlockedAppState {
     pub(crate) fn rgb_blind_receive(
         &self,
         asset_id: Option<String>,
+        duration_seconds: Option<u32>,
         transport_endpoints: Vec<String>,
         min_confirmations: u8,
     ) -> Result<ReceiveData, RgbLibError> {
         self.get_rgb_wallet().blind_receive(
             asset_id,
             None,
-            None,
+            duration_seconds,
             transport_endpoints,
             min_confirmations,
         )
     }
 
-    pub(crate) fn rgb_create_utxos(
+    pub(crate) fn rgb_create_utxos_begin(
         &self,
         up_to: bool,
         num: u8,
         size: u32,
         fee_rate: f32,
-    ) -> Result<u8, RgbLibError> {
-        self.get_rgb_wallet().create_utxos(
+    ) -> Result<String, RgbLibError> {
+        self.get_rgb_wallet().create_utxos_begin(
             self.rgb_online.clone(),
             up_to,
             Some(num),
@@ -105,6 +118,23 @@ impl UnlockedAppState {
         )
     }
 
+    pub(crate) fn rgb_create_utxos_end(&self, signed_psbt: String) -> Result<u8, RgbLibError> {
+        self.get_rgb_wallet()
+            .create_utxos_end(self.rgb_online.clone(), signed_psbt)
+    }
+
+    pub(crate) fn rgb_fail_transfers(
+        &self,
+        batch_transfer_idx: Option<i32>,
+        no_asset_only: bool,
+    ) -> Result<bool, RgbLibError> {
+        self.get_rgb_wallet().fail_transfers(
+            self.rgb_online.clone(),
+            batch_transfer_idx,
+            no_asset_only,
+        )
+    }
+
     pub(crate) fn rgb_get_address(&self) -> Result<String, RgbLibError> {
         self.get_rgb_wallet().get_address()
     }
@@ -142,6 +172,29 @@ impl UnlockedAppState {
         )
     }
 
+    pub(crate) fn rgb_get_asset_nia(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<Option<AssetNIA>, RgbLibError> {
+        let asset_id = contract_id.to_string();
+        Ok(self
+            .rgb_list_assets()?
+            .nia
+            .unwrap()
+            .into_iter()
+            .find(|a| a.asset_id == asset_id))
+    }
+
+    pub(crate) fn rgb_get_asset_precisions(&self) -> Result<HashMap<String, u8>, RgbLibError> {
+        Ok(self
+            .rgb_list_assets()?
+            .nia
+            .unwrap()
+            .into_iter()
+            .map(|a| (a.asset_id, a.precision))
+            .collect())
+    }
+
     pub(crate) fn rgb_list_assets(&self) -> Result<Assets, RgbLibError> {
         self.get_rgb_wallet().list_assets(vec![])
     }
@@ -158,11 +211,24 @@ impl UnlockedAppState {
         self.get_rgb_wallet().list_transfers(Some(asset_id))
     }
 
+    /// Transfers of the invoices not bound to an asset that haven't received one yet
+    pub(crate) fn rgb_list_transfers_without_asset(&self) -> Result<Vec<Transfer>, RgbLibError> {
+        self.get_rgb_wallet().list_transfers(None)
+    }
+
     pub(crate) fn rgb_list_unspents(&self) -> Result<Vec<Unspent>, RgbLibError> {
         self.get_rgb_wallet()
             .list_unspents(Some(self.rgb_online.clone()), false)
     }
 
+    pub(crate) fn rgb_list_unspents_vanilla(
+        &self,
+        min_confirmations: u8,
+    ) -> Result<Vec<LocalUtxo>, RgbLibError> {
+        self.get_rgb_wallet()
+            .list_unspents_vanilla(self.rgb_online.clone(), min_confirmations)
+    }
+
     pub(crate) fn rgb_refresh(&self) -> Result<bool, RgbLibError> {
         self.get_rgb_wallet()
             .refresh(self.rgb_online.clone(), None, vec![])
@@ -1,12 +1,19 @@
 use amplify::{map, s};
-use axum::{extract::State, Json};
+use axum::{
+    body::{Bytes, StreamBody},
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
 use axum_extra::extract::WithRejection;
 use bitcoin::hashes::hex::ToHex;
 use bitcoin::hashes::sha256::Hash as Sha256;
 use bitcoin::hashes::Hash;
 use bitcoin::secp256k1::PublicKey;
 use bitcoin::Network;
-use lightning::impl_writeable_tlv_based_enum;
+use lightning::blinded_path::BlindedPath;
+use lightning::ln::features::ChannelTypeFeatures;
 use lightning::ln::ChannelId;
 use lightning::onion_message::{Destination, OnionMessagePath};
 use lightning::rgb_utils::{
@@ -15,42 +22,93 @@ use lightning::rgb_utils::{
 };
 use lightning::sign::EntropySource;
 use lightning::util::config::ChannelConfig;
+use lightning::util::ser::Readable;
+use lightning::{impl_writeable_tlv_based, impl_writeable_tlv_based_enum};
 use lightning::{
     ln::{
-        channelmanager::{PaymentId, RecipientOnionFields, Retry},
+        channelmanager::{
+            PaymentId, RecentPaymentDetails, RecipientOnionFields, Retry, RetryableSendFailure,
+        },
         PaymentHash, PaymentPreimage,
     },
-    rgb_utils::{write_rgb_channel_info, write_rgb_payment_info_file, RgbInfo},
+    rgb_utils::{write_rgb_payment_info_file, RgbInfo},
     routing::{
         gossip::NodeId,
         router::{PaymentParameters, RouteParameters},
     },
-    util::config::{ChannelHandshakeConfig, ChannelHandshakeLimits, UserConfig},
 };
-use lightning_invoice::payment::pay_invoice;
+use lightning_invoice::payment::{pay_invoice, PaymentError};
 use lightning_invoice::Bolt11Invoice;
 use lightning_invoice::{utils::create_invoice_from_channelmanager, Currency};
-use rgb_lib::wallet::{Invoice as RgbLibInvoice, Recipient, RecipientData};
+use rgb_lib::wallet::{Recipient, RecipientData};
 use rgb_lib::{generate_keys, BitcoinNetwork as RgbLibNetwork, Error as RgbLibError};
 use rgbstd::contract::{ContractId, SecretSeal};
-use rgbwallet::RgbTransport;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{Cursor, Read},
+    marker::PhantomData,
     path::{Path, PathBuf},
     str::FromStr,
-    sync::Arc,
-    time::{Duration, SystemTime},
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
 };
 use tokio::sync::MutexGuard as TokioMutexGuard;
+use tokio_util::codec::{BytesCodec, FramedRead};
 
+use crate::alerts::AlertThresholds;
+use crate::amounts::{AssetUnits, MilliSats, Sats};
+use crate::announcements::{AnnouncementStatus, ChannelAnnouncementStatus};
 use crate::backup::{do_backup, restore_backup};
-use crate::ldk::{start_ldk, stop_ldk, LdkBackgroundServices, MIN_CHANNEL_CONFIRMATIONS};
+use crate::channel_fees::choose_fee_rate;
+use crate::channel_handshake::ChannelHandshakeDefaults;
+use crate::channel_opens::{accept_inbound_channel, reject_inbound_channel};
+use crate::channel_suggestions::DEFAULT_SUGGESTIONS_DAYS;
+use crate::close_negotiation::bound_close_fee_rate;
+use crate::consolidation::consolidate_asset;
+use crate::data_dir::active_data_dir;
+use crate::data_version::{check_data_version, write_data_version};
+use crate::database::{
+    ChannelFeeKind, ChannelFeeRate, PeerExposureLimits, WalletIntent, WalletIntentKind,
+    SPENDING_WINDOW_SECS,
+};
+use crate::dry_run::{max_push_msat, simulate_channel_close, simulate_rgb_send};
+use crate::integrity::check_data_dir;
+use crate::jobs::{new_job, run_job};
+use crate::latency::{RouteLatency, LATENCY_WINDOW};
+use crate::ldk::{
+    announce_node, htlc_min_receivable_msat, htlc_min_sendable_msat, open_channel_config,
+    start_ldk, stop_ldk, LdkBackgroundServices, HTLC_EXPIRY_WARNING_BLOCKS, MAX_CHANNEL_MIN_DEPTH,
+    MIN_CHANNEL_CONFIRMATIONS, MIN_FINAL_CLTV_EXPIRY_DELTA,
+};
+use crate::lsp::LspChannelRequest;
+use crate::monitor_archive::{archived_monitors, prune_archived_monitors, unswept_outputs};
+use crate::onchain_fallback::{onchain_fallback, pay_onchain_on_failure};
+use crate::pagination::{page_end, page_start, paginate, Cursor};
+use crate::payer::{payer_tlvs, PayerRequirements, PAYER_NOTE_MAX_LEN};
+use crate::payment_request::{self, unified_uri};
+use crate::peer_exposure::EXPOSURE_VIOLATION_COUNTER;
+use crate::peer_messages::{
+    is_app_msg_type, PeerMessage, CUSTOM_MSG_MAX_DATA_LEN, CUSTOM_MSG_MIN_TYPE,
+};
+use crate::pending_transfers::refresh_pending_transfers;
+use crate::proxy::{check_transport_endpoints, preferred_proxy_endpoint, proxy_url};
+use crate::received_transfers::is_own_recipient_id;
+use crate::replaced_transfers::failed_incoming_amount;
 use crate::rgb::get_bitcoin_network;
+use crate::stats::payment_stats;
+use crate::swaps::start_swap;
 use crate::utils::{
-    check_already_initialized, check_password_strength, check_password_validity,
-    encrypt_and_save_mnemonic, get_mnemonic_path, hex_str, hex_str_to_compressed_pubkey,
-    hex_str_to_vec, UnlockedAppState, UserOnionMessageContents,
+    check_already_initialized, check_asset_amount, check_close_asset_destination,
+    check_htlc_amounts, check_label, check_password_strength, check_password_validity,
+    check_payment_policy, encrypt_and_save_mnemonic, get_current_timestamp, get_mnemonic_path,
+    hex_str, hex_str_to_channel_id, hex_str_to_compressed_pubkey, hex_str_to_payment_hash,
+    hex_str_to_vec, invoice_payment_params, node_alias_bytes, UnlockedAppState,
+    UserOnionMessageContents,
 };
+use crate::utxo_creation::{created_utxos, plan_utxo_creation};
+use crate::wallet_intents::{psbt_inputs, psbt_txid};
+use crate::webhooks::{check_webhook_url, MIN_WEBHOOK_SECRET_LEN, WEBHOOK_EVENT_TYPES};
 use crate::{
     disk,
     error::APIError,
@@ -60,19 +118,93 @@ use crate::{
 
 const UTXO_NUM: u8 = 4;
 
-const OPENCHANNEL_MIN_SAT: u64 = 5506;
-const OPENCHANNEL_MAX_SAT: u64 = 16777215;
-const OPENCHANNEL_MIN_RGB_AMT: u64 = 1;
+const ASSET_TICKER_MAX_LEN: usize = 8;
+const ASSET_NAME_MAX_LEN: usize = 40;
+const ASSET_PRECISION_MAX: u8 = 18;
+
+const CHANNEL_NOTE_MAX_LEN: usize = 1024;
 
-const DUST_LIMIT_MSAT: u64 = 546000;
+pub(crate) const OPENCHANNEL_MIN_SAT: u64 = 5506;
+pub(crate) const OPENCHANNEL_MAX_SAT: u64 = 16777215;
+pub(crate) const OPENCHANNEL_MIN_RGB_AMT: u64 = 1;
 
-const HTLC_MIN_MSAT: u64 = 3000000;
+pub(crate) const HTLC_MIN_MSAT: u64 = 3000000;
+
+pub(crate) const HTLC_MIN_ASSET_AMOUNT: u64 = 1;
 
 const INVOICE_MIN_MSAT: u64 = HTLC_MIN_MSAT;
 
+const PAYMENT_RETRY_TIMEOUT_SEC: u64 = 10;
+
+// Request and response fields are snake_case, enum variants and event types are snake_case
+// strings, hashes, IDs and keys are hex strings. Up to 0.1.0 the enum variants were serialized
+// as PascalCase: the aliases of the enums already there keep accepting that spelling and will be
+// dropped in the next release.
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AbandonPaymentRequest {
+    pub(crate) payment_hash: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AcceptChannelRequest {
+    pub(crate) temporary_channel_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AddWebhookRequest {
+    pub(crate) url: String,
+    /// Types of the events to deliver, as reported by /events
+    pub(crate) events: Vec<String>,
+    /// Key of the HMAC-SHA256 signing the payloads
+    pub(crate) secret: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AddWebhookResponse {
+    pub(crate) id: u64,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct AddressRequest {
+    /// Return the last handed out address instead of a fresh one
+    #[serde(default)]
+    pub(crate) reuse_last: bool,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct AddressResponse {
     pub(crate) address: String,
+    pub(crate) derivation_index: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Alert {
+    pub(crate) kind: AlertKind,
+    pub(crate) channel_id: Option<String>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) message: String,
+    pub(crate) raised_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AlertKind {
+    LowAnchorReserve,
+    LowFeeBumpReserve,
+    ChannelAssetOutboundExhausted,
+    ChannelAssetInboundExhausted,
+    PersistenceFailure,
+    UnconfirmedTransaction,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AlertsResponse {
+    pub(crate) anchor_reserve_per_channel_sat: Option<u64>,
+    pub(crate) min_fee_bump_utxos: Option<u64>,
+    pub(crate) min_channel_asset_amount: Option<u64>,
+    pub(crate) max_unconfirmed_tx_age_secs: Option<u64>,
+    pub(crate) alerts: Vec<Alert>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -83,6 +215,7 @@ pub(crate) struct Asset {
     pub(crate) precision: u8,
     pub(crate) issued_supply: u64,
     pub(crate) timestamp: i64,
+    pub(crate) registry_status: RegistryStatus,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -97,26 +230,102 @@ pub(crate) struct AssetBalanceResponse {
     pub(crate) spendable: u64,
     pub(crate) offchain_outbound: u64,
     pub(crate) offchain_inbound: u64,
+    pub(crate) precision: u8,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssetBalances {
+    pub(crate) asset_id: String,
+    pub(crate) ticker: String,
+    pub(crate) precision: u8,
+    pub(crate) onchain_settled: u64,
+    pub(crate) onchain_future: u64,
+    pub(crate) onchain_spendable: u64,
+    pub(crate) offchain_outbound: u64,
+    pub(crate) offchain_inbound: u64,
+    pub(crate) total: u64,
+}
+
+/// Value of an asset in flight with a peer, with the limit set on it
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct AssetExposure {
+    pub(crate) asset_id: String,
+    pub(crate) amount: u64,
+    pub(crate) max_amount: Option<u64>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) enum AssetIface {
+    #[serde(rename = "rgb20", alias = "RGB20")]
     RGB20,
+    #[serde(rename = "rgb21", alias = "RGB21")]
     RGB21,
+    #[serde(rename = "rgb25", alias = "RGB25")]
     RGB25,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssetSpendingLimit {
+    pub(crate) asset_id: String,
+    pub(crate) max_amount: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssetSpendingLimitStatus {
+    pub(crate) asset_id: String,
+    pub(crate) limit: u64,
+    pub(crate) used: u64,
+    pub(crate) remaining: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssetVolume {
+    pub(crate) asset_id: String,
+    pub(crate) count: u64,
+    pub(crate) amount: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BalancesResponse {
+    pub(crate) btc: BtcBalances,
+    pub(crate) assets: Vec<AssetBalances>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct BackupRequest {
     pub(crate) backup_path: String,
     pub(crate) password: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Ban {
+    pub(crate) peer_pubkey: String,
+    pub(crate) address: Option<String>,
+    pub(crate) reason: String,
+    pub(crate) banned_at: u64,
+    pub(crate) expires_at: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BanPeerRequest {
+    pub(crate) peer_pubkey: String,
+    /// The configured ban duration when not given
+    pub(crate) duration_secs: Option<u64>,
+    /// Ban the peer even if the node has channels with it
+    #[serde(default)]
+    pub(crate) force: bool,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub(crate) enum BitcoinNetwork {
+    #[serde(alias = "Mainnet")]
     Mainnet,
+    #[serde(alias = "Testnet")]
     Testnet,
+    #[serde(alias = "Signet")]
     Signet,
+    #[serde(alias = "Regtest")]
     Regtest,
 }
 
@@ -161,6 +370,26 @@ pub(crate) struct BtcBalanceResponse {
     pub(crate) colored: BtcBalance,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BtcBalances {
+    pub(crate) onchain_vanilla: BtcBalance,
+    pub(crate) onchain_colored: BtcBalance,
+    pub(crate) offchain_local_msat: u64,
+    pub(crate) offchain_outbound_msat: u64,
+    pub(crate) offchain_inbound_msat: u64,
+    pub(crate) total_msat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CancelInvoiceRequest {
+    pub(crate) payment_hash: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CancelJobRequest {
+    pub(crate) job_id: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct ChangePasswordRequest {
     pub(crate) old_password: String,
@@ -181,9 +410,119 @@ pub(crate) struct Channel {
     pub(crate) inbound_balance_msat: Option<u64>,
     pub(crate) is_usable: bool,
     pub(crate) public: bool,
+    pub(crate) confirmations_required: Option<u32>,
+    pub(crate) confirmations: Option<u32>,
     pub(crate) asset_id: Option<String>,
     pub(crate) asset_local_amount: Option<u64>,
     pub(crate) asset_remote_amount: Option<u64>,
+    pub(crate) asset_precision: Option<u8>,
+    pub(crate) funding_fee_sat: u64,
+    pub(crate) close_fee_sat: u64,
+    pub(crate) force_close_fee_sat: u64,
+    pub(crate) note: Option<String>,
+    pub(crate) htlc_min_sendable_msat: u64,
+    pub(crate) htlc_min_receivable_msat: u64,
+    pub(crate) htlc_min_asset_amount: Option<u64>,
+    pub(crate) consignment_endpoint: Option<String>,
+    pub(crate) peer_offline_since: Option<u64>,
+    /// Only known once the channel type has been negotiated
+    pub(crate) commitment_type: Option<CommitmentType>,
+    pub(crate) created_at: u64,
+    /// Broadcasts of the channel announcements, only for public channels with a short channel ID
+    pub(crate) announcement_status: Option<ChannelAnnouncementStatus>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelDebugHtlc {
+    pub(crate) inbound: bool,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) payment_hash: String,
+    /// Status of the payment the HTLC belongs to, None for the HTLCs of forwards
+    pub(crate) payment_status: Option<HTLCStatus>,
+    pub(crate) expiry_height: u32,
+    pub(crate) blocks_to_force_close: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelDebugParams {
+    pub(crate) channel_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelDebugPeer {
+    pub(crate) connected: bool,
+    pub(crate) connected_since: Option<u64>,
+    pub(crate) last_seen: Option<u64>,
+    pub(crate) disconnect_count: u32,
+    /// Outcome of the channel_reestablish exchange following the last connection
+    pub(crate) reestablish: ReestablishState,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelDebugResponse {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) funding_txid: Option<String>,
+    pub(crate) short_channel_id: Option<u64>,
+    pub(crate) outbound: bool,
+    pub(crate) ready: bool,
+    pub(crate) is_usable: bool,
+    pub(crate) commitment_type: Option<CommitmentType>,
+    /// ID of the last update of the channel monitor, growing with each new commitment
+    pub(crate) monitor_update_id: Option<u64>,
+    /// Monitor updates not persisted yet, holding the channel until they are
+    pub(crate) pending_monitor_updates: usize,
+    pub(crate) feerate_sat_per_1000_weight: Option<u32>,
+    pub(crate) capacity_sat: u64,
+    pub(crate) local_balance_msat: u64,
+    pub(crate) outbound_capacity_msat: u64,
+    pub(crate) inbound_capacity_msat: u64,
+    /// Reserve the counterparty requires us to keep, unknown until it has accepted the channel
+    pub(crate) our_reserve_sat: Option<u64>,
+    /// Reserve we require the counterparty to keep
+    pub(crate) their_reserve_sat: u64,
+    /// Blocks our funds wait for after a force close of ours
+    pub(crate) to_self_delay: Option<u16>,
+    pub(crate) htlcs: Vec<ChannelDebugHtlc>,
+    pub(crate) rgb: Option<ChannelDebugRgb>,
+    pub(crate) peer: ChannelDebugPeer,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelDebugRgb {
+    pub(crate) asset_id: String,
+    pub(crate) local_amount: u64,
+    pub(crate) remote_amount: u64,
+    /// Amounts of the commitment being negotiated, if any
+    pub(crate) pending_local_amount: Option<u64>,
+    pub(crate) pending_remote_amount: Option<u64>,
+    /// Amount locked in the pending HTLCs of the channel
+    pub(crate) htlc_amount: u64,
+}
+
+/// How the node answered a channel opened by a peer
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ChannelRequestDecision {
+    Accepted,
+    Rejected,
+    /// Held until the user accepts or rejects it
+    AwaitingDecision,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelSuggestionsParams {
+    /// Period to analyse, in days
+    pub(crate) days: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelSuggestionsResponse {
+    pub(crate) period_start: u64,
+    pub(crate) close: Vec<CloseSuggestion>,
+    pub(crate) rebalance: Vec<RebalanceSuggestion>,
+    pub(crate) open: Vec<OpenSuggestion>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -191,6 +530,81 @@ pub(crate) struct CloseChannelRequest {
     pub(crate) channel_id: String,
     pub(crate) peer_pubkey: String,
     pub(crate) force: bool,
+    #[serde(default)]
+    pub(crate) dry_run: bool,
+    #[serde(default)]
+    pub(crate) asset_destination: Option<String>,
+    /// Fee rate to target for a cooperative close, exclusive with `target_conf_blocks`
+    #[serde(default)]
+    pub(crate) fee_rate_sat_vb: Option<f32>,
+    #[serde(default)]
+    pub(crate) target_conf_blocks: Option<u16>,
+    /// Lowest closing fee rate accepted, targeted when no fee rate is requested
+    #[serde(default)]
+    pub(crate) min_fee_rate_sat_vb: Option<f32>,
+    /// Highest closing fee rate accepted, only capping the fee of channels the node has funded
+    #[serde(default)]
+    pub(crate) max_fee_rate_sat_vb: Option<f32>,
+    /// Seconds after which a cooperative close still being negotiated gets forced
+    #[serde(default)]
+    pub(crate) force_after_sec: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CloseChannelResponse {
+    pub(crate) dry_run: Option<DryRunResult>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ClosedChannel {
+    pub(crate) channel_id: String,
+    pub(crate) funding_txid: Option<String>,
+    pub(crate) peer_pubkey: Option<String>,
+    pub(crate) capacity_sat: Option<u64>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_local_amount: Option<u64>,
+    pub(crate) asset_remote_amount: Option<u64>,
+    pub(crate) reason: String,
+    pub(crate) closed_at: u64,
+    pub(crate) funding_fee_sat: u64,
+    pub(crate) close_fee_sat: u64,
+    pub(crate) force_close_fee_sat: u64,
+    /// Fee rate paid by a cooperative close the node initiated with a fee rate or confirmation
+    /// target, the requested one until the closing transaction gets broadcast
+    pub(crate) close_fee_rate_sat_vb: Option<f32>,
+    pub(crate) close_target_conf_blocks: Option<u16>,
+    /// Fee rate of the cooperative closing transaction agreed with the peer, whoever pays for it
+    pub(crate) negotiated_close_fee_rate_sat_vb: Option<f32>,
+    pub(crate) note: Option<String>,
+    pub(crate) unclaimed_sat: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct CloseSuggestion {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) capacity_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) last_activity: Option<u64>,
+}
+
+/// Kind of commitment transactions of a channel
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CommitmentType {
+    Anchors,
+    /// Legacy commitments without anchor outputs, paying the counterparty to a static key
+    StaticRemoteKey,
+}
+
+impl From<&ChannelTypeFeatures> for CommitmentType {
+    fn from(channel_type: &ChannelTypeFeatures) -> Self {
+        if channel_type.supports_anchors_zero_fee_htlc_tx() {
+            CommitmentType::Anchors
+        } else {
+            CommitmentType::StaticRemoteKey
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize)]
@@ -198,12 +612,42 @@ pub(crate) struct ConnectPeerRequest {
     pub(crate) peer_pubkey_and_addr: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ConsolidateAssetsRequest {
+    pub(crate) asset_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ConsolidateAssetsResponse {
+    pub(crate) txid: String,
+    pub(crate) consolidated_allocations: Vec<OutpointAllocation>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CountersResponse {
+    pub(crate) counters: BTreeMap<String, u64>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct CreateUtxosRequest {
     pub(crate) up_to: bool,
     pub(crate) num: Option<u8>,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CreateUtxosResponse {
+    pub(crate) txid: String,
+    pub(crate) utxos: Vec<CreatedUtxo>,
+    /// Requested UTXOs left out with up_to, as free ones were already available
+    pub(crate) skipped: u8,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CreatedUtxo {
+    pub(crate) outpoint: String,
+    pub(crate) btc_amount: u64,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct DecodeLNInvoiceRequest {
     pub(crate) invoice: String,
@@ -220,6 +664,27 @@ pub(crate) struct DecodeLNInvoiceResponse {
     pub(crate) payment_secret: String,
     pub(crate) payee_pubkey: Option<String>,
     pub(crate) network: BitcoinNetwork,
+    pub(crate) min_final_cltv_expiry_delta: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct DecodePaymentRequestRequest {
+    /// BOLT11 invoice, RGB invoice or unified URI
+    pub(crate) payment_request: String,
+}
+
+/// What a payment request is, with its decoded fields
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum DecodePaymentRequestResponse {
+    Bolt11(DecodeLNInvoiceResponse),
+    RgbInvoice(DecodeRGBInvoiceResponse),
+    Unified {
+        lightning_invoice: Option<String>,
+        lightning: Option<DecodeLNInvoiceResponse>,
+        rgb_invoice: Option<String>,
+        rgb: Option<DecodeRGBInvoiceResponse>,
+    },
 }
 
 #[derive(Deserialize, Serialize)]
@@ -239,394 +704,1510 @@ pub(crate) struct DecodeRGBInvoiceResponse {
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct DisconnectPeerRequest {
-    pub(crate) peer_pubkey: String,
+pub(crate) struct DeletePaymentRequest {
+    pub(crate) payment_hash: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct EmptyResponse {}
-
-#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
-pub(crate) enum HTLCStatus {
-    Pending,
-    Succeeded,
-    Failed,
+pub(crate) struct DeletePaymentResponse {
+    pub(crate) removed: u64,
 }
 
-impl_writeable_tlv_based_enum!(HTLCStatus,
-    (0, Pending) => {},
-    (1, Succeeded) => {},
-    (2, Failed) => {};
-);
-
 #[derive(Deserialize, Serialize)]
-pub(crate) struct InitRequest {
-    pub(crate) password: String,
+pub(crate) struct DeleteWebhookRequest {
+    pub(crate) id: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DepletedSide {
+    Outbound,
+    Inbound,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct InitResponse {
-    pub(crate) mnemonic: String,
+pub(crate) struct DisconnectPeerRequest {
+    pub(crate) peer_pubkey: String,
 }
 
-#[derive(Clone, Copy, Deserialize, Serialize)]
-pub(crate) enum InvoiceStatus {
-    Pending,
-    Succeeded,
-    Failed,
-    Expired,
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) struct DryRunResult {
+    pub(crate) inputs: Vec<String>,
+    pub(crate) consumed_allocations: Vec<OutpointAllocation>,
+    pub(crate) fee_sat: u64,
+    pub(crate) btc_change_sat: u64,
+    pub(crate) asset_change: Option<u64>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct InvoiceStatusRequest {
-    pub(crate) invoice: String,
+pub(crate) struct EmptyResponse {}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct Event {
+    pub(crate) id: u64,
+    pub(crate) timestamp: u64,
+    #[serde(flatten)]
+    pub(crate) event: NodeEvent,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct InvoiceStatusResponse {
-    pub(crate) status: InvoiceStatus,
+pub(crate) struct EventsParams {
+    pub(crate) after_id: Option<u64>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct IssueAssetRequest {
-    pub(crate) amounts: Vec<u64>,
-    pub(crate) ticker: String,
-    pub(crate) name: String,
-    pub(crate) precision: u8,
+pub(crate) struct EventsResponse {
+    pub(crate) events: Vec<Event>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct IssueAssetResponse {
+pub(crate) struct ExportAssetParams {
     pub(crate) asset_id: String,
+    /// Export the latest transfer consignment, carrying the history of the asset, instead of
+    /// its contract
+    #[serde(default)]
+    pub(crate) history: bool,
+    #[serde(default)]
+    pub(crate) format: ExportFormat,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct KeysendRequest {
-    pub(crate) dest_pubkey: String,
-    pub(crate) amt_msat: u64,
+pub(crate) struct ExportAssetResponse {
     pub(crate) asset_id: String,
-    pub(crate) asset_amount: u64,
+    /// Base64-encoded binary consignment
+    pub(crate) consignment: String,
+    pub(crate) armored: String,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct KeysendResponse {
-    pub(crate) payment_hash: String,
-    pub(crate) payment_preimage: String,
-    pub(crate) status: HTLCStatus,
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ExportFormat {
+    #[default]
+    Binary,
+    Json,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListAssetsResponse {
-    pub(crate) assets: Vec<Asset>,
+pub(crate) struct FailTransfersRequest {
+    pub(crate) batch_transfer_idx: Option<i32>,
+    #[serde(default)]
+    pub(crate) no_asset_only: bool,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListChannelsResponse {
-    pub(crate) channels: Vec<Channel>,
+pub(crate) struct FailTransfersResponse {
+    pub(crate) transfers_changed: bool,
 }
 
+#[cfg(feature = "regtest-endpoints")]
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListPaymentsResponse {
-    pub(crate) payments: Vec<Payment>,
+pub(crate) struct FundAddressRequest {
+    pub(crate) address: String,
+    pub(crate) amount: u64,
 }
 
+#[cfg(feature = "regtest-endpoints")]
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListPeersResponse {
-    pub(crate) peers: Vec<Peer>,
+pub(crate) struct FundAddressResponse {
+    pub(crate) txid: String,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct ListTransactionsResponse {
-    pub(crate) transactions: Vec<Transaction>,
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct FailingHop {
+    pub(crate) position: u32,
+    pub(crate) node_pubkey: Option<String>,
+    pub(crate) short_channel_id: Option<u64>,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct ListTransfersRequest {
-    pub(crate) asset_id: String,
-}
+impl_writeable_tlv_based!(FailingHop, {
+    (0, position, required),
+    (2, node_pubkey, option),
+    (4, short_channel_id, option),
+});
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListTransfersResponse {
-    pub(crate) transfers: Vec<Transfer>,
+pub(crate) struct GetPaymentParams {
+    pub(crate) payment_hash: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListUnspentsResponse {
-    pub(crate) unspents: Vec<Unspent>,
+pub(crate) struct GetPaymentResponse {
+    pub(crate) payment: Payment,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct LNInvoiceRequest {
-    pub(crate) amt_msat: Option<u64>,
-    pub(crate) expiry_sec: u32,
-    pub(crate) asset_id: Option<String>,
-    pub(crate) asset_amount: Option<u64>,
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FailureReason {
+    AmountBelowMinimum,
+    ChannelDisabled,
+    DuplicatePayment,
+    FeeInsufficient,
+    IncorrectCltvExpiry,
+    IncorrectPaymentDetails,
+    PaymentExpired,
+    PermanentChannelFailure,
+    PermanentNodeFailure,
+    RetriesExhausted,
+    RgbIncorrectPaymentDetails,
+    RgbInsufficientAssetLiquidity,
+    RouteNotFound,
+    TemporaryChannelFailure,
+    TemporaryNodeFailure,
+    UnexpectedError,
+    UnknownPathFailure,
+    UserAbandoned,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct LNInvoiceResponse {
-    pub(crate) invoice: String,
+impl_writeable_tlv_based_enum!(FailureReason,
+    (0, AmountBelowMinimum) => {},
+    (1, ChannelDisabled) => {},
+    (2, DuplicatePayment) => {},
+    (3, FeeInsufficient) => {},
+    (4, IncorrectCltvExpiry) => {},
+    (5, IncorrectPaymentDetails) => {},
+    (6, PaymentExpired) => {},
+    (7, PermanentChannelFailure) => {},
+    (8, PermanentNodeFailure) => {},
+    (9, RetriesExhausted) => {},
+    (10, RgbIncorrectPaymentDetails) => {},
+    (11, RgbInsufficientAssetLiquidity) => {},
+    (12, RouteNotFound) => {},
+    (13, TemporaryChannelFailure) => {},
+    (14, TemporaryNodeFailure) => {},
+    (15, UnexpectedError) => {},
+    (16, UnknownPathFailure) => {},
+    (17, UserAbandoned) => {};
+);
+
+impl From<&RetryableSendFailure> for FailureReason {
+    fn from(failure: &RetryableSendFailure) -> Self {
+        match failure {
+            RetryableSendFailure::PaymentExpired => FailureReason::PaymentExpired,
+            RetryableSendFailure::RouteNotFound => FailureReason::RouteNotFound,
+            RetryableSendFailure::DuplicatePayment => FailureReason::DuplicatePayment,
+        }
+    }
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct NetworkInfoResponse {
-    pub(crate) network: BitcoinNetwork,
-    pub(crate) height: u32,
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum HTLCStatus {
+    #[serde(alias = "Pending")]
+    Pending,
+    #[serde(alias = "Succeeded")]
+    Succeeded,
+    #[serde(alias = "Failed")]
+    Failed,
+}
+
+impl_writeable_tlv_based_enum!(HTLCStatus,
+    (0, Pending) => {},
+    (1, Succeeded) => {},
+    (2, Failed) => {};
+);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum HtlcExpiryAction {
+    PeerReconnect,
+    PaymentAbandon,
+    HtlcFailBack,
+    ChannelForceClose,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct NodeInfoResponse {
-    pub(crate) pubkey: String,
-    pub(crate) num_channels: usize,
-    pub(crate) num_usable_channels: usize,
-    pub(crate) local_balance_msat: u64,
-    pub(crate) num_peers: usize,
+pub(crate) struct Htlc {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) inbound: bool,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) payment_hash: String,
+    pub(crate) expiry_height: u32,
+    pub(crate) blocks_to_force_close: u32,
+    pub(crate) expiry_warning: bool,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct OpenChannelRequest {
-    pub(crate) peer_pubkey_and_addr: String,
-    pub(crate) capacity_sat: u64,
-    pub(crate) push_msat: u64,
-    pub(crate) asset_amount: u64,
-    pub(crate) asset_id: String,
-    pub(crate) public: bool,
-    pub(crate) with_anchors: bool,
-    pub(crate) fee_base_msat: Option<u32>,
-    pub(crate) fee_proportional_millionths: Option<u32>,
+pub(crate) struct ImportAssetRequest {
+    /// ID of the asset whose contract gets fetched from the asset registry
+    pub(crate) asset_id: Option<String>,
+    /// Base64-encoded contract consignment, ASCII-armored or binary
+    pub(crate) consignment: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct OpenChannelResponse {
-    pub(crate) temporary_channel_id: String,
+pub(crate) struct ImportAssetResponse {
+    pub(crate) asset_id: String,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
-pub(crate) struct Payment {
-    pub(crate) amt_msat: Option<u64>,
-    pub(crate) asset_amount: Option<u64>,
-    pub(crate) asset_id: Option<String>,
-    pub(crate) payment_hash: String,
-    pub(crate) inbound: bool,
-    pub(crate) status: HTLCStatus,
+pub(crate) struct InboundChannelRequest {
+    pub(crate) request_id: String,
+    pub(crate) lsp_pubkey: String,
+    pub(crate) asset_id: String,
+    pub(crate) asset_amount: u64,
+    pub(crate) capacity_sat: u64,
+    pub(crate) status: InboundChannelRequestStatus,
+    pub(crate) fee_sat: Option<u64>,
+    pub(crate) fee_invoice: Option<String>,
+    pub(crate) temporary_channel_id: Option<String>,
+    pub(crate) reason: Option<String>,
 }
 
-#[derive(Clone, Deserialize, Serialize)]
-pub(crate) struct Peer {
-    pub(crate) pubkey: String,
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum InboundChannelRequestStatus {
+    Pending,
+    AwaitingFee,
+    Opening,
+    Rejected,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RestoreRequest {
-    pub(crate) backup_path: String,
+pub(crate) struct InitRequest {
     pub(crate) password: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RgbAllocation {
-    pub(crate) asset_id: Option<String>,
-    pub(crate) amount: u64,
-    pub(crate) settled: bool,
+pub(crate) struct InitResponse {
+    pub(crate) mnemonic: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum InvoiceStatus {
+    #[serde(alias = "Pending")]
+    Pending,
+    #[serde(alias = "Succeeded")]
+    Succeeded,
+    #[serde(alias = "Failed")]
+    Failed,
+    #[serde(alias = "Expired")]
+    Expired,
+    Cancelled,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RgbInvoiceRequest {
-    pub(crate) min_confirmations: u8,
-    pub(crate) asset_id: Option<String>,
+pub(crate) struct InvoiceStatusRequest {
+    pub(crate) invoice: Option<String>,
+    pub(crate) payment_hash: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RgbInvoiceResponse {
-    pub(crate) recipient_id: String,
-    pub(crate) invoice: String,
-    pub(crate) expiration_timestamp: Option<i64>,
+pub(crate) struct InvoiceStatusResponse {
+    pub(crate) status: InvoiceStatus,
+    pub(crate) payment_hash: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendAssetRequest {
-    pub(crate) asset_id: String,
-    pub(crate) amount: u64,
-    pub(crate) blinded_utxo: String,
-    pub(crate) donation: bool,
-    pub(crate) min_confirmations: u8,
-    pub(crate) transport_endpoints: Vec<String>,
+pub(crate) struct IssueAssetRequest {
+    pub(crate) amounts: Vec<AssetUnits>,
+    pub(crate) ticker: String,
+    pub(crate) name: String,
+    pub(crate) precision: u8,
+    /// Issue the asset even if its ticker is registered to other assets
+    #[serde(default)]
+    pub(crate) allow_duplicate_ticker: bool,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendAssetResponse {
-    pub(crate) txid: String,
+pub(crate) struct IssueAssetResponse {
+    pub(crate) asset_id: String,
+    pub(crate) registry_warning: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JobKind {
+    OpenChannel,
+    Restore,
+    SendAsset,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendBtcRequest {
-    pub(crate) amount: u64,
-    pub(crate) address: String,
-    pub(crate) fee_rate: f32,
+pub(crate) struct JobResponse {
+    pub(crate) job_id: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JobStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendBtcResponse {
-    pub(crate) txid: String,
+pub(crate) struct JobStatusParams {
+    pub(crate) job_id: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendOnionMessageRequest {
-    pub(crate) node_ids: Vec<String>,
-    pub(crate) tlv_type: u64,
-    pub(crate) data: String,
+pub(crate) struct JobStatusResponse {
+    pub(crate) job_id: String,
+    pub(crate) kind: JobKind,
+    pub(crate) status: JobStatus,
+    pub(crate) cancellable: bool,
+    pub(crate) created_at: u64,
+    pub(crate) finished_at: Option<u64>,
+    pub(crate) result: Option<serde_json::Value>,
+    pub(crate) error: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendPaymentRequest {
-    pub(crate) invoice: String,
+pub(crate) struct KeysendRequest {
+    pub(crate) dest_pubkey: String,
+    pub(crate) amt_msat: Option<MilliSats>,
+    pub(crate) asset_id: String,
+    pub(crate) asset_amount: AssetUnits,
+    pub(crate) label: Option<String>,
+    #[serde(default)]
+    pub(crate) use_minimum_btc: bool,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendPaymentResponse {
+pub(crate) struct KeysendResponse {
     pub(crate) payment_hash: String,
-    pub(crate) payment_secret: String,
+    pub(crate) payment_preimage: String,
+    pub(crate) amt_msat: u64,
     pub(crate) status: HTLCStatus,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SignMessageRequest {
-    pub(crate) message: String,
+pub(crate) struct LatencyResponse {
+    pub(crate) enabled: bool,
+    /// Latest requests of each route the percentiles are computed over
+    pub(crate) window: usize,
+    pub(crate) routes: Vec<RouteLatency>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SignMessageResponse {
-    pub(crate) signed_message: String,
+pub(crate) struct ListAddressesResponse {
+    pub(crate) addresses: Vec<WalletAddress>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct Transaction {
-    pub(crate) transaction_type: TransactionType,
-    pub(crate) txid: String,
-    pub(crate) received: u64,
-    pub(crate) sent: u64,
-    pub(crate) fee: Option<u64>,
-    pub(crate) confirmation_time: Option<BlockTime>,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListAssetsResponse {
+    pub(crate) assets: Vec<Asset>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) enum TransactionType {
-    RgbSend,
-    Drain,
-    CreateUtxos,
-    User,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListBansResponse {
+    pub(crate) bans: Vec<Ban>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct Transfer {
-    pub(crate) idx: i32,
-    pub(crate) created_at: i64,
-    pub(crate) updated_at: i64,
-    pub(crate) status: TransferStatus,
-    pub(crate) amount: u64,
-    pub(crate) kind: TransferKind,
-    pub(crate) txid: Option<String>,
-    pub(crate) recipient_id: Option<String>,
-    pub(crate) receive_utxo: Option<String>,
-    pub(crate) change_utxo: Option<String>,
-    pub(crate) expiration: Option<i64>,
-    pub(crate) transport_endpoints: Vec<TransferTransportEndpoint>,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListChannelsParams {
+    /// Cursor returned with the previous page
+    pub(crate) after: Option<String>,
+    pub(crate) limit: Option<usize>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) enum TransferKind {
-    Issuance,
-    ReceiveBlind,
-    ReceiveWitness,
-    Send,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListChannelsResponse {
+    pub(crate) channels: Vec<Channel>,
+    /// Cursor of the next page, None on the last one
+    pub(crate) next_cursor: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) enum TransferStatus {
-    WaitingCounterparty,
-    WaitingConfirmations,
-    Settled,
-    Failed,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListClosedChannelsResponse {
+    pub(crate) channels: Vec<ClosedChannel>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct TransferTransportEndpoint {
-    pub(crate) endpoint: String,
-    pub(crate) transport_type: TransportType,
-    pub(crate) used: bool,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListHtlcsResponse {
+    pub(crate) htlcs: Vec<Htlc>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) enum TransportType {
-    JsonRpc,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListInboundChannelRequestsResponse {
+    pub(crate) requests: Vec<InboundChannelRequest>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct UnlockRequest {
-    pub(crate) password: String,
+pub(crate) struct ListPaymentsParams {
+    pub(crate) label: Option<String>,
+    pub(crate) status: Option<InvoiceStatus>,
+    /// Cursor returned with the previous page
+    pub(crate) after: Option<String>,
+    pub(crate) limit: Option<usize>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct Unspent {
-    pub(crate) utxo: Utxo,
-    pub(crate) rgb_allocations: Vec<RgbAllocation>,
+pub(crate) struct ListPaymentsResponse {
+    pub(crate) payments: Vec<Payment>,
+    /// Cursor of the next page, None on the last one
+    pub(crate) next_cursor: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct Utxo {
-    pub(crate) outpoint: String,
-    pub(crate) btc_amount: u64,
-    pub(crate) colorable: bool,
+pub(crate) struct ListPeersParams {
+    /// Cursor returned with the previous page
+    pub(crate) after: Option<String>,
+    pub(crate) limit: Option<usize>,
 }
 
-impl AppState {
-    fn check_changing_state(&self) -> Result<(), APIError> {
-        if *self.get_changing_state() {
-            return Err(APIError::ChangingState);
-        }
-        Ok(())
-    }
-
-    async fn check_locked(
-        &self,
-    ) -> Result<TokioMutexGuard<Option<Arc<UnlockedAppState>>>, APIError> {
-        let unlocked_app_state = self.get_unlocked_app_state().await;
-        if unlocked_app_state.is_some() {
-            Err(APIError::UnlockedNode)
-        } else {
-            self.check_changing_state()?;
-            Ok(unlocked_app_state)
-        }
-    }
-
-    async fn check_unlocked(
-        &self,
-    ) -> Result<TokioMutexGuard<Option<Arc<UnlockedAppState>>>, APIError> {
-        let unlocked_app_state = self.get_unlocked_app_state().await;
-        if unlocked_app_state.is_none() {
-            Err(APIError::LockedNode)
-        } else {
-            self.check_changing_state()?;
-            Ok(unlocked_app_state)
-        }
-    }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListPeersResponse {
+    pub(crate) peers: Vec<Peer>,
+    /// Cursor of the next page, None on the last one
+    pub(crate) next_cursor: Option<String>,
+}
 
-    fn update_changing_state(&self, updated: bool) {
-        let mut changing_state = self.get_changing_state();
-        *changing_state = updated;
-    }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListSweepsResponse {
+    pub(crate) unswept_outputs: usize,
+    pub(crate) sweeps: Vec<Sweep>,
+}
 
-    fn update_ldk_background_services(&self, updated: Option<LdkBackgroundServices>) {
-        let mut ldk_background_services = self.get_ldk_background_services();
-        *ldk_background_services = updated;
-    }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListSwapsResponse {
+    pub(crate) swaps: Vec<Swap>,
+}
 
-    async fn update_unlocked_app_state(&self, updated: Option<Arc<UnlockedAppState>>) {
-        let mut unlocked_app_state = self.get_unlocked_app_state().await;
-        *unlocked_app_state = updated;
-    }
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct ListTransactionsParams {
+    pub(crate) min_height: Option<u32>,
+    pub(crate) max_height: Option<u32>,
+    pub(crate) offset: Option<usize>,
+    pub(crate) limit: Option<usize>,
+    /// Only list the unconfirmed transactions watched to be broadcast again
+    pub(crate) watched_only: Option<bool>,
 }
 
-impl From<RgbLibError> for APIError {
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListTransactionsResponse {
+    pub(crate) transactions: Vec<Transaction>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListTransfersRequest {
+    pub(crate) asset_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListTransfersResponse {
+    pub(crate) transfers: Vec<Transfer>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListUnspentsParams {
+    pub(crate) colorable: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListUnspentsResponse {
+    pub(crate) unspents: Vec<Unspent>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListWebhooksResponse {
+    pub(crate) webhooks: Vec<Webhook>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LNInvoiceRequest {
+    pub(crate) amt_msat: Option<MilliSats>,
+    pub(crate) expiry_sec: u32,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<AssetUnits>,
+    pub(crate) label: Option<String>,
+    #[serde(default)]
+    pub(crate) allow_insufficient_inbound: bool,
+    /// Only let the node with this pubkey pay the invoice
+    pub(crate) require_payer_pubkey: Option<String>,
+    /// Only accept payments carrying a payer note
+    #[serde(default)]
+    pub(crate) require_payer_note: bool,
+    /// Blocks the final HTLC must be left before expiring, the node minimum if not provided
+    pub(crate) min_final_cltv_expiry_delta: Option<u16>,
+    /// Also return the invoice as a unified URI, along with an RGB invoice for the asset to be
+    /// paid on-chain
+    #[serde(default)]
+    pub(crate) unified: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LNInvoiceResponse {
+    pub(crate) invoice: String,
+    /// BIP21-style URI carrying the invoice, only returned if asked for
+    pub(crate) unified: Option<String>,
+}
+
+#[cfg(feature = "regtest-endpoints")]
+#[derive(Deserialize, Serialize)]
+pub(crate) struct MineBlocksRequest {
+    pub(crate) blocks: u16,
+}
+
+#[cfg(feature = "regtest-endpoints")]
+#[derive(Deserialize, Serialize)]
+pub(crate) struct MineBlocksResponse {
+    pub(crate) block_hashes: Vec<String>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum NodeEvent {
+    AlertCleared {
+        kind: AlertKind,
+        channel_id: Option<String>,
+    },
+    AlertRaised {
+        kind: AlertKind,
+        channel_id: Option<String>,
+        asset_id: Option<String>,
+        message: String,
+    },
+    AssetReceived {
+        asset_id: String,
+        amount: u64,
+        recipient_id: Option<String>,
+        txid: Option<String>,
+    },
+    ChainReorg {
+        depth: u32,
+        fork_height: u32,
+        channel_ids: Vec<String>,
+        transfers: Vec<ReorgedTransfer>,
+    },
+    ChannelClosed {
+        channel_id: String,
+        peer_pubkey: Option<String>,
+        reason: String,
+    },
+    ChannelOpenFailed {
+        temporary_channel_id: String,
+        peer_pubkey: String,
+        asset_id: String,
+        asset_amount: u64,
+        reason: String,
+    },
+    CustomMessageReceived {
+        peer_pubkey: String,
+        msg_type: u16,
+        data: String,
+    },
+    HtlcExpiryAction {
+        action: HtlcExpiryAction,
+        channel_id: String,
+        peer_pubkey: String,
+        payment_hash: String,
+        blocks_to_force_close: u32,
+        success: bool,
+    },
+    InboundChannelRequest {
+        temporary_channel_id: String,
+        peer_pubkey: String,
+        capacity_sat: u64,
+        push_msat: u64,
+        asset_id: Option<String>,
+        asset_amount: Option<u64>,
+        decision: ChannelRequestDecision,
+        reason: Option<String>,
+    },
+    IncomingTransferFailed {
+        asset_id: Option<String>,
+        recipient_id: String,
+        txid: Option<String>,
+        reason: String,
+    },
+    InvoicePaid {
+        payment_hash: String,
+        amt_msat: u64,
+        asset_id: Option<String>,
+        asset_amount: Option<u64>,
+    },
+    OnionMessageReceived {
+        tlv_type: u64,
+        data: String,
+    },
+    PayerIdentificationRejected {
+        payment_hash: String,
+        reason: String,
+    },
+    PaymentFailed {
+        payment_hash: String,
+        failure_reason: FailureReason,
+        failing_hop: Option<FailingHop>,
+    },
+    PeerConnected {
+        peer_pubkey: String,
+    },
+    PeerDisconnected {
+        peer_pubkey: String,
+    },
+    RgbPaymentRejected {
+        payment_hash: String,
+        reason: String,
+    },
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NetworkInfoResponse {
+    pub(crate) network: BitcoinNetwork,
+    pub(crate) height: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NodeInfoResponse {
+    pub(crate) pubkey: String,
+    pub(crate) alias: String,
+    pub(crate) color: String,
+    pub(crate) num_channels: usize,
+    pub(crate) num_usable_channels: usize,
+    pub(crate) local_balance_msat: u64,
+    pub(crate) num_peers: usize,
+    pub(crate) default_min_confirmations: u8,
+    pub(crate) min_allowed_confirmations: u8,
+    pub(crate) monitor_mirror_pending_updates: Option<usize>,
+    pub(crate) monitor_mirror_lagging: bool,
+    pub(crate) onion_message_forwarding: bool,
+    pub(crate) proxy_reachable: bool,
+    pub(crate) proxy_version: Option<String>,
+    pub(crate) proxy_protocol_compatible: bool,
+    pub(crate) persistence_failure: Option<String>,
+    pub(crate) num_hot_monitors: usize,
+    pub(crate) num_archived_monitors: usize,
+    pub(crate) recovery_mode: bool,
+    /// Absolute path of the storage directory the node runs from
+    pub(crate) data_dir: String,
+    /// Handshake parameters proposed for the channels opened from now on
+    pub(crate) channel_handshake: ChannelHandshakeDefaults,
+    pub(crate) node_announcement: AnnouncementStatus,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OpenChannelRequest {
+    pub(crate) peer_pubkey_and_addr: String,
+    pub(crate) capacity_sat: Sats,
+    pub(crate) push_msat: MilliSats,
+    pub(crate) asset_amount: AssetUnits,
+    pub(crate) asset_id: String,
+    pub(crate) public: bool,
+    pub(crate) with_anchors: bool,
+    pub(crate) fee_base_msat: Option<u32>,
+    pub(crate) fee_proportional_millionths: Option<u32>,
+    pub(crate) min_depth: Option<u32>,
+    pub(crate) htlc_minimum_msat: Option<MilliSats>,
+    /// Fee rate of the funding transaction, exclusive with `target_conf_blocks`
+    pub(crate) fee_rate_sat_vb: Option<f32>,
+    pub(crate) target_conf_blocks: Option<u16>,
+    #[serde(default)]
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OpenChannelResponse {
+    pub(crate) temporary_channel_id: Option<String>,
+    pub(crate) dry_run: Option<DryRunResult>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct OpenSuggestion {
+    pub(crate) peer_pubkey: String,
+    pub(crate) failed_payments: u64,
+    pub(crate) succeeded_payments: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) struct OutpointAllocation {
+    pub(crate) outpoint: String,
+    pub(crate) amount: u64,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct Payment {
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_precision: Option<u8>,
+    pub(crate) payment_hash: String,
+    pub(crate) inbound: bool,
+    pub(crate) status: HTLCStatus,
+    /// Status taking into account the expiration and cancellation of the invoice
+    pub(crate) invoice_status: InvoiceStatus,
+    pub(crate) label: Option<String>,
+    pub(crate) created_at: u64,
+    pub(crate) failure_reason: Option<FailureReason>,
+    pub(crate) failing_hop: Option<FailingHop>,
+    /// Node that identified itself as the payer of an inbound payment
+    pub(crate) payer_pubkey: Option<String>,
+    pub(crate) payer_note: Option<String>,
+    pub(crate) settlement_method: SettlementMethod,
+    /// Transaction paying the fallback address of the invoice, for payments settled on-chain
+    pub(crate) onchain_txid: Option<String>,
+    /// Hops of the paths taken by a successful outbound payment, only reported by /getpayment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) hops: Option<Vec<PaymentHop>>,
+}
+
+/// Hop of the path taken by (a part of) an outbound payment
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct PaymentHop {
+    /// Index of the payment part the path has been taken by, multi-path payments having one
+    /// path per part
+    pub(crate) part: u32,
+    pub(crate) node_pubkey: String,
+    pub(crate) short_channel_id: u64,
+    /// Fee taken by the node for forwarding over the next channel, 0 for the recipient
+    pub(crate) fee_msat: u64,
+}
+
+impl_writeable_tlv_based!(PaymentHop, {
+    (0, part, required),
+    (2, node_pubkey, required),
+    (4, short_channel_id, required),
+    (6, fee_msat, required),
+});
+
+/// Whether a payment can be sent as quoted
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PaymentFeasibility {
+    Ok,
+    /// The usable channels can't send the amount, in the asset for colored payments
+    InsufficientLiquidity,
+    NoRoute,
+    AmountBelowMinimum,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PaymentVolume {
+    pub(crate) count: u64,
+    pub(crate) amt_msat: u64,
+    pub(crate) avg_settle_latency_secs: Option<f64>,
+    pub(crate) assets: Vec<AssetVolume>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct Peer {
+    pub(crate) pubkey: String,
+    pub(crate) rgb_support: bool,
+    pub(crate) connected_since: u64,
+    pub(crate) last_seen: Option<u64>,
+    pub(crate) disconnect_count: u32,
+    pub(crate) exposure: PeerExposure,
+}
+
+/// Value in flight with a peer across its channels, with its limits and the HTLCs they refused
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct PeerExposure {
+    pub(crate) msat: u64,
+    pub(crate) max_msat: Option<u64>,
+    pub(crate) assets: Vec<AssetExposure>,
+    pub(crate) violations: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PendingChannel {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) outbound: bool,
+    pub(crate) stage: PendingChannelStage,
+    pub(crate) capacity_sat: u64,
+    pub(crate) funding_txid: Option<String>,
+    pub(crate) confirmations: u32,
+    pub(crate) confirmations_required: Option<u32>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_local_amount: Option<u64>,
+    pub(crate) asset_remote_amount: Option<u64>,
+    pub(crate) asset_precision: Option<u8>,
+    /// Fee rate of the funding transaction, only known for the channels opened by the node
+    pub(crate) fee_rate_sat_vb: Option<f32>,
+    /// Confirmation target the funding fee rate has been derived from
+    pub(crate) target_conf_blocks: Option<u16>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PendingChannelStage {
+    /// Inbound channel held until the user accepts or rejects it
+    AwaitingDecision,
+    Negotiating,
+    FundingSigning,
+    AwaitingConfirmations,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PendingChannelsResponse {
+    pub(crate) channels: Vec<PendingChannel>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PruneGraphResponse {
+    pub(crate) nodes_removed: usize,
+    pub(crate) channels_removed: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PruneMonitorsRequest {
+    pub(crate) older_than_ts: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PruneMonitorsResponse {
+    pub(crate) removed: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PrunePaymentsRequest {
+    pub(crate) older_than_ts: u64,
+    pub(crate) statuses: Vec<InvoiceStatus>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PrunePaymentsResponse {
+    pub(crate) removed: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct QueryNodeInfoParams {
+    pub(crate) pubkey: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct QueryNodeInfoResponse {
+    pub(crate) pubkey: String,
+    pub(crate) alias: Option<String>,
+    pub(crate) color: Option<String>,
+    pub(crate) last_update: Option<u32>,
+}
+
+/// A BOLT11 invoice, or a destination with the amounts of a keysend
+#[derive(Deserialize, Serialize)]
+pub(crate) struct QuotePaymentRequest {
+    pub(crate) invoice: Option<String>,
+    pub(crate) dest_pubkey: Option<String>,
+    pub(crate) amt_msat: Option<MilliSats>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<AssetUnits>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct QuotePaymentResponse {
+    pub(crate) feasibility: PaymentFeasibility,
+    /// Fee of the cheapest route found
+    pub(crate) best_fee_msat: Option<u64>,
+    /// Fee 90% of the candidate routes cost at most
+    pub(crate) p90_fee_msat: Option<u64>,
+    /// Blocks the funds can stay locked for on the cheapest route, if the payment gets stuck
+    pub(crate) cltv_expiry_delta: Option<u32>,
+    pub(crate) candidate_routes: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct RebalanceSuggestion {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) depleted_side: DepletedSide,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) suggested_amt_msat: Option<u64>,
+    pub(crate) suggested_asset_amount: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ReadyResponse {
+    /// Off-chain payments don't need the proxy, so a node without it is still ready
+    pub(crate) proxy_reachable: bool,
+    pub(crate) proxy_version: Option<String>,
+    pub(crate) proxy_protocol_compatible: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RebroadcastAnnouncementsResponse {
+    /// Channels whose announcement has been broadcast again along with the node announcement
+    pub(crate) channels: usize,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ReestablishState {
+    PeerDisconnected,
+    /// The peer is connected but the channel isn't ready yet
+    AwaitingChannelReady,
+    /// The peer is connected but the channel isn't usable yet, the exchange or a monitor update
+    /// still being in progress
+    Pending,
+    Completed,
+}
+
+/// What the asset registry knows about an asset
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RegistryStatus {
+    /// The asset is registered under its ticker
+    Verified,
+    /// No asset is registered under the ticker
+    Unregistered,
+    /// The ticker is registered to other assets
+    TickerConflict,
+    /// No registry is configured or it couldn't be reached
+    Unverified,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RejectChannelRequest {
+    pub(crate) temporary_channel_id: String,
+}
+
+/// Settled transfer whose anchoring transaction got disconnected by a reorg
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct ReorgedTransfer {
+    pub(crate) asset_id: String,
+    pub(crate) idx: i32,
+    pub(crate) txid: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RequestInboundChannelRequest {
+    pub(crate) lsp_pubkey_and_addr: String,
+    pub(crate) asset_id: String,
+    pub(crate) asset_amount: AssetUnits,
+    pub(crate) capacity_sat: Sats,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RequestInboundChannelResponse {
+    pub(crate) request_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RestoreRequest {
+    pub(crate) backup_path: String,
+    pub(crate) password: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RgbAllocation {
+    pub(crate) asset_id: Option<String>,
+    pub(crate) amount: u64,
+    pub(crate) settled: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RgbInvoiceRequest {
+    pub(crate) min_confirmations: Option<u8>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) label: Option<String>,
+    /// Seconds the invoice stays valid for, 1 day by default
+    pub(crate) expiry_sec: Option<u32>,
+    /// Also return the invoice as a unified URI
+    #[serde(default)]
+    pub(crate) unified: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RgbInvoiceResponse {
+    pub(crate) recipient_id: String,
+    pub(crate) invoice: String,
+    pub(crate) expiration_timestamp: Option<i64>,
+    /// BIP21-style URI carrying the invoice, only returned if asked for
+    pub(crate) unified: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendAssetRequest {
+    pub(crate) asset_id: String,
+    pub(crate) amount: AssetUnits,
+    pub(crate) blinded_utxo: String,
+    pub(crate) donation: bool,
+    pub(crate) min_confirmations: u8,
+    pub(crate) transport_endpoints: Vec<String>,
+    #[serde(default)]
+    pub(crate) dry_run: bool,
+    #[serde(default)]
+    pub(crate) force: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendAssetResponse {
+    pub(crate) txid: Option<String>,
+    pub(crate) consumed_allocations: Vec<OutpointAllocation>,
+    pub(crate) change_allocation: Option<OutpointAllocation>,
+    pub(crate) dry_run: Option<DryRunResult>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendBtcRequest {
+    pub(crate) amount: Sats,
+    pub(crate) address: String,
+    pub(crate) fee_rate: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendBtcResponse {
+    pub(crate) txid: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendCustomMessageRequest {
+    pub(crate) peer_pubkey: String,
+    pub(crate) msg_type: u16,
+    pub(crate) data: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendOnionMessageRequest {
+    pub(crate) node_ids: Vec<String>,
+    pub(crate) blinded_path: Option<String>,
+    pub(crate) tlv_type: u64,
+    pub(crate) data: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendPaymentRequest {
+    pub(crate) invoice: String,
+    pub(crate) label: Option<String>,
+    pub(crate) timeout_sec: Option<u64>,
+    pub(crate) max_retries: Option<u32>,
+    #[serde(default)]
+    pub(crate) disable_mpp: bool,
+    /// Note attached to the payment for the payee
+    pub(crate) payer_note: Option<String>,
+    /// Let the payee know the node is paying, by signing the payment hash
+    #[serde(default)]
+    pub(crate) identify_payer: bool,
+    /// Pay the fallback address of the invoice on-chain if the payment fails within its timeout
+    #[serde(default)]
+    pub(crate) fallback_onchain: bool,
+    /// Fee rate of the on-chain payment, in sat/vB
+    pub(crate) fallback_fee_rate: Option<f32>,
+    /// Send a colored payment even if no usable channel of the asset seems able to carry it
+    #[serde(default)]
+    pub(crate) skip_liquidity_check: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendPaymentResponse {
+    pub(crate) payment_hash: String,
+    pub(crate) payment_secret: String,
+    pub(crate) status: HTLCStatus,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetAliasRequest {
+    pub(crate) alias: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetAlertsRequest {
+    pub(crate) anchor_reserve_per_channel_sat: Option<u64>,
+    pub(crate) min_fee_bump_utxos: Option<u64>,
+    pub(crate) min_channel_asset_amount: Option<u64>,
+    pub(crate) max_unconfirmed_tx_age_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetChannelNoteRequest {
+    pub(crate) channel_id: String,
+    pub(crate) note: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetLabelRequest {
+    pub(crate) payment_hash: Option<String>,
+    /// Recipient ID of an RGB invoice of the node
+    #[serde(alias = "blinded_utxo")]
+    pub(crate) recipient_id: Option<String>,
+    pub(crate) label: String,
+}
+
+/// Deserialize a field that is present, telling an explicit null (Some(None)) from an omitted
+/// field (None, through the serde default)
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Update of the payment policy, the omitted fields leaving their part of the policy unchanged
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct SetPaymentPolicyRequest {
+    /// Destinations outbound payments are restricted to, an empty list allowing all of them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) allowed_destinations: Option<Vec<String>>,
+    /// Daily msat limit, null to remove it
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) max_msat_per_day: Option<Option<MilliSats>>,
+    /// Daily limits by asset, replacing all the previous ones, an empty list removing them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_asset_amounts_per_day: Option<Vec<AssetSpendingLimit>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetPeerExposureLimitsRequest {
+    pub(crate) peer_pubkey: String,
+    pub(crate) max_msat: Option<MilliSats>,
+    #[serde(default)]
+    pub(crate) max_asset_amounts: Vec<AssetSpendingLimit>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SettlementMethod {
+    Lightning,
+    Onchain,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ShutdownMode {
+    /// Lock the node and keep serving only the routes needed to unlock it again
+    Restartable,
+    /// Stop the node and exit the process
+    #[default]
+    Exit,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct ShutdownRequest {
+    #[serde(default)]
+    pub(crate) mode: ShutdownMode,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SignMessageRequest {
+    pub(crate) message: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SignMessageResponse {
+    pub(crate) signed_message: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SpendingLimitStatus {
+    pub(crate) limit: u64,
+    pub(crate) used: u64,
+    pub(crate) remaining: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SpendingLimitsResponse {
+    pub(crate) window_sec: u64,
+    pub(crate) msat: Option<SpendingLimitStatus>,
+    pub(crate) assets: Vec<AssetSpendingLimitStatus>,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum StatsPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct StatsParams {
+    pub(crate) period: StatsPeriod,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct StatsResponse {
+    pub(crate) period_start: u64,
+    pub(crate) received: PaymentVolume,
+    pub(crate) sent: PaymentVolume,
+    pub(crate) outbound_succeeded: u64,
+    pub(crate) outbound_failed: u64,
+    pub(crate) outbound_success_rate: Option<f64>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct Swap {
+    pub(crate) swap_id: String,
+    pub(crate) direction: SwapDirection,
+    pub(crate) initiator: bool,
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) asset_id: String,
+    pub(crate) asset_amount: u64,
+    pub(crate) status: SwapStatus,
+    pub(crate) payment_hash: Option<String>,
+    pub(crate) txid: Option<String>,
+    pub(crate) reason: Option<String>,
+    pub(crate) created_at: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Sweep {
+    pub(crate) txid: String,
+    pub(crate) amount_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) created_at: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SwapAssetsRequest {
+    pub(crate) channel_id: String,
+    pub(crate) asset_amount: AssetUnits,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SwapAssetsResponse {
+    pub(crate) swap_id: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SwapDirection {
+    IntoChannel,
+    OutOfChannel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SwapStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct Transaction {
+    pub(crate) transaction_type: TransactionType,
+    pub(crate) txid: String,
+    pub(crate) received: u64,
+    pub(crate) sent: u64,
+    pub(crate) fee: Option<u64>,
+    pub(crate) confirmation_time: Option<BlockTime>,
+    pub(crate) direction: TransactionDirection,
+    pub(crate) net_amount_sat: i64,
+    pub(crate) purpose: TransactionPurpose,
+    pub(crate) channel_id: Option<String>,
+    /// Times the transaction has been broadcast again, set while it's unconfirmed and watched
+    pub(crate) rebroadcasts: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TransactionDirection {
+    Incoming,
+    Outgoing,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TransactionPurpose {
+    AssetSend,
+    BtcTransfer,
+    ChannelClose,
+    ChannelForceClose,
+    ChannelOpen,
+    Drain,
+    UtxoCreation,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TransactionType {
+    #[serde(alias = "RgbSend")]
+    RgbSend,
+    #[serde(alias = "Drain")]
+    Drain,
+    #[serde(alias = "CreateUtxos")]
+    CreateUtxos,
+    #[serde(alias = "User")]
+    User,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct Transfer {
+    pub(crate) idx: i32,
+    pub(crate) created_at: i64,
+    pub(crate) updated_at: i64,
+    pub(crate) status: TransferStatus,
+    pub(crate) amount: u64,
+    pub(crate) kind: TransferKind,
+    pub(crate) txid: Option<String>,
+    pub(crate) recipient_id: Option<String>,
+    pub(crate) receive_utxo: Option<String>,
+    pub(crate) change_utxo: Option<String>,
+    pub(crate) expiration: Option<i64>,
+    pub(crate) transport_endpoints: Vec<TransferTransportEndpoint>,
+    pub(crate) label: Option<String>,
+    pub(crate) failure_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TransferKind {
+    #[serde(alias = "Issuance")]
+    Issuance,
+    #[serde(alias = "ReceiveBlind")]
+    ReceiveBlind,
+    #[serde(alias = "ReceiveWitness")]
+    ReceiveWitness,
+    #[serde(alias = "Send")]
+    Send,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TransferStatus {
+    #[serde(alias = "WaitingCounterparty")]
+    WaitingCounterparty,
+    #[serde(alias = "WaitingConfirmations")]
+    WaitingConfirmations,
+    #[serde(alias = "Settled")]
+    Settled,
+    #[serde(alias = "Failed")]
+    Failed,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct TransferTransportEndpoint {
+    pub(crate) endpoint: String,
+    pub(crate) transport_type: TransportType,
+    pub(crate) used: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TransportType {
+    #[serde(alias = "JsonRpc")]
+    JsonRpc,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct UnbanPeerRequest {
+    pub(crate) peer_pubkey: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct UnlockRequest {
+    pub(crate) password: String,
+    /// Rebuild the data that can be regenerated when it cannot be read
+    #[serde(default)]
+    pub(crate) regenerate: bool,
+    /// Force-close every channel and sweep the funds on-chain, refusing new channels
+    #[serde(default)]
+    pub(crate) recovery_mode: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Unspent {
+    pub(crate) utxo: Utxo,
+    pub(crate) rgb_allocations: Vec<RgbAllocation>,
+    /// Pending operation holding the UTXO until its transaction confirms
+    pub(crate) reserved_by: Option<UtxoReservation>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Utxo {
+    pub(crate) outpoint: String,
+    pub(crate) btc_amount: u64,
+    pub(crate) colorable: bool,
+}
+
+/// Operation holding a UTXO, either spending it or creating it
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum UtxoReservation {
+    ChannelOpen { channel_id: String },
+    AssetTransfer { txid: String },
+    UtxoCreation { txid: String },
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct WalletAddress {
+    pub(crate) derivation_index: u32,
+    pub(crate) address: String,
+    /// None if the address was found used on-chain without having been handed out
+    pub(crate) issued_at: Option<u64>,
+    pub(crate) used: bool,
+    pub(crate) balance_sat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct VerifyMessageRequest {
+    pub(crate) message: String,
+    pub(crate) signature: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct VerifyMessageResponse {
+    pub(crate) pubkey: String,
+    pub(crate) known_peer: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Webhook {
+    pub(crate) id: u64,
+    pub(crate) url: String,
+    pub(crate) events: Vec<String>,
+    pub(crate) created_at: u64,
+    /// Events waiting for a first delivery attempt or a retry
+    pub(crate) pending_deliveries: usize,
+    pub(crate) delivered: u64,
+    /// Events given up on after all the delivery attempts have failed
+    pub(crate) failed: u64,
+    pub(crate) last_attempt_at: Option<u64>,
+    pub(crate) last_delivered_at: Option<u64>,
+    /// Error of the last delivery attempt, cleared once one succeeds
+    pub(crate) last_error: Option<String>,
+}
+
+impl AppState {
+    fn check_changing_state(&self) -> Result<(), APIError> {
+        if *self.get_changing_state() {
+            return Err(APIError::ChangingState);
+        }
+        Ok(())
+    }
+
+    async fn check_locked(
+        &self,
+    ) -> Result<TokioMutexGuard<Option<Arc<UnlockedAppState>>>, APIError> {
+        let unlocked_app_state = self.get_unlocked_app_state().await;
+        if unlocked_app_state.is_some() {
+            Err(APIError::UnlockedNode)
+        } else {
+            self.check_changing_state()?;
+            Ok(unlocked_app_state)
+        }
+    }
+
+    async fn check_unlocked(
+        &self,
+    ) -> Result<TokioMutexGuard<Option<Arc<UnlockedAppState>>>, APIError> {
+        let unlocked_app_state = self.get_unlocked_app_state().await;
+        if unlocked_app_state.is_none() {
+            Err(APIError::LockedNode)
+        } else {
+            self.check_changing_state()?;
+            Ok(unlocked_app_state)
+        }
+    }
+
+    fn update_changing_state(&self, updated: bool) {
+        let mut changing_state = self.get_changing_state();
+        *changing_state = updated;
+    }
+
+    fn update_ldk_background_services(&self, updated: Option<LdkBackgroundServices>) {
+        let mut ldk_background_services = self.get_ldk_background_services();
+        *ldk_background_services = updated;
+    }
+
+    async fn update_unlocked_app_state(&self, updated: Option<Arc<UnlockedAppState>>) {
+        let mut unlocked_app_state = self.get_unlocked_app_state().await;
+        *unlocked_app_state = updated;
+    }
+}
+
+impl From<RgbLibError> for APIError {
     fn from(error: RgbLibError) -> Self {
         match error {
             RgbLibError::AllocationsAlreadyAvailable => APIError::AllocationsAlreadyAvailable,
@@ -634,974 +2215,2866 @@ impl From<RgbLibError> for APIError {
             RgbLibError::FailedIssuance { details } => {
                 APIError::FailedIssuingAsset(details.clone())
             }
-            RgbLibError::InsufficientAllocationSlots => APIError::NoAvailableUtxos,
-            RgbLibError::InsufficientBitcoins { needed, available } => {
-                APIError::InsufficientFunds(needed - available)
+            RgbLibError::InsufficientAllocationSlots => APIError::NoAvailableUtxos,
+            RgbLibError::InsufficientBitcoins { needed, available } => {
+                APIError::InsufficientFunds(needed - available)
+            }
+            RgbLibError::InvalidAssetID { asset_id } => APIError::InvalidAssetID(asset_id.clone()),
+            RgbLibError::InvalidBlindedUTXO { details } => {
+                APIError::InvalidBlindedUTXO(details.clone())
+            }
+            RgbLibError::InvalidFeeRate { details } => APIError::InvalidFeeRate(details.clone()),
+            RgbLibError::InvalidName { details } => APIError::InvalidName(details.clone()),
+            RgbLibError::InvalidPrecision { details } => {
+                APIError::InvalidPrecision(details.clone())
+            }
+            RgbLibError::InvalidTicker { details } => APIError::InvalidTicker(details.clone()),
+            RgbLibError::InvalidTransportEndpoints { details } => {
+                APIError::InvalidTransportEndpoints(details.clone())
+            }
+            RgbLibError::RecipientIDAlreadyUsed => APIError::RecipientIDAlreadyUsed,
+            RgbLibError::OutputBelowDustLimit => APIError::OutputBelowDustLimit,
+            _ => APIError::Unexpected,
+        }
+    }
+}
+
+pub(crate) async fn abandon_payment(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AbandonPaymentRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let payment_hash = hex_str_to_payment_hash(&payload.payment_hash)
+            .ok_or(APIError::InvalidPaymentHash(payload.payment_hash))?;
+        let payment_id = PaymentId(payment_hash.0);
+
+        match unlocked_state.outbound_payments().get(&payment_id) {
+            Some(payment) if payment.status == HTLCStatus::Pending => {}
+            Some(_) => {
+                return Err(APIError::PaymentNotAbandonable(s!(
+                    "payment is not pending"
+                )))
+            }
+            None => return Err(APIError::UnknownPayment),
+        }
+
+        let recent_payment = unlocked_state
+            .channel_manager
+            .list_recent_payments()
+            .into_iter()
+            .find(|p| match p {
+                RecentPaymentDetails::Pending { payment_id: id, .. }
+                | RecentPaymentDetails::Fulfilled { payment_id: id, .. }
+                | RecentPaymentDetails::Abandoned { payment_id: id, .. }
+                | RecentPaymentDetails::AwaitingInvoice { payment_id: id } => *id == payment_id,
+            });
+        match recent_payment {
+            Some(RecentPaymentDetails::Fulfilled { .. }) => {
+                return Err(APIError::PaymentNotAbandonable(s!(
+                    "payment has already been claimed by the recipient"
+                )))
+            }
+            Some(_) => {
+                // the payment status will be updated once all its HTLCs have been resolved
+                unlocked_state.channel_manager.abandon_payment(payment_id);
+            }
+            None => {
+                // LDK doesn't know about the payment anymore, no HTLC can be in-flight
+                unlocked_state.fail_outbound_payment(
+                    &state.static_state,
+                    payment_id,
+                    FailureReason::UserAbandoned,
+                    None,
+                );
+            }
+        }
+
+        tracing::info!("Abandoned payment {}", hex_str(&payment_hash.0));
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn accept_channel(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AcceptChannelRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+        state.static_state.check_writable()?;
+
+        let temporary_channel_id = hex_str_to_channel_id(&payload.temporary_channel_id)
+            .ok_or(APIError::InvalidChannelID)?;
+        let request = unlocked_state
+            .inbound_channels
+            .take_held(&temporary_channel_id.to_hex())
+            .ok_or(APIError::UnknownChannelRequest)?;
+        accept_inbound_channel(&unlocked_state, &temporary_channel_id, &request.peer_pubkey)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn add_webhook(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AddWebhookRequest>, APIError>,
+) -> Result<Json<AddWebhookResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        check_webhook_url(&payload.url)?;
+        if payload.events.is_empty() {
+            return Err(APIError::InvalidWebhook(s!("no event selected")));
+        }
+        if let Some(unknown) = payload
+            .events
+            .iter()
+            .find(|e| !WEBHOOK_EVENT_TYPES.contains(&e.as_str()))
+        {
+            return Err(APIError::InvalidWebhook(format!(
+                "unknown event type {unknown}"
+            )));
+        }
+        if payload.secret.len() < MIN_WEBHOOK_SECRET_LEN {
+            return Err(APIError::InvalidWebhook(format!(
+                "the secret must be at least {MIN_WEBHOOK_SECRET_LEN} characters long"
+            )));
+        }
+        let mut events = payload.events;
+        events.sort();
+        events.dedup();
+
+        let id = unlocked_state.database.add_webhook(
+            &payload.url,
+            &events,
+            &payload.secret,
+            get_current_timestamp(),
+        )?;
+        tracing::info!("Added webhook {id} posting {events:?} to {}", payload.url);
+
+        Ok(Json(AddWebhookResponse { id }))
+    })
+    .await
+}
+
+pub(crate) async fn address(
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<Json<AddressResponse>, APIError> {
+    // calls without a body get a fresh address
+    let payload: AddressRequest = if body.is_empty() {
+        AddressRequest::default()
+    } else {
+        serde_json::from_slice(&body).map_err(|e| APIError::InvalidAddressRequest(e.to_string()))?
+    };
+
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let static_state = state.static_state.clone();
+        let record = tokio::task::spawn_blocking(move || {
+            unlocked_state.issue_wallet_address(&static_state, payload.reuse_last)
+        })
+        .await
+        .unwrap()?;
+
+        Ok(Json(AddressResponse {
+            address: record.address,
+            derivation_index: record.derivation_index,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn alerts(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<AlertsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let thresholds = unlocked_state.database.alert_thresholds()?;
+
+    Ok(Json(AlertsResponse {
+        anchor_reserve_per_channel_sat: thresholds.anchor_reserve_per_channel_sat,
+        min_fee_bump_utxos: thresholds.min_fee_bump_utxos,
+        min_channel_asset_amount: thresholds.min_channel_asset_amount,
+        max_unconfirmed_tx_age_secs: thresholds.max_unconfirmed_tx_age_secs,
+        alerts: unlocked_state.alerts.list(),
+    }))
+}
+
+pub(crate) async fn asset_balance(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AssetBalanceRequest>, APIError>,
+) -> Result<Json<AssetBalanceResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let contract_id = ContractId::from_str(&payload.asset_id)
+        .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
+
+    let balance = unlocked_state.rgb_get_asset_balance(contract_id)?;
+    let failed_amount = failed_incoming_amount(&unlocked_state, &contract_id.to_string())?;
+    let precision = unlocked_state
+        .rgb_get_asset_nia(contract_id)?
+        .map(|a| a.precision)
+        .unwrap_or_default();
+
+    let (offchain_outbound, offchain_inbound) = unlocked_state
+        .channel_asset_amounts(&state.static_state.ldk_data_dir)
+        .remove(&contract_id)
+        .unwrap_or_default();
+
+    Ok(Json(AssetBalanceResponse {
+        settled: balance.settled,
+        future: balance.future.saturating_sub(failed_amount),
+        spendable: balance.spendable,
+        offchain_outbound,
+        offchain_inbound,
+        precision,
+    }))
+}
+
+pub(crate) async fn balances(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<BalancesResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let btc_balance = unlocked_state.rgb_get_btc_balance()?;
+    let channels = unlocked_state.channel_manager.list_channels();
+    let offchain_local_msat = channels.iter().map(|c| c.balance_msat).sum::<u64>();
+    let btc = BtcBalances {
+        onchain_vanilla: BtcBalance {
+            settled: btc_balance.vanilla.settled,
+            future: btc_balance.vanilla.future,
+            spendable: btc_balance.vanilla.spendable,
+        },
+        onchain_colored: BtcBalance {
+            settled: btc_balance.colored.settled,
+            future: btc_balance.colored.future,
+            spendable: btc_balance.colored.spendable,
+        },
+        offchain_local_msat,
+        offchain_outbound_msat: channels.iter().map(|c| c.outbound_capacity_msat).sum(),
+        offchain_inbound_msat: channels.iter().map(|c| c.inbound_capacity_msat).sum(),
+        total_msat: (btc_balance.vanilla.future + btc_balance.colored.future) * 1000
+            + offchain_local_msat,
+    };
+
+    let mut channel_amounts =
+        unlocked_state.channel_asset_amounts(&state.static_state.ldk_data_dir);
+    let mut assets = vec![];
+    for asset in unlocked_state.rgb_list_assets()?.nia.unwrap_or_default() {
+        let contract_id = ContractId::from_str(&asset.asset_id)
+            .map_err(|_| APIError::InvalidAssetID(asset.asset_id.clone()))?;
+        let balance = unlocked_state.rgb_get_asset_balance(contract_id)?;
+        let failed_amount = failed_incoming_amount(&unlocked_state, &asset.asset_id)?;
+        let onchain_future = balance.future.saturating_sub(failed_amount);
+        let (offchain_outbound, offchain_inbound) =
+            channel_amounts.remove(&contract_id).unwrap_or_default();
+        assets.push(AssetBalances {
+            asset_id: asset.asset_id,
+            ticker: asset.ticker,
+            precision: asset.precision,
+            onchain_settled: balance.settled,
+            onchain_future,
+            onchain_spendable: balance.spendable,
+            offchain_outbound,
+            offchain_inbound,
+            total: onchain_future + offchain_outbound,
+        });
+    }
+
+    Ok(Json(BalancesResponse { btc, assets }))
+}
+
+pub(crate) async fn backup(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BackupRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_state = state.check_locked().await?;
+
+        let _mnemonic =
+            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
+
+        do_backup(
+            PathBuf::from(&state.static_state.storage_dir_path),
+            &payload.backup_path,
+            &payload.password,
+        )?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn ban_peer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BanPeerRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let peer_pubkey = match bitcoin::secp256k1::PublicKey::from_str(&payload.peer_pubkey) {
+            Ok(pubkey) => pubkey,
+            Err(_e) => return Err(APIError::InvalidPubkey),
+        };
+
+        if !payload.force && unlocked_state.has_channels_with(&peer_pubkey) {
+            return Err(APIError::PeerHasChannels(payload.peer_pubkey));
+        }
+
+        let duration_secs = payload
+            .duration_secs
+            .unwrap_or(unlocked_state.peer_limiter.ban_duration_secs());
+        unlocked_state.ban_peer(peer_pubkey, s!("banned by the user"), duration_secs);
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn btc_balance(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<BtcBalanceResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let btc_balance = unlocked_state.rgb_get_btc_balance()?;
+
+    let vanilla = BtcBalance {
+        settled: btc_balance.vanilla.settled,
+        future: btc_balance.vanilla.future,
+        spendable: btc_balance.vanilla.spendable,
+    };
+
+    let colored = BtcBalance {
+        settled: btc_balance.colored.settled,
+        future: btc_balance.colored.future,
+        spendable: btc_balance.colored.spendable,
+    };
+
+    Ok(Json(BtcBalanceResponse { vanilla, colored }))
+}
+
+pub(crate) async fn cancel_invoice(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CancelInvoiceRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let payment_hash = hex_str_to_payment_hash(&payload.payment_hash)
+            .ok_or(APIError::InvalidPaymentHash(payload.payment_hash))?;
+        unlocked_state.cancel_invoice(&payment_hash)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn cancel_job(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CancelJobRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    state.jobs.cancel(&payload.job_id)?;
+
+    Ok(Json(EmptyResponse {}))
+}
+
+pub(crate) async fn change_password(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ChangePasswordRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_state = state.check_locked().await?;
+
+        check_password_strength(payload.new_password.clone())?;
+
+        let mnemonic =
+            check_password_validity(&payload.old_password, &state.static_state.storage_dir_path)?;
+
+        encrypt_and_save_mnemonic(
+            payload.new_password,
+            mnemonic.to_string(),
+            get_mnemonic_path(&state.static_state.storage_dir_path),
+        )?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn channel_debug(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(params), _): WithRejection<Query<ChannelDebugParams>, APIError>,
+) -> Result<Json<ChannelDebugResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let channel_id_vec = hex_str_to_vec(&params.channel_id);
+    if channel_id_vec.is_none() || channel_id_vec.as_ref().unwrap().len() != 32 {
+        return Err(APIError::InvalidChannelID);
+    }
+    let mut channel_id = [0; 32];
+    channel_id.copy_from_slice(&channel_id_vec.unwrap());
+
+    Ok(Json(unlocked_state.channel_debug(
+        &state.static_state.ldk_data_dir,
+        ChannelId(channel_id),
+    )?))
+}
+
+pub(crate) async fn channel_suggestions(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(params), _): WithRejection<Query<ChannelSuggestionsParams>, APIError>,
+) -> Result<Json<ChannelSuggestionsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    Ok(Json(unlocked_state.channel_suggestions(
+        &state.static_state.ldk_data_dir,
+        params.days.unwrap_or(DEFAULT_SUGGESTIONS_DAYS),
+    )?))
+}
+
+pub(crate) async fn close_channel(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CloseChannelRequest>, APIError>,
+) -> Result<Json<CloseChannelResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let channel_id_vec = hex_str_to_vec(&payload.channel_id);
+        if channel_id_vec.is_none() || channel_id_vec.as_ref().unwrap().len() != 32 {
+            return Err(APIError::InvalidChannelID);
+        }
+        let mut channel_id = [0; 32];
+        channel_id.copy_from_slice(&channel_id_vec.unwrap());
+
+        let peer_pubkey_vec = match hex_str_to_vec(&payload.peer_pubkey) {
+            Some(peer_pubkey_vec) => peer_pubkey_vec,
+            None => return Err(APIError::InvalidPubkey),
+        };
+        let peer_pubkey = match PublicKey::from_slice(&peer_pubkey_vec) {
+            Ok(peer_pubkey) => peer_pubkey,
+            Err(_) => return Err(APIError::InvalidPubkey),
+        };
+
+        if payload.force
+            && (payload.fee_rate_sat_vb.is_some()
+                || payload.target_conf_blocks.is_some()
+                || payload.min_fee_rate_sat_vb.is_some()
+                || payload.max_fee_rate_sat_vb.is_some())
+        {
+            return Err(APIError::InvalidFeeRate(s!(
+                "not supported by force closes"
+            )));
+        }
+        if let Some(force_after_sec) = payload.force_after_sec {
+            if payload.force {
+                return Err(APIError::InvalidCloseTimeout(s!(
+                    "not supported by force closes"
+                )));
+            }
+            if force_after_sec == 0 {
+                return Err(APIError::InvalidCloseTimeout(s!(
+                    "must be at least 1 second"
+                )));
+            }
+        }
+        let fee_rate = choose_fee_rate(
+            &state.static_state,
+            payload.fee_rate_sat_vb,
+            payload.target_conf_blocks,
+        )
+        .await?;
+        let fee_rate = bound_close_fee_rate(
+            fee_rate,
+            payload.min_fee_rate_sat_vb,
+            payload.max_fee_rate_sat_vb,
+        )?;
+
+        if let Some(blinded_utxo) = &payload.asset_destination {
+            if payload.force {
+                return Err(APIError::InvalidAssetDestination(s!(
+                    "not supported by force closes"
+                )));
+            }
+            let chan_info = unlocked_state
+                .channel_manager
+                .list_channels()
+                .into_iter()
+                .find(|c| {
+                    c.channel_id == ChannelId(channel_id) && c.counterparty.node_id == peer_pubkey
+                })
+                .ok_or(APIError::UnknownChannel)?;
+            let info_file_path = get_rgb_channel_info_path(
+                &chan_info.channel_id.to_hex(),
+                &PathBuf::from(&state.static_state.ldk_data_dir),
+                false,
+            );
+            if !info_file_path.exists() {
+                return Err(APIError::InvalidAssetDestination(s!(
+                    "the channel carries no asset"
+                )));
+            }
+            let rgb_info = parse_rgb_channel_info(&info_file_path);
+            check_close_asset_destination(&unlocked_state, rgb_info.contract_id, blinded_utxo)?;
+        }
+
+        if payload.dry_run {
+            let chan_info = unlocked_state
+                .channel_manager
+                .list_channels()
+                .into_iter()
+                .find(|c| {
+                    c.channel_id == ChannelId(channel_id) && c.counterparty.node_id == peer_pubkey
+                })
+                .ok_or(APIError::UnknownChannel)?;
+            return Ok(Json(CloseChannelResponse {
+                dry_run: Some(simulate_channel_close(
+                    &unlocked_state,
+                    &state.static_state,
+                    &chan_info,
+                    payload.force,
+                    fee_rate.as_ref(),
+                )),
+            }));
+        }
+
+        if payload.force {
+            match unlocked_state
+                .channel_manager
+                .force_close_broadcasting_latest_txn(&ChannelId(channel_id), &peer_pubkey)
+            {
+                Ok(()) => tracing::info!("EVENT: initiating channel force-close"),
+                Err(e) => return Err(APIError::FailedClosingChannel(format!("{:?}", e))),
+            }
+        } else {
+            if let Some(max_fee_rate_sat_vb) = payload.max_fee_rate_sat_vb {
+                let chan_info = unlocked_state
+                    .channel_manager
+                    .list_channels()
+                    .into_iter()
+                    .find(|c| {
+                        c.channel_id == ChannelId(channel_id)
+                            && c.counterparty.node_id == peer_pubkey
+                    })
+                    .ok_or(APIError::UnknownChannel)?;
+                unlocked_state.cap_close_fee_rate(
+                    &state.static_state,
+                    &chan_info,
+                    max_fee_rate_sat_vb,
+                )?;
+            }
+            match unlocked_state
+                .channel_manager
+                .close_channel_with_feerate_and_script(
+                    &ChannelId(channel_id),
+                    &peer_pubkey,
+                    fee_rate.map(|f| (f.fee_rate_sat_vb * 250.0) as u32), // 1 sat/vB = 250 sat/kw
+                    None,
+                ) {
+                Ok(()) => tracing::info!("EVENT: initiating channel close"),
+                Err(e) => return Err(APIError::FailedClosingChannel(format!("{:?}", e))),
+            }
+            if let Some(fee_rate) = fee_rate {
+                unlocked_state.database.set_channel_fee_rate(
+                    &ChannelId(channel_id).to_hex(),
+                    ChannelFeeKind::Close,
+                    fee_rate,
+                )?;
+            }
+            if let Some(blinded_utxo) = &payload.asset_destination {
+                unlocked_state
+                    .database
+                    .set_close_asset_destination(&ChannelId(channel_id).to_hex(), blinded_utxo)?;
+            }
+            if let Some(force_after_sec) = payload.force_after_sec {
+                unlocked_state.database.set_close_deadline(
+                    &ChannelId(channel_id).to_hex(),
+                    get_current_timestamp() + force_after_sec,
+                )?;
+            }
+        }
+
+        Ok(Json(CloseChannelResponse { dry_run: None }))
+    })
+    .await
+}
+
+pub(crate) async fn connect_peer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ConnectPeerRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let (peer_pubkey, peer_addr) = parse_peer_info(payload.peer_pubkey_and_addr.to_string())?;
+
+        if unlocked_state.peer_limiter.is_banned(&peer_pubkey) {
+            return Err(APIError::PeerBanned(peer_pubkey.to_string()));
+        }
+
+        connect_peer_if_necessary(peer_pubkey, peer_addr, unlocked_state.peer_manager.clone())
+            .await?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn consolidate_assets(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ConsolidateAssetsRequest>, APIError>,
+) -> Result<Json<ConsolidateAssetsResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let contract_id = ContractId::from_str(&payload.asset_id)
+            .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
+
+        tokio::task::spawn_blocking(move || {
+            let (txid, consolidated_allocations) = consolidate_asset(
+                &unlocked_state,
+                &state.static_state,
+                &contract_id.to_string(),
+            )?;
+            Ok(Json(ConsolidateAssetsResponse {
+                txid,
+                consolidated_allocations,
+            }))
+        })
+        .await
+        .unwrap()
+    })
+    .await
+}
+
+pub(crate) async fn counters(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<CountersResponse>, APIError> {
+    Ok(Json(CountersResponse {
+        counters: state.static_state.counters(),
+    }))
+}
+
+pub(crate) async fn create_utxos(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateUtxosRequest>, APIError>,
+) -> Result<Json<CreateUtxosResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let plan = plan_utxo_creation(
+            &unlocked_state,
+            payload.up_to,
+            payload.num.unwrap_or(UTXO_NUM),
+        )?;
+        let unsigned_psbt =
+            unlocked_state.rgb_create_utxos_begin(false, plan.num, UTXO_SIZE_SAT, FEE_RATE)?;
+        // the wallet creates as many UTXOs as the vanilla funds cover, refuse to create fewer
+        let utxos = created_utxos(&unsigned_psbt, UTXO_SIZE_SAT)?;
+        if utxos.len() < plan.num as usize {
+            return Err(APIError::InsufficientFundsForUtxos {
+                requested: plan.num,
+                possible: utxos.len() as u8,
+            });
+        }
+        let signed_psbt = unlocked_state.rgb_sign_psbt(unsigned_psbt)?;
+        let txid = psbt_txid(&signed_psbt)?;
+        let intent = WalletIntent {
+            txid: txid.clone(),
+            kind: WalletIntentKind::CreateUtxos,
+            signed_psbt,
+            asset_id: None,
+            spending_id: None,
+            blinded_utxo: None,
+            created_at: get_current_timestamp(),
+        };
+        unlocked_state.broadcast_with_intent(&state.static_state, intent, |signed_psbt| {
+            unlocked_state.rgb_create_utxos_end(signed_psbt.clone())?;
+            unlocked_state.reserve_utxos(
+                &signed_psbt,
+                UtxoReservation::UtxoCreation { txid: txid.clone() },
+            );
+            Ok(())
+        })?;
+        tracing::debug!("UTXO creation complete");
+
+        Ok(Json(CreateUtxosResponse {
+            txid,
+            utxos,
+            skipped: plan.skipped,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn decode_ln_invoice(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DecodeLNInvoiceRequest>, APIError>,
+) -> Result<Json<DecodeLNInvoiceResponse>, APIError> {
+    let _unlocked_app_state = state.get_unlocked_app_state();
+
+    Ok(Json(payment_request::decode_ln_invoice(&payload.invoice)?))
+}
+
+pub(crate) async fn decode_payment_request(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DecodePaymentRequestRequest>, APIError>,
+) -> Result<Json<DecodePaymentRequestResponse>, APIError> {
+    let _unlocked_app_state = state.get_unlocked_app_state();
+
+    Ok(Json(payment_request::decode_payment_request(
+        &payload.payment_request,
+    )?))
+}
+
+pub(crate) async fn decode_rgb_invoice(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DecodeRGBInvoiceRequest>, APIError>,
+) -> Result<Json<DecodeRGBInvoiceResponse>, APIError> {
+    let _unlocked_app_state = state.get_unlocked_app_state();
+
+    Ok(Json(payment_request::decode_rgb_invoice(payload.invoice)?))
+}
+
+pub(crate) async fn delete_payment(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DeletePaymentRequest>, APIError>,
+) -> Result<Json<DeletePaymentResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let payment_hash = hex_str_to_payment_hash(&payload.payment_hash)
+            .ok_or(APIError::InvalidPaymentHash(payload.payment_hash))?;
+
+        let removed = unlocked_state.delete_payment(payment_hash)?;
+
+        Ok(Json(DeletePaymentResponse { removed }))
+    })
+    .await
+}
+
+pub(crate) async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DeleteWebhookRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        if !unlocked_state.database.delete_webhook(payload.id)? {
+            return Err(APIError::UnknownWebhook);
+        }
+        unlocked_state.webhooks.forget(payload.id);
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn disconnect_peer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DisconnectPeerRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let peer_pubkey = match bitcoin::secp256k1::PublicKey::from_str(&payload.peer_pubkey) {
+            Ok(pubkey) => pubkey,
+            Err(_e) => return Err(APIError::InvalidPubkey),
+        };
+
+        //check for open channels with peer
+        for channel in unlocked_state.channel_manager.list_channels() {
+            if channel.counterparty.node_id == peer_pubkey {
+                return Err(APIError::FailedPeerDisconnection(s!(
+                    "node has an active channel with this peer, close any channels first"
+                )));
+            }
+        }
+
+        //check the pubkey matches a valid connected peer
+        let peers = unlocked_state.peer_manager.get_peer_node_ids();
+        if !peers.iter().any(|(pk, _)| &peer_pubkey == pk) {
+            return Err(APIError::FailedPeerDisconnection(format!(
+                "Could not find peer {}",
+                peer_pubkey
+            )));
+        }
+
+        unlocked_state
+            .peer_manager
+            .disconnect_by_node_id(peer_pubkey);
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn events(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(params), _): WithRejection<Query<EventsParams>, APIError>,
+) -> Result<Json<EventsResponse>, APIError> {
+    Ok(Json(EventsResponse {
+        events: state.static_state.events(params.after_id),
+    }))
+}
+
+pub(crate) async fn export_asset(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(params), _): WithRejection<Query<ExportAssetParams>, APIError>,
+) -> Result<Response, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let contract_id = ContractId::from_str(&params.asset_id)
+        .map_err(|_| APIError::InvalidAssetID(params.asset_id.clone()))?;
+    if !unlocked_state
+        .rgb_get_asset_precisions()?
+        .contains_key(&params.asset_id)
+    {
+        return Err(APIError::UnknownContractId);
+    }
+
+    let static_state = state.static_state.clone();
+    let history = params.history;
+    let mut exported = tokio::task::spawn_blocking(move || {
+        unlocked_state.export_consignment(&static_state, contract_id, history)
+    })
+    .await
+    .unwrap()?;
+
+    match params.format {
+        ExportFormat::Binary => {
+            let file = tokio::fs::File::from_std(exported.file);
+            let headers = [
+                (header::CONTENT_TYPE, s!("application/octet-stream")),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", exported.file_name),
+                ),
+            ];
+            let body = StreamBody::new(FramedRead::new(file, BytesCodec::new()));
+            Ok((headers, body).into_response())
+        }
+        ExportFormat::Json => {
+            let mut consignment = vec![];
+            exported.file.read_to_end(&mut consignment)?;
+            Ok(Json(ExportAssetResponse {
+                asset_id: params.asset_id,
+                consignment: base64::encode(consignment),
+                armored: exported.armored,
+            })
+            .into_response())
+        }
+    }
+}
+
+pub(crate) async fn fail_transfers(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<FailTransfersRequest>, APIError>,
+) -> Result<Json<FailTransfersResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        tokio::task::spawn_blocking(move || {
+            let transfers_changed = unlocked_state
+                .rgb_fail_transfers(payload.batch_transfer_idx, payload.no_asset_only)?;
+            unlocked_state.release_utxo_reservations()?;
+
+            // blinded UTXOs whose transfers have all failed can be paid again
+            for (blinded_utxo, asset_id) in unlocked_state.database.sent_blinded_utxos()? {
+                let mut sends = unlocked_state
+                    .rgb_list_transfers(asset_id)?
+                    .into_iter()
+                    .filter(|t| t.recipient_id.as_ref() == Some(&blinded_utxo))
+                    .peekable();
+                if sends.peek().is_some()
+                    && sends.all(|t| matches!(t.status, rgb_lib::TransferStatus::Failed))
+                {
+                    unlocked_state
+                        .database
+                        .remove_sent_blinded_utxo(&blinded_utxo)?;
+                }
+            }
+
+            Ok(Json(FailTransfersResponse { transfers_changed }))
+        })
+        .await
+        .unwrap()
+    })
+    .await
+}
+
+#[cfg(feature = "regtest-endpoints")]
+pub(crate) async fn fund_address(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<FundAddressRequest>, APIError>,
+) -> Result<Json<FundAddressResponse>, APIError> {
+    let regtest = state.static_state.regtest()?;
+
+    let txid = async {
+        regtest.ensure_wallet().await?;
+        regtest.fund_address(&payload.address, payload.amount).await
+    }
+    .await
+    .map_err(|e| APIError::FailedRegtestCall(e.to_string()))?;
+
+    Ok(Json(FundAddressResponse {
+        txid: txid.to_string(),
+    }))
+}
+
+pub(crate) async fn get_payment(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(params), _): WithRejection<Query<GetPaymentParams>, APIError>,
+) -> Result<Json<GetPaymentResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let payment_hash = hex_str_to_payment_hash(&params.payment_hash)
+        .ok_or(APIError::InvalidPaymentHash(params.payment_hash))?;
+    let ldk_data_dir_path = Path::new(&state.static_state.ldk_data_dir);
+    let asset_precisions = unlocked_state.rgb_get_asset_precisions()?;
+
+    // the outbound record is reported for payments to self, as it has the route
+    let payment =
+        if let Some(payment_info) = unlocked_state.database.payment(&payment_hash.0, false)? {
+            let mut payment =
+                payment_info.to_payment(&payment_hash, false, ldk_data_dir_path, &asset_precisions);
+            payment.hops = Some(payment_info.hops.clone());
+            payment
+        } else if let Some(payment_info) = unlocked_state.database.payment(&payment_hash.0, true)? {
+            payment_info.to_payment(&payment_hash, true, ldk_data_dir_path, &asset_precisions)
+        } else {
+            return Err(APIError::UnknownPayment);
+        };
+
+    Ok(Json(GetPaymentResponse { payment }))
+}
+
+pub(crate) async fn health() -> Json<EmptyResponse> {
+    Json(EmptyResponse {})
+}
+
+pub(crate) async fn import_asset(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ImportAssetRequest>, APIError>,
+) -> Result<Json<ImportAssetResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let (consignment, expected_asset_id) = match (payload.asset_id, payload.consignment) {
+            (Some(asset_id), None) => {
+                let registry = state.static_state.asset_registry.as_ref().ok_or_else(|| {
+                    APIError::InvalidAssetImport(s!(
+                        "no asset registry is configured to fetch the contract from"
+                    ))
+                })?;
+                let consignment = registry
+                    .contract(&asset_id)
+                    .await
+                    .map_err(|e| APIError::FailedFetchingContract(e.to_string()))?;
+                (consignment, Some(asset_id))
+            }
+            (None, Some(consignment)) => {
+                let consignment = base64::decode(consignment).map_err(|e| {
+                    APIError::InvalidConsignment(format!("invalid base64 encoding: {e}"))
+                })?;
+                (consignment, None)
+            }
+            _ => {
+                return Err(APIError::InvalidAssetImport(s!(
+                    "exactly one of asset_id and consignment must be provided"
+                )))
+            }
+        };
+
+        let static_state = state.static_state.clone();
+        let asset_id = tokio::task::spawn_blocking(move || {
+            unlocked_state.import_contract(
+                &static_state,
+                &consignment,
+                expected_asset_id.as_deref(),
+            )
+        })
+        .await
+        .unwrap()?;
+
+        Ok(Json(ImportAssetResponse { asset_id }))
+    })
+    .await
+}
+
+pub(crate) async fn init(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<InitRequest>, APIError>,
+) -> Result<Json<InitResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_state = state.check_locked().await?;
+
+        check_password_strength(payload.password.clone())?;
+
+        let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
+        check_already_initialized(&mnemonic_path)?;
+
+        let keys = generate_keys(get_bitcoin_network(&state.static_state.network));
+
+        let mnemonic = keys.mnemonic;
+
+        encrypt_and_save_mnemonic(payload.password, mnemonic.clone(), mnemonic_path)?;
+        write_data_version(Path::new(&state.static_state.storage_dir_path))?;
+
+        Ok(Json(InitResponse { mnemonic }))
+    })
+    .await
+}
+
+pub(crate) async fn invoice_status(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<InvoiceStatusRequest>, APIError>,
+) -> Result<Json<InvoiceStatusResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let (payment_hash, invoice) = match (payload.invoice, payload.payment_hash) {
+        (Some(invoice), None) => {
+            let invoice = Bolt11Invoice::from_str(&invoice)
+                .map_err(|e| APIError::InvalidInvoice(e.to_string()))?;
+            (
+                PaymentHash(invoice.payment_hash().into_inner()),
+                Some(invoice),
+            )
+        }
+        (None, Some(payment_hash)) => {
+            let payment_hash = hex_str_to_payment_hash(&payment_hash)
+                .ok_or(APIError::InvalidPaymentHash(payment_hash))?;
+            (payment_hash, None)
+        }
+        _ => {
+            return Err(APIError::InvalidInvoice(s!(
+                "exactly one of invoice and payment_hash must be given"
+            )))
+        }
+    };
+
+    let status = match unlocked_state.database.payment(&payment_hash.0, true)? {
+        // without the invoice the expiry recorded at its creation is used
+        Some(v) => match v.invoice_status() {
+            InvoiceStatus::Pending if invoice.is_some_and(|i| i.is_expired()) => {
+                InvoiceStatus::Expired
+            }
+            status => status,
+        },
+        None => return Err(APIError::UnknownLNInvoice),
+    };
+
+    Ok(Json(InvoiceStatusResponse {
+        status,
+        payment_hash: hex_str(&payment_hash.0),
+    }))
+}
+
+pub(crate) async fn issue_asset(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetRequest>, APIError>,
+) -> Result<Json<IssueAssetResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        if payload.ticker.is_empty() || payload.ticker.len() > ASSET_TICKER_MAX_LEN {
+            return Err(APIError::InvalidTicker(format!(
+                "must be between 1 and {ASSET_TICKER_MAX_LEN} characters long"
+            )));
+        }
+        if !payload.ticker.starts_with(|c: char| c.is_ascii_uppercase())
+            || !payload
+                .ticker
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        {
+            return Err(APIError::InvalidTicker(s!(
+                "must start with an uppercase letter and contain only uppercase letters (A-Z) \
+                and digits (0-9)"
+            )));
+        }
+        if payload.name.is_empty() || payload.name.len() > ASSET_NAME_MAX_LEN {
+            return Err(APIError::InvalidName(format!(
+                "must be between 1 and {ASSET_NAME_MAX_LEN} characters long"
+            )));
+        }
+        if !payload
+            .name
+            .chars()
+            .all(|c| c.is_ascii_graphic() || c == ' ')
+        {
+            return Err(APIError::InvalidName(s!(
+                "must contain only printable ASCII characters"
+            )));
+        }
+        if payload.precision > ASSET_PRECISION_MAX {
+            return Err(APIError::InvalidPrecision(format!(
+                "must be between 0 and {ASSET_PRECISION_MAX}"
+            )));
+        }
+        if payload.amounts.is_empty() {
+            return Err(APIError::InvalidAmount(s!(
+                "at least one amount must be provided"
+            )));
+        }
+        if payload.amounts.contains(&AssetUnits(0)) {
+            return Err(APIError::InvalidAmount(s!(
+                "amounts must be greater than 0"
+            )));
+        }
+        if payload
+            .amounts
+            .iter()
+            .try_fold(AssetUnits(0), |total, amount| total.checked_add(*amount))
+            .is_none()
+        {
+            return Err(APIError::InvalidAmount(format!(
+                "total issued supply cannot exceed {}",
+                u64::MAX
+            )));
+        }
+
+        // an unreachable registry only gets reported, it never blocks the issuance
+        let mut registry_warning = None;
+        if let Some(registry) = &state.static_state.asset_registry {
+            match registry.registered_assets(&payload.ticker).await {
+                Some(asset_ids) if !asset_ids.is_empty() => {
+                    let registered = format!("{} ({})", payload.ticker, asset_ids.join(", "));
+                    if !payload.allow_duplicate_ticker {
+                        return Err(APIError::DuplicateTicker(registered));
+                    }
+                    registry_warning = Some(format!(
+                        "ticker already registered to other assets: {registered}"
+                    ));
+                }
+                Some(_) => {}
+                None => {
+                    registry_warning = Some(s!(
+                        "the asset registry is unreachable, the ticker has not been checked"
+                    ))
+                }
+            }
+        }
+
+        if !unlocked_state
+            .rgb_list_unspents()?
+            .iter()
+            .any(|u| u.utxo.colorable)
+        {
+            return Err(APIError::NoAvailableUtxos);
+        }
+
+        let asset = unlocked_state.rgb_issue_asset_nia(
+            payload.ticker,
+            payload.name,
+            payload.precision,
+            payload.amounts.iter().map(|a| a.0).collect(),
+        )?;
+
+        Ok(Json(IssueAssetResponse {
+            asset_id: asset.asset_id,
+            registry_warning,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn job_status(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(params), _): WithRejection<Query<JobStatusParams>, APIError>,
+) -> Result<Json<JobStatusResponse>, APIError> {
+    Ok(Json(state.jobs.status(&params.job_id)?))
+}
+
+pub(crate) async fn keysend(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<KeysendRequest>, APIError>,
+) -> Result<Json<KeysendResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        state.static_state.check_writable()?;
+
+        let dest_pubkey = match hex_str_to_compressed_pubkey(&payload.dest_pubkey) {
+            Some(pk) => pk,
+            None => return Err(APIError::InvalidPubkey),
+        };
+
+        check_label(&payload.label)?;
+
+        let contract_id = ContractId::from_str(&payload.asset_id)
+            .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
+        let asset_amount = payload.asset_amount.0;
+        check_asset_amount(&unlocked_state, contract_id, asset_amount)?;
+
+        let ldk_data_dir = &state.static_state.ldk_data_dir;
+        let amt_msat = match (payload.amt_msat, payload.use_minimum_btc) {
+            (Some(amt_msat), false) => amt_msat.0,
+            (None, true) => unlocked_state
+                .colored_keysend_min_msat(ldk_data_dir, contract_id, &dest_pubkey)
+                .unwrap_or(HTLC_MIN_MSAT),
+            (Some(_), true) => {
+                return Err(APIError::InvalidAmount(s!(
+                    "amt_msat cannot be given with use_minimum_btc"
+                )))
             }
-            RgbLibError::InvalidAssetID { asset_id } => APIError::InvalidAssetID(asset_id.clone()),
-            RgbLibError::InvalidBlindedUTXO { details } => {
-                APIError::InvalidBlindedUTXO(details.clone())
+            (None, false) => {
+                return Err(APIError::InvalidAmount(s!(
+                    "amt_msat is required unless use_minimum_btc is set"
+                )))
             }
-            RgbLibError::InvalidFeeRate { details } => APIError::InvalidFeeRate(details.clone()),
-            RgbLibError::InvalidName { details } => APIError::InvalidName(details.clone()),
-            RgbLibError::InvalidPrecision { details } => {
-                APIError::InvalidPrecision(details.clone())
+        };
+        check_htlc_amounts(
+            &unlocked_state,
+            ldk_data_dir,
+            contract_id,
+            amt_msat,
+            Some(asset_amount),
+            true,
+        )?;
+        if let Some(max_msat) =
+            unlocked_state.colored_htlc_max_sendable_msat(ldk_data_dir, contract_id)
+        {
+            if amt_msat > max_msat {
+                return Err(APIError::InsufficientOutboundLiquidity(format!(
+                    "{max_msat} msat"
+                )));
             }
-            RgbLibError::InvalidTicker { details } => APIError::InvalidTicker(details.clone()),
-            RgbLibError::InvalidTransportEndpoints { details } => {
-                APIError::InvalidTransportEndpoints(details.clone())
+        }
+
+        check_payment_policy(&unlocked_state, &dest_pubkey.to_string())?;
+        let avoided_channels = unlocked_state.exposure_avoided_channels(
+            &state.static_state,
+            amt_msat,
+            Some((contract_id, asset_amount)),
+        )?;
+
+        let payment_preimage =
+            PaymentPreimage(unlocked_state.keys_manager.get_secure_random_bytes());
+        let payment_hash_inner = Sha256::hash(&payment_preimage.0[..]).into_inner();
+        let payment_id = PaymentId(payment_hash_inner);
+        let payment_hash = PaymentHash(payment_hash_inner);
+
+        unlocked_state.database.reserve_spending(
+            &hex_str(&payment_id.0),
+            amt_msat,
+            Some((&contract_id.to_string(), asset_amount)),
+            get_current_timestamp(),
+        )?;
+        if let Err(e) = unlocked_state.reserve_outbound(
+            ldk_data_dir,
+            payment_id,
+            amt_msat,
+            contract_id,
+            asset_amount,
+            true,
+        ) {
+            let _ = unlocked_state
+                .database
+                .release_spending(&hex_str(&payment_id.0));
+            return Err(e);
+        }
+
+        write_rgb_payment_info_file(
+            &PathBuf::from(&state.static_state.ldk_data_dir),
+            &payment_hash,
+            contract_id,
+            asset_amount,
+            false,
+        );
+
+        let mut route_params = RouteParameters::from_payment_params_and_value(
+            PaymentParameters::for_keysend(dest_pubkey, 40, false),
+            amt_msat,
+        );
+        route_params.payment_params.previously_failed_channels = avoided_channels;
+        unlocked_state.add_outbound_payment(
+            payment_id,
+            PaymentInfo {
+                preimage: None,
+                secret: None,
+                status: HTLCStatus::Pending,
+                amt_msat: Some(amt_msat),
+                label: payload.label,
+                created_at: get_current_timestamp(),
+                expires_at: None,
+                failure_reason: None,
+                failing_hop: None,
+                settled_at: None,
+                payer_requirements: None,
+                payer: None,
+                payee: Some(dest_pubkey),
+                onchain_txid: None,
+                closure: None,
+                hops: vec![],
+            },
+        );
+        let status = match unlocked_state
+            .channel_manager
+            .send_spontaneous_payment_with_retry(
+                Some(payment_preimage),
+                RecipientOnionFields::spontaneous_empty(),
+                payment_id,
+                route_params,
+                Retry::Timeout(Duration::from_secs(10)),
+            ) {
+            Ok(_payment_hash) => {
+                unlocked_state.outbound_reservations.submitted(&payment_id);
+                tracing::info!(
+                    "EVENT: initiated sending {} msats to {}",
+                    amt_msat,
+                    dest_pubkey
+                );
+                HTLCStatus::Pending
             }
-            RgbLibError::RecipientIDAlreadyUsed => APIError::RecipientIDAlreadyUsed,
-            RgbLibError::OutputBelowDustLimit => APIError::OutputBelowDustLimit,
-            _ => APIError::Unexpected,
+            Err(e) => {
+                tracing::error!("ERROR: failed to send payment: {:?}", e);
+                unlocked_state.fail_outbound_payment(
+                    &state.static_state,
+                    payment_id,
+                    FailureReason::from(&e),
+                    None,
+                );
+                HTLCStatus::Failed
+            }
+        };
+
+        Ok(Json(KeysendResponse {
+            payment_hash: hex_str(&payment_hash.0),
+            payment_preimage: hex_str(&payment_preimage.0),
+            amt_msat,
+            status,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn latency(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LatencyResponse>, APIError> {
+    let tracker = state.static_state.latency_tracker.as_ref();
+    Ok(Json(LatencyResponse {
+        enabled: tracker.is_some(),
+        window: LATENCY_WINDOW,
+        routes: tracker.map(|t| t.latencies()).unwrap_or_default(),
+    }))
+}
+
+pub(crate) async fn list_addresses(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListAddressesResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let static_state = state.static_state.clone();
+    let activity =
+        tokio::task::spawn_blocking(move || unlocked_state.wallet_address_activity(&static_state))
+            .await
+            .unwrap()?;
+
+    let addresses = activity
+        .into_iter()
+        .map(|a| WalletAddress {
+            derivation_index: a.record.derivation_index,
+            address: a.record.address,
+            issued_at: a.record.issued_at,
+            used: a.used,
+            balance_sat: a.balance_sat,
+        })
+        .collect();
+
+    Ok(Json(ListAddressesResponse { addresses }))
+}
+
+pub(crate) async fn list_assets(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListAssetsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let rgb_assets = unlocked_state.rgb_list_assets()?;
+
+    let mut assets = vec![];
+    for asset in rgb_assets.nia.unwrap() {
+        let registry_status = match &state.static_state.asset_registry {
+            Some(registry) => registry.status(&asset.asset_id, &asset.ticker).await,
+            None => RegistryStatus::Unverified,
+        };
+        assets.push(Asset {
+            asset_id: asset.asset_id,
+            ticker: asset.ticker,
+            name: asset.name,
+            precision: asset.precision,
+            issued_supply: asset.issued_supply,
+            timestamp: asset.timestamp,
+            registry_status,
+        })
+    }
+
+    Ok(Json(ListAssetsResponse { assets }))
+}
+
+pub(crate) async fn list_bans(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListBansResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let bans = unlocked_state
+        .peer_limiter
+        .bans()
+        .into_iter()
+        .map(|(peer_pubkey, ban)| Ban {
+            peer_pubkey: peer_pubkey.to_string(),
+            address: ban.address.map(|a| a.to_string()),
+            reason: ban.reason,
+            banned_at: ban.banned_at,
+            expires_at: ban.expires_at,
+        })
+        .collect();
+
+    Ok(Json(ListBansResponse { bans }))
+}
+
+pub(crate) async fn list_channels(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(params), _): WithRejection<Query<ListChannelsParams>, APIError>,
+) -> Result<Json<ListChannelsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let all_channels = unlocked_state.channel_manager.list_channels();
+    // channels opened before creation times were recorded sort as created when first listed
+    let channel_ids: Vec<String> = all_channels.iter().map(|c| c.channel_id.to_hex()).collect();
+    let channel_ids: Vec<&str> = channel_ids.iter().map(String::as_str).collect();
+    unlocked_state
+        .database
+        .add_channel_creations(&channel_ids, get_current_timestamp())?;
+    let channel_creations = unlocked_state.database.channel_creations()?;
+
+    let asset_precisions = unlocked_state.rgb_get_asset_precisions()?;
+    let channel_fees = unlocked_state.database.channel_fees()?;
+    let mut channel_notes = unlocked_state.database.channel_notes()?;
+    let peer_connections = unlocked_state.database.peer_connections()?;
+    let connected_peers: HashSet<PublicKey> = unlocked_state
+        .peer_manager
+        .get_peer_node_ids()
+        .into_iter()
+        .map(|(pubkey, _)| pubkey)
+        .collect();
+    let offline_threshold =
+        get_current_timestamp().saturating_sub(state.static_state.peer_offline_threshold_secs);
+
+    let mut channels = vec![];
+    for chan_info in all_channels {
+        let mut channel = Channel {
+            channel_id: chan_info.channel_id.to_hex(),
+            peer_pubkey: hex_str(&chan_info.counterparty.node_id.serialize()),
+            ready: chan_info.is_channel_ready,
+            capacity_sat: chan_info.channel_value_satoshis,
+            local_balance_msat: chan_info.balance_msat,
+            is_usable: chan_info.is_usable,
+            public: chan_info.is_public,
+            confirmations_required: chan_info.confirmations_required,
+            confirmations: chan_info.confirmations,
+            htlc_min_sendable_msat: htlc_min_sendable_msat(&chan_info),
+            htlc_min_receivable_msat: htlc_min_receivable_msat(&chan_info),
+            commitment_type: chan_info.channel_type.as_ref().map(CommitmentType::from),
+            created_at: channel_creations
+                .get(&chan_info.channel_id.to_hex())
+                .copied()
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+
+        if let Some(funding_txo) = chan_info.funding_txo {
+            let funding_txid = funding_txo.txid.to_string();
+            channel.consignment_endpoint = unlocked_state
+                .database
+                .consignment_endpoint(&funding_txid)?;
+            channel.funding_txid = Some(funding_txid);
+        }
+
+        if let Some(node_info) = unlocked_state
+            .network_graph
+            .read_only()
+            .nodes()
+            .get(&NodeId::from_pubkey(&chan_info.counterparty.node_id))
+        {
+            if let Some(announcement) = &node_info.announcement_info {
+                channel.peer_alias = Some(announcement.alias.to_string());
+            }
+        }
+
+        if let Some(id) = chan_info.short_channel_id {
+            channel.short_channel_id = Some(id);
+            if chan_info.is_public {
+                channel.announcement_status = Some(unlocked_state.announcements.channel_status(id));
+            }
+        }
+
+        if chan_info.is_usable {
+            channel.outbound_balance_msat = Some(chan_info.outbound_capacity_msat);
+            channel.inbound_balance_msat = Some(chan_info.inbound_capacity_msat);
+        }
+
+        let ldk_data_dir_path = PathBuf::from(state.static_state.ldk_data_dir.clone());
+        let info_file_path =
+            get_rgb_channel_info_path(&chan_info.channel_id.to_hex(), &ldk_data_dir_path, false);
+        if info_file_path.exists() {
+            let rgb_info = parse_rgb_channel_info(&info_file_path);
+            let asset_id = rgb_info.contract_id.to_string();
+            channel.asset_precision = asset_precisions.get(&asset_id).copied();
+            channel.asset_id = Some(asset_id);
+            channel.asset_local_amount = Some(rgb_info.local_rgb_amount);
+            channel.asset_remote_amount = Some(rgb_info.remote_rgb_amount);
+            channel.htlc_min_asset_amount = Some(HTLC_MIN_ASSET_AMOUNT);
+        };
+
+        if let Some(fees) = channel_fees.get(&channel.channel_id) {
+            channel.funding_fee_sat = fees.funding_sat;
+            channel.close_fee_sat = fees.close_sat;
+            channel.force_close_fee_sat = fees.force_close_sat;
+        }
+        channel.note = channel_notes.remove(&channel.channel_id);
+        if !connected_peers.contains(&chan_info.counterparty.node_id) {
+            channel.peer_offline_since = peer_connections
+                .get(&channel.peer_pubkey)
+                .and_then(|c| c.last_seen)
+                .filter(|last_seen| *last_seen <= offline_threshold);
         }
+
+        channels.push(channel);
     }
+
+    let (channels, next_cursor) = paginate(
+        channels,
+        |c| Cursor::new(c.created_at, &c.channel_id),
+        params.after.as_deref(),
+        params.limit,
+    )?;
+
+    Ok(Json(ListChannelsResponse {
+        channels,
+        next_cursor,
+    }))
 }
 
-pub(crate) async fn address(
+pub(crate) async fn list_closed_channels(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<AddressResponse>, APIError> {
+) -> Result<Json<ListClosedChannelsResponse>, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let address = unlocked_state.rgb_get_address()?;
+    let channel_fees = unlocked_state.database.channel_fees()?;
+    let mut channel_notes = unlocked_state.database.channel_notes()?;
+    let negotiated_close_fee_rates = unlocked_state.database.negotiated_close_fee_rates()?;
+    let unclaimed_balances = unlocked_state.unclaimed_balances();
+
+    let channels = unlocked_state
+        .database
+        .closed_channels()?
+        .into_iter()
+        .map(|record| {
+            let fees = channel_fees
+                .get(&record.channel_id)
+                .copied()
+                .unwrap_or_default();
+            let close_fee_rate = unlocked_state
+                .database
+                .channel_fee_rate(&record.channel_id, ChannelFeeKind::Close)?;
+            Ok(ClosedChannel {
+                note: channel_notes.remove(&record.channel_id),
+                channel_id: record.channel_id,
+                funding_txid: record.funding_txid,
+                peer_pubkey: record.peer_pubkey,
+                capacity_sat: record.capacity_sat,
+                asset_id: record.asset_id,
+                asset_local_amount: record.asset_local_amount,
+                asset_remote_amount: record.asset_remote_amount,
+                reason: record.reason,
+                closed_at: record.closed_at,
+                funding_fee_sat: fees.funding_sat,
+                close_fee_sat: fees.close_sat,
+                force_close_fee_sat: fees.force_close_sat,
+                close_fee_rate_sat_vb: close_fee_rate.map(|f| f.fee_rate_sat_vb),
+                close_target_conf_blocks: close_fee_rate.and_then(|f| f.target_conf_blocks),
+                negotiated_close_fee_rate_sat_vb: negotiated_close_fee_rates
+                    .get(&record.channel_id)
+                    .copied(),
+                unclaimed_sat: unclaimed_balances
+                    .get(&record.channel_id)
+                    .copied()
+                    .unwrap_or(0),
+            })
+        })
+        .collect::<Result<_, APIError>>()?;
 
-    Ok(Json(AddressResponse { address }))
+    Ok(Json(ListClosedChannelsResponse { channels }))
 }
 
-pub(crate) async fn asset_balance(
+pub(crate) async fn list_htlcs(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<AssetBalanceRequest>, APIError>,
-) -> Result<Json<AssetBalanceResponse>, APIError> {
+) -> Result<Json<ListHtlcsResponse>, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let contract_id = ContractId::from_str(&payload.asset_id)
-        .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
-
-    let balance = unlocked_state.rgb_get_asset_balance(contract_id)?;
+    let cur_height = unlocked_state.channel_manager.current_best_block().height();
+    let ldk_data_dir_path = Path::new(&state.static_state.ldk_data_dir);
 
-    let ldk_data_dir_path = PathBuf::from(state.static_state.ldk_data_dir.clone());
-    let mut offchain_outbound = 0;
-    let mut offchain_inbound = 0;
-    for chan_info in unlocked_state.channel_manager.list_channels() {
-        let info_file_path =
-            get_rgb_channel_info_path(&chan_info.channel_id.to_hex(), &ldk_data_dir_path, false);
-        if !info_file_path.exists() {
-            continue;
-        }
-        let rgb_info = parse_rgb_channel_info(&info_file_path);
-        if rgb_info.contract_id == contract_id {
-            offchain_outbound += rgb_info.local_rgb_amount;
-            offchain_inbound += rgb_info.remote_rgb_amount;
-        }
+    let mut htlcs = vec![];
+    for htlc in unlocked_state.pending_htlcs() {
+        let rgb_payment_info_path =
+            get_rgb_payment_info_path(&htlc.payment_hash, ldk_data_dir_path);
+        let (asset_amount, asset_id) = if rgb_payment_info_path.exists() {
+            let rgb_payment_info = parse_rgb_payment_info(&rgb_payment_info_path);
+            (
+                Some(rgb_payment_info.amount),
+                Some(rgb_payment_info.contract_id.to_string()),
+            )
+        } else {
+            (None, None)
+        };
+        let blocks_to_force_close = htlc.force_close_height.saturating_sub(cur_height);
+        htlcs.push(Htlc {
+            channel_id: htlc.channel_id.to_hex(),
+            peer_pubkey: hex_str(&htlc.counterparty_node_id.serialize()),
+            inbound: htlc.inbound,
+            amt_msat: htlc.amt_msat,
+            asset_id,
+            asset_amount,
+            payment_hash: hex_str(&htlc.payment_hash.0),
+            expiry_height: htlc.expiry_height,
+            blocks_to_force_close,
+            expiry_warning: blocks_to_force_close < HTLC_EXPIRY_WARNING_BLOCKS,
+        })
     }
 
-    Ok(Json(AssetBalanceResponse {
-        settled: balance.settled,
-        future: balance.future,
-        spendable: balance.spendable,
-        offchain_outbound,
-        offchain_inbound,
-    }))
+    Ok(Json(ListHtlcsResponse { htlcs }))
 }
 
-pub(crate) async fn backup(
+pub(crate) async fn list_payments(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<BackupRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
-        let _unlocked_state = state.check_locked().await?;
+    WithRejection(Query(params), _): WithRejection<Query<ListPaymentsParams>, APIError>,
+) -> Result<Json<ListPaymentsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let _mnemonic =
-            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
+    let ldk_data_dir_path = Path::new(&state.static_state.ldk_data_dir);
+    let asset_precisions = unlocked_state.rgb_get_asset_precisions()?;
+
+    // one more payment than the page holds tells whether there's a next page
+    let after = page_start(params.after.as_deref(), params.limit)?;
+    let mut payments: Vec<Payment> = unlocked_state
+        .database
+        .payments_page(
+            params.label.as_deref(),
+            params.status,
+            after.as_ref(),
+            params.limit.map(|limit| limit.saturating_add(1)),
+        )?
+        .iter()
+        .map(|(id, inbound, payment_info)| {
+            payment_info.to_payment(
+                &PaymentHash(*id),
+                *inbound,
+                ldk_data_dir_path,
+                &asset_precisions,
+            )
+        })
+        .collect();
+    let next_cursor = page_end(
+        &mut payments,
+        |p| Cursor::new(p.created_at, &p.payment_hash),
+        params.limit,
+    );
+
+    Ok(Json(ListPaymentsResponse {
+        payments,
+        next_cursor,
+    }))
+}
 
-        do_backup(
-            PathBuf::from(&state.static_state.storage_dir_path),
-            &payload.backup_path,
-            &payload.password,
-        )?;
+pub(crate) async fn list_inbound_channel_requests(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListInboundChannelRequestsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        Ok(Json(EmptyResponse {}))
-    })
-    .await
+    Ok(Json(ListInboundChannelRequestsResponse {
+        requests: unlocked_state.lsp.requests(),
+    }))
 }
 
-pub(crate) async fn btc_balance(
+pub(crate) async fn list_swaps(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<BtcBalanceResponse>, APIError> {
+) -> Result<Json<ListSwapsResponse>, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let btc_balance = unlocked_state.rgb_get_btc_balance()?;
+    Ok(Json(ListSwapsResponse {
+        swaps: unlocked_state.swaps.list(),
+    }))
+}
 
-    let vanilla = BtcBalance {
-        settled: btc_balance.vanilla.settled,
-        future: btc_balance.vanilla.future,
-        spendable: btc_balance.vanilla.spendable,
-    };
+pub(crate) async fn list_sweeps(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListSweepsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let colored = BtcBalance {
-        settled: btc_balance.colored.settled,
-        future: btc_balance.colored.future,
-        spendable: btc_balance.colored.spendable,
-    };
+    let sweeps = unlocked_state
+        .database
+        .sweeps()?
+        .into_iter()
+        .map(|record| Sweep {
+            txid: record.txid,
+            amount_sat: record.amount_sat,
+            asset_id: record.asset_id,
+            asset_amount: record.asset_amount,
+            created_at: record.created_at,
+        })
+        .collect();
 
-    Ok(Json(BtcBalanceResponse { vanilla, colored }))
+    Ok(Json(ListSweepsResponse {
+        unswept_outputs: unswept_outputs(Path::new(&state.static_state.ldk_data_dir)),
+        sweeps,
+    }))
 }
 
-pub(crate) async fn change_password(
+pub(crate) async fn list_peers(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ChangePasswordRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
-        let _unlocked_state = state.check_locked().await?;
+    WithRejection(Query(params), _): WithRejection<Query<ListPeersParams>, APIError>,
+) -> Result<Json<ListPeersResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        check_password_strength(payload.new_password.clone())?;
+    let peer_connections = unlocked_state.database.peer_connections()?;
+    let exposure_limits = unlocked_state.database.peer_exposure_limits()?;
+    let mut exposures = unlocked_state.peer_exposures(&state.static_state.ldk_data_dir);
+    let counters = state.static_state.counters();
 
-        let mnemonic =
-            check_password_validity(&payload.old_password, &state.static_state.storage_dir_path)?;
+    let mut peers = vec![];
+    for (pubkey, _) in unlocked_state.peer_manager.get_peer_node_ids() {
+        let connection = peer_connections.get(&pubkey.to_string());
+        let limits = exposure_limits
+            .get(&pubkey.to_string())
+            .cloned()
+            .unwrap_or_default();
+        let exposure = exposures.remove(&pubkey).unwrap_or_default();
+        let mut assets: BTreeMap<String, AssetExposure> = BTreeMap::new();
+        for (asset_id, amount) in exposure.asset_amounts {
+            assets.insert(
+                asset_id.clone(),
+                AssetExposure {
+                    asset_id,
+                    amount,
+                    max_amount: None,
+                },
+            );
+        }
+        for (asset_id, max_amount) in limits.max_asset_amounts {
+            assets
+                .entry(asset_id.clone())
+                .or_insert(AssetExposure {
+                    asset_id,
+                    amount: 0,
+                    max_amount: None,
+                })
+                .max_amount = Some(max_amount);
+        }
+        peers.push(Peer {
+            pubkey: pubkey.to_string(),
+            rgb_support: unlocked_state
+                .peer_message_handler
+                .supports_rgb_channels(&pubkey),
+            // the connection may not have been recorded yet
+            connected_since: connection
+                .and_then(|c| c.connected_since)
+                .unwrap_or_else(get_current_timestamp),
+            last_seen: connection.and_then(|c| c.last_seen),
+            disconnect_count: connection.map_or(0, |c| c.disconnect_count),
+            exposure: PeerExposure {
+                msat: exposure.msat,
+                max_msat: limits.max_msat,
+                assets: assets.into_values().collect(),
+                violations: counters
+                    .get(&format!("{EXPOSURE_VIOLATION_COUNTER}:{pubkey}"))
+                    .copied()
+                    .unwrap_or(0),
+            },
+        })
+    }
 
-        encrypt_and_save_mnemonic(
-            payload.new_password,
-            mnemonic.to_string(),
-            get_mnemonic_path(&state.static_state.storage_dir_path),
-        )?;
+    let (peers, next_cursor) = paginate(
+        peers,
+        |p| Cursor::new(p.connected_since, &p.pubkey),
+        params.after.as_deref(),
+        params.limit,
+    )?;
 
-        Ok(Json(EmptyResponse {}))
-    })
-    .await
+    Ok(Json(ListPeersResponse { peers, next_cursor }))
 }
 
-pub(crate) async fn close_channel(
+pub(crate) async fn list_transactions(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<CloseChannelRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
-        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    WithRejection(Query(params), _): WithRejection<Query<ListTransactionsParams>, APIError>,
+) -> Result<Json<ListTransactionsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let channel_id_vec = hex_str_to_vec(&payload.channel_id);
-        if channel_id_vec.is_none() || channel_id_vec.as_ref().unwrap().len() != 32 {
-            return Err(APIError::InvalidChannelID);
+    let channel_txs = unlocked_state.database.channel_txs()?;
+    let watched_txs = unlocked_state.database.watched_txs()?;
+
+    let mut transactions = vec![];
+    for tx in unlocked_state.rgb_list_transactions()? {
+        let watched_tx = watched_txs
+            .get(&tx.txid)
+            .filter(|_| tx.confirmation_time.is_none());
+        if params.watched_only == Some(true) && watched_tx.is_none() {
+            continue;
+        }
+        let height = tx.confirmation_time.as_ref().map(|ct| ct.height);
+        // unconfirmed transactions are not in any height range
+        if params
+            .min_height
+            .is_some_and(|min| height.map_or(true, |h| h < min))
+            || params
+                .max_height
+                .is_some_and(|max| height.map_or(true, |h| h > max))
+        {
+            continue;
         }
-        let mut channel_id = [0; 32];
-        channel_id.copy_from_slice(&channel_id_vec.unwrap());
 
-        let peer_pubkey_vec = match hex_str_to_vec(&payload.peer_pubkey) {
-            Some(peer_pubkey_vec) => peer_pubkey_vec,
-            None => return Err(APIError::InvalidPubkey),
+        // rgb-lib only knows how the wallet built a transaction, the node's records tell which
+        // ones belong to channels
+        let channel_tx = channel_txs.get(&tx.txid);
+        let purpose = match (channel_tx.map(|c| c.kind), &tx.transaction_type) {
+            (Some(ChannelFeeKind::Funding), _) => TransactionPurpose::ChannelOpen,
+            (Some(ChannelFeeKind::Close), _) => TransactionPurpose::ChannelClose,
+            (Some(ChannelFeeKind::ForceClose), _) => TransactionPurpose::ChannelForceClose,
+            (None, rgb_lib::TransactionType::RgbSend) => TransactionPurpose::AssetSend,
+            (None, rgb_lib::TransactionType::Drain) => TransactionPurpose::Drain,
+            (None, rgb_lib::TransactionType::CreateUtxos) => TransactionPurpose::UtxoCreation,
+            (None, rgb_lib::TransactionType::User) => TransactionPurpose::BtcTransfer,
         };
-        let peer_pubkey = match PublicKey::from_slice(&peer_pubkey_vec) {
-            Ok(peer_pubkey) => peer_pubkey,
-            Err(_) => return Err(APIError::InvalidPubkey),
+        let net_amount_sat = tx.received as i64 - tx.sent as i64;
+
+        transactions.push(Transaction {
+            transaction_type: match tx.transaction_type {
+                rgb_lib::TransactionType::RgbSend => TransactionType::RgbSend,
+                rgb_lib::TransactionType::Drain => TransactionType::Drain,
+                rgb_lib::TransactionType::CreateUtxos => TransactionType::CreateUtxos,
+                rgb_lib::TransactionType::User => TransactionType::User,
+            },
+            txid: tx.txid,
+            received: tx.received,
+            sent: tx.sent,
+            fee: tx.fee,
+            confirmation_time: tx.confirmation_time.map(|ct| BlockTime {
+                height: ct.height,
+                timestamp: ct.timestamp,
+            }),
+            direction: if net_amount_sat > 0 {
+                TransactionDirection::Incoming
+            } else {
+                TransactionDirection::Outgoing
+            },
+            net_amount_sat,
+            purpose,
+            channel_id: channel_tx.map(|c| c.channel_id.clone()),
+            rebroadcasts: watched_tx.map(|w| w.rebroadcasts),
+        })
+    }
+
+    // newest first, unconfirmed ones at the top
+    transactions.sort_by(|a, b| {
+        let height = |t: &Transaction| {
+            t.confirmation_time
+                .as_ref()
+                .map_or(u32::MAX, |ct| ct.height)
         };
+        height(b).cmp(&height(a)).then_with(|| a.txid.cmp(&b.txid))
+    });
+    let transactions = transactions
+        .into_iter()
+        .skip(params.offset.unwrap_or(0))
+        .take(params.limit.unwrap_or(usize::MAX))
+        .collect();
 
-        if payload.force {
-            match unlocked_state
-                .channel_manager
-                .force_close_broadcasting_latest_txn(&ChannelId(channel_id), &peer_pubkey)
-            {
-                Ok(()) => tracing::info!("EVENT: initiating channel force-close"),
-                Err(e) => return Err(APIError::FailedClosingChannel(format!("{:?}", e))),
-            }
-        } else {
-            match unlocked_state
-                .channel_manager
-                .close_channel(&ChannelId(channel_id), &peer_pubkey)
-            {
-                Ok(()) => tracing::info!("EVENT: initiating channel close"),
-                Err(e) => return Err(APIError::FailedClosingChannel(format!("{:?}", e))),
-            }
-        }
+    Ok(Json(ListTransactionsResponse { transactions }))
+}
 
-        Ok(Json(EmptyResponse {}))
-    })
-    .await
+pub(crate) async fn list_transfers(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ListTransfersRequest>, APIError>,
+) -> Result<Json<ListTransfersResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let rgb_invoice_labels = unlocked_state.database.rgb_invoice_labels()?.labels;
+    let failed_incoming = unlocked_state.database.failed_incoming_transfers()?;
+    let reorged_txids = unlocked_state.chain_reorgs.unconfirmed_txids();
+    let mut transfers = vec![];
+    for transfer in unlocked_state.rgb_list_transfers(payload.asset_id)? {
+        let label = transfer
+            .recipient_id
+            .as_ref()
+            .and_then(|r| rgb_invoice_labels.get(r).cloned());
+        // the wallet keeps waiting for replaced anchoring transactions, the node failed them
+        let failure_reason = match transfer.status {
+            rgb_lib::TransferStatus::WaitingConfirmations | rgb_lib::TransferStatus::Failed => {
+                transfer
+                    .recipient_id
+                    .as_ref()
+                    .and_then(|r| failed_incoming.get(r).cloned())
+            }
+            _ => None,
+        };
+        // the wallet doesn't notice when a reorg disconnects the anchoring transaction
+        let reorged = transfer
+            .txid
+            .as_ref()
+            .is_some_and(|t| reorged_txids.contains(t));
+        transfers.push(Transfer {
+            idx: transfer.idx,
+            created_at: transfer.created_at,
+            updated_at: transfer.updated_at,
+            status: match transfer.status {
+                _ if failure_reason.is_some() => TransferStatus::Failed,
+                rgb_lib::TransferStatus::Settled if reorged => TransferStatus::WaitingConfirmations,
+                rgb_lib::TransferStatus::WaitingCounterparty => TransferStatus::WaitingCounterparty,
+                rgb_lib::TransferStatus::WaitingConfirmations => {
+                    TransferStatus::WaitingConfirmations
+                }
+                rgb_lib::TransferStatus::Settled => TransferStatus::Settled,
+                rgb_lib::TransferStatus::Failed => TransferStatus::Failed,
+            },
+            amount: transfer.amount,
+            kind: match transfer.kind {
+                rgb_lib::TransferKind::Issuance => TransferKind::Issuance,
+                rgb_lib::TransferKind::ReceiveBlind => TransferKind::ReceiveBlind,
+                rgb_lib::TransferKind::ReceiveWitness => TransferKind::ReceiveWitness,
+                rgb_lib::TransferKind::Send => TransferKind::Send,
+            },
+            txid: transfer.txid,
+            recipient_id: transfer.recipient_id,
+            receive_utxo: transfer.receive_utxo.map(|u| u.to_string()),
+            change_utxo: transfer.change_utxo.map(|u| u.to_string()),
+            expiration: transfer.expiration,
+            transport_endpoints: transfer
+                .transport_endpoints
+                .iter()
+                .map(|tte| TransferTransportEndpoint {
+                    endpoint: tte.endpoint.clone(),
+                    transport_type: match tte.transport_type {
+                        rgb_lib::TransportType::JsonRpc => TransportType::JsonRpc,
+                    },
+                    used: tte.used,
+                })
+                .collect(),
+            label,
+            failure_reason,
+        })
+    }
+    Ok(Json(ListTransfersResponse { transfers }))
 }
 
-pub(crate) async fn connect_peer(
+pub(crate) async fn list_unspents(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ConnectPeerRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
-        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    WithRejection(Query(params), _): WithRejection<Query<ListUnspentsParams>, APIError>,
+) -> Result<Json<ListUnspentsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let reorged_txids = unlocked_state.chain_reorgs.unconfirmed_txids();
+    unlocked_state.release_utxo_reservations()?;
+    let reserved = unlocked_state.reserved_utxos()?;
+    let mut unspents = vec![];
+    for unspent in unlocked_state.rgb_list_unspents()? {
+        let reorged = reorged_txids.contains(&unspent.utxo.outpoint.txid);
+        if params
+            .colorable
+            .is_some_and(|colorable| colorable != unspent.utxo.colorable)
+        {
+            continue;
+        }
+        unspents.push(Unspent {
+            utxo: Utxo {
+                outpoint: unspent.utxo.outpoint.to_string(),
+                btc_amount: unspent.utxo.btc_amount,
+                colorable: unspent.utxo.colorable,
+            },
+            rgb_allocations: unspent
+                .rgb_allocations
+                .iter()
+                .map(|a| RgbAllocation {
+                    asset_id: a.asset_id.clone(),
+                    amount: a.amount,
+                    settled: a.settled && !reorged,
+                })
+                .collect(),
+            reserved_by: reserved.reserved_by(&unspent.utxo.outpoint.to_string()),
+        })
+    }
+    Ok(Json(ListUnspentsResponse { unspents }))
+}
 
-        let (peer_pubkey, peer_addr) = parse_peer_info(payload.peer_pubkey_and_addr.to_string())?;
+pub(crate) async fn list_webhooks(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListWebhooksResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        connect_peer_if_necessary(peer_pubkey, peer_addr, unlocked_state.peer_manager.clone())
-            .await?;
+    let pending_deliveries = unlocked_state.webhooks.pending_deliveries();
+    let webhooks = unlocked_state
+        .database
+        .webhooks()?
+        .into_iter()
+        .map(|record| Webhook {
+            pending_deliveries: pending_deliveries.get(&record.id).copied().unwrap_or(0),
+            id: record.id,
+            url: record.url,
+            events: record.events,
+            created_at: record.created_at,
+            delivered: record.delivered,
+            failed: record.failed,
+            last_attempt_at: record.last_attempt_at,
+            last_delivered_at: record.last_delivered_at,
+            last_error: record.last_error,
+        })
+        .collect();
 
-        Ok(Json(EmptyResponse {}))
-    })
-    .await
+    Ok(Json(ListWebhooksResponse { webhooks }))
 }
 
-pub(crate) async fn create_utxos(
+pub(crate) async fn ln_invoice(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<CreateUtxosRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<LNInvoiceRequest>, APIError>,
+) -> Result<Json<LNInvoiceResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        unlocked_state.rgb_create_utxos(
-            payload.up_to,
-            payload.num.unwrap_or(UTXO_NUM),
-            UTXO_SIZE_SAT,
-            FEE_RATE,
-        )?;
-        tracing::debug!("UTXO creation complete");
+        state.static_state.check_writable()?;
 
-        Ok(Json(EmptyResponse {}))
-    })
-    .await
-}
+        let contract_id = if let Some(asset_id) = payload.asset_id {
+            Some(ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?)
+        } else {
+            None
+        };
+        let amt_msat = payload.amt_msat.map(|a| a.0);
+        let asset_amount = payload.asset_amount.map(|a| a.0);
+        if let (Some(contract_id), Some(asset_amount)) = (contract_id, asset_amount) {
+            check_asset_amount(&unlocked_state, contract_id, asset_amount)?;
+        }
 
-pub(crate) async fn decode_ln_invoice(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<DecodeLNInvoiceRequest>, APIError>,
-) -> Result<Json<DecodeLNInvoiceResponse>, APIError> {
-    let _unlocked_app_state = state.get_unlocked_app_state();
+        check_label(&payload.label)?;
 
-    let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
-        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
-        Ok(v) => v,
-    };
+        if let (Some(contract_id), Some(amt_msat)) = (contract_id, amt_msat) {
+            check_htlc_amounts(
+                &unlocked_state,
+                &state.static_state.ldk_data_dir,
+                contract_id,
+                amt_msat,
+                asset_amount,
+                false,
+            )?;
+        }
+        if amt_msat.is_some() && amt_msat.unwrap() < INVOICE_MIN_MSAT {
+            return Err(APIError::InvalidAmount(format!(
+                "amt_msat cannot be less than {INVOICE_MIN_MSAT}"
+            )));
+        }
+        let min_final_cltv_expiry_delta = payload
+            .min_final_cltv_expiry_delta
+            .unwrap_or(MIN_FINAL_CLTV_EXPIRY_DELTA);
+        if min_final_cltv_expiry_delta < MIN_FINAL_CLTV_EXPIRY_DELTA {
+            return Err(APIError::InvalidCltvExpiryDelta(format!(
+                "cannot be lower than {MIN_FINAL_CLTV_EXPIRY_DELTA}"
+            )));
+        }
 
-    Ok(Json(DecodeLNInvoiceResponse {
-        amt_msat: invoice.amount_milli_satoshis(),
-        expiry_sec: invoice.expiry_time().as_secs(),
-        timestamp: invoice
-            .timestamp()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-        asset_id: invoice.rgb_contract_id().map(|c| c.to_string()),
-        asset_amount: invoice.rgb_amount(),
-        payment_hash: hex_str(&invoice.payment_hash().into_inner()),
-        payment_secret: hex_str(&invoice.payment_secret().0),
-        payee_pubkey: invoice.payee_pub_key().map(|p| p.to_string()),
-        network: invoice.network().into(),
-    }))
-}
+        let payer_requirements = match payload.require_payer_pubkey {
+            Some(pubkey) => Some(PayerRequirements {
+                pubkey: Some(
+                    bitcoin::secp256k1::PublicKey::from_str(&pubkey)
+                        .map_err(|_| APIError::InvalidPubkey)?,
+                ),
+                note: payload.require_payer_note,
+            }),
+            None if payload.require_payer_note => Some(PayerRequirements {
+                pubkey: None,
+                note: true,
+            }),
+            None => None,
+        };
 
-pub(crate) async fn decode_rgb_invoice(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<DecodeRGBInvoiceRequest>, APIError>,
-) -> Result<Json<DecodeRGBInvoiceResponse>, APIError> {
-    let _unlocked_app_state = state.get_unlocked_app_state();
+        if !payload.allow_insufficient_inbound {
+            let (inbound_msat, inbound_asset) =
+                unlocked_state.inbound_liquidity(&state.static_state.ldk_data_dir, contract_id);
+            if let (Some(contract_id), Some(asset_amount)) = (contract_id, asset_amount) {
+                if asset_amount > inbound_asset {
+                    return Err(APIError::InsufficientInboundLiquidity(format!(
+                        "{inbound_asset} of asset {contract_id}"
+                    )));
+                }
+            }
+            if let Some(amt_msat) = amt_msat {
+                if amt_msat > inbound_msat {
+                    return Err(APIError::InsufficientInboundLiquidity(format!(
+                        "{inbound_msat} msat"
+                    )));
+                }
+            }
+        }
 
-    let invoice_data = match RgbLibInvoice::new(payload.invoice) {
-        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
-        Ok(v) => v.invoice_data(),
-    };
+        // payers without a channel can send the asset on-chain instead, as a separate transfer
+        let rgb_invoice = match contract_id {
+            Some(contract_id) if payload.unified => {
+                let receive_data = unlocked_state.rgb_blind_receive(
+                    Some(contract_id.to_string()),
+                    None,
+                    state.static_state.proxy_endpoints.clone(),
+                    state.static_state.default_min_confirmations,
+                )?;
+                if let Some(label) = payload.label.clone() {
+                    unlocked_state.set_rgb_invoice_label(&receive_data.recipient_id, &label);
+                }
+                Some(receive_data.invoice)
+            }
+            _ => None,
+        };
 
-    Ok(Json(DecodeRGBInvoiceResponse {
-        recipient_id: invoice_data.recipient_id,
-        asset_iface: invoice_data.asset_iface.map(|i| match i {
-            rgb_lib::wallet::AssetIface::RGB20 => AssetIface::RGB20,
-            rgb_lib::wallet::AssetIface::RGB21 => AssetIface::RGB21,
-            rgb_lib::wallet::AssetIface::RGB25 => AssetIface::RGB25,
-        }),
-        asset_id: invoice_data.asset_id,
-        amount: invoice_data.amount,
-        network: invoice_data.network.map(|n| n.into()),
-        expiration_timestamp: invoice_data.expiration_timestamp,
-        transport_endpoints: invoice_data.transport_endpoints,
-    }))
+        let currency = match state.static_state.network {
+            Network::Bitcoin => Currency::Bitcoin,
+            Network::Testnet => Currency::BitcoinTestnet,
+            Network::Regtest => Currency::Regtest,
+            Network::Signet => Currency::Signet,
+        };
+        // the delta is encoded in the payment secret, LDK refuses the HTLCs expiring sooner
+        let invoice = match create_invoice_from_channelmanager(
+            &unlocked_state.channel_manager,
+            unlocked_state.keys_manager.clone(),
+            state.static_state.logger.clone(),
+            currency,
+            amt_msat,
+            "ldk-tutorial-node".to_string(),
+            payload.expiry_sec,
+            Some(min_final_cltv_expiry_delta),
+            contract_id,
+            asset_amount,
+        ) {
+            Ok(inv) => inv,
+            Err(e) => return Err(APIError::FailedInvoiceCreation(e.to_string())),
+        };
+
+        let payment_hash = PaymentHash((*invoice.payment_hash()).into_inner());
+        unlocked_state.add_inbound_payment(
+            payment_hash,
+            PaymentInfo {
+                preimage: None,
+                secret: Some(*invoice.payment_secret()),
+                status: HTLCStatus::Pending,
+                amt_msat,
+                label: payload.label,
+                created_at: get_current_timestamp(),
+                expires_at: Some(
+                    invoice.duration_since_epoch().as_secs() + invoice.expiry_time().as_secs(),
+                ),
+                failure_reason: None,
+                failing_hop: None,
+                settled_at: None,
+                payer_requirements,
+                payer: None,
+                payee: None,
+                onchain_txid: None,
+                closure: None,
+                hops: vec![],
+            },
+        );
+
+        let invoice = invoice.to_string();
+        let unified = payload
+            .unified
+            .then(|| unified_uri(Some(&invoice), rgb_invoice.as_deref()));
+
+        Ok(Json(LNInvoiceResponse { invoice, unified }))
+    })
+    .await
 }
 
-pub(crate) async fn disconnect_peer(
+pub(crate) async fn lock(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<DisconnectPeerRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
+    tracing::info!("Lock started");
     no_cancel(async move {
-        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
-
-        let peer_pubkey = match bitcoin::secp256k1::PublicKey::from_str(&payload.peer_pubkey) {
-            Ok(pubkey) => pubkey,
-            Err(_e) => return Err(APIError::InvalidPubkey),
-        };
-
-        //check for open channels with peer
-        for channel in unlocked_state.channel_manager.list_channels() {
-            if channel.counterparty.node_id == peer_pubkey {
-                return Err(APIError::FailedPeerDisconnection(s!(
-                    "node has an active channel with this peer, close any channels first"
-                )));
+        match state.check_unlocked().await {
+            Ok(unlocked_state) => {
+                state.update_changing_state(true);
+                drop(unlocked_state);
+            }
+            Err(e) => {
+                state.update_changing_state(false);
+                return Err(e);
             }
         }
 
-        //check the pubkey matches a valid connected peer
-        let peers = unlocked_state.peer_manager.get_peer_node_ids();
-        if !peers.iter().any(|(pk, _)| &peer_pubkey == pk) {
-            return Err(APIError::FailedPeerDisconnection(format!(
-                "Could not find peer {}",
-                peer_pubkey
-            )));
-        }
+        tracing::debug!("Stopping LDK...");
+        stop_ldk(state.clone()).await;
+        tracing::debug!("LDK stopped");
 
-        unlocked_state
-            .peer_manager
-            .disconnect_by_node_id(peer_pubkey);
+        state.update_unlocked_app_state(None).await;
+
+        state.update_ldk_background_services(None);
 
+        state.update_changing_state(false);
+
+        tracing::info!("Lock completed");
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn init(
+#[cfg(feature = "regtest-endpoints")]
+pub(crate) async fn mine_blocks(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<InitRequest>, APIError>,
-) -> Result<Json<InitResponse>, APIError> {
-    no_cancel(async move {
-        let _unlocked_state = state.check_locked().await?;
+    WithRejection(Json(payload), _): WithRejection<Json<MineBlocksRequest>, APIError>,
+) -> Result<Json<MineBlocksResponse>, APIError> {
+    let regtest = state.static_state.regtest()?;
 
-        check_password_strength(payload.password.clone())?;
-
-        let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
-        check_already_initialized(&mnemonic_path)?;
+    let block_hashes = async {
+        regtest.ensure_wallet().await?;
+        regtest.mine(payload.blocks).await
+    }
+    .await
+    .map_err(|e| APIError::FailedRegtestCall(e.to_string()))?;
 
-        let keys = generate_keys(get_bitcoin_network(&state.static_state.network));
+    Ok(Json(MineBlocksResponse {
+        block_hashes: block_hashes.iter().map(|h| h.to_string()).collect(),
+    }))
+}
 
-        let mnemonic = keys.mnemonic;
+pub(crate) async fn network_info(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<NetworkInfoResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        encrypt_and_save_mnemonic(payload.password, mnemonic.clone(), mnemonic_path)?;
+    let best_block = unlocked_state.channel_manager.current_best_block();
 
-        Ok(Json(InitResponse { mnemonic }))
-    })
-    .await
+    Ok(Json(NetworkInfoResponse {
+        network: state.static_state.network.into(),
+        height: best_block.height(),
+    }))
 }
 
-pub(crate) async fn invoice_status(
+pub(crate) async fn node_info(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<InvoiceStatusRequest>, APIError>,
-) -> Result<Json<InvoiceStatusResponse>, APIError> {
+) -> Result<Json<NodeInfoResponse>, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
-        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
-        Ok(v) => v,
-    };
+    let chans = unlocked_state.channel_manager.list_channels();
 
-    let payment_hash = PaymentHash(invoice.payment_hash().into_inner());
-    let status = match unlocked_state.inbound_payments().get(&payment_hash) {
-        Some(v) => match v.status {
-            HTLCStatus::Pending if invoice.is_expired() => InvoiceStatus::Expired,
-            HTLCStatus::Pending => InvoiceStatus::Pending,
-            HTLCStatus::Succeeded => InvoiceStatus::Succeeded,
-            HTLCStatus::Failed => InvoiceStatus::Failed,
-        },
-        None => return Err(APIError::UnknownLNInvoice),
-    };
+    let monitor_mirror_pending_updates = unlocked_state
+        .monitor_mirror
+        .as_ref()
+        .map(|m| m.pending_updates());
+
+    let proxy_status = proxy_url(&preferred_proxy_endpoint(&state.static_state))
+        .and_then(|url| state.static_state.proxy_checks.get(&url));
 
-    Ok(Json(InvoiceStatusResponse { status }))
+    Ok(Json(NodeInfoResponse {
+        pubkey: unlocked_state.channel_manager.get_our_node_id().to_string(),
+        alias: state
+            .static_state
+            .ldk_announced_node_alias
+            .lock()
+            .unwrap()
+            .clone(),
+        color: hex_str(&state.static_state.ldk_announced_node_color),
+        num_channels: chans.len(),
+        num_usable_channels: chans.iter().filter(|c| c.is_usable).count(),
+        local_balance_msat: chans.iter().map(|c| c.balance_msat).sum::<u64>(),
+        num_peers: unlocked_state.peer_manager.get_peer_node_ids().len(),
+        default_min_confirmations: state.static_state.default_min_confirmations,
+        min_allowed_confirmations: state.static_state.min_allowed_confirmations,
+        monitor_mirror_pending_updates,
+        monitor_mirror_lagging: monitor_mirror_pending_updates
+            .map_or(false, |p| p > state.static_state.monitor_mirror_max_lag),
+        onion_message_forwarding: state.static_state.onion_message_forwarding,
+        proxy_reachable: proxy_status.as_ref().map_or(false, |s| s.reachable),
+        proxy_version: proxy_status.as_ref().and_then(|s| s.version.clone()),
+        proxy_protocol_compatible: proxy_status.map_or(false, |s| s.compatible()),
+        persistence_failure: state.static_state.persistence.failure(),
+        num_hot_monitors: unlocked_state.chain_monitor.list_monitors().len(),
+        num_archived_monitors: archived_monitors(Path::new(&state.static_state.ldk_data_dir)),
+        recovery_mode: unlocked_state.recovery_mode,
+        data_dir: active_data_dir(Path::new(&state.static_state.storage_dir_path))?
+            .to_string_lossy()
+            .to_string(),
+        channel_handshake: state.static_state.channel_handshake,
+        node_announcement: unlocked_state.announcements.node_status(),
+    }))
 }
 
-pub(crate) async fn issue_asset(
+pub(crate) async fn open_channel(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetRequest>, APIError>,
-) -> Result<Json<IssueAssetResponse>, APIError> {
-    no_cancel(async move {
+    WithRejection(Json(payload), _): WithRejection<Json<OpenChannelRequest>, APIError>,
+) -> Result<Response, APIError> {
+    let job = new_job(&state, JobKind::OpenChannel, payload.dry_run);
+    run_job(job.clone(), async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let asset = unlocked_state.rgb_issue_asset_nia(
-            payload.ticker,
-            payload.name,
-            payload.precision,
-            payload.amounts,
-        )?;
+        unlocked_state.check_not_recovering()?;
+        if !payload.dry_run {
+            state.static_state.check_writable()?;
+        }
 
-        Ok(Json(IssueAssetResponse {
-            asset_id: asset.asset_id,
-        }))
-    })
-    .await
-}
+        let (peer_pubkey, peer_addr) = parse_peer_info(payload.peer_pubkey_and_addr.to_string())?;
 
-pub(crate) async fn keysend(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<KeysendRequest>, APIError>,
-) -> Result<Json<KeysendResponse>, APIError> {
-    no_cancel(async move {
-        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+        let contract_id = ContractId::from_str(&payload.asset_id)
+            .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
 
-        let dest_pubkey = match hex_str_to_compressed_pubkey(&payload.dest_pubkey) {
-            Some(pk) => pk,
-            None => return Err(APIError::InvalidPubkey),
-        };
+        let capacity_sat = payload.capacity_sat.0;
+        let push_msat = payload.push_msat.0;
+        let asset_amount = payload.asset_amount.0;
+        if capacity_sat < OPENCHANNEL_MIN_SAT {
+            return Err(APIError::InvalidAmount(format!(
+                "Channel amount must be equal or higher than {OPENCHANNEL_MIN_SAT}"
+            )));
+        }
+        if capacity_sat > OPENCHANNEL_MAX_SAT {
+            return Err(APIError::InvalidAmount(format!(
+                "Channel amount must be equal or less than {OPENCHANNEL_MAX_SAT}"
+            )));
+        }
 
-        let amt_msat = payload.amt_msat;
-        if amt_msat < HTLC_MIN_MSAT {
+        let dust_limit_msat = state.static_state.channel_handshake.dust_limit_msat();
+        if push_msat < dust_limit_msat {
             return Err(APIError::InvalidAmount(format!(
-                "amt_msat cannot be less than {HTLC_MIN_MSAT}"
+                "Push amount must be equal or higher than the dust limit ({dust_limit_msat})"
             )));
         }
+        let max_push_msat = max_push_msat(&state.static_state, capacity_sat, payload.with_anchors);
+        if push_msat > max_push_msat {
+            return Err(APIError::PushAmountTooHigh(max_push_msat));
+        }
 
-        let contract_id = ContractId::from_str(&payload.asset_id)
-            .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
+        if asset_amount < OPENCHANNEL_MIN_RGB_AMT {
+            return Err(APIError::InvalidAmount(format!(
+                "Channel RGB amount must be equal or higher than {OPENCHANNEL_MIN_RGB_AMT}"
+            )));
+        }
+        check_asset_amount(&unlocked_state, contract_id, asset_amount)?;
+
+        let min_depth = payload
+            .min_depth
+            .unwrap_or(MIN_CHANNEL_CONFIRMATIONS as u32);
+        if min_depth == 0 || min_depth > MAX_CHANNEL_MIN_DEPTH {
+            return Err(APIError::InvalidMinConfirmations(format!(
+                "channel min depth must be between 1 and {MAX_CHANNEL_MIN_DEPTH}"
+            )));
+        }
 
-        let payment_preimage =
-            PaymentPreimage(unlocked_state.keys_manager.get_secure_random_bytes());
-        let payment_hash_inner = Sha256::hash(&payment_preimage.0[..]).into_inner();
-        let payment_id = PaymentId(payment_hash_inner);
-        let payment_hash = PaymentHash(payment_hash_inner);
+        let fee_rate = choose_fee_rate(
+            &state.static_state,
+            payload.fee_rate_sat_vb,
+            payload.target_conf_blocks,
+        )
+        .await?
+        .unwrap_or(ChannelFeeRate {
+            target_conf_blocks: None,
+            fee_rate_sat_vb: FEE_RATE,
+        });
+
+        let htlc_minimum_msat = payload.htlc_minimum_msat.map_or(HTLC_MIN_MSAT, |m| m.0);
+        if htlc_minimum_msat < HTLC_MIN_MSAT {
+            return Err(APIError::AmountBelowDustLimit(format!(
+                "HTLC minimum must be at least {HTLC_MIN_MSAT} msat"
+            )));
+        }
 
-        write_rgb_payment_info_file(
-            &PathBuf::from(&state.static_state.ldk_data_dir),
-            &payment_hash,
-            contract_id,
-            payload.asset_amount,
-            false,
-        );
+        if payload.dry_run {
+            // the funding transaction sends the channel assets and capacity to the funding output
+            let dry_run = simulate_rgb_send(
+                &unlocked_state,
+                &contract_id.to_string(),
+                asset_amount,
+                &[capacity_sat],
+                fee_rate.fee_rate_sat_vb,
+            )?;
+            // rgb-lib selects the allocations funding the channel itself, as for /sendasset
+            return Ok(OpenChannelResponse {
+                temporary_channel_id: None,
+                dry_run: Some(DryRunResult {
+                    inputs: vec![],
+                    consumed_allocations: vec![],
+                    asset_change: None,
+                    ..dry_run
+                }),
+            });
+        }
+
+        connect_peer_if_necessary(peer_pubkey, peer_addr, unlocked_state.peer_manager.clone())
+            .await?;
+        // vanilla channels are not supported yet, so every channel needs an RGB-aware peer
+        if !unlocked_state
+            .peer_message_handler
+            .supports_rgb_channels(&peer_pubkey)
+        {
+            return Err(APIError::PeerDoesNotSupportRgbChannels(
+                peer_pubkey.to_string(),
+            ));
+        }
 
-        let route_params = RouteParameters::from_payment_params_and_value(
-            PaymentParameters::for_keysend(dest_pubkey, 40, false),
-            amt_msat,
-        );
-        unlocked_state.add_outbound_payment(
-            payment_id,
-            PaymentInfo {
-                preimage: None,
-                secret: None,
-                status: HTLCStatus::Pending,
-                amt_msat: Some(amt_msat),
+        let mut channel_config = ChannelConfig::default();
+        if let Some(fee_base_msat) = payload.fee_base_msat {
+            channel_config.forwarding_fee_base_msat = fee_base_msat;
+        }
+        if let Some(fee_proportional_millionths) = payload.fee_proportional_millionths {
+            channel_config.forwarding_fee_proportional_millionths = fee_proportional_millionths;
+        }
+
+        job.commit()?;
+        let temporary_channel_id = unlocked_state.open_colored_channel(
+            &state.static_state,
+            peer_pubkey,
+            capacity_sat,
+            push_msat,
+            RgbInfo {
+                contract_id,
+                local_rgb_amount: asset_amount,
+                remote_rgb_amount: 0,
             },
+            fee_rate,
+            open_channel_config(
+                payload.public,
+                min_depth,
+                htlc_minimum_msat,
+                payload.with_anchors,
+                channel_config,
+                &state.static_state.channel_handshake,
+            ),
+        )?;
+
+        let peer_data_path = format!(
+            "{}/channel_peer_data",
+            state.static_state.ldk_data_dir.clone()
         );
-        let status = match unlocked_state
-            .channel_manager
-            .send_spontaneous_payment_with_retry(
-                Some(payment_preimage),
-                RecipientOnionFields::spontaneous_empty(),
-                payment_id,
-                route_params,
-                Retry::Timeout(Duration::from_secs(10)),
-            ) {
-            Ok(_payment_hash) => {
-                tracing::info!(
-                    "EVENT: initiated sending {} msats to {}",
-                    amt_msat,
-                    dest_pubkey
-                );
-                HTLCStatus::Pending
-            }
-            Err(e) => {
-                tracing::error!("ERROR: failed to send payment: {:?}", e);
-                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
-                HTLCStatus::Failed
-            }
-        };
+        let _ =
+            disk::persist_channel_peer(Path::new(&peer_data_path), &payload.peer_pubkey_and_addr);
 
-        Ok(Json(KeysendResponse {
-            payment_hash: hex_str(&payment_hash.0),
-            payment_preimage: hex_str(&payment_preimage.0),
-            status,
-        }))
+        Ok(OpenChannelResponse {
+            temporary_channel_id: Some(temporary_channel_id),
+            dry_run: None,
+        })
     })
     .await
 }
 
-pub(crate) async fn list_assets(
+pub(crate) async fn pending_channels(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListAssetsResponse>, APIError> {
+) -> Result<Json<PendingChannelsResponse>, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let rgb_assets = unlocked_state.rgb_list_assets()?;
-
-    let mut assets = vec![];
-    for asset in rgb_assets.nia.unwrap() {
-        assets.push(Asset {
-            asset_id: asset.asset_id,
-            ticker: asset.ticker,
-            name: asset.name,
-            precision: asset.precision,
-            issued_supply: asset.issued_supply,
-            timestamp: asset.timestamp,
-        })
+    let asset_precisions = unlocked_state.rgb_get_asset_precisions()?;
+    let ldk_data_dir_path = PathBuf::from(state.static_state.ldk_data_dir.clone());
+    let mut unsigned_fundings = HashSet::new();
+    let mut open_fee_rates = HashMap::new();
+    for (temporary_channel_id, open) in unlocked_state.pending_channel_opens.lock().unwrap().iter()
+    {
+        open_fee_rates.insert(temporary_channel_id.clone(), open.fee_rate);
+        // outbound channels whose funding the counterparty hasn't signed yet
+        if let Some(channel_id) = &open.channel_id {
+            unsigned_fundings.insert(channel_id.clone());
+            open_fee_rates.insert(channel_id.clone(), open.fee_rate);
+        }
     }
 
-    Ok(Json(ListAssetsResponse { assets }))
-}
-
-pub(crate) async fn list_channels(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<ListChannelsResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
-
     let mut channels = vec![];
     for chan_info in unlocked_state.channel_manager.list_channels() {
-        let mut channel = Channel {
-            channel_id: chan_info.channel_id.to_hex(),
+        if chan_info.is_channel_ready {
+            continue;
+        }
+        let channel_id = chan_info.channel_id.to_hex();
+        let (stage, funding_txid) = match chan_info.funding_txo {
+            None => (PendingChannelStage::Negotiating, None),
+            Some(_) if unsigned_fundings.contains(&channel_id) => {
+                (PendingChannelStage::FundingSigning, None)
+            }
+            Some(funding_txo) => (
+                PendingChannelStage::AwaitingConfirmations,
+                Some(funding_txo.txid.to_string()),
+            ),
+        };
+        // once the funding is signed only the database knows the fee rate it pays
+        let fee_rate = match open_fee_rates.get(&channel_id) {
+            Some(fee_rate) => Some(*fee_rate),
+            None if chan_info.is_outbound => unlocked_state
+                .database
+                .channel_fee_rate(&channel_id, ChannelFeeKind::Funding)?,
+            None => None,
+        };
+        let mut channel = PendingChannel {
+            channel_id,
             peer_pubkey: hex_str(&chan_info.counterparty.node_id.serialize()),
-            ready: chan_info.is_channel_ready,
+            outbound: chan_info.is_outbound,
+            stage,
             capacity_sat: chan_info.channel_value_satoshis,
-            local_balance_msat: chan_info.balance_msat,
-            is_usable: chan_info.is_usable,
-            public: chan_info.is_public,
-            ..Default::default()
+            funding_txid,
+            confirmations: chan_info.confirmations.unwrap_or(0),
+            confirmations_required: chan_info.confirmations_required,
+            asset_id: None,
+            asset_local_amount: None,
+            asset_remote_amount: None,
+            asset_precision: None,
+            fee_rate_sat_vb: fee_rate.map(|f| f.fee_rate_sat_vb),
+            target_conf_blocks: fee_rate.and_then(|f| f.target_conf_blocks),
         };
 
-        if let Some(funding_txo) = chan_info.funding_txo {
-            channel.funding_txid = Some(funding_txo.txid.to_string());
-        }
-
-        if let Some(node_info) = unlocked_state
-            .network_graph
-            .read_only()
-            .nodes()
-            .get(&NodeId::from_pubkey(&chan_info.counterparty.node_id))
-        {
-            if let Some(announcement) = &node_info.announcement_info {
-                channel.peer_alias = Some(announcement.alias.to_string());
-            }
-        }
-
-        if let Some(id) = chan_info.short_channel_id {
-            channel.short_channel_id = Some(id);
-        }
-
-        if chan_info.is_usable {
-            channel.outbound_balance_msat = Some(chan_info.outbound_capacity_msat);
-            channel.inbound_balance_msat = Some(chan_info.inbound_capacity_msat);
-        }
-
-        let ldk_data_dir_path = PathBuf::from(state.static_state.ldk_data_dir.clone());
-        let info_file_path =
-            get_rgb_channel_info_path(&chan_info.channel_id.to_hex(), &ldk_data_dir_path, false);
-        if info_file_path.exists() {
+        // the info file of a channel being negotiated can still be the pending one
+        let info_file_path = [false, true]
+            .into_iter()
+            .map(|pending| {
+                get_rgb_channel_info_path(&channel.channel_id, &ldk_data_dir_path, pending)
+            })
+            .find(|path| path.exists());
+        if let Some(info_file_path) = info_file_path {
             let rgb_info = parse_rgb_channel_info(&info_file_path);
-            channel.asset_id = Some(rgb_info.contract_id.to_string());
+            let asset_id = rgb_info.contract_id.to_string();
+            channel.asset_precision = asset_precisions.get(&asset_id).copied();
+            channel.asset_id = Some(asset_id);
             channel.asset_local_amount = Some(rgb_info.local_rgb_amount);
             channel.asset_remote_amount = Some(rgb_info.remote_rgb_amount);
-        };
+        }
 
         channels.push(channel);
     }
 
-    Ok(Json(ListChannelsResponse { channels }))
+    // inbound channels the user hasn't decided on are not known to the channel manager yet
+    for (temporary_channel_id, request) in unlocked_state.inbound_channels.held() {
+        channels.push(PendingChannel {
+            channel_id: temporary_channel_id,
+            peer_pubkey: request.peer_pubkey.to_string(),
+            outbound: false,
+            stage: PendingChannelStage::AwaitingDecision,
+            capacity_sat: request.capacity_sat,
+            funding_txid: None,
+            confirmations: 0,
+            confirmations_required: None,
+            asset_precision: request
+                .asset_id
+                .as_ref()
+                .and_then(|id| asset_precisions.get(id).copied()),
+            asset_id: request.asset_id,
+            asset_local_amount: None,
+            asset_remote_amount: request.asset_amount,
+            fee_rate_sat_vb: None,
+            target_conf_blocks: None,
+        });
+    }
+
+    Ok(Json(PendingChannelsResponse { channels }))
 }
 
-pub(crate) async fn list_payments(
+pub(crate) async fn prune_graph(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListPaymentsResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+) -> Result<Json<PruneGraphResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let inbound_payments = unlocked_state.inbound_payments();
-    let outbound_payments = unlocked_state.outbound_payments();
-    let mut payments = vec![];
-    let ldk_data_dir_path = Path::new(&state.static_state.ldk_data_dir);
+        let result = unlocked_state
+            .prune_network_graph(&state.static_state)
+            .await?;
 
-    for (payment_hash, payment_info) in &inbound_payments {
-        let rgb_payment_info_path = get_rgb_payment_info_path(payment_hash, ldk_data_dir_path);
-        let (asset_amount, asset_id) = if rgb_payment_info_path.exists() {
-            let rgb_payment_info = parse_rgb_payment_info(&rgb_payment_info_path);
-            (
-                Some(rgb_payment_info.amount),
-                Some(rgb_payment_info.contract_id.to_string()),
-            )
-        } else {
-            (None, None)
-        };
-        payments.push(Payment {
-            amt_msat: payment_info.amt_msat,
-            asset_amount,
-            asset_id,
-            payment_hash: hex_str(&payment_hash.0),
-            inbound: true,
-            status: payment_info.status,
-        })
-    }
+        Ok(Json(PruneGraphResponse {
+            nodes_removed: result.nodes_removed,
+            channels_removed: result.channels_removed,
+        }))
+    })
+    .await
+}
 
-    for (payment_id, payment_info) in &outbound_payments {
-        let payment_hash = PaymentHash(payment_id.0);
-        let rgb_payment_info_path = get_rgb_payment_info_path(&payment_hash, ldk_data_dir_path);
-        let (asset_amount, asset_id) = if rgb_payment_info_path.exists() {
-            let rgb_payment_info = parse_rgb_payment_info(&rgb_payment_info_path);
-            (
-                Some(rgb_payment_info.amount),
-                Some(rgb_payment_info.contract_id.to_string()),
-            )
-        } else {
-            (None, None)
-        };
-        payments.push(Payment {
-            amt_msat: payment_info.amt_msat,
-            asset_amount,
-            asset_id,
-            payment_hash: hex_str(&payment_hash.0),
-            inbound: false,
-            status: payment_info.status,
-        })
-    }
+pub(crate) async fn prune_monitors(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<PruneMonitorsRequest>, APIError>,
+) -> Result<Json<PruneMonitorsResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let removed =
+            prune_archived_monitors(&unlocked_state, &state.static_state, payload.older_than_ts)?;
 
-    Ok(Json(ListPaymentsResponse { payments }))
+        Ok(Json(PruneMonitorsResponse { removed }))
+    })
+    .await
 }
 
-pub(crate) async fn list_peers(
+pub(crate) async fn prune_payments(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListPeersResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<PrunePaymentsRequest>, APIError>,
+) -> Result<Json<PrunePaymentsResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let mut peers = vec![];
-    for (pubkey, _) in unlocked_state.peer_manager.get_peer_node_ids() {
-        peers.push(Peer {
-            pubkey: pubkey.to_string(),
-        })
-    }
+        if payload.statuses.contains(&InvoiceStatus::Pending) {
+            return Err(APIError::PaymentPending);
+        }
 
-    Ok(Json(ListPeersResponse { peers }))
+        let removed = unlocked_state.prune_payments(payload.older_than_ts, &payload.statuses);
+
+        Ok(Json(PrunePaymentsResponse { removed }))
+    })
+    .await
 }
 
-pub(crate) async fn list_transactions(
+pub(crate) async fn query_node_info(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListTransactionsResponse>, APIError> {
+    WithRejection(Query(params), _): WithRejection<Query<QueryNodeInfoParams>, APIError>,
+) -> Result<Json<QueryNodeInfoResponse>, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let mut transactions = vec![];
-    for tx in unlocked_state.rgb_list_transactions()? {
-        transactions.push(Transaction {
-            transaction_type: match tx.transaction_type {
-                rgb_lib::TransactionType::RgbSend => TransactionType::RgbSend,
-                rgb_lib::TransactionType::Drain => TransactionType::Drain,
-                rgb_lib::TransactionType::CreateUtxos => TransactionType::CreateUtxos,
-                rgb_lib::TransactionType::User => TransactionType::User,
-            },
-            txid: tx.txid,
-            received: tx.received,
-            sent: tx.sent,
-            fee: tx.fee,
-            confirmation_time: tx.confirmation_time.map(|ct| BlockTime {
-                height: ct.height,
-                timestamp: ct.timestamp,
-            }),
-        })
-    }
-
-    Ok(Json(ListTransactionsResponse { transactions }))
+    let pubkey = hex_str_to_compressed_pubkey(&params.pubkey).ok_or(APIError::InvalidPubkey)?;
+
+    let network_graph = unlocked_state.network_graph.read_only();
+    let node_info = network_graph
+        .nodes()
+        .get(&NodeId::from_pubkey(&pubkey))
+        .ok_or(APIError::UnknownNode)?;
+    // nodes are known from their channels before (or without) announcing themselves
+    let announcement = node_info.announcement_info.as_ref();
+
+    Ok(Json(QueryNodeInfoResponse {
+        pubkey: pubkey.to_string(),
+        alias: announcement.map(|a| a.alias.to_string()),
+        color: announcement.map(|a| hex_str(&a.rgb)),
+        last_update: announcement.map(|a| a.last_update),
+    }))
 }
 
-pub(crate) async fn list_transfers(
+pub(crate) async fn quote_payment(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ListTransfersRequest>, APIError>,
-) -> Result<Json<ListTransfersResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<QuotePaymentRequest>, APIError>,
+) -> Result<Json<QuotePaymentResponse>, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let mut transfers = vec![];
-    for transfer in unlocked_state.rgb_list_transfers(payload.asset_id)? {
-        transfers.push(Transfer {
-            idx: transfer.idx,
-            created_at: transfer.created_at,
-            updated_at: transfer.updated_at,
-            status: match transfer.status {
-                rgb_lib::TransferStatus::WaitingCounterparty => TransferStatus::WaitingCounterparty,
-                rgb_lib::TransferStatus::WaitingConfirmations => {
-                    TransferStatus::WaitingConfirmations
+    let (route_params, asset) = match (payload.invoice, payload.dest_pubkey) {
+        (Some(invoice), None) => {
+            if payload.amt_msat.is_some()
+                || payload.asset_id.is_some()
+                || payload.asset_amount.is_some()
+            {
+                return Err(APIError::InvalidPaymentRequest(s!(
+                    "the amounts of an invoice cannot be given"
+                )));
+            }
+            let invoice = Bolt11Invoice::from_str(&invoice)
+                .map_err(|e| APIError::InvalidInvoice(e.to_string()))?;
+            let amt_msat = invoice
+                .amount_milli_satoshis()
+                .ok_or_else(|| APIError::InvalidAmount(s!("msat amount missing in invoice")))?;
+            let asset = match (invoice.rgb_contract_id(), invoice.rgb_amount()) {
+                (Some(contract_id), Some(asset_amount)) => Some((contract_id, asset_amount)),
+                (None, None) => None,
+                _ => {
+                    return Err(APIError::InvalidInvoice(s!(
+                        "invoice has only one of an RGB contract ID and an RGB amount"
+                    )))
                 }
-                rgb_lib::TransferStatus::Settled => TransferStatus::Settled,
-                rgb_lib::TransferStatus::Failed => TransferStatus::Failed,
-            },
-            amount: transfer.amount,
-            kind: match transfer.kind {
-                rgb_lib::TransferKind::Issuance => TransferKind::Issuance,
-                rgb_lib::TransferKind::ReceiveBlind => TransferKind::ReceiveBlind,
-                rgb_lib::TransferKind::ReceiveWitness => TransferKind::ReceiveWitness,
-                rgb_lib::TransferKind::Send => TransferKind::Send,
-            },
-            txid: transfer.txid,
-            recipient_id: transfer.recipient_id,
-            receive_utxo: transfer.receive_utxo.map(|u| u.to_string()),
-            change_utxo: transfer.change_utxo.map(|u| u.to_string()),
-            expiration: transfer.expiration,
-            transport_endpoints: transfer
-                .transport_endpoints
-                .iter()
-                .map(|tte| TransferTransportEndpoint {
-                    endpoint: tte.endpoint.clone(),
-                    transport_type: match tte.transport_type {
-                        rgb_lib::TransportType::JsonRpc => TransportType::JsonRpc,
-                    },
-                    used: tte.used,
-                })
-                .collect(),
-        })
-    }
-    Ok(Json(ListTransfersResponse { transfers }))
+            };
+            let route_params = RouteParameters::from_payment_params_and_value(
+                invoice_payment_params(&invoice)?,
+                amt_msat,
+            );
+            (route_params, asset)
+        }
+        (None, Some(dest_pubkey)) => {
+            let dest_pubkey =
+                hex_str_to_compressed_pubkey(&dest_pubkey).ok_or(APIError::InvalidPubkey)?;
+            let (Some(amt_msat), Some(asset_id), Some(asset_amount)) =
+                (payload.amt_msat, payload.asset_id, payload.asset_amount)
+            else {
+                return Err(APIError::InvalidPaymentRequest(s!(
+                    "amt_msat, asset_id and asset_amount are required with dest_pubkey"
+                )));
+            };
+            let contract_id =
+                ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?;
+            let route_params = RouteParameters::from_payment_params_and_value(
+                PaymentParameters::for_keysend(dest_pubkey, 40, false),
+                amt_msat.0,
+            );
+            (route_params, Some((contract_id, asset_amount.0)))
+        }
+        _ => {
+            return Err(APIError::InvalidPaymentRequest(s!(
+                "either an invoice or a dest_pubkey is required"
+            )))
+        }
+    };
+
+    Ok(Json(unlocked_state.quote_payment(
+        &state.static_state.ldk_data_dir,
+        route_params,
+        asset,
+    )))
 }
 
-pub(crate) async fn list_unspents(
+pub(crate) async fn ready(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListUnspentsResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+) -> Result<Json<ReadyResponse>, APIError> {
+    if let Err(e) = state.check_unlocked().await {
+        if let Some(failure) = state.auto_unlock_failure.lock().unwrap().clone() {
+            return Err(APIError::AutoUnlockFailed(failure));
+        }
+        return Err(e);
+    }
 
-    let mut unspents = vec![];
-    for unspent in unlocked_state.rgb_list_unspents()? {
-        unspents.push(Unspent {
-            utxo: Utxo {
-                outpoint: unspent.utxo.outpoint.to_string(),
-                btc_amount: unspent.utxo.btc_amount,
-                colorable: unspent.utxo.colorable,
-            },
-            rgb_allocations: unspent
-                .rgb_allocations
-                .iter()
-                .map(|a| RgbAllocation {
-                    asset_id: a.asset_id.clone(),
-                    amount: a.amount,
-                    settled: a.settled,
-                })
-                .collect(),
-        })
+    if let Some(failure) = state.static_state.persistence.failure() {
+        return Err(APIError::PersistenceFailed(failure));
     }
-    Ok(Json(ListUnspentsResponse { unspents }))
+
+    let proxy_status = proxy_url(&preferred_proxy_endpoint(&state.static_state))
+        .and_then(|url| state.static_state.proxy_checks.get(&url));
+
+    Ok(Json(ReadyResponse {
+        proxy_reachable: proxy_status.as_ref().map_or(false, |s| s.reachable),
+        proxy_version: proxy_status.as_ref().and_then(|s| s.version.clone()),
+        proxy_protocol_compatible: proxy_status.map_or(false, |s| s.compatible()),
+    }))
 }
 
-pub(crate) async fn ln_invoice(
+pub(crate) async fn rebroadcast_announcements(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<LNInvoiceRequest>, APIError>,
-) -> Result<Json<LNInvoiceResponse>, APIError> {
+) -> Result<Json<RebroadcastAnnouncementsResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let contract_id = if let Some(asset_id) = payload.asset_id {
-            Some(ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?)
-        } else {
-            None
-        };
-
-        if payload.amt_msat.is_some() && payload.amt_msat.unwrap() < INVOICE_MIN_MSAT {
-            return Err(APIError::InvalidAmount(format!(
-                "amt_msat cannot be less than {INVOICE_MIN_MSAT}"
-            )));
-        }
-
-        let currency = match state.static_state.network {
-            Network::Bitcoin => Currency::Bitcoin,
-            Network::Testnet => Currency::BitcoinTestnet,
-            Network::Regtest => Currency::Regtest,
-            Network::Signet => Currency::Signet,
-        };
-        let invoice = match create_invoice_from_channelmanager(
+        if !announce_node(
+            &unlocked_state.peer_manager,
             &unlocked_state.channel_manager,
-            unlocked_state.keys_manager.clone(),
-            state.static_state.logger.clone(),
-            currency,
-            payload.amt_msat,
-            "ldk-tutorial-node".to_string(),
-            payload.expiry_sec,
-            None,
-            contract_id,
-            payload.asset_amount,
+            &unlocked_state.announcements,
+            &state.static_state,
         ) {
-            Ok(inv) => inv,
-            Err(e) => return Err(APIError::FailedInvoiceCreation(e.to_string())),
-        };
-
-        let payment_hash = PaymentHash((*invoice.payment_hash()).into_inner());
-        unlocked_state.add_inbound_payment(
-            payment_hash,
-            PaymentInfo {
-                preimage: None,
-                secret: Some(*invoice.payment_secret()),
-                status: HTLCStatus::Pending,
-                amt_msat: payload.amt_msat,
-            },
+            return Err(APIError::NoPublicChannels);
+        }
+        let public_channels = unlocked_state
+            .channel_manager
+            .list_channels()
+            .into_iter()
+            .filter(|c| c.is_public)
+            .filter_map(|c| c.short_channel_id);
+        let channels = unlocked_state.announcements.queue_channel_rebroadcasts(
+            &unlocked_state.network_graph,
+            NodeId::from_pubkey(&unlocked_state.channel_manager.get_our_node_id()),
+            public_channels,
         );
+        // the channel announcements are handed to the peers with the pending events
+        unlocked_state.peer_manager.process_events();
 
-        Ok(Json(LNInvoiceResponse {
-            invoice: invoice.to_string(),
-        }))
+        Ok(Json(RebroadcastAnnouncementsResponse { channels }))
     })
     .await
 }
 
-pub(crate) async fn lock(
+pub(crate) async fn refresh_transfers(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<EmptyResponse>, APIError> {
-    tracing::info!("Lock started");
     no_cancel(async move {
-        match state.check_unlocked().await {
-            Ok(unlocked_state) => {
-                state.update_changing_state(true);
-                drop(unlocked_state);
-            }
-            Err(e) => {
-                state.update_changing_state(false);
-                return Err(e);
-            }
-        }
-
-        tracing::debug!("Stopping LDK...");
-        stop_ldk(state.clone()).await;
-        tracing::debug!("LDK stopped");
-
-        state.update_unlocked_app_state(None).await;
-
-        state.update_ldk_background_services(None);
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        state.update_changing_state(false);
+        tokio::task::spawn_blocking(move || {
+            refresh_pending_transfers(&unlocked_state, &state.static_state)
+        })
+        .await
+        .unwrap()?;
 
-        tracing::info!("Lock completed");
+        tracing::info!("Refresh complete");
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn network_info(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<NetworkInfoResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
-
-    let best_block = unlocked_state.channel_manager.current_best_block();
-
-    Ok(Json(NetworkInfoResponse {
-        network: state.static_state.network.into(),
-        height: best_block.height(),
-    }))
-}
-
-pub(crate) async fn node_info(
+pub(crate) async fn reject_channel(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<NodeInfoResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<RejectChannelRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let chans = unlocked_state.channel_manager.list_channels();
+        let temporary_channel_id = hex_str_to_channel_id(&payload.temporary_channel_id)
+            .ok_or(APIError::InvalidChannelID)?;
+        let request = unlocked_state
+            .inbound_channels
+            .take_held(&temporary_channel_id.to_hex())
+            .ok_or(APIError::UnknownChannelRequest)?;
+        reject_inbound_channel(
+            &unlocked_state,
+            &temporary_channel_id,
+            &request.peer_pubkey,
+            "rejected by the user",
+        );
 
-    Ok(Json(NodeInfoResponse {
-        pubkey: unlocked_state.channel_manager.get_our_node_id().to_string(),
-        num_channels: chans.len(),
-        num_usable_channels: chans.iter().filter(|c| c.is_usable).count(),
-        local_balance_msat: chans.iter().map(|c| c.balance_msat).sum::<u64>(),
-        num_peers: unlocked_state.peer_manager.get_peer_node_ids().len(),
-    }))
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
 }
 
-pub(crate) async fn open_channel(
+pub(crate) async fn request_inbound_channel(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<OpenChannelRequest>, APIError>,
-) -> Result<Json<OpenChannelResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<RequestInboundChannelRequest>, APIError>,
+) -> Result<Json<RequestInboundChannelResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let (peer_pubkey, peer_addr) = parse_peer_info(payload.peer_pubkey_and_addr.to_string())?;
+        unlocked_state.check_not_recovering()?;
+
+        let (lsp_pubkey, lsp_addr) = parse_peer_info(payload.lsp_pubkey_and_addr.to_string())?;
 
         let contract_id = ContractId::from_str(&payload.asset_id)
             .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
 
-        if payload.capacity_sat < OPENCHANNEL_MIN_SAT {
+        let capacity_sat = payload.capacity_sat.0;
+        let asset_amount = payload.asset_amount.0;
+        if capacity_sat < OPENCHANNEL_MIN_SAT {
             return Err(APIError::InvalidAmount(format!(
                 "Channel amount must be equal or higher than {OPENCHANNEL_MIN_SAT}"
             )));
         }
-        if payload.capacity_sat > OPENCHANNEL_MAX_SAT {
+        if capacity_sat > OPENCHANNEL_MAX_SAT {
             return Err(APIError::InvalidAmount(format!(
                 "Channel amount must be equal or less than {OPENCHANNEL_MAX_SAT}"
             )));
         }
-
-        if payload.push_msat < DUST_LIMIT_MSAT {
-            return Err(APIError::InvalidAmount(format!(
-                "Push amount must be equal or higher than the dust limit ({DUST_LIMIT_MSAT})"
-            )));
-        }
-
-        if payload.asset_amount < OPENCHANNEL_MIN_RGB_AMT {
+        if asset_amount < OPENCHANNEL_MIN_RGB_AMT {
             return Err(APIError::InvalidAmount(format!(
                 "Channel RGB amount must be equal or higher than {OPENCHANNEL_MIN_RGB_AMT}"
             )));
         }
 
-        if !payload.with_anchors {
-            return Err(APIError::AnchorsRequired);
-        }
-
-        connect_peer_if_necessary(peer_pubkey, peer_addr, unlocked_state.peer_manager.clone())
+        connect_peer_if_necessary(lsp_pubkey, lsp_addr, unlocked_state.peer_manager.clone())
             .await?;
 
-        let balance = unlocked_state.rgb_get_asset_balance(contract_id)?;
-
-        let spendable_rgb_amount = balance.spendable;
-
-        if payload.asset_amount > spendable_rgb_amount {
-            return Err(APIError::InsufficientAssets(spendable_rgb_amount));
-        }
-
-        let mut channel_config = ChannelConfig::default();
-        if let Some(fee_base_msat) = payload.fee_base_msat {
-            channel_config.forwarding_fee_base_msat = fee_base_msat;
-        }
-        if let Some(fee_proportional_millionths) = payload.fee_proportional_millionths {
-            channel_config.forwarding_fee_proportional_millionths = fee_proportional_millionths;
-        }
-        let config = UserConfig {
-            channel_handshake_limits: ChannelHandshakeLimits {
-                // lnd's max to_self_delay is 2016, so we want to be compatible.
-                their_to_self_delay: 2016,
-                ..Default::default()
-            },
-            channel_handshake_config: ChannelHandshakeConfig {
-                announced_channel: payload.public,
-                our_htlc_minimum_msat: HTLC_MIN_MSAT,
-                minimum_depth: MIN_CHANNEL_CONFIRMATIONS as u32,
-                negotiate_anchors_zero_fee_htlc_tx: payload.with_anchors,
-                ..Default::default()
-            },
-            channel_config,
-            ..Default::default()
-        };
-
-        let consignment_endpoint =
-            RgbTransport::from_str(&state.static_state.proxy_endpoint).unwrap();
-        let temporary_channel_id = unlocked_state
-            .channel_manager
-            .create_channel(
-                peer_pubkey,
-                payload.capacity_sat,
-                payload.push_msat,
-                0,
-                Some(config),
-                Some(consignment_endpoint),
-            )
-            .map_err(|e| APIError::FailedOpenChannel(format!("{:?}", e)))?;
-        tracing::info!("EVENT: initiated channel with peer {}", peer_pubkey);
-
+        // the LSP opens the channel towards us, keep its address to reconnect to it
         let peer_data_path = format!(
             "{}/channel_peer_data",
             state.static_state.ldk_data_dir.clone()
         );
         let _ =
-            disk::persist_channel_peer(Path::new(&peer_data_path), &payload.peer_pubkey_and_addr);
+            disk::persist_channel_peer(Path::new(&peer_data_path), &payload.lsp_pubkey_and_addr);
 
-        let rgb_info = RgbInfo {
-            contract_id,
-            local_rgb_amount: payload.asset_amount,
-            remote_rgb_amount: 0,
-        };
-        let temporary_channel_id = temporary_channel_id.to_hex();
-        let ldk_data_dir_path = PathBuf::from(&state.static_state.ldk_data_dir);
-        write_rgb_channel_info(
-            &get_rgb_channel_info_path(&temporary_channel_id, &ldk_data_dir_path, true),
-            &rgb_info,
-        );
-        write_rgb_channel_info(
-            &get_rgb_channel_info_path(&temporary_channel_id, &ldk_data_dir_path, false),
-            &rgb_info,
+        let request_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        unlocked_state.lsp.add_request(InboundChannelRequest {
+            request_id: request_id.clone(),
+            lsp_pubkey: lsp_pubkey.to_string(),
+            asset_id: contract_id.to_string(),
+            asset_amount,
+            capacity_sat,
+            status: InboundChannelRequestStatus::Pending,
+            fee_sat: None,
+            fee_invoice: None,
+            temporary_channel_id: None,
+            reason: None,
+        });
+        unlocked_state.send_peer_message(
+            lsp_pubkey,
+            PeerMessage::LspChannelRequest(LspChannelRequest {
+                request_id: request_id.clone(),
+                asset_id: contract_id.to_string(),
+                asset_amount,
+                capacity_sat,
+            }),
         );
 
-        Ok(Json(OpenChannelResponse {
-            temporary_channel_id,
-        }))
-    })
-    .await
-}
-
-pub(crate) async fn refresh_transfers(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
-        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
-
-        tokio::task::spawn_blocking(move || unlocked_state.rgb_refresh())
-            .await
-            .unwrap()?;
-
-        tracing::info!("Refresh complete");
-        Ok(Json(EmptyResponse {}))
+        Ok(Json(RequestInboundChannelResponse { request_id }))
     })
     .await
 }
@@ -1609,13 +5082,15 @@ pub(crate) async fn refresh_transfers(
 pub(crate) async fn restore(
     State(state): State<Arc<AppState>>,
     WithRejection(Json(payload), _): WithRejection<Json<RestoreRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
+) -> Result<Response, APIError> {
+    let job = new_job(&state, JobKind::Restore, false);
+    run_job(job.clone(), async move {
         let _unlocked_state = state.check_locked().await?;
 
         let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
         check_already_initialized(&mnemonic_path)?;
 
+        job.commit()?;
         restore_backup(
             &payload.backup_path,
             &payload.password,
@@ -1625,7 +5100,7 @@ pub(crate) async fn restore(
         let _mnemonic =
             check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
 
-        Ok(Json(EmptyResponse {}))
+        Ok(EmptyResponse {})
     })
     .await
 }
@@ -1637,16 +5112,38 @@ pub(crate) async fn rgb_invoice(
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
+        check_label(&payload.label)?;
+
+        let min_allowed_confirmations = state.static_state.min_allowed_confirmations;
+        let min_confirmations = payload
+            .min_confirmations
+            .unwrap_or(state.static_state.default_min_confirmations);
+        if min_confirmations < min_allowed_confirmations {
+            return Err(APIError::InvalidMinConfirmations(format!(
+                "cannot be lower than {min_allowed_confirmations}"
+            )));
+        }
+
         let receive_data = unlocked_state.rgb_blind_receive(
             payload.asset_id,
-            vec![state.static_state.proxy_endpoint.clone()],
-            payload.min_confirmations,
+            payload.expiry_sec,
+            state.static_state.proxy_endpoints.clone(),
+            min_confirmations,
         )?;
 
+        if let Some(label) = payload.label {
+            unlocked_state.set_rgb_invoice_label(&receive_data.recipient_id, &label);
+        }
+
+        let unified = payload
+            .unified
+            .then(|| unified_uri(None, Some(&receive_data.invoice)));
+
         Ok(Json(RgbInvoiceResponse {
             recipient_id: receive_data.recipient_id,
             invoice: receive_data.invoice,
             expiration_timestamp: receive_data.expiration_timestamp,
+            unified,
         }))
     })
     .await
@@ -1655,47 +5152,252 @@ pub(crate) async fn rgb_invoice(
 pub(crate) async fn send_asset(
     State(state): State<Arc<AppState>>,
     WithRejection(Json(payload), _): WithRejection<Json<SendAssetRequest>, APIError>,
-) -> Result<Json<SendAssetResponse>, APIError> {
-    no_cancel(async move {
+) -> Result<Response, APIError> {
+    let job = new_job(&state, JobKind::SendAsset, payload.dry_run);
+    run_job(job.clone(), async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
+        if !payload.dry_run {
+            state.static_state.check_writable()?;
+        }
+
+        let contract_id = ContractId::from_str(&payload.asset_id)
+            .map_err(|_| APIError::InvalidAssetID(payload.asset_id.clone()))?;
+        let amount = payload.amount.0;
+        check_asset_amount(&unlocked_state, contract_id, amount)?;
+
         let secret_seal = SecretSeal::from_str(&payload.blinded_utxo)
             .map_err(|e| APIError::InvalidBlindedUTXO(e.to_string()))?;
+        check_payment_policy(&unlocked_state, &secret_seal.to_string())?;
+
+        let blinded_utxo = secret_seal.to_string();
+        if !payload.force
+            && unlocked_state
+                .database
+                .is_sent_blinded_utxo(&blinded_utxo)?
+        {
+            return Err(APIError::BlindedUtxoAlreadyUsed);
+        }
+
+        check_transport_endpoints(&state.static_state, &payload.transport_endpoints).await?;
+
+        if payload.dry_run {
+            unlocked_state.database.check_spending(
+                0,
+                Some((&contract_id.to_string(), amount)),
+                get_current_timestamp(),
+            )?;
+            return tokio::task::spawn_blocking(move || {
+                // the asset goes to the recipient's UTXO, no BTC output is added for it
+                let dry_run =
+                    simulate_rgb_send(&unlocked_state, &payload.asset_id, amount, &[], FEE_RATE)?;
+                // rgb-lib selects the allocations to spend itself, so they can't be told in advance
+                Ok(SendAssetResponse {
+                    txid: None,
+                    consumed_allocations: vec![],
+                    change_allocation: None,
+                    dry_run: Some(DryRunResult {
+                        inputs: vec![],
+                        consumed_allocations: vec![],
+                        asset_change: None,
+                        ..dry_run
+                    }),
+                })
+            })
+            .await
+            .unwrap();
+        }
+
+        job.commit()?;
+        let spending_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        unlocked_state.database.reserve_spending(
+            &spending_id,
+            0,
+            Some((&contract_id.to_string(), amount)),
+            get_current_timestamp(),
+        )?;
+        // recorded before sending, so concurrent sends to the same blinded UTXO get rejected too
+        let blinded_utxo_added = unlocked_state.database.add_sent_blinded_utxo(
+            &blinded_utxo,
+            &contract_id.to_string(),
+            get_current_timestamp(),
+        )?;
+        if !blinded_utxo_added && !payload.force {
+            let _ = unlocked_state.database.release_spending(&spending_id);
+            return Err(APIError::BlindedUtxoAlreadyUsed);
+        }
+
+        let asset_id = payload.asset_id.clone();
         let recipient_map = map! {
             payload.asset_id => vec![Recipient {
                 recipient_data: RecipientData::BlindedUTXO(secret_seal),
-                amount: payload.amount,
+                amount,
                 transport_endpoints: payload.transport_endpoints,
             }]
         };
 
-        let txid = tokio::task::spawn_blocking(move || {
-            unlocked_state.rgb_send(
-                recipient_map,
-                payload.donation,
-                FEE_RATE,
-                payload.min_confirmations,
-            )
+        tokio::task::spawn_blocking(move || {
+            // allocations of the asset before sending, to know the amounts on the spent inputs
+            let allocations = unlocked_state
+                .rgb_list_unspents()?
+                .into_iter()
+                .flat_map(|u| {
+                    let outpoint = u.utxo.outpoint.to_string();
+                    u.rgb_allocations
+                        .into_iter()
+                        .filter(|a| a.asset_id.as_ref() == Some(&asset_id))
+                        .map(move |a| OutpointAllocation {
+                            outpoint: outpoint.clone(),
+                            amount: a.amount,
+                        })
+                })
+                .collect::<Vec<OutpointAllocation>>();
+
+            let mut inputs = vec![];
+            let send = || -> Result<String, APIError> {
+                let unsigned_psbt = unlocked_state.rgb_send_begin(
+                    recipient_map,
+                    payload.donation,
+                    FEE_RATE,
+                    payload.min_confirmations,
+                )?;
+                let signed_psbt = unlocked_state.rgb_sign_psbt(unsigned_psbt)?;
+                inputs = psbt_inputs(&signed_psbt)?;
+                let intent = WalletIntent {
+                    txid: psbt_txid(&signed_psbt)?,
+                    kind: WalletIntentKind::SendAsset,
+                    signed_psbt,
+                    asset_id: Some(asset_id.clone()),
+                    spending_id: Some(spending_id.clone()),
+                    blinded_utxo: blinded_utxo_added.then(|| blinded_utxo.clone()),
+                    created_at: get_current_timestamp(),
+                };
+                unlocked_state.broadcast_with_intent(&state.static_state, intent, |signed_psbt| {
+                    let txid = unlocked_state.rgb_send_end(signed_psbt.clone())?;
+                    unlocked_state.reserve_utxos(
+                        &signed_psbt,
+                        UtxoReservation::AssetTransfer { txid: txid.clone() },
+                    );
+                    Ok(txid)
+                })
+            };
+
+            let txid = send().map_err(|e| {
+                let _ = unlocked_state.database.release_spending(&spending_id);
+                if blinded_utxo_added {
+                    let _ = unlocked_state
+                        .database
+                        .remove_sent_blinded_utxo(&blinded_utxo);
+                }
+                e
+            })?;
+
+            // the allocations on the transfer inputs all go to the recipient and the change
+            let consumed_allocations: Vec<OutpointAllocation> = allocations
+                .into_iter()
+                .filter(|a| inputs.contains(&a.outpoint))
+                .collect();
+            let consumed_amount: u64 = consumed_allocations.iter().map(|a| a.amount).sum();
+            let change_allocation = unlocked_state
+                .rgb_list_transfers(asset_id.clone())?
+                .into_iter()
+                .find(|t| {
+                    matches!(t.kind, rgb_lib::TransferKind::Send)
+                        && t.txid.as_deref() == Some(txid.as_str())
+                })
+                .and_then(|t| t.change_utxo)
+                .zip(consumed_amount.checked_sub(amount).filter(|a| *a > 0))
+                .map(|(change_utxo, change_amount)| OutpointAllocation {
+                    outpoint: change_utxo.to_string(),
+                    amount: change_amount,
+                });
+
+            Ok(SendAssetResponse {
+                txid: Some(txid),
+                consumed_allocations,
+                change_allocation,
+                dry_run: None,
+            })
         })
         .await
-        .unwrap()?;
+        .unwrap()
+    })
+    .await
+}
+
+pub(crate) async fn send_btc(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SendBtcRequest>, APIError>,
+) -> Result<Json<SendBtcResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        state.static_state.check_writable()?;
+
+        let spending_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        unlocked_state.database.reserve_spending(
+            &spending_id,
+            payload.amount.to_msat().0,
+            None,
+            get_current_timestamp(),
+        )?;
 
-        Ok(Json(SendAssetResponse { txid }))
+        let txid = unlocked_state
+            .rgb_send_btc(payload.address, payload.amount.0, payload.fee_rate)
+            .map_err(|e| {
+                let _ = unlocked_state.database.release_spending(&spending_id);
+                e
+            })?;
+
+        Ok(Json(SendBtcResponse { txid }))
     })
     .await
 }
 
-pub(crate) async fn send_btc(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<SendBtcRequest>, APIError>,
-) -> Result<Json<SendBtcResponse>, APIError> {
-    no_cancel(async move {
-        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+pub(crate) async fn send_custom_message(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SendCustomMessageRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let peer_pubkey = match bitcoin::secp256k1::PublicKey::from_str(&payload.peer_pubkey) {
+            Ok(pubkey) => pubkey,
+            Err(_e) => return Err(APIError::InvalidPubkey),
+        };
+
+        if !is_app_msg_type(payload.msg_type) {
+            return Err(APIError::InvalidCustomMessage(format!(
+                "type must be at least {CUSTOM_MSG_MIN_TYPE} and not one used by the node"
+            )));
+        }
+        // peers disconnect on even types they don't understand
+        if payload.msg_type % 2 == 0 {
+            return Err(APIError::InvalidCustomMessage(s!("type must be odd")));
+        }
+
+        let data = hex_str_to_vec(&payload.data)
+            .ok_or(APIError::InvalidCustomMessage(s!("need a hex data string")))?;
+        if data.len() > CUSTOM_MSG_MAX_DATA_LEN {
+            return Err(APIError::InvalidCustomMessage(format!(
+                "data must be at most {CUSTOM_MSG_MAX_DATA_LEN} bytes"
+            )));
+        }
+
+        let peers = unlocked_state.peer_manager.get_peer_node_ids();
+        if !peers.iter().any(|(pk, _)| &peer_pubkey == pk) {
+            return Err(APIError::PeerNotConnected(payload.peer_pubkey));
+        }
 
-        let txid =
-            unlocked_state.rgb_send_btc(payload.address, payload.amount, payload.fee_rate)?;
+        unlocked_state.send_peer_message(
+            peer_pubkey,
+            PeerMessage::Custom {
+                msg_type: payload.msg_type,
+                data,
+            },
+        );
 
-        Ok(Json(SendBtcResponse { txid }))
+        Ok(Json(EmptyResponse {}))
     })
     .await
 }
@@ -1707,7 +5409,19 @@ pub(crate) async fn send_onion_message(
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        if payload.node_ids.is_empty() {
+        let blinded_path = match payload.blinded_path {
+            Some(blinded_path) => {
+                let bytes = hex_str_to_vec(&blinded_path)
+                    .ok_or(APIError::InvalidOnionData(s!("need a hex blinded path")))?;
+                Some(
+                    BlindedPath::read(&mut Cursor::new(bytes))
+                        .map_err(|_| APIError::InvalidOnionData(s!("invalid blinded path")))?,
+                )
+            }
+            None => None,
+        };
+
+        if payload.node_ids.is_empty() && blinded_path.is_none() {
             return Err(APIError::InvalidNodeIds(s!(
                 "sendonionmessage requires at least one node id for the path"
             )));
@@ -1745,7 +5459,10 @@ pub(crate) async fn send_onion_message(
         let data = hex_str_to_vec(&payload.data)
             .ok_or(APIError::InvalidOnionData(s!("need a hex data string")))?;
 
-        let destination = Destination::Node(intermediate_nodes.pop().unwrap());
+        let destination = match blinded_path {
+            Some(blinded_path) => Destination::BlindedPath(blinded_path),
+            None => Destination::Node(intermediate_nodes.pop().unwrap()),
+        };
         let message_path = OnionMessagePath {
             intermediate_nodes,
             destination,
@@ -1777,11 +5494,25 @@ pub(crate) async fn send_payment(
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
+        state.static_state.check_writable()?;
+
         let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
             Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
             Ok(v) => v,
         };
 
+        check_label(&payload.label)?;
+
+        if payload
+            .payer_note
+            .as_ref()
+            .is_some_and(|n| n.len() > PAYER_NOTE_MAX_LEN)
+        {
+            return Err(APIError::InvalidNote(format!(
+                "cannot be longer than {PAYER_NOTE_MAX_LEN} bytes"
+            )));
+        }
+
         if let Some(amt_msat) = invoice.amount_milli_satoshis() {
             if amt_msat < INVOICE_MIN_MSAT {
                 return Err(APIError::InvalidAmount(s!(
@@ -1794,16 +5525,40 @@ pub(crate) async fn send_payment(
             )));
         }
 
+        check_payment_policy(
+            &unlocked_state,
+            &invoice.recover_payee_pub_key().to_string(),
+        )?;
+        let avoided_channels = unlocked_state.exposure_avoided_channels(
+            &state.static_state,
+            invoice.amount_milli_satoshis().unwrap(),
+            invoice.rgb_contract_id().zip(invoice.rgb_amount()),
+        )?;
+
+        let fallback = if payload.fallback_onchain {
+            Some(onchain_fallback(&invoice, payload.fallback_fee_rate)?)
+        } else {
+            None
+        };
+
+        // vanilla payments are left to the pathfinding, which knows the channels of the network
+        if let (Some(contract_id), Some(asset_amount), false) = (
+            invoice.rgb_contract_id(),
+            invoice.rgb_amount(),
+            payload.skip_liquidity_check,
+        ) {
+            unlocked_state.check_colored_payable(
+                &state.static_state.ldk_data_dir,
+                contract_id,
+                invoice.amount_milli_satoshis().unwrap(),
+                asset_amount,
+            )?;
+        }
+
         let payment_hash = PaymentHash((*invoice.payment_hash()).into_inner());
-        match (invoice.rgb_contract_id(), invoice.rgb_amount()) {
-            (Some(rgb_contract_id), Some(rgb_amount)) => write_rgb_payment_info_file(
-                &PathBuf::from(&state.static_state.ldk_data_dir.clone()),
-                &payment_hash,
-                rgb_contract_id,
-                rgb_amount,
-                false,
-            ),
-            (None, None) => {}
+        let rgb_payment = match (invoice.rgb_contract_id(), invoice.rgb_amount()) {
+            (Some(rgb_contract_id), Some(rgb_amount)) => Some((rgb_contract_id, rgb_amount)),
+            (None, None) => None,
             (Some(_), None) => {
                 return Err(APIError::InvalidInvoice(s!(
                     "invoice has an RGB contract ID but not an RGB amount"
@@ -1814,26 +5569,126 @@ pub(crate) async fn send_payment(
                     "invoice has an RGB amount but not an RGB contract ID"
                 )))
             }
-        }
+        };
+
+        let payer_secret_key = payload
+            .identify_payer
+            .then(|| unlocked_state.keys_manager.get_node_secret_key());
+        let payer_tlvs = payer_tlvs(
+            &payment_hash,
+            payer_secret_key.as_ref(),
+            payload.payer_note.as_deref(),
+        )?;
 
         let payment_id = PaymentId((*invoice.payment_hash()).into_inner());
         let payment_secret = *invoice.payment_secret();
-        unlocked_state.add_outbound_payment(
+        // a payment pending or succeeded is refused before its spending and reservation get
+        // touched, a failed one can be sent again
+        let previous_attempt = unlocked_state.start_outbound_payment(
             payment_id,
             PaymentInfo {
                 preimage: None,
                 secret: Some(payment_secret),
                 status: HTLCStatus::Pending,
                 amt_msat: invoice.amount_milli_satoshis(),
+                label: payload.label,
+                created_at: get_current_timestamp(),
+                expires_at: None,
+                failure_reason: None,
+                failing_hop: None,
+                settled_at: None,
+                payer_requirements: None,
+                payer: None,
+                payee: Some(invoice.recover_payee_pub_key()),
+                onchain_txid: None,
+                closure: None,
+                hops: vec![],
             },
-        );
-
-        let status = match pay_invoice(
-            &invoice,
-            Retry::Timeout(Duration::from_secs(10)),
-            &*unlocked_state.channel_manager,
+        )?;
+        let rgb_spending = rgb_payment.map(|(c, a)| (c.to_string(), a));
+        let spending_reserved = match unlocked_state.database.reserve_spending(
+            &hex_str(&payment_id.0),
+            invoice.amount_milli_satoshis().unwrap(),
+            rgb_spending.as_ref().map(|(c, a)| (c.as_str(), *a)),
+            get_current_timestamp(),
         ) {
-            Ok(_payment_id) => {
+            Ok(reserved) => reserved,
+            Err(e) => {
+                unlocked_state.restore_outbound_payment(payment_id, previous_attempt);
+                return Err(e);
+            }
+        };
+        let mut outbound_reserved = false;
+        if let Some((contract_id, asset_amount)) = rgb_payment {
+            match unlocked_state.reserve_outbound(
+                &state.static_state.ldk_data_dir,
+                payment_id,
+                invoice.amount_milli_satoshis().unwrap(),
+                contract_id,
+                asset_amount,
+                !payload.skip_liquidity_check,
+            ) {
+                Ok(reserved) => outbound_reserved = reserved,
+                Err(e) => {
+                    if spending_reserved {
+                        let _ = unlocked_state
+                            .database
+                            .release_spending(&hex_str(&payment_id.0));
+                    }
+                    unlocked_state.restore_outbound_payment(payment_id, previous_attempt);
+                    return Err(e);
+                }
+            }
+            write_rgb_payment_info_file(
+                &PathBuf::from(&state.static_state.ldk_data_dir),
+                &payment_hash,
+                contract_id,
+                asset_amount,
+                false,
+            );
+        }
+
+        // with both options set, max_retries bounds the attempts and the timer spawned below
+        // abandons the payment once timeout_sec has elapsed, whichever comes first
+        let retry = match payload.max_retries {
+            Some(max_retries) => Retry::Attempts(max_retries as usize),
+            None => Retry::Timeout(Duration::from_secs(
+                payload.timeout_sec.unwrap_or(PAYMENT_RETRY_TIMEOUT_SEC),
+            )),
+        };
+        // the invoice payer doesn't support custom TLVs nor avoiding channels, so payments
+        // carrying them or kept away from peers at their exposure limits are sent as the ones
+        // with MPP disabled
+        let res = if payload.disable_mpp || !payer_tlvs.is_empty() || !avoided_channels.is_empty() {
+            let mut payment_params = invoice_payment_params(&invoice)?;
+            if payload.disable_mpp {
+                payment_params.max_path_count = 1;
+            }
+            payment_params.previously_failed_channels = avoided_channels;
+            let route_params = RouteParameters::from_payment_params_and_value(
+                payment_params,
+                invoice.amount_milli_satoshis().unwrap(),
+            );
+            let mut recipient_onion = RecipientOnionFields::secret_only(payment_secret)
+                .with_custom_tlvs(payer_tlvs)
+                .expect("valid custom TLV types");
+            recipient_onion.payment_metadata = invoice.payment_metadata().cloned();
+            unlocked_state
+                .channel_manager
+                .send_payment(
+                    payment_hash,
+                    recipient_onion,
+                    payment_id,
+                    route_params,
+                    retry,
+                )
+                .map_err(PaymentError::Sending)
+        } else {
+            pay_invoice(&invoice, retry, &*unlocked_state.channel_manager).map(|_| ())
+        };
+        let status = match res {
+            Ok(()) => {
+                unlocked_state.outbound_reservations.submitted(&payment_id);
                 let payee_pubkey = invoice.recover_payee_pub_key();
                 let amt_msat = invoice.amount_milli_satoshis().unwrap();
                 tracing::info!(
@@ -1843,13 +5698,65 @@ pub(crate) async fn send_payment(
                 );
                 HTLCStatus::Pending
             }
+            Err(PaymentError::Sending(RetryableSendFailure::DuplicatePayment)) => {
+                // LDK still tracks an attempt recorded as failed, whose record is put back while
+                // only the spending and reservation made by this call get released
+                tracing::error!("ERROR: failed to send payment: duplicate payment");
+                if spending_reserved {
+                    let _ = unlocked_state
+                        .database
+                        .release_spending(&hex_str(&payment_id.0));
+                }
+                if outbound_reserved {
+                    unlocked_state.outbound_reservations.release(&payment_id);
+                }
+                unlocked_state.restore_outbound_payment(payment_id, previous_attempt);
+                return Err(APIError::DuplicatePayment);
+            }
             Err(e) => {
                 tracing::error!("ERROR: failed to send payment: {:?}", e);
-                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
+                let failure_reason = match e {
+                    PaymentError::Sending(ref e) => FailureReason::from(e),
+                    PaymentError::Invoice(_) => FailureReason::UnexpectedError,
+                };
+                unlocked_state.fail_outbound_payment(
+                    &state.static_state,
+                    payment_id,
+                    failure_reason,
+                    None,
+                );
                 HTLCStatus::Failed
             }
         };
 
+        if let (HTLCStatus::Pending, Some(timeout_sec)) = (status, payload.timeout_sec) {
+            // stop retrying and consider the payment failed once its time budget is exhausted
+            let unlocked_state = unlocked_state.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(timeout_sec)).await;
+                if unlocked_state
+                    .outbound_payments()
+                    .get(&payment_id)
+                    .is_some_and(|p| p.status == HTLCStatus::Pending)
+                {
+                    tracing::info!(
+                        "Time budget exhausted for payment {}, abandoning it",
+                        hex_str(&payment_hash.0)
+                    );
+                    unlocked_state.channel_manager.abandon_payment(payment_id);
+                }
+            });
+        }
+
+        if let Some(fallback) = fallback {
+            tokio::spawn(pay_onchain_on_failure(
+                unlocked_state.clone(),
+                payment_id,
+                fallback,
+                payload.timeout_sec.unwrap_or(PAYMENT_RETRY_TIMEOUT_SEC),
+            ));
+        }
+
         Ok(Json(SendPaymentResponse {
             payment_hash: hex_str(&payment_hash.0),
             payment_secret: hex_str(&payment_secret.0),
@@ -1861,12 +5768,246 @@ pub(crate) async fn send_payment(
 
 pub(crate) async fn shutdown(
     State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<Json<EmptyResponse>, APIError> {
+    // calls without a body predate the modes and exit the process
+    let payload: ShutdownRequest = if body.is_empty() {
+        ShutdownRequest::default()
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| APIError::InvalidShutdownRequest(e.to_string()))?
+    };
+
+    match payload.mode {
+        ShutdownMode::Restartable => {
+            match lock(State(state.clone())).await {
+                Ok(_) | Err(APIError::LockedNode) => {}
+                Err(e) => return Err(e),
+            }
+            state.api_stopped.store(true, Ordering::Release);
+            tracing::info!("API stopped, serving only the routes to restart the node");
+            Ok(Json(EmptyResponse {}))
+        }
+        ShutdownMode::Exit => {
+            no_cancel(async move {
+                let _unlocked_app_state = state.get_unlocked_app_state();
+                state.check_changing_state()?;
+
+                state.cancel_token.cancel();
+                Ok(Json(EmptyResponse {}))
+            })
+            .await
+        }
+    }
+}
+
+pub(crate) async fn set_alerts(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetAlertsRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        unlocked_state
+            .database
+            .set_alert_thresholds(&AlertThresholds {
+                anchor_reserve_per_channel_sat: payload.anchor_reserve_per_channel_sat,
+                min_fee_bump_utxos: payload.min_fee_bump_utxos,
+                min_channel_asset_amount: payload.min_channel_asset_amount,
+                max_unconfirmed_tx_age_secs: payload.max_unconfirmed_tx_age_secs,
+            })?;
+        unlocked_state.alerts.trigger();
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn set_alias(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetAliasRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        if node_alias_bytes(&payload.alias).is_none() {
+            return Err(APIError::InvalidNodeAlias(s!(
+                "cannot be longer than 32 bytes when UTF-8 encoded"
+            )));
+        }
+        *state.static_state.ldk_announced_node_alias.lock().unwrap() = payload.alias;
+
+        announce_node(
+            &unlocked_state.peer_manager,
+            &unlocked_state.channel_manager,
+            &unlocked_state.announcements,
+            &state.static_state,
+        );
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn set_channel_note(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetChannelNoteRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let channel_id_vec = hex_str_to_vec(&payload.channel_id);
+        if channel_id_vec.is_none() || channel_id_vec.as_ref().unwrap().len() != 32 {
+            return Err(APIError::InvalidChannelID);
+        }
+        let channel_id = hex_str(&channel_id_vec.unwrap());
+
+        if payload.note.len() > CHANNEL_NOTE_MAX_LEN {
+            return Err(APIError::InvalidNote(format!(
+                "cannot be longer than {CHANNEL_NOTE_MAX_LEN} bytes"
+            )));
+        }
+
+        let is_open = unlocked_state
+            .channel_manager
+            .list_channels()
+            .iter()
+            .any(|c| c.channel_id.to_hex() == channel_id);
+        if !is_open && !unlocked_state.database.is_closed_channel(&channel_id)? {
+            return Err(APIError::UnknownChannel);
+        }
+
+        let note = Some(payload.note.as_str()).filter(|n| !n.is_empty());
+        unlocked_state
+            .database
+            .set_channel_note(&channel_id, note)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn set_label(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetLabelRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        check_label(&Some(payload.label.clone()))?;
+
+        match (payload.payment_hash, payload.recipient_id) {
+            (Some(payment_hash), None) => {
+                let payment_hash = hex_str_to_payment_hash(&payment_hash)
+                    .ok_or(APIError::InvalidPaymentHash(payment_hash))?;
+                if !unlocked_state.update_payment_label(payment_hash, payload.label) {
+                    return Err(APIError::UnknownPayment);
+                }
+            }
+            (None, Some(recipient_id)) => {
+                if !is_own_recipient_id(&unlocked_state, &recipient_id)? {
+                    return Err(APIError::UnknownRecipientID);
+                }
+                unlocked_state.set_rgb_invoice_label(&recipient_id, &payload.label);
+            }
+            _ => {
+                return Err(APIError::InvalidLabel(s!(
+                    "exactly one of payment_hash and recipient_id must be provided"
+                )))
+            }
+        }
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn set_payment_policy(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetPaymentPolicyRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let destinations = payload
+            .allowed_destinations
+            .map(|destinations| {
+                destinations
+                    .iter()
+                    .map(|d| {
+                        if let Some(pubkey) = hex_str_to_compressed_pubkey(d) {
+                            Ok(pubkey.to_string())
+                        } else if let Ok(secret_seal) = SecretSeal::from_str(d) {
+                            Ok(secret_seal.to_string())
+                        } else {
+                            Err(APIError::InvalidPaymentPolicy(format!(
+                                "{d} is neither a node pubkey nor a blinded UTXO"
+                            )))
+                        }
+                    })
+                    .collect::<Result<Vec<String>, APIError>>()
+            })
+            .transpose()?;
+
+        let max_asset_amounts = payload
+            .max_asset_amounts_per_day
+            .map(|limits| {
+                let mut max_asset_amounts = BTreeMap::new();
+                for limit in limits {
+                    let contract_id = ContractId::from_str(&limit.asset_id)
+                        .map_err(|_| APIError::InvalidAssetID(limit.asset_id))?;
+                    // limits are stored as SQLite integers
+                    if limit.max_amount > i64::MAX as u64 {
+                        return Err(APIError::InvalidPaymentPolicy(format!(
+                            "the limit for asset {contract_id} can't be more than {}",
+                            i64::MAX
+                        )));
+                    }
+                    max_asset_amounts.insert(contract_id.to_string(), limit.max_amount);
+                }
+                Ok(max_asset_amounts)
+            })
+            .transpose()?;
+
+        unlocked_state.database.update_payment_policy(
+            destinations.as_deref(),
+            payload.max_msat_per_day.map(|m| m.map(|m| m.0)),
+            max_asset_amounts.as_ref(),
+        )?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn set_peer_exposure_limits(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetPeerExposureLimitsRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
-        let _unlocked_app_state = state.get_unlocked_app_state();
-        state.check_changing_state()?;
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let peer_pubkey = match hex_str_to_compressed_pubkey(&payload.peer_pubkey) {
+            Some(pubkey) => pubkey,
+            None => return Err(APIError::InvalidPubkey),
+        };
+
+        let mut limits = PeerExposureLimits {
+            max_msat: payload.max_msat.map(|m| m.0),
+            ..Default::default()
+        };
+        for limit in payload.max_asset_amounts {
+            let contract_id = ContractId::from_str(&limit.asset_id)
+                .map_err(|_| APIError::InvalidAssetID(limit.asset_id))?;
+            limits
+                .max_asset_amounts
+                .insert(contract_id.to_string(), limit.max_amount);
+        }
+
+        unlocked_state
+            .database
+            .set_peer_exposure_limits(&peer_pubkey.to_string(), &limits)?;
 
-        state.cancel_token.cancel();
         Ok(Json(EmptyResponse {}))
     })
     .await
@@ -1878,9 +6019,8 @@ pub(crate) async fn sign_message(
 ) -> Result<Json<SignMessageResponse>, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let message = payload.message.trim();
     let signed_message = lightning::util::message_signing::sign(
-        &message.as_bytes()[message.len()..],
+        payload.message.as_bytes(),
         &unlocked_state.keys_manager.get_node_secret_key(),
     )
     .map_err(|e| APIError::FailedMessageSigning(e.to_string()))?;
@@ -1888,6 +6028,116 @@ pub(crate) async fn sign_message(
     Ok(Json(SignMessageResponse { signed_message }))
 }
 
+pub(crate) async fn spending_limits(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<SpendingLimitsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let limits = unlocked_state.database.spending_limits()?;
+    let usage = unlocked_state
+        .database
+        .spending_usage(get_current_timestamp().saturating_sub(SPENDING_WINDOW_SECS))?;
+
+    let msat = limits.max_msat.map(|limit| SpendingLimitStatus {
+        limit,
+        used: usage.msat,
+        remaining: limit.saturating_sub(usage.msat),
+    });
+    let assets = limits
+        .max_asset_amounts
+        .into_iter()
+        .map(|(asset_id, limit)| {
+            let used = usage.asset_amounts.get(&asset_id).copied().unwrap_or(0);
+            AssetSpendingLimitStatus {
+                asset_id,
+                limit,
+                used,
+                remaining: limit.saturating_sub(used),
+            }
+        })
+        .collect();
+
+    Ok(Json(SpendingLimitsResponse {
+        window_sec: SPENDING_WINDOW_SECS,
+        msat,
+        assets,
+    }))
+}
+
+pub(crate) async fn stats(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(params), _): WithRejection<Query<StatsParams>, APIError>,
+) -> Result<Json<StatsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    Ok(Json(payment_stats(
+        &unlocked_state,
+        &state.static_state,
+        params.period,
+    )?))
+}
+
+pub(crate) async fn swap_into_channel(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SwapAssetsRequest>, APIError>,
+) -> Result<Json<SwapAssetsResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let swap_id = start_swap(
+            &unlocked_state,
+            &state.static_state,
+            &payload.channel_id,
+            payload.asset_amount.0,
+            SwapDirection::IntoChannel,
+        )?;
+
+        Ok(Json(SwapAssetsResponse { swap_id }))
+    })
+    .await
+}
+
+pub(crate) async fn swap_out_of_channel(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SwapAssetsRequest>, APIError>,
+) -> Result<Json<SwapAssetsResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let swap_id = start_swap(
+            &unlocked_state,
+            &state.static_state,
+            &payload.channel_id,
+            payload.asset_amount.0,
+            SwapDirection::OutOfChannel,
+        )?;
+
+        Ok(Json(SwapAssetsResponse { swap_id }))
+    })
+    .await
+}
+
+pub(crate) async fn unban_peer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UnbanPeerRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let peer_pubkey = match bitcoin::secp256k1::PublicKey::from_str(&payload.peer_pubkey) {
+            Ok(pubkey) => pubkey,
+            Err(_e) => return Err(APIError::InvalidPubkey),
+        };
+
+        if !unlocked_state.peer_limiter.unban(&peer_pubkey) {
+            return Err(APIError::UnknownBan);
+        }
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
 pub(crate) async fn unlock(
     State(state): State<Arc<AppState>>,
     WithRejection(Json(payload), _): WithRejection<Json<UnlockRequest>, APIError>,
@@ -1915,9 +6165,30 @@ pub(crate) async fn unlock(
             }
         };
 
+        // the lock file may have been removed and taken by another daemon since startup
+        if let Err(e) = state.static_state.data_dir_lock.verify() {
+            state.update_changing_state(false);
+            return Err(e);
+        }
+
+        if let Err(e) = check_data_version(Path::new(&state.static_state.storage_dir_path)) {
+            state.update_changing_state(false);
+            return Err(e);
+        }
+
+        if let Err(e) = check_data_dir(&state.static_state, payload.regenerate) {
+            state.update_changing_state(false);
+            return Err(e);
+        }
+
+        let recovery_mode = payload.recovery_mode || state.static_state.recovery_mode;
+        if recovery_mode {
+            tracing::warn!("Unlocking in recovery mode");
+        }
+
         tracing::debug!("Starting LDK...");
         let (new_ldk_background_services, new_unlocked_app_state) =
-            match start_ldk(state.clone(), mnemonic).await {
+            match start_ldk(state.clone(), mnemonic, recovery_mode).await {
                 Ok((nlbs, nuap)) => (nlbs, nuap),
                 Err(e) => {
                     state.update_changing_state(false);
@@ -1933,9 +6204,63 @@ pub(crate) async fn unlock(
         state.update_ldk_background_services(Some(new_ldk_background_services));
 
         state.update_changing_state(false);
+        state.api_stopped.store(false, Ordering::Release);
+        *state.auto_unlock_failure.lock().unwrap() = None;
 
         tracing::info!("Unlock completed");
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
+
+/// Unlock the node with the password given at startup, without ever initializing it
+pub(crate) async fn auto_unlock(state: Arc<AppState>, password: String) {
+    let payload = UnlockRequest {
+        password,
+        regenerate: false,
+        recovery_mode: false,
+    };
+    let res = unlock(
+        State(state.clone()),
+        WithRejection(Json(payload), PhantomData),
+    )
+    .await;
+    match res {
+        Ok(_) => {}
+        // unlocked by an /unlock call in the meantime
+        Err(APIError::ChangingState | APIError::UnlockedNode) => {}
+        Err(e) => {
+            tracing::error!("Failed to unlock with the password given at startup: {e}");
+            *state.auto_unlock_failure.lock().unwrap() = Some(e.to_string());
+        }
+    }
+}
+
+pub(crate) async fn verify_message(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<VerifyMessageRequest>, APIError>,
+) -> Result<Json<VerifyMessageResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let pubkey = lightning::util::message_signing::recover_pk(
+        payload.message.as_bytes(),
+        &payload.signature,
+    )
+    .map_err(|e| APIError::InvalidSignature(e.to_string()))?;
+
+    let known_peer = unlocked_state
+        .peer_manager
+        .get_peer_node_ids()
+        .iter()
+        .any(|(p, _)| *p == pubkey)
+        || unlocked_state
+            .channel_manager
+            .list_channels()
+            .iter()
+            .any(|c| c.counterparty.node_id == pubkey);
+
+    Ok(Json(VerifyMessageResponse {
+        pubkey: pubkey.to_string(),
+        known_peer,
+    }))
+}
@@ -0,0 +1,114 @@
+use lightning::ln::PaymentHash;
+use lightning::rgb_utils::{get_rgb_payment_info_path, parse_rgb_payment_info};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::error::APIError;
+use crate::routes::{AssetVolume, HTLCStatus, PaymentVolume, StatsPeriod, StatsResponse};
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+impl StatsPeriod {
+    fn secs(&self) -> u64 {
+        match self {
+            StatsPeriod::Day => 24 * 60 * 60,
+            StatsPeriod::Week => 7 * 24 * 60 * 60,
+            StatsPeriod::Month => 30 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Settled payments of a direction, summed while going through the period
+#[derive(Default)]
+struct VolumeTotals {
+    count: u64,
+    amt_msat: u64,
+    latency_secs: u64,
+    assets: BTreeMap<String, AssetVolume>,
+}
+
+impl VolumeTotals {
+    fn add(&mut self, amt_msat: u64, latency_secs: u64, asset: Option<(String, u64)>) {
+        self.count += 1;
+        self.amt_msat += amt_msat;
+        self.latency_secs += latency_secs;
+        if let Some((asset_id, amount)) = asset {
+            let volume = self
+                .assets
+                .entry(asset_id.clone())
+                .or_insert_with(|| AssetVolume {
+                    asset_id,
+                    count: 0,
+                    amount: 0,
+                });
+            volume.count += 1;
+            volume.amount += amount;
+        }
+    }
+
+    fn into_volume(self) -> PaymentVolume {
+        PaymentVolume {
+            count: self.count,
+            amt_msat: self.amt_msat,
+            avg_settle_latency_secs: (self.count > 0)
+                .then(|| self.latency_secs as f64 / self.count as f64),
+            assets: self.assets.into_values().collect(),
+        }
+    }
+}
+
+/// Compute the payment statistics of the given rolling period
+///
+/// Payments are counted in the period they have been settled in, while outbound attempts are
+/// counted in the period they have been started in. Every attempt to pay the same payment hash
+/// shares a single record, so retries count as one payment.
+pub(crate) fn payment_stats(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    period: StatsPeriod,
+) -> Result<StatsResponse, APIError> {
+    let since = get_current_timestamp().saturating_sub(period.secs());
+    let ldk_data_dir = Path::new(&static_state.ldk_data_dir);
+
+    let mut received = VolumeTotals::default();
+    let mut sent = VolumeTotals::default();
+    let mut outbound_succeeded = 0;
+    let mut outbound_failed = 0;
+    for (id, inbound, info) in unlocked_state.database.payments_since(since)? {
+        if !inbound && info.created_at >= since {
+            match info.status {
+                HTLCStatus::Succeeded => outbound_succeeded += 1,
+                HTLCStatus::Failed => outbound_failed += 1,
+                HTLCStatus::Pending => {}
+            }
+        }
+
+        let Some(settled_at) = info.settled_at.filter(|s| *s >= since) else {
+            continue;
+        };
+        let rgb_payment_info_path = get_rgb_payment_info_path(&PaymentHash(id), ldk_data_dir);
+        let asset = rgb_payment_info_path.exists().then(|| {
+            let rgb_payment_info = parse_rgb_payment_info(&rgb_payment_info_path);
+            (
+                rgb_payment_info.contract_id.to_string(),
+                rgb_payment_info.amount,
+            )
+        });
+        let totals = if inbound { &mut received } else { &mut sent };
+        totals.add(
+            info.amt_msat.unwrap_or(0),
+            settled_at.saturating_sub(info.created_at),
+            asset,
+        );
+    }
+
+    let outbound_terminated = outbound_succeeded + outbound_failed;
+    Ok(StatsResponse {
+        period_start: since,
+        received: received.into_volume(),
+        sent: sent.into_volume(),
+        outbound_succeeded,
+        outbound_failed,
+        outbound_success_rate: (outbound_terminated > 0)
+            .then(|| outbound_succeeded as f64 / outbound_terminated as f64),
+    })
+}
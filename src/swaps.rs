@@ -0,0 +1,944 @@
+use amplify::{map, s};
+use bitcoin::hashes::hex::ToHex;
+use bitcoin::hashes::Hash;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Network;
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::channelmanager::{ChannelDetails, FailureCode, PaymentId, Retry};
+use lightning::ln::{PaymentHash, PaymentPreimage};
+use lightning::rgb_utils::{
+    get_rgb_channel_info_path, parse_rgb_channel_info, write_rgb_payment_info_file,
+};
+use lightning::sign::EntropySource;
+use lightning_invoice::payment::{pay_invoice, PaymentError};
+use lightning_invoice::{utils::create_invoice_from_channelmanager, Bolt11Invoice, Currency};
+use rgb_lib::wallet::{Recipient, RecipientData};
+use rgbstd::contract::{ContractId, SecretSeal};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::database::{Database, SwapRecord};
+use crate::error::APIError;
+use crate::ldk::{PaymentInfo, FEE_RATE, MIN_FINAL_CLTV_EXPIRY_DELTA};
+use crate::peer_messages::PeerMessage;
+use crate::routes::{FailureReason, HTLCStatus, Swap, SwapDirection, SwapStatus, HTLC_MIN_MSAT};
+use crate::utils::{get_current_timestamp, hex_str, hex_str_to_vec, StaticState, UnlockedAppState};
+
+/// Time the peer has to answer a swap request
+const SWAP_RESPONSE_TIMEOUT_SEC: u64 = 60;
+/// Time the node sending the assets on-chain waits for the HTLC of the other side
+const SWAP_INVOICE_EXPIRY_SEC: u32 = 600;
+const SWAP_PAYMENT_TIMEOUT_SEC: u64 = 60;
+
+/// Request to swap assets between the on-chain balance of the requester and a channel with the
+/// peer, `into_channel` telling which way from the requester point of view
+///
+/// The node sending the assets on-chain provides the invoice for the Lightning leg, the other one
+/// provides the RGB recipient for the on-chain leg.
+#[derive(Clone, Debug)]
+pub(crate) struct SwapRequest {
+    pub(crate) swap_id: String,
+    pub(crate) into_channel: bool,
+    pub(crate) channel_id: String,
+    pub(crate) asset_id: String,
+    pub(crate) asset_amount: u64,
+    pub(crate) invoice: Option<String>,
+    pub(crate) recipient_id: Option<String>,
+    pub(crate) transport_endpoints: Vec<String>,
+}
+
+impl_writeable_tlv_based!(SwapRequest, {
+    (0, swap_id, required),
+    (2, into_channel, required),
+    (4, channel_id, required),
+    (6, asset_id, required),
+    (8, asset_amount, required),
+    (10, invoice, option),
+    (12, recipient_id, option),
+    (14, transport_endpoints, required_vec),
+});
+
+/// Answer of the peer to a swap request, sent again as a rejection if the peer gives up on an
+/// accepted swap
+#[derive(Clone, Debug)]
+pub(crate) struct SwapResponse {
+    pub(crate) swap_id: String,
+    pub(crate) accepted: bool,
+    pub(crate) invoice: Option<String>,
+    pub(crate) recipient_id: Option<String>,
+    pub(crate) transport_endpoints: Vec<String>,
+    pub(crate) reason: Option<String>,
+}
+
+impl_writeable_tlv_based!(SwapResponse, {
+    (0, swap_id, required),
+    (2, accepted, required),
+    (4, invoice, option),
+    (6, recipient_id, option),
+    (8, transport_endpoints, required_vec),
+    (10, reason, option),
+});
+
+impl SwapResponse {
+    fn rejected(swap_id: String, reason: String) -> Self {
+        Self {
+            swap_id,
+            accepted: false,
+            invoice: None,
+            recipient_id: None,
+            transport_endpoints: vec![],
+            reason: Some(reason),
+        }
+    }
+}
+
+/// Swaps with the channel peers, written to the database on every change
+pub(crate) struct Swaps {
+    swaps: Mutex<BTreeMap<String, SwapRecord>>,
+    database: Arc<Database>,
+}
+
+impl Swaps {
+    pub(crate) fn load(database: Arc<Database>) -> Result<Self, APIError> {
+        let swaps = database
+            .swaps()?
+            .into_iter()
+            .map(|s| (s.swap.swap_id.clone(), s))
+            .collect();
+        Ok(Self {
+            swaps: Mutex::new(swaps),
+            database,
+        })
+    }
+
+    fn save(&self, record: &SwapRecord) {
+        if let Err(e) = self.database.save_swap(record) {
+            tracing::error!("Failed to save swap {}: {e}", record.swap.swap_id);
+        }
+    }
+
+    fn add(
+        &self,
+        swap: Swap,
+        recipient: Option<(String, Vec<String>)>,
+        expires_at: Option<u64>,
+    ) -> Result<(), APIError> {
+        let record = SwapRecord {
+            swap,
+            recipient,
+            expires_at,
+            preimage: None,
+        };
+        self.database.save_swap(&record)?;
+        self.swaps
+            .lock()
+            .unwrap()
+            .insert(record.swap.swap_id.clone(), record);
+        Ok(())
+    }
+
+    fn contains(&self, swap_id: &str) -> bool {
+        self.swaps.lock().unwrap().contains_key(swap_id)
+    }
+
+    /// Run `f` on a pending swap, found by ID or by payment hash
+    fn with_pending<T>(
+        &self,
+        swap_id: Option<&str>,
+        payment_hash: Option<&str>,
+        f: impl FnOnce(&mut SwapRecord) -> T,
+    ) -> Option<T> {
+        let mut swaps = self.swaps.lock().unwrap();
+        let record = swaps.values_mut().find(|s| {
+            s.swap.status == SwapStatus::Pending
+                && swap_id.map_or(true, |id| s.swap.swap_id == id)
+                && payment_hash.map_or(true, |h| s.swap.payment_hash.as_deref() == Some(h))
+        })?;
+        let result = f(record);
+        self.save(record);
+        Some(result)
+    }
+
+    /// Record the preimage of the HTLC paying a swap before its assets get sent on-chain,
+    /// returning the swap and whether the preimage had already been recorded, which means the
+    /// node stopped while completing it
+    ///
+    /// Returns None if the payment is not part of a swap sending assets on-chain.
+    fn record_preimage(
+        &self,
+        payment_hash: &str,
+        preimage: &PaymentPreimage,
+    ) -> Option<(SwapRecord, Result<bool, APIError>)> {
+        let mut swaps = self.swaps.lock().unwrap();
+        let record = swaps.values_mut().find(|s| {
+            s.swap.status == SwapStatus::Pending
+                && s.swap.direction == SwapDirection::IntoChannel
+                && s.swap.payment_hash.as_deref() == Some(payment_hash)
+        })?;
+        if record.preimage.is_some() {
+            return Some((record.clone(), Ok(true)));
+        }
+        let mut updated = record.clone();
+        updated.expires_at = None;
+        updated.preimage = Some(hex_str(&preimage.0));
+        if let Err(e) = self.database.save_swap(&updated) {
+            return Some((record.clone(), Err(e)));
+        }
+        *record = updated.clone();
+        Some((updated, Ok(false)))
+    }
+
+    /// Swaps that were sending their assets on-chain when the node stopped
+    fn interrupted(&self) -> Vec<SwapRecord> {
+        self.swaps
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|s| s.swap.status == SwapStatus::Pending && s.preimage.is_some())
+            .cloned()
+            .collect()
+    }
+
+    fn finish(&self, swap_id: &str, result: Result<Option<String>, String>) {
+        let mut swaps = self.swaps.lock().unwrap();
+        let Some(state) = swaps.get_mut(swap_id) else {
+            return;
+        };
+        if state.swap.status != SwapStatus::Pending {
+            return;
+        }
+        state.expires_at = None;
+        match result {
+            Ok(txid) => {
+                tracing::info!("Swap {swap_id} succeeded");
+                state.swap.status = SwapStatus::Succeeded;
+                if txid.is_some() {
+                    state.swap.txid = txid;
+                }
+            }
+            Err(reason) => {
+                tracing::info!("Swap {swap_id} failed: {reason}");
+                state.swap.status = SwapStatus::Failed;
+                state.swap.reason = Some(reason);
+            }
+        }
+        self.save(state);
+    }
+
+    pub(crate) fn list(&self) -> Vec<Swap> {
+        let now = get_current_timestamp();
+        let mut swaps = self.swaps.lock().unwrap();
+        for state in swaps.values_mut() {
+            if state.swap.status == SwapStatus::Pending && state.expires_at.is_some_and(|t| t < now)
+            {
+                state.swap.status = SwapStatus::Failed;
+                state.swap.reason = Some(s!("timed out waiting for the peer"));
+                state.expires_at = None;
+                self.save(state);
+            }
+        }
+        swaps.values().map(|s| s.swap.clone()).collect()
+    }
+}
+
+/// The usable channel with the peer and the assets each side has in it
+fn swap_channel(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    channel_id: &str,
+    peer_pubkey: Option<PublicKey>,
+) -> Result<(ChannelDetails, ContractId, u64, u64), String> {
+    let chan_info = unlocked_state
+        .channel_manager
+        .list_channels()
+        .into_iter()
+        .find(|c| {
+            c.channel_id.to_hex() == channel_id
+                && peer_pubkey.map_or(true, |p| c.counterparty.node_id == p)
+        })
+        .ok_or(s!("unknown channel"))?;
+    if !chan_info.is_usable {
+        return Err(s!("the channel is not usable"));
+    }
+    let info_file_path = get_rgb_channel_info_path(
+        channel_id,
+        &PathBuf::from(&static_state.ldk_data_dir),
+        false,
+    );
+    if !info_file_path.exists() {
+        return Err(s!("the channel carries no asset"));
+    }
+    let rgb_info = parse_rgb_channel_info(&info_file_path);
+    Ok((
+        chan_info,
+        rgb_info.contract_id,
+        rgb_info.local_rgb_amount,
+        rgb_info.remote_rgb_amount,
+    ))
+}
+
+fn check_onchain_balance(
+    unlocked_state: &UnlockedAppState,
+    contract_id: ContractId,
+    asset_amount: u64,
+) -> Result<(), String> {
+    let spendable = unlocked_state
+        .rgb_get_asset_balance(contract_id)
+        .map_err(|_| s!("unknown asset"))?
+        .spendable;
+    if asset_amount > spendable {
+        return Err(format!(
+            "only {spendable} of the asset is spendable on-chain"
+        ));
+    }
+    Ok(())
+}
+
+/// Invoice for the Lightning leg, paid by the node sending the assets over the channel
+fn create_swap_invoice(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    swap_id: &str,
+    contract_id: ContractId,
+    asset_amount: u64,
+) -> Result<Bolt11Invoice, String> {
+    let currency = match static_state.network {
+        Network::Bitcoin => Currency::Bitcoin,
+        Network::Testnet => Currency::BitcoinTestnet,
+        Network::Regtest => Currency::Regtest,
+        Network::Signet => Currency::Signet,
+    };
+    let invoice = create_invoice_from_channelmanager(
+        &unlocked_state.channel_manager,
+        unlocked_state.keys_manager.clone(),
+        static_state.logger.clone(),
+        currency,
+        Some(HTLC_MIN_MSAT),
+        format!("swap {swap_id}"),
+        SWAP_INVOICE_EXPIRY_SEC,
+        Some(MIN_FINAL_CLTV_EXPIRY_DELTA),
+        Some(contract_id),
+        Some(asset_amount),
+    )
+    .map_err(|e| format!("failed to create the swap invoice: {e}"))?;
+    unlocked_state.add_inbound_payment(
+        PaymentHash((*invoice.payment_hash()).into_inner()),
+        PaymentInfo {
+            preimage: None,
+            secret: Some(*invoice.payment_secret()),
+            status: HTLCStatus::Pending,
+            amt_msat: Some(HTLC_MIN_MSAT),
+            label: Some(format!("swap {swap_id}")),
+            created_at: get_current_timestamp(),
+            expires_at: Some(
+                invoice.duration_since_epoch().as_secs() + invoice.expiry_time().as_secs(),
+            ),
+            failure_reason: None,
+            failing_hop: None,
+            settled_at: None,
+            payer_requirements: None,
+            payer: None,
+            payee: None,
+            onchain_txid: None,
+            closure: None,
+            hops: vec![],
+        },
+    );
+    Ok(invoice)
+}
+
+/// Check the invoice of the peer asks for the agreed Lightning leg
+fn parse_swap_invoice(
+    invoice: Option<String>,
+    peer_pubkey: PublicKey,
+    contract_id: ContractId,
+    asset_amount: u64,
+) -> Result<Bolt11Invoice, String> {
+    let invoice = Bolt11Invoice::from_str(&invoice.ok_or(s!("missing invoice"))?)
+        .map_err(|e| format!("invalid invoice: {e}"))?;
+    if invoice.recover_payee_pub_key() != peer_pubkey
+        || invoice.amount_milli_satoshis() != Some(HTLC_MIN_MSAT)
+        || invoice.rgb_contract_id() != Some(contract_id)
+        || invoice.rgb_amount() != Some(asset_amount)
+    {
+        return Err(s!("the invoice doesn't match the swap"));
+    }
+    Ok(invoice)
+}
+
+fn pay_swap_invoice(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    swap_id: &str,
+    invoice: &Bolt11Invoice,
+) -> Result<(), String> {
+    let payment_hash = PaymentHash((*invoice.payment_hash()).into_inner());
+    write_rgb_payment_info_file(
+        &PathBuf::from(&static_state.ldk_data_dir),
+        &payment_hash,
+        invoice.rgb_contract_id().unwrap(),
+        invoice.rgb_amount().unwrap(),
+        false,
+    );
+    let payment_id = PaymentId(payment_hash.0);
+    unlocked_state.add_outbound_payment(
+        payment_id,
+        PaymentInfo {
+            preimage: None,
+            secret: Some(*invoice.payment_secret()),
+            status: HTLCStatus::Pending,
+            amt_msat: invoice.amount_milli_satoshis(),
+            label: Some(format!("swap {swap_id}")),
+            created_at: get_current_timestamp(),
+            expires_at: None,
+            failure_reason: None,
+            failing_hop: None,
+            settled_at: None,
+            payer_requirements: None,
+            payer: None,
+            payee: Some(invoice.recover_payee_pub_key()),
+            onchain_txid: None,
+            closure: None,
+            hops: vec![],
+        },
+    );
+    let retry = Retry::Timeout(Duration::from_secs(SWAP_PAYMENT_TIMEOUT_SEC));
+    pay_invoice(invoice, retry, &*unlocked_state.channel_manager).map_err(|e| {
+        let failure_reason = match e {
+            PaymentError::Sending(ref e) => FailureReason::from(e),
+            PaymentError::Invoice(_) => FailureReason::UnexpectedError,
+        };
+        unlocked_state.fail_outbound_payment(static_state, payment_id, failure_reason, None);
+        format!("failed to pay the swap invoice: {e:?}")
+    })
+}
+
+/// Start a swap with the peer of a channel, returning the swap ID
+pub(crate) fn start_swap(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    channel_id: &str,
+    asset_amount: u64,
+    direction: SwapDirection,
+) -> Result<String, APIError> {
+    if asset_amount == 0 {
+        return Err(APIError::InvalidAmount(s!(
+            "asset amount must be greater than 0"
+        )));
+    }
+    static_state.check_writable()?;
+    let (chan_info, contract_id, local_amount, remote_amount) =
+        swap_channel(unlocked_state, static_state, channel_id, None)
+            .map_err(APIError::SwapNotPossible)?;
+    let peer_pubkey = chan_info.counterparty.node_id;
+    if !unlocked_state
+        .peer_manager
+        .get_peer_node_ids()
+        .iter()
+        .any(|(id, _)| *id == peer_pubkey)
+    {
+        return Err(APIError::PeerNotConnected(peer_pubkey.to_string()));
+    }
+
+    let swap_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+    let mut swap = Swap {
+        swap_id: swap_id.clone(),
+        direction,
+        initiator: true,
+        channel_id: channel_id.to_string(),
+        peer_pubkey: peer_pubkey.to_string(),
+        asset_id: contract_id.to_string(),
+        asset_amount,
+        status: SwapStatus::Pending,
+        payment_hash: None,
+        txid: None,
+        reason: None,
+        created_at: get_current_timestamp(),
+    };
+    let mut request = SwapRequest {
+        swap_id: swap_id.clone(),
+        into_channel: direction == SwapDirection::IntoChannel,
+        channel_id: channel_id.to_string(),
+        asset_id: contract_id.to_string(),
+        asset_amount,
+        invoice: None,
+        recipient_id: None,
+        transport_endpoints: vec![],
+    };
+    match direction {
+        SwapDirection::IntoChannel => {
+            check_onchain_balance(unlocked_state, contract_id, asset_amount)
+                .map_err(APIError::SwapNotPossible)?;
+            if asset_amount > remote_amount {
+                return Err(APIError::SwapNotPossible(format!(
+                    "the peer has only {remote_amount} of the asset in the channel"
+                )));
+            }
+            let invoice = create_swap_invoice(
+                unlocked_state,
+                static_state,
+                &swap_id,
+                contract_id,
+                asset_amount,
+            )
+            .map_err(APIError::SwapNotPossible)?;
+            swap.payment_hash = Some(hex_str(invoice.payment_hash().as_inner()));
+            request.invoice = Some(invoice.to_string());
+        }
+        SwapDirection::OutOfChannel => {
+            if asset_amount > local_amount {
+                return Err(APIError::SwapNotPossible(format!(
+                    "the node has only {local_amount} of the asset in the channel"
+                )));
+            }
+            let receive_data = unlocked_state.rgb_blind_receive(
+                Some(contract_id.to_string()),
+                None,
+                static_state.proxy_endpoints.clone(),
+                static_state.default_min_confirmations,
+            )?;
+            request.recipient_id = Some(receive_data.recipient_id);
+            request.transport_endpoints = static_state.proxy_endpoints.clone();
+        }
+    }
+
+    unlocked_state.swaps.add(
+        swap,
+        None,
+        Some(get_current_timestamp() + SWAP_RESPONSE_TIMEOUT_SEC),
+    )?;
+    tracing::info!("Requesting swap {swap_id} ({direction:?}) to {peer_pubkey}");
+    unlocked_state.send_peer_message(peer_pubkey, PeerMessage::SwapRequest(request));
+    Ok(swap_id)
+}
+
+pub(crate) fn handle_swap_request(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    peer_pubkey: PublicKey,
+    request: SwapRequest,
+) {
+    tracing::info!(
+        "Received swap request {} from {}",
+        request.swap_id,
+        peer_pubkey
+    );
+    let swap_id = request.swap_id.clone();
+    match accept_swap(unlocked_state, static_state, peer_pubkey, request) {
+        Ok((response, invoice)) => {
+            unlocked_state.send_peer_message(peer_pubkey, PeerMessage::SwapResponse(response));
+            if let Some(invoice) = invoice {
+                if let Err(reason) =
+                    pay_swap_invoice(unlocked_state, static_state, &swap_id, &invoice)
+                {
+                    give_up(unlocked_state, peer_pubkey, &swap_id, reason);
+                }
+            }
+        }
+        Err(reason) => {
+            tracing::info!("Rejected swap request {swap_id}: {reason}");
+            unlocked_state.send_peer_message(
+                peer_pubkey,
+                PeerMessage::SwapResponse(SwapResponse::rejected(swap_id, reason)),
+            );
+        }
+    }
+}
+
+/// Accept a swap, returning the response and the invoice to pay once it has been sent
+fn accept_swap(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    peer_pubkey: PublicKey,
+    request: SwapRequest,
+) -> Result<(SwapResponse, Option<Bolt11Invoice>), String> {
+    if unlocked_state.swaps.contains(&request.swap_id) {
+        return Err(s!("duplicate swap ID"));
+    }
+    let (_, contract_id, local_amount, remote_amount) = swap_channel(
+        unlocked_state,
+        static_state,
+        &request.channel_id,
+        Some(peer_pubkey),
+    )?;
+    if contract_id.to_string() != request.asset_id {
+        return Err(s!("the channel carries another asset"));
+    }
+
+    let mut swap = Swap {
+        swap_id: request.swap_id.clone(),
+        // the swap goes the other way for this side
+        direction: if request.into_channel {
+            SwapDirection::OutOfChannel
+        } else {
+            SwapDirection::IntoChannel
+        },
+        initiator: false,
+        channel_id: request.channel_id.clone(),
+        peer_pubkey: peer_pubkey.to_string(),
+        asset_id: request.asset_id.clone(),
+        asset_amount: request.asset_amount,
+        status: SwapStatus::Pending,
+        payment_hash: None,
+        txid: None,
+        reason: None,
+        created_at: get_current_timestamp(),
+    };
+    let mut response = SwapResponse {
+        swap_id: request.swap_id.clone(),
+        accepted: true,
+        invoice: None,
+        recipient_id: None,
+        transport_endpoints: vec![],
+        reason: None,
+    };
+
+    if request.into_channel {
+        // the peer sends the assets on-chain, this node over the channel
+        if request.asset_amount > local_amount {
+            return Err(format!(
+                "the node has only {local_amount} of the asset in the channel"
+            ));
+        }
+        let invoice = parse_swap_invoice(
+            request.invoice,
+            peer_pubkey,
+            contract_id,
+            request.asset_amount,
+        )?;
+        let receive_data = unlocked_state
+            .rgb_blind_receive(
+                Some(request.asset_id),
+                None,
+                static_state.proxy_endpoints.clone(),
+                static_state.default_min_confirmations,
+            )
+            .map_err(|e| format!("failed to create the RGB recipient: {e}"))?;
+        swap.payment_hash = Some(hex_str(invoice.payment_hash().as_inner()));
+        response.recipient_id = Some(receive_data.recipient_id);
+        response.transport_endpoints = static_state.proxy_endpoints.clone();
+        // the outcome depends on the payment from now on
+        unlocked_state
+            .swaps
+            .add(swap, None, None)
+            .map_err(|e| format!("failed to record the swap: {e}"))?;
+        Ok((response, Some(invoice)))
+    } else {
+        // the peer sends the assets over the channel, this node on-chain
+        if request.asset_amount > remote_amount {
+            return Err(format!(
+                "the peer has only {remote_amount} of the asset in the channel"
+            ));
+        }
+        check_onchain_balance(unlocked_state, contract_id, request.asset_amount)?;
+        let recipient_id = request.recipient_id.ok_or(s!("missing RGB recipient"))?;
+        SecretSeal::from_str(&recipient_id).map_err(|_| s!("invalid RGB recipient"))?;
+        let invoice = create_swap_invoice(
+            unlocked_state,
+            static_state,
+            &request.swap_id,
+            contract_id,
+            request.asset_amount,
+        )?;
+        swap.payment_hash = Some(hex_str(invoice.payment_hash().as_inner()));
+        response.invoice = Some(invoice.to_string());
+        unlocked_state
+            .swaps
+            .add(
+                swap,
+                Some((recipient_id, request.transport_endpoints)),
+                Some(get_current_timestamp() + SWAP_INVOICE_EXPIRY_SEC as u64),
+            )
+            .map_err(|e| format!("failed to record the swap: {e}"))?;
+        Ok((response, None))
+    }
+}
+
+pub(crate) fn handle_swap_response(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    peer_pubkey: PublicKey,
+    response: SwapResponse,
+) {
+    let swap_id = response.swap_id.clone();
+    let Some(swap) = unlocked_state
+        .swaps
+        .with_pending(Some(&swap_id), None, |s| s.swap.clone())
+        .filter(|s| s.peer_pubkey == peer_pubkey.to_string())
+    else {
+        tracing::debug!("Ignoring response from {peer_pubkey} to swap {swap_id}");
+        return;
+    };
+    if !response.accepted {
+        let reason = response.reason.unwrap_or(s!("unknown reason"));
+        unlocked_state
+            .swaps
+            .finish(&swap_id, Err(format!("rejected by the peer: {reason}")));
+        return;
+    }
+    if !swap.initiator {
+        return;
+    }
+
+    let result = match swap.direction {
+        SwapDirection::IntoChannel => match response.recipient_id {
+            Some(recipient_id) if SecretSeal::from_str(&recipient_id).is_ok() => {
+                unlocked_state
+                    .swaps
+                    .with_pending(Some(&swap_id), None, |s| {
+                        s.recipient = Some((recipient_id, response.transport_endpoints));
+                        s.expires_at =
+                            Some(get_current_timestamp() + SWAP_INVOICE_EXPIRY_SEC as u64);
+                    });
+                Ok(())
+            }
+            _ => Err(s!("the peer provided no valid RGB recipient")),
+        },
+        SwapDirection::OutOfChannel => {
+            let contract_id = ContractId::from_str(&swap.asset_id).unwrap();
+            parse_swap_invoice(
+                response.invoice,
+                peer_pubkey,
+                contract_id,
+                swap.asset_amount,
+            )
+            .and_then(|invoice| {
+                unlocked_state
+                    .swaps
+                    .with_pending(Some(&swap_id), None, |s| {
+                        s.swap.payment_hash = Some(hex_str(invoice.payment_hash().as_inner()));
+                        s.expires_at = None;
+                    });
+                pay_swap_invoice(unlocked_state, static_state, &swap_id, &invoice)
+            })
+        }
+    };
+    if let Err(reason) = result {
+        give_up(unlocked_state, peer_pubkey, &swap_id, reason);
+    }
+}
+
+/// Fail a swap accepted by both sides, letting the peer know
+fn give_up(
+    unlocked_state: &UnlockedAppState,
+    peer_pubkey: PublicKey,
+    swap_id: &str,
+    reason: String,
+) {
+    unlocked_state.swaps.finish(swap_id, Err(reason.clone()));
+    unlocked_state.send_peer_message(
+        peer_pubkey,
+        PeerMessage::SwapResponse(SwapResponse::rejected(swap_id.to_string(), reason)),
+    );
+}
+
+/// Handle an HTLC of a swap paying this node, sending the assets on-chain before claiming it
+///
+/// The HTLC is claimed once the transfer is broadcast, without waiting for it to confirm, so the
+/// payer trusts this node to send a valid transfer. The preimage is recorded first, so that a node stopping before claiming the HTLC can still
+/// claim it once restarted. The HTLC is failed back if the assets can't be sent. Returns false if
+/// the payment is not part of a swap.
+pub(crate) fn handle_claimable_payment(
+    unlocked_state: Arc<UnlockedAppState>,
+    #[cfg_attr(not(test), allow(unused_variables))] static_state: Arc<StaticState>,
+    payment_hash: &PaymentHash,
+    payment_preimage: PaymentPreimage,
+) -> bool {
+    let Some((swap, recorded)) = unlocked_state
+        .swaps
+        .record_preimage(&hex_str(&payment_hash.0), &payment_preimage)
+    else {
+        return false;
+    };
+    match recorded {
+        Ok(false) => {}
+        // the swap is resumed at startup
+        Ok(true) => return true,
+        Err(e) => {
+            let reason = format!("failed to record the swap: {e}");
+            complete_swap(&unlocked_state, &swap, Err(reason));
+            return true;
+        }
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let result = swap
+            .recipient
+            .clone()
+            .ok_or(s!("the peer provided no RGB recipient"))
+            .and_then(|(recipient_id, transport_endpoints)| {
+                send_onchain(
+                    &unlocked_state,
+                    &swap.swap,
+                    recipient_id,
+                    transport_endpoints,
+                )
+            });
+        #[cfg(test)]
+        if result.is_ok()
+            && static_state
+                .payment_faults
+                .stop_swap_claims
+                .load(std::sync::atomic::Ordering::Acquire)
+        {
+            return;
+        }
+        if let Ok(txid) = &result {
+            unlocked_state
+                .swaps
+                .with_pending(Some(&swap.swap.swap_id), None, |s| {
+                    s.swap.txid = Some(txid.clone())
+                });
+        }
+        complete_swap(&unlocked_state, &swap, result);
+    });
+    true
+}
+
+/// Resume the swaps that were sending their assets on-chain when the node stopped
+///
+/// The HTLC gets claimed if the assets have been sent, failed back otherwise.
+pub(crate) fn resume_swaps(unlocked_state: &Arc<UnlockedAppState>) {
+    for swap in unlocked_state.swaps.interrupted() {
+        tracing::info!("Resuming swap {}", swap.swap.swap_id);
+        let unlocked_state = Arc::clone(unlocked_state);
+        tokio::task::spawn_blocking(move || {
+            let result = match sent_onchain(&unlocked_state, &swap) {
+                Ok(Some(txid)) => Ok(txid),
+                Ok(None) => Err(s!("the node stopped before sending the assets on-chain")),
+                Err(e) => {
+                    tracing::error!("Failed to resume swap {}: {e}", swap.swap.swap_id);
+                    return;
+                }
+            };
+            complete_swap(&unlocked_state, &swap, result);
+        });
+    }
+}
+
+/// TXID of the on-chain leg of the swap, if the assets have already been sent
+fn sent_onchain(
+    unlocked_state: &UnlockedAppState,
+    swap: &SwapRecord,
+) -> Result<Option<String>, APIError> {
+    if swap.swap.txid.is_some() {
+        return Ok(swap.swap.txid.clone());
+    }
+    let Some((recipient_id, _)) = &swap.recipient else {
+        return Ok(None);
+    };
+    Ok(unlocked_state
+        .rgb_list_transfers(swap.swap.asset_id.clone())?
+        .into_iter()
+        .find(|t| {
+            matches!(t.kind, rgb_lib::TransferKind::Send)
+                && t.recipient_id.as_ref() == Some(recipient_id)
+        })
+        .and_then(|t| t.txid))
+}
+
+/// Claim the HTLC of a swap once its assets have been sent on-chain, fail it back otherwise
+fn complete_swap(
+    unlocked_state: &UnlockedAppState,
+    swap: &SwapRecord,
+    result: Result<String, String>,
+) {
+    match result {
+        Ok(txid) => {
+            let preimage = swap
+                .preimage
+                .as_deref()
+                .and_then(hex_str_to_vec)
+                .and_then(|p| p.try_into().ok())
+                .map(PaymentPreimage)
+                .expect("recorded preimage");
+            unlocked_state.channel_manager.claim_funds(preimage);
+            unlocked_state
+                .swaps
+                .finish(&swap.swap.swap_id, Ok(Some(txid)));
+        }
+        Err(reason) => {
+            let payment_hash = swap
+                .swap
+                .payment_hash
+                .as_deref()
+                .and_then(hex_str_to_vec)
+                .and_then(|h| h.try_into().ok())
+                .map(PaymentHash)
+                .expect("swap payment hash");
+            unlocked_state
+                .channel_manager
+                .fail_htlc_backwards_with_reason(
+                    &payment_hash,
+                    FailureCode::IncorrectOrUnknownPaymentDetails,
+                );
+            unlocked_state.swaps.finish(&swap.swap.swap_id, Err(reason));
+        }
+    }
+}
+
+fn send_onchain(
+    unlocked_state: &UnlockedAppState,
+    swap: &Swap,
+    recipient_id: String,
+    transport_endpoints: Vec<String>,
+) -> Result<String, String> {
+    let secret_seal =
+        SecretSeal::from_str(&recipient_id).map_err(|_| s!("invalid RGB recipient"))?;
+    // recorded before sending, so concurrent sends to the same blinded UTXO get rejected too
+    let blinded_utxo_added = unlocked_state
+        .database
+        .add_sent_blinded_utxo(&recipient_id, &swap.asset_id, get_current_timestamp())
+        .map_err(|e| e.to_string())?;
+    let recipient_map = map! {
+        swap.asset_id.clone() => vec![Recipient {
+            recipient_data: RecipientData::BlindedUTXO(secret_seal),
+            amount: swap.asset_amount,
+            transport_endpoints,
+        }]
+    };
+    // the HTLC can't wait for the peer to acknowledge the consignment
+    unlocked_state
+        .rgb_send(recipient_map, true, FEE_RATE, 1)
+        .map_err(|e| {
+            if blinded_utxo_added {
+                let _ = unlocked_state
+                    .database
+                    .remove_sent_blinded_utxo(&recipient_id);
+            }
+            format!("failed to send the assets on-chain: {e}")
+        })
+}
+
+/// Complete the swap, if any, whose Lightning leg this node has paid
+pub(crate) fn handle_payment_sent(unlocked_state: &UnlockedAppState, payment_hash: &PaymentHash) {
+    if let Some(swap_id) =
+        unlocked_state
+            .swaps
+            .with_pending(None, Some(&hex_str(&payment_hash.0)), |s| {
+                s.swap.swap_id.clone()
+            })
+    {
+        unlocked_state.swaps.finish(&swap_id, Ok(None));
+    }
+}
+
+/// Fail the swap, if any, whose Lightning leg this node couldn't pay
+pub(crate) fn handle_payment_failed(unlocked_state: &UnlockedAppState, payment_hash: &PaymentHash) {
+    if let Some((swap_id, peer_pubkey)) =
+        unlocked_state
+            .swaps
+            .with_pending(None, Some(&hex_str(&payment_hash.0)), |s| {
+                (s.swap.swap_id.clone(), s.swap.peer_pubkey.clone())
+            })
+    {
+        let reason = s!("the Lightning payment failed");
+        match PublicKey::from_str(&peer_pubkey) {
+            Ok(peer_pubkey) => give_up(unlocked_state, peer_pubkey, &swap_id, reason),
+            Err(_) => unlocked_state.swaps.finish(&swap_id, Err(reason)),
+        }
+    }
+}
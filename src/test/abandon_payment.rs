@@ -0,0 +1,159 @@
+use crate::ldk::{PaymentFaults, HTLC_EXPIRY_WARNING_BLOCKS};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/abandon_payment/";
+const NODE1_PEER_PORT: u16 = 10367;
+const NODE2_PEER_PORT: u16 = 10368;
+
+async fn abandon_payment(node_address: SocketAddr, payment_hash: &str) {
+    let payload = AbandonPaymentRequest {
+        payment_hash: payment_hash.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/abandonpayment", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn abandon_payment_in_flight() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let payment_faults = Arc::new(PaymentFaults::default());
+    let (node2_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node2"),
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            payment_faults: Arc::clone(&payment_faults),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    set_spending_limits(
+        node1_addr,
+        None,
+        vec![AssetSpendingLimit {
+            asset_id: asset_id.clone(),
+            max_amount: 500,
+        }],
+    )
+    .await;
+    assert_eq!(spending_limits(node1_addr).await.assets[0].used, 0);
+
+    // node2 doesn't settle the payment, leaving its HTLC in-flight
+    payment_faults
+        .hold_claimable_payments
+        .store(true, Ordering::Release);
+    let invoice = ln_invoice(node2_addr, &asset_id, 100, 900).await.invoice;
+    let payment_hash = send_payment_raw(node1_addr, invoice).await.payment_hash;
+    let t_0 = OffsetDateTime::now_utc();
+    let htlc = loop {
+        if let Some(htlc) = list_htlcs(node2_addr)
+            .await
+            .into_iter()
+            .find(|h| h.payment_hash == payment_hash)
+        {
+            break htlc;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("HTLC not pending")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+
+    // the abandoned payment stays pending as long as its HTLC is in-flight
+    abandon_payment(node1_addr, &payment_hash).await;
+    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+    check_payment_status(node1_addr, &payment_hash, HTLCStatus::Pending)
+        .await
+        .unwrap();
+    assert_eq!(
+        asset_balance_offchain_outbound(node1_addr, &asset_id).await,
+        600
+    );
+    assert_eq!(
+        asset_balance_offchain_outbound(node2_addr, &asset_id).await,
+        0
+    );
+    assert_eq!(spending_limits(node1_addr).await.assets[0].used, 100);
+
+    // once the HTLC gets failed back the payment fails and the assets are back on node1's side
+    mine_n_blocks(
+        false,
+        (htlc.blocks_to_force_close - HTLC_EXPIRY_WARNING_BLOCKS + 1) as u16,
+    );
+    let payment = wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+    assert_eq!(payment.failure_reason, Some(FailureReason::UserAbandoned));
+    assert_eq!(
+        get_payment(node1_addr, &payment_hash).await.failure_reason,
+        Some(FailureReason::UserAbandoned)
+    );
+    // the abandoned amount no longer counts towards the spending limit
+    assert_eq!(spending_limits(node1_addr).await.assets[0].used, 0);
+    assert!(list_htlcs(node2_addr).await.is_empty());
+    let node1_balance = asset_balance(node1_addr, &asset_id).await;
+    assert_eq!(node1_balance.offchain_outbound, 600);
+    assert_eq!(node1_balance.offchain_inbound, 0);
+    let node2_balance = asset_balance(node2_addr, &asset_id).await;
+    assert_eq!(node2_balance.offchain_outbound, 0);
+    assert_eq!(node2_balance.offchain_inbound, 600);
+    let channels = list_channels(node1_addr).await;
+    let channel = channels
+        .iter()
+        .find(|c| c.channel_id == channel.channel_id)
+        .unwrap();
+    assert_eq!(channel.asset_local_amount, Some(600));
+    assert_eq!(channel.asset_remote_amount, Some(0));
+
+    // the channel keeps working with the reconciled amounts
+    payment_faults
+        .hold_claimable_payments
+        .store(false, Ordering::Release);
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice.clone(), 600, 0).await;
+    let node2_balance = asset_balance(node2_addr, &asset_id).await;
+    assert_eq!(node2_balance.offchain_outbound, 100);
+    assert_eq!(node2_balance.offchain_inbound, 500);
+
+    // check PaymentNotAbandonable error
+    let payload = AbandonPaymentRequest {
+        payment_hash: decode_ln_invoice(node1_addr, &invoice).await.payment_hash,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/abandonpayment", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let text = res.text().await.unwrap();
+    let response: ErrorResponse = serde_json::from_str(&text).unwrap();
+    assert_eq!(
+        response.error,
+        "Payment cannot be abandoned: payment is not pending"
+    );
+    assert_eq!(response.code, 403);
+}
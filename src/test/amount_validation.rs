@@ -0,0 +1,230 @@
+use serde_json::{json, Value};
+
+use super::*;
+use crate::amounts::{MAX_SUPPLY_MSAT, MAX_SUPPLY_SAT};
+
+const TEST_DIR_BASE: &str = "tmp/amount_validation/";
+const NODE1_PEER_PORT: u16 = 10337;
+
+/// Placeholder of the amount in the request bodies, replaced by the raw JSON of each value
+const AMOUNT: &str = "<amount>";
+
+/// Values no amount field can hold, whatever its unit
+const NOT_U64_AMOUNTS: &[&str] = &["-1", "18446744073709551616", "1e30", "1.5", "\"10\""];
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Msat,
+    Sat,
+    Asset,
+    /// Amount issued along with another unit of a new asset
+    IssuedAsset,
+}
+
+async fn post_amount(
+    node_address: SocketAddr,
+    endpoint: &str,
+    body: &Value,
+    amount: &str,
+) -> reqwest::Response {
+    let body = body.to_string().replace(&format!("\"{AMOUNT}\""), amount);
+    reqwest::Client::new()
+        .post(format!("http://{}/{}", node_address, endpoint))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn check_refused(
+    res: reqwest::Response,
+    endpoint: &str,
+    amount: &str,
+    status: Option<reqwest::StatusCode>,
+) -> String {
+    let res_status = res.status();
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert!(
+        res_status.is_client_error(),
+        "/{endpoint} with {amount}: {res_status} {}",
+        response.error
+    );
+    if let Some(status) = status {
+        assert_eq!(res_status, status, "/{endpoint} with {amount}");
+    }
+    response.error
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn amount_validation() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+
+    // the asset is issued with a supply of 1000
+    let asset_id = issue_asset(node1_addr).await;
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let peer = format!("{node1_pubkey}@127.0.0.1:{NODE1_PEER_PORT}");
+
+    let requests: Vec<(&str, Unit, Value)> = vec![
+        (
+            "issueasset",
+            Unit::IssuedAsset,
+            json!({"amounts": [AMOUNT, 1], "ticker": "USDT", "name": "Tether", "precision": 0}),
+        ),
+        (
+            "keysend",
+            Unit::Msat,
+            json!({"dest_pubkey": node1_pubkey, "amt_msat": AMOUNT, "asset_id": asset_id,
+                "asset_amount": 10}),
+        ),
+        (
+            "keysend",
+            Unit::Asset,
+            json!({"dest_pubkey": node1_pubkey, "amt_msat": 3000000, "asset_id": asset_id,
+                "asset_amount": AMOUNT}),
+        ),
+        (
+            "lninvoice",
+            Unit::Msat,
+            json!({"amt_msat": AMOUNT, "expiry_sec": 900, "asset_id": asset_id,
+                "asset_amount": 10}),
+        ),
+        (
+            "lninvoice",
+            Unit::Asset,
+            json!({"amt_msat": 3000000, "expiry_sec": 900, "asset_id": asset_id,
+                "asset_amount": AMOUNT}),
+        ),
+        (
+            "openchannel",
+            Unit::Sat,
+            json!({"peer_pubkey_and_addr": peer, "capacity_sat": AMOUNT,
+                "push_msat": 3500000, "asset_amount": 600, "asset_id": asset_id,
+                "public": true, "with_anchors": true}),
+        ),
+        (
+            "openchannel",
+            Unit::Msat,
+            json!({"peer_pubkey_and_addr": peer, "capacity_sat": 100000,
+                "push_msat": AMOUNT, "asset_amount": 600, "asset_id": asset_id,
+                "public": true, "with_anchors": true}),
+        ),
+        (
+            "openchannel",
+            Unit::Msat,
+            json!({"peer_pubkey_and_addr": peer, "capacity_sat": 100000,
+                "push_msat": 3500000, "asset_amount": 600, "asset_id": asset_id,
+                "public": true, "with_anchors": true, "htlc_minimum_msat": AMOUNT}),
+        ),
+        (
+            "openchannel",
+            Unit::Asset,
+            json!({"peer_pubkey_and_addr": peer, "capacity_sat": 100000,
+                "push_msat": 3500000, "asset_amount": AMOUNT, "asset_id": asset_id,
+                "public": true, "with_anchors": true}),
+        ),
+        (
+            "quotepayment",
+            Unit::Msat,
+            json!({"dest_pubkey": node1_pubkey, "amt_msat": AMOUNT, "asset_id": asset_id,
+                "asset_amount": 10}),
+        ),
+        (
+            "requestinboundchannel",
+            Unit::Sat,
+            json!({"lsp_pubkey_and_addr": peer, "asset_id": asset_id, "asset_amount": 100,
+                "capacity_sat": AMOUNT}),
+        ),
+        (
+            "sendasset",
+            Unit::Asset,
+            json!({"asset_id": asset_id, "amount": AMOUNT, "blinded_utxo": "invalid",
+                "donation": false, "min_confirmations": 1, "transport_endpoints": []}),
+        ),
+        (
+            "sendbtc",
+            Unit::Sat,
+            json!({"amount": AMOUNT, "address": "invalid", "fee_rate": 5.0}),
+        ),
+        (
+            "setpaymentpolicy",
+            Unit::Msat,
+            json!({"allowed_destinations": [], "max_msat_per_day": AMOUNT,
+                "max_asset_amounts_per_day": []}),
+        ),
+        (
+            "setpeerexposurelimits",
+            Unit::Msat,
+            json!({"peer_pubkey": node1_pubkey, "max_msat": AMOUNT}),
+        ),
+        (
+            "swapintochannel",
+            Unit::Asset,
+            json!({"channel_id": "invalid", "asset_amount": AMOUNT}),
+        ),
+        (
+            "swapoutofchannel",
+            Unit::Asset,
+            json!({"channel_id": "invalid", "asset_amount": AMOUNT}),
+        ),
+    ];
+
+    let unprocessable = Some(reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    for (endpoint, unit, body) in &requests {
+        // amounts that aren't u64 are refused while parsing the request
+        for amount in NOT_U64_AMOUNTS {
+            let res = post_amount(node1_addr, endpoint, body, amount).await;
+            check_refused(res, endpoint, amount, unprocessable).await;
+        }
+
+        // bitcoin amounts are bounded by the supply, asset amounts by the issued supply
+        let too_high = match unit {
+            Unit::Msat => vec![MAX_SUPPLY_MSAT + 1, u64::MAX],
+            Unit::Sat => vec![MAX_SUPPLY_SAT + 1, MAX_SUPPLY_MSAT, u64::MAX],
+            Unit::Asset => vec![1001, u64::MAX / 2 + 1, u64::MAX],
+            Unit::IssuedAsset => vec![u64::MAX],
+        };
+        for amount in too_high {
+            let amount = amount.to_string();
+            let res = post_amount(node1_addr, endpoint, body, &amount).await;
+            match unit {
+                Unit::Msat | Unit::Sat => {
+                    let error = check_refused(res, endpoint, &amount, unprocessable).await;
+                    assert!(error.contains("is more than the bitcoin supply"));
+                }
+                Unit::Asset | Unit::IssuedAsset => {
+                    check_refused(res, endpoint, &amount, None).await;
+                }
+            }
+        }
+    }
+
+    // amounts at the limits go through the validation
+    let res = post_amount(
+        node1_addr,
+        "setpeerexposurelimits",
+        &json!({"peer_pubkey": node1_pubkey, "max_msat": AMOUNT}),
+        &MAX_SUPPLY_MSAT.to_string(),
+    )
+    .await;
+    _check_response_is_ok(res).await;
+    let res = post_amount(
+        node1_addr,
+        "quotepayment",
+        &json!({"dest_pubkey": node1_pubkey, "amt_msat": AMOUNT, "asset_id": asset_id,
+            "asset_amount": u64::MAX}),
+        &MAX_SUPPLY_MSAT.to_string(),
+    )
+    .await;
+    let quote: QuotePaymentResponse = _check_response_is_ok(res).await.json().await.unwrap();
+    assert_ne!(quote.feasibility, PaymentFeasibility::Ok);
+
+    // the node is still up after all of them
+    assert_eq!(node_info(node1_addr).await.pubkey, node1_pubkey);
+}
@@ -0,0 +1,108 @@
+use crate::announcements::{AnnouncementStatus, ChannelAnnouncementStatus};
+use crate::routes::RebroadcastAnnouncementsResponse;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/announcements/";
+const NODE1_PEER_PORT: u16 = 10354;
+const NODE2_PEER_PORT: u16 = 10355;
+
+async fn rebroadcast_announcements_raw(node_address: SocketAddr) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/rebroadcastannouncements", node_address))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn channel_announcement_status(
+    node_address: SocketAddr,
+    channel_id: &str,
+) -> ChannelAnnouncementStatus {
+    list_channels(node_address)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel_id)
+        .unwrap()
+        .announcement_status
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn announcements() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    // nothing gets announced without public channels
+    assert_eq!(
+        node_info(node1_addr).await.node_announcement,
+        AnnouncementStatus::default()
+    );
+    let res = rebroadcast_announcements_raw(node1_addr).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "The node has no public channels to announce"
+    );
+
+    fund_and_create_utxos(node1_addr).await;
+    let asset_id = issue_asset(node1_addr).await;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    // the channel gets announced after 6 confirmations
+    mine_n_blocks(false, 6);
+
+    let t_0 = OffsetDateTime::now_utc();
+    let announced = loop {
+        let status = channel_announcement_status(node1_addr, &channel.channel_id).await;
+        if status.announcement.last_broadcast_at.is_some()
+            && status.update.last_broadcast_at.is_some()
+        {
+            break status;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("channel announcement not broadcast")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert_eq!(announced.announcement.announced_to_peers, 1);
+    assert_eq!(announced.update.announced_to_peers, 1);
+
+    // a rebroadcast refreshes the node and channel announcements
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    let res = rebroadcast_announcements_raw(node1_addr).await;
+    let response = _check_response_is_ok(res)
+        .await
+        .json::<RebroadcastAnnouncementsResponse>()
+        .await
+        .unwrap();
+    assert_eq!(response.channels, 1);
+
+    let node_announcement = node_info(node1_addr).await.node_announcement;
+    assert_eq!(node_announcement.announced_to_peers, 1);
+    assert!(node_announcement.last_broadcast_at.is_some());
+    let rebroadcast = channel_announcement_status(node1_addr, &channel.channel_id).await;
+    assert_eq!(rebroadcast.announcement.announced_to_peers, 1);
+    assert!(rebroadcast.announcement.last_broadcast_at > announced.announcement.last_broadcast_at);
+    assert!(rebroadcast.update.last_broadcast_at > announced.update.last_broadcast_at);
+
+    // the peer gets the node announcement now that it knows the channel
+    let t_0 = OffsetDateTime::now_utc();
+    while !query_node_info_raw(node2_addr, &node_info(node1_addr).await.pubkey)
+        .await
+        .status()
+        .is_success()
+    {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("node announcement not received")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        _check_response_is_ok(rebroadcast_announcements_raw(node1_addr).await).await;
+    }
+}
@@ -0,0 +1,112 @@
+use crate::api_limits::ApiLimits;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/api_limits/";
+const NODE1_PEER_PORT: u16 = 10293;
+
+async fn node_info_raw(node_address: SocketAddr) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("http://{}/nodeinfo", node_address))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn decode_ln_invoice_chunked(node_address: SocketAddr, invoice: String) -> reqwest::Response {
+    let payload = serde_json::to_vec(&DecodeLNInvoiceRequest { invoice }).unwrap();
+    let chunks: Vec<Result<Vec<u8>, std::io::Error>> =
+        payload.chunks(256).map(|c| Ok(c.to_vec())).collect();
+    reqwest::Client::new()
+        .post(format!("http://{}/decodelninvoice", node_address))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(reqwest::Body::wrap_stream(futures::stream::iter(chunks)))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn decode_ln_invoice_raw(node_address: SocketAddr, invoice: String) -> reqwest::Response {
+    let payload = DecodeLNInvoiceRequest { invoice };
+    reqwest::Client::new()
+        .post(format!("http://{}/decodelninvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn api_limits() {
+    initialize();
+
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node1"),
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            api_limits: Some(ApiLimits {
+                requests_per_sec: 2,
+                payment_requests_per_sec: 100,
+                max_body_bytes: 1024,
+            }),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    // a burst over the limit gets rejected with the structured error
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    let mut statuses = vec![];
+    let mut rejection = None;
+    for _ in 0..10 {
+        let res = node_info_raw(node1_addr).await;
+        statuses.push(res.status());
+        if res.status() == reqwest::StatusCode::TOO_MANY_REQUESTS && rejection.is_none() {
+            rejection = Some(res.json::<ErrorResponse>().await.unwrap());
+        }
+    }
+    assert_eq!(statuses[0], reqwest::StatusCode::OK);
+    let rejection = rejection.unwrap();
+    assert_eq!(rejection.error, "Too many requests, retry later");
+    assert_eq!(rejection.code, 429);
+
+    // payment routes are counted apart
+    let res = decode_ln_invoice_raw(node1_addr, s!("lnbcrt1invalid")).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    // bodies over the limit get rejected
+    let res = decode_ln_invoice_raw(node1_addr, "a".repeat(2048)).await;
+    assert_eq!(res.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Request body too large, the limit is 1024 bytes"
+    );
+    assert_eq!(response.code, 413);
+
+    // also when they don't declare their size
+    let res = decode_ln_invoice_chunked(node1_addr, "a".repeat(2048)).await;
+    assert_eq!(res.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+    let res = decode_ln_invoice_chunked(node1_addr, s!("lnbcrt1invalid")).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    // requests get accepted again at the sustained rate
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    _check_response_is_ok(node_info_raw(node1_addr).await).await;
+
+    // routes carrying peer messages accept larger bodies
+    let payload = SendCustomMessageRequest {
+        peer_pubkey: s!("invalid"),
+        msg_type: 32768,
+        data: "aa".repeat(1024),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendcustommsg", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+}
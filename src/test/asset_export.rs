@@ -0,0 +1,109 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/asset_export/";
+const NODE1_PEER_PORT: u16 = 10309;
+const NODE2_PEER_PORT: u16 = 10310;
+
+async fn export_asset_raw(
+    node_address: SocketAddr,
+    asset_id: &str,
+    history: bool,
+    format: &str,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!(
+            "http://{}/exportasset?asset_id={asset_id}&history={history}&format={format}",
+            node_address
+        ))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn export_asset(
+    node_address: SocketAddr,
+    asset_id: &str,
+    history: bool,
+) -> ExportAssetResponse {
+    _check_response_is_ok(export_asset_raw(node_address, asset_id, history, "json").await)
+        .await
+        .json::<ExportAssetResponse>()
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn asset_export() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    // the binary download and the JSON variant carry the same contract
+    let contract = export_asset(node1_addr, &asset_id, false).await;
+    assert_eq!(contract.asset_id, asset_id);
+    let res = export_asset_raw(node1_addr, &asset_id, false, "binary").await;
+    let res = _check_response_is_ok(res).await;
+    assert_eq!(
+        res.headers()[reqwest::header::CONTENT_TYPE],
+        "application/octet-stream"
+    );
+    let downloaded = res.bytes().await.unwrap();
+    assert_eq!(
+        base64::encode(&downloaded),
+        contract.consignment,
+        "binary and JSON exports differ"
+    );
+
+    // the exported contract is accepted by another node
+    let payload = ImportAssetRequest {
+        asset_id: None,
+        consignment: Some(contract.consignment.clone()),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/importasset", node2_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    assert!(list_assets(node2_addr)
+        .await
+        .iter()
+        .any(|a| a.asset_id == asset_id));
+
+    // without transfers the history is the contract itself
+    let history = export_asset(node1_addr, &asset_id, true).await;
+    assert_eq!(history.consignment, contract.consignment);
+
+    // a transfer in flight doesn't prevent exports, and its consignment becomes the history
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    send_asset(node1_addr, &asset_id, 100, recipient_id).await;
+    let in_flight = export_asset(node1_addr, &asset_id, false).await;
+    assert_eq!(in_flight.asset_id, asset_id);
+    let history = export_asset(node1_addr, &asset_id, true).await;
+    assert_ne!(history.consignment, contract.consignment);
+    assert_ne!(history.armored, contract.armored);
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 100);
+
+    // assets unknown to the node can't be exported
+    let other_asset_id = issue_asset(node2_addr).await;
+    let res = export_asset_raw(node1_addr, &other_asset_id, false, "binary").await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Unknown RGB contract ID");
+    let res = export_asset_raw(node1_addr, "rgb:invalid", false, "json").await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,200 @@
+use axum::extract::Path as AxumPath;
+use lightning::rgb_utils::get_rgb_runtime;
+use rgbstd::contract::ContractId;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/asset_import/";
+const NODE1_PEER_PORT: u16 = 10306;
+const NODE2_PEER_PORT: u16 = 10307;
+const REGISTRY_PORT: u16 = 10308;
+
+type Contracts = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// Serve the contract consignment of each published asset
+fn start_registry(contracts: Contracts) {
+    let app = axum::Router::new().route(
+        "/contracts/:asset_id",
+        axum::routing::get(move |AxumPath(asset_id): AxumPath<String>| {
+            let contracts = Arc::clone(&contracts);
+            async move {
+                match contracts.lock().unwrap().get(&asset_id) {
+                    Some(contract) => Ok(contract.clone()),
+                    None => Err(axum::http::StatusCode::NOT_FOUND),
+                }
+            }
+        }),
+    );
+    tokio::spawn(
+        axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], REGISTRY_PORT)))
+            .serve(app.into_make_service()),
+    );
+}
+
+/// Export the contract of an asset the way an external issuer would
+fn export_contract(node_test_dir: &str, asset_id: &str) -> Vec<u8> {
+    let ldk_data_dir = format!("{node_test_dir}/{LDK_DIR}");
+    let runtime = get_rgb_runtime(Path::new(&ldk_data_dir));
+    let contract = runtime
+        .runtime
+        .export_contract(ContractId::from_str(asset_id).unwrap())
+        .unwrap();
+    let contract_path = format!("{node_test_dir}/contract_{asset_id}");
+    contract.save(&contract_path).unwrap();
+    std::fs::read(contract_path).unwrap()
+}
+
+async fn import_asset_raw(
+    node_address: SocketAddr,
+    payload: &ImportAssetRequest,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/importasset", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn import_asset(node_address: SocketAddr, payload: &ImportAssetRequest) -> String {
+    _check_response_is_ok(import_asset_raw(node_address, payload).await)
+        .await
+        .json::<ImportAssetResponse>()
+        .await
+        .unwrap()
+        .asset_id
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn asset_import() {
+    initialize();
+
+    let contracts: Contracts = Arc::new(Mutex::new(HashMap::new()));
+    start_registry(Arc::clone(&contracts));
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1.clone(), NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node2,
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            asset_registry_url: Some(format!("http://127.0.0.1:{REGISTRY_PORT}")),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let uploaded_asset_id = issue_asset(node1_addr).await;
+    let published_asset_id = issue_asset(node1_addr).await;
+    let uploaded_contract = export_contract(&test_dir_node1, &uploaded_asset_id);
+    contracts.lock().unwrap().insert(
+        published_asset_id.clone(),
+        export_contract(&test_dir_node1, &published_asset_id),
+    );
+
+    // the contract can be uploaded or fetched from the registry
+    let payload = ImportAssetRequest {
+        asset_id: None,
+        consignment: Some(base64::encode(&uploaded_contract)),
+    };
+    assert_eq!(import_asset(node2_addr, &payload).await, uploaded_asset_id);
+    // importing a known asset again is harmless
+    assert_eq!(import_asset(node2_addr, &payload).await, uploaded_asset_id);
+    let payload = ImportAssetRequest {
+        asset_id: Some(published_asset_id.clone()),
+        consignment: None,
+    };
+    assert_eq!(import_asset(node2_addr, &payload).await, published_asset_id);
+
+    // the imported assets can be used right away
+    let assets = list_assets(node2_addr).await;
+    for asset_id in [&uploaded_asset_id, &published_asset_id] {
+        assert!(assets.iter().any(|a| &a.asset_id == asset_id));
+        let balance = asset_balance(node2_addr, asset_id).await;
+        assert_eq!(balance.settled, 0);
+        assert_eq!(balance.future, 0);
+        rgb_invoice(node2_addr, Some(asset_id.clone())).await;
+    }
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(MilliSats(3000000)),
+        expiry_sec: 900,
+        asset_id: Some(uploaded_asset_id.clone()),
+        asset_amount: Some(AssetUnits(10)),
+        label: None,
+        allow_insufficient_inbound: true,
+        require_payer_pubkey: None,
+        require_payer_note: false,
+        min_final_cltv_expiry_delta: None,
+        unified: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node2_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+
+    // invalid imports are refused with the reason
+    let payload = ImportAssetRequest {
+        asset_id: None,
+        consignment: Some(base64::encode(b"not a consignment")),
+    };
+    let res = import_asset_raw(node2_addr, &payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert!(response
+        .error
+        .starts_with("Invalid consignment: cannot decode contract"));
+
+    let payload = ImportAssetRequest {
+        asset_id: Some(s!("rgb:unpublished")),
+        consignment: None,
+    };
+    let res = import_asset_raw(node2_addr, &payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert!(response.error.starts_with("Failed to fetch contract"));
+
+    // a registry serving the contract of another asset is caught
+    contracts
+        .lock()
+        .unwrap()
+        .insert(s!("rgb:other"), uploaded_contract.clone());
+    let payload = ImportAssetRequest {
+        asset_id: Some(s!("rgb:other")),
+        consignment: None,
+    };
+    let res = import_asset_raw(node2_addr, &payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        format!(
+            "Invalid consignment: contract {uploaded_asset_id} doesn't match the requested asset \
+            rgb:other"
+        )
+    );
+
+    let payload = ImportAssetRequest {
+        asset_id: Some(published_asset_id),
+        consignment: Some(base64::encode(&uploaded_contract)),
+    };
+    let res = import_asset_raw(node2_addr, &payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Invalid asset import: exactly one of asset_id and consignment must be provided"
+    );
+}
@@ -0,0 +1,168 @@
+use axum::extract::Query;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/asset_registry/";
+const NODE1_PEER_PORT: u16 = 10302;
+const NODE2_PEER_PORT: u16 = 10303;
+const REGISTRY_PORT: u16 = 10304;
+const UNREACHABLE_REGISTRY_PORT: u16 = 10305;
+
+type Registry = Arc<Mutex<HashMap<String, Vec<String>>>>;
+
+/// Serve the assets registered with each ticker
+fn start_registry(registry: Registry) {
+    let app = axum::Router::new().route(
+        "/assets",
+        axum::routing::get(move |Query(params): Query<HashMap<String, String>>| {
+            let registry = Arc::clone(&registry);
+            async move {
+                let asset_ids = registry
+                    .lock()
+                    .unwrap()
+                    .get(&params["ticker"])
+                    .cloned()
+                    .unwrap_or_default();
+                let assets: Vec<_> = asset_ids
+                    .into_iter()
+                    .map(|asset_id| serde_json::json!({"asset_id": asset_id}))
+                    .collect();
+                axum::Json(serde_json::json!({ "assets": assets }))
+            }
+        }),
+    );
+    tokio::spawn(
+        axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], REGISTRY_PORT)))
+            .serve(app.into_make_service()),
+    );
+}
+
+async fn issue_asset_raw(
+    node_address: SocketAddr,
+    ticker: &str,
+    allow_duplicate_ticker: bool,
+) -> reqwest::Response {
+    let payload = IssueAssetRequest {
+        amounts: vec![AssetUnits(1000)],
+        ticker: ticker.to_string(),
+        name: s!("Test asset"),
+        precision: 0,
+        allow_duplicate_ticker,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/issueasset", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn issue_asset_with_ticker(
+    node_address: SocketAddr,
+    ticker: &str,
+    allow_duplicate_ticker: bool,
+) -> IssueAssetResponse {
+    let res = issue_asset_raw(node_address, ticker, allow_duplicate_ticker).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<IssueAssetResponse>()
+        .await
+        .unwrap()
+}
+
+fn registry_status(assets: &[Asset], asset_id: &str) -> RegistryStatus {
+    assets
+        .iter()
+        .find(|a| a.asset_id == asset_id)
+        .unwrap()
+        .registry_status
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn asset_registry() {
+    initialize();
+
+    let registry: Registry = Arc::new(Mutex::new(HashMap::from([(
+        s!("USDT"),
+        vec![s!("rgb:registered")],
+    )])));
+    start_registry(Arc::clone(&registry));
+
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node1"),
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            asset_registry_url: Some(format!("http://127.0.0.1:{REGISTRY_PORT}")),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node2_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node2"),
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            asset_registry_url: Some(format!("http://127.0.0.1:{UNREACHABLE_REGISTRY_PORT}")),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    // a registered ticker is refused unless explicitly allowed
+    let res = issue_asset_raw(node1_addr, "USDT", false).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Ticker already registered to other assets: USDT (rgb:registered)"
+    );
+    let duplicate = issue_asset_with_ticker(node1_addr, "USDT", true).await;
+    assert_eq!(
+        duplicate.registry_warning.unwrap(),
+        "ticker already registered to other assets: USDT (rgb:registered)"
+    );
+
+    let own = issue_asset_with_ticker(node1_addr, "OWN", false).await;
+    assert!(own.registry_warning.is_none());
+    let unregistered = issue_asset_with_ticker(node1_addr, "NEW", false).await;
+    assert!(unregistered.registry_warning.is_none());
+
+    // listed assets are annotated once the registry knows them
+    registry
+        .lock()
+        .unwrap()
+        .insert(s!("OWN"), vec![own.asset_id.clone()]);
+    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+    let assets = list_assets(node1_addr).await;
+    assert_eq!(
+        registry_status(&assets, &duplicate.asset_id),
+        RegistryStatus::TickerConflict
+    );
+    assert_eq!(
+        registry_status(&assets, &own.asset_id),
+        RegistryStatus::Verified
+    );
+    assert_eq!(
+        registry_status(&assets, &unregistered.asset_id),
+        RegistryStatus::Unregistered
+    );
+
+    // an unreachable registry never blocks issuance or listing
+    let issued = issue_asset_with_ticker(node2_addr, "USDT", false).await;
+    assert_eq!(
+        issued.registry_warning.unwrap(),
+        "the asset registry is unreachable, the ticker has not been checked"
+    );
+    let assets = list_assets(node2_addr).await;
+    assert_eq!(
+        registry_status(&assets, &issued.asset_id),
+        RegistryStatus::Unverified
+    );
+}
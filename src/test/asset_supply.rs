@@ -0,0 +1,79 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/asset_supply/";
+const NODE1_PEER_PORT: u16 = 10407;
+const NODE2_PEER_PORT: u16 = 10408;
+
+async fn check_amount_exceeds_supply(res: reqwest::Response) {
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let text = res.text().await.unwrap();
+    let response: ErrorResponse = serde_json::from_str(&text).unwrap();
+    assert_eq!(
+        response.error,
+        "Amount exceeds the issued supply of the asset (1000)"
+    );
+    assert_eq!(response.code, 400);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn asset_supply() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    assert_eq!(asset_balance(node1_addr, &asset_id).await.precision, 0);
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    assert_eq!(channel.asset_precision, Some(0));
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 600, 0).await;
+    let payments = list_payments(node1_addr).await;
+    assert_eq!(payments.len(), 1);
+    assert_eq!(payments[0].asset_precision, Some(0));
+
+    // check AmountExceedsSupply error
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(MilliSats(3000000)),
+        expiry_sec: 900,
+        asset_id: Some(asset_id.clone()),
+        asset_amount: Some(AssetUnits(1001)),
+        label: None,
+        allow_insufficient_inbound: false,
+        require_payer_pubkey: None,
+        require_payer_note: false,
+        min_final_cltv_expiry_delta: None,
+        unified: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_amount_exceeds_supply(res).await;
+    let payload = KeysendRequest {
+        dest_pubkey: node2_pubkey.clone(),
+        amt_msat: Some(MilliSats(3000000)),
+        asset_id: asset_id.clone(),
+        asset_amount: AssetUnits(1001),
+        label: None,
+        use_minimum_btc: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/keysend", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_amount_exceeds_supply(res).await;
+}
@@ -0,0 +1,103 @@
+use std::os::unix::fs::PermissionsExt;
+
+use super::*;
+use crate::args::read_unlock_password_file;
+
+const TEST_DIR_BASE: &str = "tmp/auto_unlock/";
+const NODE1_PEER_PORT: u16 = 10329;
+
+async fn start_daemon_unlocking(node_test_dir: &str, password: &str) -> SocketAddr {
+    start_daemon_with_args(LdkUserInfo {
+        storage_dir_path: node_test_dir.to_string(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        unlock_password: Some(password.to_string()),
+        ..Default::default()
+    })
+    .await
+}
+
+/// Wait for the unlock at startup to be over, returning the error reported by /ready if it
+/// failed
+async fn wait_for_auto_unlock(node_address: SocketAddr) -> Option<String> {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let res = reqwest::Client::new()
+            .get(format!("http://{}/ready", node_address))
+            .send()
+            .await
+            .unwrap();
+        if res.status().is_success() {
+            return None;
+        }
+        assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+        let response: ErrorResponse = res.json().await.unwrap();
+        if response.error.starts_with("Automatic unlock failed") {
+            return Some(response.error);
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("unlock at startup not completing")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn auto_unlock() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let password = s!("auto-unlock-password");
+    let ldk_sockets = get_ldk_sockets(&[NODE1_PEER_PORT]);
+    if Path::new(&test_dir_node1).is_dir() {
+        std::fs::remove_dir_all(&test_dir_node1).unwrap();
+    }
+
+    // a brand-new node doesn't get initialized implicitly
+    let node1_addr = start_daemon_unlocking(&test_dir_node1, &password).await;
+    assert_eq!(
+        wait_for_auto_unlock(node1_addr).await.unwrap(),
+        "Automatic unlock failed: Wallet has not been initialized (hint: call init)"
+    );
+    assert!(!Path::new(&test_dir_node1).join("mnemonic").exists());
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/init", node1_addr))
+        .json(&InitRequest {
+            password: password.clone(),
+        })
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    unlock(node1_addr, password.clone()).await;
+    assert!(wait_for_auto_unlock(node1_addr).await.is_none());
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    shutdown(&[node1_addr], &ldk_sockets).await;
+
+    // once initialized, the node is usable after a restart without calling /unlock
+    let node1_addr = start_daemon_unlocking(&test_dir_node1, &password).await;
+    assert!(wait_for_auto_unlock(node1_addr).await.is_none());
+    assert_eq!(node_info(node1_addr).await.pubkey, node1_pubkey);
+    shutdown(&[node1_addr], &ldk_sockets).await;
+
+    // a wrong password leaves the node locked, waiting for /unlock
+    let node1_addr = start_daemon_unlocking(&test_dir_node1, "wrong-password").await;
+    assert_eq!(
+        wait_for_auto_unlock(node1_addr).await.unwrap(),
+        "Automatic unlock failed: The provided password is incorrect"
+    );
+    unlock(node1_addr, password.clone()).await;
+    assert!(wait_for_auto_unlock(node1_addr).await.is_none());
+    shutdown(&[node1_addr], &ldk_sockets).await;
+
+    // password files must be private
+    let password_file = Path::new(TEST_DIR_BASE).join("password");
+    std::fs::write(&password_file, format!("{password}\n")).unwrap();
+    std::fs::set_permissions(&password_file, std::fs::Permissions::from_mode(0o644)).unwrap();
+    let err = read_unlock_password_file(&password_file).unwrap_err();
+    assert!(err
+        .to_string()
+        .ends_with("is accessible by other users (hint: chmod 600 it)"));
+    std::fs::set_permissions(&password_file, std::fs::Permissions::from_mode(0o600)).unwrap();
+    assert_eq!(read_unlock_password_file(&password_file).unwrap(), password);
+}
@@ -1,11 +1,48 @@
 use super::*;
+use crate::pending_transfers::PENDING_TRANSFERS_FNAME;
+use crate::routes::SetLabelRequest;
 use regex::RegexSet;
 
 const TEST_DIR_BASE: &str = "tmp/backup_and_restore/";
-const NODE1_PEER_PORT: u16 = 9921;
-const NODE2_PEER_PORT: u16 = 9922;
+const NODE1_PEER_PORT: u16 = 10161;
+const NODE2_PEER_PORT: u16 = 10162;
+
+async fn set_label(
+    node_address: SocketAddr,
+    payment_hash: Option<String>,
+    recipient_id: Option<String>,
+    label: &str,
+) -> reqwest::Response {
+    let payload = SetLabelRequest {
+        payment_hash,
+        recipient_id,
+        label: label.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/setlabel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_payments_with_label(node_address: SocketAddr, label: &str) -> Vec<Payment> {
+    let res = reqwest::Client::new()
+        .get(format!(
+            "http://{}/listpayments?label={label}",
+            node_address
+        ))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListPaymentsResponse>()
+        .await
+        .unwrap()
+        .payments
+}
 
-#[serial_test::serial]
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[traced_test]
 async fn backup_and_restore() {
@@ -29,12 +66,72 @@ async fn backup_and_restore() {
     let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
 
-    keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
+    // check UnknownChannel error
+    let res = set_channel_note(node1_addr, &"00".repeat(32), "unknown").await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Unknown channel");
+
+    let res = set_channel_note(node1_addr, &channel.channel_id, "to node2").await;
+    _check_response_is_ok(res).await;
+    let channels = list_channels(node1_addr).await;
+    assert_eq!(channels[0].note, Some(s!("to node2")));
+
+    let payment = keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
+    let res = set_label(
+        node1_addr,
+        Some(payment.payment_hash.clone()),
+        None,
+        "coffee",
+    )
+    .await;
+    _check_response_is_ok(res).await;
+    let labelled = list_payments_with_label(node1_addr, "coffee").await;
+    assert_eq!(labelled.len(), 1);
+    assert_eq!(labelled[0].payment_hash, payment.payment_hash);
+    assert_eq!(labelled[0].label, Some(s!("coffee")));
+    assert!(list_payments_with_label(node1_addr, "tea").await.is_empty());
 
     close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
     wait_for_balance(node1_addr, &asset_id, 900).await;
     wait_for_balance(node2_addr, &asset_id, 100).await;
 
+    // the note is kept in the closed channel history
+    let closed_channels = list_closed_channels(node1_addr).await;
+    assert_eq!(closed_channels[0].channel_id, channel.channel_id);
+    assert_eq!(closed_channels[0].note, Some(s!("to node2")));
+
+    // the backup gets taken while a transfer to node1 waits for its anchor to confirm
+    let recipient_id = rgb_invoice(node1_addr, Some(asset_id.clone()))
+        .await
+        .recipient_id;
+    stop_mining();
+    let res = set_label(node1_addr, None, Some(recipient_id.clone()), "from node2").await;
+    _check_response_is_ok(res).await;
+
+    // check UnknownRecipientID error, the recipient ID being from an invoice of node2
+    let node2_recipient_id = rgb_invoice(node2_addr, Some(asset_id.clone()))
+        .await
+        .recipient_id;
+    let res = set_label(node1_addr, None, Some(node2_recipient_id), "not ours").await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Unknown recipient ID");
+    assert_eq!(response.code, 403);
+
+    send_asset(node2_addr, &asset_id, 50, recipient_id.clone()).await;
+    refresh_transfers(node1_addr).await;
+    let transfer = list_transfers(node1_addr, &asset_id)
+        .await
+        .into_iter()
+        .find(|t| t.recipient_id.as_ref() == Some(&recipient_id))
+        .unwrap();
+    assert!(matches!(
+        transfer.status,
+        TransferStatus::WaitingConfirmations
+    ));
+    assert_eq!(transfer.label, Some(s!("from node2")));
+
     let node1_info = node_info(node1_addr).await;
     let node1_pubkey = node1_info.pubkey;
 
@@ -82,9 +179,31 @@ async fn backup_and_restore() {
         .compare(old_test_dir_node1_path, Path::new(&test_dir_node1))
         .unwrap();
     assert!(diff.is_empty());
+    assert!(Path::new(&test_dir_node1).join(DATABASE_FNAME).exists());
+    assert!(Path::new(&test_dir_node1)
+        .join(PENDING_TRANSFERS_FNAME)
+        .exists());
 
     unlock(node1_addr, node1_password).await;
 
     let node1_info = node_info(node1_addr).await;
     assert_eq!(node1_pubkey, node1_info.pubkey);
+
+    let closed_channels = list_closed_channels(node1_addr).await;
+    assert_eq!(closed_channels[0].note, Some(s!("to node2")));
+
+    // the labels are restored with the rest of the node data
+    let labelled = list_payments_with_label(node1_addr, "coffee").await;
+    assert_eq!(labelled.len(), 1);
+    assert_eq!(labelled[0].payment_hash, payment.payment_hash);
+    let transfer = list_transfers(node1_addr, &asset_id)
+        .await
+        .into_iter()
+        .find(|t| t.recipient_id.as_ref() == Some(&recipient_id))
+        .unwrap();
+    assert_eq!(transfer.label, Some(s!("from node2")));
+
+    // the restored node resumes the pending transfer, which completes once its anchor confirms
+    mine(true);
+    wait_for_balance(node1_addr, &asset_id, 950).await;
 }
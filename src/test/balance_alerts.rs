@@ -0,0 +1,132 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/balance_alerts/";
+const NODE1_PEER_PORT: u16 = 10121;
+const NODE2_PEER_PORT: u16 = 10122;
+
+fn count_alert_events(events: &[Event], raised: bool, alert_kind: AlertKind) -> usize {
+    events
+        .iter()
+        .filter(|e| match &e.event {
+            NodeEvent::AlertRaised { kind, .. } => raised && *kind == alert_kind,
+            NodeEvent::AlertCleared { kind, .. } => !raised && *kind == alert_kind,
+            _ => false,
+        })
+        .count()
+}
+
+async fn wait_for_alerts(node_address: SocketAddr, expected_kinds: &[AlertKind]) -> Vec<Alert> {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let alerts = alerts(node_address).await.alerts;
+        let mut kinds: Vec<AlertKind> = alerts.iter().map(|a| a.kind).collect();
+        kinds.sort();
+        if kinds == expected_kinds {
+            return alerts;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("alerts are not becoming the expected ones")
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn balance_alerts() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // no thresholds, no alerts
+    let response = alerts(node1_addr).await;
+    assert!(response.min_channel_asset_amount.is_none());
+    assert!(response.alerts.is_empty());
+
+    // the channel has no asset on the remote side yet
+    set_alerts(
+        node1_addr,
+        &SetAlertsRequest {
+            anchor_reserve_per_channel_sat: None,
+            min_fee_bump_utxos: None,
+            min_channel_asset_amount: Some(0),
+            max_unconfirmed_tx_age_secs: None,
+        },
+    )
+    .await;
+    let alerts_raised =
+        wait_for_alerts(node1_addr, &[AlertKind::ChannelAssetInboundExhausted]).await;
+    assert_eq!(
+        alerts_raised[0].channel_id.as_ref(),
+        Some(&channel.channel_id)
+    );
+    assert_eq!(alerts_raised[0].asset_id.as_ref(), Some(&asset_id));
+    assert_eq!(alerts(node1_addr).await.min_channel_asset_amount, Some(0));
+
+    // an alert still active is not notified again
+    tokio::time::sleep(std::time::Duration::from_secs(6)).await;
+    let node1_events = events(node1_addr).await;
+    assert_eq!(
+        count_alert_events(&node1_events, true, AlertKind::ChannelAssetInboundExhausted),
+        1
+    );
+
+    // the alert clears once the channel gets some inbound asset liquidity
+    keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
+    wait_for_alerts(node1_addr, &[]).await;
+    let node1_events = events(node1_addr).await;
+    assert_eq!(
+        count_alert_events(
+            &node1_events,
+            false,
+            AlertKind::ChannelAssetInboundExhausted
+        ),
+        1
+    );
+
+    // the vanilla wallet can't cover unrealistic reserves
+    set_alerts(
+        node1_addr,
+        &SetAlertsRequest {
+            anchor_reserve_per_channel_sat: Some(1_000_000_000),
+            min_fee_bump_utxos: Some(1000),
+            min_channel_asset_amount: None,
+            max_unconfirmed_tx_age_secs: None,
+        },
+    )
+    .await;
+    wait_for_alerts(
+        node1_addr,
+        &[AlertKind::LowAnchorReserve, AlertKind::LowFeeBumpReserve],
+    )
+    .await;
+
+    // removing the thresholds clears the alerts
+    set_alerts(
+        node1_addr,
+        &SetAlertsRequest {
+            anchor_reserve_per_channel_sat: None,
+            min_fee_bump_utxos: None,
+            min_channel_asset_amount: None,
+            max_unconfirmed_tx_age_secs: None,
+        },
+    )
+    .await;
+    wait_for_alerts(node1_addr, &[]).await;
+    let node1_events = events(node1_addr).await;
+    assert_eq!(
+        count_alert_events(&node1_events, false, AlertKind::LowAnchorReserve),
+        1
+    );
+}
@@ -0,0 +1,64 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/balances/";
+const NODE1_PEER_PORT: u16 = 10381;
+const NODE2_PEER_PORT: u16 = 10382;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn balances() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    keysend_with_ln_balance(
+        node1_addr,
+        node2_addr,
+        &node2_pubkey,
+        &asset_id,
+        150,
+        600,
+        0,
+    )
+    .await;
+    keysend_with_ln_balance(
+        node2_addr,
+        node1_addr,
+        &node1_pubkey,
+        &asset_id,
+        50,
+        150,
+        450,
+    )
+    .await;
+
+    let node1_balances = super::balances(node1_addr).await;
+    let asset_balances = node1_balances
+        .assets
+        .iter()
+        .find(|a| a.asset_id == asset_id)
+        .unwrap();
+    assert_eq!(asset_balances.onchain_spendable, 400);
+    assert_eq!(asset_balances.offchain_outbound, 450);
+    assert_eq!(asset_balances.offchain_inbound, 150);
+    assert_eq!(asset_balances.total, 850);
+    assert!(node1_balances.btc.offchain_local_msat > 0);
+    assert_eq!(
+        node1_balances.btc.total_msat,
+        (node1_balances.btc.onchain_vanilla.future + node1_balances.btc.onchain_colored.future)
+            * 1000
+            + node1_balances.btc.offchain_local_msat
+    );
+}
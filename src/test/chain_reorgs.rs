@@ -0,0 +1,180 @@
+use bitcoin::BlockHash;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/chain_reorgs/";
+const NODE1_PEER_PORT: u16 = 10330;
+const NODE2_PEER_PORT: u16 = 10331;
+
+fn generate_block(txids: &[String]) -> BlockHash {
+    let block_hash = block_on(test_env().regtest_backend().generate_block(txids));
+    wait_electrs_sync();
+    block_hash
+}
+
+fn invalidate_block(block_hash: &BlockHash) {
+    block_on(test_env().regtest_backend().invalidate_block(block_hash));
+}
+
+/// Wait for the node to report a reorg, returning its depth, affected channels and transfers
+async fn wait_for_reorg(node_address: SocketAddr) -> (u32, Vec<String>, Vec<ReorgedTransfer>) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        for event in events(node_address).await.into_iter().rev() {
+            if let NodeEvent::ChainReorg {
+                depth,
+                channel_ids,
+                transfers,
+                ..
+            } = event.event
+            {
+                return (depth, channel_ids, transfers);
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("chain reorg not reported")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+async fn wait_for_pending_confirmations(node_address: SocketAddr, confirmations: u32) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let channels = pending_channels(node_address).await;
+        if channels.len() == 1 && channels[0].confirmations == confirmations {
+            return;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("pending channel not reaching {confirmations} confirmations")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+async fn transfer_status(node_address: SocketAddr, asset_id: &str, txid: &str) -> TransferStatus {
+    list_transfers(node_address, asset_id)
+        .await
+        .into_iter()
+        .find(|t| t.txid.as_deref() == Some(txid))
+        .unwrap()
+        .status
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn chain_reorgs() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // a pending channel whose funding gets reorged out goes back to waiting for it
+    stop_mining();
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", node2_pubkey, NODE2_PEER_PORT),
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(600),
+        asset_id: asset_id.clone(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    };
+    _check_response_is_ok(open_channel_raw(node1_addr, &payload).await).await;
+    let t_0 = OffsetDateTime::now_utc();
+    let pending = loop {
+        let channels = pending_channels(node1_addr).await;
+        if let Some(channel) = channels.into_iter().next() {
+            if channel
+                .funding_txid
+                .as_ref()
+                .is_some_and(|t| txout_exists(t))
+            {
+                break channel;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("funding transaction not broadcast")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    let funding_block = generate_block(&[pending.funding_txid.clone().unwrap()]);
+    wait_for_pending_confirmations(node1_addr, 1).await;
+
+    invalidate_block(&funding_block);
+    let (depth, channel_ids, transfers) = wait_for_reorg(node1_addr).await;
+    assert_eq!(depth, 1);
+    assert_eq!(channel_ids, vec![pending.channel_id.clone()]);
+    assert!(transfers.is_empty());
+    wait_for_pending_confirmations(node1_addr, 0).await;
+
+    // the funding confirming again lets the channel get ready
+    let channel = wait_for_channel_ready(node1_addr, &node2_pubkey).await;
+    assert_eq!(channel.channel_id, pending.channel_id);
+
+    // a settled transfer whose anchor gets reorged out is reported unconfirmed until it confirms
+    // again
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    let txid = send_asset(node1_addr, &asset_id, 50, recipient_id)
+        .await
+        .txid
+        .unwrap();
+    stop_mining();
+    let anchor_block = generate_block(&[txid.clone()]);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    assert!(matches!(
+        transfer_status(node1_addr, &asset_id, &txid).await,
+        TransferStatus::Settled
+    ));
+
+    invalidate_block(&anchor_block);
+    let (depth, channel_ids, transfers) = wait_for_reorg(node1_addr).await;
+    assert_eq!(depth, 1);
+    assert!(channel_ids.is_empty());
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(
+        (&transfers[0].asset_id, &transfers[0].txid),
+        (&asset_id, &txid)
+    );
+    assert!(matches!(
+        transfer_status(node1_addr, &asset_id, &txid).await,
+        TransferStatus::WaitingConfirmations
+    ));
+    let change: Vec<RgbAllocation> = list_unspents(node1_addr)
+        .await
+        .into_iter()
+        .filter(|u| u.utxo.outpoint.starts_with(&txid))
+        .flat_map(|u| u.rgb_allocations)
+        .collect();
+    assert!(!change.is_empty());
+    assert!(change.iter().all(|a| !a.settled));
+
+    mine(true);
+    let t_0 = OffsetDateTime::now_utc();
+    while !matches!(
+        transfer_status(node1_addr, &asset_id, &txid).await,
+        TransferStatus::Settled
+    ) {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("transfer not settled again")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
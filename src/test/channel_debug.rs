@@ -0,0 +1,116 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_debug/";
+const NODE1_PEER_PORT: u16 = 10321;
+const NODE2_PEER_PORT: u16 = 10322;
+
+async fn channel_debug_raw(node_address: SocketAddr, channel_id: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("http://{}/channeldebug", node_address))
+        .query(&[("channel_id", channel_id)])
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn get_channel_debug(node_address: SocketAddr, channel_id: &str) -> ChannelDebugResponse {
+    _check_response_is_ok(channel_debug_raw(node_address, channel_id).await)
+        .await
+        .json::<ChannelDebugResponse>()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_reestablish(
+    node_address: SocketAddr,
+    channel_id: &str,
+    expected: ReestablishState,
+) -> ChannelDebugResponse {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let debug = get_channel_debug(node_address, channel_id).await;
+        if debug.peer.reestablish == expected {
+            return debug;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("channel_reestablish outcome is not becoming {expected:?}")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_debug() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, node2_password) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    let debug = get_channel_debug(node1_addr, &channel.channel_id).await;
+    assert_eq!(debug.peer_pubkey, node2_pubkey);
+    assert!(debug.outbound && debug.ready && debug.is_usable);
+    assert_eq!(debug.commitment_type, Some(CommitmentType::Anchors));
+    assert!(debug.feerate_sat_per_1000_weight.is_some());
+    assert!(debug.our_reserve_sat.is_some() && debug.their_reserve_sat > 0);
+    assert!(debug.htlcs.is_empty());
+    assert_eq!(debug.pending_monitor_updates, 0);
+    let rgb = debug.rgb.unwrap();
+    assert_eq!(rgb.asset_id, asset_id);
+    assert_eq!((rgb.local_amount, rgb.remote_amount), (600, 0));
+    assert_eq!(rgb.htlc_amount, 0);
+    assert!(debug.peer.connected);
+    assert_eq!(debug.peer.reestablish, ReestablishState::Completed);
+    let monitor_update_id = debug.monitor_update_id.unwrap();
+
+    // the counterparty sees the same channel from its side
+    let debug = get_channel_debug(node2_addr, &channel.channel_id).await;
+    assert_eq!(debug.peer_pubkey, node1_pubkey);
+    assert!(!debug.outbound);
+    let rgb = debug.rgb.unwrap();
+    assert_eq!((rgb.local_amount, rgb.remote_amount), (0, 600));
+
+    // payments move the commitments forward
+    keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
+    let debug = get_channel_debug(node1_addr, &channel.channel_id).await;
+    assert!(debug.monitor_update_id.unwrap() > monitor_update_id);
+    assert!(debug.htlcs.is_empty());
+    let rgb = debug.rgb.unwrap();
+    assert_eq!((rgb.local_amount, rgb.remote_amount), (500, 100));
+
+    // the channel gets reestablished once the peer comes back
+    lock(node2_addr).await;
+    let debug = wait_for_reestablish(
+        node1_addr,
+        &channel.channel_id,
+        ReestablishState::PeerDisconnected,
+    )
+    .await;
+    assert!(!debug.is_usable);
+    assert!(debug.peer.connected_since.is_none());
+    unlock(node2_addr, node2_password).await;
+    let debug =
+        wait_for_reestablish(node1_addr, &channel.channel_id, ReestablishState::Completed).await;
+    assert!(debug.is_usable);
+    assert!(debug.peer.connected_since >= debug.peer.last_seen);
+    assert_eq!(debug.peer.disconnect_count, 1);
+
+    // only the open channels can be looked at
+    let res = channel_debug_raw(node1_addr, "not-a-channel-id").await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let res = channel_debug_raw(node1_addr, &"00".repeat(32)).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Unknown channel");
+}
@@ -0,0 +1,136 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_fee_rates/";
+const NODE1_PEER_PORT: u16 = 10312;
+const NODE2_PEER_PORT: u16 = 10313;
+
+fn open_payload(
+    dest_peer_pubkey: &str,
+    asset_id: &str,
+    fee_rate_sat_vb: Option<f32>,
+    target_conf_blocks: Option<u16>,
+) -> OpenChannelRequest {
+    OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", dest_peer_pubkey, NODE2_PEER_PORT),
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(600),
+        asset_id: asset_id.to_string(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb,
+        target_conf_blocks,
+        dry_run: false,
+    }
+}
+
+async fn check_fee_rate_error(res: reqwest::Response, expected: &str) {
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, format!("Invalid fee rate: {expected}"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_fee_rates() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // a fee rate and a confirmation target can't be combined
+    let payload = open_payload(&node2_pubkey, &asset_id, Some(10.0), Some(6));
+    check_fee_rate_error(
+        open_channel_raw(node1_addr, &payload).await,
+        "only one of fee_rate_sat_vb and target_conf_blocks can be provided",
+    )
+    .await;
+    let payload = open_payload(&node2_pubkey, &asset_id, None, Some(0));
+    check_fee_rate_error(
+        open_channel_raw(node1_addr, &payload).await,
+        "confirmation target must be between 1 and 1008 blocks",
+    )
+    .await;
+    let payload = open_payload(&node2_pubkey, &asset_id, Some(0.5), None);
+    check_fee_rate_error(
+        open_channel_raw(node1_addr, &payload).await,
+        "cannot be less than 1 sat/vB",
+    )
+    .await;
+
+    // the pending channel reports the fee rate its funding pays
+    stop_mining();
+    let payload = open_payload(&node2_pubkey, &asset_id, Some(15.0), None);
+    _check_response_is_ok(open_channel_raw(node1_addr, &payload).await).await;
+    let t_0 = OffsetDateTime::now_utc();
+    let pending = loop {
+        let channels = pending_channels(node1_addr).await;
+        if let Some(channel) = channels
+            .into_iter()
+            .find(|c| c.stage == PendingChannelStage::AwaitingConfirmations)
+        {
+            break channel;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("funding is taking too long to be broadcast")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    let fee_rate_sat_vb = pending.fee_rate_sat_vb.unwrap();
+    assert!((15.0..16.0).contains(&fee_rate_sat_vb), "{fee_rate_sat_vb}");
+    assert_eq!(pending.target_conf_blocks, None);
+    let channel = wait_for_channel_ready(node1_addr, &node2_pubkey).await;
+
+    // force closes pay the fee rate of the commitment transaction
+    let mut payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: true,
+        dry_run: false,
+        asset_destination: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: Some(6),
+        min_fee_rate_sat_vb: None,
+        max_fee_rate_sat_vb: None,
+        force_after_sec: None,
+    };
+    check_fee_rate_error(
+        close_channel_raw(node1_addr, &payload).await,
+        "not supported by force closes",
+    )
+    .await;
+
+    // the confirmation target of a cooperative close is kept with the channel
+    stop_mining();
+    payload.force = false;
+    _check_response_is_ok(close_channel_raw(node1_addr, &payload).await).await;
+    let t_0 = OffsetDateTime::now_utc();
+    let closed = loop {
+        let channels = list_closed_channels(node1_addr).await;
+        if let Some(closed) = channels
+            .into_iter()
+            .find(|c| c.channel_id == channel.channel_id)
+        {
+            break closed;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel is taking too long to close")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    mine_n_blocks(true, 6);
+    assert_eq!(closed.close_target_conf_blocks, Some(6));
+    assert!(closed.close_fee_rate_sat_vb.unwrap() >= 1.0);
+}
@@ -0,0 +1,155 @@
+use super::*;
+use crate::channel_handshake::{AnnouncedChannelPreference, ChannelHandshakeDefaults};
+
+const TEST_DIR_BASE: &str = "tmp/channel_handshake/";
+const NODE1_PEER_PORT: u16 = 10346;
+const NODE2_PEER_PORT: u16 = 10347;
+const NODE3_PEER_PORT: u16 = 10348;
+
+/// Blocks the peer of the node waits for its funds after force-closing the given channel, as set
+/// by the node
+async fn counterparty_to_self_delay(node_address: SocketAddr, channel_id: &str) -> Option<u16> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/channeldebug", node_address))
+        .query(&[("channel_id", channel_id)])
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ChannelDebugResponse>()
+        .await
+        .unwrap()
+        .to_self_delay
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_handshake() {
+    initialize();
+
+    // values out of bounds are refused at startup
+    for (defaults, error) in [
+        (
+            ChannelHandshakeDefaults {
+                to_self_delay: 143,
+                ..Default::default()
+            },
+            "to_self_delay must be between 144 and 2016 blocks",
+        ),
+        (
+            ChannelHandshakeDefaults {
+                to_self_delay: 2017,
+                ..Default::default()
+            },
+            "to_self_delay must be between 144 and 2016 blocks",
+        ),
+        (
+            ChannelHandshakeDefaults {
+                max_htlc_value_in_flight_percent: 0,
+                ..Default::default()
+            },
+            "max HTLC value in flight must be between 1 and 100%",
+        ),
+        (
+            ChannelHandshakeDefaults {
+                dust_limit_sat: 353,
+                ..Default::default()
+            },
+            "dust limit cannot be lower than the protocol floor (354 sat)",
+        ),
+    ] {
+        assert_eq!(
+            defaults.validate().unwrap_err().to_string(),
+            format!("Invalid channel handshake defaults: {error}")
+        );
+    }
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let ldk_sockets = get_ldk_sockets(&[NODE1_PEER_PORT]);
+    let (node1_addr, _) = start_node(test_dir_node1.clone(), NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+    assert_eq!(
+        node_info(node1_addr).await.channel_handshake,
+        ChannelHandshakeDefaults::default()
+    );
+
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 400, &asset_id).await;
+    assert_eq!(
+        counterparty_to_self_delay(node2_addr, &channel.channel_id).await,
+        Some(144)
+    );
+
+    // the node restarts proposing other parameters, reporting the effective ones
+    shutdown(&[node1_addr], &ldk_sockets).await;
+    let channel_handshake = ChannelHandshakeDefaults {
+        to_self_delay: 300,
+        max_htlc_value_in_flight_percent: 50,
+        announced_channel_preference: AnnouncedChannelPreference::Public,
+        dust_limit_sat: 1000,
+    };
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1,
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            channel_handshake,
+            ..Default::default()
+        },
+        true,
+    )
+    .await;
+    assert_eq!(
+        node_info(node1_addr).await.channel_handshake,
+        channel_handshake
+    );
+
+    // push amounts below the new dust limit are refused
+    let res = open_channel_raw(
+        node1_addr,
+        &OpenChannelRequest {
+            peer_pubkey_and_addr: format!("{node3_pubkey}@127.0.0.1:{NODE3_PEER_PORT}"),
+            capacity_sat: Sats(100_000),
+            push_msat: MilliSats(546_000),
+            asset_amount: AssetUnits(400),
+            asset_id: asset_id.clone(),
+            public: true,
+            with_anchors: true,
+            fee_base_msat: None,
+            fee_proportional_millionths: None,
+            min_depth: None,
+            htlc_minimum_msat: None,
+            fee_rate_sat_vb: None,
+            target_conf_blocks: None,
+            dry_run: false,
+        },
+    )
+    .await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Invalid amount: Push amount must be equal or higher than the dust limit (1000000)"
+    );
+
+    // only the channels opened afterwards get them, the existing one keeps its parameters
+    let new_channel =
+        open_channel(node1_addr, &node3_pubkey, NODE3_PEER_PORT, 400, &asset_id).await;
+    assert_eq!(
+        counterparty_to_self_delay(node3_addr, &new_channel.channel_id).await,
+        Some(300)
+    );
+    assert_eq!(
+        counterparty_to_self_delay(node2_addr, &channel.channel_id).await,
+        Some(144)
+    );
+}
@@ -0,0 +1,163 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_min_depth/";
+const NODE1_PEER_PORT: u16 = 10369;
+const NODE2_PEER_PORT: u16 = 10370;
+
+const MIN_DEPTH: u32 = 10;
+
+fn open_payload(node2_pubkey: &str, asset_id: &str, min_depth: u32) -> OpenChannelRequest {
+    OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", node2_pubkey, NODE2_PEER_PORT),
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(600),
+        asset_id: asset_id.to_string(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: Some(min_depth),
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_min_depth() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node2"),
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            inbound_channel_min_depth: MIN_DEPTH,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // the channel gets dropped when the peer would let it become usable with fewer confirmations
+    let res = open_channel_raw(
+        node1_addr,
+        &open_payload(&node2_pubkey, &asset_id, MIN_DEPTH + 2),
+    )
+    .await;
+    let temporary_channel_id = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap()
+        .temporary_channel_id
+        .unwrap();
+    let t_0 = OffsetDateTime::now_utc();
+    let reason = loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let failure = events(node1_addr)
+            .await
+            .into_iter()
+            .find_map(|e| match e.event {
+                NodeEvent::ChannelOpenFailed {
+                    temporary_channel_id: id,
+                    reason,
+                    ..
+                } if id == temporary_channel_id => Some(reason),
+                _ => None,
+            });
+        if let Some(reason) = failure {
+            break reason;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("channel open failure not notified")
+        }
+    };
+    assert_eq!(
+        reason,
+        format!(
+            "peer requires {MIN_DEPTH} confirmations instead of {}",
+            MIN_DEPTH + 2
+        )
+    );
+    assert!(list_channels(node1_addr).await.is_empty());
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 1000);
+
+    // the channel isn't usable before reaching the requested depth
+    stop_mining();
+    let res = open_channel_raw(
+        node1_addr,
+        &open_payload(&node2_pubkey, &asset_id, MIN_DEPTH),
+    )
+    .await;
+    _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap();
+    let channel = wait_for_funding_broadcast(node1_addr, &node2_pubkey).await;
+    assert_eq!(channel.confirmations_required, Some(MIN_DEPTH));
+    assert_eq!(channel.confirmations, Some(0));
+
+    let partial_depth = MIN_DEPTH / 2;
+    mine_n_blocks(true, partial_depth as u16);
+    stop_mining();
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        let channel = channels
+            .iter()
+            .find(|c| c.channel_id == channel.channel_id)
+            .unwrap();
+        if channel.confirmations >= Some(partial_depth) {
+            assert!(channel.confirmations < Some(MIN_DEPTH));
+            assert!(!channel.ready);
+            assert!(!channel.is_usable);
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("channel confirmations not progressing")
+        }
+    }
+    let node2_channel = list_channels(node2_addr)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel.channel_id)
+        .unwrap();
+    assert_eq!(node2_channel.confirmations_required, Some(MIN_DEPTH));
+    assert!(!node2_channel.ready);
+    assert!(!node2_channel.is_usable);
+
+    mine_n_blocks(true, (MIN_DEPTH - partial_depth) as u16);
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        let channel = channels
+            .iter()
+            .find(|c| c.channel_id == channel.channel_id)
+            .unwrap();
+        if channel.ready {
+            assert!(channel.confirmations >= Some(MIN_DEPTH));
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("channel is taking too long to be ready")
+        }
+    }
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 600, 0).await;
+}
@@ -0,0 +1,158 @@
+use super::*;
+use crate::routes::{ChannelSuggestionsResponse, DepletedSide};
+
+const TEST_DIR_BASE: &str = "tmp/channel_suggestions/";
+const NODE1_PEER_PORT: u16 = 10281;
+const NODE2_PEER_PORT: u16 = 10282;
+const NODE3_PEER_PORT: u16 = 10283;
+const NODE4_PEER_PORT: u16 = 10284;
+
+async fn get_channel_suggestions(
+    node_address: SocketAddr,
+    days: u64,
+) -> ChannelSuggestionsResponse {
+    let res = reqwest::Client::new()
+        .get(format!(
+            "http://{}/channelsuggestions?days={days}",
+            node_address
+        ))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ChannelSuggestionsResponse>()
+        .await
+        .unwrap()
+}
+
+async fn ln_invoice_unchecked(
+    node_address: SocketAddr,
+    asset_id: &str,
+    asset_amount: u64,
+) -> LNInvoiceResponse {
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(MilliSats(3000000)),
+        expiry_sec: 900,
+        asset_id: Some(asset_id.to_string()),
+        asset_amount: Some(AssetUnits(asset_amount)),
+        label: None,
+        allow_insufficient_inbound: true,
+        require_payer_pubkey: None,
+        require_payer_note: false,
+        min_final_cltv_expiry_delta: None,
+        unified: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_suggestions() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let test_dir_node4 = format!("{TEST_DIR_BASE}node4");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(test_dir_node3, NODE3_PEER_PORT, false).await;
+    let (node4_addr, _) = start_node(test_dir_node4, NODE4_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+    fund_and_create_utxos(node3_addr).await;
+    fund_and_create_utxos(node4_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+    let node4_pubkey = node_info(node4_addr).await.pubkey;
+
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    send_asset(node1_addr, &asset_id, 100, recipient_id).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+
+    let channel_12 = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    let channel_23 = open_channel(node2_addr, &node3_pubkey, NODE3_PEER_PORT, 100, &asset_id).await;
+    let channel_14 = open_channel(node1_addr, &node4_pubkey, NODE4_PEER_PORT, 50, &asset_id).await;
+
+    // nothing to suggest before any payment, the channels being too recent to be closed
+    let suggestions = get_channel_suggestions(node1_addr, 1).await;
+    assert!(suggestions.close.is_empty());
+    assert!(suggestions.open.is_empty());
+
+    // a forward through node2 drains the channel towards node3
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node3_addr, &asset_id, 95, 900).await;
+    send_payment(node1_addr, invoice).await;
+
+    let rebalance = get_channel_suggestions(node2_addr, 1).await.rebalance;
+    let suggestion = rebalance
+        .iter()
+        .find(|s| s.channel_id == channel_23.channel_id)
+        .unwrap();
+    assert_eq!(suggestion.peer_pubkey, node3_pubkey);
+    assert_eq!(suggestion.depleted_side, DepletedSide::Outbound);
+    assert_eq!(suggestion.asset_id, Some(asset_id.clone()));
+    assert_eq!(suggestion.suggested_asset_amount, Some(45));
+    assert_eq!(suggestion.suggested_amt_msat, None);
+    assert!(!rebalance
+        .iter()
+        .any(|s| s.channel_id == channel_12.channel_id));
+
+    // payments from node3 to node1 fail, its invoices having been cancelled
+    for _ in 0..3 {
+        let LNInvoiceResponse { invoice, .. } =
+            ln_invoice_unchecked(node1_addr, &asset_id, 50).await;
+        let payment_hash = decode_ln_invoice(node1_addr, &invoice).await.payment_hash;
+        let res = reqwest::Client::new()
+            .post(format!("http://{}/cancelinvoice", node1_addr))
+            .json(&CancelInvoiceRequest { payment_hash })
+            .send()
+            .await
+            .unwrap();
+        _check_response_is_ok(res).await;
+        send_payment_with_status(node3_addr, invoice, HTLCStatus::Failed).await;
+    }
+    let open = get_channel_suggestions(node3_addr, 1).await.open;
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].peer_pubkey, node1_pubkey);
+    assert_eq!(open[0].failed_payments, 3);
+    assert_eq!(open[0].succeeded_payments, 0);
+
+    // once open for the whole period, only the channel without payments is a candidate to close
+    mine_n_blocks(false, 144);
+    let t_0 = OffsetDateTime::now_utc();
+    let close = loop {
+        let close = get_channel_suggestions(node1_addr, 1).await.close;
+        if !close.is_empty() {
+            break close;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel has not been suggested for closing")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert_eq!(close.len(), 1);
+    assert_eq!(close[0].channel_id, channel_14.channel_id);
+    assert_eq!(close[0].peer_pubkey, node4_pubkey);
+    assert_eq!(close[0].last_activity, None);
+    let close = get_channel_suggestions(node2_addr, 1).await.close;
+    assert!(close.is_empty());
+}
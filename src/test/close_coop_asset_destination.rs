@@ -0,0 +1,124 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/close_coop_asset_destination/";
+const NODE1_PEER_PORT: u16 = 10111;
+const NODE2_PEER_PORT: u16 = 10112;
+
+async fn check_invalid_destination(
+    node_address: SocketAddr,
+    payload: &CloseChannelRequest,
+    expected_error: &str,
+) {
+    let res = close_channel_raw(node_address, payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, expected_error);
+    assert_eq!(response.code, 400);
+}
+
+async fn wait_for_transfer_settled(node_address: SocketAddr, asset_id: &str, recipient_id: &str) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        refresh_transfers(node_address).await;
+        if list_transfers(node_address, asset_id)
+            .await
+            .iter()
+            .any(|t| {
+                t.recipient_id.as_deref() == Some(recipient_id)
+                    && matches!(t.status, TransferStatus::Settled)
+            })
+        {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("transfer is taking too long to settle")
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn close_coop_asset_destination() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
+
+    let recipient_id = rgb_invoice(node1_addr, Some(asset_id.clone()))
+        .await
+        .recipient_id;
+    let receive_utxo = list_transfers(node1_addr, &asset_id)
+        .await
+        .into_iter()
+        .find(|t| t.recipient_id.as_ref() == Some(&recipient_id))
+        .unwrap()
+        .receive_utxo
+        .unwrap();
+
+    // force closes and blinded UTXOs of other wallets are rejected
+    let mut payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: true,
+        dry_run: false,
+        asset_destination: Some(recipient_id.clone()),
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        min_fee_rate_sat_vb: None,
+        max_fee_rate_sat_vb: None,
+        force_after_sec: None,
+    };
+    check_invalid_destination(
+        node1_addr,
+        &payload,
+        "Invalid asset destination: not supported by force closes",
+    )
+    .await;
+    payload.force = false;
+    payload.asset_destination = Some(
+        rgb_invoice(node2_addr, Some(asset_id.clone()))
+            .await
+            .recipient_id,
+    );
+    check_invalid_destination(
+        node1_addr,
+        &payload,
+        "Invalid asset destination: not a pending blinded UTXO of the node for the channel asset",
+    )
+    .await;
+    assert_eq!(list_channels(node1_addr).await.len(), 1);
+
+    close_channel_to(
+        node1_addr,
+        &channel.channel_id,
+        &node2_pubkey,
+        false,
+        Some(recipient_id.clone()),
+    )
+    .await;
+    wait_for_transfer_settled(node1_addr, &asset_id, &recipient_id).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 1000);
+
+    // the channel assets landed on the UTXO behind the blinded UTXO
+    let unspent = list_unspents(node1_addr)
+        .await
+        .into_iter()
+        .find(|u| u.utxo.outpoint == receive_utxo)
+        .unwrap();
+    assert!(unspent
+        .rgb_allocations
+        .iter()
+        .any(|a| a.asset_id.as_ref() == Some(&asset_id) && a.amount == 600 && a.settled));
+}
@@ -5,7 +5,37 @@ const NODE1_PEER_PORT: u16 = 9841;
 const NODE2_PEER_PORT: u16 = 9842;
 const NODE3_PEER_PORT: u16 = 9843;
 
-#[serial_test::serial]
+fn close_payload(
+    channel_id: &str,
+    peer_pubkey: &str,
+    min_fee_rate_sat_vb: Option<f32>,
+    max_fee_rate_sat_vb: Option<f32>,
+) -> CloseChannelRequest {
+    CloseChannelRequest {
+        channel_id: channel_id.to_string(),
+        peer_pubkey: peer_pubkey.to_string(),
+        force: false,
+        dry_run: false,
+        asset_destination: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        min_fee_rate_sat_vb,
+        max_fee_rate_sat_vb,
+        force_after_sec: None,
+    }
+}
+
+async fn check_close_error(
+    node_address: SocketAddr,
+    payload: &CloseChannelRequest,
+    expected: &str,
+) {
+    let res = close_channel_raw(node_address, payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, expected);
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[traced_test]
 async fn close_coop_other_side() {
@@ -34,10 +64,64 @@ async fn close_coop_other_side() {
 
     keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
 
-    close_channel(node2_addr, &channel.channel_id, &node1_pubkey, false).await;
+    // the acceptable closing fee rates must make sense
+    let payload = close_payload(&channel.channel_id, &node1_pubkey, Some(5.0), Some(4.0));
+    check_close_error(
+        node2_addr,
+        &payload,
+        "Invalid fee rate: min_fee_rate_sat_vb cannot exceed max_fee_rate_sat_vb",
+    )
+    .await;
+    let mut payload = close_payload(&channel.channel_id, &node1_pubkey, None, Some(4.0));
+    payload.fee_rate_sat_vb = Some(8.0);
+    check_close_error(
+        node2_addr,
+        &payload,
+        "Invalid fee rate: the targeted 8 sat/vB is out of the acceptable closing fee rates",
+    )
+    .await;
+    let mut payload = close_payload(&channel.channel_id, &node1_pubkey, Some(5.0), None);
+    payload.force_after_sec = Some(0);
+    check_close_error(
+        node2_addr,
+        &payload,
+        "Invalid close timeout: must be at least 1 second",
+    )
+    .await;
+    payload.force_after_sec = Some(60);
+    payload.force = true;
+    check_close_error(
+        node2_addr,
+        &payload,
+        "Invalid fee rate: not supported by force closes",
+    )
+    .await;
+    // the funder cannot cap the fee below what LDK estimates a close needs
+    let payload = close_payload(&channel.channel_id, &node2_pubkey, None, Some(1.0));
+    let res = close_channel_raw(node1_addr, &payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    assert_eq!(list_channels(node1_addr).await.len(), 1);
+
+    // the funder would pay the minimum fee rate, the peer closing the channel demanding more
+    let mut payload = close_payload(&channel.channel_id, &node1_pubkey, Some(5.0), Some(6.0));
+    payload.force_after_sec = Some(60);
+    close_channel_with_payload(node2_addr, &payload).await;
     wait_for_balance(node1_addr, &asset_id, 900).await;
     wait_for_balance(node2_addr, &asset_id, 100).await;
 
+    // both sides see the fee rate they have agreed on, only the funder paying for it
+    let closed_node1 = list_closed_channels(node1_addr).await.pop().unwrap();
+    let closed_node2 = list_closed_channels(node2_addr).await.pop().unwrap();
+    assert_eq!(closed_node1.channel_id, channel.channel_id);
+    assert!(closed_node1.close_fee_sat > 0);
+    assert_eq!(closed_node2.close_fee_sat, 0);
+    let negotiated_fee_rate = closed_node1.negotiated_close_fee_rate_sat_vb.unwrap();
+    assert!(negotiated_fee_rate >= 5.0);
+    assert_eq!(
+        closed_node2.negotiated_close_fee_rate_sat_vb,
+        Some(negotiated_fee_rate)
+    );
+
     let recipient_id = rgb_invoice(node3_addr, None).await.recipient_id;
     send_asset(node1_addr, &asset_id, 700, recipient_id).await;
     mine(false);
@@ -5,7 +5,6 @@ const NODE1_PEER_PORT: u16 = 9861;
 const NODE2_PEER_PORT: u16 = 9862;
 const NODE3_PEER_PORT: u16 = 9863;
 
-#[serial_test::serial]
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[traced_test]
 async fn close_force_standard() {
@@ -0,0 +1,76 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/colored_payment_silent_proxy/";
+const NODE1_PEER_PORT: u16 = 10373;
+const NODE2_PEER_PORT: u16 = 10374;
+const SILENT_PROXY_PORT: u16 = 10375;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn colored_payment_silent_proxy() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let ldk_sockets = get_ldk_sockets(&[NODE2_PEER_PORT]);
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2.clone(), NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // node2 comes back with a proxy that accepts connections but never answers
+    let _silent_proxy = tokio::net::TcpListener::bind(("127.0.0.1", SILENT_PROXY_PORT))
+        .await
+        .unwrap();
+    shutdown(&[node2_addr], &ldk_sockets).await;
+    let (node2_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node2,
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            proxy_endpoints: vec![format!("rpc://127.0.0.1:{SILENT_PROXY_PORT}/json-rpc")],
+            ..Default::default()
+        },
+        true,
+    )
+    .await;
+    assert!(!node_info(node2_addr).await.proxy_reachable);
+    // the node is still ready, just reporting the proxy as unreachable
+    assert!(!ready(node2_addr).await.proxy_reachable);
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let usable = list_channels(node1_addr)
+            .await
+            .into_iter()
+            .any(|c| c.channel_id == channel.channel_id && c.is_usable);
+        if usable {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel not usable again after the restart")
+        }
+    }
+
+    // the RGB amount travels with the HTLC, so receiving doesn't wait on the proxy
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let t_0 = OffsetDateTime::now_utc();
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 600, 0).await;
+    assert!((OffsetDateTime::now_utc() - t_0).as_seconds_f32() < 30.0);
+    let payment = list_payments(node2_addr).await.pop().unwrap();
+    assert!(payment.inbound);
+    assert_eq!(payment.status, HTLCStatus::Succeeded);
+    assert_eq!(payment.asset_amount, Some(100));
+
+    // and neither does sending
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node1_addr, &asset_id, 40, 900).await;
+    send_payment_with_ln_balance(node2_addr, node1_addr, invoice, 100, 500).await;
+    let node2_balance = asset_balance(node2_addr, &asset_id).await;
+    assert_eq!(node2_balance.offchain_outbound, 60);
+    assert_eq!(node2_balance.offchain_inbound, 540);
+}
@@ -0,0 +1,108 @@
+use crate::consolidation::ConsolidationPolicy;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/consolidation/";
+const NODE1_PEER_PORT: u16 = 10011;
+const NODE2_PEER_PORT: u16 = 10012;
+
+fn asset_allocations(unspents: &[Unspent], asset_id: &str) -> Vec<u64> {
+    unspents
+        .iter()
+        .flat_map(|u| &u.rgb_allocations)
+        .filter(|a| a.settled && a.asset_id.as_deref() == Some(asset_id))
+        .map(|a| a.amount)
+        .collect()
+}
+
+async fn receive_asset(
+    sender_addr: SocketAddr,
+    receiver_addr: SocketAddr,
+    asset_id: &str,
+    amount: u64,
+) {
+    let recipient_id = rgb_invoice(receiver_addr, None).await.recipient_id;
+    send_asset(sender_addr, asset_id, amount, recipient_id).await;
+    mine(false);
+    refresh_transfers(receiver_addr).await;
+    refresh_transfers(receiver_addr).await;
+    refresh_transfers(sender_addr).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn consolidation() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1,
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            consolidation_policy: Some(ConsolidationPolicy { max_allocations: 2 }),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node2_addr).await;
+
+    // check NothingToConsolidate error
+    let res = consolidate_assets(node1_addr, &asset_id).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Fewer than two allocations of the asset can be consolidated"
+    );
+
+    // manual consolidation, the allocations don't exceed the policy threshold
+    receive_asset(node2_addr, node1_addr, &asset_id, 100).await;
+    receive_asset(node2_addr, node1_addr, &asset_id, 200).await;
+    let unspents = list_unspents(node1_addr).await;
+    assert_eq!(asset_allocations(&unspents, &asset_id).len(), 2);
+    let res = consolidate_assets(node1_addr, &asset_id).await;
+    let response = _check_response_is_ok(res)
+        .await
+        .json::<ConsolidateAssetsResponse>()
+        .await
+        .unwrap();
+    let mut consolidated: Vec<u64> = response
+        .consolidated_allocations
+        .iter()
+        .map(|a| a.amount)
+        .collect();
+    consolidated.sort();
+    assert_eq!(consolidated, vec![100, 200]);
+    mine(false);
+    refresh_transfers(node1_addr).await;
+    refresh_transfers(node1_addr).await;
+    let unspents = list_unspents(node1_addr).await;
+    assert_eq!(asset_allocations(&unspents, &asset_id), vec![300]);
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 300);
+
+    // automatic consolidation once the allocations exceed the threshold
+    receive_asset(node2_addr, node1_addr, &asset_id, 50).await;
+    receive_asset(node2_addr, node1_addr, &asset_id, 150).await;
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        mine(false);
+        refresh_transfers(node1_addr).await;
+        refresh_transfers(node1_addr).await;
+        let unspents = list_unspents(node1_addr).await;
+        if asset_allocations(&unspents, &asset_id) == vec![500] {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("allocations have not been consolidated")
+        }
+    }
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 500);
+}
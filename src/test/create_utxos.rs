@@ -0,0 +1,100 @@
+use crate::error::APIError;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/create_utxos/";
+const NODE1_PEER_PORT: u16 = 10353;
+
+async fn create_utxos_raw(node_address: SocketAddr, up_to: bool, num: u8) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/createutxos", node_address))
+        .json(&CreateUtxosRequest {
+            up_to,
+            num: Some(num),
+        })
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn create_utxos(node_address: SocketAddr, up_to: bool, num: u8) -> CreateUtxosResponse {
+    let res = create_utxos_raw(node_address, up_to, num).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<CreateUtxosResponse>()
+        .await
+        .unwrap()
+}
+
+async fn fund(node_address: SocketAddr, amount_sat: u64) {
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/address", node_address))
+        .send()
+        .await
+        .unwrap();
+    let address = _check_response_is_ok(res)
+        .await
+        .json::<AddressResponse>()
+        .await
+        .unwrap()
+        .address;
+    block_on(
+        test_env()
+            .regtest_backend()
+            .fund_address(&address, amount_sat),
+    );
+    mine(false);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn create_utxos_response() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    // requests the vanilla funds only partly cover are refused, reporting what can be created
+    fund(node1_addr, 100_000).await;
+    let res = create_utxos_raw(node1_addr, false, 5).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    let possible: u8 = response
+        .error
+        .strip_prefix("Not enough funds to create 5 UTXOs, can create at most ")
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(possible > 0 && possible < 5);
+    assert!(list_unspents_filtered(node1_addr, Some(true))
+        .await
+        .is_empty());
+
+    // the created UTXOs are reported with the transaction carrying them
+    let created = create_utxos(node1_addr, false, possible).await;
+    assert_eq!(created.utxos.len(), possible as usize);
+    assert_eq!(created.skipped, 0);
+    mine(false);
+    let unspents = list_unspents_filtered(node1_addr, Some(true)).await;
+    for utxo in &created.utxos {
+        assert!(utxo.outpoint.starts_with(&format!("{}:", created.txid)));
+        let unspent = unspents
+            .iter()
+            .find(|u| u.utxo.outpoint == utxo.outpoint)
+            .unwrap();
+        assert_eq!(unspent.utxo.btc_amount, utxo.btc_amount);
+    }
+
+    // with up_to the free UTXOs already available are skipped
+    let res = create_utxos_raw(node1_addr, true, possible).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        APIError::AllocationsAlreadyAvailable.to_string()
+    );
+    fund(node1_addr, 1_000_000).await;
+    let created = create_utxos(node1_addr, true, possible + 2).await;
+    assert_eq!(created.utxos.len(), 2);
+    assert_eq!(created.skipped, possible);
+}
@@ -0,0 +1,88 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/custom_messages/";
+const NODE1_PEER_PORT: u16 = 9991;
+const NODE2_PEER_PORT: u16 = 9992;
+
+async fn check_bad_request(res: reqwest::Response, error: &str) {
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, error);
+    assert_eq!(response.code, 400);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn custom_messages() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // check PeerNotConnected error
+    let res = send_custom_message(node1_addr, &node2_pubkey, 42001, "68656c6c6f").await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        format!("Peer {node2_pubkey} is not connected")
+    );
+
+    connect_peer(
+        node1_addr,
+        &node2_pubkey,
+        &format!("127.0.0.1:{NODE2_PEER_PORT}"),
+    )
+    .await;
+
+    // check InvalidCustomMessage errors
+    let res = send_custom_message(node1_addr, &node2_pubkey, 42000, "68656c6c6f").await;
+    check_bad_request(res, "Invalid custom message: type must be odd").await;
+    let res = send_custom_message(node1_addr, &node2_pubkey, 101, "68656c6c6f").await;
+    check_bad_request(
+        res,
+        "Invalid custom message: type must be at least 32768 and not one used by the node",
+    )
+    .await;
+    let res = send_custom_message(node1_addr, &node2_pubkey, 42001, "hello").await;
+    check_bad_request(res, "Invalid custom message: need a hex data string").await;
+
+    let res = send_custom_message(node1_addr, &node2_pubkey, 42001, "68656c6c6f").await;
+    _check_response_is_ok(res).await;
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let received = events(node2_addr)
+            .await
+            .into_iter()
+            .find_map(|e| match e.event {
+                NodeEvent::CustomMessageReceived {
+                    peer_pubkey,
+                    msg_type,
+                    data,
+                } => Some((peer_pubkey, msg_type, data)),
+                _ => None,
+            });
+        if let Some((peer_pubkey, msg_type, data)) = received {
+            assert_eq!(peer_pubkey, node1_pubkey);
+            assert_eq!(msg_type, 42001);
+            assert_eq!(data, "68656c6c6f");
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("custom message has not been received")
+        }
+    }
+
+    // the connection survives the exchange
+    assert!(list_peers(node1_addr)
+        .await
+        .iter()
+        .any(|p| p.pubkey == node2_pubkey));
+}
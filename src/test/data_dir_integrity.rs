@@ -0,0 +1,83 @@
+use crate::disk::NETWORK_GRAPH_FNAME;
+use crate::utils::LDK_DIR;
+use lightning::rgb_utils::WALLET_FINGERPRINT_FNAME;
+use lightning::util::persist::CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE;
+use std::{fs, path::PathBuf};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/data_dir_integrity/";
+const NODE1_PEER_PORT: u16 = 10131;
+
+async fn check_corrupted(
+    node_address: SocketAddr,
+    password: &str,
+    regenerate: bool,
+    expected_error: &str,
+) {
+    let res = unlock_raw(node_address, password.to_string(), regenerate).await;
+    assert_eq!(res.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert!(
+        response.error.starts_with(expected_error),
+        "unexpected error: {}",
+        response.error
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn data_dir_integrity() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, node1_password) =
+        start_node(test_dir_node1.clone(), NODE1_PEER_PORT, false).await;
+    let ldk_data_dir = PathBuf::from(&test_dir_node1).join(LDK_DIR);
+    lock(node1_addr).await;
+
+    // a corrupted network graph is only rebuilt on request
+    fs::write(ldk_data_dir.join(NETWORK_GRAPH_FNAME), "garbage").unwrap();
+    check_corrupted(
+        node1_addr,
+        &node1_password,
+        false,
+        "Corrupted data directory: network graph cannot be read",
+    )
+    .await;
+    let res = unlock_raw(node1_addr, node1_password.clone(), true).await;
+    _check_response_is_ok(res).await;
+    lock(node1_addr).await;
+
+    // a corrupted channel monitor is never regenerated
+    let monitors_dir = ldk_data_dir.join(CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE);
+    fs::create_dir_all(&monitors_dir).unwrap();
+    let monitor_key = format!("{}_0", "ab".repeat(32));
+    fs::write(monitors_dir.join(&monitor_key), "garbage").unwrap();
+    check_corrupted(
+        node1_addr,
+        &node1_password,
+        true,
+        &format!("Corrupted data directory: channel monitor {monitor_key} cannot be read"),
+    )
+    .await;
+    fs::remove_file(monitors_dir.join(&monitor_key)).unwrap();
+
+    // missing RGB wallet data is reported before starting the node
+    let fingerprint =
+        fs::read_to_string(PathBuf::from(&test_dir_node1).join(WALLET_FINGERPRINT_FNAME)).unwrap();
+    let wallet_dir = PathBuf::from(&test_dir_node1).join(fingerprint.trim());
+    let moved_wallet_dir = PathBuf::from(&test_dir_node1).join("moved_wallet");
+    fs::rename(&wallet_dir, &moved_wallet_dir).unwrap();
+    check_corrupted(
+        node1_addr,
+        &node1_password,
+        true,
+        "Corrupted data directory: RGB wallet data is missing",
+    )
+    .await;
+    fs::rename(&moved_wallet_dir, &wallet_dir).unwrap();
+
+    unlock(node1_addr, node1_password).await;
+    node_info(node1_addr).await;
+}
@@ -0,0 +1,79 @@
+use crate::data_dir_lock::LOCK_FNAME;
+use std::fs;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/data_dir_lock/";
+const NODE1_PEER_PORT: u16 = 10351;
+const NODE2_PEER_PORT: u16 = 10352;
+
+fn lock_owner_pid(lock_path: &Path) -> u64 {
+    let owner: serde_json::Value = serde_json::from_slice(&fs::read(lock_path).unwrap()).unwrap();
+    owner["pid"].as_u64().unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn data_dir_lock() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let lock_path = Path::new(&test_dir_node1).join(LOCK_FNAME);
+    let ldk_sockets = get_ldk_sockets(&[NODE1_PEER_PORT]);
+    let (node1_addr, node1_password) =
+        start_node(test_dir_node1.clone(), NODE1_PEER_PORT, false).await;
+    assert_eq!(lock_owner_pid(&lock_path), std::process::id() as u64);
+
+    // a second daemon on the same directory refuses to start
+    let second_args = || LdkUserInfo {
+        storage_dir_path: test_dir_node1.clone(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        ..Default::default()
+    };
+    let Err(err) = app(second_args()).await else {
+        panic!("second daemon started on a locked data directory")
+    };
+    assert!(matches!(err, AppError::DataDirInUse(_)));
+    assert!(err.to_string().starts_with(&format!(
+        "Data directory in use by another node: locked by process {}",
+        std::process::id()
+    )));
+
+    // a daemon that lost its lock to another one refuses to unlock
+    lock(node1_addr).await;
+    fs::remove_file(&lock_path).unwrap();
+    let node2_addr = start_daemon_with_args(second_args()).await;
+    let t_0 = OffsetDateTime::now_utc();
+    while !lock_path.exists() {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("second daemon didn't lock the data directory")
+        }
+    }
+    let res = unlock_raw(node1_addr, node1_password.clone(), false).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert!(response
+        .error
+        .starts_with("Data directory in use by another node:"));
+    assert!(response.error.contains("has been replaced"));
+
+    // the lock file is cleared by the daemon holding it when it exits
+    shutdown(&[node1_addr], &ldk_sockets).await;
+    assert_eq!(lock_owner_pid(&lock_path), std::process::id() as u64);
+    shutdown(&[node2_addr], &[]).await;
+    assert!(fs::read(&lock_path).unwrap().is_empty());
+
+    // the lock left by a process that doesn't hold it gets taken over, even if still running
+    let mut stale = Command::new("sleep").arg("30").spawn().unwrap();
+    fs::write(
+        &lock_path,
+        format!("{{\"pid\":{},\"started_at\":0}}", stale.id()),
+    )
+    .unwrap();
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, true).await;
+    assert_eq!(lock_owner_pid(&lock_path), std::process::id() as u64);
+    node_info(node1_addr).await;
+    stale.kill().unwrap();
+    stale.wait().unwrap();
+}
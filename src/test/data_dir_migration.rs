@@ -0,0 +1,126 @@
+use crate::data_dir::{migrate_data_dir, DATA_DIR_FNAME};
+use rusqlite::Connection;
+use std::{fs, path::PathBuf};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/data_dir_migration/";
+const NODE1_PEER_PORT: u16 = 10338;
+const NODE2_PEER_PORT: u16 = 10339;
+
+/// Wait for the channel to be usable again after the node restarted
+async fn wait_for_usable_channel(node_address: SocketAddr, channel_id: &str) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node_address).await;
+        if channels
+            .iter()
+            .any(|c| c.channel_id == channel_id && c.is_usable)
+        {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("channel {channel_id} not usable after the restart")
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn data_dir_migration() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let moved_dir_node1 = format!("{TEST_DIR_BASE}node1_moved");
+    if Path::new(&moved_dir_node1).is_dir() {
+        fs::remove_dir_all(&moved_dir_node1).unwrap();
+    }
+    let ldk_sockets = get_ldk_sockets(&[NODE1_PEER_PORT]);
+    let (node1_addr, node1_password) =
+        start_node(test_dir_node1.clone(), NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
+
+    // the node reports the directory it runs from, which gets recorded at unlock
+    let node1_info = node_info(node1_addr).await;
+    let old_data_dir = fs::canonicalize(&test_dir_node1).unwrap();
+    assert_eq!(node1_info.data_dir, old_data_dir.to_string_lossy());
+    assert_eq!(
+        fs::read_to_string(Path::new(&test_dir_node1).join(DATA_DIR_FNAME)).unwrap(),
+        node1_info.data_dir
+    );
+    shutdown(&[node1_addr], &ldk_sockets).await;
+
+    // references to the old directory get rewritten, not the ones to other directories
+    let references_db = Path::new(&test_dir_node1).join("references.db");
+    let conn = Connection::open(&references_db).unwrap();
+    conn.execute_batch("CREATE TABLE refs (id INTEGER PRIMARY KEY, path TEXT NOT NULL)")
+        .unwrap();
+    let old_dir = old_data_dir.to_string_lossy();
+    for path in [format!("{old_dir}/.ldk/file"), format!("{old_dir}2/file")] {
+        conn.execute("INSERT INTO refs (path) VALUES (?1)", [path])
+            .unwrap();
+    }
+    drop(conn);
+
+    // a missing old directory is refused
+    let err = migrate_data_dir(
+        Path::new(&format!("{TEST_DIR_BASE}missing")),
+        Path::new(&moved_dir_node1),
+    )
+    .unwrap_err();
+    assert!(err.to_string().starts_with(
+        "Failed to migrate the data directory: no node data found in tmp/data_dir_migration/missing"
+    ));
+
+    // the node starts from the new directory, with the old one left untouched
+    let node1_addr = start_daemon_with_args(LdkUserInfo {
+        storage_dir_path: moved_dir_node1.clone(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        migrate_data_dir_from: Some(PathBuf::from(&test_dir_node1)),
+        ..Default::default()
+    })
+    .await;
+    unlock(node1_addr, node1_password).await;
+    let new_data_dir = fs::canonicalize(&moved_dir_node1).unwrap();
+    let new_dir = new_data_dir.to_string_lossy();
+    let moved_node1_info = node_info(node1_addr).await;
+    assert_eq!(moved_node1_info.pubkey, node1_info.pubkey);
+    assert_eq!(moved_node1_info.data_dir, new_dir);
+    assert_eq!(
+        fs::read_to_string(Path::new(&moved_dir_node1).join(DATA_DIR_FNAME)).unwrap(),
+        new_dir
+    );
+    assert_eq!(
+        fs::read_to_string(Path::new(&test_dir_node1).join(DATA_DIR_FNAME)).unwrap(),
+        old_dir
+    );
+    let conn = Connection::open(Path::new(&moved_dir_node1).join("references.db")).unwrap();
+    let paths: Vec<String> = conn
+        .prepare("SELECT path FROM refs ORDER BY id")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        paths,
+        vec![format!("{new_dir}/.ldk/file"), format!("{old_dir}2/file")]
+    );
+
+    // funds and channels moved along with the node
+    wait_for_usable_channel(node1_addr, &channel.channel_id).await;
+    keysend(node1_addr, &node2_pubkey, &asset_id, 50).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 20, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 450, 150).await;
+}
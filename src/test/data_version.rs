@@ -0,0 +1,80 @@
+use crate::data_version::{DataVersion, DATA_VERSION_FNAME};
+use crate::disk::RGB_INVOICE_LABELS_FNAME;
+use crate::ldk::RgbInvoiceLabelStorage;
+use crate::utils::LDK_DIR;
+use lightning::util::ser::Writeable;
+use std::collections::HashMap;
+use std::{fs, path::PathBuf};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/data_version/";
+const NODE1_PEER_PORT: u16 = 10261;
+
+fn read_data_version(storage_dir: &PathBuf) -> DataVersion {
+    serde_json::from_slice(&fs::read(storage_dir.join(DATA_VERSION_FNAME)).unwrap()).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn data_version() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, node1_password) =
+        start_node(test_dir_node1.clone(), NODE1_PEER_PORT, false).await;
+    let storage_dir = PathBuf::from(&test_dir_node1);
+    assert_eq!(read_data_version(&storage_dir), DataVersion::current());
+
+    fund_and_create_utxos(node1_addr).await;
+    let asset_id = issue_asset(node1_addr).await;
+    let recipient_id = rgb_invoice(node1_addr, Some(asset_id.clone()))
+        .await
+        .recipient_id;
+    lock(node1_addr).await;
+
+    // a data directory of the previous format has no version and keeps the labels in a file
+    fs::remove_file(storage_dir.join(DATA_VERSION_FNAME)).unwrap();
+    let labels = RgbInvoiceLabelStorage {
+        labels: HashMap::from([(recipient_id.clone(), s!("legacy label"))]),
+    };
+    let labels_path = storage_dir.join(LDK_DIR).join(RGB_INVOICE_LABELS_FNAME);
+    fs::write(&labels_path, labels.encode()).unwrap();
+
+    // unlocking migrates it to the current format
+    unlock(node1_addr, node1_password.clone()).await;
+    assert_eq!(read_data_version(&storage_dir), DataVersion::current());
+    assert!(!labels_path.exists());
+    let transfer = list_transfers(node1_addr, &asset_id)
+        .await
+        .into_iter()
+        .find(|t| t.recipient_id.as_ref() == Some(&recipient_id))
+        .unwrap();
+    assert_eq!(transfer.label.as_deref(), Some("legacy label"));
+    lock(node1_addr).await;
+
+    // data written by a newer release is refused
+    let newer = DataVersion {
+        format_version: 99,
+        crate_version: s!("9.9.9"),
+    };
+    fs::write(
+        storage_dir.join(DATA_VERSION_FNAME),
+        serde_json::to_vec(&newer).unwrap(),
+    )
+    .unwrap();
+    let res = unlock_raw(node1_addr, node1_password.clone(), false).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Unsupported data version: found format 99 (rgb-lightning-node 9.9.9), supported formats 1 to 2"
+    );
+
+    fs::write(
+        storage_dir.join(DATA_VERSION_FNAME),
+        serde_json::to_vec(&DataVersion::current()).unwrap(),
+    )
+    .unwrap();
+    unlock(node1_addr, node1_password).await;
+}
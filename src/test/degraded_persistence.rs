@@ -0,0 +1,146 @@
+use crate::persistence::PersistenceHealth;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/degraded_persistence/";
+const NODE1_PEER_PORT: u16 = 10141;
+const NODE2_PEER_PORT: u16 = 10142;
+const NODE3_PEER_PORT: u16 = 10356;
+const NODE4_PEER_PORT: u16 = 10357;
+
+// background writes can fail first, so the failing artifact is not checked
+const DEGRADED_ERROR: &str = "Node degraded, new payments and channel updates are refused: \
+    failed persisting ";
+
+async fn check_degraded(res: reqwest::Response) {
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert!(response.error.starts_with(DEGRADED_ERROR));
+    assert!(response.error.ends_with("injected write failure"));
+    assert_eq!(response.code, 403);
+}
+
+async fn ready_raw(node_address: SocketAddr) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("http://{}/ready", node_address))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn degraded_persistence() {
+    initialize();
+
+    let persistence = Arc::new(PersistenceHealth::default());
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node1"),
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            persistence: Arc::clone(&persistence),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 100).await;
+    _check_response_is_ok(ready_raw(node1_addr).await).await;
+    assert!(node_info(node1_addr).await.persistence_failure.is_none());
+    let payments = list_payments(node1_addr).await.len();
+
+    // the disk stops accepting writes, the payment is refused before touching the channel
+    persistence.fail_writes.store(true, Ordering::Release);
+    let res = keysend_with_msat(node1_addr, &node2_pubkey, &asset_id, 50, 3000000).await;
+    check_degraded(res).await;
+    assert_eq!(list_payments(node1_addr).await.len(), payments);
+    wait_for_ln_balance(node1_addr, &asset_id, 500).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 100).await;
+
+    // the condition is reported and raises an alert
+    check_degraded(ready_raw(node1_addr).await).await;
+    let failure = node_info(node1_addr).await.persistence_failure.unwrap();
+    assert!(failure.ends_with("injected write failure"));
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let alerts = alerts(node1_addr).await.alerts;
+        if alerts
+            .iter()
+            .any(|a| a.kind == AlertKind::PersistenceFailure)
+        {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("persistence failure alert not raised")
+        }
+    }
+
+    // the node stays degraded once writes work again, until it restarts
+    persistence.fail_writes.store(false, Ordering::Release);
+    let res = keysend_with_msat(node1_addr, &node2_pubkey, &asset_id, 50, 3000000).await;
+    check_degraded(res).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn degraded_persistence_spendable_output() {
+    initialize();
+
+    let persistence = Arc::new(PersistenceHealth::default());
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}spendable_node1"),
+            ldk_peer_listening_port: NODE3_PEER_PORT,
+            persistence: Arc::clone(&persistence),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let test_dir_node2 = format!("{TEST_DIR_BASE}spendable_node2");
+    let (node2_addr, _) = start_node(test_dir_node2, NODE4_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE4_PEER_PORT, 600, &asset_id).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
+
+    // the output of node1 in the force-closed channel fails to be persisted
+    *persistence.fail_writes_under.lock().unwrap() = Some(s!("pending_spendable_outputs"));
+    close_channel(node2_addr, &channel.channel_id, &node1_pubkey, true).await;
+    let t_0 = OffsetDateTime::now_utc();
+    while node_info(node1_addr).await.persistence_failure.is_none() {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("spendable output write not attempted")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    let failure = node_info(node1_addr).await.persistence_failure.unwrap();
+    assert!(failure.starts_with("failed persisting pending_spendable_outputs/"));
+    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
+
+    // the output is kept until a write succeeds, then it gets swept with its assets
+    *persistence.fail_writes_under.lock().unwrap() = None;
+    wait_for_balance(node1_addr, &asset_id, 1000).await;
+}
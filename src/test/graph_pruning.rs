@@ -0,0 +1,85 @@
+use super::*;
+use crate::routes::PruneGraphResponse;
+
+const TEST_DIR_BASE: &str = "tmp/graph_pruning/";
+const NODE1_PEER_PORT: u16 = 10241;
+const NODE2_PEER_PORT: u16 = 10242;
+const NODE3_PEER_PORT: u16 = 10243;
+
+async fn prune_graph(node_address: SocketAddr) -> PruneGraphResponse {
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/prunegraph", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PruneGraphResponse>()
+        .await
+        .unwrap()
+}
+
+async fn knows_node(node_address: SocketAddr, pubkey: &str) -> bool {
+    query_node_info_raw(node_address, pubkey)
+        .await
+        .status()
+        .is_success()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn graph_pruning() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    send_asset(node1_addr, &asset_id, 400, recipient_id).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 500, &asset_id).await;
+    let channel_23 = open_channel(node2_addr, &node3_pubkey, NODE3_PEER_PORT, 300, &asset_id).await;
+    // the channels get announced after 6 confirmations
+    mine_n_blocks(false, 6);
+
+    let t_0 = OffsetDateTime::now_utc();
+    while !knows_node(node1_addr, &node3_pubkey).await {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("channel announcement not received")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    }
+
+    // live channels are kept
+    let pruned = prune_graph(node1_addr).await;
+    assert_eq!(pruned.channels_removed, 0);
+    assert_eq!(pruned.nodes_removed, 0);
+
+    // once its funding is spent the channel goes, along with node3 which has no other channel,
+    // while the channel of node1 stays
+    close_channel(node2_addr, &channel_23.channel_id, &node3_pubkey, false).await;
+    let pruned = prune_graph(node1_addr).await;
+    assert_eq!(pruned.channels_removed, 1);
+    assert_eq!(pruned.nodes_removed, 1);
+    assert!(!knows_node(node1_addr, &node3_pubkey).await);
+    assert!(knows_node(node1_addr, &node2_pubkey).await);
+
+    let pruned = prune_graph(node1_addr).await;
+    assert_eq!(pruned.channels_removed, 0);
+}
@@ -0,0 +1,108 @@
+use crate::routes::HTLC_MIN_MSAT;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/htlc_dust_limits/";
+const NODE1_PEER_PORT: u16 = 10021;
+const NODE2_PEER_PORT: u16 = 10022;
+
+async fn check_below_dust_limit(res: reqwest::Response, expected_min_msat: u64) {
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        format!(
+            "Amount below the dust limit of colored HTLCs: amount must be at least {expected_min_msat} msat"
+        )
+    );
+    assert_eq!(response.code, 400);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn htlc_dust_limits() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // node1 only accepts HTLCs of at least 4000000 msat
+    let channel = open_channel_with_custom_config(
+        node1_addr,
+        &node2_pubkey,
+        NODE2_PEER_PORT,
+        600,
+        &asset_id,
+        None,
+        None,
+        Some(4_000_000),
+    )
+    .await;
+    assert_eq!(channel.htlc_min_sendable_msat, HTLC_MIN_MSAT);
+    assert_eq!(channel.htlc_min_receivable_msat, 4_000_000);
+    assert_eq!(channel.htlc_min_asset_amount, Some(1));
+    let node2_channel = list_channels(node2_addr)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel.channel_id)
+        .unwrap();
+    assert_eq!(node2_channel.htlc_min_sendable_msat, 4_000_000);
+    assert_eq!(node2_channel.htlc_min_receivable_msat, HTLC_MIN_MSAT);
+
+    // node1 -> node2, the boundary is the colored HTLC minimum
+    let res = keysend_with_msat(node1_addr, &node2_pubkey, &asset_id, 100, 2_999_999).await;
+    check_below_dust_limit(res, HTLC_MIN_MSAT).await;
+    let res = keysend_with_msat(node1_addr, &node2_pubkey, &asset_id, 0, 3_000_000).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Amount below the dust limit of colored HTLCs: asset amount must be at least 1"
+    );
+    keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
+
+    // node2 -> node1, the boundary is the minimum set by node1 at open time
+    let res = keysend_with_msat(node2_addr, &node1_pubkey, &asset_id, 50, 3_999_999).await;
+    check_below_dust_limit(res, 4_000_000).await;
+    let res = keysend_with_msat(node2_addr, &node1_pubkey, &asset_id, 50, 4_000_000).await;
+    let payment_hash = _check_response_is_ok(res)
+        .await
+        .json::<KeysendResponse>()
+        .await
+        .unwrap()
+        .payment_hash;
+    wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    wait_for_ln_balance(node1_addr, &asset_id, 550).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 50).await;
+
+    // invoices of node1 are subject to the same minimum
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(MilliSats(3_999_999)),
+        expiry_sec: 900,
+        asset_id: Some(asset_id.clone()),
+        asset_amount: Some(AssetUnits(10)),
+        label: None,
+        allow_insufficient_inbound: false,
+        require_payer_pubkey: None,
+        require_payer_note: false,
+        min_final_cltv_expiry_delta: None,
+        unified: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_below_dust_limit(res, 4_000_000).await;
+}
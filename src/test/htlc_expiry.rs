@@ -0,0 +1,136 @@
+use crate::ldk::{PaymentFaults, HTLC_EXPIRY_WARNING_BLOCKS};
+use crate::routes::HtlcExpiryAction;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/htlc_expiry/";
+const NODE1_PEER_PORT: u16 = 10358;
+const NODE2_PEER_PORT: u16 = 10359;
+
+async fn wait_for_htlc(node_address: SocketAddr, payment_hash: &str) -> Htlc {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if let Some(htlc) = list_htlcs(node_address)
+            .await
+            .into_iter()
+            .find(|h| h.payment_hash == payment_hash)
+        {
+            return htlc;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("HTLC not pending")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn htlc_expiry() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let payment_faults = Arc::new(PaymentFaults::default());
+    let (node2_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node2"),
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            payment_faults: Arc::clone(&payment_faults),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // node2 doesn't settle the payment, leaving the HTLC pending
+    payment_faults
+        .hold_claimable_payments
+        .store(true, Ordering::Release);
+    let invoice = ln_invoice(node2_addr, &asset_id, 100, 900).await.invoice;
+    let payment_hash = send_payment_raw(node1_addr, invoice).await.payment_hash;
+    let htlc = wait_for_htlc(node2_addr, &payment_hash).await;
+    assert!(htlc.inbound);
+    assert!(!htlc.expiry_warning);
+    assert!(htlc.blocks_to_force_close >= HTLC_EXPIRY_WARNING_BLOCKS);
+
+    // nothing is done until the HTLC gets close to the force-close
+    tokio::time::sleep(std::time::Duration::from_secs(6)).await;
+    assert!(counters(node2_addr)
+        .await
+        .keys()
+        .all(|k| !k.starts_with("htlc_expiry_")));
+
+    // within the warning window the receiver fails the HTLC back instead of letting the channel
+    // get force-closed
+    mine_n_blocks(
+        false,
+        (htlc.blocks_to_force_close - HTLC_EXPIRY_WARNING_BLOCKS + 1) as u16,
+    );
+    let t_0 = OffsetDateTime::now_utc();
+    let event = loop {
+        let found = events(node2_addr)
+            .await
+            .into_iter()
+            .find_map(|e| match e.event {
+                NodeEvent::HtlcExpiryAction {
+                    action,
+                    channel_id,
+                    peer_pubkey,
+                    payment_hash: hash,
+                    blocks_to_force_close,
+                    success,
+                } if hash == payment_hash => Some((
+                    action,
+                    channel_id,
+                    peer_pubkey,
+                    blocks_to_force_close,
+                    success,
+                )),
+                _ => None,
+            });
+        if let Some(event) = found {
+            break event;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("HTLC expiry action not taken")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    let (action, channel_id, peer_pubkey, blocks_to_force_close, success) = event;
+    assert_eq!(action, HtlcExpiryAction::HtlcFailBack);
+    assert_eq!(channel_id, channel.channel_id);
+    assert_eq!(peer_pubkey, node1_pubkey);
+    assert!(blocks_to_force_close < HTLC_EXPIRY_WARNING_BLOCKS);
+    assert!(success);
+    assert_eq!(
+        counters(node2_addr).await.get("htlc_expiry_htlc_fail_back"),
+        Some(&1)
+    );
+
+    // the payment fails without closing the channel, the action being taken only once
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+    assert!(list_htlcs(node2_addr).await.is_empty());
+    assert!(list_channels(node1_addr)
+        .await
+        .iter()
+        .any(|c| c.channel_id == channel.channel_id));
+    tokio::time::sleep(std::time::Duration::from_secs(6)).await;
+    let counters = counters(node2_addr).await;
+    assert_eq!(counters.get("htlc_expiry_htlc_fail_back"), Some(&1));
+    assert!(counters
+        .keys()
+        .filter(|k| k.starts_with("htlc_expiry_"))
+        .all(|k| k == "htlc_expiry_htlc_fail_back"));
+}
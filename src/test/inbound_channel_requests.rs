@@ -0,0 +1,275 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/inbound_channel_requests/";
+const NODE1_PEER_PORT: u16 = 10340;
+const NODE2_PEER_PORT: u16 = 10341;
+const NODE3_PEER_PORT: u16 = 10342;
+const NODE4_PEER_PORT: u16 = 10343;
+
+const CAPACITY_SAT: u64 = 100_000;
+const PUSH_MSAT: u64 = 3_500_000;
+const ASSET_AMOUNT: u64 = 100;
+
+async fn open_channel_to(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    peer_port: u16,
+    asset_id: &str,
+) -> String {
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", peer_pubkey, peer_port),
+        capacity_sat: Sats(CAPACITY_SAT),
+        push_msat: MilliSats(PUSH_MSAT),
+        asset_amount: AssetUnits(ASSET_AMOUNT),
+        asset_id: asset_id.to_string(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    };
+    let res = open_channel_raw(node_address, &payload).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap()
+        .temporary_channel_id
+        .unwrap()
+}
+
+async fn decide_channel(
+    node_address: SocketAddr,
+    endpoint: &str,
+    temporary_channel_id: &str,
+) -> reqwest::Response {
+    let temporary_channel_id = temporary_channel_id.to_string();
+    let client = reqwest::Client::new().post(format!("http://{}/{}", node_address, endpoint));
+    match endpoint {
+        "acceptchannel" => client.json(&AcceptChannelRequest {
+            temporary_channel_id,
+        }),
+        _ => client.json(&RejectChannelRequest {
+            temporary_channel_id,
+        }),
+    }
+    .send()
+    .await
+    .unwrap()
+}
+
+/// Wait for the event of the inbound channel with the given decision, returning its asset
+/// details and the reason of the decision
+async fn wait_for_channel_request(
+    node_address: SocketAddr,
+    temporary_channel_id: &str,
+    peer_pubkey: &str,
+    decision: ChannelRequestDecision,
+) -> (Option<String>, Option<u64>, Option<String>) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let request = events(node_address)
+            .await
+            .into_iter()
+            .find_map(|e| match e.event {
+                NodeEvent::InboundChannelRequest {
+                    temporary_channel_id: id,
+                    peer_pubkey: request_peer_pubkey,
+                    capacity_sat,
+                    push_msat,
+                    asset_id,
+                    asset_amount,
+                    decision: request_decision,
+                    reason,
+                } if id == temporary_channel_id && request_decision == decision => {
+                    assert_eq!(request_peer_pubkey, peer_pubkey);
+                    assert_eq!((capacity_sat, push_msat), (CAPACITY_SAT, PUSH_MSAT));
+                    Some((asset_id, asset_amount, reason))
+                }
+                _ => None,
+            });
+        if let Some(request) = request {
+            return request;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("inbound channel {temporary_channel_id} not notified as {decision:?}")
+        }
+    }
+}
+
+/// Wait for the opener to roll back the channel, returning the failure reason
+async fn wait_for_open_failure(node_address: SocketAddr, temporary_channel_id: &str) -> String {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let reason = events(node_address)
+            .await
+            .into_iter()
+            .find_map(|e| match e.event {
+                NodeEvent::ChannelOpenFailed {
+                    temporary_channel_id: id,
+                    reason,
+                    ..
+                } if id == temporary_channel_id => Some(reason),
+                _ => None,
+            });
+        if let Some(reason) = reason {
+            return reason;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("channel open failure not notified")
+        }
+    }
+}
+
+fn awaiting_decision(channels: &[PendingChannel]) -> Vec<&PendingChannel> {
+    channels
+        .iter()
+        .filter(|c| c.stage == PendingChannelStage::AwaitingDecision)
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn inbound_channel_requests() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let test_dir_node4 = format!("{TEST_DIR_BASE}node4");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node3,
+            ldk_peer_listening_port: NODE3_PEER_PORT,
+            channel_acceptance_policy: ChannelAcceptancePolicy::Reject,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node4_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node4,
+            ldk_peer_listening_port: NODE4_PEER_PORT,
+            channel_acceptance_policy: ChannelAcceptancePolicy::Ask,
+            channel_request_timeout_secs: 5,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node1_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+    let node4_pubkey = node_info(node4_addr).await.pubkey;
+    let asset_details = (Some(asset_id.clone()), Some(ASSET_AMOUNT));
+
+    // accepted channels are notified along with the asset they carry
+    stop_mining();
+    let temp_id = open_channel_to(node1_addr, &node2_pubkey, NODE2_PEER_PORT, &asset_id).await;
+    let (asset, amount, reason) = wait_for_channel_request(
+        node2_addr,
+        &temp_id,
+        &node1_pubkey,
+        ChannelRequestDecision::Accepted,
+    )
+    .await;
+    assert_eq!((asset, amount), asset_details);
+    assert_eq!(reason, None);
+    wait_for_channel_ready(node1_addr, &node2_pubkey).await;
+
+    // rejected channels are notified with the reason
+    let temp_id = open_channel_to(node1_addr, &node3_pubkey, NODE3_PEER_PORT, &asset_id).await;
+    let (asset, amount, reason) = wait_for_channel_request(
+        node3_addr,
+        &temp_id,
+        &node1_pubkey,
+        ChannelRequestDecision::Rejected,
+    )
+    .await;
+    assert_eq!((asset, amount), asset_details);
+    assert_eq!(reason.unwrap(), "refused by the acceptance policy");
+    wait_for_open_failure(node1_addr, &temp_id).await;
+
+    // held channels wait for the user to accept them
+    stop_mining();
+    let temp_id = open_channel_to(node1_addr, &node4_pubkey, NODE4_PEER_PORT, &asset_id).await;
+    let (asset, amount, reason) = wait_for_channel_request(
+        node4_addr,
+        &temp_id,
+        &node1_pubkey,
+        ChannelRequestDecision::AwaitingDecision,
+    )
+    .await;
+    assert_eq!((asset, amount), asset_details);
+    assert_eq!(reason, None);
+    let pending = pending_channels(node4_addr).await;
+    let held = awaiting_decision(&pending);
+    assert_eq!(held.len(), 1);
+    assert_eq!(held[0].channel_id, temp_id);
+    assert_eq!(held[0].peer_pubkey, node1_pubkey);
+    assert!(!held[0].outbound);
+    assert_eq!(held[0].capacity_sat, CAPACITY_SAT);
+    assert_eq!(held[0].asset_id.as_ref(), Some(&asset_id));
+    assert_eq!(held[0].asset_remote_amount, Some(ASSET_AMOUNT));
+    let res = decide_channel(node4_addr, "acceptchannel", &temp_id).await;
+    _check_response_is_ok(res).await;
+    wait_for_channel_ready(node1_addr, &node4_pubkey).await;
+    assert!(awaiting_decision(&pending_channels(node4_addr).await).is_empty());
+
+    // a channel can only be decided on once, while it's held
+    let res = decide_channel(node4_addr, "rejectchannel", &temp_id).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Unknown channel request");
+    let res = decide_channel(node4_addr, "acceptchannel", "invalid").await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    // or to reject them
+    let temp_id = open_channel_to(node1_addr, &node4_pubkey, NODE4_PEER_PORT, &asset_id).await;
+    wait_for_channel_request(
+        node4_addr,
+        &temp_id,
+        &node1_pubkey,
+        ChannelRequestDecision::AwaitingDecision,
+    )
+    .await;
+    let res = decide_channel(node4_addr, "rejectchannel", &temp_id).await;
+    _check_response_is_ok(res).await;
+    let reason = wait_for_open_failure(node1_addr, &temp_id).await;
+    assert!(reason.contains("counterparty force-closed"));
+    assert!(awaiting_decision(&pending_channels(node4_addr).await).is_empty());
+
+    // channels left undecided get rejected once the timeout expires
+    let temp_id = open_channel_to(node1_addr, &node4_pubkey, NODE4_PEER_PORT, &asset_id).await;
+    let (_, _, reason) = wait_for_channel_request(
+        node4_addr,
+        &temp_id,
+        &node1_pubkey,
+        ChannelRequestDecision::Rejected,
+    )
+    .await;
+    assert_eq!(reason.unwrap(), "no decision within 5 seconds");
+    wait_for_open_failure(node1_addr, &temp_id).await;
+    assert!(awaiting_decision(&pending_channels(node4_addr).await).is_empty());
+    let res = decide_channel(node4_addr, "acceptchannel", &temp_id).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+
+    // the assets of the channels that didn't open are spendable again
+    assert_eq!(
+        asset_balance_spendable(node1_addr, &asset_id).await,
+        1000 - 2 * ASSET_AMOUNT
+    );
+}
@@ -0,0 +1,122 @@
+use crate::wallet_intents::WalletFaults;
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/interrupted_wallet_ops/";
+const NODE1_PEER_PORT: u16 = 10291;
+const NODE2_PEER_PORT: u16 = 10292;
+
+async fn create_utxos_raw(node_address: SocketAddr, num: u8) -> reqwest::Response {
+    let payload = CreateUtxosRequest {
+        up_to: false,
+        num: Some(num),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/createutxos", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn restart_wallet(node_address: SocketAddr, password: &str) {
+    lock(node_address).await;
+    unlock(node_address, password.to_string()).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn interrupted_wallet_ops() {
+    initialize();
+
+    let wallet_faults = Arc::new(WalletFaults::default());
+    let (node1_addr, password) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node1"),
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            wallet_faults: Arc::clone(&wallet_faults),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    // the node dies after broadcasting a UTXO creation, before the wallet records it
+    let outpoints_before: HashSet<String> = list_unspents(node1_addr)
+        .await
+        .into_iter()
+        .map(|u| u.utxo.outpoint)
+        .collect();
+    wallet_faults
+        .crash_after_broadcast
+        .store(true, Ordering::Release);
+    let res = create_utxos_raw(node1_addr, 5).await;
+    assert_eq!(res.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+    wallet_faults
+        .crash_after_broadcast
+        .store(false, Ordering::Release);
+
+    // at unlock the transaction is found in the mempool and the wallet picks up its outputs
+    restart_wallet(node1_addr, &password).await;
+    mine(false);
+    let unspents = list_unspents(node1_addr).await;
+    let created = unspents
+        .iter()
+        .filter(|u| u.utxo.colorable && !outpoints_before.contains(&u.utxo.outpoint))
+        .count();
+    assert_eq!(created, 5);
+    let outpoints: HashSet<String> = unspents.into_iter().map(|u| u.utxo.outpoint).collect();
+    assert!(outpoints_before.iter().any(|o| !outpoints.contains(o)));
+
+    // the node dies after broadcasting an asset send, before the wallet records it
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    wallet_faults
+        .crash_after_broadcast
+        .store(true, Ordering::Release);
+    let payload = SendAssetRequest {
+        asset_id: asset_id.clone(),
+        amount: AssetUnits(100),
+        blinded_utxo: recipient_id,
+        donation: true,
+        min_confirmations: 1,
+        transport_endpoints: vec![test_env().proxy_endpoint.clone()],
+        dry_run: false,
+        force: false,
+    };
+    let res = send_asset_raw(node1_addr, &payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+    wallet_faults
+        .crash_after_broadcast
+        .store(false, Ordering::Release);
+    assert!(!list_transfers(node1_addr, &asset_id)
+        .await
+        .iter()
+        .any(|t| matches!(t.kind, TransferKind::Send)));
+
+    // at unlock the send gets recorded and completed instead of its allocation being spent again
+    restart_wallet(node1_addr, &password).await;
+    let sends: Vec<Transfer> = list_transfers(node1_addr, &asset_id)
+        .await
+        .into_iter()
+        .filter(|t| matches!(t.kind, TransferKind::Send))
+        .collect();
+    assert_eq!(sends.len(), 1);
+    assert_eq!(sends[0].amount, 100);
+
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 900);
+    assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 100);
+}
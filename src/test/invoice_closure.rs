@@ -0,0 +1,120 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/invoice_closure/";
+const NODE1_PEER_PORT: u16 = 10296;
+const NODE2_PEER_PORT: u16 = 10297;
+
+async fn cancel_invoice_raw(node_address: SocketAddr, payment_hash: &str) -> reqwest::Response {
+    let payload = CancelInvoiceRequest {
+        payment_hash: payment_hash.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/cancelinvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_payments_with_status(node_address: SocketAddr, status: &str) -> Vec<Payment> {
+    let res = reqwest::Client::new()
+        .get(format!(
+            "http://{}/listpayments?status={status}",
+            node_address
+        ))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListPaymentsResponse>()
+        .await
+        .unwrap()
+        .payments
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn invoice_closure() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // a cancelled invoice refuses payments
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let payment_hash = decode_ln_invoice(node1_addr, &invoice).await.payment_hash;
+    _check_response_is_ok(cancel_invoice_raw(node2_addr, &payment_hash).await).await;
+    assert!(matches!(
+        invoice_status(node2_addr, &invoice).await,
+        InvoiceStatus::Cancelled
+    ));
+    send_payment_with_status(node1_addr, invoice.clone(), HTLCStatus::Failed).await;
+    assert!(matches!(
+        invoice_status(node2_addr, &invoice).await,
+        InvoiceStatus::Cancelled
+    ));
+    assert_eq!(
+        asset_balance_offchain_outbound(node1_addr, &asset_id).await,
+        600
+    );
+
+    let res = cancel_invoice_raw(node2_addr, &payment_hash).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Invoice cannot be cancelled: invoice has already been cancelled"
+    );
+
+    // a paid invoice can't be cancelled
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let payment_hash = decode_ln_invoice(node1_addr, &invoice).await.payment_hash;
+    send_payment(node1_addr, invoice).await;
+    let res = cancel_invoice_raw(node2_addr, &payment_hash).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Invoice cannot be cancelled: invoice has already been paid"
+    );
+
+    // an invoice past its expiry gets marked as expired, and the filters tell the states apart
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 1).await;
+    let expired_hash = decode_ln_invoice(node1_addr, &invoice).await.payment_hash;
+    tokio::time::sleep(std::time::Duration::from_secs(7)).await;
+    assert!(matches!(
+        invoice_status(node2_addr, &invoice).await,
+        InvoiceStatus::Expired
+    ));
+    let expired = list_payments_with_status(node2_addr, "expired").await;
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].payment_hash, expired_hash);
+    assert!(matches!(expired[0].invoice_status, InvoiceStatus::Expired));
+    assert!(matches!(expired[0].status, HTLCStatus::Pending));
+    let cancelled = list_payments_with_status(node2_addr, "cancelled").await;
+    assert_eq!(cancelled.len(), 1);
+    assert_ne!(cancelled[0].payment_hash, expired_hash);
+    assert_eq!(
+        list_payments_with_status(node2_addr, "succeeded")
+            .await
+            .len(),
+        1
+    );
+
+    // the spelling used up to 0.1.0 is still accepted
+    assert_eq!(
+        list_payments_with_status(node2_addr, "Expired").await.len(),
+        1
+    );
+}
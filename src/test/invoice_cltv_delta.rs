@@ -0,0 +1,78 @@
+use super::*;
+use crate::ldk::MIN_FINAL_CLTV_EXPIRY_DELTA;
+
+const TEST_DIR_BASE: &str = "tmp/invoice_cltv_delta/";
+const NODE1_PEER_PORT: u16 = 10271;
+const NODE2_PEER_PORT: u16 = 10272;
+
+async fn ln_invoice_with_delta(
+    node_address: SocketAddr,
+    asset_id: &str,
+    min_final_cltv_expiry_delta: Option<u16>,
+) -> reqwest::Response {
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(MilliSats(3000000)),
+        expiry_sec: 900,
+        asset_id: Some(asset_id.to_string()),
+        asset_amount: Some(AssetUnits(10)),
+        label: None,
+        allow_insufficient_inbound: false,
+        require_payer_pubkey: None,
+        require_payer_note: false,
+        min_final_cltv_expiry_delta,
+        unified: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn invoice_cltv_delta() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // a delta lower than the minimum gets rejected
+    let res =
+        ln_invoice_with_delta(node2_addr, &asset_id, Some(MIN_FINAL_CLTV_EXPIRY_DELTA - 1)).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        format!("Invalid CLTV expiry delta: cannot be lower than {MIN_FINAL_CLTV_EXPIRY_DELTA}")
+    );
+
+    // invoices advertise the minimum by default
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 10, 900).await;
+    let decoded = decode_ln_invoice(node1_addr, &invoice).await;
+    assert_eq!(
+        decoded.min_final_cltv_expiry_delta,
+        MIN_FINAL_CLTV_EXPIRY_DELTA as u64
+    );
+
+    // a custom delta gets advertised and the payment honoring it gets claimed
+    let res = ln_invoice_with_delta(node2_addr, &asset_id, Some(100)).await;
+    let LNInvoiceResponse { invoice, .. } = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap();
+    let decoded = decode_ln_invoice(node1_addr, &invoice).await;
+    assert_eq!(decoded.min_final_cltv_expiry_delta, 100);
+    send_payment(node1_addr, invoice).await;
+}
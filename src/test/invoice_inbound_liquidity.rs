@@ -0,0 +1,73 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/invoice_inbound_liquidity/";
+const NODE1_PEER_PORT: u16 = 10414;
+const NODE2_PEER_PORT: u16 = 10415;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn invoice_inbound_liquidity() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // check InsufficientInboundLiquidity error
+    for (node_address, amt_msat, asset_amount, max_receivable) in [
+        (
+            node1_addr,
+            3000000,
+            Some(10),
+            format!("0 of asset {asset_id}"),
+        ),
+        (node2_addr, 1_000_000_000, None, s!(" msat")),
+    ] {
+        let mut payload = LNInvoiceRequest {
+            amt_msat: Some(MilliSats(amt_msat)),
+            expiry_sec: 900,
+            asset_id: asset_amount.map(|_| asset_id.clone()),
+            asset_amount: asset_amount.map(AssetUnits),
+            label: None,
+            allow_insufficient_inbound: false,
+            require_payer_pubkey: None,
+            require_payer_note: false,
+            min_final_cltv_expiry_delta: None,
+            unified: false,
+        };
+        let res = reqwest::Client::new()
+            .post(format!("http://{}/lninvoice", node_address))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+        let text = res.text().await.unwrap();
+        let response: ErrorResponse = serde_json::from_str(&text).unwrap();
+        assert!(response
+            .error
+            .starts_with("Not enough inbound liquidity, can receive at most "));
+        assert!(response.error.ends_with(&max_receivable));
+        assert_eq!(response.code, 403);
+        payload.allow_insufficient_inbound = true;
+        let res = reqwest::Client::new()
+            .post(format!("http://{}/lninvoice", node_address))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap();
+        _check_response_is_ok(res).await;
+    }
+
+    // within the inbound liquidity no opt-in is needed
+    ln_invoice(node2_addr, &asset_id, 100, 900).await;
+}
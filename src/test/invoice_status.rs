@@ -0,0 +1,81 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/invoice_status/";
+const NODE1_PEER_PORT: u16 = 10416;
+const NODE2_PEER_PORT: u16 = 10417;
+
+async fn invoice_status_by_hash(node_address: SocketAddr, payment_hash: &str) -> InvoiceStatus {
+    let payload = InvoiceStatusRequest {
+        invoice: None,
+        payment_hash: Some(payment_hash.to_string()),
+    };
+    let res = invoice_status_raw(node_address, &payload).await;
+    let response = _check_response_is_ok(res)
+        .await
+        .json::<InvoiceStatusResponse>()
+        .await
+        .unwrap();
+    assert_eq!(response.payment_hash, payment_hash);
+    response.status
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn invoice_status() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // the status can also be asked by payment hash
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let payment_hash = decode_ln_invoice(node1_addr, &invoice).await.payment_hash;
+    assert!(matches!(
+        invoice_status_by_hash(node2_addr, &payment_hash).await,
+        InvoiceStatus::Pending
+    ));
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice.clone(), 600, 0).await;
+    assert!(matches!(
+        super::invoice_status(node2_addr, &invoice).await,
+        InvoiceStatus::Succeeded
+    ));
+    assert!(matches!(
+        invoice_status_by_hash(node2_addr, &payment_hash).await,
+        InvoiceStatus::Succeeded
+    ));
+
+    // check UnknownLNInvoice error
+    let payload = InvoiceStatusRequest {
+        invoice: None,
+        payment_hash: Some("00".repeat(32)),
+    };
+    let res = invoice_status_raw(node2_addr, &payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Unknown LN invoice");
+
+    // exactly one of the invoice and its payment hash is accepted
+    for (invoice, payment_hash) in [(Some(invoice), Some(payment_hash)), (None, None)] {
+        let payload = InvoiceStatusRequest {
+            invoice,
+            payment_hash,
+        };
+        let res = invoice_status_raw(node2_addr, &payload).await;
+        assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+        let response: ErrorResponse = res.json().await.unwrap();
+        assert_eq!(
+            response.error,
+            "Invalid invoice: exactly one of invoice and payment_hash must be given"
+        );
+    }
+}
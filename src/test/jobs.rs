@@ -0,0 +1,129 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/jobs/";
+const NODE1_PEER_PORT: u16 = 10051;
+const SILENT_PEER_PORT: u16 = 10052;
+
+// a peer that accepts connections but never completes the handshake
+const SILENT_PEER_PUBKEY: &str =
+    "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn jobs() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1,
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            async_jobs: true,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node1_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    // a channel open stuck connecting to the peer can be cancelled
+    let _silent_peer = tokio::net::TcpListener::bind(("127.0.0.1", SILENT_PEER_PORT))
+        .await
+        .unwrap();
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{SILENT_PEER_PUBKEY}@127.0.0.1:{SILENT_PEER_PORT}"),
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(600),
+        asset_id: asset_id.clone(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    };
+    let res = open_channel_raw(node1_addr, &payload).await;
+    let job_id = _check_response_is_ok(res)
+        .await
+        .json::<JobResponse>()
+        .await
+        .unwrap()
+        .job_id;
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    let job = job_status(node1_addr, &job_id).await;
+    assert_eq!(job.kind, JobKind::OpenChannel);
+    assert_eq!(job.status, JobStatus::Running);
+    assert!(job.cancellable);
+    cancel_job(node1_addr, &job_id).await;
+    let job = job_status(node1_addr, &job_id).await;
+    assert_eq!(job.status, JobStatus::Cancelled);
+    assert!(job.result.is_none());
+    assert!(list_channels(node1_addr).await.is_empty());
+
+    let recipient_id = rgb_invoice(node1_addr, None).await.recipient_id;
+
+    // dry runs still answer synchronously
+    send_asset_dry_run(node1_addr, &asset_id, 100, recipient_id.clone()).await;
+
+    // sendasset answers with a job ID and the result is available from jobstatus
+    let payload = SendAssetRequest {
+        asset_id: asset_id.clone(),
+        amount: AssetUnits(100),
+        blinded_utxo: recipient_id,
+        donation: true,
+        min_confirmations: 1,
+        transport_endpoints: vec![test_env().proxy_endpoint.clone()],
+        dry_run: false,
+        force: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendasset", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let job_id = _check_response_is_ok(res)
+        .await
+        .json::<JobResponse>()
+        .await
+        .unwrap()
+        .job_id;
+    let job = wait_for_job(node1_addr, &job_id).await;
+    assert_eq!(job.kind, JobKind::SendAsset);
+    assert_eq!(job.status, JobStatus::Succeeded);
+    assert!(!job.cancellable);
+    assert!(job.finished_at.is_some());
+    let result: SendAssetResponse = serde_json::from_value(job.result.unwrap()).unwrap();
+    assert!(result.txid.is_some());
+
+    // finished and unknown jobs can't be cancelled
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/canceljob", node1_addr))
+        .json(&CancelJobRequest {
+            job_id: job_id.clone(),
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Job cannot be cancelled: the job has already finished"
+    );
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/jobstatus?job_id=unknown", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Unknown job");
+}
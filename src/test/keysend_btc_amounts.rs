@@ -0,0 +1,150 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/keysend_btc_amounts/";
+const NODE1_PEER_PORT: u16 = 10221;
+const NODE2_PEER_PORT: u16 = 10222;
+
+async fn keysend_with_payload(
+    node_address: SocketAddr,
+    payload: &KeysendRequest,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/keysend", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn check_bad_request(res: reqwest::Response, error: &str) {
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, error);
+}
+
+async fn local_balance_msat(node_address: SocketAddr, channel_id: &str) -> u64 {
+    list_channels(node_address)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel_id)
+        .unwrap()
+        .local_balance_msat
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn keysend_btc_amounts() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel_with_custom_config(
+        node1_addr,
+        &node2_pubkey,
+        NODE2_PEER_PORT,
+        600,
+        &asset_id,
+        None,
+        None,
+        Some(4_000_000),
+    )
+    .await;
+    let node2_initial_msat = local_balance_msat(node2_addr, &channel.channel_id).await;
+
+    // the msat amount is free above the colored HTLC minimum and goes to the receiver's balance
+    // along with the assets
+    let payload = KeysendRequest {
+        dest_pubkey: node2_pubkey.clone(),
+        amt_msat: Some(MilliSats(10_000_000)),
+        asset_id: asset_id.clone(),
+        asset_amount: AssetUnits(100),
+        label: None,
+        use_minimum_btc: false,
+    };
+    let keysend = _check_response_is_ok(keysend_with_payload(node1_addr, &payload).await)
+        .await
+        .json::<KeysendResponse>()
+        .await
+        .unwrap();
+    assert_eq!(keysend.amt_msat, 10_000_000);
+    let payee_payment =
+        wait_for_ln_payment(node2_addr, &keysend.payment_hash, HTLCStatus::Succeeded).await;
+    assert_eq!(payee_payment.amt_msat, Some(10_000_000));
+    assert_eq!(payee_payment.asset_amount, Some(100));
+    wait_for_ln_balance(node2_addr, &asset_id, 100).await;
+    assert_eq!(
+        local_balance_msat(node2_addr, &channel.channel_id).await,
+        node2_initial_msat + 10_000_000
+    );
+
+    // use_minimum_btc picks the minimum node1 accepts on the channel
+    let payload = KeysendRequest {
+        dest_pubkey: node1_pubkey.clone(),
+        amt_msat: None,
+        asset_id: asset_id.clone(),
+        asset_amount: AssetUnits(40),
+        label: None,
+        use_minimum_btc: true,
+    };
+    let keysend = _check_response_is_ok(keysend_with_payload(node2_addr, &payload).await)
+        .await
+        .json::<KeysendResponse>()
+        .await
+        .unwrap();
+    assert_eq!(keysend.amt_msat, 4_000_000);
+    let payee_payment =
+        wait_for_ln_payment(node1_addr, &keysend.payment_hash, HTLCStatus::Succeeded).await;
+    assert_eq!(payee_payment.amt_msat, Some(4_000_000));
+    assert_eq!(payee_payment.asset_amount, Some(40));
+    wait_for_ln_balance(node2_addr, &asset_id, 60).await;
+    assert_eq!(
+        local_balance_msat(node2_addr, &channel.channel_id).await,
+        node2_initial_msat + 6_000_000
+    );
+
+    // the msat amount can't exceed what the channel can send
+    let payload = KeysendRequest {
+        dest_pubkey: node1_pubkey.clone(),
+        amt_msat: Some(MilliSats(50_000_000)),
+        asset_id: asset_id.clone(),
+        asset_amount: AssetUnits(10),
+        label: None,
+        use_minimum_btc: false,
+    };
+    let res = keysend_with_payload(node2_addr, &payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert!(response
+        .error
+        .starts_with("Not enough outbound liquidity, can send at most "));
+
+    // exactly one of amt_msat and use_minimum_btc must be given
+    let payload = KeysendRequest {
+        use_minimum_btc: true,
+        ..payload
+    };
+    check_bad_request(
+        keysend_with_payload(node2_addr, &payload).await,
+        "Invalid amount: amt_msat cannot be given with use_minimum_btc",
+    )
+    .await;
+    let payload = KeysendRequest {
+        amt_msat: None,
+        use_minimum_btc: false,
+        ..payload
+    };
+    check_bad_request(
+        keysend_with_payload(node2_addr, &payload).await,
+        "Invalid amount: amt_msat is required unless use_minimum_btc is set",
+    )
+    .await;
+}
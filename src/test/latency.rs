@@ -0,0 +1,90 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/latency/";
+const NODE1_PEER_PORT: u16 = 10349;
+const NODE2_PEER_PORT: u16 = 10350;
+
+async fn latency(node_address: SocketAddr) -> LatencyResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/latency", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LatencyResponse>()
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn latency_tracking() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1,
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            latency_tracking: true,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    // the requests of each route are recorded under its path
+    let node1_latency = latency(node1_addr).await;
+    assert!(node1_latency.enabled);
+    let count_before = |route: &str| {
+        node1_latency
+            .routes
+            .iter()
+            .find(|r| r.route == route)
+            .map(|r| r.count)
+            .unwrap_or_default()
+    };
+    let nodeinfo_before = count_before("/nodeinfo");
+    let listchannels_before = count_before("/listchannels");
+    for _ in 0..5 {
+        node_info(node1_addr).await;
+    }
+    for _ in 0..3 {
+        list_channels(node1_addr).await;
+    }
+    let node1_latency = latency(node1_addr).await;
+    assert_eq!(node1_latency.window, 1024);
+    let route = |route: &str| {
+        node1_latency
+            .routes
+            .iter()
+            .find(|r| r.route == route)
+            .unwrap()
+    };
+    assert_eq!(route("/nodeinfo").count, nodeinfo_before + 5);
+    assert_eq!(route("/listchannels").count, listchannels_before + 3);
+    for latency in &node1_latency.routes {
+        assert_eq!(latency.samples as u64, latency.count);
+        assert!(latency.p50_ms <= latency.p95_ms);
+        assert!(latency.p95_ms <= latency.p99_ms);
+        assert!(latency.p99_ms <= latency.max_ms);
+    }
+    // the routes are listed in order
+    let routes: Vec<&str> = node1_latency
+        .routes
+        .iter()
+        .map(|r| r.route.as_str())
+        .collect();
+    let mut sorted_routes = routes.clone();
+    sorted_routes.sort();
+    assert_eq!(routes, sorted_routes);
+
+    // nothing is recorded when latency tracking is disabled
+    node_info(node2_addr).await;
+    let node2_latency = latency(node2_addr).await;
+    assert!(!node2_latency.enabled);
+    assert!(node2_latency.routes.is_empty());
+}
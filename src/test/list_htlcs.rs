@@ -0,0 +1,157 @@
+use bitcoin::hashes::hex::ToHex;
+use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+use lightning::ln::{ChannelId, PaymentHash};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::database::ChannelHtlcRecord;
+use crate::ldk::{merge_recorded_htlcs, PaymentFaults, PendingHtlc, HTLC_EXPIRY_WARNING_BLOCKS};
+use crate::utils::hex_str;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/list_htlcs/";
+const NODE1_PEER_PORT: u16 = 10403;
+const NODE2_PEER_PORT: u16 = 10404;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn list_htlcs() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let payment_faults = Arc::new(PaymentFaults::default());
+    let (node2_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node2"),
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            payment_faults: Arc::clone(&payment_faults),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // settled payments leave no HTLCs behind
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 600, 0).await;
+    assert!(super::list_htlcs(node1_addr).await.is_empty());
+    assert!(super::list_htlcs(node2_addr).await.is_empty());
+
+    // node2 doesn't settle the payment, leaving its HTLC pending on both sides
+    payment_faults
+        .hold_claimable_payments
+        .store(true, Ordering::Release);
+    let invoice = ln_invoice(node2_addr, &asset_id, 50, 900).await.invoice;
+    let payment_hash = send_payment_raw(node1_addr, invoice).await.payment_hash;
+    let t_0 = OffsetDateTime::now_utc();
+    let inbound_htlc = loop {
+        if let Some(htlc) = super::list_htlcs(node2_addr)
+            .await
+            .into_iter()
+            .find(|h| h.payment_hash == payment_hash)
+        {
+            break htlc;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("HTLC not pending")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert!(inbound_htlc.inbound);
+    assert_eq!(inbound_htlc.amt_msat, 3000000);
+    assert_eq!(inbound_htlc.channel_id, channel.channel_id);
+    assert_eq!(inbound_htlc.peer_pubkey, node1_pubkey);
+    assert_eq!(inbound_htlc.asset_id, Some(asset_id.clone()));
+    assert_eq!(inbound_htlc.asset_amount, Some(50));
+    let outbound_htlcs = super::list_htlcs(node1_addr).await;
+    assert_eq!(outbound_htlcs.len(), 1);
+    let outbound_htlc = &outbound_htlcs[0];
+    assert!(!outbound_htlc.inbound);
+    assert_eq!(outbound_htlc.channel_id, channel.channel_id);
+    assert_eq!(outbound_htlc.peer_pubkey, node2_pubkey);
+    assert_eq!(outbound_htlc.payment_hash, payment_hash);
+    assert_eq!(outbound_htlc.amt_msat, inbound_htlc.amt_msat);
+    assert_eq!(outbound_htlc.asset_amount, Some(50));
+    assert_eq!(outbound_htlc.expiry_height, inbound_htlc.expiry_height);
+
+    // once node2 fails the HTLC back it's gone from both sides
+    mine_n_blocks(
+        false,
+        (inbound_htlc.blocks_to_force_close - HTLC_EXPIRY_WARNING_BLOCKS + 1) as u16,
+    );
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+    assert!(super::list_htlcs(node1_addr).await.is_empty());
+    assert!(super::list_htlcs(node2_addr).await.is_empty());
+}
+
+#[test]
+fn list_htlcs_dust() {
+    let channel_id = ChannelId([7; 32]);
+    let secret_key = SecretKey::from_slice(&[1; 32]).unwrap();
+    let counterparty_node_id = PublicKey::from_secret_key(&Secp256k1::new(), &secret_key);
+    let record =
+        |inbound, htlc_id, amt_msat, payment_hash: &PaymentHash, cltv_expiry| ChannelHtlcRecord {
+            channel_id: channel_id.to_hex(),
+            inbound,
+            htlc_id,
+            peer_pubkey: counterparty_node_id.to_string(),
+            amt_msat,
+            payment_hash: hex_str(&payment_hash.0),
+            cltv_expiry,
+        };
+
+    // the monitor reports the HTLC with an output in sats, the dust one not at all
+    let payment_hash = PaymentHash([1; 32]);
+    let dust_payment_hash = PaymentHash([2; 32]);
+    let monitor_htlc = PendingHtlc {
+        channel_id,
+        counterparty_node_id,
+        inbound: true,
+        amt_msat: 3000000,
+        payment_hash,
+        expiry_height: 500,
+        force_close_height: 482,
+        dust: false,
+    };
+    let recorded = vec![
+        record(true, 0, 3000500, &payment_hash, 500),
+        record(false, 0, 300250, &dust_payment_hash, 520),
+    ];
+
+    let htlcs = merge_recorded_htlcs(
+        channel_id,
+        counterparty_node_id,
+        vec![monitor_htlc],
+        recorded,
+    );
+    assert_eq!(htlcs.len(), 2);
+    let htlc = htlcs
+        .iter()
+        .find(|h| h.payment_hash == payment_hash)
+        .unwrap();
+    assert!(htlc.inbound);
+    assert_eq!(htlc.amt_msat, 3000500);
+    assert!(!htlc.dust);
+    let dust_htlc = htlcs
+        .iter()
+        .find(|h| h.payment_hash == dust_payment_hash)
+        .unwrap();
+    assert_eq!(dust_htlc.channel_id, channel_id);
+    assert_eq!(dust_htlc.counterparty_node_id, counterparty_node_id);
+    assert!(!dust_htlc.inbound);
+    assert_eq!(dust_htlc.amt_msat, 300250);
+    assert_eq!(dust_htlc.expiry_height, 520);
+    assert_eq!(dust_htlc.force_close_height, 523);
+    assert!(dust_htlc.dust);
+}
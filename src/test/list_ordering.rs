@@ -0,0 +1,162 @@
+use serde::de::DeserializeOwned;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/list_ordering/";
+const NODE1_PEER_PORT: u16 = 10334;
+const NODE2_PEER_PORT: u16 = 10335;
+const NODE3_PEER_PORT: u16 = 10336;
+
+async fn list_page_raw(
+    node_address: SocketAddr,
+    endpoint: &str,
+    after: Option<&str>,
+    limit: &str,
+) -> reqwest::Response {
+    let mut query = vec![("limit", limit.to_string())];
+    if let Some(after) = after {
+        query.push(("after", after.to_string()));
+    }
+    reqwest::Client::new()
+        .get(format!("http://{}/{}", node_address, endpoint))
+        .query(&query)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_page<R: DeserializeOwned>(
+    node_address: SocketAddr,
+    endpoint: &str,
+    after: Option<&str>,
+    limit: usize,
+) -> R {
+    let res = list_page_raw(node_address, endpoint, after, &limit.to_string()).await;
+    _check_response_is_ok(res).await.json::<R>().await.unwrap()
+}
+
+/// Go through all the pages of a list, checking their size, returning the IDs of the items in
+/// the order they have been received
+async fn list_all_pages<R: DeserializeOwned>(
+    node_address: SocketAddr,
+    endpoint: &str,
+    limit: usize,
+    page_ids: impl Fn(R) -> (Vec<String>, Option<String>),
+) -> Vec<String> {
+    let mut ids = vec![];
+    let mut after = None;
+    loop {
+        let page = list_page(node_address, endpoint, after.as_deref(), limit).await;
+        let (page_ids, next_cursor) = page_ids(page);
+        assert!(page_ids.len() <= limit);
+        ids.extend(page_ids);
+        match next_cursor {
+            Some(cursor) => after = Some(cursor),
+            None => return ids,
+        }
+    }
+}
+
+fn channel_page(page: ListChannelsResponse) -> (Vec<String>, Option<String>) {
+    let ids = page.channels.into_iter().map(|c| c.channel_id).collect();
+    (ids, page.next_cursor)
+}
+
+fn payment_page(page: ListPaymentsResponse) -> (Vec<String>, Option<String>) {
+    let ids = page.payments.into_iter().map(|p| p.payment_hash).collect();
+    (ids, page.next_cursor)
+}
+
+fn peer_page(page: ListPeersResponse) -> (Vec<String>, Option<String>) {
+    let ids = page.peers.into_iter().map(|p| p.pubkey).collect();
+    (ids, page.next_cursor)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn list_ordering() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+    fund_and_create_utxos(node3_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 300, &asset_id).await;
+    open_channel(node1_addr, &node3_pubkey, NODE3_PEER_PORT, 300, &asset_id).await;
+    for amount in [10, 20, 30] {
+        keysend(node1_addr, &node2_pubkey, &asset_id, amount).await;
+    }
+
+    // lists are sorted by creation time then ID, the same way on every call
+    let channels = list_channels(node1_addr).await;
+    assert_eq!(channels.len(), 2);
+    assert!(channels
+        .windows(2)
+        .all(|w| (w[0].created_at, &w[0].channel_id) < (w[1].created_at, &w[1].channel_id)));
+    let payments = list_payments(node1_addr).await;
+    assert_eq!(payments.len(), 3);
+    assert!(payments
+        .windows(2)
+        .all(|w| (w[0].created_at, &w[0].payment_hash) < (w[1].created_at, &w[1].payment_hash)));
+    let peers = list_peers(node1_addr).await;
+    assert_eq!(peers.len(), 2);
+    assert!(peers
+        .windows(2)
+        .all(|w| (w[0].connected_since, &w[0].pubkey) < (w[1].connected_since, &w[1].pubkey)));
+    let channel_ids: Vec<String> = channels.into_iter().map(|c| c.channel_id).collect();
+    let payment_hashes: Vec<String> = payments.into_iter().map(|p| p.payment_hash).collect();
+    let peer_pubkeys: Vec<String> = peers.into_iter().map(|p| p.pubkey).collect();
+    let channels = list_channels(node1_addr).await;
+    assert_eq!(
+        channels
+            .into_iter()
+            .map(|c| c.channel_id)
+            .collect::<Vec<_>>(),
+        channel_ids
+    );
+
+    // the pages go through the whole lists in the same order
+    let ids = list_all_pages(node1_addr, "listchannels", 1, channel_page).await;
+    assert_eq!(ids, channel_ids);
+    for limit in [1, 2, 3] {
+        let ids = list_all_pages(node1_addr, "listpayments", limit, payment_page).await;
+        assert_eq!(ids, payment_hashes);
+    }
+    let ids = list_all_pages(node1_addr, "listpeers", 1, peer_page).await;
+    assert_eq!(ids, peer_pubkeys);
+
+    // items added while paging show up once, after the ones already listed
+    let page: ListPaymentsResponse = list_page(node1_addr, "listpayments", None, 1).await;
+    let mut ids: Vec<String> = page.payments.into_iter().map(|p| p.payment_hash).collect();
+    let mut after = page.next_cursor;
+    let new_payment = keysend(node1_addr, &node2_pubkey, &asset_id, 40).await;
+    while let Some(cursor) = after {
+        let page: ListPaymentsResponse =
+            list_page(node1_addr, "listpayments", Some(cursor.as_str()), 1).await;
+        ids.extend(page.payments.into_iter().map(|p| p.payment_hash));
+        after = page.next_cursor;
+    }
+    let mut expected = payment_hashes.clone();
+    expected.push(new_payment.payment_hash);
+    assert_eq!(ids, expected);
+
+    // unknown cursors and empty pages are refused
+    let res = list_page_raw(node1_addr, "listpayments", Some("invalid"), "1").await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Invalid pagination: unknown cursor invalid");
+    let res = list_page_raw(node1_addr, "listchannels", None, "0").await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Invalid pagination: limit must be positive");
+}
@@ -0,0 +1,60 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/ln_invoice_route_hints/";
+const NODE1_PEER_PORT: u16 = 10376;
+const NODE2_PEER_PORT: u16 = 10377;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn ln_invoice_route_hints() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", node2_pubkey, NODE2_PEER_PORT),
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(600),
+        asset_id: asset_id.clone(),
+        public: false,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    };
+    let res = open_channel_raw(node1_addr, &payload).await;
+    _check_response_is_ok(res).await;
+    let channel = wait_for_channel_ready(node1_addr, &node2_pubkey).await;
+    assert!(!channel.public);
+
+    // BOLT11 invoices can't carry blinded paths, a private channel is disclosed in a route hint
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let bolt11_invoice = Bolt11Invoice::from_str(&invoice).unwrap();
+    let route_hints = bolt11_invoice.route_hints();
+    assert_eq!(route_hints.len(), 1);
+    assert_eq!(route_hints[0].0.len(), 1);
+    assert_eq!(route_hints[0].0[0].src_node_id.to_string(), node1_pubkey);
+    assert_eq!(
+        bolt11_invoice.rgb_contract_id().unwrap().to_string(),
+        asset_id
+    );
+    assert_eq!(bolt11_invoice.rgb_amount(), Some(100));
+
+    // the payer follows the route hint
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 600, 0).await;
+}
@@ -0,0 +1,137 @@
+use crate::lsp::LspPolicy;
+use crate::routes::OPENCHANNEL_MAX_SAT;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/lsp_channel_fee/";
+const NODE1_PEER_PORT: u16 = 10362;
+const NODE2_PEER_PORT: u16 = 10363;
+
+const LSP_FEE_SAT: u64 = 5000;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn lsp_channel_fee() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1,
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            lsp_policy: Some(LspPolicy {
+                fee_sat: LSP_FEE_SAT,
+                max_capacity_sat: OPENCHANNEL_MAX_SAT,
+                max_asset_amount: None,
+            }),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // a first channel gives node2 the liquidity to pay the fee
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", node2_pubkey, NODE2_PEER_PORT),
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(20_000_000),
+        asset_amount: AssetUnits(100),
+        asset_id: asset_id.clone(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    };
+    let paying_channel = open_channel_with_payload(node1_addr, &node2_pubkey, &payload).await;
+
+    // the LSP asks for the fee before opening the channel
+    let request_id =
+        request_inbound_channel(node2_addr, &node1_pubkey, NODE1_PEER_PORT, &asset_id, 400).await;
+    let request = wait_for_request_status(
+        node2_addr,
+        &request_id,
+        InboundChannelRequestStatus::AwaitingFee,
+    )
+    .await;
+    assert_eq!(request.fee_sat, Some(LSP_FEE_SAT));
+    assert!(request.temporary_channel_id.is_none());
+    let fee_invoice = request.fee_invoice.unwrap();
+    let decoded = decode_ln_invoice(node2_addr, &fee_invoice).await;
+    assert_eq!(decoded.amt_msat, Some(LSP_FEE_SAT * 1000));
+    assert!(decoded.asset_id.is_none());
+    assert_eq!(list_channels(node1_addr).await.len(), 1);
+
+    // paying the fee gets the channel opened
+    stop_mining();
+    let fee_payment = send_payment(node2_addr, fee_invoice).await;
+    let request = wait_for_request_status(
+        node2_addr,
+        &request_id,
+        InboundChannelRequestStatus::Opening,
+    )
+    .await;
+    assert_eq!(request.fee_sat, Some(LSP_FEE_SAT));
+    assert!(request.temporary_channel_id.is_some());
+    let lsp_payment =
+        wait_for_ln_payment(node1_addr, &fee_payment.payment_hash, HTLCStatus::Succeeded).await;
+    assert!(lsp_payment.inbound);
+    assert_eq!(lsp_payment.amt_msat, Some(LSP_FEE_SAT * 1000));
+    assert_eq!(
+        lsp_payment.label,
+        Some(format!("LSP channel request {request_id}"))
+    );
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if let Some(channel) = channels
+            .iter()
+            .find(|c| c.channel_id != paying_channel.channel_id)
+        {
+            if let Some(funding_txid) = &channel.funding_txid {
+                if txout_exists(funding_txid) {
+                    mine_n_blocks(true, channel.confirmations_required.unwrap() as u16);
+                    break;
+                }
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+    }
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node2_addr).await;
+        if let Some(channel) = channels
+            .iter()
+            .find(|c| c.ready && c.channel_id != paying_channel.channel_id)
+        {
+            assert_eq!(channel.peer_pubkey, node1_pubkey);
+            assert_eq!(channel.asset_id, Some(asset_id.clone()));
+            assert_eq!(channel.asset_local_amount, Some(0));
+            assert_eq!(channel.asset_remote_amount, Some(400));
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("channel is taking too long to be ready")
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::lsp::LspPolicy;
+use crate::routes::OPENCHANNEL_MAX_SAT;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/lsp_inbound_channel/";
+const NODE1_PEER_PORT: u16 = 9981;
+const NODE2_PEER_PORT: u16 = 9982;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn lsp_inbound_channel() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1,
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            lsp_policy: Some(LspPolicy {
+                fee_sat: 0,
+                max_capacity_sat: OPENCHANNEL_MAX_SAT,
+                max_asset_amount: Some(500),
+            }),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // requests outside the LSP policy get rejected
+    let request_id =
+        request_inbound_channel(node2_addr, &node1_pubkey, NODE1_PEER_PORT, &asset_id, 600).await;
+    let request = wait_for_request_status(
+        node2_addr,
+        &request_id,
+        InboundChannelRequestStatus::Rejected,
+    )
+    .await;
+    assert_eq!(request.reason, Some(s!("asset amount must be at most 500")));
+    assert!(list_channels(node1_addr).await.is_empty());
+
+    // the LSP opens the channel right away when it charges no fee
+    stop_mining();
+    let request_id =
+        request_inbound_channel(node2_addr, &node1_pubkey, NODE1_PEER_PORT, &asset_id, 400).await;
+    let request = wait_for_request_status(
+        node2_addr,
+        &request_id,
+        InboundChannelRequestStatus::Opening,
+    )
+    .await;
+    assert_eq!(request.lsp_pubkey, node1_pubkey);
+    assert_eq!(request.fee_sat, Some(0));
+    assert!(request.fee_invoice.is_none());
+    assert!(request.temporary_channel_id.is_some());
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if let Some(channel) = channels.iter().find(|c| c.peer_pubkey == node2_pubkey) {
+            if let Some(funding_txid) = &channel.funding_txid {
+                if txout_exists(funding_txid) {
+                    mine_n_blocks(true, channel.confirmations_required.unwrap() as u16);
+                    break;
+                }
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+    }
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node2_addr).await;
+        if let Some(channel) = channels.iter().find(|c| c.ready) {
+            assert_eq!(channel.peer_pubkey, node1_pubkey);
+            assert_eq!(channel.asset_id, Some(asset_id.clone()));
+            assert_eq!(channel.asset_local_amount, Some(0));
+            assert_eq!(channel.asset_remote_amount, Some(400));
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("channel is taking too long to be ready")
+        }
+    }
+
+    // the new channel gives inbound liquidity for the asset
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 400, 0).await;
+}
@@ -4,50 +4,226 @@ use electrum_client::ElectrumApi;
 use lightning_invoice::Bolt11Invoice;
 use once_cell::sync::Lazy;
 use serde::Deserialize;
-use std::net::{SocketAddr, TcpListener};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, TcpListener};
 use std::path::Path;
 use std::process::{Command, Stdio};
 use std::str::FromStr;
-use std::sync::{Once, RwLock};
+use std::sync::{Condvar, Mutex, Once, RwLock};
 use time::OffsetDateTime;
 use tracing_test::traced_test;
 
+use crate::amounts::{AssetUnits, MilliSats, Sats};
+use crate::channel_handshake::ChannelHandshakeDefaults;
+use crate::channel_opens::ChannelAcceptancePolicy;
+use crate::database::DATABASE_FNAME;
+use crate::ldk::UTXO_SIZE_SAT;
+use crate::mirror::MirrorTarget;
+use crate::peer_limits::PeerRateLimits;
+use crate::regtest::RegtestBackend;
 use crate::routes::{
-    AddressResponse, Asset, AssetBalanceRequest, AssetBalanceResponse, BackupRequest, Channel,
-    CloseChannelRequest, ConnectPeerRequest, CreateUtxosRequest, DecodeLNInvoiceRequest,
-    DecodeLNInvoiceResponse, DecodeRGBInvoiceRequest, DecodeRGBInvoiceResponse,
-    DisconnectPeerRequest, EmptyResponse, HTLCStatus, InitRequest, InitResponse, InvoiceStatus,
-    InvoiceStatusRequest, InvoiceStatusResponse, IssueAssetRequest, IssueAssetResponse,
-    KeysendRequest, KeysendResponse, LNInvoiceRequest, LNInvoiceResponse, ListAssetsResponse,
-    ListChannelsResponse, ListPaymentsResponse, ListPeersResponse, ListUnspentsResponse,
-    NodeInfoResponse, OpenChannelRequest, OpenChannelResponse, Payment, Peer, RestoreRequest,
-    RgbInvoiceRequest, RgbInvoiceResponse, SendAssetRequest, SendAssetResponse, SendPaymentRequest,
-    SendPaymentResponse, UnlockRequest, Unspent,
+    AbandonPaymentRequest, AcceptChannelRequest, AddWebhookRequest, AddWebhookResponse,
+    AddressRequest, AddressResponse, Alert, AlertKind, AlertsResponse, Asset, AssetBalanceRequest,
+    AssetBalanceResponse, AssetSpendingLimit, BackupRequest, BalancesResponse, Ban,
+    BtcBalanceResponse, CancelInvoiceRequest, CancelJobRequest, Channel, ChannelDebugResponse,
+    ChannelRequestDecision, CloseChannelRequest, CloseChannelResponse, ClosedChannel,
+    CommitmentType, ConnectPeerRequest, ConsolidateAssetsRequest, ConsolidateAssetsResponse,
+    CountersResponse, CreateUtxosRequest, CreateUtxosResponse, DecodeLNInvoiceRequest,
+    DecodeLNInvoiceResponse, DecodePaymentRequestRequest, DecodePaymentRequestResponse,
+    DecodeRGBInvoiceRequest, DecodeRGBInvoiceResponse, DeletePaymentRequest, DeletePaymentResponse,
+    DeleteWebhookRequest, DisconnectPeerRequest, DryRunResult, EmptyResponse, Event,
+    EventsResponse, ExportAssetResponse, FailTransfersRequest, FailTransfersResponse,
+    FailureReason, GetPaymentResponse, HTLCStatus, Htlc, ImportAssetRequest, ImportAssetResponse,
+    InboundChannelRequest, InboundChannelRequestStatus, InitRequest, InitResponse, InvoiceStatus,
+    InvoiceStatusRequest, InvoiceStatusResponse, IssueAssetRequest, IssueAssetResponse, JobKind,
+    JobResponse, JobStatus, JobStatusResponse, KeysendRequest, KeysendResponse, LNInvoiceRequest,
+    LNInvoiceResponse, LatencyResponse, ListAddressesResponse, ListAssetsResponse,
+    ListBansResponse, ListChannelsResponse, ListClosedChannelsResponse, ListHtlcsResponse,
+    ListInboundChannelRequestsResponse, ListPaymentsResponse, ListPeersResponse, ListSwapsResponse,
+    ListTransactionsParams, ListTransactionsResponse, ListTransfersRequest, ListTransfersResponse,
+    ListUnspentsResponse, ListWebhooksResponse, NodeEvent, NodeInfoResponse, OpenChannelRequest,
+    OpenChannelResponse, OutpointAllocation, Payment, PaymentFeasibility, Peer, PendingChannel,
+    PendingChannelStage, PendingChannelsResponse, PruneMonitorsRequest, PruneMonitorsResponse,
+    PrunePaymentsRequest, PrunePaymentsResponse, QueryNodeInfoResponse, QuotePaymentRequest,
+    QuotePaymentResponse, ReadyResponse, ReestablishState, RegistryStatus, RejectChannelRequest,
+    ReorgedTransfer, RequestInboundChannelRequest, RequestInboundChannelResponse, RestoreRequest,
+    RgbAllocation, RgbInvoiceRequest, RgbInvoiceResponse, SendAssetRequest, SendAssetResponse,
+    SendCustomMessageRequest, SendOnionMessageRequest, SendPaymentRequest, SendPaymentResponse,
+    SetAlertsRequest, SetAliasRequest, SetChannelNoteRequest, SetPaymentPolicyRequest,
+    SetPeerExposureLimitsRequest, SettlementMethod, ShutdownMode, ShutdownRequest,
+    SignMessageRequest, SignMessageResponse, SpendingLimitsResponse, StatsParams, StatsPeriod,
+    StatsResponse, Swap, SwapAssetsRequest, SwapAssetsResponse, SwapDirection, SwapStatus,
+    Transaction, TransactionDirection, TransactionPurpose, Transfer, TransferKind, TransferStatus,
+    UnlockRequest, Unspent, UtxoReservation, VerifyMessageRequest, VerifyMessageResponse,
+    WalletAddress, Webhook,
 };
-use crate::utils::PROXY_ENDPOINT_REGTEST;
 
 use super::*;
 
-const ELECTRUM_URL: &str = "127.0.0.1:50001";
+const BITCOIND_RPC_HOST: &str = "localhost";
+const BITCOIND_RPC_USERNAME: &str = "user";
+const BITCOIND_RPC_PASSWORD: &str = "password";
+const EXTERNAL_SERVICES_ENV: &str = "RLN_TEST_EXTERNAL_SERVICES";
+/// Regtest stacks the tests get spread over, as comma-separated
+/// `<bitcoind RPC port>/<electrum port>/<proxy port>` triplets
+const TEST_ENVS_ENV: &str = "RLN_TEST_ENVS";
+/// Stack started by regtest.sh
+const DEFAULT_TEST_ENV: &str = "18443/50001/3000";
+/// Blocks mined on a fresh chain, so that the miner wallet has mature coins to spend
+const INITIAL_BLOCKS: u16 = 103;
 
 static INIT: Once = Once::new();
 
-static MINER: Lazy<RwLock<Miner>> = Lazy::new(|| RwLock::new(Miner { no_mine_count: 0 }));
+static TEST_ENVS: Lazy<Vec<TestEnv>> = Lazy::new(|| {
+    std::env::var(TEST_ENVS_ENV)
+        .unwrap_or_else(|_| DEFAULT_TEST_ENV.to_string())
+        .split(',')
+        .map(TestEnv::parse)
+        .collect()
+});
+
+/// Indexes of the environments no test is running against, with the condition notified when one
+/// gets released
+static FREE_TEST_ENVS: Lazy<(Mutex<Vec<usize>>, Condvar)> = Lazy::new(|| {
+    (
+        Mutex::new((0..TEST_ENVS.len()).rev().collect()),
+        Condvar::new(),
+    )
+});
+
+thread_local! {
+    /// Environment of the test running on this thread
+    static CURRENT_TEST_ENV: RefCell<Option<TestEnvGuard>> = RefCell::new(None);
+}
+
+/// Regtest services (bitcoind, electrs and the RGB proxy) a test runs against
+///
+/// Each environment is used by a single test at a time, so that tests running in parallel don't
+/// mine blocks under each other.
+struct TestEnv {
+    bitcoind_rpc_port: u16,
+    electrum_url: String,
+    proxy_endpoint: String,
+    miner: RwLock<Miner>,
+    prepared: Once,
+}
+
+impl TestEnv {
+    fn parse(descriptor: &str) -> Self {
+        let ports: Vec<u16> = descriptor
+            .split('/')
+            .map(|p| p.trim().parse().expect("valid port"))
+            .collect();
+        let &[bitcoind_rpc_port, electrum_port, proxy_port] = ports.as_slice() else {
+            panic!("invalid test environment {descriptor}");
+        };
+        Self {
+            bitcoind_rpc_port,
+            electrum_url: format!("127.0.0.1:{electrum_port}"),
+            proxy_endpoint: format!("rpc://127.0.0.1:{proxy_port}/json-rpc"),
+            miner: RwLock::new(Miner { no_mine_count: 0 }),
+            prepared: Once::new(),
+        }
+    }
+
+    fn regtest_backend(&self) -> RegtestBackend {
+        RegtestBackend::new(
+            BITCOIND_RPC_HOST,
+            self.bitcoind_rpc_port,
+            BITCOIND_RPC_USERNAME,
+            BITCOIND_RPC_PASSWORD,
+        )
+        .expect("valid bitcoind RPC endpoint")
+    }
+}
+
+/// Exclusive use of an environment, given back when the test thread ends
+struct TestEnvGuard(usize);
+
+impl TestEnvGuard {
+    fn acquire() -> Self {
+        let (free, released) = &*FREE_TEST_ENVS;
+        let mut free = free.lock().unwrap();
+        loop {
+            if let Some(idx) = free.pop() {
+                return Self(idx);
+            }
+            free = released.wait(free).unwrap();
+        }
+    }
+}
+
+impl Drop for TestEnvGuard {
+    fn drop(&mut self) {
+        let (free, released) = &*FREE_TEST_ENVS;
+        free.lock().unwrap().push(self.0);
+        released.notify_one();
+    }
+}
+
+/// Environment of the running test
+fn test_env() -> &'static TestEnv {
+    let idx =
+        CURRENT_TEST_ENV.with(|env| env.borrow().as_ref().expect("initialize has been called").0);
+    &TEST_ENVS[idx]
+}
 
 #[cfg(test)]
 impl Default for LdkUserInfo {
     fn default() -> Self {
+        let env = test_env();
         Self {
-            bitcoind_rpc_username: s!("user"),
-            bitcoind_rpc_password: s!("password"),
-            bitcoind_rpc_host: s!("localhost"),
-            bitcoind_rpc_port: 18443,
+            bitcoind_rpc_username: BITCOIND_RPC_USERNAME.to_string(),
+            bitcoind_rpc_password: BITCOIND_RPC_PASSWORD.to_string(),
+            bitcoind_rpc_host: BITCOIND_RPC_HOST.to_string(),
+            bitcoind_rpc_port: env.bitcoind_rpc_port,
             ldk_announced_listen_addr: vec![],
-            ldk_announced_node_name: [0; 32],
+            ldk_announced_node_alias: s!(""),
+            ldk_announced_node_color: [0; 3],
             network: Network::Regtest,
             storage_dir_path: s!("tmp/test_name/nodeN"),
             daemon_listening_port: 3001,
             ldk_peer_listening_port: 9735,
+            ldk_peer_listening_host: Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+            htlc_force_close_buffer: 6,
+            default_min_confirmations: 1,
+            min_allowed_confirmations: 1,
+            inbound_channel_min_depth: 6,
+            monitor_mirror: None,
+            monitor_mirror_max_lag: 10,
+            monitor_archive_depth: 4032,
+            max_graph_size_mb: 256,
+            peer_offline_threshold_secs: 300,
+            asset_registry_url: None,
+            address_gap_limit: 20,
+            api_token: None,
+            read_only_api_token: None,
+            unlock_password: None,
+            migrate_data_dir_from: None,
+            lsp_policy: None,
+            onion_message_forwarding: false,
+            consolidation_policy: None,
+            consolidation_max_fee_rate: 10.0,
+            channel_acceptance_policy: ChannelAcceptancePolicy::Accept,
+            channel_request_timeout_secs: 45,
+            channel_handshake: ChannelHandshakeDefaults::default(),
+            accept_non_anchor_channels: false,
+            async_jobs: false,
+            recovery_mode: false,
+            proxy_endpoints: vec![env.proxy_endpoint.clone()],
+            electrum_url: Some(env.electrum_url.clone()),
+            peer_rate_limits: PeerRateLimits {
+                max_msgs_per_sec: 100,
+                max_gossip_bytes_per_min: 4_000_000,
+                ban_duration_secs: 3600,
+            },
+            api_limits: None,
+            latency_tracking: false,
+            persistence: Default::default(),
+            wallet_faults: Default::default(),
+            payment_faults: Default::default(),
         }
     }
 }
@@ -58,16 +234,10 @@ struct ErrorResponse {
     code: u16,
 }
 
-fn _bitcoin_cli() -> [String; 7] {
-    [
-        s!("exec"),
-        s!("-T"),
-        s!("-u"),
-        s!("blits"),
-        s!("bitcoind"),
-        s!("bitcoin-cli"),
-        s!("-regtest"),
-    ]
+/// Wait for a regtest bitcoind call from the synchronous test helpers
+fn block_on<T>(call: impl Future<Output = std::io::Result<T>>) -> T {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(call))
+        .expect("regtest bitcoind call failed")
 }
 
 async fn _check_response_is_ok(res: reqwest::Response) -> reqwest::Response {
@@ -78,36 +248,15 @@ async fn _check_response_is_ok(res: reqwest::Response) -> reqwest::Response {
 }
 
 fn fund_wallet(address: String) {
-    let status = Command::new("docker")
-        .stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .arg("compose")
-        .args(_bitcoin_cli())
-        .arg("-rpcwallet=miner")
-        .arg("sendtoaddress")
-        .arg(address)
-        .arg("1")
-        .status()
-        .expect("failed to fund wallet");
-    assert!(status.success());
-}
-
-fn get_txout(txid: &str) -> String {
-    String::from_utf8(
-        Command::new("docker")
-            .stdin(Stdio::null())
-            .arg("compose")
-            .args(_bitcoin_cli())
-            .arg("-rpcwallet=miner")
-            .arg("gettxout")
-            .arg(txid)
-            .arg("0")
-            .output()
-            .expect("failed get txout")
-            .stdout,
-    )
-    .unwrap()
+    block_on(
+        test_env()
+            .regtest_backend()
+            .fund_address(&address, 100_000_000),
+    );
+}
+
+fn txout_exists(txid: &str) -> bool {
+    block_on(test_env().regtest_backend().txout_exists(txid, 0))
 }
 
 fn get_ldk_sockets(peer_ports: &[u16]) -> Vec<SocketAddr> {
@@ -123,19 +272,23 @@ fn get_ldk_sockets(peer_ports: &[u16]) -> Vec<SocketAddr> {
 }
 
 async fn start_daemon(node_test_dir: &str, node_peer_port: u16) -> SocketAddr {
-    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
-    let node_address = listener.local_addr().unwrap();
-    std::fs::create_dir_all(node_test_dir).unwrap();
-    let args = LdkUserInfo {
+    start_daemon_with_args(LdkUserInfo {
         storage_dir_path: node_test_dir.to_string(),
         ldk_peer_listening_port: node_peer_port,
         ..Default::default()
-    };
+    })
+    .await
+}
+
+async fn start_daemon_with_args(args: LdkUserInfo) -> SocketAddr {
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let node_address = listener.local_addr().unwrap();
+    std::fs::create_dir_all(&args.storage_dir_path).unwrap();
     tokio::spawn(async move {
         let (router, app_state) = app(args).await.unwrap();
         axum::Server::from_tcp(listener)
             .unwrap()
-            .serve(router.into_make_service())
+            .serve(router.into_make_service_with_connect_info::<SocketAddr>())
             .with_graceful_shutdown(shutdown_signal(app_state))
             .await
             .unwrap();
@@ -148,12 +301,33 @@ async fn start_node(
     node_peer_port: u16,
     keep_node_dir: bool,
 ) -> (SocketAddr, String) {
-    if !keep_node_dir && Path::new(&node_test_dir).is_dir() {
-        std::fs::remove_dir_all(node_test_dir.clone()).unwrap();
-    }
-    let node_address = start_daemon(&node_test_dir, node_peer_port).await;
+    start_node_with_monitor_mirror(node_test_dir, node_peer_port, keep_node_dir, None).await
+}
+
+async fn start_node_with_monitor_mirror(
+    node_test_dir: String,
+    node_peer_port: u16,
+    keep_node_dir: bool,
+    monitor_mirror: Option<MirrorTarget>,
+) -> (SocketAddr, String) {
+    start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: node_test_dir,
+            ldk_peer_listening_port: node_peer_port,
+            monitor_mirror,
+            ..Default::default()
+        },
+        keep_node_dir,
+    )
+    .await
+}
 
-    let password = format!("{node_test_dir}.{node_peer_port}");
+async fn start_node_with_args(args: LdkUserInfo, keep_node_dir: bool) -> (SocketAddr, String) {
+    let password = format!("{}.{}", args.storage_dir_path, args.ldk_peer_listening_port);
+    if !keep_node_dir && Path::new(&args.storage_dir_path).is_dir() {
+        std::fs::remove_dir_all(&args.storage_dir_path).unwrap();
+    }
+    let node_address = start_daemon_with_args(args).await;
 
     if !keep_node_dir {
         let payload = InitRequest {
@@ -194,10 +368,36 @@ async fn asset_balance(node_address: SocketAddr, asset_id: &str) -> AssetBalance
         .unwrap()
 }
 
+async fn alerts(node_address: SocketAddr) -> AlertsResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/alerts", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<AlertsResponse>()
+        .await
+        .unwrap()
+}
+
 async fn asset_balance_spendable(node_address: SocketAddr, asset_id: &str) -> u64 {
     asset_balance(node_address, asset_id).await.spendable
 }
 
+async fn balances(node_address: SocketAddr) -> BalancesResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/balances", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<BalancesResponse>()
+        .await
+        .unwrap()
+}
+
 async fn backup(node_address: SocketAddr, backup_path: &str, password: &str) {
     let payload = BackupRequest {
         backup_path: backup_path.to_string(),
@@ -247,15 +447,24 @@ async fn connect_peer(node_address: SocketAddr, peer_pubkey: &str, peer_addr: &s
         .unwrap();
 }
 
-async fn close_channel(node_address: SocketAddr, channel_id: &str, peer_pubkey: &str, force: bool) {
-    stop_mining();
-    let payload = CloseChannelRequest {
-        channel_id: channel_id.to_string(),
-        peer_pubkey: peer_pubkey.to_string(),
-        force,
+async fn consolidate_assets(node_address: SocketAddr, asset_id: &str) -> reqwest::Response {
+    let payload = ConsolidateAssetsRequest {
+        asset_id: asset_id.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/consolidateassets", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn cancel_job(node_address: SocketAddr, job_id: &str) {
+    let payload = CancelJobRequest {
+        job_id: job_id.to_string(),
     };
     let res = reqwest::Client::new()
-        .post(format!("http://{}/closechannel", node_address))
+        .post(format!("http://{}/canceljob", node_address))
         .json(&payload)
         .send()
         .await
@@ -265,13 +474,61 @@ async fn close_channel(node_address: SocketAddr, channel_id: &str, peer_pubkey:
         .json::<EmptyResponse>()
         .await
         .unwrap();
+}
+
+async fn close_channel(node_address: SocketAddr, channel_id: &str, peer_pubkey: &str, force: bool) {
+    close_channel_to(node_address, channel_id, peer_pubkey, force, None).await
+}
+
+async fn close_channel_raw(
+    node_address: SocketAddr,
+    payload: &CloseChannelRequest,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/closechannel", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn close_channel_to(
+    node_address: SocketAddr,
+    channel_id: &str,
+    peer_pubkey: &str,
+    force: bool,
+    asset_destination: Option<String>,
+) {
+    let payload = CloseChannelRequest {
+        channel_id: channel_id.to_string(),
+        peer_pubkey: peer_pubkey.to_string(),
+        force,
+        dry_run: false,
+        asset_destination,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        min_fee_rate_sat_vb: None,
+        max_fee_rate_sat_vb: None,
+        force_after_sec: None,
+    };
+    close_channel_with_payload(node_address, &payload).await
+}
+
+async fn close_channel_with_payload(node_address: SocketAddr, payload: &CloseChannelRequest) {
+    stop_mining();
+    let res = close_channel_raw(node_address, payload).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<CloseChannelResponse>()
+        .await
+        .unwrap();
 
     let t_0 = OffsetDateTime::now_utc();
     loop {
         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
         let channels = list_channels(node_address).await;
-        if !channels.iter().any(|c| c.channel_id == channel_id) {
-            let block_num = match force {
+        if !channels.iter().any(|c| c.channel_id == payload.channel_id) {
+            let block_num = match payload.force {
                 true => 144,
                 false => 6,
             };
@@ -284,6 +541,53 @@ async fn close_channel(node_address: SocketAddr, channel_id: &str, peer_pubkey:
     }
 }
 
+async fn close_channel_dry_run(
+    node_address: SocketAddr,
+    channel_id: &str,
+    peer_pubkey: &str,
+    force: bool,
+) -> DryRunResult {
+    let payload = CloseChannelRequest {
+        channel_id: channel_id.to_string(),
+        peer_pubkey: peer_pubkey.to_string(),
+        force,
+        dry_run: true,
+        asset_destination: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        min_fee_rate_sat_vb: None,
+        max_fee_rate_sat_vb: None,
+        force_after_sec: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/closechannel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<CloseChannelResponse>()
+        .await
+        .unwrap()
+        .dry_run
+        .unwrap()
+}
+
+async fn counters(node_address: SocketAddr) -> BTreeMap<String, u64> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/counters", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<CountersResponse>()
+        .await
+        .unwrap()
+        .counters
+}
+
 async fn decode_ln_invoice(node_address: SocketAddr, invoice: &str) -> DecodeLNInvoiceResponse {
     let payload = DecodeLNInvoiceRequest {
         invoice: invoice.to_string(),
@@ -318,6 +622,24 @@ async fn decode_rgb_invoice(node_address: SocketAddr, invoice: &str) -> DecodeRG
         .unwrap()
 }
 
+async fn delete_payment(node_address: SocketAddr, payment_hash: &str) -> u64 {
+    let payload = DeletePaymentRequest {
+        payment_hash: payment_hash.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/deletepayment", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<DeletePaymentResponse>()
+        .await
+        .unwrap()
+        .removed
+}
+
 async fn disconnect_peer(node_address: SocketAddr, peer_pubkey: &str) {
     let payload = DisconnectPeerRequest {
         peer_pubkey: peer_pubkey.to_string(),
@@ -335,6 +657,39 @@ async fn disconnect_peer(node_address: SocketAddr, peer_pubkey: &str) {
         .unwrap();
 }
 
+async fn events(node_address: SocketAddr) -> Vec<Event> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/events", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EventsResponse>()
+        .await
+        .unwrap()
+        .events
+}
+
+async fn fail_transfers(node_address: SocketAddr, batch_transfer_idx: Option<i32>) -> bool {
+    let payload = FailTransfersRequest {
+        batch_transfer_idx,
+        no_asset_only: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/failtransfers", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<FailTransfersResponse>()
+        .await
+        .unwrap()
+        .transfers_changed
+}
+
 async fn fund_and_create_utxos(node_address: SocketAddr) {
     let res = reqwest::Client::new()
         .post(format!("http://{}/address", node_address))
@@ -362,25 +717,49 @@ async fn fund_and_create_utxos(node_address: SocketAddr) {
         .send()
         .await
         .unwrap();
-    _check_response_is_ok(res)
+    let response = _check_response_is_ok(res)
         .await
-        .json::<EmptyResponse>()
+        .json::<CreateUtxosResponse>()
         .await
         .unwrap();
+    assert_eq!(response.utxos.len(), 10);
+    assert_eq!(response.skipped, 0);
+    assert!(response
+        .utxos
+        .iter()
+        .all(|u| u.outpoint.starts_with(&format!("{}:", response.txid))
+            && u.btc_amount == UTXO_SIZE_SAT as u64));
 
     mine(false);
+    let unspents = list_unspents_filtered(node_address, Some(true)).await;
+    for utxo in &response.utxos {
+        assert!(unspents.iter().any(|u| u.utxo.outpoint == utxo.outpoint));
+    }
 }
 
-async fn invoice_status(node_address: SocketAddr, invoice: &str) -> InvoiceStatus {
-    let payload = InvoiceStatusRequest {
-        invoice: invoice.to_string(),
-    };
+async fn get_payment(node_address: SocketAddr, payment_hash: &str) -> Payment {
     let res = reqwest::Client::new()
-        .post(format!("http://{}/invoicestatus", node_address))
-        .json(&payload)
+        .get(format!(
+            "http://{}/getpayment?payment_hash={}",
+            node_address, payment_hash
+        ))
         .send()
         .await
         .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<GetPaymentResponse>()
+        .await
+        .unwrap()
+        .payment
+}
+
+async fn invoice_status(node_address: SocketAddr, invoice: &str) -> InvoiceStatus {
+    let payload = InvoiceStatusRequest {
+        invoice: Some(invoice.to_string()),
+        payment_hash: None,
+    };
+    let res = invoice_status_raw(node_address, &payload).await;
     _check_response_is_ok(res)
         .await
         .json::<InvoiceStatusResponse>()
@@ -389,12 +768,25 @@ async fn invoice_status(node_address: SocketAddr, invoice: &str) -> InvoiceStatu
         .status
 }
 
+async fn invoice_status_raw(
+    node_address: SocketAddr,
+    payload: &InvoiceStatusRequest,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/invoicestatus", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
 async fn issue_asset(node_address: SocketAddr) -> String {
     let payload = IssueAssetRequest {
-        amounts: vec![1000],
+        amounts: vec![AssetUnits(1000)],
         ticker: s!("USDT"),
         name: s!("Tether"),
         precision: 0,
+        allow_duplicate_ticker: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/issueasset", node_address))
@@ -410,6 +802,22 @@ async fn issue_asset(node_address: SocketAddr) -> String {
         .asset_id
 }
 
+async fn job_status(node_address: SocketAddr, job_id: &str) -> JobStatusResponse {
+    let res = reqwest::Client::new()
+        .get(format!(
+            "http://{}/jobstatus?job_id={}",
+            node_address, job_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<JobStatusResponse>()
+        .await
+        .unwrap()
+}
+
 async fn list_peers(node_address: SocketAddr) -> Vec<Peer> {
     let res = reqwest::Client::new()
         .get(format!("http://{}/listpeers", node_address))
@@ -424,6 +832,38 @@ async fn list_peers(node_address: SocketAddr) -> Vec<Peer> {
         .peers
 }
 
+async fn list_swaps(node_address: SocketAddr) -> Vec<Swap> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listswaps", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListSwapsResponse>()
+        .await
+        .unwrap()
+        .swaps
+}
+
+async fn list_transactions(
+    node_address: SocketAddr,
+    params: &ListTransactionsParams,
+) -> Vec<Transaction> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listtransactions", node_address))
+        .query(params)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListTransactionsResponse>()
+        .await
+        .unwrap()
+        .transactions
+}
+
 async fn asset_balance_offchain_outbound(node_address: SocketAddr, asset_id: &str) -> u64 {
     asset_balance(node_address, asset_id)
         .await
@@ -437,10 +877,16 @@ async fn ln_invoice(
     expiry_sec: u32,
 ) -> LNInvoiceResponse {
     let payload = LNInvoiceRequest {
-        amt_msat: Some(3000000),
+        amt_msat: Some(MilliSats(3000000)),
         expiry_sec,
         asset_id: Some(asset_id.to_string()),
-        asset_amount: Some(asset_amount),
+        asset_amount: Some(AssetUnits(asset_amount)),
+        label: None,
+        allow_insufficient_inbound: false,
+        require_payer_pubkey: None,
+        require_payer_note: false,
+        min_final_cltv_expiry_delta: None,
+        unified: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/lninvoice", node_address))
@@ -491,24 +937,36 @@ async fn _with_ln_balance_checks(
     .await;
 }
 
-async fn keysend_raw(
+async fn keysend_with_msat(
     node_address: SocketAddr,
     dest_pubkey: &str,
     asset_id: &str,
     asset_amount: u64,
-) -> KeysendResponse {
+    amt_msat: u64,
+) -> reqwest::Response {
     let payload = KeysendRequest {
         dest_pubkey: dest_pubkey.to_string(),
-        amt_msat: 3000000,
+        amt_msat: Some(MilliSats(amt_msat)),
         asset_id: asset_id.to_string(),
-        asset_amount,
+        asset_amount: AssetUnits(asset_amount),
+        label: None,
+        use_minimum_btc: false,
     };
-    let res = reqwest::Client::new()
+    reqwest::Client::new()
         .post(format!("http://{}/keysend", node_address))
         .json(&payload)
         .send()
         .await
-        .unwrap();
+        .unwrap()
+}
+
+async fn keysend_raw(
+    node_address: SocketAddr,
+    dest_pubkey: &str,
+    asset_id: &str,
+    asset_amount: u64,
+) -> KeysendResponse {
+    let res = keysend_with_msat(node_address, dest_pubkey, asset_id, asset_amount, 3000000).await;
     _check_response_is_ok(res)
         .await
         .json::<KeysendResponse>()
@@ -549,6 +1007,19 @@ async fn keysend_with_ln_balance(
     .await;
 }
 
+async fn ready(node_address: SocketAddr) -> ReadyResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/ready", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ReadyResponse>()
+        .await
+        .unwrap()
+}
+
 async fn node_info(node_address: SocketAddr) -> NodeInfoResponse {
     let res = reqwest::Client::new()
         .get(format!("http://{}/nodeinfo", node_address))
@@ -562,7 +1033,8 @@ async fn node_info(node_address: SocketAddr) -> NodeInfoResponse {
         .unwrap()
 }
 
-async fn open_channel_with_custom_fees(
+#[allow(clippy::too_many_arguments)]
+async fn open_channel_with_custom_config(
     node_address: SocketAddr,
     dest_peer_pubkey: &str,
     dest_peer_port: u16,
@@ -570,45 +1042,63 @@ async fn open_channel_with_custom_fees(
     asset_id: &str,
     fee_base_msat: Option<u32>,
     fee_proportional_millionths: Option<u32>,
+    htlc_minimum_msat: Option<u64>,
 ) -> Channel {
-    stop_mining();
     let payload = OpenChannelRequest {
         peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", dest_peer_pubkey, dest_peer_port),
-        capacity_sat: 100_000,
-        push_msat: 3_500_000,
-        asset_amount,
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(asset_amount),
         asset_id: asset_id.to_string(),
         public: true,
         with_anchors: true,
         fee_base_msat,
         fee_proportional_millionths,
+        min_depth: None,
+        htlc_minimum_msat: htlc_minimum_msat.map(MilliSats),
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
     };
-    let res = reqwest::Client::new()
-        .post(format!("http://{}/openchannel", node_address))
-        .json(&payload)
+    open_channel_with_payload(node_address, dest_peer_pubkey, &payload).await
+}
+
+async fn open_channel_raw(
+    node_address: SocketAddr,
+    payload: &OpenChannelRequest,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node_address))
+        .json(payload)
         .send()
         .await
-        .unwrap();
+        .unwrap()
+}
+
+async fn open_channel_with_payload(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    payload: &OpenChannelRequest,
+) -> Channel {
+    stop_mining();
+    let res = open_channel_raw(node_address, payload).await;
     _check_response_is_ok(res)
         .await
         .json::<OpenChannelResponse>()
         .await
         .unwrap();
+    wait_for_channel_ready(node_address, dest_peer_pubkey).await
+}
 
+async fn wait_for_funding_broadcast(node_address: SocketAddr, dest_peer_pubkey: &str) -> Channel {
     let t_0 = OffsetDateTime::now_utc();
-    let mut channel_id = None;
-    let mut channel_funded = false;
-    while !channel_funded {
+    loop {
         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
         let channels = list_channels(node_address).await;
         if let Some(channel) = channels.iter().find(|c| c.peer_pubkey == dest_peer_pubkey) {
             if channel.funding_txid.is_some() {
-                let txout = get_txout(channel.funding_txid.as_ref().unwrap());
-                if !txout.is_empty() {
-                    mine_n_blocks(true, 6);
-                    channel_id = Some(channel.channel_id.clone());
-                    channel_funded = true;
-                    continue;
+                if txout_exists(channel.funding_txid.as_ref().unwrap()) {
+                    return channel.clone();
                 }
             }
         }
@@ -616,7 +1106,13 @@ async fn open_channel_with_custom_fees(
             panic!("cannot find funding TX")
         }
     }
-    let channel_id = channel_id.unwrap();
+}
+
+async fn wait_for_channel_ready(node_address: SocketAddr, dest_peer_pubkey: &str) -> Channel {
+    let channel = wait_for_funding_broadcast(node_address, dest_peer_pubkey).await;
+    let confirmations_required = channel.confirmations_required.unwrap();
+    mine_n_blocks(true, confirmations_required as u16);
+    let channel_id = channel.channel_id;
 
     let t_0 = OffsetDateTime::now_utc();
     loop {
@@ -627,6 +1123,7 @@ async fn open_channel_with_custom_fees(
             .find(|c| c.channel_id == channel_id)
             .unwrap();
         if channel.ready {
+            assert!(channel.confirmations >= channel.confirmations_required);
             return channel.clone();
         }
         if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
@@ -635,6 +1132,28 @@ async fn open_channel_with_custom_fees(
     }
 }
 
+async fn open_channel_with_custom_fees(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    dest_peer_port: u16,
+    asset_amount: u64,
+    asset_id: &str,
+    fee_base_msat: Option<u32>,
+    fee_proportional_millionths: Option<u32>,
+) -> Channel {
+    open_channel_with_custom_config(
+        node_address,
+        dest_peer_pubkey,
+        dest_peer_port,
+        asset_amount,
+        asset_id,
+        fee_base_msat,
+        fee_proportional_millionths,
+        None,
+    )
+    .await
+}
+
 async fn open_channel(
     node_address: SocketAddr,
     dest_peer_pubkey: &str,
@@ -654,6 +1173,44 @@ async fn open_channel(
     .await
 }
 
+async fn open_channel_dry_run(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    dest_peer_port: u16,
+    asset_amount: u64,
+    asset_id: &str,
+) -> DryRunResult {
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", dest_peer_pubkey, dest_peer_port),
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(asset_amount),
+        asset_id: asset_id.to_string(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: true,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let response = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap();
+    assert!(response.temporary_channel_id.is_none());
+    response.dry_run.unwrap()
+}
+
 async fn list_assets(node_address: SocketAddr) -> Vec<Asset> {
     let res = reqwest::Client::new()
         .get(format!("http://{}/listassets", node_address))
@@ -668,6 +1225,20 @@ async fn list_assets(node_address: SocketAddr) -> Vec<Asset> {
         .assets
 }
 
+async fn list_bans(node_address: SocketAddr) -> Vec<Ban> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listbans", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListBansResponse>()
+        .await
+        .unwrap()
+        .bans
+}
+
 async fn list_channels(node_address: SocketAddr) -> Vec<Channel> {
     let res = reqwest::Client::new()
         .get(format!("http://{}/listchannels", node_address))
@@ -682,6 +1253,73 @@ async fn list_channels(node_address: SocketAddr) -> Vec<Channel> {
         .channels
 }
 
+async fn list_closed_channels(node_address: SocketAddr) -> Vec<ClosedChannel> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listclosedchannels", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListClosedChannelsResponse>()
+        .await
+        .unwrap()
+        .channels
+}
+
+async fn list_htlcs(node_address: SocketAddr) -> Vec<Htlc> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listhtlcs", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListHtlcsResponse>()
+        .await
+        .unwrap()
+        .htlcs
+}
+
+async fn list_inbound_channel_requests(node_address: SocketAddr) -> Vec<InboundChannelRequest> {
+    let res = reqwest::Client::new()
+        .get(format!(
+            "http://{}/listinboundchannelrequests",
+            node_address
+        ))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListInboundChannelRequestsResponse>()
+        .await
+        .unwrap()
+        .requests
+}
+
+async fn wait_for_request_status(
+    node_address: SocketAddr,
+    request_id: &str,
+    status: InboundChannelRequestStatus,
+) -> InboundChannelRequest {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let requests = list_inbound_channel_requests(node_address).await;
+        let request = requests
+            .into_iter()
+            .find(|r| r.request_id == request_id)
+            .unwrap();
+        if request.status == status {
+            return request;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("channel request is taking too long to reach status {status:?}")
+        }
+    }
+}
+
 async fn list_payments(node_address: SocketAddr) -> Vec<Payment> {
     let res = reqwest::Client::new()
         .get(format!("http://{}/listpayments", node_address))
@@ -696,12 +1334,34 @@ async fn list_payments(node_address: SocketAddr) -> Vec<Payment> {
         .payments
 }
 
-async fn list_unspents(node_address: SocketAddr) -> Vec<Unspent> {
+async fn list_transfers(node_address: SocketAddr, asset_id: &str) -> Vec<Transfer> {
+    let payload = ListTransfersRequest {
+        asset_id: asset_id.to_string(),
+    };
     let res = reqwest::Client::new()
-        .get(format!("http://{}/listunspents", node_address))
+        .post(format!("http://{}/listtransfers", node_address))
+        .json(&payload)
         .send()
         .await
         .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListTransfersResponse>()
+        .await
+        .unwrap()
+        .transfers
+}
+
+async fn list_unspents(node_address: SocketAddr) -> Vec<Unspent> {
+    list_unspents_filtered(node_address, None).await
+}
+
+async fn list_unspents_filtered(node_address: SocketAddr, colorable: Option<bool>) -> Vec<Unspent> {
+    let mut url = format!("http://{}/listunspents", node_address);
+    if let Some(colorable) = colorable {
+        url = format!("{url}?colorable={colorable}");
+    }
+    let res = reqwest::Client::new().get(url).send().await.unwrap();
     _check_response_is_ok(res)
         .await
         .json::<ListUnspentsResponse>()
@@ -723,10 +1383,167 @@ async fn lock(node_address: SocketAddr) {
         .unwrap();
 }
 
+async fn set_payment_policy(node_address: SocketAddr, allowed_destinations: Vec<String>) {
+    let payload = SetPaymentPolicyRequest {
+        allowed_destinations: Some(allowed_destinations),
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/setpaymentpolicy", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+async fn set_spending_limits(
+    node_address: SocketAddr,
+    max_msat_per_day: Option<u64>,
+    max_asset_amounts_per_day: Vec<AssetSpendingLimit>,
+) {
+    let payload = SetPaymentPolicyRequest {
+        allowed_destinations: None,
+        max_msat_per_day: Some(max_msat_per_day.map(MilliSats)),
+        max_asset_amounts_per_day: Some(max_asset_amounts_per_day),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/setpaymentpolicy", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+async fn sign_message(node_address: SocketAddr, message: &str) -> String {
+    let payload = SignMessageRequest {
+        message: message.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/signmessage", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<SignMessageResponse>()
+        .await
+        .unwrap()
+        .signed_message
+}
+
+async fn spending_limits(node_address: SocketAddr) -> SpendingLimitsResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/spendinglimits", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<SpendingLimitsResponse>()
+        .await
+        .unwrap()
+}
+
+async fn stats(node_address: SocketAddr, period: StatsPeriod) -> StatsResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/stats", node_address))
+        .query(&StatsParams { period })
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<StatsResponse>()
+        .await
+        .unwrap()
+}
+
+async fn pending_channels(node_address: SocketAddr) -> Vec<PendingChannel> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/pendingchannels", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PendingChannelsResponse>()
+        .await
+        .unwrap()
+        .channels
+}
+
+async fn prune_monitors_raw(node_address: SocketAddr, older_than_ts: u64) -> reqwest::Response {
+    let payload = PruneMonitorsRequest { older_than_ts };
+    reqwest::Client::new()
+        .post(format!("http://{}/prunemonitors", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn prune_monitors(node_address: SocketAddr, older_than_ts: u64) -> u64 {
+    let res = prune_monitors_raw(node_address, older_than_ts).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<PruneMonitorsResponse>()
+        .await
+        .unwrap()
+        .removed
+}
+
+async fn prune_payments(
+    node_address: SocketAddr,
+    older_than_ts: u64,
+    statuses: Vec<InvoiceStatus>,
+) -> u64 {
+    let payload = PrunePaymentsRequest {
+        older_than_ts,
+        statuses,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/prunepayments", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PrunePaymentsResponse>()
+        .await
+        .unwrap()
+        .removed
+}
+
+async fn query_node_info_raw(node_address: SocketAddr, pubkey: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!(
+            "http://{}/querynodeinfo?pubkey={}",
+            node_address, pubkey
+        ))
+        .send()
+        .await
+        .unwrap()
+}
+
 async fn rgb_invoice(node_address: SocketAddr, asset_id: Option<String>) -> RgbInvoiceResponse {
     let payload = RgbInvoiceRequest {
-        min_confirmations: 1,
+        min_confirmations: None,
         asset_id,
+        label: None,
+        expiry_sec: None,
+        unified: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/rgbinvoice", node_address))
@@ -754,6 +1571,33 @@ async fn refresh_transfers(node_address: SocketAddr) {
         .unwrap();
 }
 
+async fn request_inbound_channel(
+    node_address: SocketAddr,
+    lsp_pubkey: &str,
+    lsp_port: u16,
+    asset_id: &str,
+    asset_amount: u64,
+) -> String {
+    let payload = RequestInboundChannelRequest {
+        lsp_pubkey_and_addr: format!("{}@127.0.0.1:{}", lsp_pubkey, lsp_port),
+        asset_id: asset_id.to_string(),
+        asset_amount: AssetUnits(asset_amount),
+        capacity_sat: Sats(100_000),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/requestinboundchannel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<RequestInboundChannelResponse>()
+        .await
+        .unwrap()
+        .request_id
+}
+
 async fn restore(node_address: SocketAddr, backup_path: &str, password: &str) {
     let payload = RestoreRequest {
         backup_path: backup_path.to_string(),
@@ -772,14 +1616,21 @@ async fn restore(node_address: SocketAddr, backup_path: &str, password: &str) {
         .unwrap();
 }
 
-async fn send_asset(node_address: SocketAddr, asset_id: &str, amount: u64, blinded_utxo: String) {
+async fn send_asset(
+    node_address: SocketAddr,
+    asset_id: &str,
+    amount: u64,
+    blinded_utxo: String,
+) -> SendAssetResponse {
     let payload = SendAssetRequest {
         asset_id: asset_id.to_string(),
-        amount,
+        amount: AssetUnits(amount),
         blinded_utxo,
         donation: true,
         min_confirmations: 1,
-        transport_endpoints: vec![PROXY_ENDPOINT_REGTEST.to_string()],
+        transport_endpoints: vec![test_env().proxy_endpoint.clone()],
+        dry_run: false,
+        force: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/sendasset", node_address))
@@ -791,11 +1642,111 @@ async fn send_asset(node_address: SocketAddr, asset_id: &str, amount: u64, blind
         .await
         .json::<SendAssetResponse>()
         .await
+        .unwrap()
+}
+
+async fn send_asset_raw(node_address: SocketAddr, payload: &SendAssetRequest) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/sendasset", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn send_asset_dry_run(
+    node_address: SocketAddr,
+    asset_id: &str,
+    amount: u64,
+    blinded_utxo: String,
+) -> DryRunResult {
+    let payload = SendAssetRequest {
+        asset_id: asset_id.to_string(),
+        amount: AssetUnits(amount),
+        blinded_utxo,
+        donation: true,
+        min_confirmations: 1,
+        transport_endpoints: vec![test_env().proxy_endpoint.clone()],
+        dry_run: true,
+        force: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendasset", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let response = _check_response_is_ok(res)
+        .await
+        .json::<SendAssetResponse>()
+        .await
+        .unwrap();
+    assert!(response.txid.is_none());
+    response.dry_run.unwrap()
+}
+
+async fn send_custom_message(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    msg_type: u16,
+    data: &str,
+) -> reqwest::Response {
+    let payload = SendCustomMessageRequest {
+        peer_pubkey: peer_pubkey.to_string(),
+        msg_type,
+        data: data.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/sendcustommsg", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn send_onion_message(node_address: SocketAddr, node_ids: Vec<String>, data: &str) {
+    let payload = SendOnionMessageRequest {
+        node_ids,
+        blinded_path: None,
+        tlv_type: 77,
+        data: data.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendonionmessage", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
         .unwrap();
 }
 
 async fn send_payment_raw(node_address: SocketAddr, invoice: String) -> SendPaymentResponse {
-    let payload = SendPaymentRequest { invoice };
+    send_payment_raw_with_retry_config(node_address, invoice, None, None, false).await
+}
+
+async fn send_payment_raw_with_retry_config(
+    node_address: SocketAddr,
+    invoice: String,
+    timeout_sec: Option<u64>,
+    max_retries: Option<u32>,
+    disable_mpp: bool,
+) -> SendPaymentResponse {
+    let payload = SendPaymentRequest {
+        invoice,
+        label: None,
+        timeout_sec,
+        max_retries,
+        disable_mpp,
+        payer_note: None,
+        identify_payer: false,
+        fallback_onchain: false,
+        fallback_fee_rate: None,
+        skip_liquidity_check: false,
+    };
     let res = reqwest::Client::new()
         .post(format!("http://{}/sendpayment", node_address))
         .json(&payload)
@@ -847,14 +1798,125 @@ async fn send_payment_with_status(
     wait_for_ln_payment(node_address, &send_payment.payment_hash, expected_status).await
 }
 
-async fn unlock(node_address: SocketAddr, password: String) {
-    let payload = UnlockRequest { password };
+async fn send_payment_with_retry_config(
+    node_address: SocketAddr,
+    invoice: String,
+    timeout_sec: Option<u64>,
+    max_retries: Option<u32>,
+    disable_mpp: bool,
+    expected_status: HTLCStatus,
+) -> Payment {
+    let send_payment = send_payment_raw_with_retry_config(
+        node_address,
+        invoice,
+        timeout_sec,
+        max_retries,
+        disable_mpp,
+    )
+    .await;
+    wait_for_ln_payment(node_address, &send_payment.payment_hash, expected_status).await
+}
+
+async fn set_alerts(node_address: SocketAddr, payload: &SetAlertsRequest) {
     let res = reqwest::Client::new()
+        .post(format!("http://{}/setalerts", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+async fn set_alias(node_address: SocketAddr, alias: &str) -> reqwest::Response {
+    let payload = SetAliasRequest {
+        alias: alias.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/setalias", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn set_channel_note(
+    node_address: SocketAddr,
+    channel_id: &str,
+    note: &str,
+) -> reqwest::Response {
+    let payload = SetChannelNoteRequest {
+        channel_id: channel_id.to_string(),
+        note: note.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/setchannelnote", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn swap_raw(
+    node_address: SocketAddr,
+    direction: SwapDirection,
+    channel_id: &str,
+    asset_amount: u64,
+) -> reqwest::Response {
+    let endpoint = match direction {
+        SwapDirection::IntoChannel => "swapintochannel",
+        SwapDirection::OutOfChannel => "swapoutofchannel",
+    };
+    let payload = SwapAssetsRequest {
+        channel_id: channel_id.to_string(),
+        asset_amount: AssetUnits(asset_amount),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/{}", node_address, endpoint))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn swap(
+    node_address: SocketAddr,
+    direction: SwapDirection,
+    channel_id: &str,
+    asset_amount: u64,
+) -> String {
+    let res = swap_raw(node_address, direction, channel_id, asset_amount).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<SwapAssetsResponse>()
+        .await
+        .unwrap()
+        .swap_id
+}
+
+async fn unlock_raw(
+    node_address: SocketAddr,
+    password: String,
+    regenerate: bool,
+) -> reqwest::Response {
+    let payload = UnlockRequest {
+        password,
+        regenerate,
+        recovery_mode: false,
+    };
+    reqwest::Client::new()
         .post(format!("http://{}/unlock", node_address))
         .json(&payload)
         .send()
         .await
-        .unwrap();
+        .unwrap()
+}
+
+async fn unlock(node_address: SocketAddr, password: String) {
+    let res = unlock_raw(node_address, password, false).await;
     _check_response_is_ok(res)
         .await
         .json::<EmptyResponse>()
@@ -907,11 +1969,69 @@ async fn wait_for_ln_payment(
     }
 }
 
+async fn wait_for_job(node_address: SocketAddr, job_id: &str) -> JobStatusResponse {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let job = job_status(node_address, job_id).await;
+        if job.status != JobStatus::Running {
+            return job;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("job {job_id} didn't finish")
+        }
+    }
+}
+
+async fn wait_for_swap(node_address: SocketAddr, swap_id: &str) -> Swap {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let swap = list_swaps(node_address)
+            .await
+            .into_iter()
+            .find(|s| s.swap_id == swap_id)
+            .unwrap();
+        if swap.status != SwapStatus::Pending {
+            return swap;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("swap {swap_id} didn't finish")
+        }
+    }
+}
+
+async fn verify_message(
+    node_address: SocketAddr,
+    message: &str,
+    signature: &str,
+) -> VerifyMessageResponse {
+    let payload = VerifyMessageRequest {
+        message: message.to_string(),
+        signature: signature.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/verifymessage", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<VerifyMessageResponse>()
+        .await
+        .unwrap()
+}
+
 async fn shutdown(node_sockets: &[SocketAddr], ldk_sockets: &[SocketAddr]) {
     // shutdown nodes
     for node_address in node_sockets {
+        let payload = ShutdownRequest {
+            mode: ShutdownMode::Exit,
+        };
         let res = reqwest::Client::new()
             .post(format!("http://{}/shutdown", node_address))
+            .json(&payload)
             .send()
             .await
             .unwrap();
@@ -968,22 +2088,11 @@ struct Miner {
 }
 
 impl Miner {
-    fn mine(&self, num_blocks: u16) -> bool {
+    fn mine(&self, backend: &RegtestBackend, num_blocks: u16) -> bool {
         if self.no_mine_count > 0 {
             return false;
         }
-        let status = Command::new("docker")
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .arg("compose")
-            .args(_bitcoin_cli())
-            .arg("-rpcwallet=miner")
-            .arg("-generate")
-            .arg(num_blocks.to_string())
-            .status()
-            .expect("failed to mine");
-        assert!(status.success());
+        block_on(backend.mine(num_blocks));
         true
     }
 
@@ -1004,16 +2113,17 @@ fn mine(resume: bool) {
 
 fn mine_n_blocks(resume: bool, num_blocks: u16) {
     let t_0 = OffsetDateTime::now_utc();
+    let env = test_env();
     if resume {
         resume_mining();
     }
     let mut last_result = false;
     while !last_result {
-        let miner = MINER.read();
+        let miner = env.miner.read();
         last_result = miner
             .as_ref()
-            .expect("MINER has been initialized")
-            .mine(num_blocks);
+            .expect("miner lock is not poisoned")
+            .mine(&env.regtest_backend(), num_blocks);
         drop(miner);
         if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 120.0 {
             eprintln!("forcibly breaking mining wait");
@@ -1027,41 +2137,30 @@ fn mine_n_blocks(resume: bool, num_blocks: u16) {
 }
 
 fn stop_mining() {
-    MINER
+    test_env()
+        .miner
         .write()
-        .expect("MINER has been initialized")
+        .expect("miner lock is not poisoned")
         .stop_mining()
 }
 
 fn resume_mining() {
-    MINER
+    test_env()
+        .miner
         .write()
-        .expect("MINER has been initialized")
+        .expect("miner lock is not poisoned")
         .resume_mining()
 }
 
 fn wait_electrs_sync() {
     let t_0 = OffsetDateTime::now_utc();
-    let output = Command::new("docker")
-        .stdin(Stdio::null())
-        .stderr(Stdio::null())
-        .arg("compose")
-        .args(_bitcoin_cli())
-        .arg("getblockcount")
-        .output()
-        .expect("failed to call getblockcount");
-    assert!(output.status.success());
-    let blockcount_str =
-        std::str::from_utf8(&output.stdout).expect("could not parse blockcount output");
-    let blockcount = blockcount_str
-        .trim()
-        .parse::<u32>()
-        .expect("could not parse blockcount");
+    let env = test_env();
+    let blockcount = block_on(env.regtest_backend().block_count());
     loop {
         std::thread::sleep(std::time::Duration::from_millis(100));
         let mut all_synced = true;
         let electrum =
-            electrum_client::Client::new(ELECTRUM_URL).expect("cannot get electrum client");
+            electrum_client::Client::new(&env.electrum_url).expect("cannot get electrum client");
         if electrum.block_header(blockcount as usize).is_err() {
             all_synced = false;
         }
@@ -1076,18 +2175,54 @@ fn wait_electrs_sync() {
 
 pub fn initialize() {
     INIT.call_once(|| {
-        println!("starting test services...");
-        let status = Command::new("./regtest.sh")
-            .args(["start"])
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .expect("failed to start test services");
-        assert!(status.success());
+        // services already running elsewhere (bitcoind, electrs and the proxy) can be used instead
+        if std::env::var_os(EXTERNAL_SERVICES_ENV).is_none()
+            && std::env::var_os(TEST_ENVS_ENV).is_none()
+        {
+            println!("starting test services...");
+            let status = Command::new("./regtest.sh")
+                .args(["start"])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .expect("failed to start test services");
+            assert!(status.success());
+        }
+    });
+
+    // tests run on their own thread, which keeps the environment until the test ends
+    CURRENT_TEST_ENV.with(|env| {
+        env.borrow_mut().get_or_insert_with(TestEnvGuard::acquire);
+    });
+    let env = test_env();
+    env.prepared.call_once(|| {
+        let backend = env.regtest_backend();
+        block_on(backend.ensure_wallet());
+        if block_on(backend.wallet_balance_sat()) == 0 {
+            mine_n_blocks(false, INITIAL_BLOCKS);
+        }
     });
 }
 
+mod abandon_payment;
+mod amount_validation;
+mod announcements;
+mod api_limits;
+mod asset_export;
+mod asset_import;
+mod asset_registry;
+mod asset_supply;
+mod auto_unlock;
 mod backup_and_restore;
+mod balance_alerts;
+mod balances;
+mod chain_reorgs;
+mod channel_debug;
+mod channel_fee_rates;
+mod channel_handshake;
+mod channel_min_depth;
+mod channel_suggestions;
+mod close_coop_asset_destination;
 mod close_coop_nobtc_acceptor;
 mod close_coop_other_side;
 mod close_coop_standard;
@@ -1095,10 +2230,74 @@ mod close_coop_zero_balance;
 mod close_force_nobtc_acceptor;
 mod close_force_other_side;
 mod close_force_standard;
+mod colored_payment_silent_proxy;
+mod consolidation;
+mod create_utxos;
+mod custom_messages;
+mod data_dir_integrity;
+mod data_dir_lock;
+mod data_dir_migration;
+mod data_version;
+mod degraded_persistence;
+mod graph_pruning;
+mod htlc_dust_limits;
+mod htlc_expiry;
+mod inbound_channel_requests;
+mod interrupted_wallet_ops;
+mod invoice_closure;
+mod invoice_cltv_delta;
+mod invoice_inbound_liquidity;
+mod invoice_status;
+mod jobs;
+mod keysend_btc_amounts;
+mod latency;
+mod list_htlcs;
+mod list_ordering;
+mod ln_invoice_route_hints;
+mod lsp_channel_fee;
+mod lsp_inbound_channel;
+mod monitor_archive;
+mod monitor_mirror;
 mod multi_hop;
 mod multi_open_close;
+mod node_alias;
+mod non_anchor_channels;
+mod onchain_fallback;
+mod onion_messages;
 mod open_after_double_send;
+mod open_push_limit;
+mod open_rejected;
+mod outbound_reservations;
+mod payer_identification;
+mod payer_liquidity_checks;
 mod payment;
+mod payment_filters;
+mod payment_policy;
+mod payment_pruning;
+mod payment_quotes;
+mod payment_requests;
+mod payment_retry_config;
+mod payment_stats;
+mod peer_bans;
+mod peer_connections;
+mod peer_exposure;
+mod peer_listening;
+mod proxy_check;
+mod proxy_failover;
+mod read_only_token;
+mod rebroadcast_unconfirmed;
+mod recovery_mode;
 mod refuse_high_fees;
+mod reject_invalid_rgb_htlc;
 mod restart;
+mod restartable_shutdown;
+mod rgb_invoice_expiry;
+mod rgb_support;
 mod send_receive;
+mod send_same_blinded_utxo;
+mod serde_format;
+mod sign_message;
+mod spending_limits;
+mod swaps;
+mod wallet_addresses;
+mod webhooks;
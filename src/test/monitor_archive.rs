@@ -0,0 +1,85 @@
+use crate::disk::PENDING_SPENDABLE_OUTPUT_DIR;
+use crate::utils::LDK_DIR;
+use std::path::PathBuf;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/monitor_archive/";
+const NODE1_PEER_PORT: u16 = 10151;
+const NODE2_PEER_PORT: u16 = 10152;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn monitor_archive() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, node1_password) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1.clone(),
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            monitor_archive_depth: 0,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    let info = node_info(node1_addr).await;
+    assert_eq!(info.num_hot_monitors, 1);
+    assert_eq!(info.num_archived_monitors, 0);
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    wait_for_balance(node1_addr, &asset_id, 1000).await;
+    mine_n_blocks(false, 6);
+
+    // resolved monitors are only moved to the archive when the node gets unlocked
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        lock(node1_addr).await;
+        unlock(node1_addr, node1_password.clone()).await;
+        let info = node_info(node1_addr).await;
+        if info.num_archived_monitors == 1 {
+            assert_eq!(info.num_hot_monitors, 0);
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("monitor has not been archived")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    }
+
+    // archives are kept while a spendable output waits to be swept
+    let pending_dir = PathBuf::from(&test_dir_node1)
+        .join(LDK_DIR)
+        .join(PENDING_SPENDABLE_OUTPUT_DIR);
+    std::fs::create_dir_all(&pending_dir).unwrap();
+    let pending_output = pending_dir.join("dummy");
+    std::fs::write(&pending_output, "dummy").unwrap();
+    let now = OffsetDateTime::now_utc().unix_timestamp() as u64;
+    let res = prune_monitors_raw(node1_addr, now + 1).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Cannot prune monitors while 1 spendable outputs are waiting to be swept"
+    );
+    assert_eq!(response.code, 403);
+    std::fs::remove_file(pending_output).unwrap();
+
+    // archives newer than the given timestamp are kept
+    assert_eq!(prune_monitors(node1_addr, 0).await, 0);
+    assert_eq!(node_info(node1_addr).await.num_archived_monitors, 1);
+
+    assert_eq!(prune_monitors(node1_addr, now + 1).await, 1);
+    assert_eq!(node_info(node1_addr).await.num_archived_monitors, 0);
+}
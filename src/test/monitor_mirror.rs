@@ -0,0 +1,308 @@
+use axum::extract::Path as UrlPath;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use std::sync::Arc;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/monitor_mirror/";
+const NODE1_PEER_PORT: u16 = 9941;
+const NODE2_PEER_PORT: u16 = 9942;
+const HTTP_NODE1_PEER_PORT: u16 = 10364;
+const HTTP_NODE2_PEER_PORT: u16 = 10365;
+const HTTP_MIRROR_PORT: u16 = 10366;
+
+const HTTP_MIRROR_TOKEN: &str = "mirror-token";
+
+/// Files of the HTTP mirror, by key path
+type MirrorFiles = Arc<Mutex<BTreeMap<String, Vec<u8>>>>;
+
+/// HTTP mirror storing the files it gets in memory, listing the key paths under a namespace
+fn start_http_mirror(files: MirrorFiles) {
+    let app = axum::Router::new().route(
+        "/*key_path",
+        axum::routing::any(
+            move |method: axum::http::Method,
+                  headers: HeaderMap,
+                  UrlPath(key_path): UrlPath<String>,
+                  body: axum::body::Bytes| {
+                let files = files.clone();
+                async move {
+                    let authorization = format!("Bearer {HTTP_MIRROR_TOKEN}");
+                    if headers.get("Authorization").map(|h| h.to_str().unwrap())
+                        != Some(authorization.as_str())
+                    {
+                        return StatusCode::UNAUTHORIZED.into_response();
+                    }
+                    let key_path = key_path.trim_start_matches('/').to_string();
+                    let mut files = files.lock().unwrap();
+                    match method {
+                        axum::http::Method::PUT => {
+                            files.insert(key_path, body.to_vec());
+                            StatusCode::OK.into_response()
+                        }
+                        axum::http::Method::DELETE => match files.remove(&key_path) {
+                            Some(_) => StatusCode::OK.into_response(),
+                            None => StatusCode::NOT_FOUND.into_response(),
+                        },
+                        _ => {
+                            if let Some(buf) = files.get(&key_path) {
+                                return buf.clone().into_response();
+                            }
+                            let prefix = format!("{key_path}/");
+                            let keys: Vec<String> = files
+                                .keys()
+                                .filter_map(|k| k.strip_prefix(&prefix).map(|k| k.to_string()))
+                                .collect();
+                            if keys.is_empty() {
+                                return StatusCode::NOT_FOUND.into_response();
+                            }
+                            axum::Json(keys).into_response()
+                        }
+                    }
+                }
+            },
+        ),
+    );
+    tokio::spawn(
+        axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], HTTP_MIRROR_PORT)))
+            .serve(app.into_make_service()),
+    );
+}
+
+/// Files of the HTTP mirror under the namespace, by name
+fn http_mirror_files(files: &MirrorFiles, namespace: &str) -> Vec<(String, Vec<u8>)> {
+    let prefix = format!("{namespace}/");
+    files
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|(k, v)| k.strip_prefix(&prefix).map(|k| (k.to_string(), v.clone())))
+        .collect()
+}
+
+fn dir_files(dir: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut files: Vec<(String, Vec<u8>)> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.is_file())
+        .map(|p| {
+            (
+                p.file_name().unwrap().to_string_lossy().to_string(),
+                std::fs::read(p).unwrap(),
+            )
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+async fn wait_for_mirror_sync(node_address: SocketAddr) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let info = node_info(node_address).await;
+        if info.monitor_mirror_pending_updates == Some(0) {
+            assert!(!info.monitor_mirror_lagging);
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("monitor mirror didn't catch up")
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+}
+
+async fn wait_for_channel_reestablished(node_address: SocketAddr, channel_id: &str) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node_address).await;
+        let channel = channels
+            .iter()
+            .find(|c| c.channel_id == channel_id)
+            .unwrap();
+        if channel.ready {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("cannot find re-established channel")
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn monitor_mirror() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let mirror_dir = format!("{TEST_DIR_BASE}mirror");
+    if Path::new(&mirror_dir).is_dir() {
+        std::fs::remove_dir_all(&mirror_dir).unwrap();
+    }
+    let mirror = MirrorTarget::from_str(&mirror_dir).unwrap();
+    let ldk_sockets = get_ldk_sockets(&[NODE1_PEER_PORT, NODE2_PEER_PORT]);
+
+    let (node1_addr, _) = start_node_with_monitor_mirror(
+        test_dir_node1.clone(),
+        NODE1_PEER_PORT,
+        false,
+        Some(mirror.clone()),
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2.clone(), NODE2_PEER_PORT, false).await;
+    assert_eq!(
+        node_info(node2_addr).await.monitor_mirror_pending_updates,
+        None
+    );
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 600, 0).await;
+
+    // the mirror holds the same monitors as the local store
+    wait_for_mirror_sync(node1_addr).await;
+    let local_monitors = Path::new(&test_dir_node1).join(".ldk/monitors");
+    let mirror_monitors = Path::new(&mirror_dir).join("monitors");
+    let monitors = dir_files(&local_monitors);
+    assert!(!monitors.is_empty());
+    assert_eq!(monitors, dir_files(&mirror_monitors));
+
+    // lose the local monitors and restart, they get restored from the mirror
+    shutdown(&[node1_addr, node2_addr], &ldk_sockets).await;
+    std::fs::remove_dir_all(&local_monitors).unwrap();
+    let local_updates = Path::new(&test_dir_node1).join(".ldk/monitor_updates");
+    if local_updates.is_dir() {
+        std::fs::remove_dir_all(&local_updates).unwrap();
+    }
+    let (node1_addr, _) =
+        start_node_with_monitor_mirror(test_dir_node1.clone(), NODE1_PEER_PORT, true, Some(mirror))
+            .await;
+    let (node2_addr, _) = start_node(test_dir_node2.clone(), NODE2_PEER_PORT, true).await;
+    assert_eq!(dir_files(&local_monitors), monitors);
+    wait_for_channel_reestablished(node1_addr, &channel.channel_id).await;
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 500, 100).await;
+
+    // once the monitors get written again, the mirror has newer copies than the old ones
+    let t_0 = OffsetDateTime::now_utc();
+    let newer_monitors = loop {
+        mine_n_blocks(false, 1);
+        wait_for_mirror_sync(node1_addr).await;
+        let mirrored = dir_files(&mirror_monitors);
+        if mirrored != monitors {
+            break mirrored;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("monitors not written again")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+
+    // old local monitors get replaced by the newer copies of the mirror
+    shutdown(&[node1_addr, node2_addr], &ldk_sockets).await;
+    for (name, buf) in &monitors {
+        std::fs::write(local_monitors.join(name), buf).unwrap();
+    }
+    if local_updates.is_dir() {
+        std::fs::remove_dir_all(&local_updates).unwrap();
+    }
+    let mirror = MirrorTarget::from_str(&mirror_dir).unwrap();
+    let (node1_addr, _) =
+        start_node_with_monitor_mirror(test_dir_node1.clone(), NODE1_PEER_PORT, true, Some(mirror))
+            .await;
+    let (node2_addr, _) = start_node(test_dir_node2.clone(), NODE2_PEER_PORT, true).await;
+    assert_eq!(dir_files(&local_monitors), newer_monitors);
+    wait_for_channel_reestablished(node1_addr, &channel.channel_id).await;
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 400, 200).await;
+    wait_for_mirror_sync(node1_addr).await;
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    wait_for_balance(node1_addr, &asset_id, 700).await;
+    wait_for_balance(node2_addr, &asset_id, 300).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn http_monitor_mirror() {
+    initialize();
+
+    let files = MirrorFiles::default();
+    start_http_mirror(files.clone());
+    let mirror = MirrorTarget::Http {
+        url: format!("http://127.0.0.1:{HTTP_MIRROR_PORT}"),
+        token: Some(s!(HTTP_MIRROR_TOKEN)),
+    };
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}http_node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}http_node2");
+    let ldk_sockets = get_ldk_sockets(&[HTTP_NODE1_PEER_PORT, HTTP_NODE2_PEER_PORT]);
+    let (node1_addr, _) = start_node_with_monitor_mirror(
+        test_dir_node1.clone(),
+        HTTP_NODE1_PEER_PORT,
+        false,
+        Some(mirror.clone()),
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2.clone(), HTTP_NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        HTTP_NODE2_PEER_PORT,
+        600,
+        &asset_id,
+    )
+    .await;
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 600, 0).await;
+
+    // the mirror holds the same monitors as the local store
+    wait_for_mirror_sync(node1_addr).await;
+    let local_monitors = Path::new(&test_dir_node1).join(".ldk/monitors");
+    let monitors = dir_files(&local_monitors);
+    assert!(!monitors.is_empty());
+    assert_eq!(monitors, http_mirror_files(&files, "monitors"));
+
+    // lose the local monitors and restart, they get restored from the mirror
+    shutdown(&[node1_addr, node2_addr], &ldk_sockets).await;
+    std::fs::remove_dir_all(&local_monitors).unwrap();
+    let local_updates = Path::new(&test_dir_node1).join(".ldk/monitor_updates");
+    if local_updates.is_dir() {
+        std::fs::remove_dir_all(&local_updates).unwrap();
+    }
+    let (node1_addr, _) = start_node_with_monitor_mirror(
+        test_dir_node1.clone(),
+        HTTP_NODE1_PEER_PORT,
+        true,
+        Some(mirror),
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2.clone(), HTTP_NODE2_PEER_PORT, true).await;
+    assert_eq!(dir_files(&local_monitors), monitors);
+    wait_for_channel_reestablished(node1_addr, &channel.channel_id).await;
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 500, 100).await;
+    wait_for_mirror_sync(node1_addr).await;
+}
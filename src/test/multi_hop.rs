@@ -5,7 +5,6 @@ const NODE1_PEER_PORT: u16 = 9901;
 const NODE2_PEER_PORT: u16 = 9902;
 const NODE3_PEER_PORT: u16 = 9903;
 
-#[serial_test::serial]
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[traced_test]
 async fn multi_hop() {
@@ -46,8 +45,43 @@ async fn multi_hop() {
     let channel_23 = open_channel(node2_addr, &node3_pubkey, NODE3_PEER_PORT, 300, &asset_id).await;
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 100);
 
-    let LNInvoiceResponse { invoice } = ln_invoice(node3_addr, &asset_id, 50, 900).await;
-    let _ = send_payment(node1_addr, invoice).await;
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node3_addr, &asset_id, 50, 900).await;
+    let payment = send_payment(node1_addr, invoice).await;
+    assert!(payment.hops.is_none());
+
+    // the route taken is recorded once the path is reported successful
+    let t_0 = OffsetDateTime::now_utc();
+    let hops = loop {
+        let hops = get_payment(node1_addr, &payment.payment_hash)
+            .await
+            .hops
+            .unwrap();
+        if !hops.is_empty() {
+            break hops;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("route of the payment hasn't been recorded")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert_eq!(hops.len(), 2);
+    assert!(hops.iter().all(|h| h.part == 0));
+    assert_eq!(hops[0].node_pubkey, node2_pubkey);
+    assert_eq!(
+        hops[0].short_channel_id,
+        channel_12.short_channel_id.unwrap()
+    );
+    assert!(hops[0].fee_msat > 0);
+    assert_eq!(hops[1].node_pubkey, node3_pubkey);
+    assert_eq!(
+        hops[1].short_channel_id,
+        channel_23.short_channel_id.unwrap()
+    );
+    assert_eq!(hops[1].fee_msat, 0);
+    // the recipient reports no route
+    let received = get_payment(node3_addr, &payment.payment_hash).await;
+    assert!(received.inbound);
+    assert!(received.hops.is_none());
 
     close_channel(node2_addr, &channel_12.channel_id, &node1_pubkey, false).await;
     wait_for_balance(node1_addr, &asset_id, 550).await;
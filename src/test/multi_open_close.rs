@@ -5,7 +5,26 @@ const NODE1_PEER_PORT: u16 = 9891;
 const NODE2_PEER_PORT: u16 = 9892;
 const NODE3_PEER_PORT: u16 = 9893;
 
-#[serial_test::serial]
+fn get_tx_fee(txid: &str) -> u64 {
+    let electrum = electrum_client::Client::new(&test_env().electrum_url).unwrap();
+    let tx = electrum
+        .transaction_get(&electrum_client::bitcoin::Txid::from_str(txid).unwrap())
+        .unwrap();
+    let input_sat: u64 = tx
+        .input
+        .iter()
+        .map(|i| {
+            electrum
+                .transaction_get(&i.previous_output.txid)
+                .unwrap()
+                .output[i.previous_output.vout as usize]
+                .value
+        })
+        .sum();
+    let output_sat: u64 = tx.output.iter().map(|o| o.value).sum();
+    input_sat - output_sat
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[traced_test]
 async fn multi_open_close() {
@@ -24,18 +43,67 @@ async fn multi_open_close() {
 
     let asset_id = issue_asset(node1_addr).await;
 
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
     let node2_info = node_info(node2_addr).await;
     let node2_pubkey = node2_info.pubkey;
 
+    let dry_run =
+        open_channel_dry_run(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    // the wallet selects the allocations when funding, the dry run doesn't guess them
+    assert!(dry_run.inputs.is_empty());
+    assert!(dry_run.consumed_allocations.is_empty());
+    assert!(dry_run.fee_sat > 0);
+    assert_eq!(dry_run.asset_change, None);
+    assert_eq!(
+        open_channel_dry_run(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await,
+        dry_run
+    );
+    assert!(list_channels(node1_addr).await.is_empty());
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 1000);
+
     let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
+    let funding_fee_sat = get_tx_fee(channel.funding_txid.as_ref().unwrap());
+    assert_eq!(channel.funding_fee_sat, funding_fee_sat);
+    let node2_channel = list_channels(node2_addr).await.pop().unwrap();
+    assert_eq!(node2_channel.funding_fee_sat, 0);
 
     keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
 
+    let dry_run =
+        close_channel_dry_run(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    assert_eq!(dry_run.inputs.len(), 1);
+    assert!(dry_run.inputs[0].starts_with(channel.funding_txid.as_ref().unwrap()));
+    assert_eq!(dry_run.consumed_allocations[0].amount, 600);
+    assert!(dry_run.fee_sat > 0);
+    assert_eq!(dry_run.asset_change, Some(500));
+    let node2_dry_run =
+        close_channel_dry_run(node2_addr, &channel.channel_id, &node1_pubkey, false).await;
+    assert_eq!(node2_dry_run.fee_sat, 0);
+    assert_eq!(node2_dry_run.asset_change, Some(100));
+    assert_eq!(list_channels(node1_addr).await.len(), 1);
+
     close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
     wait_for_balance(node1_addr, &asset_id, 900).await;
     wait_for_balance(node2_addr, &asset_id, 100).await;
 
+    let closed_channels = list_closed_channels(node1_addr).await;
+    assert_eq!(closed_channels.len(), 1);
+    let closed_channel = &closed_channels[0];
+    assert_eq!(closed_channel.channel_id, channel.channel_id);
+    assert_eq!(closed_channel.funding_txid, channel.funding_txid);
+    assert_eq!(closed_channel.peer_pubkey, Some(node2_pubkey.clone()));
+    assert_eq!(closed_channel.asset_id, Some(asset_id.clone()));
+    assert_eq!(closed_channel.asset_local_amount, Some(500));
+    assert_eq!(closed_channel.asset_remote_amount, Some(100));
+    assert_eq!(closed_channel.funding_fee_sat, funding_fee_sat);
+    assert!(closed_channel.close_fee_sat > 0);
+    assert_eq!(closed_channel.force_close_fee_sat, 0);
+    let node2_closed_channel = list_closed_channels(node2_addr).await.pop().unwrap();
+    assert_eq!(node2_closed_channel.funding_fee_sat, 0);
+    assert_eq!(node2_closed_channel.close_fee_sat, 0);
+
+    let first_channel = channel;
     let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 500, &asset_id).await;
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
 
@@ -47,12 +115,58 @@ async fn multi_open_close() {
     wait_for_balance(node2_addr, &asset_id, 200).await;
 
     let recipient_id = rgb_invoice(node3_addr, None).await.recipient_id;
-    send_asset(node1_addr, &asset_id, 700, recipient_id).await;
+    let send_txid = send_asset(node1_addr, &asset_id, 700, recipient_id)
+        .await
+        .txid
+        .unwrap();
     mine(false);
     refresh_transfers(node3_addr).await;
     refresh_transfers(node3_addr).await;
     refresh_transfers(node1_addr).await;
 
+    let transactions = list_transactions(node1_addr, &ListTransactionsParams::default()).await;
+    let funding_txid = first_channel.funding_txid.unwrap();
+    let funding_tx = transactions
+        .iter()
+        .find(|t| t.txid == funding_txid)
+        .unwrap();
+    assert_eq!(funding_tx.purpose, TransactionPurpose::ChannelOpen);
+    assert_eq!(funding_tx.channel_id, Some(first_channel.channel_id));
+    assert_eq!(funding_tx.direction, TransactionDirection::Outgoing);
+    assert_eq!(funding_tx.fee, Some(funding_fee_sat));
+    let send_tx = transactions.iter().find(|t| t.txid == send_txid).unwrap();
+    assert_eq!(send_tx.purpose, TransactionPurpose::AssetSend);
+    assert!(send_tx.channel_id.is_none());
+    assert!(transactions
+        .iter()
+        .any(|t| t.purpose == TransactionPurpose::UtxoCreation));
+    let page = list_transactions(
+        node1_addr,
+        &ListTransactionsParams {
+            offset: Some(1),
+            limit: Some(2),
+            ..Default::default()
+        },
+    )
+    .await;
+    let page_txids: Vec<_> = page.iter().map(|t| &t.txid).collect();
+    let expected_txids: Vec<_> = transactions[1..3].iter().map(|t| &t.txid).collect();
+    assert_eq!(page_txids, expected_txids);
+    let funding_height = funding_tx.confirmation_time.as_ref().unwrap().height;
+    let in_range = list_transactions(
+        node1_addr,
+        &ListTransactionsParams {
+            min_height: Some(funding_height),
+            max_height: Some(funding_height),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(in_range.iter().any(|t| t.txid == funding_txid));
+    assert!(in_range
+        .iter()
+        .all(|t| t.confirmation_time.as_ref().unwrap().height == funding_height));
+
     let recipient_id = rgb_invoice(node3_addr, None).await.recipient_id;
     send_asset(node2_addr, &asset_id, 150, recipient_id).await;
     mine(false);
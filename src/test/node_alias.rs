@@ -0,0 +1,75 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/node_alias/";
+const NODE1_PEER_PORT: u16 = 10091;
+const NODE2_PEER_PORT: u16 = 10092;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn node_alias() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1,
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            ldk_announced_node_alias: s!("ñode one"),
+            ldk_announced_node_color: [0x33, 0x99, 0xff],
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    let node1_info = node_info(node1_addr).await;
+    assert_eq!(node1_info.alias, "ñode one");
+    assert_eq!(node1_info.color, "3399ff");
+
+    // the alias limit is on the UTF-8 encoded bytes, 17 characters here
+    let res = set_alias(node1_addr, &"ñ".repeat(17)).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Invalid node alias: cannot be longer than 32 bytes when UTF-8 encoded"
+    );
+    assert_eq!(node_info(node1_addr).await.alias, "ñode one");
+
+    // nodes without channels are unknown to the network graph
+    let res = query_node_info_raw(node2_addr, &node1_info.pubkey).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Unknown node");
+
+    fund_and_create_utxos(node1_addr).await;
+    let asset_id = issue_asset(node1_addr).await;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    // the channel gets announced after 6 confirmations
+    mine_n_blocks(false, 6);
+
+    _check_response_is_ok(set_alias(node1_addr, &"ñ".repeat(16)).await).await;
+    assert_eq!(node_info(node1_addr).await.alias, "ñ".repeat(16));
+
+    // the peer drops the node announcement until it knows the channel, so keep announcing
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let res = query_node_info_raw(node2_addr, &node1_info.pubkey).await;
+        if res.status().is_success() {
+            let info: QueryNodeInfoResponse = res.json().await.unwrap();
+            if info.alias.as_deref() == Some(&"ñ".repeat(16)) {
+                assert_eq!(info.color.as_deref(), Some("3399ff"));
+                assert!(info.last_update.is_some());
+                break;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("node announcement not received")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        _check_response_is_ok(set_alias(node1_addr, &"ñ".repeat(16)).await).await;
+    }
+}
@@ -0,0 +1,133 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/non_anchor_channels/";
+const NODE1_PEER_PORT: u16 = 10318;
+const NODE2_PEER_PORT: u16 = 10319;
+const NODE3_PEER_PORT: u16 = 10320;
+
+fn open_payload(
+    dest_peer_pubkey: &str,
+    dest_peer_port: u16,
+    asset_id: &str,
+    with_anchors: bool,
+) -> OpenChannelRequest {
+    OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", dest_peer_pubkey, dest_peer_port),
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(600),
+        asset_id: asset_id.to_string(),
+        public: true,
+        with_anchors,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    }
+}
+
+async fn check_commitment_type(
+    node_address: SocketAddr,
+    channel_id: &str,
+    expected: CommitmentType,
+) {
+    let channel = list_channels(node_address)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel_id)
+        .unwrap();
+    assert_eq!(channel.commitment_type, Some(expected));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn non_anchor_channels() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node2,
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            accept_non_anchor_channels: true,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node3_addr, _) = start_node(test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    // channels without anchors are rejected by default
+    let payload = open_payload(&node3_pubkey, NODE3_PEER_PORT, &asset_id, false);
+    let res = open_channel_raw(node1_addr, &payload).await;
+    let temporary_channel_id = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap()
+        .temporary_channel_id
+        .unwrap();
+    let t_0 = OffsetDateTime::now_utc();
+    while !events(node1_addr).await.into_iter().any(|e| {
+        matches!(e.event, NodeEvent::ChannelOpenFailed { temporary_channel_id: id, .. }
+            if id == temporary_channel_id)
+    }) {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("channel open failure not notified")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    assert!(list_channels(node3_addr).await.is_empty());
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 1000);
+
+    // a node accepting them pays and force-closes over a legacy channel
+    let payload = open_payload(&node2_pubkey, NODE2_PEER_PORT, &asset_id, false);
+    let channel = open_channel_with_payload(node1_addr, &node2_pubkey, &payload).await;
+    check_commitment_type(
+        node1_addr,
+        &channel.channel_id,
+        CommitmentType::StaticRemoteKey,
+    )
+    .await;
+    check_commitment_type(
+        node2_addr,
+        &channel.channel_id,
+        CommitmentType::StaticRemoteKey,
+    )
+    .await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
+
+    keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
+
+    // the opener sweeps the output paying it on the counterparty's commitment
+    close_channel(node2_addr, &channel.channel_id, &node1_pubkey, true).await;
+    wait_for_balance(node1_addr, &asset_id, 900).await;
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+
+    // anchor channels keep working with the same node
+    let payload = open_payload(&node2_pubkey, NODE2_PEER_PORT, &asset_id, true);
+    let channel = open_channel_with_payload(node1_addr, &node2_pubkey, &payload).await;
+    check_commitment_type(node2_addr, &channel.channel_id, CommitmentType::Anchors).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 300);
+
+    keysend(node1_addr, &node2_pubkey, &asset_id, 50).await;
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    wait_for_balance(node1_addr, &asset_id, 850).await;
+    wait_for_balance(node2_addr, &asset_id, 150).await;
+}
@@ -0,0 +1,167 @@
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{Secp256k1, SecretKey};
+use bitcoin::util::address::{Address, Payload};
+use lightning::ln::PaymentSecret;
+use lightning_invoice::{Currency, Fallback, InvoiceBuilder};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/onchain_fallback/";
+const NODE1_PEER_PORT: u16 = 10294;
+const NODE2_PEER_PORT: u16 = 10295;
+
+async fn new_address(node_address: SocketAddr) -> String {
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/address", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<AddressResponse>()
+        .await
+        .unwrap()
+        .address
+}
+
+/// Invoice of a node nobody can route to, with the given fallback address
+fn unreachable_invoice(fallback_address: &str) -> String {
+    let address = Address::from_str(fallback_address).unwrap();
+    let fallback = match address.payload {
+        Payload::WitnessProgram { version, program } => {
+            Fallback::SegWitProgram { version, program }
+        }
+        Payload::PubkeyHash(hash) => Fallback::PubKeyHash(hash),
+        Payload::ScriptHash(hash) => Fallback::ScriptHash(hash),
+    };
+    let secret_key = SecretKey::from_slice(&[42; 32]).unwrap();
+    InvoiceBuilder::new(Currency::Regtest)
+        .description(s!("unreachable"))
+        .payment_hash(sha256::Hash::hash(&[1; 32]))
+        .payment_secret(PaymentSecret([2; 32]))
+        .amount_milli_satoshis(3_000_000)
+        .current_timestamp()
+        .min_final_cltv_expiry_delta(144)
+        .fallback(fallback)
+        .build_signed(|hash| Secp256k1::new().sign_ecdsa_recoverable(hash, &secret_key))
+        .unwrap()
+        .to_string()
+}
+
+async fn ln_invoice_unchecked(node_address: SocketAddr, asset_id: Option<&str>) -> String {
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(MilliSats(3000000)),
+        expiry_sec: 900,
+        asset_id: asset_id.map(|a| a.to_string()),
+        asset_amount: asset_id.map(|_| AssetUnits(10)),
+        label: None,
+        allow_insufficient_inbound: true,
+        require_payer_pubkey: None,
+        require_payer_note: false,
+        min_final_cltv_expiry_delta: None,
+        unified: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice
+}
+
+async fn send_payment_with_fallback(
+    node_address: SocketAddr,
+    invoice: String,
+) -> reqwest::Response {
+    let payload = SendPaymentRequest {
+        invoice,
+        label: None,
+        timeout_sec: Some(5),
+        max_retries: None,
+        disable_mpp: false,
+        payer_note: None,
+        identify_payer: false,
+        fallback_onchain: true,
+        fallback_fee_rate: Some(2.0),
+        skip_liquidity_check: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/sendpayment", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn onchain_fallback() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    let asset_id = issue_asset(node1_addr).await;
+
+    // RGB invoices can't be paid on-chain
+    let invoice = ln_invoice_unchecked(node1_addr, Some(&asset_id)).await;
+    let res = send_payment_with_fallback(node1_addr, invoice).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "On-chain fallback unavailable: the assets of an RGB invoice cannot be paid on-chain"
+    );
+
+    // the invoice has to include a fallback address
+    let invoice = ln_invoice_unchecked(node2_addr, None).await;
+    let res = send_payment_with_fallback(node1_addr, invoice).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "On-chain fallback unavailable: the invoice has no fallback address"
+    );
+
+    // the payment can't be routed, so its fallback address gets paid instead
+    let fallback_address = new_address(node2_addr).await;
+    let invoice = unreachable_invoice(&fallback_address);
+    let res = send_payment_with_fallback(node1_addr, invoice).await;
+    let payment_hash = _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap()
+        .payment_hash;
+    let t_0 = OffsetDateTime::now_utc();
+    let payment = loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let payment = list_payments(node1_addr)
+            .await
+            .into_iter()
+            .find(|p| p.payment_hash == payment_hash)
+            .unwrap();
+        if payment.settlement_method == SettlementMethod::Onchain {
+            break payment;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("payment hasn't been settled on-chain")
+        }
+    };
+    assert!(matches!(payment.status, HTLCStatus::Succeeded));
+    assert!(payment.failure_reason.is_none());
+    assert!(payment.onchain_txid.is_some());
+
+    mine(false);
+    let btc = balances(node2_addr).await.btc;
+    assert_eq!(btc.onchain_vanilla.settled, 3000);
+}
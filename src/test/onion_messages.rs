@@ -0,0 +1,107 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/onion_messages/";
+const NODE1_PEER_PORT: u16 = 10001;
+const NODE2_PEER_PORT: u16 = 10002;
+const NODE3_PEER_PORT: u16 = 10003;
+
+async fn received_onion_messages(node_address: SocketAddr) -> Vec<String> {
+    events(node_address)
+        .await
+        .into_iter()
+        .filter_map(|e| match e.event {
+            NodeEvent::OnionMessageReceived { tlv_type, data } => {
+                assert_eq!(tlv_type, 77);
+                Some(data)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+async fn wait_for_onion_message(node_address: SocketAddr, data: &str) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        if received_onion_messages(node_address)
+            .await
+            .iter()
+            .any(|d| d == data)
+        {
+            return;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("onion message has not been received")
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn onion_messages() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node2,
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            onion_message_forwarding: true,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node3_addr, _) = start_node(test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    assert!(!node_info(node1_addr).await.onion_message_forwarding);
+    assert!(node_info(node2_addr).await.onion_message_forwarding);
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    connect_peer(
+        node1_addr,
+        &node2_pubkey,
+        &format!("127.0.0.1:{NODE2_PEER_PORT}"),
+    )
+    .await;
+    connect_peer(
+        node1_addr,
+        &node3_pubkey,
+        &format!("127.0.0.1:{NODE3_PEER_PORT}"),
+    )
+    .await;
+    connect_peer(
+        node2_addr,
+        &node3_pubkey,
+        &format!("127.0.0.1:{NODE3_PEER_PORT}"),
+    )
+    .await;
+
+    // direct message
+    send_onion_message(node1_addr, vec![node2_pubkey.clone()], "01").await;
+    wait_for_onion_message(node2_addr, "01").await;
+
+    // message relayed by a node with forwarding enabled
+    send_onion_message(
+        node1_addr,
+        vec![node2_pubkey.clone(), node3_pubkey.clone()],
+        "02",
+    )
+    .await;
+    wait_for_onion_message(node3_addr, "02").await;
+
+    // message dropped by a node with forwarding disabled
+    send_onion_message(
+        node1_addr,
+        vec![node3_pubkey.clone(), node2_pubkey.clone()],
+        "03",
+    )
+    .await;
+    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    assert_eq!(received_onion_messages(node2_addr).await, vec![s!("01")]);
+}
@@ -5,7 +5,6 @@ const NODE1_PEER_PORT: u16 = 9911;
 const NODE2_PEER_PORT: u16 = 9912;
 const NODE3_PEER_PORT: u16 = 9913;
 
-#[serial_test::serial]
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[traced_test]
 async fn open_after_double_send() {
@@ -29,13 +28,35 @@ async fn open_after_double_send() {
     let node2_info = node_info(node2_addr).await;
     let node2_pubkey = node2_info.pubkey;
 
+    let reserved = |unspents: Vec<Unspent>| {
+        unspents
+            .into_iter()
+            .filter_map(|u| u.reserved_by.map(|r| (u.utxo.outpoint, r)))
+            .collect::<Vec<_>>()
+    };
+    // the UTXO creations have confirmed, nothing is held anymore
+    assert!(reserved(list_unspents(node1_addr).await).is_empty());
+
     let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
-    send_asset(node1_addr, &asset_id, 100, recipient_id).await;
+    let txid = send_asset(node1_addr, &asset_id, 100, recipient_id)
+        .await
+        .txid
+        .unwrap();
+    // the change of the transfer is held by it until its transaction confirms
+    let transfer_reserved = reserved(list_unspents(node1_addr).await);
+    assert!(!transfer_reserved.is_empty());
+    assert!(transfer_reserved
+        .iter()
+        .all(|(_, r)| *r == UtxoReservation::AssetTransfer { txid: txid.clone() }));
+    assert!(transfer_reserved
+        .iter()
+        .any(|(outpoint, _)| outpoint.starts_with(&format!("{txid}:"))));
     mine(false);
     refresh_transfers(node2_addr).await;
     refresh_transfers(node2_addr).await;
     refresh_transfers(node1_addr).await;
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 900);
+    assert!(reserved(list_unspents(node1_addr).await).is_empty());
 
     let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
     send_asset(node1_addr, &asset_id, 200, recipient_id).await;
@@ -46,10 +67,70 @@ async fn open_after_double_send() {
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 700);
     assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 300);
 
-    let channel = open_channel(node2_addr, &node1_pubkey, NODE1_PEER_PORT, 250, &asset_id).await;
+    // the two received allocations sit on two distinct colored outpoints
+    assert!(list_unspents_filtered(node2_addr, Some(false))
+        .await
+        .iter()
+        .all(|u| !u.utxo.colorable && u.rgb_allocations.is_empty()));
+    let asset_outpoints = |unspents: Vec<Unspent>| {
+        let mut outpoints = unspents
+            .into_iter()
+            .flat_map(|u| {
+                u.rgb_allocations
+                    .into_iter()
+                    .filter(|a| a.asset_id.as_ref() == Some(&asset_id))
+                    .map(move |a| (u.utxo.outpoint.clone(), a.amount, a.settled))
+            })
+            .collect::<Vec<_>>();
+        outpoints.sort_by_key(|(_, amount, _)| *amount);
+        outpoints
+    };
+    let received = asset_outpoints(list_unspents_filtered(node2_addr, Some(true)).await);
+    assert_eq!(received.len(), 2);
+    assert_ne!(received[0].0, received[1].0);
+    assert_eq!((received[0].1, received[0].2), (100, true));
+    assert_eq!((received[1].1, received[1].2), (200, true));
+
+    stop_mining();
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{node1_pubkey}@127.0.0.1:{NODE1_PEER_PORT}"),
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(250),
+        asset_id: asset_id.clone(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    };
+    _check_response_is_ok(open_channel_raw(node2_addr, &payload).await).await;
+    // the funding transaction holds its UTXOs until it confirms
+    let pending = wait_for_funding_broadcast(node2_addr, &node1_pubkey).await;
+    let funding_reserved = reserved(list_unspents(node2_addr).await);
+    assert!(!funding_reserved.is_empty());
+    assert!(funding_reserved.iter().all(|(_, r)| *r
+        == UtxoReservation::ChannelOpen {
+            channel_id: pending.channel_id.clone()
+        }));
+    let channel = wait_for_channel_ready(node2_addr, &node1_pubkey).await;
+    assert_eq!(channel.channel_id, pending.channel_id);
+    assert!(reserved(list_unspents(node2_addr).await).is_empty());
     assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 50);
 
-    let LNInvoiceResponse { invoice } = ln_invoice(node1_addr, &asset_id, 50, 900).await;
+    // both received outpoints have been spent to fund the channel, the change sits on a new one
+    let change = asset_outpoints(list_unspents_filtered(node2_addr, Some(true)).await);
+    assert_eq!(change.len(), 1);
+    assert_eq!(change[0].1, 50);
+    assert!(received
+        .iter()
+        .all(|(outpoint, _, _)| *outpoint != change[0].0));
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node1_addr, &asset_id, 50, 900).await;
     let _ = send_payment(node2_addr, invoice).await;
 
     close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
@@ -0,0 +1,85 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/open_push_limit/";
+const NODE1_PEER_PORT: u16 = 10171;
+const NODE2_PEER_PORT: u16 = 10172;
+
+const PUSH_TOO_HIGH_PREFIX: &str =
+    "Push amount leaves no room for the reserve and the commitment fee, max: ";
+
+async fn check_push_too_high(node_address: SocketAddr, payload: &OpenChannelRequest) -> u64 {
+    let res = open_channel_raw(node_address, payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.code, 400);
+    response
+        .error
+        .strip_prefix(PUSH_TOO_HIGH_PREFIX)
+        .and_then(|e| e.strip_suffix(" msat"))
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn open_push_limit() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let capacity_sat = 100_000;
+    let mut payload = OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", node2_pubkey, NODE2_PEER_PORT),
+        capacity_sat: Sats(capacity_sat),
+        push_msat: MilliSats(capacity_sat * 1000),
+        asset_amount: AssetUnits(600),
+        asset_id: asset_id.clone(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    };
+
+    // the opener keeps at least the 1% reserve and the commitment fee
+    let max_push_msat = check_push_too_high(node1_addr, &payload).await;
+    assert!(max_push_msat < (capacity_sat - 1000) * 1000);
+    assert!(max_push_msat > (capacity_sat - 5000) * 1000);
+
+    payload.push_msat = MilliSats(max_push_msat + 1);
+    assert_eq!(
+        check_push_too_high(node1_addr, &payload).await,
+        max_push_msat
+    );
+    payload.dry_run = true;
+    assert_eq!(
+        check_push_too_high(node1_addr, &payload).await,
+        max_push_msat
+    );
+
+    // the peer accepts a channel pushing the maximum
+    payload.push_msat = MilliSats(max_push_msat);
+    payload.dry_run = false;
+    open_channel_with_payload(node1_addr, &node2_pubkey, &payload).await;
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let channels = list_channels(node2_addr).await;
+    let channel = channels
+        .iter()
+        .find(|c| c.peer_pubkey == node1_pubkey)
+        .unwrap();
+    assert_eq!(channel.local_balance_msat, max_push_msat);
+}
@@ -0,0 +1,89 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/open_rejected/";
+const NODE1_PEER_PORT: u16 = 10041;
+const NODE2_PEER_PORT: u16 = 10042;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn open_rejected() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node2,
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            channel_acceptance_policy: ChannelAcceptancePolicy::Reject,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node1_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", node2_pubkey, NODE2_PEER_PORT),
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(600),
+        asset_id: asset_id.clone(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    };
+    let res = open_channel_raw(node1_addr, &payload).await;
+    let temporary_channel_id = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap()
+        .temporary_channel_id
+        .unwrap();
+
+    // the rejection is notified and the assets are spendable again within seconds
+    let t_0 = OffsetDateTime::now_utc();
+    let reason = loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let failure = events(node1_addr)
+            .await
+            .into_iter()
+            .find_map(|e| match e.event {
+                NodeEvent::ChannelOpenFailed {
+                    temporary_channel_id: id,
+                    peer_pubkey,
+                    asset_id: failed_asset_id,
+                    asset_amount,
+                    reason,
+                } if id == temporary_channel_id => {
+                    assert_eq!(peer_pubkey, node2_pubkey);
+                    assert_eq!(failed_asset_id, asset_id);
+                    assert_eq!(asset_amount, 600);
+                    Some(reason)
+                }
+                _ => None,
+            });
+        if let Some(reason) = failure {
+            break reason;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("channel open failure not notified")
+        }
+    };
+    assert!(reason.contains("counterparty force-closed"));
+    assert!(list_channels(node1_addr).await.is_empty());
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 1000);
+}
@@ -0,0 +1,176 @@
+use crate::ldk::PaymentFaults;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/outbound_reservations/";
+const NODE1_PEER_PORT: u16 = 10315;
+const NODE2_PEER_PORT: u16 = 10316;
+const NODE3_PEER_PORT: u16 = 10418;
+const NODE4_PEER_PORT: u16 = 10419;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn outbound_reservations() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // the channel is large enough for all the HTLCs to be in flight at once
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", node2_pubkey, NODE2_PEER_PORT),
+        capacity_sat: Sats(1_000_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(550),
+        asset_id: asset_id.clone(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    };
+    open_channel_with_payload(node1_addr, &node2_pubkey, &payload).await;
+
+    // 10 keysends of 100 against 550: 5 get reserved, the others are refused upfront
+    let responses = futures::future::join_all(
+        (0..10).map(|_| keysend_with_msat(node1_addr, &node2_pubkey, &asset_id, 100, 3000000)),
+    )
+    .await;
+    let mut payment_hashes = vec![];
+    let mut refused = 0;
+    for res in responses {
+        if res.status() == reqwest::StatusCode::FORBIDDEN {
+            let response: ErrorResponse = res.json().await.unwrap();
+            assert!(
+                response
+                    .error
+                    .starts_with("Not enough asset balance, available: "),
+                "{}",
+                response.error
+            );
+            refused += 1;
+            continue;
+        }
+        let response = _check_response_is_ok(res)
+            .await
+            .json::<KeysendResponse>()
+            .await
+            .unwrap();
+        assert!(matches!(response.status, HTLCStatus::Pending));
+        payment_hashes.push(response.payment_hash);
+    }
+    assert_eq!(payment_hashes.len(), 5);
+    assert_eq!(refused, 5);
+
+    // the reserved payments all go through
+    for payment_hash in &payment_hashes {
+        wait_for_ln_payment(node1_addr, payment_hash, HTLCStatus::Succeeded).await;
+    }
+    assert_eq!(
+        asset_balance(node1_addr, &asset_id).await.offchain_outbound,
+        50
+    );
+
+    // settled payments don't hold their reservation, a failed one releases it
+    let res = keysend_with_msat(node1_addr, &node2_pubkey, &asset_id, 60, 3000000).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Not enough asset balance, available: 50, reserved by pending payments: 0"
+    );
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let failed = _check_response_is_ok(
+        keysend_with_msat(node1_addr, &node1_pubkey, &asset_id, 50, 3000000).await,
+    )
+    .await
+    .json::<KeysendResponse>()
+    .await
+    .unwrap();
+    assert!(matches!(failed.status, HTLCStatus::Failed));
+    keysend(node1_addr, &node2_pubkey, &asset_id, 50).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn outbound_reservation_duplicate_send() {
+    initialize();
+
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node3_addr, _) = start_node(test_dir_node3, NODE3_PEER_PORT, false).await;
+    let payment_faults = Arc::new(PaymentFaults::default());
+    let (node4_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node4"),
+            ldk_peer_listening_port: NODE4_PEER_PORT,
+            payment_faults: Arc::clone(&payment_faults),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node3_addr).await;
+    fund_and_create_utxos(node4_addr).await;
+
+    let asset_id = issue_asset(node3_addr).await;
+    let node4_pubkey = node_info(node4_addr).await.pubkey;
+    open_channel(node3_addr, &node4_pubkey, NODE4_PEER_PORT, 600, &asset_id).await;
+
+    // node4 doesn't settle the payment, keeping it in flight
+    payment_faults
+        .hold_claimable_payments
+        .store(true, Ordering::Release);
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node4_addr, &asset_id, 100, 900).await;
+    let payment_hash = send_payment_raw(node3_addr, invoice.clone())
+        .await
+        .payment_hash;
+
+    // sending it again is refused and leaves its reservation in place
+    let payload = SendPaymentRequest {
+        invoice,
+        label: None,
+        timeout_sec: None,
+        max_retries: None,
+        disable_mpp: false,
+        payer_note: None,
+        identify_payer: false,
+        fallback_onchain: false,
+        fallback_fee_rate: None,
+        skip_liquidity_check: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendpayment", node3_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Payment already pending or succeeded");
+    check_payment_status(node3_addr, &payment_hash, HTLCStatus::Pending)
+        .await
+        .unwrap();
+
+    let res = keysend_with_msat(node3_addr, &node4_pubkey, &asset_id, 550, 3000000).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Not enough asset balance, available: 500, reserved by pending payments: 100"
+    );
+}
@@ -0,0 +1,160 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payer_identification/";
+const NODE1_PEER_PORT: u16 = 10211;
+const NODE2_PEER_PORT: u16 = 10212;
+
+async fn payer_invoice(
+    node_address: SocketAddr,
+    asset_id: &str,
+    require_payer_pubkey: Option<&str>,
+    require_payer_note: bool,
+) -> String {
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(MilliSats(3000000)),
+        expiry_sec: 900,
+        asset_id: Some(asset_id.to_string()),
+        asset_amount: Some(AssetUnits(10)),
+        label: None,
+        allow_insufficient_inbound: false,
+        require_payer_pubkey: require_payer_pubkey.map(|pk| pk.to_string()),
+        require_payer_note,
+        min_final_cltv_expiry_delta: None,
+        unified: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice
+}
+
+async fn pay_identified(
+    node_address: SocketAddr,
+    invoice: String,
+    identify_payer: bool,
+    payer_note: Option<&str>,
+    expected_status: HTLCStatus,
+) -> Payment {
+    let payload = SendPaymentRequest {
+        invoice,
+        label: None,
+        timeout_sec: None,
+        max_retries: None,
+        disable_mpp: false,
+        payer_note: payer_note.map(|n| n.to_string()),
+        identify_payer,
+        fallback_onchain: false,
+        fallback_fee_rate: None,
+        skip_liquidity_check: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendpayment", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let payment_hash = _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap()
+        .payment_hash;
+    wait_for_ln_payment(node_address, &payment_hash, expected_status).await
+}
+
+async fn inbound_payment(node_address: SocketAddr, payment_hash: &str) -> Payment {
+    list_payments(node_address)
+        .await
+        .into_iter()
+        .find(|p| p.inbound && p.payment_hash == payment_hash)
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payer_identification() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node2_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node2_addr, &node1_pubkey, NODE1_PEER_PORT, 600, &asset_id).await;
+
+    // an invoice reserved to node2 can't be paid anonymously
+    let invoice = payer_invoice(node1_addr, &asset_id, Some(&node2_pubkey), false).await;
+    let payment = pay_identified(node2_addr, invoice, false, None, HTLCStatus::Failed).await;
+    let payee_payment = inbound_payment(node1_addr, &payment.payment_hash).await;
+    assert_eq!(payee_payment.status, HTLCStatus::Pending);
+    assert!(payee_payment.payer_pubkey.is_none());
+
+    // nor by a payer identifying as another node
+    let invoice = payer_invoice(node1_addr, &asset_id, Some(&node1_pubkey), false).await;
+    pay_identified(node2_addr, invoice, true, None, HTLCStatus::Failed).await;
+
+    // an invoice requiring a note can't be paid without one
+    let invoice = payer_invoice(node1_addr, &asset_id, None, true).await;
+    pay_identified(node2_addr, invoice, true, None, HTLCStatus::Failed).await;
+
+    let rejections = events(node1_addr)
+        .await
+        .into_iter()
+        .filter_map(|e| match e.event {
+            NodeEvent::PayerIdentificationRejected { reason, .. } => Some(reason),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rejections,
+        vec![
+            s!("payer did not identify itself"),
+            format!("payer {node2_pubkey} is not the expected one"),
+            s!("payer did not attach a note"),
+        ]
+    );
+
+    // the expected payer with a note gets through and is recorded on the payment
+    let invoice = payer_invoice(node1_addr, &asset_id, Some(&node2_pubkey), true).await;
+    let payment = pay_identified(
+        node2_addr,
+        invoice,
+        true,
+        Some("order 42"),
+        HTLCStatus::Succeeded,
+    )
+    .await;
+    let payee_payment =
+        wait_for_ln_payment(node1_addr, &payment.payment_hash, HTLCStatus::Succeeded).await;
+    assert_eq!(payee_payment.payer_pubkey, Some(node2_pubkey.clone()));
+    assert_eq!(payee_payment.payer_note, Some(s!("order 42")));
+
+    // invoices without requirements take payments with or without identification
+    let invoice = payer_invoice(node1_addr, &asset_id, None, false).await;
+    let payment = pay_identified(
+        node2_addr,
+        invoice,
+        false,
+        Some("tip"),
+        HTLCStatus::Succeeded,
+    )
+    .await;
+    let payee_payment =
+        wait_for_ln_payment(node1_addr, &payment.payment_hash, HTLCStatus::Succeeded).await;
+    assert!(payee_payment.payer_pubkey.is_none());
+    assert_eq!(payee_payment.payer_note, Some(s!("tip")));
+}
@@ -0,0 +1,135 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payer_liquidity_checks/";
+const NODE1_PEER_PORT: u16 = 10344;
+const NODE2_PEER_PORT: u16 = 10345;
+
+async fn colored_invoice(
+    node_address: SocketAddr,
+    asset_id: &str,
+    asset_amount: u64,
+    amt_msat: u64,
+) -> String {
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node_address))
+        .json(&LNInvoiceRequest {
+            amt_msat: Some(MilliSats(amt_msat)),
+            expiry_sec: 900,
+            asset_id: Some(asset_id.to_string()),
+            asset_amount: Some(AssetUnits(asset_amount)),
+            label: None,
+            allow_insufficient_inbound: true,
+            require_payer_pubkey: None,
+            require_payer_note: false,
+            min_final_cltv_expiry_delta: None,
+            unified: false,
+        })
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice
+}
+
+async fn pay_invoice(
+    node_address: SocketAddr,
+    invoice: String,
+    skip_liquidity_check: bool,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/sendpayment", node_address))
+        .json(&SendPaymentRequest {
+            invoice,
+            label: None,
+            timeout_sec: None,
+            max_retries: None,
+            disable_mpp: false,
+            payer_note: None,
+            identify_payer: false,
+            fallback_onchain: false,
+            fallback_fee_rate: None,
+            skip_liquidity_check,
+        })
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn check_payment_refused(res: reqwest::Response, error: &str) {
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, error);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payer_liquidity_checks() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    let unknown_asset_id = issue_asset(node2_addr).await;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // assets the payer has never seen are refused upfront
+    let invoice = colored_invoice(node2_addr, &unknown_asset_id, 10, 3000000).await;
+    let res = pay_invoice(node1_addr, invoice, false).await;
+    check_payment_refused(res, "Unknown RGB contract ID").await;
+
+    // as are asset amounts no channel can send, reporting the most that can be sent
+    let invoice = colored_invoice(node2_addr, &asset_id, 700, 3000000).await;
+    let res = pay_invoice(node1_addr, invoice.clone(), false).await;
+    check_payment_refused(
+        res,
+        "Not enough outbound asset liquidity, can send at most 600",
+    )
+    .await;
+
+    // and msat amounts the channels carrying the asset can't send
+    let outbound_msat = list_channels(node1_addr)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel.channel_id)
+        .unwrap()
+        .outbound_balance_msat
+        .unwrap();
+    let big_invoice = colored_invoice(node2_addr, &asset_id, 10, outbound_msat + 1000).await;
+    let res = pay_invoice(node1_addr, big_invoice, false).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    let max_msat: u64 = response
+        .error
+        .strip_prefix("Not enough outbound liquidity, can send at most ")
+        .and_then(|e| e.strip_suffix(" msat"))
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(max_msat > 0 && max_msat <= outbound_msat);
+    assert!(list_payments(node1_addr).await.is_empty());
+
+    // the checks can be skipped, leaving the payment to fail while being sent
+    let res = pay_invoice(node1_addr, invoice, true).await;
+    let payment_hash = _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap()
+        .payment_hash;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+
+    // payments the channels can carry go through
+    let invoice = colored_invoice(node2_addr, &asset_id, 100, 3000000).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 600, 0).await;
+}
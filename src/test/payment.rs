@@ -7,7 +7,6 @@ const NODE1_PEER_PORT: u16 = 9821;
 const NODE2_PEER_PORT: u16 = 9822;
 const NODE3_PEER_PORT: u16 = 9823;
 
-#[serial_test::serial]
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[traced_test]
 async fn payment() {
@@ -32,7 +31,7 @@ async fn payment() {
     let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
 
-    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
     send_payment_with_ln_balance(node1_addr, node2_addr, invoice.clone(), 600, 0).await;
 
     let decoded = decode_ln_invoice(node1_addr, &invoice).await;
@@ -44,13 +43,13 @@ async fn payment() {
     let status = invoice_status(node2_addr, &invoice).await;
     assert!(matches!(status, InvoiceStatus::Succeeded));
 
-    let LNInvoiceResponse { invoice } = ln_invoice(node1_addr, &asset_id, 50, 900).await;
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node1_addr, &asset_id, 50, 900).await;
     send_payment_with_ln_balance(node2_addr, node1_addr, invoice.clone(), 100, 500).await;
 
-    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, &asset_id, 50, 900).await;
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 50, 900).await;
     let _ = send_payment(node1_addr, invoice.clone()).await;
 
-    let LNInvoiceResponse { invoice } = ln_invoice(node1_addr, &asset_id, 50, 900).await;
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node1_addr, &asset_id, 50, 900).await;
     let _ = send_payment(node2_addr, invoice.clone()).await;
 
     close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
@@ -0,0 +1,158 @@
+use crate::database::Database;
+use crate::invoices::InvoiceClosure;
+use crate::ldk::PaymentInfo;
+use crate::pagination::Cursor;
+use crate::utils::{get_current_timestamp, hex_str};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payment_filters/";
+
+fn payment_info(
+    label: Option<&str>,
+    status: HTLCStatus,
+    created_at: u64,
+    expires_at: Option<u64>,
+    closure: Option<InvoiceClosure>,
+) -> PaymentInfo {
+    PaymentInfo {
+        preimage: None,
+        secret: None,
+        status,
+        amt_msat: Some(3000000),
+        label: label.map(|l| l.to_string()),
+        created_at,
+        expires_at,
+        failure_reason: None,
+        failing_hop: None,
+        settled_at: None,
+        payer_requirements: None,
+        payer: None,
+        payee: None,
+        onchain_txid: None,
+        closure,
+        hops: vec![],
+    }
+}
+
+fn page_ids(
+    database: &Database,
+    label: Option<&str>,
+    status: Option<InvoiceStatus>,
+    after: Option<&Cursor>,
+    limit: Option<usize>,
+) -> Vec<u8> {
+    database
+        .payments_page(label, status, after, limit)
+        .unwrap()
+        .iter()
+        .map(|(id, _, _)| id[0])
+        .collect()
+}
+
+#[test]
+fn payment_filters() {
+    let _ = std::fs::remove_dir_all(TEST_DIR_BASE);
+    std::fs::create_dir_all(TEST_DIR_BASE).unwrap();
+    let database = Database::open(&Path::new(TEST_DIR_BASE).join(DATABASE_FNAME)).unwrap();
+
+    let later = get_current_timestamp() + 3600;
+    let payments = [
+        (
+            1,
+            true,
+            payment_info(Some("a"), HTLCStatus::Pending, 100, Some(later), None),
+        ),
+        (
+            2,
+            true,
+            payment_info(Some("a"), HTLCStatus::Pending, 101, Some(200), None),
+        ),
+        (
+            3,
+            true,
+            payment_info(
+                Some("b"),
+                HTLCStatus::Pending,
+                102,
+                Some(later),
+                Some(InvoiceClosure::Cancelled),
+            ),
+        ),
+        (
+            4,
+            false,
+            payment_info(Some("a"), HTLCStatus::Succeeded, 102, None, None),
+        ),
+        (
+            5,
+            false,
+            payment_info(None, HTLCStatus::Failed, 103, None, None),
+        ),
+        (
+            6,
+            true,
+            payment_info(
+                Some("a"),
+                HTLCStatus::Pending,
+                104,
+                Some(later),
+                Some(InvoiceClosure::Expired),
+            ),
+        ),
+    ];
+    for (id, inbound, info) in &payments {
+        database.save_payment(&[*id; 32], *inbound, info).unwrap();
+    }
+
+    // payments get sorted by creation time, then by ID
+    assert_eq!(
+        page_ids(&database, None, None, None, None),
+        [1, 2, 3, 4, 5, 6]
+    );
+
+    // filters
+    assert_eq!(
+        page_ids(&database, Some("a"), None, None, None),
+        [1, 2, 4, 6]
+    );
+    assert!(page_ids(&database, Some("c"), None, None, None).is_empty());
+    let status_ids = |status| page_ids(&database, None, Some(status), None, None);
+    assert_eq!(status_ids(InvoiceStatus::Pending), [1]);
+    assert_eq!(status_ids(InvoiceStatus::Expired), [2, 6]);
+    assert_eq!(status_ids(InvoiceStatus::Cancelled), [3]);
+    assert_eq!(status_ids(InvoiceStatus::Succeeded), [4]);
+    assert_eq!(status_ids(InvoiceStatus::Failed), [5]);
+    assert_eq!(
+        page_ids(
+            &database,
+            Some("b"),
+            Some(InvoiceStatus::Expired),
+            None,
+            None
+        ),
+        Vec::<u8>::new()
+    );
+
+    // paging, also between payments created in the same second
+    assert_eq!(page_ids(&database, None, None, None, Some(2)), [1, 2]);
+    let after = Cursor::new(102, &hex_str(&[3; 32]));
+    assert_eq!(
+        page_ids(&database, None, None, Some(&after), Some(2)),
+        [4, 5]
+    );
+    let after = Cursor::new(101, &hex_str(&[2; 32]));
+    assert_eq!(
+        page_ids(&database, Some("a"), None, Some(&after), None),
+        [4, 6]
+    );
+
+    // updated payments get filtered on their new values
+    let (_, _, info) = &payments[0];
+    let mut info = info.clone();
+    info.status = HTLCStatus::Succeeded;
+    info.label = Some(s!("b"));
+    database.save_payment(&[1; 32], true, &info).unwrap();
+    assert_eq!(status_ids(InvoiceStatus::Succeeded), [1, 4]);
+    assert_eq!(page_ids(&database, Some("b"), None, None, None), [1, 3]);
+}
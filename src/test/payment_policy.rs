@@ -0,0 +1,118 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payment_policy/";
+const NODE1_PEER_PORT: u16 = 10409;
+const NODE2_PEER_PORT: u16 = 10410;
+
+async fn check_policy_violation(res: reqwest::Response, destination: &str) {
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let text = res.text().await.unwrap();
+    let response: ErrorResponse = serde_json::from_str(&text).unwrap();
+    assert_eq!(
+        response.error,
+        format!("Payment policy violation: destination {destination} is not allowed")
+    );
+    assert_eq!(response.code, 403);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payment_policy() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // check InvalidPaymentPolicy error
+    let payload = SetPaymentPolicyRequest {
+        allowed_destinations: Some(vec![s!("invalid")]),
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/setpaymentpolicy", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Invalid payment policy: invalid is neither a node pubkey nor a blinded UTXO"
+    );
+
+    // only a blinded UTXO of node2 is allowed, LN payments to node2 are not
+    let allowed_blinded_utxo = rgb_invoice(node2_addr, None).await.recipient_id;
+    set_payment_policy(node1_addr, vec![allowed_blinded_utxo.clone()]).await;
+
+    // check PolicyViolation error
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 10, 900).await;
+    let payload = SendPaymentRequest {
+        invoice,
+        label: None,
+        timeout_sec: None,
+        max_retries: None,
+        disable_mpp: false,
+        payer_note: None,
+        identify_payer: false,
+        fallback_onchain: false,
+        fallback_fee_rate: None,
+        skip_liquidity_check: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendpayment", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_policy_violation(res, &node2_pubkey).await;
+    let payload = KeysendRequest {
+        dest_pubkey: node2_pubkey.clone(),
+        amt_msat: Some(MilliSats(3000000)),
+        asset_id: asset_id.clone(),
+        asset_amount: AssetUnits(10),
+        label: None,
+        use_minimum_btc: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/keysend", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_policy_violation(res, &node2_pubkey).await;
+    let other_blinded_utxo = rgb_invoice(node2_addr, None).await.recipient_id;
+    let payload = SendAssetRequest {
+        asset_id: asset_id.clone(),
+        amount: AssetUnits(10),
+        blinded_utxo: other_blinded_utxo.clone(),
+        donation: true,
+        min_confirmations: 1,
+        transport_endpoints: vec![test_env().proxy_endpoint.clone()],
+        dry_run: false,
+        force: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendasset", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_policy_violation(res, &other_blinded_utxo).await;
+
+    send_asset(node1_addr, &asset_id, 10, allowed_blinded_utxo).await;
+
+    // an empty whitelist allows any destination
+    set_payment_policy(node1_addr, vec![]).await;
+    keysend(node1_addr, &node2_pubkey, &asset_id, 10).await;
+}
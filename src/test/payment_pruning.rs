@@ -0,0 +1,82 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payment_pruning/";
+const NODE1_PEER_PORT: u16 = 10401;
+const NODE2_PEER_PORT: u16 = 10402;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payment_pruning() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice.clone(), 600, 0).await;
+    let payment_hash = decode_ln_invoice(node1_addr, &invoice).await.payment_hash;
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 50, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 500, 100).await;
+
+    let payments_num = list_payments(node2_addr).await.len();
+    assert_eq!(delete_payment(node2_addr, &payment_hash).await, 1);
+    assert_eq!(list_payments(node2_addr).await.len(), payments_num - 1);
+    assert!(!list_payments(node2_addr)
+        .await
+        .iter()
+        .any(|p| p.payment_hash == payment_hash));
+
+    // check UnknownPayment error
+    let payload = DeletePaymentRequest {
+        payment_hash: payment_hash.clone(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/deletepayment", node2_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Unknown payment");
+
+    // pending payments can't be pruned
+    let payload = PrunePaymentsRequest {
+        older_than_ts: 0,
+        statuses: vec![InvoiceStatus::Pending],
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/prunepayments", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Cannot delete a pending payment");
+
+    // payments newer than the threshold are kept
+    assert_eq!(
+        prune_payments(node1_addr, 0, vec![InvoiceStatus::Succeeded]).await,
+        0
+    );
+    let now = OffsetDateTime::now_utc().unix_timestamp() as u64;
+    assert_eq!(
+        prune_payments(node1_addr, now + 1, vec![InvoiceStatus::Succeeded]).await,
+        2
+    );
+    assert!(list_payments(node1_addr)
+        .await
+        .iter()
+        .all(|p| p.status != HTLCStatus::Succeeded));
+}
@@ -0,0 +1,145 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payment_quotes/";
+const NODE1_PEER_PORT: u16 = 10323;
+const NODE2_PEER_PORT: u16 = 10324;
+const NODE3_PEER_PORT: u16 = 10325;
+
+fn keysend_quote(
+    dest_pubkey: &str,
+    amt_msat: u64,
+    asset_id: &str,
+    asset_amount: u64,
+) -> QuotePaymentRequest {
+    QuotePaymentRequest {
+        invoice: None,
+        dest_pubkey: Some(dest_pubkey.to_string()),
+        amt_msat: Some(MilliSats(amt_msat)),
+        asset_id: Some(asset_id.to_string()),
+        asset_amount: Some(AssetUnits(asset_amount)),
+    }
+}
+
+fn invoice_quote(invoice: &str) -> QuotePaymentRequest {
+    QuotePaymentRequest {
+        invoice: Some(invoice.to_string()),
+        dest_pubkey: None,
+        amt_msat: None,
+        asset_id: None,
+        asset_amount: None,
+    }
+}
+
+async fn quote_payment_raw(
+    node_address: SocketAddr,
+    payload: &QuotePaymentRequest,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/quotepayment", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn quote_payment(
+    node_address: SocketAddr,
+    payload: &QuotePaymentRequest,
+) -> QuotePaymentResponse {
+    _check_response_is_ok(quote_payment_raw(node_address, payload).await)
+        .await
+        .json::<QuotePaymentResponse>()
+        .await
+        .unwrap()
+}
+
+async fn check_infeasible(
+    node_address: SocketAddr,
+    payload: &QuotePaymentRequest,
+    expected: PaymentFeasibility,
+) {
+    let quote = quote_payment(node_address, payload).await;
+    assert_eq!(quote.feasibility, expected);
+    assert!(quote.best_fee_msat.is_none() && quote.cltv_expiry_delta.is_none());
+    assert_eq!(quote.candidate_routes, 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payment_quotes() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // a payment to the peer goes through the channel with it, without fees
+    let quote = quote_payment(
+        node1_addr,
+        &keysend_quote(&node2_pubkey, 3000000, &asset_id, 100),
+    )
+    .await;
+    assert_eq!(quote.feasibility, PaymentFeasibility::Ok);
+    assert_eq!(quote.best_fee_msat, Some(0));
+    assert_eq!(quote.p90_fee_msat, Some(0));
+    assert!(quote.cltv_expiry_delta.unwrap() >= 40);
+    assert_eq!(quote.candidate_routes, 1);
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let quote = quote_payment(node1_addr, &invoice_quote(&invoice)).await;
+    assert_eq!(quote.feasibility, PaymentFeasibility::Ok);
+    assert_eq!(quote.best_fee_msat, Some(0));
+    assert!(quote.cltv_expiry_delta.is_some());
+
+    // the verdicts tell why a payment can't be sent
+    check_infeasible(
+        node1_addr,
+        &keysend_quote(&node2_pubkey, 3000000, &asset_id, 700),
+        PaymentFeasibility::InsufficientLiquidity,
+    )
+    .await;
+    check_infeasible(
+        node1_addr,
+        &keysend_quote(&node2_pubkey, 1000, &asset_id, 100),
+        PaymentFeasibility::AmountBelowMinimum,
+    )
+    .await;
+    check_infeasible(
+        node1_addr,
+        &keysend_quote(&node3_pubkey, 3000000, &asset_id, 100),
+        PaymentFeasibility::NoRoute,
+    )
+    .await;
+    check_infeasible(
+        node2_addr,
+        &keysend_quote(&node3_pubkey, 3000000, &asset_id, 100),
+        PaymentFeasibility::InsufficientLiquidity,
+    )
+    .await;
+
+    // quoting leaves no trace
+    assert!(list_payments(node1_addr).await.is_empty());
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
+
+    // the request needs exactly one of an invoice and a destination
+    let mut payload = invoice_quote(&invoice);
+    payload.dest_pubkey = Some(node2_pubkey.clone());
+    let res = quote_payment_raw(node1_addr, &payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let mut payload = keysend_quote(&node2_pubkey, 3000000, &asset_id, 100);
+    payload.asset_amount = None;
+    let res = quote_payment_raw(node1_addr, &payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,189 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payment_requests/";
+const NODE1_PEER_PORT: u16 = 10317;
+
+/// The node has no channels, so the invoices are created regardless of the inbound liquidity
+async fn colored_ln_invoice(
+    node_address: SocketAddr,
+    asset_id: &str,
+    unified: bool,
+) -> LNInvoiceResponse {
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(MilliSats(3000000)),
+        expiry_sec: 900,
+        asset_id: Some(asset_id.to_string()),
+        asset_amount: Some(AssetUnits(100)),
+        label: None,
+        allow_insufficient_inbound: true,
+        require_payer_pubkey: None,
+        require_payer_note: false,
+        min_final_cltv_expiry_delta: None,
+        unified,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+}
+
+async fn rgb_invoice_unified(node_address: SocketAddr, asset_id: &str) -> RgbInvoiceResponse {
+    let payload = RgbInvoiceRequest {
+        min_confirmations: None,
+        asset_id: Some(asset_id.to_string()),
+        label: None,
+        expiry_sec: None,
+        unified: true,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/rgbinvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<RgbInvoiceResponse>()
+        .await
+        .unwrap()
+}
+
+async fn decode_payment_request_raw(
+    node_address: SocketAddr,
+    payment_request: &str,
+) -> reqwest::Response {
+    let payload = DecodePaymentRequestRequest {
+        payment_request: payment_request.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/decodepaymentrequest", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn decode_payment_request(
+    node_address: SocketAddr,
+    payment_request: &str,
+) -> DecodePaymentRequestResponse {
+    _check_response_is_ok(decode_payment_request_raw(node_address, payment_request).await)
+        .await
+        .json::<DecodePaymentRequestResponse>()
+        .await
+        .unwrap()
+}
+
+async fn check_payment_request_error(node_address: SocketAddr, payment_request: &str) {
+    let res = decode_payment_request_raw(node_address, payment_request).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert!(
+        response.error.starts_with("Invalid payment request: "),
+        "{}",
+        response.error
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payment_requests() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    // a BOLT11 invoice decodes as such
+    let LNInvoiceResponse { invoice, unified } =
+        colored_ln_invoice(node1_addr, &asset_id, false).await;
+    assert!(unified.is_none());
+    let expected = decode_ln_invoice(node1_addr, &invoice).await;
+    let DecodePaymentRequestResponse::Bolt11(decoded) =
+        decode_payment_request(node1_addr, &invoice).await
+    else {
+        panic!("BOLT11 invoice not recognized")
+    };
+    assert_eq!(decoded.payment_hash, expected.payment_hash);
+    assert_eq!(decoded.asset_id, Some(asset_id.clone()));
+    assert_eq!(decoded.asset_amount, Some(100));
+
+    // an RGB invoice decodes as such, and as part of its unified URI
+    let rgb = rgb_invoice_unified(node1_addr, &asset_id).await;
+    let uri = rgb.unified.unwrap();
+    assert!(uri.starts_with("bitcoin:?rgb="), "{uri}");
+    let DecodePaymentRequestResponse::RgbInvoice(decoded) =
+        decode_payment_request(node1_addr, &rgb.invoice).await
+    else {
+        panic!("RGB invoice not recognized")
+    };
+    assert_eq!(decoded.recipient_id, rgb.recipient_id);
+    let DecodePaymentRequestResponse::Unified {
+        lightning_invoice,
+        lightning,
+        rgb_invoice,
+        rgb: decoded,
+    } = decode_payment_request(node1_addr, &uri).await
+    else {
+        panic!("unified URI not recognized")
+    };
+    assert!(lightning_invoice.is_none() && lightning.is_none());
+    assert_eq!(rgb_invoice, Some(rgb.invoice.clone()));
+    assert_eq!(decoded.unwrap().recipient_id, rgb.recipient_id);
+
+    // the unified URI of a colored BOLT11 invoice also carries an RGB invoice for the asset
+    let LNInvoiceResponse { invoice, unified } =
+        colored_ln_invoice(node1_addr, &asset_id, true).await;
+    let uri = unified.unwrap();
+    let DecodePaymentRequestResponse::Unified {
+        lightning_invoice,
+        lightning,
+        rgb_invoice,
+        rgb,
+    } = decode_payment_request(node1_addr, &uri).await
+    else {
+        panic!("unified URI not recognized")
+    };
+    assert_eq!(lightning_invoice, Some(invoice.clone()));
+    let lightning = lightning.unwrap();
+    assert_eq!(
+        lightning.payment_hash,
+        decode_ln_invoice(node1_addr, &invoice).await.payment_hash
+    );
+    assert_eq!(lightning.asset_amount, Some(100));
+    let rgb = rgb.unwrap();
+    assert_eq!(rgb.asset_id, Some(asset_id.clone()));
+    assert_eq!(
+        rgb.recipient_id,
+        decode_rgb_invoice(node1_addr, &rgb_invoice.unwrap())
+            .await
+            .recipient_id
+    );
+    // the scheme and the parameter names are case-insensitive
+    let uppercase = uri
+        .replacen("bitcoin:", "BITCOIN:", 1)
+        .replacen("lightning=", "LIGHTNING=", 1);
+    assert!(matches!(
+        decode_payment_request(node1_addr, &uppercase).await,
+        DecodePaymentRequestResponse::Unified { .. }
+    ));
+
+    // anything else is refused
+    check_payment_request_error(node1_addr, "lnbcrt1invalid").await;
+    check_payment_request_error(node1_addr, "bitcoin:?label=coffee").await;
+    check_payment_request_error(node1_addr, &format!("{uri}&req-amount=1")).await;
+    check_payment_request_error(node1_addr, &format!("{uri}&lightning={invoice}")).await;
+    let onchain =
+        format!("bitcoin:bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080?lightning={invoice}");
+    check_payment_request_error(node1_addr, &onchain).await;
+}
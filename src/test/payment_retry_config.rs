@@ -0,0 +1,131 @@
+use crate::ldk::{PaymentFaults, HTLC_EXPIRY_WARNING_BLOCKS};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payment_retry_config/";
+const NODE1_PEER_PORT: u16 = 10405;
+const NODE2_PEER_PORT: u16 = 10406;
+const NODE3_PEER_PORT: u16 = 10420;
+const NODE4_PEER_PORT: u16 = 10421;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payment_retry_config() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // quick-fail configuration
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let payment = send_payment_with_retry_config(
+        node1_addr,
+        invoice,
+        None,
+        Some(0),
+        true,
+        HTLCStatus::Succeeded,
+    )
+    .await;
+    assert!(payment.failure_reason.is_none());
+
+    // long-retry configuration
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node1_addr, &asset_id, 50, 900).await;
+    let payment = send_payment_with_retry_config(
+        node2_addr,
+        invoice,
+        Some(120),
+        None,
+        false,
+        HTLCStatus::Succeeded,
+    )
+    .await;
+    assert!(payment.failure_reason.is_none());
+
+    assert_eq!(
+        asset_balance_offchain_outbound(node1_addr, &asset_id).await,
+        550
+    );
+    assert_eq!(
+        asset_balance_offchain_outbound(node2_addr, &asset_id).await,
+        50
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payment_retry_config_timeout_with_max_retries() {
+    initialize();
+
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node3_addr, _) = start_node(test_dir_node3, NODE3_PEER_PORT, false).await;
+    let payment_faults = Arc::new(PaymentFaults::default());
+    let (node4_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node4"),
+            ldk_peer_listening_port: NODE4_PEER_PORT,
+            payment_faults: Arc::clone(&payment_faults),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node3_addr).await;
+    fund_and_create_utxos(node4_addr).await;
+
+    let asset_id = issue_asset(node3_addr).await;
+
+    let node4_pubkey = node_info(node4_addr).await.pubkey;
+    open_channel(node3_addr, &node4_pubkey, NODE4_PEER_PORT, 600, &asset_id).await;
+
+    // node4 doesn't settle the payment, leaving its HTLC in-flight past the time budget
+    payment_faults
+        .hold_claimable_payments
+        .store(true, Ordering::Release);
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node4_addr, &asset_id, 100, 900).await;
+    let payment_hash =
+        send_payment_raw_with_retry_config(node3_addr, invoice, Some(5), Some(3), false)
+            .await
+            .payment_hash;
+    let t_0 = OffsetDateTime::now_utc();
+    let htlc = loop {
+        if let Some(htlc) = list_htlcs(node4_addr)
+            .await
+            .into_iter()
+            .find(|h| h.payment_hash == payment_hash)
+        {
+            break htlc;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("HTLC not pending")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    tokio::time::sleep(std::time::Duration::from_secs(6)).await;
+
+    // the retries left by max_retries don't outlive timeout_sec: the payment has been abandoned,
+    // so it fails once its HTLC gets failed back instead of being retried
+    mine_n_blocks(
+        false,
+        (htlc.blocks_to_force_close - HTLC_EXPIRY_WARNING_BLOCKS + 1) as u16,
+    );
+    let payment = wait_for_ln_payment(node3_addr, &payment_hash, HTLCStatus::Failed).await;
+    assert_eq!(payment.failure_reason, Some(FailureReason::UserAbandoned));
+    assert_eq!(
+        asset_balance_offchain_outbound(node3_addr, &asset_id).await,
+        600
+    );
+}
@@ -0,0 +1,91 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payment_stats/";
+const NODE1_PEER_PORT: u16 = 10191;
+const NODE2_PEER_PORT: u16 = 10192;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payment_stats() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // no payments yet
+    let node1_stats = stats(node1_addr, StatsPeriod::Day).await;
+    assert_eq!(node1_stats.sent.count, 0);
+    assert!(node1_stats.sent.avg_settle_latency_secs.is_none());
+    assert!(node1_stats.outbound_success_rate.is_none());
+
+    keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 50, 900).await;
+    send_payment(node1_addr, invoice).await;
+
+    // an invoice larger than the channel fails, paying it again is the same payment
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node2_addr))
+        .json(&LNInvoiceRequest {
+            amt_msat: Some(MilliSats(1_000_000_000)),
+            expiry_sec: 900,
+            asset_id: None,
+            asset_amount: None,
+            label: None,
+            allow_insufficient_inbound: true,
+            require_payer_pubkey: None,
+            require_payer_note: false,
+            min_final_cltv_expiry_delta: None,
+            unified: false,
+        })
+        .send()
+        .await
+        .unwrap();
+    let LNInvoiceResponse { invoice, .. } = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap();
+    send_payment_with_status(node1_addr, invoice.clone(), HTLCStatus::Failed).await;
+    send_payment_with_status(node1_addr, invoice, HTLCStatus::Failed).await;
+
+    for period in [StatsPeriod::Day, StatsPeriod::Week, StatsPeriod::Month] {
+        let node1_stats = stats(node1_addr, period).await;
+        assert_eq!(node1_stats.sent.count, 2);
+        assert!(node1_stats.sent.amt_msat > 3000000);
+        assert!(node1_stats.sent.avg_settle_latency_secs.is_some());
+        assert_eq!(node1_stats.sent.assets.len(), 1);
+        assert_eq!(node1_stats.sent.assets[0].asset_id, asset_id);
+        assert_eq!(node1_stats.sent.assets[0].count, 2);
+        assert_eq!(node1_stats.sent.assets[0].amount, 150);
+        assert_eq!(node1_stats.received.count, 0);
+        assert_eq!(node1_stats.outbound_succeeded, 2);
+        assert_eq!(node1_stats.outbound_failed, 1);
+        assert_eq!(node1_stats.outbound_success_rate, Some(2.0 / 3.0));
+    }
+
+    let node2_stats = stats(node2_addr, StatsPeriod::Day).await;
+    assert_eq!(node2_stats.received.count, 2);
+    assert!(node2_stats.received.avg_settle_latency_secs.is_some());
+    assert_eq!(node2_stats.received.assets.len(), 1);
+    assert_eq!(node2_stats.received.assets[0].amount, 150);
+    assert_eq!(node2_stats.sent.count, 0);
+    assert!(node2_stats.outbound_success_rate.is_none());
+
+    // only day, week and month periods are supported
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/stats?period=year", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+}
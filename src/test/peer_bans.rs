@@ -0,0 +1,145 @@
+use super::*;
+use crate::routes::{BanPeerRequest, UnbanPeerRequest};
+
+const TEST_DIR_BASE: &str = "tmp/peer_bans/";
+const NODE1_PEER_PORT: u16 = 10201;
+const NODE2_PEER_PORT: u16 = 10202;
+
+async fn ban_peer(node_address: SocketAddr, peer_pubkey: &str, force: bool) -> reqwest::Response {
+    let payload = BanPeerRequest {
+        peer_pubkey: peer_pubkey.to_string(),
+        duration_secs: None,
+        force,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/banpeer", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn unban_peer(node_address: SocketAddr, peer_pubkey: &str) -> reqwest::Response {
+    let payload = UnbanPeerRequest {
+        peer_pubkey: peer_pubkey.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/unbanpeer", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn check_forbidden(res: reqwest::Response, error: &str) {
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, error);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn peer_bans() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1,
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            peer_rate_limits: PeerRateLimits {
+                max_msgs_per_sec: 2,
+                max_gossip_bytes_per_min: 4_000_000,
+                ban_duration_secs: 3600,
+            },
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node1_peer_addr = format!("127.0.0.1:{NODE1_PEER_PORT}");
+
+    // a peer flooding the node with messages gets disconnected and banned
+    connect_peer(node2_addr, &node1_pubkey, &node1_peer_addr).await;
+    for _ in 0..10 {
+        let _ = send_custom_message(node2_addr, &node1_pubkey, 42001, "00").await;
+    }
+    let t_0 = OffsetDateTime::now_utc();
+    let ban = loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        if let Some(ban) = list_bans(node1_addr)
+            .await
+            .into_iter()
+            .find(|b| b.peer_pubkey == node2_pubkey)
+        {
+            break ban;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("flooding peer has not been banned")
+        }
+    };
+    assert_eq!(ban.reason, "sent more than 2 messages per second");
+    assert_eq!(ban.address, Some(s!("127.0.0.1")));
+    assert_eq!(ban.expires_at, ban.banned_at + 3600);
+    assert!(!list_peers(node1_addr)
+        .await
+        .iter()
+        .any(|p| p.pubkey == node2_pubkey));
+
+    // connections from the banned peer are dropped and the node doesn't connect to it
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/connectpeer", node2_addr))
+        .json(&ConnectPeerRequest {
+            peer_pubkey_and_addr: format!("{node1_pubkey}@{node1_peer_addr}"),
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/connectpeer", node1_addr))
+        .json(&ConnectPeerRequest {
+            peer_pubkey_and_addr: format!("{node2_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        })
+        .send()
+        .await
+        .unwrap();
+    check_forbidden(
+        res,
+        &format!("Peer {node2_pubkey} is banned (hint: call unbanpeer)"),
+    )
+    .await;
+
+    // lifting the ban lets the peer connect again
+    _check_response_is_ok(unban_peer(node1_addr, &node2_pubkey).await).await;
+    assert!(list_bans(node1_addr).await.is_empty());
+    check_forbidden(
+        unban_peer(node1_addr, &node2_pubkey).await,
+        "Peer is not banned",
+    )
+    .await;
+    connect_peer(node2_addr, &node1_pubkey, &node1_peer_addr).await;
+
+    // peers with channels are only banned when forced
+    fund_and_create_utxos(node1_addr).await;
+    let asset_id = issue_asset(node1_addr).await;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    check_forbidden(
+        ban_peer(node1_addr, &node2_pubkey, false).await,
+        &format!(
+            "Node has open channels with peer {node2_pubkey} (hint: set force to ban it anyway)"
+        ),
+    )
+    .await;
+    assert!(list_bans(node1_addr).await.is_empty());
+    _check_response_is_ok(ban_peer(node1_addr, &node2_pubkey, true).await).await;
+    let bans = list_bans(node1_addr).await;
+    assert_eq!(bans.len(), 1);
+    assert_eq!(bans[0].peer_pubkey, node2_pubkey);
+    assert_eq!(bans[0].reason, "banned by the user");
+}
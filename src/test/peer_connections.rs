@@ -0,0 +1,105 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/peer_connections/";
+const NODE1_PEER_PORT: u16 = 10298;
+const NODE2_PEER_PORT: u16 = 10299;
+
+async fn peer_events(node_address: SocketAddr, peer_pubkey: &str) -> Vec<&'static str> {
+    events(node_address)
+        .await
+        .into_iter()
+        .filter_map(|e| match e.event {
+            NodeEvent::PeerConnected { peer_pubkey: p } if p == peer_pubkey => Some("connected"),
+            NodeEvent::PeerDisconnected { peer_pubkey: p } if p == peer_pubkey => {
+                Some("disconnected")
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn peer_connections() {
+    initialize();
+
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node1"),
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            peer_offline_threshold_secs: 0,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node2_addr, node2_password) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    let peer = list_peers(node1_addr)
+        .await
+        .into_iter()
+        .find(|p| p.pubkey == node2_pubkey)
+        .unwrap();
+    assert!(peer.last_seen.is_none());
+    assert_eq!(peer.disconnect_count, 0);
+    assert_eq!(
+        peer_events(node1_addr, &node2_pubkey).await,
+        vec!["connected"]
+    );
+    let channels = list_channels(node1_addr).await;
+    assert!(channels[0].peer_offline_since.is_none());
+
+    // the channel gets flagged once the peer goes offline
+    lock(node2_addr).await;
+    let t_0 = OffsetDateTime::now_utc();
+    let offline_since = loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        let channel = channels
+            .iter()
+            .find(|c| c.channel_id == channel.channel_id)
+            .unwrap();
+        if let Some(offline_since) = channel.peer_offline_since {
+            break offline_since;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("channel has not been flagged as offline")
+        }
+    };
+    assert!(offline_since >= peer.connected_since);
+    assert_eq!(
+        peer_events(node1_addr, &node2_pubkey).await,
+        vec!["connected", "disconnected"]
+    );
+
+    // the history of the peer is reported once it reconnects
+    unlock(node2_addr, node2_password).await;
+    let t_0 = OffsetDateTime::now_utc();
+    let peer = loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        if let Some(peer) = list_peers(node1_addr)
+            .await
+            .into_iter()
+            .find(|p| p.pubkey == node2_pubkey)
+        {
+            break peer;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("peer has not reconnected")
+        }
+    };
+    assert_eq!(peer.last_seen, Some(offline_since));
+    assert!(peer.connected_since >= offline_since);
+    assert_eq!(peer.disconnect_count, 1);
+    let channels = list_channels(node1_addr).await;
+    assert!(channels[0].peer_offline_since.is_none());
+}
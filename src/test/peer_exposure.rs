@@ -0,0 +1,131 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/peer_exposure/";
+const NODE1_PEER_PORT: u16 = 10332;
+const NODE2_PEER_PORT: u16 = 10333;
+
+async fn set_peer_exposure_limits(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    max_msat: Option<u64>,
+    max_asset_amounts: Vec<AssetSpendingLimit>,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/setpeerexposurelimits", node_address))
+        .json(&SetPeerExposureLimitsRequest {
+            peer_pubkey: peer_pubkey.to_string(),
+            max_msat: max_msat.map(MilliSats),
+            max_asset_amounts,
+        })
+        .send()
+        .await
+        .unwrap()
+}
+
+fn asset_limit(asset_id: &str, max_amount: u64) -> Vec<AssetSpendingLimit> {
+    vec![AssetSpendingLimit {
+        asset_id: asset_id.to_string(),
+        max_amount,
+    }]
+}
+
+async fn peer(node_address: SocketAddr, peer_pubkey: &str) -> Peer {
+    list_peers(node_address)
+        .await
+        .into_iter()
+        .find(|p| p.pubkey == peer_pubkey)
+        .unwrap()
+}
+
+async fn check_keysend_refused(
+    node_address: SocketAddr,
+    dest_pubkey: &str,
+    asset_id: &str,
+    asset_amount: u64,
+) {
+    let res = keysend_with_msat(node_address, dest_pubkey, asset_id, asset_amount, 3000000).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        format!(
+            "HTLC exposure limit reached: the payment would exceed the limits of {dest_pubkey}"
+        )
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn peer_exposure() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // no limit and nothing in flight by default
+    let exposure = peer(node1_addr, &node2_pubkey).await.exposure;
+    assert_eq!((exposure.msat, exposure.max_msat), (0, None));
+    assert!(exposure.assets.is_empty());
+    assert_eq!(exposure.violations, 0);
+
+    // invalid limits are refused
+    let res = set_peer_exposure_limits(node1_addr, "invalid", Some(0), vec![]).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let res =
+        set_peer_exposure_limits(node1_addr, &node2_pubkey, None, asset_limit("rgb:x", 1)).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    // a zero limit quarantines the peer
+    let res = set_peer_exposure_limits(node1_addr, &node2_pubkey, Some(0), vec![]).await;
+    _check_response_is_ok(res).await;
+    check_keysend_refused(node1_addr, &node2_pubkey, &asset_id, 10).await;
+    let exposure = peer(node1_addr, &node2_pubkey).await.exposure;
+    assert_eq!((exposure.msat, exposure.max_msat), (0, Some(0)));
+    assert_eq!(exposure.violations, 1);
+    let counters = counters(node1_addr).await;
+    assert_eq!(
+        counters.get(&format!("htlc_exposure_violation:{node2_pubkey}")),
+        Some(&1)
+    );
+
+    // asset limits only refuse the HTLCs going over them
+    let res =
+        set_peer_exposure_limits(node1_addr, &node2_pubkey, None, asset_limit(&asset_id, 20)).await;
+    _check_response_is_ok(res).await;
+    check_keysend_refused(node1_addr, &node2_pubkey, &asset_id, 30).await;
+    keysend(node1_addr, &node2_pubkey, &asset_id, 10).await;
+    let exposure = peer(node1_addr, &node2_pubkey).await.exposure;
+    assert_eq!(exposure.max_msat, None);
+    assert_eq!(exposure.assets.len(), 1);
+    let asset_exposure = &exposure.assets[0];
+    assert_eq!(asset_exposure.asset_id, asset_id);
+    assert_eq!(
+        (asset_exposure.amount, asset_exposure.max_amount),
+        (0, Some(20))
+    );
+    assert_eq!(exposure.violations, 2);
+
+    // payments received from a peer over its limits are refused
+    let res = set_peer_exposure_limits(node1_addr, &node2_pubkey, None, vec![]).await;
+    _check_response_is_ok(res).await;
+    let res =
+        set_peer_exposure_limits(node2_addr, &node1_pubkey, None, asset_limit(&asset_id, 50)).await;
+    _check_response_is_ok(res).await;
+    let payment = keysend_raw(node1_addr, &node2_pubkey, &asset_id, 100).await;
+    wait_for_ln_payment(node1_addr, &payment.payment_hash, HTLCStatus::Failed).await;
+    assert_eq!(peer(node2_addr, &node1_pubkey).await.exposure.violations, 1);
+    keysend(node1_addr, &node2_pubkey, &asset_id, 50).await;
+    let exposure = peer(node1_addr, &node2_pubkey).await.exposure;
+    assert_eq!(exposure.max_msat, None);
+    assert!(exposure.assets.is_empty());
+}
@@ -0,0 +1,88 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/peer_listening/";
+const NODE1_PEER_PORT: u16 = 10300;
+const NODE2_PEER_PORT: u16 = 10301;
+
+async fn connect_peer_raw(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    peer_addr: &str,
+) -> reqwest::Response {
+    let payload = ConnectPeerRequest {
+        peer_pubkey_and_addr: format!("{peer_pubkey}@{peer_addr}"),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/connectpeer", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn peer_listening() {
+    initialize();
+
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node1"),
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            ldk_peer_listening_host: None,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node2_addr, node2_password) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: format!("{TEST_DIR_BASE}node2"),
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            ldk_peer_listening_host: Some(IpAddr::from([127, 0, 0, 1])),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // a node not listening can't be reached
+    let res = connect_peer_raw(
+        node2_addr,
+        &node1_pubkey,
+        &format!("127.0.0.1:{NODE1_PEER_PORT}"),
+    )
+    .await;
+    assert_eq!(res.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Failed to connect to peer");
+
+    // but it can still connect to other nodes, listening on the configured interface
+    connect_peer(
+        node1_addr,
+        &node2_pubkey,
+        &format!("127.0.0.1:{NODE2_PEER_PORT}"),
+    )
+    .await;
+    assert!(list_peers(node2_addr)
+        .await
+        .iter()
+        .any(|p| p.pubkey == node1_pubkey));
+
+    // the listener releases its port once the node gets locked
+    lock(node2_addr).await;
+    unlock(node2_addr, node2_password).await;
+    connect_peer(
+        node1_addr,
+        &node2_pubkey,
+        &format!("127.0.0.1:{NODE2_PEER_PORT}"),
+    )
+    .await;
+    assert!(list_peers(node2_addr)
+        .await
+        .iter()
+        .any(|p| p.pubkey == node1_pubkey));
+}
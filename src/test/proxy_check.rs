@@ -0,0 +1,91 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/proxy_check/";
+const NODE1_PEER_PORT: u16 = 10071;
+const UNREACHABLE_PROXY_PORT: u16 = 10072;
+const OLD_PROXY_PORT: u16 = 10073;
+
+async fn check_invalid_endpoint(node_address: SocketAddr, payload: &SendAssetRequest, error: &str) {
+    let res = send_asset_raw(node_address, payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        format!("Invalid transport endpoints: {error}")
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn proxy_check() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    // the default proxy gets checked at unlock
+    let info = node_info(node1_addr).await;
+    assert!(info.proxy_reachable);
+    assert!(info.proxy_protocol_compatible);
+    assert!(info.proxy_version.is_some());
+    let ready = ready(node1_addr).await;
+    assert!(ready.proxy_reachable);
+    assert!(ready.proxy_protocol_compatible);
+    assert_eq!(ready.proxy_version, info.proxy_version);
+
+    fund_and_create_utxos(node1_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+    let recipient_id = rgb_invoice(node1_addr, None).await.recipient_id;
+
+    // a proxy speaking an older protocol
+    let old_proxy = axum::Router::new().route(
+        "/json-rpc",
+        axum::routing::post(|| async {
+            axum::Json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": "1",
+                "result": {"protocol_version": "0.1", "version": "0.1.0", "uptime": 1},
+            }))
+        }),
+    );
+    tokio::spawn(
+        axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], OLD_PROXY_PORT)))
+            .serve(old_proxy.into_make_service()),
+    );
+
+    let mut payload = SendAssetRequest {
+        asset_id: asset_id.clone(),
+        amount: AssetUnits(100),
+        blinded_utxo: recipient_id,
+        donation: true,
+        min_confirmations: 1,
+        transport_endpoints: vec![format!("rpc://127.0.0.1:{UNREACHABLE_PROXY_PORT}/json-rpc")],
+        dry_run: true,
+        force: false,
+    };
+    check_invalid_endpoint(
+        node1_addr,
+        &payload,
+        &format!("proxy http://127.0.0.1:{UNREACHABLE_PROXY_PORT}/json-rpc is unreachable"),
+    )
+    .await;
+    payload.transport_endpoints = vec![
+        test_env().proxy_endpoint.clone(),
+        format!("rpc://127.0.0.1:{OLD_PROXY_PORT}/json-rpc"),
+    ];
+    check_invalid_endpoint(
+        node1_addr,
+        &payload,
+        &format!(
+            "proxy http://127.0.0.1:{OLD_PROXY_PORT}/json-rpc speaks protocol version 0.1, 0.2 is required"
+        ),
+    )
+    .await;
+
+    // compatible proxies are accepted
+    payload.transport_endpoints = vec![test_env().proxy_endpoint.clone()];
+    payload.dry_run = false;
+    let res = send_asset_raw(node1_addr, &payload).await;
+    _check_response_is_ok(res).await;
+}
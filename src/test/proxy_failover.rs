@@ -0,0 +1,72 @@
+use crate::proxy::proxy_url;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/proxy_failover/";
+const NODE1_PEER_PORT: u16 = 10081;
+const NODE2_PEER_PORT: u16 = 10082;
+const UNREACHABLE_PROXY_PORT: u16 = 10083;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn proxy_failover() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let unreachable_endpoint = format!("rpc://127.0.0.1:{UNREACHABLE_PROXY_PORT}/json-rpc");
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1,
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            proxy_endpoints: vec![
+                unreachable_endpoint.clone(),
+                test_env().proxy_endpoint.clone(),
+            ],
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    // the node reports the first default proxy that works
+    let info = node_info(node1_addr).await;
+    assert!(info.proxy_reachable);
+    assert!(info.proxy_protocol_compatible);
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    // the funding consignment skips the unreachable proxy
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    let channel = list_channels(node1_addr)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel.channel_id)
+        .unwrap();
+    assert_eq!(
+        channel.consignment_endpoint.as_deref(),
+        Some(test_env().proxy_endpoint.as_str())
+    );
+    // the peer got the consignment from the same proxy
+    assert_eq!(
+        asset_balance(node2_addr, &asset_id).await.offchain_inbound,
+        600
+    );
+    assert_eq!(
+        asset_balance(node1_addr, &asset_id).await.offchain_outbound,
+        600
+    );
+
+    let counters = counters(node1_addr).await;
+    let unreachable_url = format!("http://127.0.0.1:{UNREACHABLE_PROXY_PORT}/json-rpc");
+    let proxy_url = proxy_url(&test_env().proxy_endpoint).unwrap();
+    assert!(counters[&format!("proxy_check_failed:{unreachable_url}")] > 0);
+    assert!(counters[&format!("proxy_check_succeeded:{proxy_url}")] > 0);
+    assert_eq!(counters[&format!("proxy_upload_succeeded:{proxy_url}")], 1);
+    assert!(!counters.contains_key(&format!("proxy_upload_failed:{unreachable_url}")));
+}
@@ -0,0 +1,100 @@
+use super::*;
+use crate::READ_ONLY_ROUTES;
+use regex::Regex;
+
+const TEST_DIR_BASE: &str = "tmp/read_only_token/";
+const NODE1_PEER_PORT: u16 = 9951;
+
+const API_TOKEN: &str = "admin-token";
+const READ_ONLY_API_TOKEN: &str = "read-only-token";
+
+/// Get the (method, path) of every API documented in the OpenAPI spec
+fn documented_routes() -> Vec<(String, String)> {
+    let path_re = Regex::new(r"^  (/\w+):$").unwrap();
+    let method_re = Regex::new(r"^    (get|post):$").unwrap();
+    let spec = std::fs::read_to_string("openapi.yaml").unwrap();
+    let mut routes = vec![];
+    let mut path = None;
+    for line in spec.lines() {
+        if let Some(caps) = path_re.captures(line) {
+            path = Some(caps[1].to_string());
+        } else if let Some(caps) = method_re.captures(line) {
+            routes.push((caps[1].to_string(), path.clone().unwrap()));
+        }
+    }
+    routes
+}
+
+async fn call(
+    node_address: SocketAddr,
+    method: &str,
+    path: &str,
+    token: Option<&str>,
+) -> reqwest::Response {
+    let url = format!("http://{}{}", node_address, path);
+    let client = reqwest::Client::new();
+    let request = match method {
+        "get" => client.get(url),
+        _ => client.post(url).json(&serde_json::json!({})),
+    };
+    let request = match token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    };
+    request.send().await.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn read_only_token() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    if Path::new(&test_dir_node1).is_dir() {
+        std::fs::remove_dir_all(&test_dir_node1).unwrap();
+    }
+    let node1_addr = start_daemon_with_args(LdkUserInfo {
+        storage_dir_path: test_dir_node1.clone(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        api_token: Some(s!(API_TOKEN)),
+        read_only_api_token: Some(s!(READ_ONLY_API_TOKEN)),
+        ..Default::default()
+    })
+    .await;
+
+    // missing or wrong token
+    for token in [None, Some("wrong-token")] {
+        let res = call(node1_addr, "get", "/nodeinfo", token).await;
+        assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let response: ErrorResponse = res.json().await.unwrap();
+        assert_eq!(response.error, "Missing or invalid API token");
+        assert_eq!(response.code, 401);
+    }
+
+    // the admin token is accepted everywhere
+    let res = call(node1_addr, "get", "/nodeinfo", Some(API_TOKEN)).await;
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Wallet has not been initialized (hint: call init)"
+    );
+
+    // the read-only token is refused on every route not declared as read-only
+    let routes = documented_routes();
+    assert!(READ_ONLY_ROUTES
+        .iter()
+        .all(|r| routes.iter().any(|(_, p)| p == r)));
+    for (method, path) in routes {
+        let res = call(node1_addr, &method, &path, Some(READ_ONLY_API_TOKEN)).await;
+        let status = res.status();
+        let text = res.text().await.unwrap();
+        let denied = status == reqwest::StatusCode::FORBIDDEN
+            && serde_json::from_str::<ErrorResponse>(&text)
+                .is_ok_and(|r| r.error == "The API token doesn't allow this operation");
+        assert_eq!(
+            denied,
+            !READ_ONLY_ROUTES.contains(&path.as_str()),
+            "unexpected response for {method} {path}: {status} {text}"
+        );
+    }
+}
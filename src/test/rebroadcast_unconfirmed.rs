@@ -0,0 +1,132 @@
+use super::*;
+use crate::routes::{SendBtcRequest, SendBtcResponse};
+
+const TEST_DIR_BASE: &str = "tmp/rebroadcast_unconfirmed/";
+const NODE1_PEER_PORT: u16 = 10181;
+
+async fn wait_for_watched_txs(
+    node_address: SocketAddr,
+    expected_txids: &[&str],
+) -> Vec<Transaction> {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let transactions = list_transactions(
+            node_address,
+            &ListTransactionsParams {
+                watched_only: Some(true),
+                ..Default::default()
+            },
+        )
+        .await;
+        let txids: Vec<&str> = transactions.iter().map(|t| t.txid.as_str()).collect();
+        if txids == expected_txids {
+            return transactions;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("watched transactions are not becoming the expected ones")
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn rebroadcast_unconfirmed() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+
+    // confirmed transactions are not watched
+    wait_for_watched_txs(node1_addr, &[]).await;
+
+    set_alerts(
+        node1_addr,
+        &SetAlertsRequest {
+            anchor_reserve_per_channel_sat: None,
+            min_fee_bump_utxos: None,
+            min_channel_asset_amount: None,
+            max_unconfirmed_tx_age_secs: Some(0),
+        },
+    )
+    .await;
+    assert_eq!(
+        alerts(node1_addr).await.max_unconfirmed_tx_age_secs,
+        Some(0)
+    );
+
+    stop_mining();
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/address", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    let address = _check_response_is_ok(res)
+        .await
+        .json::<AddressResponse>()
+        .await
+        .unwrap()
+        .address;
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendbtc", node1_addr))
+        .json(&SendBtcRequest {
+            amount: Sats(10_000),
+            address,
+            fee_rate: 5.0,
+        })
+        .send()
+        .await
+        .unwrap();
+    let txid = _check_response_is_ok(res)
+        .await
+        .json::<SendBtcResponse>()
+        .await
+        .unwrap()
+        .txid;
+
+    // the transaction stays in the mempool, so it's watched without being broadcast again
+    let watched = wait_for_watched_txs(node1_addr, &[&txid]).await;
+    assert_eq!(watched[0].rebroadcasts, Some(0));
+    assert!(watched[0].confirmation_time.is_none());
+
+    // it's reported as stuck once older than the configured age
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let alerts = alerts(node1_addr).await.alerts;
+        if let Some(alert) = alerts
+            .iter()
+            .find(|a| a.kind == AlertKind::UnconfirmedTransaction)
+        {
+            assert!(alert.message.contains(&txid));
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("unconfirmed transaction alert has not been raised")
+        }
+    }
+
+    // confirmed transactions stop being watched and the alert clears
+    mine(true);
+    wait_for_watched_txs(node1_addr, &[]).await;
+    let t_0 = OffsetDateTime::now_utc();
+    while alerts(node1_addr)
+        .await
+        .alerts
+        .iter()
+        .any(|a| a.kind == AlertKind::UnconfirmedTransaction)
+    {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("unconfirmed transaction alert has not been cleared")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    let transaction = list_transactions(node1_addr, &ListTransactionsParams::default())
+        .await
+        .into_iter()
+        .find(|t| t.txid == txid)
+        .unwrap();
+    assert!(transaction.rebroadcasts.is_none());
+}
@@ -0,0 +1,112 @@
+use super::*;
+use crate::routes::ListSweepsResponse;
+
+const TEST_DIR_BASE: &str = "tmp/recovery_mode/";
+const NODE1_PEER_PORT: u16 = 10251;
+const NODE2_PEER_PORT: u16 = 10252;
+
+async fn list_sweeps(node_address: SocketAddr) -> ListSweepsResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listsweeps", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListSweepsResponse>()
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn recovery_mode() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, node1_password) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    keysend(node1_addr, &node2_pubkey, &asset_id, 150).await;
+    keysend(node2_addr, &node1_pubkey, &asset_id, 50).await;
+    assert!(!node_info(node1_addr).await.recovery_mode);
+
+    // unlocking in recovery mode force-closes the channel right away
+    lock(node1_addr).await;
+    stop_mining();
+    let payload = UnlockRequest {
+        password: node1_password,
+        regenerate: false,
+        recovery_mode: true,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/unlock", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    assert!(node_info(node1_addr).await.recovery_mode);
+    assert!(list_channels(node1_addr).await.is_empty());
+
+    let t_0 = OffsetDateTime::now_utc();
+    let closed_channel = loop {
+        if let Some(closed_channel) = list_closed_channels(node1_addr)
+            .await
+            .into_iter()
+            .find(|c| c.channel_id == channel.channel_id)
+        {
+            break closed_channel;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel has not been recorded as closed")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert!(closed_channel.unclaimed_sat > 0);
+
+    // no new channel can be opened
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_addr: format!("{}@127.0.0.1:{}", node2_pubkey, NODE2_PEER_PORT),
+        capacity_sat: Sats(100_000),
+        push_msat: MilliSats(3_500_000),
+        asset_amount: AssetUnits(100),
+        asset_id: asset_id.clone(),
+        public: true,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        min_depth: None,
+        htlc_minimum_msat: None,
+        fee_rate_sat_vb: None,
+        target_conf_blocks: None,
+        dry_run: false,
+    };
+    let res = open_channel_raw(node1_addr, &payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Not allowed while the node is in recovery mode"
+    );
+
+    // once the timelock expires the funds, assets included, are swept to the wallet
+    mine_n_blocks(true, 144);
+    wait_for_balance(node1_addr, &asset_id, 900).await;
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+    let sweeps = list_sweeps(node1_addr).await;
+    assert_eq!(sweeps.unswept_outputs, 0);
+    assert!(sweeps
+        .sweeps
+        .iter()
+        .any(|s| s.asset_id.as_ref() == Some(&asset_id) && s.asset_amount == Some(500)));
+}
@@ -13,7 +13,6 @@ const NODE1_PEER_PORT: u16 = 9931;
 const NODE2_PEER_PORT: u16 = 9932;
 const NODE3_PEER_PORT: u16 = 9933;
 
-#[serial_test::serial]
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[traced_test]
 async fn refuse_high_fees() {
@@ -61,8 +60,41 @@ async fn refuse_high_fees() {
     .await;
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 100);
 
-    let LNInvoiceResponse { invoice } = ln_invoice(node3_addr, &asset_id, 50, 900).await;
-    let _ = send_payment_with_status(node1_addr, invoice, HTLCStatus::Failed).await;
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node3_addr, &asset_id, 50, 900).await;
+    let payment = send_payment_with_status(node1_addr, invoice, HTLCStatus::Failed).await;
+    assert_eq!(payment.failure_reason, Some(FailureReason::RouteNotFound));
+    assert!(payment.failing_hop.is_none());
+    assert!(events(node1_addr).await.iter().any(|e| matches!(
+        &e.event,
+        NodeEvent::PaymentFailed { payment_hash, failure_reason: FailureReason::RouteNotFound, .. }
+            if *payment_hash == payment.payment_hash
+    )));
+
+    // quick-fail configuration
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node3_addr, &asset_id, 50, 900).await;
+    let payment = send_payment_with_retry_config(
+        node1_addr,
+        invoice,
+        None,
+        Some(0),
+        false,
+        HTLCStatus::Failed,
+    )
+    .await;
+    assert!(payment.failure_reason.is_some());
+
+    // long-retry configuration, fails as soon as no route within the fee limit is found
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node3_addr, &asset_id, 50, 900).await;
+    let payment = send_payment_with_retry_config(
+        node1_addr,
+        invoice,
+        Some(60),
+        None,
+        true,
+        HTLCStatus::Failed,
+    )
+    .await;
+    assert!(payment.failure_reason.is_some());
 
     let file = File::open(
         PathBuf::from(test_dir_node1)
@@ -0,0 +1,58 @@
+use bitcoin::hashes::Hash;
+use lightning::ln::PaymentHash;
+use lightning::rgb_utils::{get_rgb_payment_info_path, parse_rgb_payment_info};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/reject_invalid_rgb_htlc/";
+const NODE1_PEER_PORT: u16 = 9971;
+const NODE2_PEER_PORT: u16 = 9972;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn reject_invalid_rgb_htlc() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2.clone(), NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    // craft the receiver state so the HTLC carries less than the invoice asks for
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let bolt11_invoice = Bolt11Invoice::from_str(&invoice).unwrap();
+    let payment_hash = PaymentHash(bolt11_invoice.payment_hash().into_inner());
+    let ldk_data_dir = Path::new(&test_dir_node2).join(".ldk");
+    let rgb_payment_info_path = get_rgb_payment_info_path(&payment_hash, &ldk_data_dir);
+    let mut rgb_payment_info = parse_rgb_payment_info(&rgb_payment_info_path);
+    rgb_payment_info.amount = 200;
+    std::fs::write(
+        &rgb_payment_info_path,
+        serde_json::to_string(&rgb_payment_info).unwrap(),
+    )
+    .unwrap();
+
+    let payment = send_payment_with_status(node1_addr, invoice, HTLCStatus::Failed).await;
+    assert_eq!(
+        payment.failure_reason,
+        Some(FailureReason::RgbIncorrectPaymentDetails)
+    );
+    assert!(events(node2_addr).await.iter().any(|e| matches!(
+        &e.event,
+        NodeEvent::RgbPaymentRejected { payment_hash, .. } if *payment_hash == payment.payment_hash
+    )));
+    assert!(list_htlcs(node2_addr).await.is_empty());
+
+    // channel balances are untouched and a valid payment goes through
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice, 600, 0).await;
+}
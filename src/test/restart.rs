@@ -5,7 +5,6 @@ const NODE1_PEER_PORT: u16 = 9921;
 const NODE2_PEER_PORT: u16 = 9922;
 const NODE3_PEER_PORT: u16 = 9923;
 
-#[serial_test::serial]
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[traced_test]
 async fn restart() {
@@ -84,7 +83,7 @@ async fn restart() {
     }
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
 
-    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
     let send_payment = send_payment(node1_addr, invoice).await;
 
     println!("5 - restart 1+2");
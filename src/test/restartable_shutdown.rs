@@ -0,0 +1,87 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/restartable_shutdown/";
+const NODE1_PEER_PORT: u16 = 10311;
+
+async fn shutdown_restartable(node_address: SocketAddr) {
+    let payload = ShutdownRequest {
+        mode: ShutdownMode::Restartable,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/shutdown", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn restartable_shutdown() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, password) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let pubkey = node_info(node1_addr).await.pubkey;
+
+    shutdown_restartable(node1_addr).await;
+
+    // the LDK listener stops while the API keeps answering
+    let ldk_socket = get_ldk_sockets(&[NODE1_PEER_PORT])[0];
+    let t_0 = OffsetDateTime::now_utc();
+    while TcpListener::bind(ldk_socket).is_err() {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("LDK socket not becoming available")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/health", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/nodeinfo", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Node is locked (hint: call unlock)");
+
+    // other routes are refused until the node gets unlocked
+    for route in ["listchannels", "listpeers"] {
+        let res = reqwest::Client::new()
+            .get(format!("http://{}/{route}", node1_addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        let response: ErrorResponse = res.json().await.unwrap();
+        assert_eq!(
+            response.error,
+            "The node has been stopped (hint: call unlock)"
+        );
+    }
+
+    unlock(node1_addr, password).await;
+    assert_eq!(node_info(node1_addr).await.pubkey, pubkey);
+    assert!(list_channels(node1_addr).await.is_empty());
+
+    // a malformed mode doesn't exit the process
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/shutdown", node1_addr))
+        .json(&serde_json::json!({ "mode": "restart" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/health", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+}
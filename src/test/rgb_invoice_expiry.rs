@@ -0,0 +1,154 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/rgb_invoice_expiry/";
+const NODE1_PEER_PORT: u16 = 10371;
+const NODE2_PEER_PORT: u16 = 10372;
+
+const EXPIRY_SEC: u32 = 10;
+
+async fn rgb_invoice_with_expiry(
+    node_address: SocketAddr,
+    asset_id: &str,
+    expiry_sec: u32,
+) -> RgbInvoiceResponse {
+    let payload = RgbInvoiceRequest {
+        min_confirmations: None,
+        asset_id: Some(asset_id.to_string()),
+        label: None,
+        expiry_sec: Some(expiry_sec),
+        unified: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/rgbinvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<RgbInvoiceResponse>()
+        .await
+        .unwrap()
+}
+
+async fn incoming_transfer(
+    node_address: SocketAddr,
+    asset_id: &str,
+    recipient_id: &str,
+) -> Transfer {
+    list_transfers(node_address, asset_id)
+        .await
+        .into_iter()
+        .find(|t| t.recipient_id.as_deref() == Some(recipient_id))
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn rgb_invoice_expiry() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+
+    // node2 has a single colorable UTXO, so all its invoices are bound to it
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/address", node2_addr))
+        .send()
+        .await
+        .unwrap();
+    let address = _check_response_is_ok(res)
+        .await
+        .json::<AddressResponse>()
+        .await
+        .unwrap()
+        .address;
+    fund_wallet(address);
+    mine(false);
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/createutxos", node2_addr))
+        .json(&CreateUtxosRequest {
+            up_to: false,
+            num: Some(1),
+        })
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    mine(false);
+
+    let asset_id = issue_asset(node1_addr).await;
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    send_asset(node1_addr, &asset_id, 100, recipient_id).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 100);
+    let colorable: Vec<Unspent> = list_unspents(node2_addr)
+        .await
+        .into_iter()
+        .filter(|u| u.utxo.colorable)
+        .collect();
+    assert_eq!(colorable.len(), 1);
+    let utxo = colorable[0].utxo.outpoint.clone();
+
+    // an invoice nobody pays fails once expired, releasing its UTXO
+    let RgbInvoiceResponse {
+        recipient_id,
+        expiration_timestamp,
+        ..
+    } = rgb_invoice_with_expiry(node2_addr, &asset_id, EXPIRY_SEC).await;
+    let transfer = incoming_transfer(node2_addr, &asset_id, &recipient_id).await;
+    assert!(matches!(
+        transfer.status,
+        TransferStatus::WaitingCounterparty
+    ));
+    assert_eq!(transfer.receive_utxo, Some(utxo.clone()));
+    refresh_transfers(node2_addr).await;
+    let transfer = incoming_transfer(node2_addr, &asset_id, &recipient_id).await;
+    assert!(matches!(
+        transfer.status,
+        TransferStatus::WaitingCounterparty
+    ));
+
+    let expiration_timestamp = expiration_timestamp.unwrap();
+    while OffsetDateTime::now_utc().unix_timestamp() <= expiration_timestamp {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    refresh_transfers(node2_addr).await;
+    let transfer = incoming_transfer(node2_addr, &asset_id, &recipient_id).await;
+    assert!(matches!(transfer.status, TransferStatus::Failed));
+    assert_eq!(
+        transfer.failure_reason.as_deref(),
+        Some("invoice expired before the transfer completed")
+    );
+    assert!(events(node2_addr).await.into_iter().any(|e| matches!(
+        e.event,
+        NodeEvent::IncomingTransferFailed {
+            asset_id: Some(ref a),
+            recipient_id: ref r,
+            txid: None,
+            ..
+        } if *a == asset_id && *r == recipient_id
+    )));
+
+    // the UTXO gets reused by the next invoice, which can be paid
+    let recipient_id = rgb_invoice(node2_addr, Some(asset_id.clone()))
+        .await
+        .recipient_id;
+    let transfer = incoming_transfer(node2_addr, &asset_id, &recipient_id).await;
+    assert_eq!(transfer.receive_utxo, Some(utxo));
+    send_asset(node1_addr, &asset_id, 50, recipient_id.clone()).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    let transfer = incoming_transfer(node2_addr, &asset_id, &recipient_id).await;
+    assert!(matches!(transfer.status, TransferStatus::Settled));
+    assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 150);
+}
@@ -0,0 +1,36 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/rgb_support/";
+const NODE1_PEER_PORT: u16 = 10391;
+const NODE2_PEER_PORT: u16 = 10392;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn rgb_support() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    connect_peer(
+        node1_addr,
+        &node2_pubkey,
+        &format!("127.0.0.1:{NODE2_PEER_PORT}"),
+    )
+    .await;
+
+    // both sides see the other advertising RGB channels support
+    let peers = list_peers(node1_addr).await;
+    assert!(peers
+        .iter()
+        .any(|p| p.pubkey == node2_pubkey && p.rgb_support));
+    let peers = list_peers(node2_addr).await;
+    assert!(peers
+        .iter()
+        .any(|p| p.pubkey == node1_pubkey && p.rgb_support));
+}
@@ -6,7 +6,6 @@ const TEST_DIR_BASE: &str = "tmp/send_receive/";
 const NODE1_PEER_PORT: u16 = 9811;
 const NODE2_PEER_PORT: u16 = 9812;
 
-#[serial_test::serial]
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[traced_test]
 async fn send_receive() {
@@ -20,10 +19,151 @@ async fn send_receive() {
     fund_and_create_utxos(node1_addr).await;
     fund_and_create_utxos(node2_addr).await;
 
+    // check min confirmations policy
+    let node2_info = node_info(node2_addr).await;
+    assert_eq!(node2_info.default_min_confirmations, 1);
+    assert_eq!(node2_info.min_allowed_confirmations, 1);
+    let payload = RgbInvoiceRequest {
+        min_confirmations: Some(0),
+        asset_id: None,
+        label: None,
+        expiry_sec: None,
+        unified: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/rgbinvoice", node2_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let text = res.text().await.unwrap();
+    let response: ErrorResponse = serde_json::from_str(&text).unwrap();
+    assert_eq!(
+        response.error,
+        "Invalid min confirmations: cannot be lower than 1"
+    );
+    assert_eq!(response.code, 400);
+
+    // check IssueAssetRequest validation errors
+    let ticker_charset_error = "Invalid ticker: must start with an uppercase letter and contain \
+        only uppercase letters (A-Z) and digits (0-9)";
+    for (ticker, name, precision, amounts, expected_error) in [
+        (
+            "",
+            "Tether",
+            0,
+            vec![1000],
+            "Invalid ticker: must be between 1 and 8 characters long",
+        ),
+        (
+            "USDTUSDTU",
+            "Tether",
+            0,
+            vec![1000],
+            "Invalid ticker: must be between 1 and 8 characters long",
+        ),
+        ("usdt", "Tether", 0, vec![1000], ticker_charset_error),
+        (
+            "USDT",
+            "",
+            0,
+            vec![1000],
+            "Invalid name: must be between 1 and 40 characters long",
+        ),
+        (
+            "USDT",
+            "Tether",
+            19,
+            vec![1000],
+            "Invalid precision: must be between 0 and 18",
+        ),
+        (
+            "USDT",
+            "Tether",
+            0,
+            vec![],
+            "Invalid amount: at least one amount must be provided",
+        ),
+        (
+            "USDT",
+            "Tether",
+            0,
+            vec![1000, 0],
+            "Invalid amount: amounts must be greater than 0",
+        ),
+        (
+            "USDT",
+            "Tether",
+            0,
+            vec![u64::MAX, 1],
+            "Invalid amount: total issued supply cannot exceed 18446744073709551615",
+        ),
+    ] {
+        let payload = IssueAssetRequest {
+            amounts: amounts.into_iter().map(AssetUnits).collect(),
+            ticker: ticker.to_string(),
+            name: name.to_string(),
+            precision,
+            allow_duplicate_ticker: false,
+        };
+        let res = reqwest::Client::new()
+            .post(format!("http://{}/issueasset", node1_addr))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+        let text = res.text().await.unwrap();
+        let response: ErrorResponse = serde_json::from_str(&text).unwrap();
+        assert_eq!(response.error, expected_error);
+        assert_eq!(response.code, 400);
+    }
+
     let asset_id = issue_asset(node1_addr).await;
 
+    let unspents = list_unspents_filtered(node1_addr, Some(true)).await;
+    let issuance_outpoint = unspents
+        .iter()
+        .find(|u| {
+            u.rgb_allocations
+                .iter()
+                .any(|a| a.asset_id.as_ref() == Some(&asset_id))
+        })
+        .unwrap()
+        .utxo
+        .outpoint
+        .clone();
     let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
-    send_asset(node1_addr, &asset_id, 400, recipient_id).await;
+    let dry_run = send_asset_dry_run(node1_addr, &asset_id, 400, recipient_id.clone()).await;
+    // the wallet selects the allocations when sending, the dry run doesn't guess them
+    assert!(dry_run.inputs.is_empty());
+    assert!(dry_run.consumed_allocations.is_empty());
+    assert!(dry_run.fee_sat > 0);
+    assert_eq!(dry_run.asset_change, None);
+    // a dry run changes nothing, so repeating it gives the same answer
+    assert_eq!(
+        send_asset_dry_run(node1_addr, &asset_id, 400, recipient_id.clone()).await,
+        dry_run
+    );
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 1000);
+    let SendAssetResponse {
+        txid,
+        consumed_allocations,
+        change_allocation,
+        ..
+    } = send_asset(node1_addr, &asset_id, 400, recipient_id).await;
+    let txid = txid.unwrap();
+    assert_eq!(
+        consumed_allocations,
+        vec![OutpointAllocation {
+            outpoint: issuance_outpoint,
+            amount: 1000,
+        }]
+    );
+    let change_allocation = change_allocation.unwrap();
+    assert!(change_allocation.outpoint.starts_with(&txid));
+    assert_eq!(change_allocation.amount, 600);
     mine(false);
     refresh_transfers(node2_addr).await;
     refresh_transfers(node2_addr).await;
@@ -35,6 +175,7 @@ async fn send_receive() {
         recipient_id,
         invoice,
         expiration_timestamp: _,
+        unified: _,
     } = rgb_invoice(node1_addr, Some(asset_id.clone())).await;
     send_asset(node2_addr, &asset_id, 300, recipient_id.clone()).await;
     mine(false);
@@ -52,14 +193,55 @@ async fn send_receive() {
     assert_eq!(decoded.amount, None);
     assert!(decoded.network.is_none());
     assert!(decoded.expiration_timestamp.is_some());
-    assert_eq!(decoded.transport_endpoints, vec![PROXY_ENDPOINT_REGTEST]);
+    assert_eq!(
+        decoded.transport_endpoints,
+        vec![test_env().proxy_endpoint.clone()]
+    );
 
+    // with more allocations than needed, only the spent ones are reported
+    let asset_allocations = |unspents: Vec<Unspent>| {
+        unspents
+            .into_iter()
+            .flat_map(|u| {
+                let outpoint = u.utxo.outpoint;
+                u.rgb_allocations
+                    .into_iter()
+                    .filter(|a| a.asset_id.as_ref() == Some(&asset_id))
+                    .map(move |a| OutpointAllocation {
+                        outpoint: outpoint.clone(),
+                        amount: a.amount,
+                    })
+            })
+            .collect::<Vec<OutpointAllocation>>()
+    };
+    let allocations = asset_allocations(list_unspents_filtered(node1_addr, Some(true)).await);
+    assert_eq!(allocations.len(), 2);
     let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
-    send_asset(node1_addr, &asset_id, 200, recipient_id).await;
+    let SendAssetResponse {
+        txid,
+        consumed_allocations,
+        change_allocation,
+        ..
+    } = send_asset(node1_addr, &asset_id, 200, recipient_id).await;
+    let txid = txid.unwrap();
+    assert!(!consumed_allocations.is_empty());
+    assert!(consumed_allocations.iter().all(|a| allocations.contains(a)));
+    let consumed_amount: u64 = consumed_allocations.iter().map(|a| a.amount).sum();
+    let change_allocation = change_allocation.unwrap();
+    assert!(change_allocation.outpoint.starts_with(&txid));
+    assert_eq!(change_allocation.amount, consumed_amount - 200);
     mine(false);
     refresh_transfers(node2_addr).await;
     refresh_transfers(node2_addr).await;
     refresh_transfers(node1_addr).await;
+    let allocations_after = asset_allocations(list_unspents_filtered(node1_addr, Some(true)).await);
+    for allocation in &allocations {
+        assert_eq!(
+            allocations_after.contains(allocation),
+            !consumed_allocations.contains(allocation)
+        );
+    }
+    assert!(allocations_after.contains(&change_allocation));
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 700);
     assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 300);
 }
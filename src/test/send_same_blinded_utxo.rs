@@ -0,0 +1,76 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/send_same_blinded_utxo/";
+const NODE1_PEER_PORT: u16 = 10061;
+const NODE2_PEER_PORT: u16 = 10062;
+
+async fn check_already_used(node_address: SocketAddr, payload: &SendAssetRequest) {
+    let res = send_asset_raw(node_address, payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(
+        response.error,
+        "Blinded UTXO already used by a previous send (hint: set force to send anyway)"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn send_same_blinded_utxo() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let ldk_sockets = get_ldk_sockets(&[NODE1_PEER_PORT]);
+    let (node1_addr, _) = start_node(test_dir_node1.clone(), NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    // node2 never refreshes, so the transfer keeps waiting for its acknowledgement
+    let recipient_id = rgb_invoice(node2_addr, Some(asset_id.clone()))
+        .await
+        .recipient_id;
+    let mut payload = SendAssetRequest {
+        asset_id: asset_id.clone(),
+        amount: AssetUnits(100),
+        blinded_utxo: recipient_id.clone(),
+        donation: false,
+        min_confirmations: 1,
+        transport_endpoints: vec![test_env().proxy_endpoint.clone()],
+        dry_run: false,
+        force: false,
+    };
+    let res = send_asset_raw(node1_addr, &payload).await;
+    _check_response_is_ok(res).await;
+
+    // a retry is rejected, dry run included, also after a restart
+    check_already_used(node1_addr, &payload).await;
+    payload.dry_run = true;
+    check_already_used(node1_addr, &payload).await;
+    payload.dry_run = false;
+    shutdown(&[node1_addr], &ldk_sockets).await;
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, true).await;
+    check_already_used(node1_addr, &payload).await;
+
+    // failing the transfer allows paying the blinded UTXO again
+    let transfer = list_transfers(node1_addr, &asset_id)
+        .await
+        .into_iter()
+        .find(|t| {
+            matches!(t.kind, TransferKind::Send) && t.recipient_id.as_ref() == Some(&recipient_id)
+        })
+        .unwrap();
+    assert!(matches!(
+        transfer.status,
+        TransferStatus::WaitingCounterparty
+    ));
+    // each batch holds a single transfer here, so the transfer and batch IDs match
+    assert!(fail_transfers(node1_addr, Some(transfer.idx)).await);
+    let res = send_asset_raw(node1_addr, &payload).await;
+    _check_response_is_ok(res).await;
+    check_already_used(node1_addr, &payload).await;
+}
@@ -0,0 +1,1492 @@
+use super::*;
+use crate::channel_handshake::AnnouncedChannelPreference;
+use crate::routes::{
+    AbandonPaymentRequest, AcceptChannelRequest, AddWebhookRequest, AddWebhookResponse,
+    AddressRequest, AddressResponse, Alert, AlertKind, AlertsResponse, Asset, AssetBalanceRequest,
+    AssetBalanceResponse, AssetBalances, AssetExposure, AssetIface, AssetSpendingLimit,
+    AssetSpendingLimitStatus, AssetVolume, BackupRequest, BalancesResponse, Ban, BanPeerRequest,
+    BitcoinNetwork, BlockTime, BtcBalance, BtcBalanceResponse, BtcBalances, CancelInvoiceRequest,
+    CancelJobRequest, ChangePasswordRequest, Channel, ChannelDebugHtlc, ChannelDebugParams,
+    ChannelDebugPeer, ChannelDebugResponse, ChannelDebugRgb, ChannelRequestDecision,
+    ChannelSuggestionsParams, ChannelSuggestionsResponse, CloseChannelRequest,
+    CloseChannelResponse, CloseSuggestion, ClosedChannel, CommitmentType, ConnectPeerRequest,
+    ConsolidateAssetsRequest, ConsolidateAssetsResponse, CountersResponse, CreateUtxosRequest,
+    CreateUtxosResponse, CreatedUtxo, DecodeLNInvoiceRequest, DecodeLNInvoiceResponse,
+    DecodePaymentRequestRequest, DecodePaymentRequestResponse, DecodeRGBInvoiceRequest,
+    DecodeRGBInvoiceResponse, DeletePaymentRequest, DeletePaymentResponse, DeleteWebhookRequest,
+    DepletedSide, DisconnectPeerRequest, DryRunResult, EmptyResponse, Event, EventsParams,
+    EventsResponse, ExportAssetParams, ExportAssetResponse, ExportFormat, FailTransfersRequest,
+    FailTransfersResponse, FailingHop, FailureReason, FundAddressRequest, FundAddressResponse,
+    GetPaymentParams, GetPaymentResponse, Htlc, HtlcExpiryAction, ImportAssetRequest,
+    ImportAssetResponse, InboundChannelRequest, InboundChannelRequestStatus, InitRequest,
+    InitResponse, InvoiceStatus, InvoiceStatusRequest, InvoiceStatusResponse, IssueAssetRequest,
+    IssueAssetResponse, JobKind, JobResponse, JobStatus, JobStatusParams, JobStatusResponse,
+    KeysendRequest, KeysendResponse, LNInvoiceRequest, LNInvoiceResponse, LatencyResponse,
+    ListAddressesResponse, ListAssetsResponse, ListBansResponse, ListChannelsParams,
+    ListChannelsResponse, ListClosedChannelsResponse, ListHtlcsResponse,
+    ListInboundChannelRequestsResponse, ListPaymentsParams, ListPaymentsResponse, ListPeersParams,
+    ListPeersResponse, ListSwapsResponse, ListSweepsResponse, ListTransactionsParams,
+    ListTransactionsResponse, ListTransfersRequest, ListTransfersResponse, ListUnspentsParams,
+    ListUnspentsResponse, ListWebhooksResponse, MineBlocksRequest, MineBlocksResponse,
+    NetworkInfoResponse, NodeInfoResponse, OpenChannelRequest, OpenChannelResponse, OpenSuggestion,
+    OutpointAllocation, Payment, PaymentFeasibility, PaymentHop, PaymentVolume, Peer, PeerExposure,
+    PendingChannel, PendingChannelStage, PendingChannelsResponse, PruneGraphResponse,
+    PruneMonitorsRequest, PruneMonitorsResponse, PrunePaymentsRequest, PrunePaymentsResponse,
+    QueryNodeInfoParams, QueryNodeInfoResponse, QuotePaymentRequest, QuotePaymentResponse,
+    ReadyResponse, RebalanceSuggestion, RebroadcastAnnouncementsResponse, ReestablishState,
+    RegistryStatus, RejectChannelRequest, ReorgedTransfer, RequestInboundChannelRequest,
+    RequestInboundChannelResponse, RestoreRequest, RgbAllocation, RgbInvoiceRequest,
+    RgbInvoiceResponse, SendAssetRequest, SendAssetResponse, SendBtcRequest, SendBtcResponse,
+    SendCustomMessageRequest, SendOnionMessageRequest, SendPaymentRequest, SendPaymentResponse,
+    SetAlertsRequest, SetAliasRequest, SetChannelNoteRequest, SetLabelRequest,
+    SetPaymentPolicyRequest, SetPeerExposureLimitsRequest, SettlementMethod, ShutdownMode,
+    ShutdownRequest, SignMessageRequest, SignMessageResponse, SpendingLimitStatus,
+    SpendingLimitsResponse, StatsParams, StatsPeriod, StatsResponse, Swap, SwapAssetsRequest,
+    SwapAssetsResponse, SwapDirection, SwapStatus, Sweep, Transaction, TransactionDirection,
+    TransactionPurpose, TransactionType, Transfer, TransferKind, TransferStatus,
+    TransferTransportEndpoint, TransportType, UnbanPeerRequest, UnlockRequest, Unspent, Utxo,
+    VerifyMessageRequest, VerifyMessageResponse, WalletAddress, Webhook,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Spelling used for the variants up to 0.1.0, e.g. "awaiting_fee" -> "AwaitingFee"
+fn pascal_case(snake_case: &str) -> String {
+    snake_case
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Check each variant serializes to the given spelling and only deserializes from it
+fn check_variants<T: Serialize + DeserializeOwned>(variants: &[(T, &str)]) {
+    for (variant, spelling) in variants {
+        assert_eq!(serde_json::to_value(variant).unwrap(), json!(spelling));
+        let parsed: T = serde_json::from_value(json!(spelling)).unwrap();
+        assert_eq!(serde_json::to_value(parsed).unwrap(), json!(spelling));
+        assert!(serde_json::from_value::<T>(json!(pascal_case(spelling))).is_err());
+    }
+}
+
+/// Check each variant of an enum already in 0.1.0 serializes to the given spelling and that both
+/// the given and the old spelling deserialize to it
+fn check_old_variants<T: Serialize + DeserializeOwned>(variants: &[(T, &str)]) {
+    for (variant, spelling) in variants {
+        assert_eq!(serde_json::to_value(variant).unwrap(), json!(spelling));
+        for input in [spelling.to_string(), pascal_case(spelling)] {
+            let parsed: T = serde_json::from_value(json!(input)).unwrap();
+            assert_eq!(serde_json::to_value(parsed).unwrap(), json!(spelling));
+        }
+    }
+}
+
+/// Check the JSON deserializes and serializes back unchanged
+fn check_round_trip<T: Serialize + DeserializeOwned>(json: Value) {
+    let parsed: T = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(serde_json::to_value(parsed).unwrap(), json);
+}
+
+#[test]
+fn enum_variants() {
+    check_variants(&[
+        (AlertKind::LowAnchorReserve, "low_anchor_reserve"),
+        (AlertKind::LowFeeBumpReserve, "low_fee_bump_reserve"),
+        (
+            AlertKind::ChannelAssetOutboundExhausted,
+            "channel_asset_outbound_exhausted",
+        ),
+        (
+            AlertKind::ChannelAssetInboundExhausted,
+            "channel_asset_inbound_exhausted",
+        ),
+        (AlertKind::PersistenceFailure, "persistence_failure"),
+        (AlertKind::UnconfirmedTransaction, "unconfirmed_transaction"),
+    ]);
+    check_variants(&[
+        (AnnouncedChannelPreference::Any, "any"),
+        (AnnouncedChannelPreference::Public, "public"),
+        (AnnouncedChannelPreference::Private, "private"),
+    ]);
+    check_old_variants(&[
+        (BitcoinNetwork::Mainnet, "mainnet"),
+        (BitcoinNetwork::Testnet, "testnet"),
+        (BitcoinNetwork::Signet, "signet"),
+        (BitcoinNetwork::Regtest, "regtest"),
+    ]);
+    check_variants(&[
+        (ChannelRequestDecision::Accepted, "accepted"),
+        (ChannelRequestDecision::Rejected, "rejected"),
+        (
+            ChannelRequestDecision::AwaitingDecision,
+            "awaiting_decision",
+        ),
+    ]);
+    check_variants(&[
+        (CommitmentType::Anchors, "anchors"),
+        (CommitmentType::StaticRemoteKey, "static_remote_key"),
+    ]);
+    check_variants(&[
+        (DepletedSide::Outbound, "outbound"),
+        (DepletedSide::Inbound, "inbound"),
+    ]);
+    check_variants(&[
+        (ExportFormat::Binary, "binary"),
+        (ExportFormat::Json, "json"),
+    ]);
+    check_variants(&[
+        (FailureReason::AmountBelowMinimum, "amount_below_minimum"),
+        (FailureReason::ChannelDisabled, "channel_disabled"),
+        (FailureReason::DuplicatePayment, "duplicate_payment"),
+        (FailureReason::FeeInsufficient, "fee_insufficient"),
+        (FailureReason::IncorrectCltvExpiry, "incorrect_cltv_expiry"),
+        (
+            FailureReason::IncorrectPaymentDetails,
+            "incorrect_payment_details",
+        ),
+        (FailureReason::PaymentExpired, "payment_expired"),
+        (
+            FailureReason::PermanentChannelFailure,
+            "permanent_channel_failure",
+        ),
+        (
+            FailureReason::PermanentNodeFailure,
+            "permanent_node_failure",
+        ),
+        (FailureReason::RetriesExhausted, "retries_exhausted"),
+        (
+            FailureReason::RgbIncorrectPaymentDetails,
+            "rgb_incorrect_payment_details",
+        ),
+        (
+            FailureReason::RgbInsufficientAssetLiquidity,
+            "rgb_insufficient_asset_liquidity",
+        ),
+        (FailureReason::RouteNotFound, "route_not_found"),
+        (
+            FailureReason::TemporaryChannelFailure,
+            "temporary_channel_failure",
+        ),
+        (
+            FailureReason::TemporaryNodeFailure,
+            "temporary_node_failure",
+        ),
+        (FailureReason::UnexpectedError, "unexpected_error"),
+        (FailureReason::UnknownPathFailure, "unknown_path_failure"),
+        (FailureReason::UserAbandoned, "user_abandoned"),
+    ]);
+    check_old_variants(&[
+        (HTLCStatus::Pending, "pending"),
+        (HTLCStatus::Succeeded, "succeeded"),
+        (HTLCStatus::Failed, "failed"),
+    ]);
+    check_variants(&[
+        (HtlcExpiryAction::PeerReconnect, "peer_reconnect"),
+        (HtlcExpiryAction::PaymentAbandon, "payment_abandon"),
+        (HtlcExpiryAction::HtlcFailBack, "htlc_fail_back"),
+        (HtlcExpiryAction::ChannelForceClose, "channel_force_close"),
+    ]);
+    check_variants(&[
+        (InboundChannelRequestStatus::Pending, "pending"),
+        (InboundChannelRequestStatus::AwaitingFee, "awaiting_fee"),
+        (InboundChannelRequestStatus::Opening, "opening"),
+        (InboundChannelRequestStatus::Rejected, "rejected"),
+    ]);
+    check_old_variants(&[
+        (InvoiceStatus::Pending, "pending"),
+        (InvoiceStatus::Succeeded, "succeeded"),
+        (InvoiceStatus::Failed, "failed"),
+        (InvoiceStatus::Expired, "expired"),
+    ]);
+    check_variants(&[(InvoiceStatus::Cancelled, "cancelled")]);
+    check_variants(&[
+        (JobKind::OpenChannel, "open_channel"),
+        (JobKind::Restore, "restore"),
+        (JobKind::SendAsset, "send_asset"),
+    ]);
+    check_variants(&[
+        (JobStatus::Running, "running"),
+        (JobStatus::Succeeded, "succeeded"),
+        (JobStatus::Failed, "failed"),
+        (JobStatus::Cancelled, "cancelled"),
+    ]);
+    check_variants(&[
+        (PaymentFeasibility::Ok, "ok"),
+        (
+            PaymentFeasibility::InsufficientLiquidity,
+            "insufficient_liquidity",
+        ),
+        (PaymentFeasibility::NoRoute, "no_route"),
+        (
+            PaymentFeasibility::AmountBelowMinimum,
+            "amount_below_minimum",
+        ),
+    ]);
+    check_variants(&[
+        (PendingChannelStage::Negotiating, "negotiating"),
+        (PendingChannelStage::FundingSigning, "funding_signing"),
+        (
+            PendingChannelStage::AwaitingConfirmations,
+            "awaiting_confirmations",
+        ),
+    ]);
+    check_variants(&[
+        (ReestablishState::PeerDisconnected, "peer_disconnected"),
+        (
+            ReestablishState::AwaitingChannelReady,
+            "awaiting_channel_ready",
+        ),
+        (ReestablishState::Pending, "pending"),
+        (ReestablishState::Completed, "completed"),
+    ]);
+    check_variants(&[
+        (RegistryStatus::Verified, "verified"),
+        (RegistryStatus::Unregistered, "unregistered"),
+        (RegistryStatus::TickerConflict, "ticker_conflict"),
+        (RegistryStatus::Unverified, "unverified"),
+    ]);
+    check_variants(&[
+        (SettlementMethod::Lightning, "lightning"),
+        (SettlementMethod::Onchain, "onchain"),
+    ]);
+    check_variants(&[
+        (ShutdownMode::Restartable, "restartable"),
+        (ShutdownMode::Exit, "exit"),
+    ]);
+    check_variants(&[
+        (StatsPeriod::Day, "day"),
+        (StatsPeriod::Week, "week"),
+        (StatsPeriod::Month, "month"),
+    ]);
+    check_variants(&[
+        (SwapDirection::IntoChannel, "into_channel"),
+        (SwapDirection::OutOfChannel, "out_of_channel"),
+    ]);
+    check_variants(&[
+        (SwapStatus::Pending, "pending"),
+        (SwapStatus::Succeeded, "succeeded"),
+        (SwapStatus::Failed, "failed"),
+    ]);
+    check_variants(&[
+        (TransactionDirection::Incoming, "incoming"),
+        (TransactionDirection::Outgoing, "outgoing"),
+    ]);
+    check_variants(&[
+        (TransactionPurpose::AssetSend, "asset_send"),
+        (TransactionPurpose::BtcTransfer, "btc_transfer"),
+        (TransactionPurpose::ChannelClose, "channel_close"),
+        (TransactionPurpose::ChannelForceClose, "channel_force_close"),
+        (TransactionPurpose::ChannelOpen, "channel_open"),
+        (TransactionPurpose::Drain, "drain"),
+        (TransactionPurpose::UtxoCreation, "utxo_creation"),
+    ]);
+    check_old_variants(&[
+        (TransactionType::RgbSend, "rgb_send"),
+        (TransactionType::Drain, "drain"),
+        (TransactionType::CreateUtxos, "create_utxos"),
+        (TransactionType::User, "user"),
+    ]);
+    check_old_variants(&[
+        (TransferKind::Issuance, "issuance"),
+        (TransferKind::ReceiveBlind, "receive_blind"),
+        (TransferKind::ReceiveWitness, "receive_witness"),
+        (TransferKind::Send, "send"),
+    ]);
+    check_old_variants(&[
+        (TransferStatus::WaitingCounterparty, "waiting_counterparty"),
+        (
+            TransferStatus::WaitingConfirmations,
+            "waiting_confirmations",
+        ),
+        (TransferStatus::Settled, "settled"),
+        (TransferStatus::Failed, "failed"),
+    ]);
+    check_old_variants(&[(TransportType::JsonRpc, "json_rpc")]);
+
+    // the interfaces keep their uppercase names as the old spelling
+    for (iface, spelling, old_spelling) in [
+        (AssetIface::RGB20, "rgb20", "RGB20"),
+        (AssetIface::RGB21, "rgb21", "RGB21"),
+        (AssetIface::RGB25, "rgb25", "RGB25"),
+    ] {
+        assert_eq!(serde_json::to_value(iface).unwrap(), json!(spelling));
+        let parsed: AssetIface = serde_json::from_value(json!(old_spelling)).unwrap();
+        assert_eq!(serde_json::to_value(parsed).unwrap(), json!(spelling));
+    }
+}
+
+#[test]
+fn event_types() {
+    let event = json!({
+        "id": 3,
+        "timestamp": 1700000000,
+        "type": "payment_failed",
+        "payment_hash": "aa".repeat(32),
+        "failure_reason": "route_not_found",
+        "failing_hop": {
+            "position": 1,
+            "node_pubkey": format!("02{}", "bb".repeat(32)),
+            "short_channel_id": 123456789,
+        },
+    });
+    check_round_trip::<Event>(event.clone());
+
+    // events came after 0.1.0, so they never had another spelling
+    let mut old_event = event.clone();
+    old_event["type"] = json!("PaymentFailed");
+    assert!(serde_json::from_value::<Event>(old_event).is_err());
+    let mut old_event = event.clone();
+    old_event["failure_reason"] = json!("RouteNotFound");
+    assert!(serde_json::from_value::<Event>(old_event).is_err());
+
+    check_round_trip::<Event>(json!({
+        "id": 4,
+        "timestamp": 1700000001,
+        "type": "alert_raised",
+        "kind": "low_anchor_reserve",
+        "channel_id": null,
+        "asset_id": null,
+        "message": "anchor reserve below the minimum",
+    }));
+    check_round_trip::<Event>(json!({
+        "id": 5,
+        "timestamp": 1700000002,
+        "type": "htlc_expiry_action",
+        "action": "htlc_fail_back",
+        "channel_id": "cc".repeat(32),
+        "peer_pubkey": format!("03{}", "dd".repeat(32)),
+        "payment_hash": "ee".repeat(32),
+        "blocks_to_force_close": 12,
+        "success": true,
+    }));
+}
+
+#[test]
+fn request_round_trips() {
+    let channel_id = "11".repeat(32);
+    let peer_pubkey = format!("02{}", "22".repeat(32));
+    let asset_id = "rgb:2dkSTbr-jFhznbPmo-TQafzswCN-av4gTsJjX-ttx6CNou5-M98k8Zd";
+    let payment_hash = "33".repeat(32);
+    let txid = "44".repeat(32);
+
+    check_round_trip::<AbandonPaymentRequest>(json!({
+        "payment_hash": payment_hash,
+    }));
+    check_round_trip::<AcceptChannelRequest>(json!({
+        "temporary_channel_id": channel_id,
+    }));
+    check_round_trip::<AddWebhookRequest>(json!({
+        "url": "https://example.com/hook",
+        "events": ["payment_received"],
+        "secret": "secret",
+    }));
+    check_round_trip::<AddressRequest>(json!({
+        "reuse_last": true,
+    }));
+    check_round_trip::<AssetBalanceRequest>(json!({
+        "asset_id": asset_id,
+    }));
+    let asset_spending_limit = json!({
+        "asset_id": asset_id,
+        "max_amount": 1000,
+    });
+    check_round_trip::<BackupRequest>(json!({
+        "backup_path": "backup_path",
+        "password": "password",
+    }));
+    check_round_trip::<BanPeerRequest>(json!({
+        "peer_pubkey": peer_pubkey,
+        "duration_secs": 1000,
+        "force": true,
+    }));
+    check_round_trip::<CancelInvoiceRequest>(json!({
+        "payment_hash": payment_hash,
+    }));
+    check_round_trip::<CancelJobRequest>(json!({
+        "job_id": "job_id",
+    }));
+    check_round_trip::<ChangePasswordRequest>(json!({
+        "old_password": "old_password",
+        "new_password": "new_password",
+    }));
+    check_round_trip::<ChannelDebugParams>(json!({
+        "channel_id": channel_id,
+    }));
+    check_round_trip::<ChannelSuggestionsParams>(json!({
+        "days": 1000,
+    }));
+    check_round_trip::<CloseChannelRequest>(json!({
+        "channel_id": channel_id,
+        "peer_pubkey": peer_pubkey,
+        "force": true,
+        "dry_run": true,
+        "asset_destination": "asset_destination",
+        "fee_rate_sat_vb": 1.5,
+        "target_conf_blocks": 30,
+        "min_fee_rate_sat_vb": 1.5,
+        "max_fee_rate_sat_vb": 1.5,
+        "force_after_sec": 1000,
+    }));
+    check_round_trip::<ConnectPeerRequest>(json!({
+        "peer_pubkey_and_addr": "peer_pubkey_and_addr",
+    }));
+    check_round_trip::<ConsolidateAssetsRequest>(json!({
+        "asset_id": asset_id,
+    }));
+    check_round_trip::<CreateUtxosRequest>(json!({
+        "up_to": true,
+        "num": 2,
+    }));
+    check_round_trip::<DecodeLNInvoiceRequest>(json!({
+        "invoice": "invoice",
+    }));
+    check_round_trip::<DecodePaymentRequestRequest>(json!({
+        "payment_request": "payment_request",
+    }));
+    check_round_trip::<DecodeRGBInvoiceRequest>(json!({
+        "invoice": "invoice",
+    }));
+    check_round_trip::<DeletePaymentRequest>(json!({
+        "payment_hash": payment_hash,
+    }));
+    check_round_trip::<DeleteWebhookRequest>(json!({
+        "id": 1000,
+    }));
+    check_round_trip::<DisconnectPeerRequest>(json!({
+        "peer_pubkey": peer_pubkey,
+    }));
+    check_round_trip::<EventsParams>(json!({
+        "after_id": 1000,
+    }));
+    check_round_trip::<ExportAssetParams>(json!({
+        "asset_id": asset_id,
+        "history": true,
+        "format": "binary",
+    }));
+    check_round_trip::<FailTransfersRequest>(json!({
+        "batch_transfer_idx": 3,
+        "no_asset_only": true,
+    }));
+    check_round_trip::<FundAddressRequest>(json!({
+        "address": "address",
+        "amount": 1000,
+    }));
+    check_round_trip::<GetPaymentParams>(json!({
+        "payment_hash": payment_hash,
+    }));
+    check_round_trip::<ImportAssetRequest>(json!({
+        "asset_id": asset_id,
+        "consignment": "consignment",
+    }));
+    check_round_trip::<InitRequest>(json!({
+        "password": "password",
+    }));
+    check_round_trip::<InvoiceStatusRequest>(json!({
+        "invoice": "invoice",
+        "payment_hash": payment_hash,
+    }));
+    check_round_trip::<IssueAssetRequest>(json!({
+        "amounts": [100],
+        "ticker": "ticker",
+        "name": "name",
+        "precision": 2,
+        "allow_duplicate_ticker": true,
+    }));
+    check_round_trip::<JobStatusParams>(json!({
+        "job_id": "job_id",
+    }));
+    check_round_trip::<KeysendRequest>(json!({
+        "dest_pubkey": peer_pubkey,
+        "amt_msat": 3000000,
+        "asset_id": asset_id,
+        "asset_amount": 100,
+        "label": "label",
+        "use_minimum_btc": true,
+    }));
+    check_round_trip::<ListChannelsParams>(json!({
+        "after": "after",
+        "limit": 5,
+    }));
+    check_round_trip::<ListPaymentsParams>(json!({
+        "label": "label",
+        "status": "pending",
+        "after": "after",
+        "limit": 5,
+    }));
+    check_round_trip::<ListPeersParams>(json!({
+        "after": "after",
+        "limit": 5,
+    }));
+    check_round_trip::<ListTransactionsParams>(json!({
+        "min_height": 144,
+        "max_height": 144,
+        "offset": 5,
+        "limit": 5,
+        "watched_only": true,
+    }));
+    check_round_trip::<ListTransfersRequest>(json!({
+        "asset_id": asset_id,
+    }));
+    check_round_trip::<ListUnspentsParams>(json!({
+        "colorable": true,
+    }));
+    check_round_trip::<LNInvoiceRequest>(json!({
+        "amt_msat": 3000000,
+        "expiry_sec": 144,
+        "asset_id": asset_id,
+        "asset_amount": 100,
+        "label": "label",
+        "allow_insufficient_inbound": true,
+        "require_payer_pubkey": peer_pubkey,
+        "require_payer_note": true,
+        "min_final_cltv_expiry_delta": 30,
+        "unified": true,
+    }));
+    check_round_trip::<MineBlocksRequest>(json!({
+        "blocks": 30,
+    }));
+    check_round_trip::<OpenChannelRequest>(json!({
+        "peer_pubkey_and_addr": "peer_pubkey_and_addr",
+        "capacity_sat": 100000,
+        "push_msat": 3000000,
+        "asset_amount": 100,
+        "asset_id": asset_id,
+        "public": true,
+        "with_anchors": true,
+        "fee_base_msat": 144,
+        "fee_proportional_millionths": 144,
+        "min_depth": 144,
+        "htlc_minimum_msat": 3000000,
+        "fee_rate_sat_vb": 1.5,
+        "target_conf_blocks": 30,
+        "dry_run": true,
+    }));
+    check_round_trip::<PruneMonitorsRequest>(json!({
+        "older_than_ts": 1000,
+    }));
+    check_round_trip::<PrunePaymentsRequest>(json!({
+        "older_than_ts": 1000,
+        "statuses": ["pending"],
+    }));
+    check_round_trip::<QueryNodeInfoParams>(json!({
+        "pubkey": peer_pubkey,
+    }));
+    check_round_trip::<QuotePaymentRequest>(json!({
+        "invoice": "invoice",
+        "dest_pubkey": peer_pubkey,
+        "amt_msat": 3000000,
+        "asset_id": asset_id,
+        "asset_amount": 100,
+    }));
+    check_round_trip::<RejectChannelRequest>(json!({
+        "temporary_channel_id": channel_id,
+    }));
+    check_round_trip::<RequestInboundChannelRequest>(json!({
+        "lsp_pubkey_and_addr": "lsp_pubkey_and_addr",
+        "asset_id": asset_id,
+        "asset_amount": 100,
+        "capacity_sat": 100000,
+    }));
+    check_round_trip::<RestoreRequest>(json!({
+        "backup_path": "backup_path",
+        "password": "password",
+    }));
+    check_round_trip::<RgbInvoiceRequest>(json!({
+        "min_confirmations": 2,
+        "asset_id": asset_id,
+        "label": "label",
+        "expiry_sec": 3600,
+        "unified": true,
+    }));
+    check_round_trip::<SendAssetRequest>(json!({
+        "asset_id": asset_id,
+        "amount": 100,
+        "blinded_utxo": "blinded_utxo",
+        "donation": true,
+        "min_confirmations": 2,
+        "transport_endpoints": ["rpc://127.0.0.1:3000/json-rpc"],
+        "dry_run": true,
+        "force": true,
+    }));
+    check_round_trip::<SendBtcRequest>(json!({
+        "amount": 100000,
+        "address": "address",
+        "fee_rate": 1.5,
+    }));
+    check_round_trip::<SendCustomMessageRequest>(json!({
+        "peer_pubkey": peer_pubkey,
+        "msg_type": 30,
+        "data": "data",
+    }));
+    check_round_trip::<SendOnionMessageRequest>(json!({
+        "node_ids": [peer_pubkey],
+        "blinded_path": "blinded_path",
+        "tlv_type": 1000,
+        "data": "data",
+    }));
+    check_round_trip::<SendPaymentRequest>(json!({
+        "invoice": "invoice",
+        "label": "label",
+        "timeout_sec": 1000,
+        "max_retries": 144,
+        "disable_mpp": true,
+        "payer_note": "payer_note",
+        "identify_payer": true,
+        "fallback_onchain": true,
+        "fallback_fee_rate": 1.5,
+        "skip_liquidity_check": true,
+    }));
+    check_round_trip::<SetAliasRequest>(json!({
+        "alias": "alias",
+    }));
+    check_round_trip::<SetAlertsRequest>(json!({
+        "anchor_reserve_per_channel_sat": 1000,
+        "min_fee_bump_utxos": 1000,
+        "min_channel_asset_amount": 1000,
+        "max_unconfirmed_tx_age_secs": 1000,
+    }));
+    check_round_trip::<SetChannelNoteRequest>(json!({
+        "channel_id": channel_id,
+        "note": "note",
+    }));
+    check_round_trip::<SetLabelRequest>(json!({
+        "payment_hash": payment_hash,
+        "recipient_id": "recipient_id",
+        "label": "label",
+    }));
+    check_round_trip::<SetPaymentPolicyRequest>(json!({
+        "allowed_destinations": [peer_pubkey],
+        "max_msat_per_day": 3000000,
+        "max_asset_amounts_per_day": [asset_spending_limit],
+    }));
+    check_round_trip::<SetPaymentPolicyRequest>(json!({
+        "max_msat_per_day": null,
+    }));
+    check_round_trip::<SetPaymentPolicyRequest>(json!({}));
+    check_round_trip::<SetPeerExposureLimitsRequest>(json!({
+        "peer_pubkey": peer_pubkey,
+        "max_msat": 3000000,
+        "max_asset_amounts": [asset_spending_limit],
+    }));
+    check_round_trip::<ShutdownRequest>(json!({
+        "mode": "restartable",
+    }));
+    check_round_trip::<SignMessageRequest>(json!({
+        "message": "message",
+    }));
+    check_round_trip::<StatsParams>(json!({
+        "period": "day",
+    }));
+    check_round_trip::<SwapAssetsRequest>(json!({
+        "channel_id": channel_id,
+        "asset_amount": 100,
+    }));
+    check_round_trip::<UnbanPeerRequest>(json!({
+        "peer_pubkey": peer_pubkey,
+    }));
+    check_round_trip::<UnlockRequest>(json!({
+        "password": "password",
+        "regenerate": true,
+        "recovery_mode": true,
+    }));
+    check_round_trip::<VerifyMessageRequest>(json!({
+        "message": "message",
+        "signature": "signature",
+    }));
+}
+
+#[test]
+fn response_round_trips() {
+    let channel_id = "11".repeat(32);
+    let peer_pubkey = format!("02{}", "22".repeat(32));
+    let asset_id = "rgb:2dkSTbr-jFhznbPmo-TQafzswCN-av4gTsJjX-ttx6CNou5-M98k8Zd";
+    let payment_hash = "33".repeat(32);
+    let txid = "44".repeat(32);
+    let event = json!({
+        "id": 4,
+        "timestamp": 1700000001,
+        "type": "alert_raised",
+        "kind": "low_anchor_reserve",
+        "channel_id": null,
+        "asset_id": null,
+        "message": "anchor reserve below the minimum",
+    });
+
+    check_round_trip::<AddWebhookResponse>(json!({
+        "id": 1000,
+    }));
+    check_round_trip::<AddressResponse>(json!({
+        "address": "address",
+        "derivation_index": 144,
+    }));
+    let alert = json!({
+        "kind": "channel_asset_outbound_exhausted",
+        "channel_id": channel_id,
+        "asset_id": asset_id,
+        "message": "no outbound asset liquidity left",
+        "raised_at": 1700000000,
+    });
+    check_round_trip::<Alert>(alert.clone());
+    check_round_trip::<AlertsResponse>(json!({
+        "anchor_reserve_per_channel_sat": 1000,
+        "min_fee_bump_utxos": 1000,
+        "min_channel_asset_amount": 1000,
+        "max_unconfirmed_tx_age_secs": 1000,
+        "alerts": [alert],
+    }));
+    let asset = json!({
+        "asset_id": asset_id,
+        "ticker": "ticker",
+        "name": "name",
+        "precision": 2,
+        "issued_supply": 1000,
+        "timestamp": 1700000000,
+        "registry_status": "verified",
+    });
+    check_round_trip::<Asset>(asset.clone());
+    check_round_trip::<AssetBalanceResponse>(json!({
+        "settled": 1000,
+        "future": 1000,
+        "spendable": 1000,
+        "offchain_outbound": 1000,
+        "offchain_inbound": 1000,
+        "precision": 2,
+    }));
+    let asset_balances = json!({
+        "asset_id": asset_id,
+        "ticker": "ticker",
+        "precision": 2,
+        "onchain_settled": 1000,
+        "onchain_future": 1000,
+        "onchain_spendable": 1000,
+        "offchain_outbound": 1000,
+        "offchain_inbound": 1000,
+        "total": 1000,
+    });
+    check_round_trip::<AssetBalances>(asset_balances.clone());
+    let asset_exposure = json!({
+        "asset_id": asset_id,
+        "amount": 1000,
+        "max_amount": 1000,
+    });
+    check_round_trip::<AssetExposure>(asset_exposure.clone());
+    check_round_trip::<AssetSpendingLimit>(json!({
+        "asset_id": asset_id,
+        "max_amount": 1000,
+    }));
+    let asset_spending_limit_status = json!({
+        "asset_id": asset_id,
+        "limit": 1000,
+        "used": 1000,
+        "remaining": 1000,
+    });
+    check_round_trip::<AssetSpendingLimitStatus>(asset_spending_limit_status.clone());
+    let asset_volume = json!({
+        "asset_id": asset_id,
+        "count": 1000,
+        "amount": 1000,
+    });
+    check_round_trip::<AssetVolume>(asset_volume.clone());
+    let btc_balance = json!({
+        "settled": 1000,
+        "future": 1000,
+        "spendable": 1000,
+    });
+    check_round_trip::<BtcBalance>(btc_balance.clone());
+    let btc_balances = json!({
+        "onchain_vanilla": btc_balance,
+        "onchain_colored": btc_balance,
+        "offchain_local_msat": 1000,
+        "offchain_outbound_msat": 1000,
+        "offchain_inbound_msat": 1000,
+        "total_msat": 1000,
+    });
+    check_round_trip::<BtcBalances>(btc_balances.clone());
+    check_round_trip::<BalancesResponse>(json!({
+        "btc": btc_balances,
+        "assets": [asset_balances],
+    }));
+    let ban = json!({
+        "peer_pubkey": peer_pubkey,
+        "address": "address",
+        "reason": "reason",
+        "banned_at": 1700000000,
+        "expires_at": 1700000000,
+    });
+    check_round_trip::<Ban>(ban.clone());
+    let block_time = json!({
+        "height": 144,
+        "timestamp": 1700000000,
+    });
+    check_round_trip::<BlockTime>(block_time.clone());
+    check_round_trip::<BtcBalanceResponse>(json!({
+        "vanilla": btc_balance,
+        "colored": btc_balance,
+    }));
+    let channel = json!({
+        "channel_id": channel_id,
+        "funding_txid": txid,
+        "peer_pubkey": peer_pubkey,
+        "peer_alias": "peer_alias",
+        "short_channel_id": 1000,
+        "ready": true,
+        "capacity_sat": 1000,
+        "local_balance_msat": 1000,
+        "outbound_balance_msat": 1000,
+        "inbound_balance_msat": 1000,
+        "is_usable": true,
+        "public": true,
+        "confirmations_required": 144,
+        "confirmations": 144,
+        "asset_id": asset_id,
+        "asset_local_amount": 1000,
+        "asset_remote_amount": 1000,
+        "asset_precision": 2,
+        "funding_fee_sat": 1000,
+        "close_fee_sat": 1000,
+        "force_close_fee_sat": 1000,
+        "note": "note",
+        "htlc_min_sendable_msat": 1000,
+        "htlc_min_receivable_msat": 1000,
+        "htlc_min_asset_amount": 1000,
+        "consignment_endpoint": "consignment_endpoint",
+        "peer_offline_since": 1700000000,
+        "commitment_type": "anchors",
+        "created_at": 1700000000,
+        "announcement_status": {
+            "announcement": {"announced_to_peers": 2, "last_broadcast_at": 1700000000},
+            "update": {"announced_to_peers": 2, "last_broadcast_at": null},
+        },
+    });
+    check_round_trip::<Channel>(channel.clone());
+    let channel_debug_htlc = json!({
+        "inbound": true,
+        "amt_msat": 1000,
+        "asset_amount": 1000,
+        "payment_hash": payment_hash,
+        "payment_status": "pending",
+        "expiry_height": 144,
+        "blocks_to_force_close": 144,
+    });
+    check_round_trip::<ChannelDebugHtlc>(channel_debug_htlc.clone());
+    let channel_debug_peer = json!({
+        "connected": true,
+        "connected_since": 1700000000,
+        "last_seen": 1700000000,
+        "disconnect_count": 144,
+        "reestablish": "peer_disconnected",
+    });
+    check_round_trip::<ChannelDebugPeer>(channel_debug_peer.clone());
+    let channel_debug_rgb = json!({
+        "asset_id": asset_id,
+        "local_amount": 1000,
+        "remote_amount": 1000,
+        "pending_local_amount": 1000,
+        "pending_remote_amount": 1000,
+        "htlc_amount": 1000,
+    });
+    check_round_trip::<ChannelDebugRgb>(channel_debug_rgb.clone());
+    check_round_trip::<ChannelDebugResponse>(json!({
+        "channel_id": channel_id,
+        "peer_pubkey": peer_pubkey,
+        "funding_txid": txid,
+        "short_channel_id": 1000,
+        "outbound": true,
+        "ready": true,
+        "is_usable": true,
+        "commitment_type": "anchors",
+        "monitor_update_id": 1000,
+        "pending_monitor_updates": 5,
+        "feerate_sat_per_1000_weight": 144,
+        "capacity_sat": 1000,
+        "local_balance_msat": 1000,
+        "outbound_capacity_msat": 1000,
+        "inbound_capacity_msat": 1000,
+        "our_reserve_sat": 1000,
+        "their_reserve_sat": 1000,
+        "to_self_delay": 30,
+        "htlcs": [channel_debug_htlc],
+        "rgb": channel_debug_rgb,
+        "peer": channel_debug_peer,
+    }));
+    let close_suggestion = json!({
+        "channel_id": channel_id,
+        "peer_pubkey": peer_pubkey,
+        "capacity_sat": 1000,
+        "asset_id": asset_id,
+        "last_activity": 1000,
+    });
+    check_round_trip::<CloseSuggestion>(close_suggestion.clone());
+    let rebalance_suggestion = json!({
+        "channel_id": channel_id,
+        "peer_pubkey": peer_pubkey,
+        "depleted_side": "outbound",
+        "asset_id": asset_id,
+        "suggested_amt_msat": null,
+        "suggested_asset_amount": 45,
+    });
+    check_round_trip::<RebalanceSuggestion>(rebalance_suggestion.clone());
+    let open_suggestion = json!({
+        "peer_pubkey": peer_pubkey,
+        "failed_payments": 1000,
+        "succeeded_payments": 1000,
+    });
+    check_round_trip::<OpenSuggestion>(open_suggestion.clone());
+    check_round_trip::<ChannelSuggestionsResponse>(json!({
+        "period_start": 1000,
+        "close": [close_suggestion],
+        "rebalance": [rebalance_suggestion],
+        "open": [open_suggestion],
+    }));
+    let outpoint_allocation = json!({
+        "outpoint": "outpoint",
+        "amount": 1000,
+    });
+    check_round_trip::<OutpointAllocation>(outpoint_allocation.clone());
+    let dry_run_result = json!({
+        "inputs": ["inputs"],
+        "consumed_allocations": [outpoint_allocation],
+        "fee_sat": 1000,
+        "btc_change_sat": 1000,
+        "asset_change": 1000,
+    });
+    check_round_trip::<DryRunResult>(dry_run_result.clone());
+    check_round_trip::<CloseChannelResponse>(json!({
+        "dry_run": dry_run_result,
+    }));
+    let closed_channel = json!({
+        "channel_id": channel_id,
+        "funding_txid": txid,
+        "peer_pubkey": peer_pubkey,
+        "capacity_sat": 1000,
+        "asset_id": asset_id,
+        "asset_local_amount": 1000,
+        "asset_remote_amount": 1000,
+        "reason": "reason",
+        "closed_at": 1700000000,
+        "funding_fee_sat": 1000,
+        "close_fee_sat": 1000,
+        "force_close_fee_sat": 1000,
+        "close_fee_rate_sat_vb": 1.5,
+        "close_target_conf_blocks": 30,
+        "negotiated_close_fee_rate_sat_vb": 1.5,
+        "note": "note",
+        "unclaimed_sat": 1000,
+    });
+    check_round_trip::<ClosedChannel>(closed_channel.clone());
+    check_round_trip::<ConsolidateAssetsResponse>(json!({
+        "txid": txid,
+        "consolidated_allocations": [outpoint_allocation],
+    }));
+    check_round_trip::<CountersResponse>(json!({
+        "counters": {"open_channel": 2},
+    }));
+    let created_utxo = json!({
+        "outpoint": "outpoint",
+        "btc_amount": 1000,
+    });
+    check_round_trip::<CreatedUtxo>(created_utxo.clone());
+    check_round_trip::<CreateUtxosResponse>(json!({
+        "txid": txid,
+        "utxos": [created_utxo],
+        "skipped": 2,
+    }));
+    check_round_trip::<DecodeLNInvoiceResponse>(json!({
+        "amt_msat": 1000,
+        "expiry_sec": 1000,
+        "timestamp": 1700000000,
+        "asset_id": asset_id,
+        "asset_amount": 1000,
+        "payment_hash": payment_hash,
+        "payment_secret": "payment_secret",
+        "payee_pubkey": peer_pubkey,
+        "network": "mainnet",
+        "min_final_cltv_expiry_delta": 1000,
+    }));
+    let decode_rgb_invoice_response = json!({
+        "recipient_id": "recipient_id",
+        "asset_iface": "rgb20",
+        "asset_id": asset_id,
+        "amount": 1000,
+        "network": "mainnet",
+        "expiration_timestamp": -500,
+        "transport_endpoints": ["rpc://127.0.0.1:3000/json-rpc"],
+    });
+    check_round_trip::<DecodeRGBInvoiceResponse>(decode_rgb_invoice_response.clone());
+    check_round_trip::<DeletePaymentResponse>(json!({
+        "removed": 1000,
+    }));
+    check_round_trip::<EmptyResponse>(json!({}));
+    check_round_trip::<EventsResponse>(json!({
+        "events": [event],
+    }));
+    check_round_trip::<ExportAssetResponse>(json!({
+        "asset_id": asset_id,
+        "consignment": "consignment",
+        "armored": "armored",
+    }));
+    check_round_trip::<FailTransfersResponse>(json!({
+        "transfers_changed": true,
+    }));
+    check_round_trip::<FundAddressResponse>(json!({
+        "txid": txid,
+    }));
+    let failing_hop = json!({
+        "position": 144,
+        "node_pubkey": peer_pubkey,
+        "short_channel_id": 1000,
+    });
+    check_round_trip::<FailingHop>(failing_hop.clone());
+    let payment_hop = json!({
+        "part": 144,
+        "node_pubkey": peer_pubkey,
+        "short_channel_id": 1000,
+        "fee_msat": 1000,
+    });
+    check_round_trip::<PaymentHop>(payment_hop.clone());
+    let payment = json!({
+        "amt_msat": 3000000,
+        "asset_amount": 10,
+        "asset_id": asset_id,
+        "asset_precision": 0,
+        "payment_hash": payment_hash,
+        "inbound": false,
+        "status": "failed",
+        "invoice_status": "failed",
+        "label": null,
+        "created_at": 1700000000,
+        "failure_reason": "rgb_insufficient_asset_liquidity",
+        "failing_hop": null,
+        "payer_pubkey": null,
+        "payer_note": null,
+        "settlement_method": "lightning",
+        "onchain_txid": null,
+    });
+    check_round_trip::<Payment>(payment.clone());
+    check_round_trip::<GetPaymentResponse>(json!({
+        "payment": payment,
+    }));
+    let htlc = json!({
+        "channel_id": channel_id,
+        "peer_pubkey": peer_pubkey,
+        "inbound": true,
+        "amt_msat": 1000,
+        "asset_id": asset_id,
+        "asset_amount": 1000,
+        "payment_hash": payment_hash,
+        "expiry_height": 144,
+        "blocks_to_force_close": 144,
+        "expiry_warning": true,
+    });
+    check_round_trip::<Htlc>(htlc.clone());
+    check_round_trip::<ImportAssetResponse>(json!({
+        "asset_id": asset_id,
+    }));
+    let inbound_channel_request = json!({
+        "request_id": "55".repeat(16),
+        "lsp_pubkey": peer_pubkey,
+        "asset_id": asset_id,
+        "asset_amount": 100,
+        "capacity_sat": 100000,
+        "status": "awaiting_fee",
+        "fee_sat": 1000,
+        "fee_invoice": null,
+        "temporary_channel_id": null,
+        "reason": null,
+    });
+    check_round_trip::<InboundChannelRequest>(inbound_channel_request.clone());
+    check_round_trip::<InitResponse>(json!({
+        "mnemonic": "mnemonic",
+    }));
+    check_round_trip::<InvoiceStatusResponse>(json!({
+        "status": "expired",
+        "payment_hash": payment_hash,
+    }));
+    check_round_trip::<IssueAssetResponse>(json!({
+        "asset_id": asset_id,
+        "registry_warning": "registry_warning",
+    }));
+    check_round_trip::<JobResponse>(json!({
+        "job_id": "job_id",
+    }));
+    check_round_trip::<JobStatusResponse>(json!({
+        "job_id": "66".repeat(16),
+        "kind": "open_channel",
+        "status": "succeeded",
+        "cancellable": false,
+        "created_at": 1700000000,
+        "finished_at": 1700000060,
+        "result": {"temporary_channel_id": channel_id},
+        "error": null,
+    }));
+    check_round_trip::<KeysendResponse>(json!({
+        "payment_hash": payment_hash,
+        "payment_preimage": "payment_preimage",
+        "amt_msat": 1000,
+        "status": "pending",
+    }));
+    check_round_trip::<LatencyResponse>(json!({
+        "enabled": true,
+        "window": 5,
+        "routes": [{
+            "route": "/nodeinfo",
+            "count": 12,
+            "samples": 12,
+            "p50_ms": 1.5,
+            "p95_ms": 4.5,
+            "p99_ms": 8.0,
+            "max_ms": 9.5,
+        }],
+    }));
+    let wallet_address = json!({
+        "derivation_index": 144,
+        "address": "address",
+        "issued_at": 1700000000,
+        "used": true,
+        "balance_sat": 1000,
+    });
+    check_round_trip::<WalletAddress>(wallet_address.clone());
+    check_round_trip::<ListAddressesResponse>(json!({
+        "addresses": [wallet_address],
+    }));
+    check_round_trip::<ListAssetsResponse>(json!({
+        "assets": [asset],
+    }));
+    check_round_trip::<ListBansResponse>(json!({
+        "bans": [ban],
+    }));
+    check_round_trip::<ListChannelsResponse>(json!({
+        "channels": [channel],
+        "next_cursor": "next_cursor",
+    }));
+    check_round_trip::<ListClosedChannelsResponse>(json!({
+        "channels": [closed_channel],
+    }));
+    check_round_trip::<ListHtlcsResponse>(json!({
+        "htlcs": [htlc],
+    }));
+    check_round_trip::<ListInboundChannelRequestsResponse>(json!({
+        "requests": [inbound_channel_request],
+    }));
+    check_round_trip::<ListPaymentsResponse>(json!({
+        "payments": [payment],
+        "next_cursor": "next_cursor",
+    }));
+    let peer_exposure = json!({
+        "msat": 1000,
+        "max_msat": 1000,
+        "assets": [asset_exposure],
+        "violations": 1000,
+    });
+    check_round_trip::<PeerExposure>(peer_exposure.clone());
+    let peer = json!({
+        "pubkey": peer_pubkey,
+        "rgb_support": true,
+        "connected_since": 1700000000,
+        "last_seen": 1700000000,
+        "disconnect_count": 144,
+        "exposure": peer_exposure,
+    });
+    check_round_trip::<Peer>(peer.clone());
+    check_round_trip::<ListPeersResponse>(json!({
+        "peers": [peer],
+        "next_cursor": "next_cursor",
+    }));
+    let sweep = json!({
+        "txid": txid,
+        "amount_sat": 1000,
+        "asset_id": asset_id,
+        "asset_amount": 1000,
+        "created_at": 1700000000,
+    });
+    check_round_trip::<Sweep>(sweep.clone());
+    check_round_trip::<ListSweepsResponse>(json!({
+        "unswept_outputs": 5,
+        "sweeps": [sweep],
+    }));
+    let swap = json!({
+        "swap_id": "77".repeat(16),
+        "direction": "out_of_channel",
+        "initiator": true,
+        "channel_id": channel_id,
+        "peer_pubkey": peer_pubkey,
+        "asset_id": asset_id,
+        "asset_amount": 10,
+        "status": "pending",
+        "payment_hash": payment_hash,
+        "txid": null,
+        "reason": null,
+        "created_at": 1700000000,
+    });
+    check_round_trip::<Swap>(swap.clone());
+    check_round_trip::<ListSwapsResponse>(json!({
+        "swaps": [swap],
+    }));
+    let transaction = json!({
+        "transaction_type": "create_utxos",
+        "txid": txid,
+        "received": 32000,
+        "sent": 100000,
+        "fee": 500,
+        "confirmation_time": {"height": 150, "timestamp": 1700000000},
+        "direction": "outgoing",
+        "net_amount_sat": -68000,
+        "purpose": "utxo_creation",
+        "channel_id": null,
+        "rebroadcasts": null,
+    });
+    check_round_trip::<Transaction>(transaction.clone());
+    check_round_trip::<ListTransactionsResponse>(json!({
+        "transactions": [transaction],
+    }));
+    let transfer_transport_endpoint = json!({
+        "endpoint": "rpc://127.0.0.1:3000/json-rpc",
+        "transport_type": "json_rpc",
+        "used": true,
+    });
+    check_round_trip::<TransferTransportEndpoint>(transfer_transport_endpoint.clone());
+    let transfer = json!({
+        "idx": 2,
+        "created_at": 1700000000,
+        "updated_at": 1700000100,
+        "status": "waiting_confirmations",
+        "amount": 100,
+        "kind": "receive_blind",
+        "txid": txid,
+        "recipient_id": "utxob:2FZsSuk-iyVQLVuU4-Gc6J4qkE8-mLS17N4jd-MEx6cWz9F-MFkyE1n",
+        "receive_utxo": format!("{txid}:0"),
+        "change_utxo": null,
+        "expiration": 1700086400,
+        "transport_endpoints": [transfer_transport_endpoint],
+        "label": null,
+        "failure_reason": null,
+    });
+    check_round_trip::<Transfer>(transfer.clone());
+    check_round_trip::<ListTransfersResponse>(json!({
+        "transfers": [transfer],
+    }));
+    let utxo = json!({
+        "outpoint": "outpoint",
+        "btc_amount": 1000,
+        "colorable": true,
+    });
+    check_round_trip::<Utxo>(utxo.clone());
+    let rgb_allocation = json!({
+        "asset_id": asset_id,
+        "amount": 1000,
+        "settled": true,
+    });
+    check_round_trip::<RgbAllocation>(rgb_allocation.clone());
+    let unspent = json!({
+        "utxo": utxo,
+        "rgb_allocations": [rgb_allocation],
+        "reserved_by": {"type": "channel_open", "channel_id": channel_id},
+    });
+    check_round_trip::<Unspent>(unspent.clone());
+    check_round_trip::<ListUnspentsResponse>(json!({
+        "unspents": [unspent],
+    }));
+    let webhook = json!({
+        "id": 1000,
+        "url": "https://example.com/hook",
+        "events": ["payment_received"],
+        "created_at": 1700000000,
+        "pending_deliveries": 5,
+        "delivered": 1000,
+        "failed": 1000,
+        "last_attempt_at": 1700000000,
+        "last_delivered_at": 1700000000,
+        "last_error": "last_error",
+    });
+    check_round_trip::<Webhook>(webhook.clone());
+    check_round_trip::<ListWebhooksResponse>(json!({
+        "webhooks": [webhook],
+    }));
+    check_round_trip::<LNInvoiceResponse>(json!({
+        "invoice": "invoice",
+        "unified": "unified",
+    }));
+    check_round_trip::<MineBlocksResponse>(json!({
+        "block_hashes": ["block_hashes"],
+    }));
+    check_round_trip::<NetworkInfoResponse>(json!({
+        "network": "regtest",
+        "height": 150,
+    }));
+    check_round_trip::<NodeInfoResponse>(json!({
+        "pubkey": peer_pubkey,
+        "alias": "alias",
+        "color": "color",
+        "num_channels": 5,
+        "num_usable_channels": 5,
+        "local_balance_msat": 1000,
+        "num_peers": 5,
+        "default_min_confirmations": 2,
+        "min_allowed_confirmations": 2,
+        "monitor_mirror_pending_updates": 5,
+        "monitor_mirror_lagging": true,
+        "onion_message_forwarding": true,
+        "proxy_reachable": true,
+        "proxy_version": "proxy_version",
+        "proxy_protocol_compatible": true,
+        "persistence_failure": "persistence_failure",
+        "num_hot_monitors": 5,
+        "num_archived_monitors": 5,
+        "recovery_mode": true,
+        "data_dir": "data_dir",
+        "channel_handshake": {
+            "to_self_delay": 144,
+            "max_htlc_value_in_flight_percent": 10,
+            "announced_channel_preference": "any",
+            "dust_limit_sat": 546,
+        },
+        "node_announcement": {"announced_to_peers": 2, "last_broadcast_at": 1700000000},
+    }));
+    check_round_trip::<OpenChannelResponse>(json!({
+        "temporary_channel_id": channel_id,
+        "dry_run": dry_run_result,
+    }));
+    let payment_volume = json!({
+        "count": 1000,
+        "amt_msat": 1000,
+        "avg_settle_latency_secs": 2.5,
+        "assets": [asset_volume],
+    });
+    check_round_trip::<PaymentVolume>(payment_volume.clone());
+    let pending_channel = json!({
+        "channel_id": channel_id,
+        "peer_pubkey": peer_pubkey,
+        "outbound": true,
+        "stage": "awaiting_confirmations",
+        "capacity_sat": 100000,
+        "funding_txid": txid,
+        "confirmations": 1,
+        "confirmations_required": 6,
+        "asset_id": asset_id,
+        "asset_local_amount": 600,
+        "asset_remote_amount": 0,
+        "asset_precision": 0,
+    });
+    check_round_trip::<PendingChannel>(pending_channel.clone());
+    check_round_trip::<PendingChannelsResponse>(json!({
+        "channels": [pending_channel],
+    }));
+    check_round_trip::<PruneGraphResponse>(json!({
+        "nodes_removed": 5,
+        "channels_removed": 5,
+    }));
+    check_round_trip::<PruneMonitorsResponse>(json!({
+        "removed": 1000,
+    }));
+    check_round_trip::<PrunePaymentsResponse>(json!({
+        "removed": 1000,
+    }));
+    check_round_trip::<QueryNodeInfoResponse>(json!({
+        "pubkey": peer_pubkey,
+        "alias": "alias",
+        "color": "color",
+        "last_update": 144,
+    }));
+    check_round_trip::<QuotePaymentResponse>(json!({
+        "feasibility": "ok",
+        "best_fee_msat": 1000,
+        "p90_fee_msat": 1000,
+        "cltv_expiry_delta": 144,
+        "candidate_routes": 5,
+    }));
+    check_round_trip::<ReadyResponse>(json!({
+        "proxy_reachable": true,
+        "proxy_version": "0.2.0",
+        "proxy_protocol_compatible": true,
+    }));
+    check_round_trip::<RebroadcastAnnouncementsResponse>(json!({
+        "channels": 5,
+    }));
+    check_round_trip::<ReorgedTransfer>(json!({
+        "asset_id": asset_id,
+        "idx": 3,
+        "txid": txid,
+    }));
+    check_round_trip::<RequestInboundChannelResponse>(json!({
+        "request_id": "request_id",
+    }));
+    check_round_trip::<RgbInvoiceResponse>(json!({
+        "recipient_id": "recipient_id",
+        "invoice": "invoice",
+        "expiration_timestamp": -500,
+        "unified": "unified",
+    }));
+    check_round_trip::<SendAssetResponse>(json!({
+        "txid": txid,
+        "consumed_allocations": [outpoint_allocation],
+        "change_allocation": outpoint_allocation,
+        "dry_run": dry_run_result,
+    }));
+    check_round_trip::<SendBtcResponse>(json!({
+        "txid": txid,
+    }));
+    check_round_trip::<SendPaymentResponse>(json!({
+        "payment_hash": payment_hash,
+        "payment_secret": "payment_secret",
+        "status": "pending",
+    }));
+    check_round_trip::<SignMessageResponse>(json!({
+        "signed_message": "signed_message",
+    }));
+    let spending_limit_status = json!({
+        "limit": 1000,
+        "used": 1000,
+        "remaining": 1000,
+    });
+    check_round_trip::<SpendingLimitStatus>(spending_limit_status.clone());
+    check_round_trip::<SpendingLimitsResponse>(json!({
+        "window_sec": 1000,
+        "msat": spending_limit_status,
+        "assets": [asset_spending_limit_status],
+    }));
+    check_round_trip::<StatsResponse>(json!({
+        "period_start": 1000,
+        "received": payment_volume,
+        "sent": payment_volume,
+        "outbound_succeeded": 1000,
+        "outbound_failed": 1000,
+        "outbound_success_rate": 2.5,
+    }));
+    check_round_trip::<SwapAssetsResponse>(json!({
+        "swap_id": "swap_id",
+    }));
+    check_round_trip::<VerifyMessageResponse>(json!({
+        "pubkey": peer_pubkey,
+        "known_peer": true,
+    }));
+    check_round_trip::<DecodePaymentRequestResponse>(json!({
+        "type": "unified",
+        "lightning_invoice": null,
+        "lightning": null,
+        "rgb_invoice": "rgb_invoice",
+        "rgb": decode_rgb_invoice_response,
+    }));
+}
+
+#[test]
+fn decoded_invoice_round_trip() {
+    let decoded = json!({
+        "amt_msat": 3000000,
+        "expiry_sec": 900,
+        "timestamp": 1700000000,
+        "asset_id": null,
+        "asset_amount": null,
+        "payment_hash": "88".repeat(32),
+        "payment_secret": "99".repeat(32),
+        "payee_pubkey": format!("02{}", "aa".repeat(32)),
+        "network": "regtest",
+        "min_final_cltv_expiry_delta": 42,
+    });
+    check_round_trip::<DecodeLNInvoiceResponse>(decoded);
+}
@@ -0,0 +1,43 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/sign_message/";
+const NODE1_PEER_PORT: u16 = 10411;
+const NODE2_PEER_PORT: u16 = 10412;
+const NODE3_PEER_PORT: u16 = 10413;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn sign_message() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    connect_peer(
+        node1_addr,
+        &node2_pubkey,
+        &format!("127.0.0.1:{NODE2_PEER_PORT}"),
+    )
+    .await;
+
+    // sign with the node key and verify from a peer and a non-peer
+    let message = " a message with surrounding spaces ";
+    let signature = super::sign_message(node1_addr, message).await;
+    let verified = verify_message(node2_addr, message, &signature).await;
+    assert_eq!(verified.pubkey, node1_pubkey);
+    assert!(verified.known_peer);
+    let verified = verify_message(node3_addr, message, &signature).await;
+    assert_eq!(verified.pubkey, node1_pubkey);
+    assert!(!verified.known_peer);
+
+    // the message is signed as is, without trimming it
+    let verified = verify_message(node2_addr, message.trim(), &signature).await;
+    assert_ne!(verified.pubkey, node1_pubkey);
+}
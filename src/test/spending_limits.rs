@@ -0,0 +1,257 @@
+use crate::routes::SendBtcRequest;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/spending_limits/";
+const NODE1_PEER_PORT: u16 = 9961;
+const NODE2_PEER_PORT: u16 = 9962;
+
+async fn check_spending_limit_exceeded(res: reqwest::Response, remaining: &str) {
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let text = res.text().await.unwrap();
+    let response: ErrorResponse = serde_json::from_str(&text).unwrap();
+    assert_eq!(
+        response.error,
+        format!("Spending limit exceeded: {remaining}")
+    );
+    assert_eq!(response.code, 403);
+}
+
+async fn wait_for_asset_remaining(node_address: SocketAddr, expected_remaining: u64) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if super::spending_limits(node_address).await.assets[0].remaining == expected_remaining {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("spending not released")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn spending_limits() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    let limits = super::spending_limits(node1_addr).await;
+    assert_eq!(limits.window_sec, 86400);
+    assert!(limits.msat.is_none());
+    assert!(limits.assets.is_empty());
+
+    set_spending_limits(
+        node1_addr,
+        None,
+        vec![AssetSpendingLimit {
+            asset_id: asset_id.clone(),
+            max_amount: 150,
+        }],
+    )
+    .await;
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    send_payment_with_ln_balance(node1_addr, node2_addr, invoice.clone(), 600, 0).await;
+    let limits = super::spending_limits(node1_addr).await;
+    assert!(limits.msat.is_none());
+    assert_eq!(limits.assets.len(), 1);
+    assert_eq!(limits.assets[0].asset_id, asset_id);
+    assert_eq!(limits.assets[0].limit, 150);
+    assert_eq!(limits.assets[0].used, 100);
+    assert_eq!(limits.assets[0].remaining, 50);
+
+    // check DuplicatePayment error, paying the invoice again leaves the payment and its spending
+    let payment_hash = decode_ln_invoice(node1_addr, &invoice).await.payment_hash;
+    let payload = SendPaymentRequest {
+        invoice,
+        label: None,
+        timeout_sec: None,
+        max_retries: None,
+        disable_mpp: false,
+        payer_note: None,
+        identify_payer: false,
+        fallback_onchain: false,
+        fallback_fee_rate: None,
+        skip_liquidity_check: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendpayment", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Payment already pending or succeeded");
+    assert_eq!(response.code, 403);
+    let payment = get_payment(node1_addr, &payment_hash).await;
+    assert!(matches!(payment.status, HTLCStatus::Succeeded));
+    assert!(!payment.hops.unwrap().is_empty());
+    assert_eq!(super::spending_limits(node1_addr).await.assets[0].used, 100);
+
+    // check SpendingLimitExceeded error
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let payload = SendPaymentRequest {
+        invoice,
+        label: None,
+        timeout_sec: None,
+        max_retries: None,
+        disable_mpp: false,
+        payer_note: None,
+        identify_payer: false,
+        fallback_onchain: false,
+        fallback_fee_rate: None,
+        skip_liquidity_check: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendpayment", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_spending_limit_exceeded(
+        res,
+        &format!("remaining allowance for asset {asset_id} is 50"),
+    )
+    .await;
+
+    // a failed payment (no route to ourselves) doesn't count towards the limit
+    let payload = KeysendRequest {
+        dest_pubkey: node1_pubkey,
+        amt_msat: Some(MilliSats(3000000)),
+        asset_id: asset_id.clone(),
+        asset_amount: AssetUnits(50),
+        label: None,
+        use_minimum_btc: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/keysend", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let keysend_res = _check_response_is_ok(res)
+        .await
+        .json::<KeysendResponse>()
+        .await
+        .unwrap();
+    assert!(matches!(keysend_res.status, HTLCStatus::Failed));
+    assert_eq!(
+        super::spending_limits(node1_addr).await.assets[0].remaining,
+        50
+    );
+
+    // neither does a payment failed by the recipient
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 50, 900).await;
+    let payment_hash = decode_ln_invoice(node1_addr, &invoice).await.payment_hash;
+    let payload = CancelInvoiceRequest { payment_hash };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/cancelinvoice", node2_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    send_payment_with_status(node1_addr, invoice, HTLCStatus::Failed).await;
+    wait_for_asset_remaining(node1_addr, 50).await;
+
+    keysend(node1_addr, &node2_pubkey, &asset_id, 50).await;
+    assert_eq!(
+        super::spending_limits(node1_addr).await.assets[0].remaining,
+        0
+    );
+
+    // the msat limit covers on-chain sends too
+    set_spending_limits(node1_addr, Some(6_001_000), vec![]).await;
+    let limits = super::spending_limits(node1_addr).await;
+    let msat = limits.msat.unwrap();
+    assert_eq!(msat.used, 6_000_000);
+    assert_eq!(msat.remaining, 1_000);
+    assert!(limits.assets.is_empty());
+    // updating the destinations leaves the limits in place
+    set_payment_policy(node1_addr, vec![node2_pubkey.clone()]).await;
+    let limits = super::spending_limits(node1_addr).await;
+    assert_eq!(limits.msat.unwrap().limit, 6_001_000);
+    set_payment_policy(node1_addr, vec![]).await;
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/address", node2_addr))
+        .send()
+        .await
+        .unwrap();
+    let address = _check_response_is_ok(res)
+        .await
+        .json::<AddressResponse>()
+        .await
+        .unwrap()
+        .address;
+    let payload = SendBtcRequest {
+        amount: Sats(2),
+        address,
+        fee_rate: 5.0,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendbtc", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_spending_limit_exceeded(res, "remaining allowance is 1000 msat").await;
+
+    // asset limits go up to the largest amount the database can store
+    set_spending_limits(
+        node1_addr,
+        None,
+        vec![AssetSpendingLimit {
+            asset_id: asset_id.clone(),
+            max_amount: i64::MAX as u64,
+        }],
+    )
+    .await;
+    let limits = super::spending_limits(node1_addr).await;
+    assert_eq!(limits.assets[0].limit, i64::MAX as u64);
+    assert_eq!(limits.assets[0].used, 150);
+    assert_eq!(limits.assets[0].remaining, i64::MAX as u64 - 150);
+    for max_amount in [i64::MAX as u64 + 1, u64::MAX] {
+        let payload = SetPaymentPolicyRequest {
+            max_asset_amounts_per_day: Some(vec![AssetSpendingLimit {
+                asset_id: asset_id.clone(),
+                max_amount,
+            }]),
+            ..Default::default()
+        };
+        let res = reqwest::Client::new()
+            .post(format!("http://{}/setpaymentpolicy", node1_addr))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+        let response: ErrorResponse = res.json().await.unwrap();
+        assert_eq!(
+            response.error,
+            format!(
+                "Invalid payment policy: the limit for asset {asset_id} can't be more than {}",
+                i64::MAX
+            )
+        );
+        assert_eq!(response.code, 400);
+    }
+    // the refused policy left the previous one in place
+    let limits = super::spending_limits(node1_addr).await;
+    assert_eq!(limits.assets[0].limit, i64::MAX as u64);
+}
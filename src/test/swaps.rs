@@ -0,0 +1,221 @@
+use crate::ldk::PaymentFaults;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/swaps/";
+const NODE1_PEER_PORT: u16 = 10101;
+const NODE2_PEER_PORT: u16 = 10102;
+const NODE3_PEER_PORT: u16 = 10360;
+const NODE4_PEER_PORT: u16 = 10361;
+
+async fn check_swap_not_possible(
+    node_address: SocketAddr,
+    direction: SwapDirection,
+    channel_id: &str,
+    asset_amount: u64,
+    expected_error: &str,
+) {
+    let res = swap_raw(node_address, direction, channel_id, asset_amount).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, expected_error);
+    assert_eq!(response.code, 403);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn swaps() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    send_asset(node1_addr, &asset_id, 200, recipient_id).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 200);
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 200);
+    let channel_id = channel.channel_id;
+
+    // the peer rejects a swap it cannot cover on-chain
+    let swap_id = swap(node1_addr, SwapDirection::OutOfChannel, &channel_id, 300).await;
+    let swap_info = wait_for_swap(node1_addr, &swap_id).await;
+    assert_eq!(swap_info.status, SwapStatus::Failed);
+    assert_eq!(
+        swap_info.reason.unwrap(),
+        "rejected by the peer: only 200 of the asset is spendable on-chain"
+    );
+    let swap_info = wait_for_swap(node2_addr, &swap_id).await;
+    assert_eq!(swap_info.status, SwapStatus::Failed);
+    assert!(!swap_info.initiator);
+
+    // the peer has nothing to move out of the channel yet
+    check_swap_not_possible(
+        node1_addr,
+        SwapDirection::IntoChannel,
+        &channel_id,
+        100,
+        "Swap not possible: the peer has only 0 of the asset in the channel",
+    )
+    .await;
+    check_swap_not_possible(
+        node1_addr,
+        SwapDirection::OutOfChannel,
+        &channel_id,
+        700,
+        "Swap not possible: the node has only 600 of the asset in the channel",
+    )
+    .await;
+
+    // move assets out of the channel, node2 sends them to node1 on-chain
+    let swap_id = swap(node1_addr, SwapDirection::OutOfChannel, &channel_id, 100).await;
+    let swap_info = wait_for_swap(node1_addr, &swap_id).await;
+    assert_eq!(swap_info.status, SwapStatus::Succeeded);
+    assert_eq!(swap_info.direction, SwapDirection::OutOfChannel);
+    assert!(swap_info.initiator);
+    let swap_info = wait_for_swap(node2_addr, &swap_id).await;
+    assert_eq!(swap_info.status, SwapStatus::Succeeded);
+    assert_eq!(swap_info.direction, SwapDirection::IntoChannel);
+    assert!(!swap_info.initiator);
+    assert!(swap_info.txid.is_some());
+    wait_for_ln_balance(node1_addr, &asset_id, 500).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 100).await;
+    mine(false);
+    refresh_transfers(node1_addr).await;
+    refresh_transfers(node1_addr).await;
+    refresh_transfers(node2_addr).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 300);
+    assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 100);
+
+    // move assets into the channel, node1 sends them to node2 on-chain
+    let swap_id = swap(node1_addr, SwapDirection::IntoChannel, &channel_id, 50).await;
+    let swap_info = wait_for_swap(node1_addr, &swap_id).await;
+    assert_eq!(swap_info.status, SwapStatus::Succeeded);
+    assert!(swap_info.txid.is_some());
+    let swap_info = wait_for_swap(node2_addr, &swap_id).await;
+    assert_eq!(swap_info.status, SwapStatus::Succeeded);
+    wait_for_ln_balance(node1_addr, &asset_id, 550).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 50).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 250);
+    assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 150);
+
+    let swaps = list_swaps(node1_addr).await;
+    assert_eq!(swaps.len(), 3);
+
+    // no swap can start once the peer is gone
+    shutdown(&[node2_addr], &get_ldk_sockets(&[NODE2_PEER_PORT])).await;
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    let res = swap_raw(node1_addr, SwapDirection::IntoChannel, &channel_id, 10).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn swap_interrupted() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}interrupted_node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}interrupted_node2");
+    let payment_faults = Arc::new(PaymentFaults::default());
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1.clone(),
+            ldk_peer_listening_port: NODE3_PEER_PORT,
+            payment_faults: Arc::clone(&payment_faults),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE4_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE4_PEER_PORT, 600, &asset_id).await;
+    keysend(node1_addr, &node2_pubkey, &asset_id, 100).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 100).await;
+    let sent_transfers = |transfers: Vec<Transfer>| {
+        transfers
+            .into_iter()
+            .filter(|t| matches!(t.kind, TransferKind::Send))
+            .count()
+    };
+    let sent = sent_transfers(list_transfers(node1_addr, &asset_id).await);
+
+    // node1 stops after sending the assets on-chain, before claiming the HTLC of node2
+    payment_faults
+        .stop_swap_claims
+        .store(true, Ordering::Release);
+    let swap_id = swap(
+        node1_addr,
+        SwapDirection::IntoChannel,
+        &channel.channel_id,
+        50,
+    )
+    .await;
+    let t_0 = OffsetDateTime::now_utc();
+    while sent_transfers(list_transfers(node1_addr, &asset_id).await) == sent {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("swap assets not sent on-chain")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+    let swap_info = list_swaps(node1_addr)
+        .await
+        .into_iter()
+        .find(|s| s.swap_id == swap_id)
+        .unwrap();
+    assert_eq!(swap_info.status, SwapStatus::Pending);
+    shutdown(&[node1_addr], &get_ldk_sockets(&[NODE3_PEER_PORT])).await;
+
+    // once restarted node1 finds the assets sent and claims the HTLC
+    let (node1_addr, _) = start_node_with_args(
+        LdkUserInfo {
+            storage_dir_path: test_dir_node1,
+            ldk_peer_listening_port: NODE3_PEER_PORT,
+            ..Default::default()
+        },
+        true,
+    )
+    .await;
+    let swap_info = wait_for_swap(node1_addr, &swap_id).await;
+    assert_eq!(swap_info.status, SwapStatus::Succeeded);
+    assert!(swap_info.txid.is_some());
+    let swap_info = wait_for_swap(node2_addr, &swap_id).await;
+    assert_eq!(swap_info.status, SwapStatus::Succeeded);
+    wait_for_ln_balance(node1_addr, &asset_id, 550).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 50).await;
+    assert_eq!(
+        sent_transfers(list_transfers(node1_addr, &asset_id).await),
+        sent + 1
+    );
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 50);
+}
@@ -0,0 +1,92 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/wallet_addresses/";
+const NODE1_PEER_PORT: u16 = 10314;
+
+async fn address_raw(node_address: SocketAddr, reuse_last: bool) -> AddressResponse {
+    let payload = AddressRequest { reuse_last };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/address", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<AddressResponse>()
+        .await
+        .unwrap()
+}
+
+async fn list_addresses(node_address: SocketAddr) -> Vec<WalletAddress> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listaddresses", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListAddressesResponse>()
+        .await
+        .unwrap()
+        .addresses
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn wallet_addresses() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    // each call hands out a fresh address, unless asked to reuse the last one
+    let first = address_raw(node1_addr, false).await;
+    let second = address_raw(node1_addr, false).await;
+    assert_ne!(first.address, second.address);
+    assert!(second.derivation_index > first.derivation_index);
+    let reused = address_raw(node1_addr, true).await;
+    assert_eq!(reused.address, second.address);
+    assert_eq!(reused.derivation_index, second.derivation_index);
+
+    let addresses = list_addresses(node1_addr).await;
+    assert_eq!(addresses.len(), 2);
+    assert!(addresses.iter().all(|a| !a.used && a.balance_sat == 0));
+    assert!(addresses.iter().all(|a| a.issued_at.is_some()));
+
+    // funds sent to an address that is no longer the last one are detected
+    fund_wallet(first.address.clone());
+    mine(false);
+    let addresses = list_addresses(node1_addr).await;
+    let funded = addresses
+        .iter()
+        .find(|a| a.address == first.address)
+        .unwrap();
+    assert!(funded.used);
+    assert_eq!(funded.balance_sat, 100_000_000);
+    assert_eq!(funded.derivation_index, first.derivation_index);
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/btcbalance", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    let balance = _check_response_is_ok(res)
+        .await
+        .json::<BtcBalanceResponse>()
+        .await
+        .unwrap();
+    assert_eq!(balance.vanilla.settled, 100_000_000);
+
+    // a used address isn't handed out again
+    let third = address_raw(node1_addr, false).await;
+    assert!(![first.address, second.address].contains(&third.address));
+
+    // a malformed request gets refused
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/address", node1_addr))
+        .json(&serde_json::json!({ "reuse_last": "yes" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+}
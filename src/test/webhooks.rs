@@ -0,0 +1,254 @@
+use axum::http::{HeaderMap, StatusCode};
+use bitcoin::hashes::hmac::{Hmac, HmacEngine};
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use super::*;
+use crate::utils::hex_str;
+
+const TEST_DIR_BASE: &str = "tmp/webhooks/";
+const NODE1_PEER_PORT: u16 = 10326;
+const NODE2_PEER_PORT: u16 = 10327;
+const RECEIVER_PORT: u16 = 10328;
+
+const SECRET: &str = "0123456789abcdef";
+
+/// Time the slow hook of the receiver takes to answer, within the delivery timeout
+const SLOW_HOOK_DELAY: std::time::Duration = std::time::Duration::from_secs(8);
+
+/// Webhook receiver keeping the signature and body of the events it accepts, after refusing the
+/// given number of deliveries, and the bodies its slow hook accepted
+#[derive(Clone, Default)]
+struct Receiver {
+    received: Arc<Mutex<Vec<(String, String)>>>,
+    refusals_left: Arc<AtomicU32>,
+    received_slowly: Arc<Mutex<Vec<String>>>,
+}
+
+fn start_receiver(receiver: Receiver) {
+    let slow_receiver = receiver.clone();
+    let app = axum::Router::new()
+        .route(
+            "/slowhook",
+            axum::routing::post(move |body: String| {
+                let receiver = slow_receiver.clone();
+                async move {
+                    tokio::time::sleep(SLOW_HOOK_DELAY).await;
+                    receiver.received_slowly.lock().unwrap().push(body);
+                    StatusCode::OK
+                }
+            }),
+        )
+        .route(
+            "/hook",
+            axum::routing::post(move |headers: HeaderMap, body: String| {
+                let receiver = receiver.clone();
+                async move {
+                    if receiver
+                        .refusals_left
+                        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                        .is_ok()
+                    {
+                        return StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    let signature = headers["X-Webhook-Signature"].to_str().unwrap().to_string();
+                    receiver.received.lock().unwrap().push((signature, body));
+                    StatusCode::OK
+                }
+            }),
+        );
+    tokio::spawn(
+        axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], RECEIVER_PORT)))
+            .serve(app.into_make_service()),
+    );
+}
+
+/// Wait for the receiver to get an event of the given type, checking its signature
+async fn wait_for_delivery(receiver: &Receiver, event_type: &str) -> serde_json::Value {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let received = receiver.received.lock().unwrap().clone();
+        for (signature, body) in received {
+            let event: serde_json::Value = serde_json::from_str(&body).unwrap();
+            if event["type"] != event_type {
+                continue;
+            }
+            let mut engine = HmacEngine::<sha256::Hash>::new(SECRET.as_bytes());
+            engine.input(body.as_bytes());
+            let expected = Hmac::<sha256::Hash>::from_engine(engine).into_inner();
+            assert_eq!(signature, format!("sha256={}", hex_str(&expected)));
+            return event;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("{event_type} event not delivered")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+fn webhook_request(url: &str, events: &[&str]) -> AddWebhookRequest {
+    AddWebhookRequest {
+        url: url.to_string(),
+        events: events.iter().map(|e| e.to_string()).collect(),
+        secret: SECRET.to_string(),
+    }
+}
+
+async fn add_webhook_raw(
+    node_address: SocketAddr,
+    payload: &AddWebhookRequest,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/addwebhook", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn check_invalid_webhook(
+    node_address: SocketAddr,
+    payload: &AddWebhookRequest,
+    expected: &str,
+) {
+    let res = add_webhook_raw(node_address, payload).await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, format!("Invalid webhook: {expected}"));
+}
+
+async fn delete_webhook_raw(node_address: SocketAddr, id: u64) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/deletewebhook", node_address))
+        .json(&DeleteWebhookRequest { id })
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_webhooks(node_address: SocketAddr) -> Vec<Webhook> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listwebhooks", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListWebhooksResponse>()
+        .await
+        .unwrap()
+        .webhooks
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn webhooks() {
+    initialize();
+
+    let receiver = Receiver::default();
+    start_receiver(receiver.clone());
+    let url = format!("http://127.0.0.1:{RECEIVER_PORT}/hook");
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr).await;
+    fund_and_create_utxos(node2_addr).await;
+
+    let asset_id = issue_asset(node1_addr).await;
+
+    // callbacks must be HTTPS, for known events and with a secret hard to guess
+    check_invalid_webhook(
+        node2_addr,
+        &webhook_request("http://example.com/hook", &["invoice_paid"]),
+        "the URL must use HTTPS",
+    )
+    .await;
+    check_invalid_webhook(
+        node2_addr,
+        &webhook_request(&url, &["invoice_payed"]),
+        "unknown event type invoice_payed",
+    )
+    .await;
+    check_invalid_webhook(node2_addr, &webhook_request(&url, &[]), "no event selected").await;
+    let mut payload = webhook_request(&url, &["invoice_paid"]);
+    payload.secret = s!("short");
+    check_invalid_webhook(
+        node2_addr,
+        &payload,
+        "the secret must be at least 16 characters long",
+    )
+    .await;
+
+    // deliveries refused by the receiver get retried
+    receiver.refusals_left.store(2, Ordering::SeqCst);
+    let payload = webhook_request(&url, &["invoice_paid", "channel_closed", "asset_received"]);
+    let res = add_webhook_raw(node2_addr, &payload).await;
+    let id = _check_response_is_ok(res)
+        .await
+        .json::<AddWebhookResponse>()
+        .await
+        .unwrap()
+        .id;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT, 600, &asset_id).await;
+
+    let LNInvoiceResponse { invoice, .. } = ln_invoice(node2_addr, &asset_id, 100, 900).await;
+    let payment = send_payment(node1_addr, invoice).await;
+    let event = wait_for_delivery(&receiver, "invoice_paid").await;
+    assert_eq!(event["payment_hash"], payment.payment_hash);
+    assert_eq!(event["asset_id"], asset_id);
+    assert_eq!(event["asset_amount"], 100);
+
+    let webhooks = list_webhooks(node2_addr).await;
+    assert_eq!(webhooks.len(), 1);
+    let webhook = &webhooks[0];
+    assert_eq!((webhook.id, &webhook.url), (id, &url));
+    assert_eq!(
+        webhook.events,
+        vec!["asset_received", "channel_closed", "invoice_paid"]
+    );
+    assert_eq!((webhook.delivered, webhook.failed), (1, 0));
+    assert_eq!(webhook.pending_deliveries, 0);
+    assert!(webhook.last_error.is_none());
+    assert_eq!(webhook.last_attempt_at, webhook.last_delivered_at);
+
+    // assets received on-chain get notified once their transfer settles
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    send_asset(node1_addr, &asset_id, 50, recipient_id.clone()).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    let event = wait_for_delivery(&receiver, "asset_received").await;
+    assert_eq!(event["asset_id"], asset_id);
+    assert_eq!(event["amount"], 50);
+    assert_eq!(event["recipient_id"], recipient_id);
+
+    // deleted webhooks get no more events
+    _check_response_is_ok(delete_webhook_raw(node2_addr, id).await).await;
+    assert!(list_webhooks(node2_addr).await.is_empty());
+    let res = delete_webhook_raw(node2_addr, id).await;
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let response: ErrorResponse = res.json().await.unwrap();
+    assert_eq!(response.error, "Unknown webhook");
+
+    // a slow webhook doesn't hold back the deliveries to the webhooks registered after it
+    let slow_url = format!("http://127.0.0.1:{RECEIVER_PORT}/slowhook");
+    for url in [&slow_url, &url] {
+        let res = add_webhook_raw(node2_addr, &webhook_request(url, &["channel_closed"])).await;
+        _check_response_is_ok(res).await;
+    }
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    let event = wait_for_delivery(&receiver, "channel_closed").await;
+    assert_eq!(event["channel_id"], channel.channel_id);
+    assert_eq!(event["peer_pubkey"], node1_pubkey);
+    assert!(receiver.received_slowly.lock().unwrap().is_empty());
+    tokio::time::sleep(SLOW_HOOK_DELAY + std::time::Duration::from_secs(2)).await;
+    assert_eq!(receiver.received_slowly.lock().unwrap().len(), 1);
+}
@@ -1,53 +1,99 @@
 use amplify::s;
 use bdk::keys::bip39::Mnemonic;
+use bitcoin::hashes::hex::ToHex;
 use bitcoin::secp256k1::PublicKey;
 use bitcoin::Network;
 use futures::Future;
-use lightning::ln::msgs::SocketAddress;
-use lightning::rgb_utils::{BITCOIN_NETWORK_FNAME, ELECTRUM_URL_FNAME};
+use lightning::ln::msgs::{DecodeError, SocketAddress};
+use lightning::ln::ChannelId;
+use lightning::rgb_utils::{
+    get_rgb_channel_info_path, parse_rgb_channel_info, BITCOIN_NETWORK_FNAME, ELECTRUM_URL_FNAME,
+};
 use lightning::{
-    onion_message::OnionMessageContents,
+    ln::PaymentHash,
+    onion_message::{CustomOnionMessageHandler, OnionMessageContents},
+    routing::router::PaymentParameters,
     sign::KeysManager,
     util::ser::{Writeable, Writer},
 };
-use lightning_persister::fs_store::FilesystemStore;
+use lightning_invoice::Bolt11Invoice;
 use magic_crypt::{new_magic_crypt, MagicCryptTrait};
 use reqwest::Client as RestClient;
 use rgb_lib::wallet::{Online, Wallet as RgbLibWallet};
+use rgbstd::contract::{ContractId, SecretSeal};
 use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
     fmt::Write,
     fs,
-    net::{SocketAddr, ToSocketAddrs},
-    path::Path,
+    io::Read,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    path::{Path, PathBuf},
     str::FromStr,
+    sync::atomic::AtomicBool,
     sync::{Arc, Mutex, MutexGuard},
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 use tokio::sync::{Mutex as TokioMutex, MutexGuard as TokioMutexGuard};
 use tokio_util::sync::CancellationToken;
 
 use crate::{
+    alerts::Alerts,
+    announcements::Announcements,
+    api_limits::ApiLimiter,
     args::LdkUserInfo,
+    asset_registry::AssetRegistry,
     bitcoind::BitcoindClient,
+    channel_fees::ChannelFeeTracker,
+    channel_handshake::ChannelHandshakeDefaults,
+    channel_opens::{ChannelAcceptancePolicy, InboundChannels, PendingChannelOpen},
+    consolidation::ConsolidationPolicy,
+    data_dir::migrate_data_dir,
+    data_dir_lock::DataDirLock,
+    database::Database,
     disk::FilesystemLogger,
     error::{APIError, AppError},
+    graph_pruning::GraphPruner,
+    integrity::fatal_artifact_error,
+    jobs::Jobs,
+    latency::LatencyTracker,
     ldk::{
-        BumpTxEventHandler, ChannelManager, InboundPaymentInfoStorage, LdkBackgroundServices,
-        NetworkGraph, OnionMessenger, OutboundPaymentInfoStorage, PeerManager,
+        BumpTxEventHandler, ChainMonitor, ChannelManager, InboundPaymentInfoStorage,
+        LdkBackgroundServices, NetworkGraph, OnionMessenger, OutboundPaymentInfoStorage,
+        PeerManager, Router,
     },
+    lsp::{LspPolicy, LspState},
+    mirror::{MirrorTarget, MirroredStore, MonitorMirror},
+    outbound_reservations::OutboundReservations,
+    payment_quotes::QuoteCache,
+    peer_limits::{PeerLimiter, PeerRateLimits},
+    peer_messages::{PeerMessage, PeerMessageHandler},
+    persistence::{PersistenceHealth, UnpersistedOutputs},
+    proxy::ProxyChecks,
+    reorgs::ChainReorgs,
     rgb::get_bitcoin_network,
+    routes::{Event, NodeEvent, HTLC_MIN_ASSET_AMOUNT, HTLC_MIN_MSAT},
+    swaps::Swaps,
+    wallet_addresses::VanillaAddresses,
+    webhooks::Webhooks,
 };
 
+#[cfg(test)]
+use crate::ldk::PaymentFaults;
+#[cfg(feature = "regtest-endpoints")]
+use crate::regtest::RegtestBackend;
+#[cfg(test)]
+use crate::wallet_intents::WalletFaults;
+
 pub(crate) const LDK_DIR: &str = ".ldk";
 pub(crate) const LOGS_DIR: &str = "logs";
 const ELECTRUM_URL_REGTEST: &str = "127.0.0.1:50001";
 const ELECTRUM_URL_TESTNET: &str = "ssl://electrum.iriswallet.com:50013";
-pub(crate) const PROXY_ENDPOINT_REGTEST: &str = "rpc://127.0.0.1:3000/json-rpc";
-const PROXY_URL_REGTEST: &str = "http://127.0.0.1:3000/json-rpc";
+const PROXY_ENDPOINT_REGTEST: &str = "rpc://127.0.0.1:3000/json-rpc";
 const PROXY_ENDPOINT_TESTNET: &str = "rpcs://proxy.iriswallet.com/0.2/json-rpc";
-const PROXY_URL_TESTNET: &str = "https://proxy.iriswallet.com/0.2/json-rpc";
 const PROXY_TIMEOUT: u8 = 90;
 const PASSWORD_MIN_LENGTH: u8 = 8;
+const LABEL_MAX_LENGTH: u16 = 256;
+const MAX_EVENTS: usize = 1000;
 
 pub(crate) struct AppState {
     pub(crate) static_state: Arc<StaticState>,
@@ -55,6 +101,13 @@ pub(crate) struct AppState {
     pub(crate) unlocked_app_state: Arc<TokioMutex<Option<Arc<UnlockedAppState>>>>,
     pub(crate) ldk_background_services: Arc<Mutex<Option<LdkBackgroundServices>>>,
     pub(crate) changing_state: Mutex<bool>,
+    pub(crate) jobs: Jobs,
+    /// Whether the API has been stopped by a restartable shutdown, serving only the routes
+    /// needed to unlock the node again
+    pub(crate) api_stopped: AtomicBool,
+    /// Why unlocking with the password given at startup failed, reported by /ready until the
+    /// node gets unlocked
+    pub(crate) auto_unlock_failure: Mutex<Option<String>>,
 }
 
 impl AppState {
@@ -75,32 +128,165 @@ impl AppState {
 
 pub(crate) struct StaticState {
     pub(crate) ldk_peer_listening_port: u16,
+    /// None if the node doesn't listen for peers
+    pub(crate) ldk_peer_listening_host: Option<IpAddr>,
     pub(crate) ldk_announced_listen_addr: Vec<SocketAddress>,
-    pub(crate) ldk_announced_node_name: [u8; 32],
+    /// Can be changed at runtime, until the next restart
+    pub(crate) ldk_announced_node_alias: Mutex<String>,
+    pub(crate) ldk_announced_node_color: [u8; 3],
     pub(crate) network: Network,
     pub(crate) storage_dir_path: String,
     pub(crate) ldk_data_dir: String,
     pub(crate) logger: Arc<FilesystemLogger>,
     pub(crate) electrum_url: String,
-    pub(crate) proxy_endpoint: String,
-    pub(crate) proxy_url: String,
+    /// Default transport endpoints, in order of preference
+    pub(crate) proxy_endpoints: Vec<String>,
     pub(crate) proxy_client: Arc<RestClient>,
+    pub(crate) proxy_checks: ProxyChecks,
     pub(crate) bitcoind_client: Arc<BitcoindClient>,
+    pub(crate) htlc_force_close_buffer: u32,
+    pub(crate) default_min_confirmations: u8,
+    pub(crate) min_allowed_confirmations: u8,
+    pub(crate) inbound_channel_min_depth: u32,
+    pub(crate) monitor_mirror: Option<MirrorTarget>,
+    pub(crate) monitor_mirror_max_lag: usize,
+    pub(crate) monitor_archive_depth: u32,
+    pub(crate) max_graph_size_mb: u64,
+    pub(crate) peer_offline_threshold_secs: u64,
+    /// Checked for ticker collisions when issuing and listing assets
+    pub(crate) asset_registry: Option<AssetRegistry>,
+    /// Unused addresses in a row ending the search for used wallet addresses
+    pub(crate) address_gap_limit: u32,
+    pub(crate) api_token: Option<String>,
+    pub(crate) read_only_api_token: Option<String>,
+    pub(crate) lsp_policy: Option<LspPolicy>,
+    pub(crate) onion_message_forwarding: bool,
+    pub(crate) consolidation_policy: Option<ConsolidationPolicy>,
+    pub(crate) consolidation_max_fee_rate: f32,
+    pub(crate) channel_acceptance_policy: ChannelAcceptancePolicy,
+    /// Seconds an inbound channel waits for the user decision before getting rejected
+    pub(crate) channel_request_timeout_secs: u64,
+    pub(crate) channel_handshake: ChannelHandshakeDefaults,
+    /// Inbound channels without anchor outputs are accepted too
+    pub(crate) accept_non_anchor_channels: bool,
+    pub(crate) async_jobs: bool,
+    /// Every unlock is in recovery mode
+    pub(crate) recovery_mode: bool,
+    pub(crate) peer_rate_limits: PeerRateLimits,
+    pub(crate) api_limiter: ApiLimiter,
+    /// Durations of the API requests, None when latency tracking is disabled
+    pub(crate) latency_tracker: Option<LatencyTracker>,
+    pub(crate) data_dir_lock: DataDirLock,
+    pub(crate) events: Mutex<EventLog>,
+    pub(crate) counters: Mutex<BTreeMap<String, u64>>,
+    pub(crate) persistence: Arc<PersistenceHealth>,
+    #[cfg(test)]
+    pub(crate) wallet_faults: Arc<WalletFaults>,
+    #[cfg(test)]
+    pub(crate) payment_faults: Arc<PaymentFaults>,
+    /// Backend of the test-only endpoints, only available on regtest
+    #[cfg(feature = "regtest-endpoints")]
+    pub(crate) regtest: Option<RegtestBackend>,
+}
+
+impl StaticState {
+    pub(crate) fn counters(&self) -> BTreeMap<String, u64> {
+        self.counters.lock().unwrap().clone()
+    }
+
+    /// Refuse new payments and channel updates once a write has failed
+    pub(crate) fn check_writable(&self) -> Result<(), APIError> {
+        self.persistence
+            .check_writable(Path::new(&self.ldk_data_dir))
+    }
+
+    #[cfg(feature = "regtest-endpoints")]
+    pub(crate) fn regtest(&self) -> Result<&RegtestBackend, APIError> {
+        self.regtest.as_ref().ok_or(APIError::RegtestOnly)
+    }
+
+    pub(crate) fn emit_event(&self, event: NodeEvent) {
+        let mut events = self.events.lock().unwrap();
+        let id = events.next_id;
+        events.next_id += 1;
+        events.events.push_back(Event {
+            id,
+            timestamp: get_current_timestamp(),
+            event,
+        });
+        if events.events.len() > MAX_EVENTS {
+            events.events.pop_front();
+        }
+    }
+
+    /// Get the buffered events, optionally only the ones following the given event ID
+    pub(crate) fn events(&self, after_id: Option<u64>) -> Vec<Event> {
+        self.events
+            .lock()
+            .unwrap()
+            .events
+            .iter()
+            .filter(|e| after_id.map_or(true, |after_id| e.id > after_id))
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn increment_counter(&self, name: &str) {
+        *self
+            .counters
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert(0) += 1;
+    }
+}
+
+/// In-memory buffer of the most recent node events
+#[derive(Default)]
+pub(crate) struct EventLog {
+    events: VecDeque<Event>,
+    next_id: u64,
 }
 
 pub(crate) struct UnlockedAppState {
+    pub(crate) chain_monitor: Arc<ChainMonitor>,
     pub(crate) channel_manager: Arc<ChannelManager>,
+    pub(crate) database: Arc<Database>,
+    /// Payments as in the database, kept in memory for the LDK event handlers which update them,
+    /// changed records being written through as they change while the API reads the database
     pub(crate) inbound_payments: Arc<Mutex<InboundPaymentInfoStorage>>,
     pub(crate) keys_manager: Arc<KeysManager>,
     pub(crate) network_graph: Arc<NetworkGraph>,
     pub(crate) onion_messenger: Arc<OnionMessenger>,
     pub(crate) outbound_payments: Arc<Mutex<OutboundPaymentInfoStorage>>,
+    pub(crate) outbound_reservations: Arc<OutboundReservations>,
+    pub(crate) quote_cache: Arc<QuoteCache>,
+    pub(crate) router: Arc<Router>,
     pub(crate) peer_manager: Arc<PeerManager>,
-    pub(crate) fs_store: Arc<FilesystemStore>,
-    pub(crate) persister: Arc<FilesystemStore>,
+    pub(crate) peer_message_handler: Arc<PeerMessageHandler>,
+    pub(crate) peer_limiter: Arc<PeerLimiter>,
+    pub(crate) lsp: Arc<LspState>,
+    pub(crate) swaps: Arc<Swaps>,
+    pub(crate) alerts: Arc<Alerts>,
+    pub(crate) announcements: Arc<Announcements>,
+    pub(crate) channel_fee_tracker: Arc<ChannelFeeTracker>,
+    pub(crate) graph_pruner: Arc<GraphPruner>,
+    pub(crate) chain_reorgs: Arc<ChainReorgs>,
+    /// Channels get force-closed and no new ones are accepted
+    pub(crate) recovery_mode: bool,
+    /// Outbound channels being negotiated, by temporary channel ID
+    pub(crate) pending_channel_opens: Arc<Mutex<HashMap<String, PendingChannelOpen>>>,
+    /// Inbound channels waiting for their asset details or for the user decision
+    pub(crate) inbound_channels: Arc<InboundChannels>,
+    pub(crate) persister: Arc<MirroredStore>,
+    pub(crate) persistence: Arc<PersistenceHealth>,
+    pub(crate) unpersisted_outputs: Arc<UnpersistedOutputs>,
+    pub(crate) monitor_mirror: Option<Arc<MonitorMirror>>,
     pub(crate) bump_tx_event_handler: Arc<BumpTxEventHandler>,
     pub(crate) rgb_wallet: Arc<Mutex<RgbLibWallet>>,
     pub(crate) rgb_online: Online,
+    pub(crate) vanilla_addresses: Arc<VanillaAddresses>,
+    pub(crate) webhooks: Arc<Webhooks>,
 }
 
 impl UnlockedAppState {
@@ -115,6 +301,35 @@ impl UnlockedAppState {
     pub(crate) fn get_rgb_wallet(&self) -> MutexGuard<RgbLibWallet> {
         self.rgb_wallet.lock().unwrap()
     }
+
+    /// Asset amounts on the local and remote side of the node's channels, by asset
+    pub(crate) fn channel_asset_amounts(
+        &self,
+        ldk_data_dir: &str,
+    ) -> HashMap<ContractId, (u64, u64)> {
+        let ldk_data_dir_path = PathBuf::from(ldk_data_dir);
+        let mut amounts: HashMap<ContractId, (u64, u64)> = HashMap::new();
+        for chan_info in self.channel_manager.list_channels() {
+            let info_file_path = get_rgb_channel_info_path(
+                &chan_info.channel_id.to_hex(),
+                &ldk_data_dir_path,
+                false,
+            );
+            if !info_file_path.exists() {
+                continue;
+            }
+            let rgb_info = parse_rgb_channel_info(&info_file_path);
+            let (local, remote) = amounts.entry(rgb_info.contract_id).or_default();
+            *local += rgb_info.local_rgb_amount;
+            *remote += rgb_info.remote_rgb_amount;
+        }
+        amounts
+    }
+
+    pub(crate) fn send_peer_message(&self, peer_pubkey: PublicKey, msg: PeerMessage) {
+        self.peer_message_handler.enqueue(peer_pubkey, msg);
+        self.peer_manager.process_events();
+    }
 }
 
 pub(crate) struct UserOnionMessageContents {
@@ -134,6 +349,47 @@ impl Writeable for UserOnionMessageContents {
     }
 }
 
+/// Handler reporting the custom onion messages addressed to the node as events
+pub(crate) struct UserOnionMessageHandler {
+    pub(crate) static_state: Arc<StaticState>,
+}
+
+impl CustomOnionMessageHandler for UserOnionMessageHandler {
+    type CustomMessage = UserOnionMessageContents;
+
+    fn handle_custom_message(
+        &self,
+        msg: UserOnionMessageContents,
+    ) -> Option<UserOnionMessageContents> {
+        tracing::info!("Received onion message with TLV type {}", msg.tlv_type);
+        self.static_state
+            .emit_event(NodeEvent::OnionMessageReceived {
+                tlv_type: msg.tlv_type,
+                data: hex_str(&msg.data),
+            });
+        None
+    }
+
+    fn read_custom_message<R: std::io::Read>(
+        &self,
+        message_type: u64,
+        buffer: &mut R,
+    ) -> Result<Option<UserOnionMessageContents>, DecodeError> {
+        // TLV types below 64 are reserved to the onion message spec
+        if message_type < 64 {
+            return Ok(None);
+        }
+        let mut data = vec![];
+        buffer
+            .read_to_end(&mut data)
+            .map_err(|e| DecodeError::Io(e.kind()))?;
+        Ok(Some(UserOnionMessageContents {
+            tlv_type: message_type,
+            data,
+        }))
+    }
+}
+
 pub(crate) fn check_already_initialized(mnemonic_path: &str) -> Result<(), APIError> {
     if Path::new(&mnemonic_path).exists() {
         return Err(APIError::AlreadyInitialized);
@@ -150,22 +406,131 @@ pub(crate) fn check_password_strength(password: String) -> Result<(), APIError>
     Ok(())
 }
 
+pub(crate) fn check_asset_amount(
+    unlocked_state: &UnlockedAppState,
+    contract_id: ContractId,
+    amount: u64,
+) -> Result<(), APIError> {
+    if let Some(asset) = unlocked_state.rgb_get_asset_nia(contract_id)? {
+        if amount > asset.issued_supply {
+            return Err(APIError::AmountExceedsSupply(asset.issued_supply));
+        }
+    }
+    Ok(())
+}
+
+/// Check the amounts of a colored HTLC are not below the limits of the usable channels carrying
+/// the asset in the given direction
+pub(crate) fn check_htlc_amounts(
+    unlocked_state: &UnlockedAppState,
+    ldk_data_dir: &str,
+    contract_id: ContractId,
+    amt_msat: u64,
+    asset_amount: Option<u64>,
+    outbound: bool,
+) -> Result<(), APIError> {
+    if asset_amount.is_some_and(|a| a < HTLC_MIN_ASSET_AMOUNT) {
+        return Err(APIError::AmountBelowDustLimit(format!(
+            "asset amount must be at least {HTLC_MIN_ASSET_AMOUNT}"
+        )));
+    }
+    let min_msat = unlocked_state
+        .colored_htlc_min_msat(ldk_data_dir, contract_id, outbound)
+        .unwrap_or(HTLC_MIN_MSAT);
+    if amt_msat < min_msat {
+        return Err(APIError::AmountBelowDustLimit(format!(
+            "amount must be at least {min_msat} msat"
+        )));
+    }
+    Ok(())
+}
+
+/// Check the assets of a channel can be reclaimed on the given blinded UTXO at its cooperative
+/// close, which must come from an invoice of the node for the channel asset still waiting for a
+/// transfer, on a colorable UTXO of the wallet
+pub(crate) fn check_close_asset_destination(
+    unlocked_state: &UnlockedAppState,
+    contract_id: ContractId,
+    blinded_utxo: &str,
+) -> Result<(), APIError> {
+    SecretSeal::from_str(blinded_utxo).map_err(|e| APIError::InvalidBlindedUTXO(e.to_string()))?;
+    let receive_utxo = unlocked_state
+        .rgb_list_transfers(contract_id.to_string())?
+        .into_iter()
+        .find(|t| {
+            matches!(t.kind, rgb_lib::TransferKind::ReceiveBlind)
+                && matches!(t.status, rgb_lib::TransferStatus::WaitingCounterparty)
+                && t.recipient_id.as_deref() == Some(blinded_utxo)
+        })
+        .and_then(|t| t.receive_utxo)
+        .ok_or_else(|| {
+            APIError::InvalidAssetDestination(s!(
+                "not a pending blinded UTXO of the node for the channel asset"
+            ))
+        })?
+        .to_string();
+    if !unlocked_state
+        .rgb_list_unspents()?
+        .iter()
+        .any(|u| u.utxo.outpoint.to_string() == receive_utxo && u.utxo.colorable)
+    {
+        return Err(APIError::InvalidAssetDestination(format!(
+            "{receive_utxo} is not a colorable UTXO of the wallet"
+        )));
+    }
+    Ok(())
+}
+
+/// Check the destination of an outbound payment is allowed by the payment policy
+pub(crate) fn check_payment_policy(
+    unlocked_state: &UnlockedAppState,
+    destination: &str,
+) -> Result<(), APIError> {
+    let allowed = unlocked_state.database.payment_policy_destinations()?;
+    if !allowed.is_empty() && !allowed.iter().any(|d| d == destination) {
+        return Err(APIError::PolicyViolation(destination.to_string()));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_label(label: &Option<String>) -> Result<(), APIError> {
+    if let Some(label) = label {
+        if label.len() > LABEL_MAX_LENGTH as usize {
+            return Err(APIError::InvalidLabel(format!(
+                "cannot be longer than {LABEL_MAX_LENGTH} bytes"
+            )));
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn check_password_validity(
     password: &str,
     storage_dir_path: &str,
 ) -> Result<Mnemonic, APIError> {
     let mnemonic_path = get_mnemonic_path(storage_dir_path);
     if let Ok(encrypted_mnemonic) = fs::read_to_string(mnemonic_path) {
+        if encrypted_mnemonic.trim().is_empty() {
+            return Err(fatal_artifact_error("mnemonic file", "is empty"));
+        }
         let mcrypt = new_magic_crypt!(password, 256);
         let mnemonic_str = mcrypt
             .decrypt_base64_to_string(encrypted_mnemonic)
             .map_err(|_| APIError::WrongPassword)?;
-        Ok(Mnemonic::from_str(&mnemonic_str).expect("valid mnemonic"))
+        Mnemonic::from_str(&mnemonic_str)
+            .map_err(|e| fatal_artifact_error("mnemonic file", &format!("cannot be parsed ({e})")))
     } else {
         Err(APIError::NotInitialized)
     }
 }
 
+pub(crate) fn get_current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
 pub(crate) fn get_mnemonic_path(storage_dir_path: &str) -> String {
     format!("{}/mnemonic", storage_dir_path)
 }
@@ -236,6 +601,23 @@ pub(crate) fn hex_str(value: &[u8]) -> String {
     res
 }
 
+pub(crate) fn hex_str_to_channel_id(hex: &str) -> Option<ChannelId> {
+    if hex.len() != 32 * 2 {
+        return None;
+    }
+    let data = hex_str_to_vec(hex)?;
+    Some(ChannelId(data.try_into().unwrap()))
+}
+
+/// Parse an RGB color hex code (e.g. 3399ff), optionally prefixed by #
+pub(crate) fn hex_str_to_color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if hex.len() != 3 * 2 {
+        return None;
+    }
+    hex_str_to_vec(hex)?.try_into().ok()
+}
+
 pub(crate) fn hex_str_to_compressed_pubkey(hex: &str) -> Option<PublicKey> {
     if hex.len() != 33 * 2 {
         return None;
@@ -250,6 +632,14 @@ pub(crate) fn hex_str_to_compressed_pubkey(hex: &str) -> Option<PublicKey> {
     }
 }
 
+pub(crate) fn hex_str_to_payment_hash(hex: &str) -> Option<PaymentHash> {
+    if hex.len() != 32 * 2 {
+        return None;
+    }
+    let data = hex_str_to_vec(hex)?;
+    Some(PaymentHash(data.try_into().unwrap()))
+}
+
 pub(crate) fn hex_str_to_vec(hex: &str) -> Option<Vec<u8>> {
     let mut out = Vec::with_capacity(hex.len() / 2);
 
@@ -284,6 +674,16 @@ where
     rx.await.unwrap()
 }
 
+/// Alias padded to the 32 bytes of a node announcement, None if longer once UTF-8 encoded
+pub(crate) fn node_alias_bytes(alias: &str) -> Option<[u8; 32]> {
+    if alias.len() > 32 {
+        return None;
+    }
+    let mut bytes = [0; 32];
+    bytes[..alias.len()].copy_from_slice(alias.as_bytes());
+    Some(bytes)
+}
+
 pub(crate) fn parse_peer_info(
     peer_pubkey_and_ip_addr: String,
 ) -> Result<(PublicKey, SocketAddr), APIError> {
@@ -316,7 +716,34 @@ pub(crate) fn parse_peer_info(
     Ok((pubkey.unwrap(), peer_addr.unwrap().unwrap()))
 }
 
+/// Parameters to route a payment of the invoice with, its route hints and features included
+pub(crate) fn invoice_payment_params(
+    invoice: &Bolt11Invoice,
+) -> Result<PaymentParameters, APIError> {
+    let mut payment_params = PaymentParameters::from_node_id(
+        invoice.recover_payee_pub_key(),
+        invoice.min_final_cltv_expiry_delta() as u32,
+    )
+    .with_expiry_time(invoice.duration_since_epoch().as_secs() + invoice.expiry_time().as_secs())
+    .with_route_hints(invoice.route_hints())
+    .map_err(|_| APIError::InvalidInvoice(s!("invalid route hints")))?;
+    if let Some(features) = invoice.features() {
+        payment_params = payment_params
+            .with_bolt11_features(features.clone())
+            .map_err(|_| APIError::InvalidInvoice(s!("invalid features")))?;
+    }
+    Ok(payment_params)
+}
+
 pub(crate) async fn start_daemon(args: LdkUserInfo) -> Result<Arc<AppState>, AppError> {
+    // no other daemon may use the storage directory, not even while migrating into it
+    let data_dir_lock = DataDirLock::acquire(Path::new(&args.storage_dir_path))?;
+
+    // the node data must be in place before anything gets written to the storage directory
+    if let Some(old_dir) = &args.migrate_data_dir_from {
+        migrate_data_dir(old_dir, Path::new(&args.storage_dir_path))?;
+    }
+
     // Initialize the Logger (creates ldk_data_dir and its logs directory)
     let ldk_data_dir = format!("{}/{LDK_DIR}", args.storage_dir_path);
     let logger = Arc::new(FilesystemLogger::new(ldk_data_dir.clone()));
@@ -353,24 +780,24 @@ pub(crate) async fn start_daemon(args: LdkUserInfo) -> Result<Arc<AppState>, App
     }
 
     // RGB setup
-    let (electrum_url, proxy_url, proxy_endpoint) = match network {
-        bitcoin::Network::Testnet => (
-            ELECTRUM_URL_TESTNET,
-            PROXY_URL_TESTNET,
-            PROXY_ENDPOINT_TESTNET,
-        ),
-        bitcoin::Network::Regtest => (
-            ELECTRUM_URL_REGTEST,
-            PROXY_URL_REGTEST,
-            PROXY_ENDPOINT_REGTEST,
-        ),
+    let (electrum_url, proxy_endpoint) = match network {
+        bitcoin::Network::Testnet => (ELECTRUM_URL_TESTNET, PROXY_ENDPOINT_TESTNET),
+        bitcoin::Network::Regtest => (ELECTRUM_URL_REGTEST, PROXY_ENDPOINT_REGTEST),
         _ => {
             return Err(AppError::UnsupportedBitcoinNetwork);
         }
     };
+    let proxy_endpoints = if args.proxy_endpoints.is_empty() {
+        vec![proxy_endpoint.to_string()]
+    } else {
+        args.proxy_endpoints
+    };
+    let electrum_url = args
+        .electrum_url
+        .unwrap_or_else(|| electrum_url.to_string());
     fs::write(
         format!("{}/{ELECTRUM_URL_FNAME}", args.storage_dir_path),
-        electrum_url,
+        &electrum_url,
     )
     .expect("able to write");
     let bitcoin_network = get_bitcoin_network(&network);
@@ -388,19 +815,76 @@ pub(crate) async fn start_daemon(args: LdkUserInfo) -> Result<Arc<AppState>, App
 
     let cancel_token = CancellationToken::new();
 
+    #[cfg(test)]
+    let persistence = args.persistence;
+    #[cfg(not(test))]
+    let persistence = Arc::new(PersistenceHealth::default());
+
+    #[cfg(feature = "regtest-endpoints")]
+    let regtest = match network {
+        bitcoin::Network::Regtest => Some(
+            RegtestBackend::new(
+                &args.bitcoind_rpc_host,
+                args.bitcoind_rpc_port,
+                &args.bitcoind_rpc_username,
+                &args.bitcoind_rpc_password,
+            )
+            .map_err(|e| AppError::FailedBitcoindConnection(e.to_string()))?,
+        ),
+        _ => None,
+    };
+
     let static_state = Arc::new(StaticState {
         ldk_peer_listening_port: args.ldk_peer_listening_port,
+        ldk_peer_listening_host: args.ldk_peer_listening_host,
         ldk_announced_listen_addr: args.ldk_announced_listen_addr,
-        ldk_announced_node_name: args.ldk_announced_node_name,
+        ldk_announced_node_alias: Mutex::new(args.ldk_announced_node_alias),
+        ldk_announced_node_color: args.ldk_announced_node_color,
         network,
         storage_dir_path: args.storage_dir_path,
         ldk_data_dir,
         logger,
-        electrum_url: electrum_url.to_string(),
-        proxy_endpoint: proxy_endpoint.to_string(),
-        proxy_url: proxy_url.to_string(),
+        electrum_url,
+        proxy_endpoints,
         proxy_client,
+        proxy_checks: ProxyChecks::default(),
         bitcoind_client,
+        htlc_force_close_buffer: args.htlc_force_close_buffer,
+        default_min_confirmations: args.default_min_confirmations,
+        min_allowed_confirmations: args.min_allowed_confirmations,
+        inbound_channel_min_depth: args.inbound_channel_min_depth,
+        monitor_mirror: args.monitor_mirror,
+        monitor_mirror_max_lag: args.monitor_mirror_max_lag,
+        monitor_archive_depth: args.monitor_archive_depth,
+        max_graph_size_mb: args.max_graph_size_mb,
+        peer_offline_threshold_secs: args.peer_offline_threshold_secs,
+        asset_registry: args.asset_registry_url.map(AssetRegistry::new),
+        address_gap_limit: args.address_gap_limit,
+        api_token: args.api_token,
+        read_only_api_token: args.read_only_api_token,
+        lsp_policy: args.lsp_policy,
+        onion_message_forwarding: args.onion_message_forwarding,
+        consolidation_policy: args.consolidation_policy,
+        consolidation_max_fee_rate: args.consolidation_max_fee_rate,
+        channel_acceptance_policy: args.channel_acceptance_policy,
+        channel_request_timeout_secs: args.channel_request_timeout_secs,
+        channel_handshake: args.channel_handshake,
+        accept_non_anchor_channels: args.accept_non_anchor_channels,
+        async_jobs: args.async_jobs,
+        recovery_mode: args.recovery_mode,
+        peer_rate_limits: args.peer_rate_limits,
+        api_limiter: ApiLimiter::new(args.api_limits),
+        latency_tracker: args.latency_tracking.then(LatencyTracker::default),
+        data_dir_lock,
+        events: Mutex::new(EventLog::default()),
+        counters: Mutex::new(BTreeMap::new()),
+        persistence,
+        #[cfg(test)]
+        wallet_faults: args.wallet_faults,
+        #[cfg(test)]
+        payment_faults: args.payment_faults,
+        #[cfg(feature = "regtest-endpoints")]
+        regtest,
     });
 
     Ok(Arc::new(AppState {
@@ -409,5 +893,8 @@ pub(crate) async fn start_daemon(args: LdkUserInfo) -> Result<Arc<AppState>, App
         unlocked_app_state: Arc::new(TokioMutex::new(None)),
         ldk_background_services: Arc::new(Mutex::new(None)),
         changing_state: Mutex::new(false),
+        jobs: Jobs::default(),
+        api_stopped: AtomicBool::new(false),
+        auto_unlock_failure: Mutex::new(None),
     }))
 }
@@ -0,0 +1,60 @@
+use bdk::bitcoin::psbt::PartiallySignedTransaction as BdkPsbt;
+use std::str::FromStr;
+
+use crate::error::APIError;
+use crate::routes::CreatedUtxo;
+use crate::utils::UnlockedAppState;
+
+/// UTXOs a creation request leads to, planned before building its transaction
+pub(crate) struct UtxoPlan {
+    pub(crate) num: u8,
+    /// Requested UTXOs left out as free ones are already available (up_to)
+    pub(crate) skipped: u8,
+}
+
+/// Plan the creation of UTXOs, with up_to counting the colorable UTXOs without allocations as
+/// already available, as the wallet does
+pub(crate) fn plan_utxo_creation(
+    unlocked_state: &UnlockedAppState,
+    up_to: bool,
+    num: u8,
+) -> Result<UtxoPlan, APIError> {
+    if !up_to {
+        return Ok(UtxoPlan { num, skipped: 0 });
+    }
+    let available = unlocked_state
+        .rgb_list_unspents()?
+        .iter()
+        .filter(|u| u.utxo.colorable && u.rgb_allocations.is_empty())
+        .count();
+    let skipped = available.min(num as usize) as u8;
+    if skipped == num {
+        return Err(APIError::AllocationsAlreadyAvailable);
+    }
+    Ok(UtxoPlan {
+        num: num - skipped,
+        skipped,
+    })
+}
+
+/// UTXOs created by the transaction of a UTXO creation, its outputs of the requested size
+///
+/// The only other output is the change, sent back to the vanilla wallet.
+pub(crate) fn created_utxos(psbt: &str, size: u32) -> Result<Vec<CreatedUtxo>, APIError> {
+    let psbt = BdkPsbt::from_str(psbt).map_err(|e| {
+        tracing::error!("Invalid PSBT from the wallet: {e}");
+        APIError::Unexpected
+    })?;
+    let txid = psbt.unsigned_tx.txid();
+    Ok(psbt
+        .unsigned_tx
+        .output
+        .iter()
+        .enumerate()
+        .filter(|(_, output)| output.value == size as u64)
+        .map(|(vout, output)| CreatedUtxo {
+            outpoint: format!("{txid}:{vout}"),
+            btc_amount: output.value,
+        })
+        .collect())
+}
@@ -0,0 +1,125 @@
+use bdk::bitcoin::psbt::PartiallySignedTransaction as BdkPsbt;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use crate::database::UtxoReservationRecord;
+use crate::error::APIError;
+use crate::routes::UtxoReservation;
+use crate::utils::{get_current_timestamp, UnlockedAppState};
+
+/// UTXOs held by the pending operations, the ones their transactions spend and create
+#[derive(Default)]
+pub(crate) struct ReservedUtxos {
+    /// Operations by outpoint spent
+    inputs: HashMap<String, UtxoReservation>,
+    /// Operations by transaction, holding all of its outputs
+    txs: HashMap<String, UtxoReservation>,
+}
+
+impl ReservedUtxos {
+    /// Operation holding the given outpoint, if any
+    pub(crate) fn reserved_by(&self, outpoint: &str) -> Option<UtxoReservation> {
+        if let Some(reservation) = self.inputs.get(outpoint) {
+            return Some(reservation.clone());
+        }
+        let (txid, _) = outpoint.split_once(':')?;
+        self.txs.get(txid).cloned()
+    }
+}
+
+impl UnlockedAppState {
+    /// Reserve the UTXOs spent and created by the transaction of an operation, until the
+    /// transaction confirms or the operation fails
+    ///
+    /// The operation has already gone through when it gets reserved, so a failure to record the
+    /// reservation is only logged.
+    pub(crate) fn reserve_utxos(&self, psbt: &str, reservation: UtxoReservation) {
+        let tx = match BdkPsbt::from_str(psbt) {
+            Ok(psbt) => psbt.unsigned_tx,
+            Err(e) => {
+                tracing::error!("Cannot reserve the UTXOs of an invalid PSBT: {e}");
+                return;
+            }
+        };
+        let record = UtxoReservationRecord {
+            txid: tx.txid().to_string(),
+            reservation,
+            inputs: tx
+                .input
+                .iter()
+                .map(|i| i.previous_output.to_string())
+                .collect(),
+            reserved_at: get_current_timestamp(),
+        };
+        if let Err(e) = self.database.add_utxo_reservation(&record) {
+            tracing::error!("Failed to reserve the UTXOs of {}: {e}", record.txid);
+        }
+    }
+
+    /// Release the UTXOs held by an operation that won't complete
+    pub(crate) fn release_utxos(&self, txid: &str, reason: &str) {
+        match self.database.remove_utxo_reservation(txid) {
+            Ok(true) => tracing::info!("Released the UTXOs reserved by {txid}: {reason}"),
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to release the UTXOs reserved by {txid}: {e}"),
+        }
+    }
+
+    pub(crate) fn reserved_utxos(&self) -> Result<ReservedUtxos, APIError> {
+        let mut reserved = ReservedUtxos::default();
+        for record in self.database.utxo_reservations()? {
+            for input in record.inputs {
+                reserved.inputs.insert(input, record.reservation.clone());
+            }
+            reserved.txs.insert(record.txid, record.reservation);
+        }
+        Ok(reserved)
+    }
+
+    /// Release the reservations whose transaction has confirmed or whose operation has failed
+    ///
+    /// - asset transfers fail when the wallet marks them as failed
+    /// - UTXO creations fail when the wallet forgets their transaction, after it got replaced or
+    ///   dropped, as it records them once broadcast
+    /// - channel opens get released by the channel events, as their transaction is only known to
+    ///   the wallet once the counterparty has signed it
+    pub(crate) fn release_utxo_reservations(&self) -> Result<(), APIError> {
+        let records = self.database.utxo_reservations()?;
+        if records.is_empty() {
+            return Ok(());
+        }
+        let confirmed: HashMap<String, bool> = self
+            .rgb_list_transactions()?
+            .into_iter()
+            .map(|t| (t.txid, t.confirmation_time.is_some()))
+            .collect();
+        let mut failed_transfers = HashSet::new();
+        if records
+            .iter()
+            .any(|r| matches!(r.reservation, UtxoReservation::AssetTransfer { .. }))
+        {
+            for asset in self.rgb_list_assets()?.nia.unwrap_or_default() {
+                failed_transfers.extend(
+                    self.rgb_list_transfers(asset.asset_id)?
+                        .into_iter()
+                        .filter(|t| matches!(t.status, rgb_lib::TransferStatus::Failed))
+                        .filter_map(|t| t.txid),
+                );
+            }
+        }
+
+        for record in records {
+            let txid = &record.txid;
+            let reason = match (&record.reservation, confirmed.get(txid)) {
+                (_, Some(true)) => "transaction confirmed",
+                (UtxoReservation::AssetTransfer { .. }, _) if failed_transfers.contains(txid) => {
+                    "transfer failed"
+                }
+                (UtxoReservation::UtxoCreation { .. }, None) => "transaction dropped",
+                _ => continue,
+            };
+            self.release_utxos(txid, reason);
+        }
+        Ok(())
+    }
+}
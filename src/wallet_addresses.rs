@@ -0,0 +1,221 @@
+use bdk::database::MemoryDatabase;
+use bdk::electrum_client::{Client, ElectrumApi};
+use bdk::wallet::AddressIndex;
+use bdk::Wallet;
+use bitcoin::{Address, Network};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::database::WalletAddressRecord;
+use crate::error::APIError;
+use crate::utils::{get_current_timestamp, StaticState, UnlockedAppState};
+
+/// Keychain rgb-lib derives the addresses of the vanilla wallet on
+const VANILLA_KEYCHAIN: u8 = 1;
+/// Derivation indexes looked through to find the index of an address given by the wallet
+const MAX_INDEX_SEARCH: u32 = 10_000;
+/// Addresses asked to the wallet before giving up on getting one never handed out nor used
+const MAX_FRESH_ADDRESS_ATTEMPTS: u32 = 1_000;
+
+/// Watch-only copy of the vanilla wallet descriptor, deriving the same addresses as rgb-lib
+pub(crate) struct VanillaAddresses {
+    wallet: Mutex<Wallet<MemoryDatabase>>,
+}
+
+impl VanillaAddresses {
+    /// The given xpub is the one the RGB wallet has been created with
+    pub(crate) fn new(xpub: &str, network: Network) -> Self {
+        let descriptor = format!("wpkh({xpub}/{VANILLA_KEYCHAIN}/*)");
+        let wallet = Wallet::new(&descriptor, None, network, MemoryDatabase::default())
+            .expect("valid vanilla descriptor");
+        Self {
+            wallet: Mutex::new(wallet),
+        }
+    }
+
+    pub(crate) fn address_at(&self, index: u32) -> Address {
+        self.wallet
+            .lock()
+            .unwrap()
+            .get_address(AddressIndex::Peek(index))
+            .expect("derivable address")
+            .address
+    }
+
+    /// Derivation index of an address of the wallet, looked for from the given index onward first
+    fn index_of(&self, address: &Address, from: u32) -> Option<u32> {
+        (from..from.saturating_add(MAX_INDEX_SEARCH))
+            .chain(0..from)
+            .find(|i| self.address_at(*i) == *address)
+    }
+}
+
+fn electrum_client(static_state: &StaticState) -> Result<Client, APIError> {
+    Client::new(&static_state.electrum_url)
+        .map_err(|e| APIError::FailedQueryingIndexer(e.to_string()))
+}
+
+/// Whether each address has already received funds, in order
+fn used_addresses(client: &Client, addresses: &[Address]) -> Result<Vec<bool>, APIError> {
+    let scripts: Vec<_> = addresses.iter().map(|a| a.script_pubkey()).collect();
+    let histories = client
+        .batch_script_get_history(scripts.iter())
+        .map_err(|e| APIError::FailedQueryingIndexer(e.to_string()))?;
+    Ok(histories.iter().map(|h| !h.is_empty()).collect())
+}
+
+/// Address of the vanilla wallet with its on-chain activity
+pub(crate) struct WalletAddressActivity {
+    pub(crate) record: WalletAddressRecord,
+    pub(crate) used: bool,
+    /// Confirmed and unconfirmed funds held by the address, in sats
+    pub(crate) balance_sat: u64,
+}
+
+impl UnlockedAppState {
+    /// Hand out an address of the vanilla wallet that has never been handed out nor used, or
+    /// the last handed out one if asked to reuse it
+    ///
+    /// Addresses the wallet gives again, as it may after being restored, get skipped, so that
+    /// each call returns a fresh address.
+    pub(crate) fn issue_wallet_address(
+        &self,
+        static_state: &StaticState,
+        reuse_last: bool,
+    ) -> Result<WalletAddressRecord, APIError> {
+        let last_issued = self.database.last_issued_wallet_address()?;
+        if reuse_last {
+            if let Some(record) = last_issued {
+                return Ok(record);
+            }
+        }
+
+        let client = electrum_client(static_state)?;
+        // the wallet moves on from the last address it gave
+        let mut search_from = last_issued.map_or(0, |r| r.derivation_index + 1);
+        for _ in 0..MAX_FRESH_ADDRESS_ATTEMPTS {
+            let address = self.rgb_get_address()?;
+            if self.database.is_known_wallet_address(&address)? {
+                continue;
+            }
+            let parsed = Address::from_str(&address).expect("valid wallet address");
+            let Some(derivation_index) = self.vanilla_addresses.index_of(&parsed, search_from)
+            else {
+                tracing::error!("Cannot find the derivation index of wallet address {address}");
+                return Err(APIError::Unexpected);
+            };
+            search_from = derivation_index + 1;
+            let used = used_addresses(&client, &[parsed])?[0];
+            let record = WalletAddressRecord {
+                derivation_index,
+                address,
+                issued_at: (!used).then(get_current_timestamp),
+            };
+            self.database.add_wallet_address(&record)?;
+            if !used {
+                return Ok(record);
+            }
+        }
+        tracing::error!("The wallet keeps giving addresses already handed out or used");
+        Err(APIError::Unexpected)
+    }
+
+    /// Get the known addresses of the vanilla wallet with their current activity
+    pub(crate) fn wallet_address_activity(
+        &self,
+        static_state: &StaticState,
+    ) -> Result<Vec<WalletAddressActivity>, APIError> {
+        let records = self.database.wallet_addresses()?;
+        if records.is_empty() {
+            return Ok(vec![]);
+        }
+        let client = electrum_client(static_state)?;
+        let scripts: Vec<_> = records
+            .iter()
+            .map(|r| {
+                Address::from_str(&r.address)
+                    .expect("valid wallet address")
+                    .script_pubkey()
+            })
+            .collect();
+        let histories = client
+            .batch_script_get_history(scripts.iter())
+            .map_err(|e| APIError::FailedQueryingIndexer(e.to_string()))?;
+        let balances = client
+            .batch_script_get_balance(scripts.iter())
+            .map_err(|e| APIError::FailedQueryingIndexer(e.to_string()))?;
+        Ok(records
+            .into_iter()
+            .zip(histories.iter().zip(balances.iter()))
+            .map(|(record, (history, balance))| WalletAddressActivity {
+                record,
+                used: !history.is_empty(),
+                // unconfirmed spends show up as a negative unconfirmed balance
+                balance_sat: (balance.confirmed as i64 + balance.unconfirmed).max(0) as u64,
+            })
+            .collect())
+    }
+
+    /// Look for the used addresses of the vanilla wallet, until `address_gap_limit` unused ones
+    /// in a row, and make sure the wallet watches up to the last of them
+    ///
+    /// A wallet restored from its mnemonic only watches the addresses close to the last one it
+    /// has seen used, so the addresses it would miss get derived again for it to pick up their
+    /// funds.
+    pub(crate) fn discover_wallet_addresses(
+        &self,
+        static_state: &StaticState,
+    ) -> Result<(), APIError> {
+        let gap_limit = static_state.address_gap_limit;
+        let client = electrum_client(static_state)?;
+        let known = self.database.wallet_addresses()?;
+        let last_known = known.iter().map(|r| r.derivation_index).max();
+
+        let mut last_used = None;
+        let mut start = 0;
+        loop {
+            let addresses: Vec<Address> = (start..start + gap_limit)
+                .map(|i| self.vanilla_addresses.address_at(i))
+                .collect();
+            let used = used_addresses(&client, &addresses)?;
+            let mut batch_used = false;
+            for ((index, address), used) in (start..).zip(addresses).zip(used) {
+                if !used {
+                    continue;
+                }
+                batch_used = true;
+                last_used = Some(index);
+                // addresses already handed out keep their record
+                self.database.add_wallet_address(&WalletAddressRecord {
+                    derivation_index: index,
+                    address: address.to_string(),
+                    issued_at: None,
+                })?;
+            }
+            if !batch_used {
+                break;
+            }
+            start += gap_limit;
+        }
+
+        let Some(last_used) = last_used else {
+            return Ok(());
+        };
+        if last_known.is_some_and(|i| i >= last_used) {
+            return Ok(());
+        }
+        tracing::info!("Found used wallet addresses up to index {last_used}");
+        // the wallet watches every address it has derived so far
+        let mut search_from = 0;
+        for _ in 0..MAX_FRESH_ADDRESS_ATTEMPTS {
+            let address = Address::from_str(&self.rgb_get_address()?).expect("valid address");
+            match self.vanilla_addresses.index_of(&address, search_from) {
+                Some(index) if index > last_used => return Ok(()),
+                Some(index) => search_from = index + 1,
+                None => break,
+            }
+        }
+        tracing::error!("Cannot make the wallet watch the addresses up to index {last_used}");
+        Err(APIError::Unexpected)
+    }
+}
@@ -0,0 +1,196 @@
+use bdk::bitcoin::psbt::PartiallySignedTransaction as BdkPsbt;
+use bdk::electrum_client::{Client, ElectrumApi, Error as ElectrumError};
+use bitcoin::Transaction;
+use std::str::FromStr;
+#[cfg(test)]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::database::{WalletIntent, WalletIntentKind};
+use crate::error::APIError;
+use crate::routes::UtxoReservation;
+use crate::utils::{StaticState, UnlockedAppState};
+
+/// Points where the wallet operations can be interrupted, shared with the test to simulate a crash
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct WalletFaults {
+    /// Stop the operations right after broadcasting, before the wallet records the transaction
+    pub(crate) crash_after_broadcast: AtomicBool,
+}
+
+/// Where the transaction of an interrupted operation ended up, according to the indexer
+#[derive(Clone, Copy, Debug)]
+enum IntentTxStatus {
+    Confirmed,
+    Mempool,
+    Vanished,
+}
+
+/// ID of the transaction of a PSBT
+pub(crate) fn psbt_txid(psbt: &str) -> Result<String, APIError> {
+    let psbt = BdkPsbt::from_str(psbt).map_err(|e| {
+        tracing::error!("Invalid PSBT from the wallet: {e}");
+        APIError::Unexpected
+    })?;
+    Ok(psbt.extract_tx().txid().to_string())
+}
+
+/// Outpoints spent by a PSBT
+pub(crate) fn psbt_inputs(psbt: &str) -> Result<Vec<String>, APIError> {
+    let psbt = BdkPsbt::from_str(psbt).map_err(|e| {
+        tracing::error!("Invalid PSBT from the wallet: {e}");
+        APIError::Unexpected
+    })?;
+    Ok(psbt
+        .unsigned_tx
+        .input
+        .iter()
+        .map(|i| i.previous_output.to_string())
+        .collect())
+}
+
+fn intent_tx_status(client: &Client, tx: &Transaction) -> Result<IntentTxStatus, ElectrumError> {
+    let txid = tx.txid();
+    let Some(output) = tx.output.first() else {
+        return Ok(IntentTxStatus::Vanished);
+    };
+    let history = client.script_get_history(&output.script_pubkey)?;
+    Ok(match history.iter().find(|h| h.tx_hash == txid) {
+        Some(h) if h.height > 0 => IntentTxStatus::Confirmed,
+        Some(_) => IntentTxStatus::Mempool,
+        None => IntentTxStatus::Vanished,
+    })
+}
+
+impl UnlockedAppState {
+    /// Have the wallet broadcast and record the signed transaction of an operation, recording the
+    /// intent beforehand so that an interruption in between gets reconciled at the next unlock
+    pub(crate) fn broadcast_with_intent<T>(
+        &self,
+        #[cfg_attr(not(test), allow(unused_variables))] static_state: &StaticState,
+        intent: WalletIntent,
+        broadcast: impl FnOnce(String) -> Result<T, APIError>,
+    ) -> Result<T, APIError> {
+        self.database.add_wallet_intent(&intent)?;
+
+        #[cfg(test)]
+        if static_state
+            .wallet_faults
+            .crash_after_broadcast
+            .load(Ordering::Acquire)
+        {
+            // leave the transaction broadcast, the wallet unaware of it and the intent recorded, as
+            // a crash inside the wallet call would
+            let tx = BdkPsbt::from_str(&intent.signed_psbt).unwrap().extract_tx();
+            Client::new(&static_state.electrum_url)
+                .unwrap()
+                .transaction_broadcast(&tx)
+                .unwrap();
+            return Err(APIError::Unexpected);
+        }
+        // once the wallet call returns, the caller handles its outcome as usual
+        let result = broadcast(intent.signed_psbt);
+        self.database.remove_wallet_intent(&intent.txid)?;
+        result
+    }
+
+    /// Reconcile with the wallet the transactions of the operations interrupted before the
+    /// wallet could record them, so that their inputs don't get selected again
+    ///
+    /// - transactions unknown to the indexer have never been broadcast or have been dropped, the
+    ///   operation gets abandoned releasing what it reserved
+    /// - transactions in the mempool or confirmed get recorded, UTXO creations by syncing the
+    ///   wallet and asset sends by completing them if the wallet has no transfer for them
+    ///
+    /// Intents are kept for the next unlock if the indexer can't be reached.
+    pub(crate) fn reconcile_wallet_intents(
+        &self,
+        static_state: &StaticState,
+    ) -> Result<(), APIError> {
+        let intents = self.database.wallet_intents()?;
+        if intents.is_empty() {
+            return Ok(());
+        }
+        let client = match Client::new(&static_state.electrum_url) {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::error!("Cannot reconcile the interrupted wallet operations: {e}");
+                return Ok(());
+            }
+        };
+
+        for intent in intents {
+            let txid = &intent.txid;
+            let tx = match BdkPsbt::from_str(&intent.signed_psbt) {
+                Ok(psbt) => psbt.extract_tx(),
+                Err(e) => {
+                    tracing::error!("Dropping wallet intent {txid} with an invalid PSBT: {e}");
+                    self.database.remove_wallet_intent(txid)?;
+                    continue;
+                }
+            };
+            let status = match intent_tx_status(&client, &tx) {
+                Ok(status) => status,
+                Err(e) => {
+                    tracing::error!("Cannot check the transaction of wallet intent {txid}: {e}");
+                    continue;
+                }
+            };
+
+            match (status, intent.kind) {
+                (IntentTxStatus::Vanished, kind) => {
+                    tracing::warn!(
+                        "Abandoning interrupted {kind:?} {txid}, unknown to the indexer"
+                    );
+                    if let Some(spending_id) = &intent.spending_id {
+                        self.database.release_spending(spending_id)?;
+                    }
+                    if let Some(blinded_utxo) = &intent.blinded_utxo {
+                        self.database.remove_sent_blinded_utxo(blinded_utxo)?;
+                    }
+                }
+                (status, WalletIntentKind::CreateUtxos) => {
+                    tracing::warn!(
+                        "Syncing the wallet for interrupted CreateUtxos {txid} ({status:?})"
+                    );
+                    self.rgb_list_unspents()?;
+                }
+                (status, WalletIntentKind::SendAsset) => {
+                    let recorded = match &intent.asset_id {
+                        Some(asset_id) => self
+                            .rgb_list_transfers(asset_id.clone())?
+                            .iter()
+                            .any(|t| t.txid.as_ref() == Some(txid)),
+                        None => false,
+                    };
+                    if recorded {
+                        tracing::info!("Interrupted SendAsset {txid} already recorded");
+                    } else {
+                        match self.rgb_send_end(intent.signed_psbt.clone()) {
+                            Ok(_) => {
+                                tracing::warn!("Recorded interrupted SendAsset {txid} ({status:?})")
+                            }
+                            Err(e) => {
+                                tracing::error!("Cannot record interrupted SendAsset {txid}: {e}")
+                            }
+                        }
+                    }
+                }
+            }
+            // the operation went through, its UTXOs are held until the transaction confirms
+            if matches!(status, IntentTxStatus::Mempool) {
+                let reservation = match intent.kind {
+                    WalletIntentKind::CreateUtxos => {
+                        UtxoReservation::UtxoCreation { txid: txid.clone() }
+                    }
+                    WalletIntentKind::SendAsset => {
+                        UtxoReservation::AssetTransfer { txid: txid.clone() }
+                    }
+                };
+                self.reserve_utxos(&intent.signed_psbt, reservation);
+            }
+            self.database.remove_wallet_intent(txid)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+use amplify::s;
+use bitcoin::hashes::hmac::{Hmac, HmacEngine};
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use futures::StreamExt;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Url};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::database::WebhookRecord;
+use crate::error::APIError;
+use crate::routes::NodeEvent;
+use crate::utils::{get_current_timestamp, hex_str, StaticState, UnlockedAppState};
+
+/// Header carrying the HMAC-SHA256 of the body keyed with the webhook secret, as `sha256=<hex>`
+pub(crate) const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// Shortest secret accepted, so that signatures can't be forged by guessing it
+pub(crate) const MIN_WEBHOOK_SECRET_LEN: usize = 16;
+/// Types of the node events webhooks can be registered for
+pub(crate) const WEBHOOK_EVENT_TYPES: &[&str] = &[
+    "alert_cleared",
+    "alert_raised",
+    "asset_received",
+    "chain_reorg",
+    "channel_closed",
+    "channel_open_failed",
+    "custom_message_received",
+    "htlc_expiry_action",
+    "inbound_channel_request",
+    "incoming_transfer_failed",
+    "invoice_paid",
+    "onion_message_received",
+    "payer_identification_rejected",
+    "payment_failed",
+    "peer_connected",
+    "peer_disconnected",
+    "rgb_payment_rejected",
+];
+
+/// Time given to the receiver to answer a delivery
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Deliveries attempted at the same time, so that a slow webhook doesn't hold back the others
+const MAX_CONCURRENT_DELIVERIES: usize = 16;
+/// Attempts made to deliver an event before giving up on it
+const MAX_DELIVERY_ATTEMPTS: u32 = 8;
+/// Delay before retrying a failed delivery, doubled by each further failure
+#[cfg(test)]
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+#[cfg(not(test))]
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(10);
+
+/// Event waiting to be delivered to a webhook
+struct Delivery {
+    webhook_id: u64,
+    body: String,
+    attempts: u32,
+    next_attempt_at: Instant,
+}
+
+/// Deliveries of events to the webhooks, kept in memory until they succeed or are given up on
+pub(crate) struct Webhooks {
+    client: Client,
+    pending: Mutex<Vec<Delivery>>,
+}
+
+impl Default for Webhooks {
+    fn default() -> Self {
+        Webhooks {
+            client: Client::builder()
+                .timeout(DELIVERY_TIMEOUT)
+                .build()
+                .expect("valid webhook client"),
+            pending: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl Webhooks {
+    /// Number of events waiting to be delivered, by webhook ID
+    pub(crate) fn pending_deliveries(&self) -> HashMap<u64, usize> {
+        let mut pending_deliveries = HashMap::new();
+        for delivery in self.pending.lock().unwrap().iter() {
+            *pending_deliveries.entry(delivery.webhook_id).or_insert(0) += 1;
+        }
+        pending_deliveries
+    }
+
+    /// Drop the deliveries still pending for a deleted webhook
+    pub(crate) fn forget(&self, webhook_id: u64) {
+        self.pending
+            .lock()
+            .unwrap()
+            .retain(|d| d.webhook_id != webhook_id);
+    }
+
+    fn enqueue(&self, webhook_id: u64, body: String) {
+        self.pending.lock().unwrap().push(Delivery {
+            webhook_id,
+            body,
+            attempts: 0,
+            next_attempt_at: Instant::now(),
+        });
+    }
+
+    /// Take the deliveries due for an attempt, oldest first
+    fn take_due(&self) -> Vec<Delivery> {
+        let now = Instant::now();
+        let mut pending = self.pending.lock().unwrap();
+        let (due, later): (Vec<_>, Vec<_>) =
+            pending.drain(..).partition(|d| d.next_attempt_at <= now);
+        *pending = later;
+        due
+    }
+
+    fn retry(&self, mut delivery: Delivery) {
+        delivery.next_attempt_at =
+            Instant::now() + RETRY_BASE_DELAY * 2u32.pow(delivery.attempts - 1);
+        self.pending.lock().unwrap().push(delivery);
+    }
+
+    fn is_idle(&self) -> bool {
+        self.pending.lock().unwrap().is_empty()
+    }
+}
+
+/// Check that a webhook URL uses HTTPS, plain HTTP being only accepted for the local host
+pub(crate) fn check_webhook_url(url: &str) -> Result<(), APIError> {
+    let url = Url::parse(url).map_err(|e| APIError::InvalidWebhook(format!("invalid URL: {e}")))?;
+    let local = matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+    match url.scheme() {
+        "https" => Ok(()),
+        "http" if local => Ok(()),
+        _ => Err(APIError::InvalidWebhook(s!("the URL must use HTTPS"))),
+    }
+}
+
+/// Hex-encoded HMAC-SHA256 of a payload keyed with a webhook secret
+pub(crate) fn sign_payload(secret: &str, body: &str) -> String {
+    let mut engine = HmacEngine::<sha256::Hash>::new(secret.as_bytes());
+    engine.input(body.as_bytes());
+    hex_str(&Hmac::<sha256::Hash>::from_engine(engine).into_inner())
+}
+
+fn event_type(event: &NodeEvent) -> String {
+    serde_json::to_value(event).unwrap()["type"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn deliver(client: &Client, webhook: &WebhookRecord, body: &str) -> Result<(), String> {
+    let res = client
+        .post(&webhook.url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(
+            SIGNATURE_HEADER,
+            format!("sha256={}", sign_payload(&webhook.secret, body)),
+        )
+        .body(body.to_string())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(format!("HTTP status {}", res.status()));
+    }
+    Ok(())
+}
+
+/// Attempt a delivery, scheduling a retry if it fails and recording how it went
+async fn attempt_delivery(
+    unlocked_state: &UnlockedAppState,
+    webhook: &WebhookRecord,
+    mut delivery: Delivery,
+) {
+    delivery.attempts += 1;
+    let error = deliver(&unlocked_state.webhooks.client, webhook, &delivery.body)
+        .await
+        .err();
+    let gave_up = error.is_some() && delivery.attempts >= MAX_DELIVERY_ATTEMPTS;
+    if let Some(error) = &error {
+        tracing::warn!(
+            "Failed to deliver an event to webhook {} (attempt {}): {error}",
+            webhook.id,
+            delivery.attempts
+        );
+        if !gave_up {
+            unlocked_state.webhooks.retry(delivery);
+        }
+    }
+    if let Err(e) = unlocked_state.database.record_webhook_attempt(
+        webhook.id,
+        get_current_timestamp(),
+        error.as_deref(),
+        gave_up,
+    ) {
+        tracing::error!("Failed to record webhook delivery: {e}");
+    }
+}
+
+/// Post the events following the unlock to the webhooks registered for them until the node gets
+/// stopped
+///
+/// Deliveries are attempted concurrently and failed ones are retried with an exponential backoff,
+/// which can deliver an event after the ones following it, so receivers should order the events
+/// by ID.
+pub(crate) async fn deliver_webhooks(
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    let mut last_event_id = static_state.events(None).last().map(|e| e.id);
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+
+        let events = static_state.events(last_event_id);
+        if events.is_empty() && unlocked_state.webhooks.is_idle() {
+            continue;
+        }
+        let webhooks = match unlocked_state.database.webhooks() {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                tracing::error!("Failed to get the webhooks: {e}");
+                continue;
+            }
+        };
+        if let Some(event) = events.last() {
+            last_event_id = Some(event.id);
+        }
+        for event in &events {
+            let event_type = event_type(&event.event);
+            let body = serde_json::to_string(event).unwrap();
+            for webhook in webhooks.iter().filter(|w| w.events.contains(&event_type)) {
+                unlocked_state.webhooks.enqueue(webhook.id, body.clone());
+            }
+        }
+
+        let deliveries = unlocked_state
+            .webhooks
+            .take_due()
+            .into_iter()
+            .filter_map(|delivery| {
+                // the webhook has been deleted in the meantime
+                let webhook = webhooks.iter().find(|w| w.id == delivery.webhook_id)?;
+                Some(attempt_delivery(&unlocked_state, webhook, delivery))
+            });
+        futures::stream::iter(deliveries)
+            .buffer_unordered(MAX_CONCURRENT_DELIVERIES)
+            .collect::<Vec<()>>()
+            .await;
+    }
+}